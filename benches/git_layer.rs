@@ -0,0 +1,79 @@
+// Benchmarks for the git layer
+// Run with: cargo bench --features bench
+
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use rust_tig::git::{CommitWalker, Repository, diff, status};
+use rust_tig::testsupport;
+use tokio::runtime::Runtime;
+
+fn bench_commit_walker(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+    let mut group = c.benchmark_group("commit_walker");
+    group.sample_size(10);
+
+    for commits in [100, 1_000, 5_000] {
+        let dir = testsupport::repo_with_history(commits, 20);
+        let repo = rt.block_on(Repository::open(dir.path())).unwrap();
+
+        group.bench_with_input(BenchmarkId::new("load_all", commits), &repo, |b, repo| {
+            b.iter(|| {
+                rt.block_on(CommitWalker::new(repo.clone()).load_all())
+                    .unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+fn bench_commit_diff(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+    let mut group = c.benchmark_group("commit_diff");
+    group.sample_size(10);
+
+    for lines in [1_000, 10_000, 100_000] {
+        let (dir, head) = testsupport::repo_with_large_diff(lines);
+        let repo = rt.block_on(Repository::open(dir.path())).unwrap();
+
+        group.bench_with_input(
+            BenchmarkId::new("load_commit_diff", lines),
+            &repo,
+            |b, repo| {
+                b.iter(|| {
+                    rt.block_on(diff::load_commit_diff(
+                        repo,
+                        head,
+                        &diff::SizeLimit::default(),
+                    ))
+                    .unwrap()
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+fn bench_status(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+    let mut group = c.benchmark_group("status");
+    group.sample_size(10);
+
+    for untracked in [100, 1_000, 10_000] {
+        let dir = testsupport::repo_with_untracked(untracked);
+        let repo = rt.block_on(Repository::open(dir.path())).unwrap();
+
+        group.bench_with_input(
+            BenchmarkId::new("load_status", untracked),
+            &repo,
+            |b, repo| b.iter(|| rt.block_on(status::load_status(repo)).unwrap()),
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_commit_walker,
+    bench_commit_diff,
+    bench_status
+);
+criterion_main!(benches);
@@ -1,9 +1,9 @@
 // Example demonstrating how to use the rust-tig configuration system
 // Run with: cargo run --example config_demo
 
-use rust_tig::config::Config;
 use anyhow::Result;
 use crossterm::style::{Color, Stylize};
+use rust_tig::config::Config;
 
 /// Parse a color string and return the corresponding crossterm Color
 fn parse_color(color_str: &str) -> Color {
@@ -96,12 +96,18 @@ fn main() -> Result<()> {
 
     // 4. Modify configuration
     println!("\n--- Modifying Configuration ---");
-    config.keybindings.global.insert("custom_action".to_string(), "x".to_string());
+    config
+        .keybindings
+        .global
+        .insert("custom_action".to_string(), "x".to_string());
     config.colors.added = "bright green".to_string();
     config.settings.commit_chunk_size = 100;
     println!("Modified:");
     println!("  - Added custom keybinding: custom_action -> x");
-    println!("  - Changed added color to: {}", "bright green".with(parse_color("bright green")));
+    println!(
+        "  - Changed added color to: {}",
+        "bright green".with(parse_color("bright green"))
+    );
     println!("  - Changed commit_chunk_size to: 100");
 
     // 5. Save configuration to a temporary location
@@ -118,8 +124,14 @@ fn main() -> Result<()> {
         println!("  ✓ Custom keybinding loaded: {}", custom_key);
     }
     let added_color = &loaded_config.colors.added;
-    println!("  ✓ Added color: {}", added_color.clone().with(parse_color(added_color)));
-    println!("  ✓ Commit chunk size: {}", loaded_config.settings.commit_chunk_size);
+    println!(
+        "  ✓ Added color: {}",
+        added_color.clone().with(parse_color(added_color))
+    );
+    println!(
+        "  ✓ Commit chunk size: {}",
+        loaded_config.settings.commit_chunk_size
+    );
 
     // 7. Display YAML content
     println!("\n--- Generated YAML ---");
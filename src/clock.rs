@@ -0,0 +1,54 @@
+use chrono::{DateTime, Local};
+use std::fmt::Debug;
+use std::sync::Arc;
+
+/// Source of the current time for relative dates and other age displays
+///
+/// Views read the time through a clock instead of `Local::now()` so tests
+/// and rendered snapshots can pin it.
+pub trait Clock: Debug + Send + Sync {
+    /// Get the current time
+    fn now(&self) -> DateTime<Local>;
+}
+
+/// Clock shared between the app and its views
+pub type SharedClock = Arc<dyn Clock>;
+
+/// The real wall clock
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Local> {
+        Local::now()
+    }
+}
+
+/// A clock stopped at a fixed time
+#[derive(Debug, Clone, Copy)]
+pub struct FixedClock(pub DateTime<Local>);
+
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime<Local> {
+        self.0
+    }
+}
+
+/// Get the wall clock as a shared clock
+pub fn system_clock() -> SharedClock {
+    Arc::new(SystemClock)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_fixed_clock_does_not_move() {
+        let time = Local.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap();
+        let clock = FixedClock(time);
+        assert_eq!(clock.now(), time);
+        assert_eq!(clock.now(), time);
+    }
+}
@@ -1,4 +1,8 @@
-use ratatui::style::{Color, Style};
+use super::ColorMode;
+use ratatui::{
+    buffer::Buffer,
+    style::{Color, Modifier, Style},
+};
 
 /// Parse a color string and return the corresponding ratatui Color
 ///
@@ -7,6 +11,8 @@ use ratatui::style::{Color, Style};
 /// - Dark variants: dark red, dark green, dark yellow, dark blue, dark magenta, dark cyan
 /// - Grey variants: grey, gray, dark grey, dark gray
 /// - Aliases: bright black (dark grey)
+/// - Palette indexes: 0-255
+/// - RGB: #rrggbb
 ///
 /// Note: In crossterm/ratatui:
 /// - "red", "green", etc. are the bright/normal terminal colors
@@ -42,10 +48,22 @@ pub fn parse_color(color_str: &str) -> Color {
         "bright magenta" => Color::Magenta,
         "bright cyan" => Color::Cyan,
         "bright white" => Color::White,
-        _ => Color::White, // fallback
+        other => parse_extended_color(other).unwrap_or(Color::White), // fallback
     }
 }
 
+/// Parse a palette index ("208") or an RGB hex color ("#ff8700")
+fn parse_extended_color(color: &str) -> Option<Color> {
+    if let Some(hex) = color.strip_prefix('#') {
+        if hex.len() != 6 {
+            return None;
+        }
+        let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+        return Some(Color::Rgb(channel(0)?, channel(2)?, channel(4)?));
+    }
+    color.parse::<u8>().ok().map(Color::Indexed)
+}
+
 /// Parse a style string that may include foreground and background colors
 /// Supports formats like:
 /// - "green" - just foreground
@@ -54,9 +72,7 @@ pub fn parse_style(style_str: &str) -> Style {
     if let Some(idx) = style_str.find(" on ") {
         let (fg, bg) = style_str.split_at(idx);
         let bg = &bg[4..]; // Skip " on "
-        Style::default()
-            .fg(parse_color(fg))
-            .bg(parse_color(bg))
+        Style::default().fg(parse_color(fg)).bg(parse_color(bg))
     } else {
         Style::default().fg(parse_color(style_str))
     }
@@ -74,6 +90,7 @@ pub struct ColorScheme {
     pub author: Color,
     pub selected: Style,
     pub status_bar: Style,
+    pub search_match: Style,
 }
 
 impl ColorScheme {
@@ -89,10 +106,181 @@ impl ColorScheme {
             author: parse_color(&colors.author),
             selected: parse_style(&colors.selected),
             status_bar: parse_style(&colors.status_bar),
+            search_match: parse_style(&colors.search_match),
         }
     }
 }
 
+/// Colors authors are told apart by, readable on dark and light backgrounds
+const AUTHOR_PALETTE: [Color; 12] = [
+    Color::Rgb(230, 97, 89),
+    Color::Rgb(235, 150, 65),
+    Color::Rgb(200, 180, 40),
+    Color::Rgb(120, 190, 70),
+    Color::Rgb(60, 175, 120),
+    Color::Rgb(50, 180, 180),
+    Color::Rgb(70, 150, 220),
+    Color::Rgb(110, 120, 230),
+    Color::Rgb(160, 110, 220),
+    Color::Rgb(210, 100, 190),
+    Color::Rgb(225, 110, 140),
+    Color::Rgb(150, 150, 150),
+];
+
+/// Pick an author's color from a hash of their email, so it stays the same
+/// across runs and machines
+pub fn author_color(email: &str) -> Color {
+    // FNV-1a; std's hashers are not stable across releases
+    let hash = email
+        .trim()
+        .to_lowercase()
+        .bytes()
+        .fold(0xcbf29ce484222325u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        });
+    AUTHOR_PALETTE[(hash % AUTHOR_PALETTE.len() as u64) as usize]
+}
+
+/// The 16 basic colors with their usual xterm RGB values
+const BASIC_COLORS: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::Red, (205, 0, 0)),
+    (Color::Green, (0, 205, 0)),
+    (Color::Yellow, (205, 205, 0)),
+    (Color::Blue, (0, 0, 238)),
+    (Color::Magenta, (205, 0, 205)),
+    (Color::Cyan, (0, 205, 205)),
+    (Color::Gray, (229, 229, 229)),
+    (Color::DarkGray, (127, 127, 127)),
+    (Color::LightRed, (255, 0, 0)),
+    (Color::LightGreen, (0, 255, 0)),
+    (Color::LightYellow, (255, 255, 0)),
+    (Color::LightBlue, (92, 92, 255)),
+    (Color::LightMagenta, (255, 0, 255)),
+    (Color::LightCyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+/// Channel levels of the 6x6x6 color cube in the 256-color palette
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// Get the RGB value of a palette index
+fn indexed_to_rgb(index: u8) -> (u8, u8, u8) {
+    match index {
+        0..=15 => BASIC_COLORS[index as usize].1,
+        16..=231 => {
+            let i = index - 16;
+            (
+                CUBE_LEVELS[(i / 36) as usize],
+                CUBE_LEVELS[(i / 6 % 6) as usize],
+                CUBE_LEVELS[(i % 6) as usize],
+            )
+        }
+        _ => {
+            let level = 8 + 10 * (index - 232);
+            (level, level, level)
+        }
+    }
+}
+
+/// Get the RGB value of a color, if it has a fixed one
+fn color_to_rgb(color: Color) -> Option<(u8, u8, u8)> {
+    match color {
+        Color::Rgb(r, g, b) => Some((r, g, b)),
+        Color::Indexed(index) => Some(indexed_to_rgb(index)),
+        _ => BASIC_COLORS
+            .iter()
+            .find(|(c, _)| *c == color)
+            .map(|(_, rgb)| *rgb),
+    }
+}
+
+/// Blend between evenly spaced color stops, `t` running from 0.0 at the
+/// first stop to 1.0 at the last
+///
+/// Stops without a fixed RGB value (such as `reset`) are skipped.
+pub fn gradient_color(stops: &[Color], t: f64) -> Option<Color> {
+    let stops: Vec<(u8, u8, u8)> = stops.iter().filter_map(|&c| color_to_rgb(c)).collect();
+    let last = stops.len().checked_sub(1)?;
+    let position = t.clamp(0.0, 1.0) * last as f64;
+    let index = (position.floor() as usize).min(last.saturating_sub(1));
+    let (from, to) = (stops[index], stops[(index + 1).min(last)]);
+    let fraction = position - index as f64;
+    let blend = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * fraction).round() as u8;
+    Some(Color::Rgb(
+        blend(from.0, to.0),
+        blend(from.1, to.1),
+        blend(from.2, to.2),
+    ))
+}
+
+/// Squared distance between two RGB colors
+fn distance((r1, g1, b1): (u8, u8, u8), (r2, g2, b2): (u8, u8, u8)) -> u32 {
+    let d = |a: u8, b: u8| (a as i32 - b as i32).unsigned_abs().pow(2);
+    d(r1, r2) + d(g1, g2) + d(b1, b2)
+}
+
+/// Find the nearest 256-color palette index for an RGB color
+fn rgb_to_indexed(rgb: (u8, u8, u8)) -> u8 {
+    let nearest_level = |c: u8| {
+        (0..6)
+            .min_by_key(|&i| (CUBE_LEVELS[i] as i32 - c as i32).abs())
+            .unwrap_or(0) as u8
+    };
+    let cube = 16 + 36 * nearest_level(rgb.0) + 6 * nearest_level(rgb.1) + nearest_level(rgb.2);
+
+    let average = (rgb.0 as u32 + rgb.1 as u32 + rgb.2 as u32) / 3;
+    let gray = 232 + (average.saturating_sub(8) / 10).min(23) as u8;
+
+    if distance(indexed_to_rgb(gray), rgb) < distance(indexed_to_rgb(cube), rgb) {
+        gray
+    } else {
+        cube
+    }
+}
+
+/// Find the nearest of the 16 basic colors for an RGB color
+fn rgb_to_basic(rgb: (u8, u8, u8)) -> Color {
+    BASIC_COLORS
+        .iter()
+        .min_by_key(|(_, value)| distance(*value, rgb))
+        .map_or(Color::White, |(color, _)| *color)
+}
+
+/// Convert a color to the nearest one the terminal can show in `mode`
+///
+/// In `ColorMode::None` every color is reset; use `apply_color_mode` to
+/// turn backgrounds into attributes.
+pub fn downgrade_color(color: Color, mode: ColorMode) -> Color {
+    match (mode, color) {
+        (ColorMode::None, _) => Color::Reset,
+        (ColorMode::Ansi16, Color::Indexed(index)) if index < 16 => BASIC_COLORS[index as usize].0,
+        (ColorMode::Ansi16, Color::Indexed(index)) => rgb_to_basic(indexed_to_rgb(index)),
+        (ColorMode::Ansi16, Color::Rgb(r, g, b)) => rgb_to_basic((r, g, b)),
+        (ColorMode::Ansi256, Color::Rgb(r, g, b)) => Color::Indexed(rgb_to_indexed((r, g, b))),
+        _ => color,
+    }
+}
+
+/// Rewrite a rendered buffer so it only uses what the terminal can show
+///
+/// Without colors, anything drawn on a background (selection, status bar,
+/// search matches) is shown reversed instead.
+pub fn apply_color_mode(buffer: &mut Buffer, mode: ColorMode) {
+    if mode == ColorMode::TrueColor {
+        return;
+    }
+
+    for cell in buffer.content.iter_mut() {
+        if mode == ColorMode::None && cell.bg != Color::Reset {
+            cell.modifier.insert(Modifier::REVERSED);
+        }
+        cell.fg = downgrade_color(cell.fg, mode);
+        cell.bg = downgrade_color(cell.bg, mode);
+        cell.underline_color = downgrade_color(cell.underline_color, mode);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -140,4 +328,78 @@ mod tests {
         assert_eq!(parse_color("Green"), Color::Green);
         assert_eq!(parse_color("DARK BLUE"), Color::Blue);
     }
+
+    #[test]
+    fn test_parse_extended_colors() {
+        assert_eq!(parse_color("208"), Color::Indexed(208));
+        assert_eq!(parse_color("#FF8700"), Color::Rgb(255, 135, 0));
+        assert_eq!(parse_color("#ff87"), Color::White);
+    }
+
+    #[test]
+    fn test_author_color_is_stable() {
+        assert_eq!(
+            author_color("ann@example.com"),
+            author_color(" Ann@Example.com")
+        );
+        let colors: std::collections::HashSet<String> = (0..20)
+            .map(|i| format!("{:?}", author_color(&format!("dev{}@example.com", i))))
+            .collect();
+        assert!(colors.len() > 4);
+    }
+
+    #[test]
+    fn test_gradient_color() {
+        let stops = [
+            Color::Rgb(0, 0, 0),
+            Color::Rgb(100, 200, 0),
+            Color::Indexed(231),
+        ];
+        assert_eq!(gradient_color(&stops, 0.0), Some(Color::Rgb(0, 0, 0)));
+        assert_eq!(gradient_color(&stops, 0.25), Some(Color::Rgb(50, 100, 0)));
+        assert_eq!(gradient_color(&stops, 1.0), Some(Color::Rgb(255, 255, 255)));
+        assert_eq!(gradient_color(&stops, 7.0), Some(Color::Rgb(255, 255, 255)));
+        assert_eq!(
+            gradient_color(&[Color::Red], 0.5),
+            Some(Color::Rgb(205, 0, 0))
+        );
+        assert_eq!(gradient_color(&[Color::Reset], 0.5), None);
+        assert_eq!(gradient_color(&[], 0.5), None);
+    }
+
+    #[test]
+    fn test_downgrade_color() {
+        let orange = Color::Rgb(255, 135, 0);
+        assert_eq!(downgrade_color(orange, ColorMode::TrueColor), orange);
+        assert_eq!(
+            downgrade_color(orange, ColorMode::Ansi256),
+            Color::Indexed(208)
+        );
+        assert_eq!(downgrade_color(orange, ColorMode::Ansi16), Color::Yellow);
+        assert_eq!(
+            downgrade_color(Color::Indexed(10), ColorMode::Ansi16),
+            Color::LightGreen
+        );
+        assert_eq!(
+            downgrade_color(Color::Rgb(40, 40, 40), ColorMode::Ansi256),
+            Color::Indexed(235)
+        );
+        assert_eq!(downgrade_color(Color::Green, ColorMode::None), Color::Reset);
+    }
+
+    #[test]
+    fn test_apply_no_color_mode() {
+        use ratatui::layout::Rect;
+
+        let mut buffer = Buffer::empty(Rect::new(0, 0, 2, 1));
+        buffer[(0, 0)].set_style(Style::default().fg(Color::Green));
+        buffer[(1, 0)].set_style(Style::default().fg(Color::Black).bg(Color::White));
+
+        apply_color_mode(&mut buffer, ColorMode::None);
+
+        assert_eq!(buffer[(0, 0)].fg, Color::Reset);
+        assert!(!buffer[(0, 0)].modifier.contains(Modifier::REVERSED));
+        assert_eq!(buffer[(1, 0)].bg, Color::Reset);
+        assert!(buffer[(1, 0)].modifier.contains(Modifier::REVERSED));
+    }
 }
@@ -4,8 +4,11 @@ use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 
+/// Status bar clock format, also used in place of one that doesn't parse
+pub const DEFAULT_CLOCK_FORMAT: &str = "%H:%M";
+
 /// Main configuration structure for rust-tig
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
 pub struct Config {
     /// Keybinding configuration for different views
     pub keybindings: KeyBindings,
@@ -30,6 +33,7 @@ pub struct KeyBindings {
 
 /// Color configuration for various UI elements
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
 pub struct Colors {
     /// Color for added lines in diffs
     pub added: String,
@@ -49,10 +53,13 @@ pub struct Colors {
     pub selected: String,
     /// Color for status bar
     pub status_bar: String,
+    /// Color for search matches
+    pub search_match: String,
 }
 
 /// General application settings
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
 pub struct Settings {
     /// Number of commits to load per chunk
     pub commit_chunk_size: usize,
@@ -64,16 +71,442 @@ pub struct Settings {
     pub show_line_numbers: bool,
     /// Tab width for display
     pub tab_width: usize,
+    /// Files larger than this many bytes are not read when loading a diff,
+    /// and show their size until expanded (0 disables the guard)
+    pub max_diff_file_bytes: u64,
+    /// Case sensitivity of searches
+    pub search_case: SearchCase,
+    /// Milliseconds to wait for the next key of a multi-key binding
+    pub key_sequence_timeout_ms: u64,
+    /// Format of the status bar clock (chrono format, empty hides it)
+    pub clock_format: String,
+    /// Colors the terminal can show; `none` renders with attributes only
+    pub color_mode: ColorMode,
+    /// Glyphs for borders, scrollbars and the status bar
+    pub symbols: SymbolSet,
+    /// Whether files outside the sparse-checkout are listed in the status view
+    pub sparse_paths: SparseDisplay,
+    /// How thoroughly the status view scans the working tree
+    pub status: StatusSettings,
+    /// Show each commit's nearest tag (`git describe --tags`) in the main view
+    pub describe_column: bool,
+    /// Show how many lines each commit adds and removes in the main view
+    pub diffstat_column: bool,
+    /// Give each author their own color, picked from a hash of their email
+    pub author_colors: bool,
+    /// Show conventional-commit types like `feat(parser):` as colored
+    /// badges in the main view
+    pub conventional_commits: bool,
+    /// Underline misspelled words in commit messages
+    pub spell_check: bool,
+    /// Word list for the spell checker, one word per line; system
+    /// dictionaries such as /usr/share/dict/words are tried without one
+    pub spell_dictionary: Option<String>,
+    /// Colors of the blame gutter from the file's oldest commit to its
+    /// newest; empty colors every line alike
+    pub blame_heat: Vec<String>,
+    /// What to warn about in the staged changes before committing
+    pub precommit_checks: PrecommitSettings,
+    /// Fetching every remote in the background
+    pub auto_fetch: AutoFetchSettings,
+    /// Offer a plain force push, which overwrites the remote branch
+    /// without checking it first, in the push dialog
+    pub allow_force_push: bool,
+    /// Commands for the `:` prompt, each expanding to a command line, with
+    /// `%(commit)`, `%(ref)`, `%(branch)` and `%(file)` filled in from the
+    /// selection
+    pub aliases: HashMap<String, String>,
+    /// Show a diff below the view it was opened from instead of in place
+    /// of it
+    pub split_diff: bool,
+    /// Which refs decorate the history and which histories it takes in or
+    /// leaves out
+    pub refs: RefSettings,
+    /// Formats `Y` offers for copying a commit in the main view, with
+    /// `%H`, `%h`, `%s`, `%b`, `%an`, `%ae`, `%ad` and `%(url)` filled in
+    pub copy_formats: Vec<String>,
+    /// How `L` writes the commits from the mark to the selection as a
+    /// changelog
+    pub changelog: ChangelogSettings,
+    /// Issue and pull request references in commit messages, opened from
+    /// the diff view with `o`; a repository adds its own with
+    /// `git config --add rust-tig.link '<pattern> <url>'`
+    pub issue_links: Vec<IssueLinkSettings>,
+    /// Bounds of the main view's author and date columns, which are as
+    /// wide as the loaded commits need within them
+    pub column_widths: ColumnWidths,
+    /// Terminal width below which the main view shows only the hash and
+    /// message, and the status bar only what matters most; 0 never does
+    pub compact_width: u16,
+    /// Title templates of the views, with `%(name)` placeholders filled in
+    /// from what each view shows
+    pub titles: TitleTemplates,
+    /// Headers pinned to the top of the diff view while the content under
+    /// them scrolls
+    pub sticky_diff_headers: StickyHeaders,
+}
+
+/// Background fetches of every remote
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct AutoFetchSettings {
+    /// Seconds between fetches; 0 never fetches
+    pub interval_secs: u64,
+    /// Drop remote-tracking branches the remote no longer has
+    pub prune: bool,
+}
+
+impl Default for AutoFetchSettings {
+    fn default() -> Self {
+        AutoFetchSettings {
+            interval_secs: 0,
+            prune: true,
+        }
+    }
+}
+
+/// Checks of the staged changes run before each commit
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct PrecommitSettings {
+    /// Run the checks at all
+    pub enabled: bool,
+    /// Warn about staged files larger than this many bytes
+    pub max_file_bytes: u64,
+    /// Regular expressions that added lines must not match
+    pub secret_patterns: Vec<String>,
+}
+
+impl Default for PrecommitSettings {
+    fn default() -> Self {
+        PrecommitSettings {
+            enabled: true,
+            max_file_bytes: 5 * 1024 * 1024,
+            secret_patterns: vec![
+                // AWS access key ids
+                "(A3T[A-Z0-9]|AKIA|ASIA)[A-Z0-9]{16}".to_string(),
+                // PEM private keys (RSA, EC, OpenSSH, ...)
+                "-----BEGIN ([A-Z]+ )?PRIVATE KEY-----".to_string(),
+                // GitHub tokens
+                "gh[pousr]_[A-Za-z0-9]{36}".to_string(),
+            ],
+        }
+    }
+}
+
+/// Ref patterns for the main view: full names like `refs/pull`, where `*`
+/// and `?` match like shell globs and a plain name covers everything under
+/// it
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct RefSettings {
+    /// Refs left out of the refs column
+    pub hide_decorations: Vec<String>,
+    /// Refs whose history is listed along with HEAD's, like `refs/heads`
+    pub walk: Vec<String>,
+    /// Refs whose history is left out, like a fork's `refs/remotes/fork`
+    pub hide: Vec<String>,
+}
+
+impl Default for RefSettings {
+    fn default() -> Self {
+        RefSettings {
+            hide_decorations: vec![
+                "refs/notes".to_string(),
+                "refs/stash".to_string(),
+                "refs/pull".to_string(),
+            ],
+            walk: Vec::new(),
+            hide: Vec::new(),
+        }
+    }
+}
+
+/// Bounds of the main view's content-sized columns
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct ColumnWidths {
+    pub author: WidthRange,
+    pub date: WidthRange,
+}
+
+impl Default for ColumnWidths {
+    fn default() -> Self {
+        ColumnWidths {
+            author: WidthRange { min: 6, max: 30 },
+            date: WidthRange { min: 8, max: 20 },
+        }
+    }
 }
 
-impl Default for Config {
+/// Title templates of the views
+///
+/// The main view fills in `%(view)`, `%(range)`, `%(count)`, `%(shown)`,
+/// `%(total)`, `%(filtered)`, `%(filters)` and `%(chips)`; the diff view
+/// `%(commit)`, `%(summary)`, `%(line)`, `%(lines)`, `%(files)`, `%(hunk)`
+/// and `%(hunks)`; the blame view `%(path)`, `%(revision)`, `%(lines)` and
+/// `%(commits)`; and the status view `%(changes)`, `%(staged)`,
+/// `%(unstaged)`, `%(untracked)`, `%(conflicted)` and `%(sparse)`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct TitleTemplates {
+    pub main: String,
+    pub diff: String,
+    pub blame: String,
+    pub status: String,
+}
+
+impl Default for TitleTemplates {
+    fn default() -> Self {
+        TitleTemplates {
+            main: "%(view) - %(count) commits%(filtered)%(chips)".to_string(),
+            diff: "Diff - %(line) / %(lines) lines".to_string(),
+            blame: "Blame - %(path)%(revision) - %(lines) lines, %(commits) commits".to_string(),
+            status: "Status - %(changes)%(sparse)".to_string(),
+        }
+    }
+}
+
+/// The narrowest and widest a column may be, in cells
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct WidthRange {
+    pub min: u16,
+    pub max: u16,
+}
+
+impl WidthRange {
+    /// Fit a content width into the range; a range with its bounds the
+    /// wrong way round is read as `max`
+    pub fn clamp(self, width: usize) -> u16 {
+        (width.min(u16::MAX as usize) as u16)
+            .max(self.min)
+            .min(self.max)
+    }
+}
+
+/// A reference to an issue or pull request and the page it links to
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct IssueLinkSettings {
+    /// Regular expression of the reference, like `#(\d+)`
+    pub pattern: String,
+    /// The page, with `%(0)` the whole reference, `%(1)` and on its groups
+    /// and `%(url)` the repository's web page
+    pub url: String,
+}
+
+/// Changelog drafts, with commits grouped into sections by their
+/// conventional-commit type (`feat`, `fix(scope)!`, ...)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct ChangelogSettings {
+    /// Sections in the order they're written
+    pub sections: Vec<ChangelogSection>,
+    /// Heading for commits of no listed type; empty leaves them out
+    pub other: String,
+    /// Format of a section heading, with `%(title)` filled in
+    pub heading: String,
+    /// Format of a commit: the copy format placeholders along with
+    /// `%(type)`, `%(scope)` and `%(description)`, the summary without its
+    /// type
+    pub entry: String,
+}
+
+/// A changelog heading and the commit types listed under it
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ChangelogSection {
+    pub title: String,
+    pub types: Vec<String>,
+}
+
+impl Default for ChangelogSettings {
     fn default() -> Self {
-        Config {
-            keybindings: KeyBindings::default(),
-            colors: Colors::default(),
-            settings: Settings::default(),
+        let section = |title: &str, types: &[&str]| ChangelogSection {
+            title: title.to_string(),
+            types: types.iter().map(|kind| kind.to_string()).collect(),
+        };
+        ChangelogSettings {
+            sections: vec![
+                section("Features", &["feat"]),
+                section("Bug fixes", &["fix"]),
+                section("Performance", &["perf"]),
+                section("Documentation", &["docs"]),
+            ],
+            other: "Other changes".to_string(),
+            heading: "### %(title)".to_string(),
+            entry: "- %(description) (%h)".to_string(),
+        }
+    }
+}
+
+/// Status scan tuning for large repositories
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct StatusSettings {
+    /// Pair up deleted and added files as renames
+    pub detect_renames: bool,
+    /// List every file in untracked directories instead of the directory
+    pub recurse_untracked_dirs: bool,
+    /// Paths the status view never scans, relative to the repository root
+    pub exclude_paths: Vec<String>,
+    /// Write refreshed stat data back to the index to speed up later scans
+    pub update_index: bool,
+}
+
+impl Default for StatusSettings {
+    fn default() -> Self {
+        StatusSettings {
+            detect_renames: false,
+            recurse_untracked_dirs: true,
+            exclude_paths: Vec::new(),
+            update_index: false,
+        }
+    }
+}
+
+/// Case sensitivity mode for searches
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchCase {
+    /// Case-insensitive unless the query contains an uppercase letter
+    #[default]
+    Smart,
+    /// Always case-sensitive
+    Sensitive,
+    /// Always case-insensitive
+    Insensitive,
+}
+
+impl SearchCase {
+    /// Check whether a search for `query` should be case-sensitive
+    pub fn is_case_sensitive(&self, query: &str) -> bool {
+        match self {
+            SearchCase::Smart => query.chars().any(|c| c.is_uppercase()),
+            SearchCase::Sensitive => true,
+            SearchCase::Insensitive => false,
+        }
+    }
+
+    /// Cycle to the next mode (smart -> sensitive -> insensitive)
+    pub fn next(&self) -> Self {
+        match self {
+            SearchCase::Smart => SearchCase::Sensitive,
+            SearchCase::Sensitive => SearchCase::Insensitive,
+            SearchCase::Insensitive => SearchCase::Smart,
+        }
+    }
+
+    /// Short label for display in titles
+    pub fn label(&self) -> &'static str {
+        match self {
+            SearchCase::Smart => "smart",
+            SearchCase::Sensitive => "case",
+            SearchCase::Insensitive => "nocase",
+        }
+    }
+}
+
+/// How files excluded by sparse-checkout are shown
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SparseDisplay {
+    /// Only count them in the status view title
+    #[default]
+    Hide,
+    /// List them in their own section of the status view
+    Mark,
+}
+
+/// Which headers stay at the top of the diff view once scrolled past
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum StickyHeaders {
+    /// None; headers scroll away with their content
+    None,
+    /// The header of the file being read
+    #[default]
+    File,
+    /// The headers of the file and of the hunk being read
+    Hunk,
+}
+
+/// Glyph set for decorative symbols
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SymbolSet {
+    /// Unicode arrows, box drawing and the powerline branch glyph
+    #[default]
+    Unicode,
+    /// Plain ASCII for fonts without those glyphs
+    Ascii,
+}
+
+/// Color capability of the terminal
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(try_from = "RawColorMode", into = "String")]
+pub enum ColorMode {
+    /// No colors: styles degrade to bold/reverse attributes
+    None,
+    /// The 16 basic ANSI colors
+    Ansi16,
+    /// The 256-color xterm palette
+    Ansi256,
+    /// 24-bit RGB colors
+    #[default]
+    TrueColor,
+}
+
+impl ColorMode {
+    /// Parse a mode name as written in the config ("none", "16", "256", "truecolor")
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "none" | "0" => Some(ColorMode::None),
+            "16" => Some(ColorMode::Ansi16),
+            "256" => Some(ColorMode::Ansi256),
+            "truecolor" | "24bit" => Some(ColorMode::TrueColor),
+            _ => None,
         }
     }
+
+    /// Name of the mode as written in the config
+    pub fn name(&self) -> &'static str {
+        match self {
+            ColorMode::None => "none",
+            ColorMode::Ansi16 => "16",
+            ColorMode::Ansi256 => "256",
+            ColorMode::TrueColor => "truecolor",
+        }
+    }
+}
+
+/// A color mode as written in YAML, where `16` and `256` are numbers
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawColorMode {
+    Number(u32),
+    Name(String),
+}
+
+impl TryFrom<RawColorMode> for ColorMode {
+    type Error = String;
+
+    fn try_from(raw: RawColorMode) -> Result<Self, Self::Error> {
+        let name = match raw {
+            RawColorMode::Number(n) => n.to_string(),
+            RawColorMode::Name(name) => name,
+        };
+        ColorMode::parse(&name).ok_or_else(|| {
+            format!(
+                "unknown color mode '{}' (expected none, 16, 256 or truecolor)",
+                name
+            )
+        })
+    }
+}
+
+impl From<ColorMode> for String {
+    fn from(mode: ColorMode) -> Self {
+        mode.name().to_string()
+    }
 }
 
 impl Default for KeyBindings {
@@ -122,6 +555,7 @@ impl Default for Colors {
             author: "magenta".to_string(),
             selected: "black on white".to_string(),
             status_bar: "black on cyan".to_string(),
+            search_match: "black on yellow".to_string(),
         }
     }
 }
@@ -134,6 +568,45 @@ impl Default for Settings {
             mouse_support: true,
             show_line_numbers: true,
             tab_width: 4,
+            max_diff_file_bytes: 1024 * 1024,
+            search_case: SearchCase::Smart,
+            key_sequence_timeout_ms: 1000,
+            clock_format: DEFAULT_CLOCK_FORMAT.to_string(),
+            color_mode: ColorMode::TrueColor,
+            symbols: SymbolSet::Unicode,
+            sparse_paths: SparseDisplay::Hide,
+            status: StatusSettings::default(),
+            describe_column: false,
+            diffstat_column: false,
+            author_colors: false,
+            conventional_commits: false,
+            spell_check: false,
+            spell_dictionary: None,
+            blame_heat: vec![
+                "#5f87af".to_string(),
+                "#d7af5f".to_string(),
+                "#ff5f00".to_string(),
+            ],
+            precommit_checks: PrecommitSettings::default(),
+            auto_fetch: AutoFetchSettings::default(),
+            allow_force_push: false,
+            aliases: HashMap::new(),
+            split_diff: false,
+            refs: RefSettings::default(),
+            copy_formats: vec![
+                "%H".to_string(),
+                "%h (\"%s\", %ad)".to_string(),
+                "[%h %s](%(url))".to_string(),
+            ],
+            changelog: ChangelogSettings::default(),
+            issue_links: vec![IssueLinkSettings {
+                pattern: r"#(\d+)".to_string(),
+                url: "%(url)/issues/%(1)".to_string(),
+            }],
+            column_widths: ColumnWidths::default(),
+            compact_width: 80,
+            titles: TitleTemplates::default(),
+            sticky_diff_headers: StickyHeaders::File,
         }
     }
 }
@@ -180,12 +653,12 @@ impl Config {
 
         // Create parent directories if they don't exist
         if let Some(parent) = path.parent() {
-            fs::create_dir_all(parent)
-                .with_context(|| format!("Failed to create config directory: {}", parent.display()))?;
+            fs::create_dir_all(parent).with_context(|| {
+                format!("Failed to create config directory: {}", parent.display())
+            })?;
         }
 
-        let yaml = serde_yaml::to_string(self)
-            .context("Failed to serialize configuration")?;
+        let yaml = serde_yaml::to_string(self).context("Failed to serialize configuration")?;
 
         fs::write(path, yaml)
             .with_context(|| format!("Failed to write config file: {}", path.display()))?;
@@ -224,7 +697,10 @@ mod tests {
     fn test_default_config() {
         let config = Config::default();
 
-        assert_eq!(config.keybindings.global.get("quit"), Some(&"q".to_string()));
+        assert_eq!(
+            config.keybindings.global.get("quit"),
+            Some(&"q".to_string())
+        );
         assert_eq!(config.colors.added, "green");
         assert_eq!(config.settings.commit_chunk_size, 50);
     }
@@ -253,7 +729,10 @@ mod tests {
     #[test]
     fn test_custom_keybindings() {
         let mut config = Config::default();
-        config.keybindings.global.insert("custom".to_string(), "x".to_string());
+        config
+            .keybindings
+            .global
+            .insert("custom".to_string(), "x".to_string());
 
         let temp_dir = TempDir::new().unwrap();
         let config_path = temp_dir.path().join("config.yaml");
@@ -261,7 +740,10 @@ mod tests {
         config.save_to_file(&config_path).unwrap();
         let loaded = Config::load_from_file(&config_path).unwrap();
 
-        assert_eq!(loaded.keybindings.global.get("custom"), Some(&"x".to_string()));
+        assert_eq!(
+            loaded.keybindings.global.get("custom"),
+            Some(&"x".to_string())
+        );
     }
 
     #[test]
@@ -291,6 +773,105 @@ mod tests {
         let loaded = Config::load_from_file(&config_path).unwrap();
 
         assert_eq!(loaded.settings.commit_chunk_size, 100);
-        assert_eq!(loaded.settings.mouse_support, false);
+        assert!(!loaded.settings.mouse_support);
+    }
+
+    #[test]
+    fn test_status_settings_from_yaml() {
+        let yaml =
+            "status:\n  recurse_untracked_dirs: false\n  exclude_paths: [vendor/, node_modules/]\n";
+        let settings: Settings = serde_yaml::from_str(yaml).unwrap();
+        let status = &settings.status;
+        assert!(!status.recurse_untracked_dirs);
+        assert_eq!(status.exclude_paths, ["vendor/", "node_modules/"]);
+        assert!(!status.detect_renames);
+        assert!(!status.update_index);
+    }
+
+    #[test]
+    fn test_auto_fetch_settings_from_yaml() {
+        let settings: Settings =
+            serde_yaml::from_str("auto_fetch:\n  interval_secs: 300\n").unwrap();
+        assert_eq!(settings.auto_fetch.interval_secs, 300);
+        assert!(settings.auto_fetch.prune);
+        assert_eq!(Settings::default().auto_fetch.interval_secs, 0);
+    }
+
+    #[test]
+    fn test_title_templates_from_yaml() {
+        let settings: Settings =
+            serde_yaml::from_str("titles:\n  main: '%(range) (%(total))'\n").unwrap();
+        assert_eq!(settings.titles.main, "%(range) (%(total))");
+        assert_eq!(settings.titles.diff, TitleTemplates::default().diff);
+    }
+
+    #[test]
+    fn test_column_widths_from_yaml() {
+        let settings: Settings =
+            serde_yaml::from_str("column_widths:\n  author: { min: 4, max: 12 }\n").unwrap();
+        let author = settings.column_widths.author;
+        assert_eq!(
+            (author.clamp(2), author.clamp(9), author.clamp(40)),
+            (4, 9, 12)
+        );
+        assert_eq!(settings.column_widths.date, ColumnWidths::default().date);
+        assert_eq!(WidthRange { min: 10, max: 5 }.clamp(7), 5);
+    }
+
+    #[test]
+    fn test_aliases_from_yaml() {
+        let yaml = "aliases:\n  mb: merge-base %(commit) main\n  up: fetch --prune\n";
+        let settings: Settings = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(settings.aliases["mb"], "merge-base %(commit) main");
+        assert_eq!(settings.aliases.len(), 2);
+        assert!(Settings::default().aliases.is_empty());
+    }
+
+    #[test]
+    fn test_search_case_modes() {
+        assert!(!SearchCase::Smart.is_case_sensitive("fix"));
+        assert!(SearchCase::Smart.is_case_sensitive("Fix"));
+        assert!(SearchCase::Sensitive.is_case_sensitive("fix"));
+        assert!(!SearchCase::Insensitive.is_case_sensitive("Fix"));
+        assert_eq!(SearchCase::Insensitive.next(), SearchCase::Smart);
+
+        let yaml = serde_yaml::to_string(&SearchCase::Insensitive).unwrap();
+        assert_eq!(yaml.trim(), "insensitive");
+    }
+
+    #[test]
+    fn test_color_mode_names() {
+        let parse = |yaml: &str| serde_yaml::from_str::<ColorMode>(yaml);
+        assert_eq!(parse("none").unwrap(), ColorMode::None);
+        assert_eq!(parse("16").unwrap(), ColorMode::Ansi16);
+        assert_eq!(parse("'256'").unwrap(), ColorMode::Ansi256);
+        assert_eq!(parse("truecolor").unwrap(), ColorMode::TrueColor);
+        assert!(parse("8").is_err());
+
+        let yaml = serde_yaml::to_string(&ColorMode::Ansi16).unwrap();
+        assert_eq!(parse(&yaml).unwrap(), ColorMode::Ansi16);
+    }
+
+    #[test]
+    fn test_missing_settings_use_defaults() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("config.yaml");
+
+        let mut config = Config::default();
+        config.settings.commit_chunk_size = 25;
+        let yaml = serde_yaml::to_string(&config)
+            .unwrap()
+            .lines()
+            .filter(|line| !line.contains("max_diff_file_bytes"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        fs::write(&config_path, yaml).unwrap();
+
+        let loaded = Config::load_from_file(&config_path).unwrap();
+        assert_eq!(loaded.settings.commit_chunk_size, 25);
+        assert_eq!(
+            loaded.settings.max_diff_file_bytes,
+            Settings::default().max_diff_file_bytes
+        );
     }
 }
@@ -1,5 +1,20 @@
-mod config;
 mod colors;
+#[allow(clippy::module_inception)]
+mod config;
+pub mod review;
+pub mod session;
+mod symbols;
 
-pub use colors::{parse_color, parse_style, ColorScheme};
-pub use config::{Colors, Config, KeyBindings, Settings};
+pub use colors::{
+    ColorScheme, apply_color_mode, author_color, downgrade_color, gradient_color, parse_color,
+    parse_style,
+};
+pub use config::{
+    AutoFetchSettings, ChangelogSection, ChangelogSettings, ColorMode, Colors, ColumnWidths,
+    Config, DEFAULT_CLOCK_FORMAT, IssueLinkSettings, KeyBindings, PrecommitSettings, RefSettings,
+    SearchCase, Settings, SparseDisplay, StatusSettings, StickyHeaders, SymbolSet, TitleTemplates,
+    WidthRange,
+};
+pub use review::{ReviewNote, ReviewNotes, SharedReviewNotes};
+pub use session::SessionState;
+pub use symbols::Symbols;
@@ -0,0 +1,247 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use super::session::session_file_name;
+
+/// A note on a file or hunk of a diff, kept outside git while reviewing
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ReviewNote {
+    /// The commit whose diff the note is on, or `from..to` for a range
+    pub commit: String,
+    /// The commit's summary, for listing and exporting
+    #[serde(default)]
+    pub summary: String,
+    pub path: String,
+    /// Header of the hunk, like `@@ -1,3 +1,4 @@`; None for the whole file
+    #[serde(default)]
+    pub hunk: Option<String>,
+    pub text: String,
+}
+
+impl ReviewNote {
+    /// Check whether the note is on the given file or hunk
+    pub fn is_on(&self, commit: &str, path: &str, hunk: Option<&str>) -> bool {
+        self.commit == commit && self.path == path && self.hunk.as_deref() == hunk
+    }
+
+    /// Shorten the commit, or both ends of a range, to 7 digits
+    pub fn short_commit(&self) -> String {
+        match self.commit.split_once("..") {
+            Some((from, to)) => format!("{:.7}..{:.7}", from, to),
+            None => format!("{:.7}", self.commit),
+        }
+    }
+}
+
+/// Notes on the diffs of a repository, kept in the cache directory until
+/// they are deleted
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct ReviewNotes {
+    /// In the order they were first written
+    pub notes: Vec<ReviewNote>,
+}
+
+/// Review notes the views opened in a session share
+pub type SharedReviewNotes = Rc<RefCell<ReviewNotes>>;
+
+impl ReviewNotes {
+    /// Get the notes file path for a repository
+    /// Returns e.g. ~/.cache/rust-tig/reviews/<repo>-<hash>.yaml on Linux
+    pub fn path_for_repo(repo_path: &Path) -> Result<PathBuf> {
+        let reviews_dir = dirs::cache_dir()
+            .context("Could not determine cache directory")?
+            .join("rust-tig")
+            .join("reviews");
+
+        Ok(reviews_dir.join(session_file_name(repo_path)))
+    }
+
+    /// Load notes from a YAML file
+    /// If the file doesn't exist, returns no notes
+    pub fn load_from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+
+        if !path.exists() {
+            return Ok(ReviewNotes::default());
+        }
+
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read review notes: {}", path.display()))?;
+
+        serde_yaml::from_str(&content)
+            .with_context(|| format!("Failed to parse review notes: {}", path.display()))
+    }
+
+    /// Save notes to a YAML file
+    pub fn save_to_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).with_context(|| {
+                format!(
+                    "Failed to create review notes directory: {}",
+                    parent.display()
+                )
+            })?;
+        }
+
+        let yaml = serde_yaml::to_string(self).context("Failed to serialize review notes")?;
+
+        fs::write(path, yaml)
+            .with_context(|| format!("Failed to write review notes: {}", path.display()))
+    }
+
+    /// Load the notes for a repository
+    pub fn load_for_repo(repo_path: &Path) -> Result<Self> {
+        Self::load_from_file(Self::path_for_repo(repo_path)?)
+    }
+
+    /// Save the notes for a repository
+    pub fn save_for_repo(&self, repo_path: &Path) -> Result<()> {
+        self.save_to_file(Self::path_for_repo(repo_path)?)
+    }
+
+    /// Wrap the notes to share between views
+    pub fn shared(self) -> SharedReviewNotes {
+        Rc::new(RefCell::new(self))
+    }
+
+    /// Get the note on a file or hunk
+    pub fn find(&self, commit: &str, path: &str, hunk: Option<&str>) -> Option<&ReviewNote> {
+        self.notes
+            .iter()
+            .find(|note| note.is_on(commit, path, hunk))
+    }
+
+    /// Write a note, replacing the one on the same file or hunk; a note
+    /// without text deletes it instead
+    pub fn set(&mut self, note: ReviewNote) {
+        let existing = self
+            .notes
+            .iter()
+            .position(|n| n.is_on(&note.commit, &note.path, note.hunk.as_deref()));
+        match (existing, note.text.trim().is_empty()) {
+            (Some(index), true) => {
+                self.notes.remove(index);
+            }
+            (Some(index), false) => self.notes[index] = note,
+            (None, true) => {}
+            (None, false) => self.notes.push(note),
+        }
+    }
+
+    /// Format the notes as Markdown, grouped by commit in the order the
+    /// commits were first noted on, ready to paste into a review
+    pub fn to_markdown(&self) -> String {
+        let mut commits: Vec<&str> = Vec::new();
+        for note in &self.notes {
+            if !commits.contains(&note.commit.as_str()) {
+                commits.push(&note.commit);
+            }
+        }
+
+        let mut markdown = String::from("# Review notes\n");
+        for commit in commits {
+            let mut notes = self
+                .notes
+                .iter()
+                .filter(|note| note.commit == commit)
+                .peekable();
+            if let Some(first) = notes.peek() {
+                markdown.push_str(
+                    format!("\n## {} {}", first.short_commit(), first.summary).trim_end(),
+                );
+                markdown.push('\n');
+            }
+            for note in notes {
+                match &note.hunk {
+                    Some(hunk) => {
+                        markdown.push_str(&format!("\n### `{}` `{}`\n\n", note.path, hunk))
+                    }
+                    None => markdown.push_str(&format!("\n### `{}`\n\n", note.path)),
+                }
+                markdown.push_str(note.text.trim_end());
+                markdown.push('\n');
+            }
+        }
+        markdown
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn note(commit: &str, path: &str, hunk: Option<&str>, text: &str) -> ReviewNote {
+        ReviewNote {
+            commit: commit.to_string(),
+            summary: format!("Change {}", path),
+            path: path.to_string(),
+            hunk: hunk.map(str::to_string),
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_set_replaces_and_deletes() {
+        let mut notes = ReviewNotes::default();
+        notes.set(note("aaaa", "a.rs", None, "Split this file"));
+        notes.set(note("aaaa", "a.rs", Some("@@ -1 +1 @@"), "Off by one?"));
+        notes.set(note("aaaa", "a.rs", None, "Split this file up"));
+        assert_eq!(notes.notes.len(), 2);
+        assert_eq!(
+            notes.find("aaaa", "a.rs", None).unwrap().text,
+            "Split this file up"
+        );
+
+        notes.set(note("aaaa", "a.rs", None, "  \n"));
+        assert_eq!(notes.notes.len(), 1);
+        assert!(notes.find("aaaa", "a.rs", None).is_none());
+    }
+
+    #[test]
+    fn test_markdown_groups_by_commit() {
+        let mut notes = ReviewNotes::default();
+        notes.set(note(
+            "1234567890",
+            "a.rs",
+            Some("@@ -1 +1 @@"),
+            "Off by one?",
+        ));
+        notes.set(note("abcdef0123", "b.rs", None, "Needs a test\n"));
+        notes.set(note("1234567890", "c.rs", None, "Nice"));
+
+        assert_eq!(
+            notes.to_markdown(),
+            "# Review notes\n\
+             \n## 1234567 Change a.rs\n\
+             \n### `a.rs` `@@ -1 +1 @@`\n\nOff by one?\n\
+             \n### `c.rs`\n\nNice\n\
+             \n## abcdef0 Change b.rs\n\
+             \n### `b.rs`\n\nNeeds a test\n"
+        );
+    }
+
+    #[test]
+    fn test_save_and_load_notes() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("notes.yaml");
+        assert_eq!(
+            ReviewNotes::load_from_file(&path).unwrap(),
+            ReviewNotes::default()
+        );
+
+        let mut notes = ReviewNotes::default();
+        notes.set(note("aaaa..bbbb", "a.rs", Some("@@ -1 +1 @@"), "Why?"));
+        notes.save_to_file(&path).unwrap();
+        let loaded = ReviewNotes::load_from_file(&path).unwrap();
+        assert_eq!(loaded, notes);
+        assert_eq!(loaded.notes[0].short_commit(), "aaaa..bbbb");
+    }
+}
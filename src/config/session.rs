@@ -0,0 +1,213 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Maximum number of entries kept in each history list
+pub const MAX_HISTORY: usize = 100;
+
+/// Per-repository state persisted between sessions
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct SessionState {
+    /// Recent search queries, oldest first
+    pub search_history: Vec<String>,
+    /// Recent commit messages, oldest first
+    pub commit_messages: Vec<String>,
+    /// Recent lines run at the `:` prompt, oldest first
+    pub command_history: Vec<String>,
+    /// Percent of the screen the upper pane of each split gets, by split
+    /// name like "main/diff"
+    pub split_ratios: HashMap<String, u16>,
+    /// Files marked viewed while reviewing a diff, by the commit, or
+    /// `from..to` for a range
+    pub viewed_files: HashMap<String, Vec<String>>,
+}
+
+impl SessionState {
+    /// Get the session file path for a repository
+    /// Returns e.g. ~/.local/share/rust-tig/sessions/<repo>-<hash>.yaml on Linux
+    pub fn path_for_repo(repo_path: &Path) -> Result<PathBuf> {
+        let sessions_dir = dirs::data_local_dir()
+            .context("Could not determine data directory")?
+            .join("rust-tig")
+            .join("sessions");
+
+        Ok(sessions_dir.join(session_file_name(repo_path)))
+    }
+
+    /// Load session state from a YAML file
+    /// If the file doesn't exist, returns an empty state
+    pub fn load_from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+
+        if !path.exists() {
+            return Ok(SessionState::default());
+        }
+
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read session file: {}", path.display()))?;
+
+        serde_yaml::from_str(&content)
+            .with_context(|| format!("Failed to parse session file: {}", path.display()))
+    }
+
+    /// Save session state to a YAML file
+    pub fn save_to_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).with_context(|| {
+                format!("Failed to create session directory: {}", parent.display())
+            })?;
+        }
+
+        let yaml = serde_yaml::to_string(self).context("Failed to serialize session state")?;
+
+        fs::write(path, yaml)
+            .with_context(|| format!("Failed to write session file: {}", path.display()))
+    }
+
+    /// Load the session state for a repository
+    pub fn load_for_repo(repo_path: &Path) -> Result<Self> {
+        Self::load_from_file(Self::path_for_repo(repo_path)?)
+    }
+
+    /// Save the session state for a repository
+    pub fn save_for_repo(&self, repo_path: &Path) -> Result<()> {
+        self.save_to_file(Self::path_for_repo(repo_path)?)
+    }
+
+    /// Record a search query as the most recent entry
+    pub fn record_search(&mut self, query: &str) {
+        push_history(&mut self.search_history, query);
+    }
+
+    /// Record a commit message as the most recent entry
+    pub fn record_commit_message(&mut self, message: &str) {
+        push_history(&mut self.commit_messages, message);
+    }
+
+    /// Record a line run at the `:` prompt as the most recent entry
+    pub fn record_command(&mut self, line: &str) {
+        push_history(&mut self.command_history, line);
+    }
+
+    /// Mark a file of a diff viewed or not, forgetting diffs with no
+    /// viewed files left
+    pub fn set_viewed(&mut self, diff: &str, path: &str, viewed: bool) {
+        let paths = self.viewed_files.entry(diff.to_string()).or_default();
+        paths.retain(|p| p != path);
+        if viewed {
+            paths.push(path.to_string());
+        }
+        if paths.is_empty() {
+            self.viewed_files.remove(diff);
+        }
+    }
+}
+
+/// Append `entry` to a history list, moving duplicates to the end and
+/// dropping the oldest entries beyond `MAX_HISTORY`
+pub fn push_history(entries: &mut Vec<String>, entry: &str) {
+    if entry.is_empty() {
+        return;
+    }
+
+    entries.retain(|e| e != entry);
+    entries.push(entry.to_string());
+
+    if entries.len() > MAX_HISTORY {
+        let excess = entries.len() - MAX_HISTORY;
+        entries.drain(..excess);
+    }
+}
+
+/// Build a stable, readable file name for a repository path
+pub(super) fn session_file_name(repo_path: &Path) -> String {
+    // FNV-1a, so the name is stable across Rust releases
+    let hash = repo_path
+        .to_string_lossy()
+        .bytes()
+        .fold(0xcbf29ce484222325u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        });
+
+    let name = repo_path
+        .components()
+        .rev()
+        .filter_map(|c| c.as_os_str().to_str())
+        .find(|c| *c != ".git")
+        .unwrap_or("repo");
+
+    format!("{}-{:016x}.yaml", name, hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_save_and_load_session() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("session.yaml");
+
+        let mut state = SessionState::default();
+        state.record_search("fix");
+        state.record_commit_message("Fix the parser\n\nIt dropped commas.");
+        state.record_command("fetch origin");
+        state.split_ratios.insert("main/diff".to_string(), 30);
+        state.set_viewed("abc123", "src/lib.rs", true);
+        state.save_to_file(&path).unwrap();
+
+        let loaded = SessionState::load_from_file(&path).unwrap();
+        assert_eq!(loaded, state);
+    }
+
+    #[test]
+    fn test_load_missing_session() {
+        let temp_dir = TempDir::new().unwrap();
+        let state = SessionState::load_from_file(temp_dir.path().join("none.yaml")).unwrap();
+        assert_eq!(state, SessionState::default());
+    }
+
+    #[test]
+    fn test_push_history_dedupes_and_caps() {
+        let mut entries = Vec::new();
+        push_history(&mut entries, "a");
+        push_history(&mut entries, "b");
+        push_history(&mut entries, "a");
+        push_history(&mut entries, "");
+        assert_eq!(entries, vec!["b", "a"]);
+
+        for i in 0..MAX_HISTORY {
+            push_history(&mut entries, &i.to_string());
+        }
+        assert_eq!(entries.len(), MAX_HISTORY);
+        assert_eq!(entries[0], "0");
+    }
+
+    #[test]
+    fn test_set_viewed_forgets_unviewed_diffs() {
+        let mut state = SessionState::default();
+        state.set_viewed("abc123", "a.rs", true);
+        state.set_viewed("abc123", "b.rs", true);
+        state.set_viewed("abc123", "a.rs", true);
+        assert_eq!(state.viewed_files["abc123"], ["b.rs", "a.rs"]);
+
+        state.set_viewed("abc123", "a.rs", false);
+        state.set_viewed("abc123", "b.rs", false);
+        assert!(state.viewed_files.is_empty());
+    }
+
+    #[test]
+    fn test_session_file_name_per_repo() {
+        let a = session_file_name(Path::new("/home/me/project/.git"));
+        let b = session_file_name(Path::new("/home/me/other/project/.git"));
+        assert!(a.starts_with("project-"));
+        assert_ne!(a, b);
+        assert_eq!(a, session_file_name(Path::new("/home/me/project/.git")));
+    }
+}
@@ -0,0 +1,184 @@
+use super::{ColorMode, Settings, SymbolSet};
+use ratatui::{
+    symbols::border,
+    widgets::{Block, Borders, Scrollbar, ScrollbarOrientation},
+};
+
+/// Border set drawn with `+`, `-` and `|`
+const ASCII_BORDER: border::Set = border::Set {
+    top_left: "+",
+    top_right: "+",
+    bottom_left: "+",
+    bottom_right: "+",
+    vertical_left: "|",
+    vertical_right: "|",
+    horizontal_top: "-",
+    horizontal_bottom: "-",
+};
+
+/// Decorative glyphs used across the UI
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Symbols {
+    /// Prefix of the branch name in the status bar
+    pub branch: &'static str,
+    /// Marks an ascending sort
+    pub ascending: &'static str,
+    /// Marks a descending sort
+    pub descending: &'static str,
+    /// Top end of a scrollbar
+    pub scroll_begin: &'static str,
+    /// Bottom end of a scrollbar
+    pub scroll_end: &'static str,
+    /// Scrollbar track
+    pub scroll_track: &'static str,
+    /// Scrollbar thumb
+    pub scroll_thumb: &'static str,
+    /// Marks an open directory or section
+    pub expanded: &'static str,
+    /// Marks a folded directory or section
+    pub collapsed: &'static str,
+    /// Prefix of the last background fetch time in the status bar
+    pub fetched: &'static str,
+    /// Marks a review note in a diff
+    pub note: &'static str,
+    /// Points from one thing to what it became or goes to
+    pub arrow: &'static str,
+    /// Stands for a line break in text shown on one line
+    pub enter: &'static str,
+    /// Starts an item of a list in a commit message
+    pub bullet: &'static str,
+    /// Separates parts of a line, like a summary and its date
+    pub separator: &'static str,
+    /// Borders around views and popups
+    pub border: border::Set,
+}
+
+impl Symbols {
+    /// Unicode glyphs; the branch symbol needs a powerline-patched font
+    pub const UNICODE: Symbols = Symbols {
+        branch: "\u{e0a0} ",
+        ascending: "↑",
+        descending: "↓",
+        scroll_begin: "↑",
+        scroll_end: "↓",
+        scroll_track: "║",
+        scroll_thumb: "█",
+        expanded: "▾",
+        collapsed: "▸",
+        fetched: "⇣",
+        note: "✎ ",
+        arrow: "→",
+        enter: "↵",
+        bullet: "•",
+        separator: "·",
+        border: border::PLAIN,
+    };
+
+    /// Plain ASCII that renders with any font and reads well in screen readers
+    pub const ASCII: Symbols = Symbols {
+        branch: "",
+        ascending: "^",
+        descending: "v",
+        scroll_begin: "^",
+        scroll_end: "v",
+        scroll_track: "|",
+        scroll_thumb: "#",
+        expanded: "-",
+        collapsed: "+",
+        fetched: "fetched ",
+        note: "note: ",
+        arrow: "->",
+        enter: "\\n",
+        bullet: "*",
+        separator: "-",
+        border: ASCII_BORDER,
+    };
+
+    /// Pick the symbol table for the settings; no-color mode implies ASCII
+    pub fn from_settings(settings: &Settings) -> Self {
+        match (settings.symbols, settings.color_mode) {
+            (SymbolSet::Ascii, _) | (_, ColorMode::None) => Self::ASCII,
+            (SymbolSet::Unicode, _) => Self::UNICODE,
+        }
+    }
+
+    /// A bordered block using this table's border set
+    pub fn block(&self) -> Block<'static> {
+        Block::default()
+            .borders(Borders::ALL)
+            .border_set(self.border)
+    }
+
+    /// A vertical scrollbar using this table's glyphs
+    pub fn scrollbar(&self) -> Scrollbar<'static> {
+        Scrollbar::default()
+            .orientation(ScrollbarOrientation::VerticalRight)
+            .begin_symbol(Some(self.scroll_begin))
+            .end_symbol(Some(self.scroll_end))
+            .track_symbol(Some(self.scroll_track))
+            .thumb_symbol(self.scroll_thumb)
+    }
+
+    /// The marker for a sort direction
+    pub fn sort_direction(&self, descending: bool) -> &'static str {
+        if descending {
+            self.descending
+        } else {
+            self.ascending
+        }
+    }
+}
+
+impl Default for Symbols {
+    fn default() -> Self {
+        Self::UNICODE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ascii_symbols_are_ascii() {
+        let s = Symbols::ASCII;
+        let border = s.border;
+        let glyphs = [
+            s.branch,
+            s.ascending,
+            s.descending,
+            s.scroll_begin,
+            s.scroll_end,
+            s.scroll_track,
+            s.scroll_thumb,
+            s.fetched,
+            s.note,
+            s.arrow,
+            s.enter,
+            s.bullet,
+            s.separator,
+            border.top_left,
+            border.top_right,
+            border.bottom_left,
+            border.bottom_right,
+            border.vertical_left,
+            border.vertical_right,
+            border.horizontal_top,
+            border.horizontal_bottom,
+        ];
+        assert!(glyphs.iter().all(|g| g.is_ascii()));
+    }
+
+    #[test]
+    fn test_symbols_from_settings() {
+        let mut settings = Settings::default();
+        assert_eq!(Symbols::from_settings(&settings), Symbols::UNICODE);
+
+        settings.symbols = SymbolSet::Ascii;
+        assert_eq!(Symbols::from_settings(&settings), Symbols::ASCII);
+
+        settings.symbols = SymbolSet::Unicode;
+        settings.color_mode = ColorMode::None;
+        assert_eq!(Symbols::from_settings(&settings), Symbols::ASCII);
+    }
+}
@@ -0,0 +1,102 @@
+use chrono::Local;
+use std::backtrace::Backtrace;
+use std::collections::VecDeque;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+
+/// Recent events and actions kept for the crash report
+const MAX_BREADCRUMBS: usize = 50;
+
+static BREADCRUMBS: LazyLock<Mutex<VecDeque<String>>> = LazyLock::new(Default::default);
+
+/// Remember a dispatched event or action, dropping the oldest past the limit
+pub fn breadcrumb(entry: impl Into<String>) {
+    if let Ok(mut breadcrumbs) = BREADCRUMBS.lock() {
+        if breadcrumbs.len() == MAX_BREADCRUMBS {
+            breadcrumbs.pop_front();
+        }
+        breadcrumbs.push_back(entry.into());
+    }
+}
+
+/// Get the remembered events and actions, oldest first
+fn breadcrumbs() -> Vec<String> {
+    BREADCRUMBS
+        .lock()
+        .map(|breadcrumbs| breadcrumbs.iter().cloned().collect())
+        .unwrap_or_default()
+}
+
+/// Directory crash reports are written to
+pub fn report_dir() -> Option<PathBuf> {
+    dirs::cache_dir().map(|dir| dir.join("rust-tig").join("crashes"))
+}
+
+/// Format a crash report
+pub fn format_report(message: &str, backtrace: &str, breadcrumbs: &[String]) -> String {
+    let mut report = format!(
+        "rust-tig {} crashed at {}\n\n{}\n\nLast {} events and actions, oldest first:\n",
+        env!("CARGO_PKG_VERSION"),
+        Local::now().format("%Y-%m-%d %H:%M:%S"),
+        message,
+        breadcrumbs.len()
+    );
+    for entry in breadcrumbs {
+        report.push_str("  ");
+        report.push_str(entry);
+        report.push('\n');
+    }
+    report.push_str("\nBacktrace:\n");
+    report.push_str(backtrace);
+    report.push('\n');
+    report
+}
+
+/// Write a report for a panic with `message` into `dir`, returning its path
+pub fn write_report(dir: &Path, message: &str) -> io::Result<PathBuf> {
+    let backtrace = Backtrace::force_capture().to_string();
+    let report = format_report(message, &backtrace, &breadcrumbs());
+
+    fs::create_dir_all(dir)?;
+    let path = dir.join(format!(
+        "crash-{}.txt",
+        Local::now().format("%Y%m%d-%H%M%S")
+    ));
+    fs::write(&path, report)?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_report_contents() {
+        let report = format_report(
+            "panicked at src/main.rs:1:1",
+            "0: main",
+            &["key j".to_string(), "action PopView".to_string()],
+        );
+        assert!(report.contains(env!("CARGO_PKG_VERSION")));
+        assert!(report.contains("panicked at src/main.rs:1:1"));
+        assert!(report.contains("  key j\n  action PopView\n"));
+        assert!(report.contains("Backtrace:\n0: main"));
+    }
+
+    #[test]
+    fn test_write_report_keeps_recent_breadcrumbs() {
+        for i in 0..MAX_BREADCRUMBS + 5 {
+            breadcrumb(format!("crumb {}", i));
+        }
+        assert!(breadcrumbs().len() <= MAX_BREADCRUMBS);
+
+        let temp_dir = TempDir::new().unwrap();
+        let path = write_report(temp_dir.path(), "boom").unwrap();
+        let report = fs::read_to_string(path).unwrap();
+        assert!(report.contains("boom"));
+        assert!(report.contains(&format!("crumb {}", MAX_BREADCRUMBS + 4)));
+    }
+}
@@ -0,0 +1,65 @@
+use crate::git::GitError;
+use thiserror::Error;
+
+/// Errors returned from views to the application
+///
+/// Routine failures such as "nothing to commit" are recoverable: the app
+/// shows them as a toast and keeps going. Anything else is fatal and ends
+/// the app with its message.
+#[derive(Error, Debug)]
+pub enum AppError {
+    /// A routine failure, shown as a toast
+    #[error("{0}")]
+    Recoverable(String),
+
+    /// A failure the app can't continue after
+    #[error(transparent)]
+    Fatal(#[from] anyhow::Error),
+}
+
+impl AppError {
+    /// Create a recoverable error from a message
+    pub fn recoverable(message: impl Into<String>) -> Self {
+        AppError::Recoverable(message.into())
+    }
+
+    /// Check whether the app can carry on after this error
+    pub fn is_recoverable(&self) -> bool {
+        matches!(self, AppError::Recoverable(_))
+    }
+}
+
+/// Git operations fail for routine reasons (locks, conflicts, nothing staged)
+/// that the user can fix without restarting
+impl From<GitError> for AppError {
+    fn from(error: GitError) -> Self {
+        match error.hint() {
+            Some(hint) => AppError::Recoverable(format!("{}: {}", error, hint)),
+            None => AppError::Recoverable(error.to_string()),
+        }
+    }
+}
+
+pub type AppResult<T> = std::result::Result<T, AppError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_git_errors_are_recoverable() {
+        let error: AppError = GitError::NothingToCommit.into();
+        assert!(error.is_recoverable());
+        assert_eq!(
+            error.to_string(),
+            "Nothing staged to commit: stage files with u first"
+        );
+    }
+
+    #[test]
+    fn test_other_errors_are_fatal() {
+        let error: AppError = anyhow::anyhow!("terminal gone").into();
+        assert!(!error.is_recoverable());
+        assert_eq!(error.to_string(), "terminal gone");
+    }
+}
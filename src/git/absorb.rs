@@ -0,0 +1,292 @@
+use super::{
+    commit::Commit,
+    error::{GitError, Result},
+    repository::Repository,
+    watch::note_local_write,
+};
+use git2::{ApplyOptions, BlameOptions, Delta, DiffOptions, Oid};
+use std::cell::RefCell;
+use std::collections::{HashMap, hash_map::Entry};
+use std::path::Path;
+use std::rc::Rc;
+
+/// A staged hunk and the commit it most likely fixes up
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AbsorbHunk {
+    pub path: String,
+    /// Hunk header, such as `@@ -3,2 +3,2 @@`
+    pub header: String,
+    old_start: u32,
+    old_lines: u32,
+    new_start: u32,
+    new_lines: u32,
+    /// The one commit that last touched the lines around the hunk, or None
+    /// when it's a new file or the lines came from several commits
+    pub target: Option<Oid>,
+}
+
+impl AbsorbHunk {
+    fn matches(&self, path: &str, hunk: &git2::DiffHunk) -> bool {
+        self.path == path
+            && self.old_start == hunk.old_start()
+            && self.old_lines == hunk.old_lines()
+            && self.new_start == hunk.new_start()
+            && self.new_lines == hunk.new_lines()
+    }
+}
+
+/// Where each staged hunk would go, as worked out by `plan_absorb`
+#[derive(Debug, Clone)]
+pub struct AbsorbPlan {
+    /// HEAD when the plan was made; the fixups go on top of it
+    pub head: Oid,
+    pub hunks: Vec<AbsorbHunk>,
+    /// The commits the hunks point at
+    pub commits: HashMap<Oid, Commit>,
+}
+
+impl AbsorbPlan {
+    /// Get the targets in the order their first hunk is staged
+    pub fn targets(&self) -> Vec<Oid> {
+        let mut targets = Vec::new();
+        for target in self.hunks.iter().filter_map(|hunk| hunk.target) {
+            if !targets.contains(&target) {
+                targets.push(target);
+            }
+        }
+        targets
+    }
+}
+
+/// Diff HEAD against the index one changed run of lines per hunk, so each
+/// hunk gets its own target
+fn staged_diff<'r>(git_repo: &'r git2::Repository, head: &git2::Commit) -> Result<git2::Diff<'r>> {
+    let mut options = DiffOptions::new();
+    options.context_lines(0);
+    Ok(git_repo.diff_tree_to_index(Some(&head.tree()?), None, Some(&mut options))?)
+}
+
+/// Suggest a commit for each staged hunk, the way `git absorb` does: blame
+/// the lines the hunk changes, or the lines either side of an insertion,
+/// and pick the commit when they all come from one
+pub async fn plan_absorb(repo: &Repository) -> Result<AbsorbPlan> {
+    let repo = repo.clone();
+
+    tokio::task::spawn_blocking(move || {
+        let git_repo = repo.open_git2()?;
+        let head = git_repo.head()?.peel_to_commit()?;
+        let diff = staged_diff(&git_repo, &head)?;
+
+        let mut hunks = Vec::new();
+        let mut commits = HashMap::new();
+        for (index, delta) in diff.deltas().enumerate() {
+            let Some(path) = delta.new_file().path().or(delta.old_file().path()) else {
+                continue;
+            };
+            let path = path.to_string_lossy().into_owned();
+            let Some(patch) = git2::Patch::from_diff(&diff, index)? else {
+                continue;
+            };
+            let blame = match delta.status() {
+                Delta::Modified | Delta::Deleted => {
+                    let mut options = BlameOptions::new();
+                    options.newest_commit(head.id());
+                    Some(git_repo.blame_file(Path::new(&path), Some(&mut options))?)
+                }
+                _ => None,
+            };
+
+            for hunk_index in 0..patch.num_hunks() {
+                let (hunk, _) = patch.hunk(hunk_index)?;
+                let lines = match hunk.old_lines() {
+                    0 => hunk.old_start()..hunk.old_start() + 2,
+                    count => hunk.old_start()..hunk.old_start() + count,
+                };
+                let mut blamed: Vec<Oid> = lines
+                    .filter_map(|line| blame.as_ref()?.get_line(line as usize))
+                    .map(|blame_hunk| blame_hunk.final_commit_id())
+                    .collect();
+                blamed.dedup();
+                let target = match blamed.as_slice() {
+                    [only] => Some(*only),
+                    _ => None,
+                };
+                if let Some(target) = target
+                    && let Entry::Vacant(entry) = commits.entry(target)
+                {
+                    entry.insert(Commit::from_git2(&git_repo.find_commit(target)?)?);
+                }
+                hunks.push(AbsorbHunk {
+                    path: path.clone(),
+                    header: String::from_utf8_lossy(hunk.header())
+                        .trim_end()
+                        .to_string(),
+                    old_start: hunk.old_start(),
+                    old_lines: hunk.old_lines(),
+                    new_start: hunk.new_start(),
+                    new_lines: hunk.new_lines(),
+                    target,
+                });
+            }
+        }
+
+        Ok(AbsorbPlan {
+            head: head.id(),
+            hunks,
+            commits,
+        })
+    })
+    .await
+    .map_err(GitError::task_join("plan an absorb"))?
+}
+
+/// Commit the planned hunks as `fixup!` commits on top of HEAD, one per
+/// target, ready for `git rebase -i --autosquash`
+///
+/// Hunks without a target stay staged. Returns the fixup commits, oldest
+/// first.
+pub async fn absorb(repo: &Repository, plan: AbsorbPlan) -> Result<Vec<Oid>> {
+    let repo = repo.clone();
+
+    tokio::task::spawn_blocking(move || {
+        let git_repo = repo.open_git2()?;
+        let mut parent = git_repo.head()?.peel_to_commit()?;
+        if parent.id() != plan.head {
+            return Err(GitError::CannotAbsorb(
+                "HEAD moved since the hunks were matched".to_string(),
+            ));
+        }
+        let head_tree = parent.tree()?;
+        let diff = staged_diff(&git_repo, &parent)?;
+        let signature = git_repo.signature()?;
+
+        // Each fixup's tree is HEAD with the hunks of its target and the
+        // targets before it, so every hunk applies to the lines it was
+        // planned against
+        let mut taken: Vec<&AbsorbHunk> = Vec::new();
+        let mut fixups = Vec::new();
+        for target in plan.targets() {
+            taken.extend(plan.hunks.iter().filter(|hunk| hunk.target == Some(target)));
+
+            // Files without a taken hunk are skipped whole, so a new file
+            // isn't left behind empty
+            let path = Rc::new(RefCell::new(String::new()));
+            let delta_path = Rc::clone(&path);
+            let delta_taken = &taken;
+            let mut options = ApplyOptions::new();
+            options.delta_callback(move |delta| {
+                let file = delta.and_then(|d| d.new_file().path().or(d.old_file().path()));
+                let file = file
+                    .map(|p| p.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                let wanted = delta_taken.iter().any(|hunk| hunk.path == file);
+                *delta_path.borrow_mut() = file;
+                wanted
+            });
+            options.hunk_callback(|hunk| {
+                hunk.is_some_and(|hunk| {
+                    taken
+                        .iter()
+                        .any(|taken| taken.matches(&path.borrow(), &hunk))
+                })
+            });
+            let mut index = git_repo.apply_to_tree(&head_tree, &diff, Some(&mut options))?;
+            let tree = git_repo.find_tree(index.write_tree_to(&git_repo)?)?;
+
+            let summary = git_repo
+                .find_commit(target)?
+                .summary()
+                .unwrap_or_default()
+                .to_string();
+            let message = format!("fixup! {}", summary);
+            let id = git_repo.commit(
+                Some("HEAD"),
+                &signature,
+                &signature,
+                &message,
+                &tree,
+                &[&parent],
+            )?;
+            parent = git_repo.find_commit(id)?;
+            fixups.push(id);
+        }
+
+        // The index keeps every staged change, so hunks without a target are
+        // still staged against the new HEAD
+        if !fixups.is_empty() {
+            note_local_write(repo.path());
+        }
+        Ok(fixups)
+    })
+    .await
+    .map_err(GitError::task_join("absorb the staged hunks"))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::RepoBuilder;
+
+    #[tokio::test]
+    async fn test_absorb_makes_a_fixup_per_target() {
+        let builder = RepoBuilder::new()
+            .file("a.txt", "one\ntwo\nthree\n")
+            .commit("Add a");
+        let add_a = builder.head();
+        let builder = builder.file("b.txt", "alpha\nbeta\n").commit("Add b");
+        let add_b = builder.head();
+        let (_temp_dir, repo) = builder
+            .file("a.txt", "one\nTWO\nthree\n")
+            .file("b.txt", "alpha\nbeta\ngamma\n")
+            .file("c.txt", "new\n")
+            .open()
+            .await;
+
+        let plan = plan_absorb(&repo).await.unwrap();
+        let targets: Vec<(&str, Option<Oid>)> = plan
+            .hunks
+            .iter()
+            .map(|hunk| (hunk.path.as_str(), hunk.target))
+            .collect();
+        assert_eq!(
+            targets,
+            [
+                ("a.txt", Some(add_a)),
+                ("b.txt", Some(add_b)),
+                ("c.txt", None)
+            ]
+        );
+        assert!(plan.hunks[0].header.starts_with("@@ -2 +2 @@"));
+        assert_eq!(plan.targets(), [add_a, add_b]);
+
+        let fixups = absorb(&repo, plan).await.unwrap();
+        assert_eq!(fixups.len(), 2);
+        let git_repo = repo.open_git2().unwrap();
+        let first = git_repo.find_commit(fixups[0]).unwrap();
+        assert_eq!(first.summary(), Some("fixup! Add a"));
+        let tip = git_repo.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(tip.id(), fixups[1]);
+        assert_eq!(tip.summary(), Some("fixup! Add b"));
+        assert!(tip.tree().unwrap().get_path(Path::new("c.txt")).is_err());
+
+        // The new file is all that's left staged
+        let plan = plan_absorb(&repo).await.unwrap();
+        assert_eq!(plan.hunks.len(), 1);
+        assert_eq!(plan.hunks[0].path, "c.txt");
+    }
+
+    #[tokio::test]
+    async fn test_lines_from_several_commits_have_no_target() {
+        let builder = RepoBuilder::new()
+            .file("a.txt", "one\n")
+            .commit("Add one")
+            .file("a.txt", "one\ntwo\n")
+            .commit("Add two");
+        let (_temp_dir, repo) = builder.file("a.txt", "ONE\nTWO\n").open().await;
+
+        let plan = plan_absorb(&repo).await.unwrap();
+        assert_eq!(plan.hunks.len(), 1);
+        assert_eq!(plan.hunks[0].target, None);
+        assert!(plan.targets().is_empty());
+    }
+}
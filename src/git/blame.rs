@@ -0,0 +1,169 @@
+use super::{commit::Commit, error::Result, repository::Repository};
+use git2::{BlameOptions, Oid};
+use std::collections::{HashMap, hash_map::Entry};
+
+/// A line of a file with the commit that last changed it
+#[derive(Debug, Clone)]
+pub struct BlameLine {
+    /// Line number in the blamed revision, starting at 1
+    pub line_no: usize,
+    pub content: String,
+    pub commit_id: Oid,
+    /// Path and line number of the line in that commit, which differ after
+    /// renames and changes above it since
+    pub orig_path: String,
+    pub orig_line_no: usize,
+}
+
+/// Who last changed each line of a file
+#[derive(Debug, Clone)]
+pub struct Blame {
+    pub path: String,
+    /// Commit the file was blamed at
+    pub revision: Oid,
+    pub lines: Vec<BlameLine>,
+    /// The commits the lines point at
+    pub commits: HashMap<Oid, Commit>,
+    /// First parent of each of those commits; root commits have none
+    pub parents: HashMap<Oid, Oid>,
+}
+
+impl Blame {
+    /// Get the commit that last changed a line
+    pub fn commit(&self, line: &BlameLine) -> Option<&Commit> {
+        self.commits.get(&line.commit_id)
+    }
+
+    /// Get the commit before the one that last changed a line, to blame
+    /// again to see who changed it before
+    pub fn parent(&self, line: &BlameLine) -> Option<Oid> {
+        self.parents.get(&line.commit_id).copied()
+    }
+}
+
+/// Blame `path` as of `revision`, or HEAD when there is none
+pub async fn load_blame(repo: &Repository, path: String, revision: Option<Oid>) -> Result<Blame> {
+    let repo = repo.clone();
+
+    tokio::task::spawn_blocking(move || {
+        let git_repo = repo.open_git2()?;
+        let commit = match revision {
+            Some(id) => git_repo.find_commit(id)?,
+            None => git_repo.head()?.peel_to_commit()?,
+        };
+
+        let blob = commit
+            .tree()?
+            .get_path(path.as_ref())?
+            .to_object(&git_repo)?
+            .peel_to_blob()?;
+        let content = String::from_utf8_lossy(blob.content()).into_owned();
+
+        let mut options = BlameOptions::new();
+        options.newest_commit(commit.id());
+        let blame = git_repo.blame_file(path.as_ref(), Some(&mut options))?;
+
+        let mut lines = Vec::new();
+        let mut commits = HashMap::new();
+        let mut parents = HashMap::new();
+        for (index, text) in content.lines().enumerate() {
+            let line_no = index + 1;
+            let Some(hunk) = blame.get_line(line_no) else {
+                continue;
+            };
+            let commit_id = hunk.final_commit_id();
+            if let Entry::Vacant(entry) = commits.entry(commit_id) {
+                let found = git_repo.find_commit(commit_id)?;
+                if let Ok(parent) = found.parent_id(0) {
+                    parents.insert(commit_id, parent);
+                }
+                entry.insert(Commit::from_git2(&found)?);
+            }
+            let orig_path = hunk
+                .path()
+                .and_then(|p| p.to_str())
+                .unwrap_or(&path)
+                .to_string();
+            lines.push(BlameLine {
+                line_no,
+                content: text.to_string(),
+                commit_id,
+                orig_path,
+                orig_line_no: hunk.orig_start_line() + (line_no - hunk.final_start_line()),
+            });
+        }
+
+        Ok(Blame {
+            path,
+            revision: commit.id(),
+            lines,
+            commits,
+            parents,
+        })
+    })
+    .await
+    .map_err(super::error::GitError::task_join("blame a file"))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::RepoBuilder;
+
+    #[tokio::test]
+    async fn test_blame_lines_point_at_their_commits() {
+        let builder = RepoBuilder::new()
+            .file("a.txt", "one\ntwo\n")
+            .commit("First");
+        let first = builder.head();
+        let builder = builder.file("a.txt", "one\n2\nthree\n").commit("Second");
+        let second = builder.head();
+        let (_temp_dir, repo) = builder.open().await;
+
+        let blame = load_blame(&repo, "a.txt".to_string(), None).await.unwrap();
+        let owners: Vec<(usize, &str, Oid)> = blame
+            .lines
+            .iter()
+            .map(|l| (l.line_no, l.content.as_str(), l.commit_id))
+            .collect();
+        assert_eq!(
+            owners,
+            [(1, "one", first), (2, "2", second), (3, "three", second)]
+        );
+        assert_eq!(blame.commit(&blame.lines[0]).unwrap().summary, "First");
+        assert_eq!(blame.parent(&blame.lines[1]), Some(first));
+        assert_eq!(blame.parent(&blame.lines[0]), None);
+
+        // Blaming an older revision sees the file as it was then
+        let blame = load_blame(&repo, "a.txt".to_string(), Some(first))
+            .await
+            .unwrap();
+        assert_eq!(blame.lines.len(), 2);
+        assert!(blame.lines.iter().all(|l| l.commit_id == first));
+
+        assert!(
+            load_blame(&repo, "missing.txt".to_string(), None)
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_lines_keep_their_number_in_their_commit() {
+        let (_temp_dir, repo) = RepoBuilder::new()
+            .file("a.txt", "one\ntwo\n")
+            .commit("First")
+            .file("a.txt", "zero\none\ntwo\n")
+            .commit("Second")
+            .open()
+            .await;
+
+        let blame = load_blame(&repo, "a.txt".to_string(), None).await.unwrap();
+        let origins: Vec<(usize, &str, usize)> = blame
+            .lines
+            .iter()
+            .map(|l| (l.line_no, l.orig_path.as_str(), l.orig_line_no))
+            .collect();
+        assert_eq!(origins, [(1, "a.txt", 1), (2, "a.txt", 1), (3, "a.txt", 2)]);
+    }
+}
@@ -0,0 +1,193 @@
+use super::commit::Commit;
+
+/// A heading of the changelog and the conventional-commit types listed
+/// under it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChangelogSection {
+    /// Heading text, like "Features"
+    pub title: String,
+    /// Types of the commits listed, like `feat`
+    pub types: Vec<String>,
+}
+
+/// How commits are written out as a changelog
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChangelogTemplate {
+    /// Sections in the order they're written
+    pub sections: Vec<ChangelogSection>,
+    /// Heading for commits of no listed type, which are left out without
+    /// one
+    pub other: Option<String>,
+    /// Format of a heading, with `%(title)` filled in
+    pub heading: String,
+    /// Format of a commit, with `%(type)`, `%(scope)` and `%(description)`
+    /// filled in along with the copy format placeholders
+    pub entry: String,
+}
+
+impl Default for ChangelogTemplate {
+    /// Every commit under one heading, without grouping
+    fn default() -> Self {
+        ChangelogTemplate {
+            sections: Vec::new(),
+            other: Some("Changes".to_string()),
+            heading: "### %(title)".to_string(),
+            entry: "- %s (%h)".to_string(),
+        }
+    }
+}
+
+/// The parts of a conventional-commit summary like `fix(parser)!: desc`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Conventional<'a> {
+    pub kind: &'a str,
+    pub scope: Option<&'a str>,
+    /// Marked `!`, for a breaking change
+    pub breaking: bool,
+    pub description: &'a str,
+}
+
+impl<'a> Conventional<'a> {
+    /// Split a commit summary into its conventional-commit parts, if it
+    /// follows the convention
+    pub fn parse(summary: &'a str) -> Option<Self> {
+        let (prefix, description) = summary.split_once(": ")?;
+        let (prefix, breaking) = match prefix.strip_suffix('!') {
+            Some(prefix) => (prefix, true),
+            None => (prefix, false),
+        };
+        let (kind, scope) = match prefix.split_once('(') {
+            Some((kind, scope)) => (kind, Some(scope.strip_suffix(')')?)),
+            None => (prefix, None),
+        };
+        if kind.is_empty() || !kind.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return None;
+        }
+        Some(Conventional {
+            kind,
+            scope,
+            breaking,
+            description: description.trim(),
+        })
+    }
+}
+
+/// Write commits out as a changelog, grouped into the template's sections
+/// by their conventional-commit type and kept in the given order within
+/// each; sections without commits are left out
+pub fn render(commits: &[Commit], template: &ChangelogTemplate, web_url: Option<&str>) -> String {
+    let mut groups: Vec<(&str, Vec<String>)> = template
+        .sections
+        .iter()
+        .map(|section| (section.title.as_str(), Vec::new()))
+        .chain(template.other.as_deref().map(|title| (title, Vec::new())))
+        .collect();
+
+    for commit in commits {
+        let conventional = Conventional::parse(&commit.summary);
+        let kind = conventional.map(|c| c.kind.to_ascii_lowercase());
+        let section = template
+            .sections
+            .iter()
+            .position(|section| {
+                kind.as_ref()
+                    .is_some_and(|kind| section.types.contains(kind))
+            })
+            .or(template.other.as_ref().map(|_| template.sections.len()));
+        let Some(section) = section else {
+            continue;
+        };
+        let extra = [
+            ("%(type)", conventional.map_or("", |c| c.kind)),
+            (
+                "%(scope)",
+                conventional.and_then(|c| c.scope).unwrap_or_default(),
+            ),
+            (
+                "%(description)",
+                conventional.map_or(commit.summary.as_str(), |c| c.description),
+            ),
+        ];
+        groups[section]
+            .1
+            .push(commit.format_with(&template.entry, web_url, &extra));
+    }
+
+    groups
+        .into_iter()
+        .filter(|(_, entries)| !entries.is_empty())
+        .map(|(title, entries)| {
+            let heading = template.heading.replace("%(title)", title);
+            format!("{}\n\n{}\n", heading, entries.join("\n"))
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Local, TimeZone};
+    use git2::Oid;
+
+    fn commit(summary: &str) -> Commit {
+        Commit {
+            id: Oid::from_str("1234567890abcdef1234567890abcdef12345678").unwrap(),
+            short_id: "1234567".to_string(),
+            author: "Ann".to_string(),
+            author_email: "ann@example.com".to_string(),
+            date: Local.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap(),
+            summary: summary.to_string(),
+            message: summary.to_string(),
+            refs: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_parse_conventional() {
+        let parsed = Conventional::parse("feat(parser)!: Accept tabs").unwrap();
+        assert_eq!(
+            (parsed.kind, parsed.scope, parsed.breaking),
+            ("feat", Some("parser"), true)
+        );
+        assert_eq!(parsed.description, "Accept tabs");
+        assert_eq!(Conventional::parse("fix: Crash").unwrap().scope, None);
+        assert_eq!(Conventional::parse("Merge branch 'x': y"), None);
+        assert_eq!(Conventional::parse("Fix the crash"), None);
+    }
+
+    #[test]
+    fn test_render_groups_by_type() {
+        let section = |title: &str, kind: &str| ChangelogSection {
+            title: title.to_string(),
+            types: vec![kind.to_string()],
+        };
+        let mut template = ChangelogTemplate {
+            sections: vec![
+                section("Features", "feat"),
+                section("Fixes", "fix"),
+                section("Docs", "docs"),
+            ],
+            other: Some("Other".to_string()),
+            heading: "## %(title)".to_string(),
+            entry: "- %(description) (%h)".to_string(),
+        };
+        let commits = [
+            commit("fix(ui): Flicker"),
+            commit("Tidy up"),
+            commit("feat: Tabs"),
+            commit("Fix: Crash"),
+        ];
+
+        let expected = "## Features\n\n- Tabs (1234567)\n\n## Fixes\n\n- Flicker (1234567)\n- Crash (1234567)\n\n\
+                        ## Other\n\n- Tidy up (1234567)\n";
+        assert_eq!(render(&commits, &template, None), expected);
+
+        template.other = None;
+        template.entry = "- %(scope)|%(type)|%s".to_string();
+        assert_eq!(
+            render(&commits[..2], &template, None),
+            "## Fixes\n\n- ui|fix|fix(ui): Flicker\n"
+        );
+    }
+}
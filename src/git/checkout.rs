@@ -0,0 +1,239 @@
+use super::{
+    error::{GitError, Result},
+    pull::is_dirty,
+    refs::RefKind,
+    repository::Repository,
+    watch::note_local_write,
+};
+use git2::{BranchType, Oid, build::CheckoutBuilder};
+
+/// What to check out
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CheckoutTarget {
+    /// A reference by its full name: local branches are checked out,
+    /// remote branches through a local branch of the same name, and
+    /// anything else detached at its commit
+    Ref(String),
+    /// A commit, detaching HEAD
+    Commit(Oid),
+}
+
+impl CheckoutTarget {
+    /// Name the target for messages, like "main" or "1234567"
+    pub fn describe(&self) -> String {
+        match self {
+            CheckoutTarget::Ref(name) => shorthand(name).to_string(),
+            CheckoutTarget::Commit(id) => format!("{:.7}", id.to_string()),
+        }
+    }
+}
+
+/// Check out a branch or commit, returning what HEAD is now, like "main" or
+/// "detached at 1234567"
+///
+/// Uncommitted changes to tracked files stop the checkout unless
+/// `carry_changes` is set; then they come along, as long as none of the
+/// files differ between HEAD and the target.
+pub async fn checkout(
+    repo: &Repository,
+    target: CheckoutTarget,
+    carry_changes: bool,
+) -> Result<String> {
+    let repo = repo.clone();
+
+    tokio::task::spawn_blocking(move || {
+        let git_repo = repo.open_git2()?;
+        if !carry_changes && is_dirty(&git_repo)? {
+            return Err(GitError::UncommittedChanges);
+        }
+
+        let (object, branch) = match &target {
+            CheckoutTarget::Commit(id) => (git_repo.find_commit(*id)?.into_object(), None),
+            CheckoutTarget::Ref(name) => {
+                let reference = git_repo.find_reference(name)?;
+                let commit = reference.peel_to_commit()?;
+                let branch = match RefKind::from_name(name) {
+                    RefKind::LocalBranch => Some(name.clone()),
+                    RefKind::RemoteBranch => Some(local_branch_for(&git_repo, name, &commit)?),
+                    RefKind::Tag | RefKind::Other => None,
+                };
+                let object = match &branch {
+                    Some(branch) => git_repo
+                        .find_reference(branch)?
+                        .peel_to_commit()?
+                        .into_object(),
+                    None => commit.into_object(),
+                };
+                (object, branch)
+            }
+        };
+
+        git_repo
+            .checkout_tree(&object, Some(CheckoutBuilder::new().safe()))
+            .map_err(|e| match e.code() {
+                git2::ErrorCode::Conflict => GitError::CannotCheckout(format!(
+                    "uncommitted changes to files that differ in {}",
+                    target.describe()
+                )),
+                _ => e.into(),
+            })?;
+        let head = match &branch {
+            Some(branch) => {
+                git_repo.set_head(branch)?;
+                shorthand(branch).to_string()
+            }
+            None => {
+                git_repo.set_head_detached(object.id())?;
+                format!("detached at {:.7}", object.id().to_string())
+            }
+        };
+        note_local_write(repo.path());
+        Ok(head)
+    })
+    .await
+    .map_err(GitError::task_join("check out"))?
+}
+
+/// Work out what a name typed at the prompt checks out: a branch, remote
+/// branch or tag by its short or full name, or else any revision, detached
+pub async fn resolve_target(repo: &Repository, name: String) -> Result<CheckoutTarget> {
+    let repo = repo.clone();
+
+    tokio::task::spawn_blocking(move || {
+        let git_repo = repo.open_git2()?;
+        if let Ok(reference) = git_repo.resolve_reference_from_short_name(&name)
+            && let Some(full_name) = reference.name()
+        {
+            return Ok(CheckoutTarget::Ref(full_name.to_string()));
+        }
+        let commit = git_repo
+            .revparse_single(&name)
+            .and_then(|object| object.peel_to_commit())
+            .map_err(|_| GitError::RefNotFound(name))?;
+        Ok(CheckoutTarget::Commit(commit.id()))
+    })
+    .await
+    .map_err(GitError::task_join("resolve a checkout target"))?
+}
+
+/// Drop the `refs/heads/`-style prefix of a reference name
+pub fn shorthand(name: &str) -> &str {
+    ["refs/heads/", "refs/remotes/", "refs/tags/"]
+        .iter()
+        .find_map(|prefix| name.strip_prefix(prefix))
+        .unwrap_or(name)
+}
+
+/// Find the local branch a remote branch is checked out through, creating
+/// it to track the remote branch when there's none, like `git switch`
+fn local_branch_for(
+    git_repo: &git2::Repository,
+    remote_ref: &str,
+    commit: &git2::Commit,
+) -> Result<String> {
+    let remote_branch = shorthand(remote_ref);
+    let name = remote_branch
+        .split_once('/')
+        .map_or(remote_branch, |(_, name)| name);
+    if git_repo.find_branch(name, BranchType::Local).is_err() {
+        let mut branch = git_repo.branch(name, commit, false)?;
+        branch.set_upstream(Some(remote_branch))?;
+    }
+    Ok(format!("refs/heads/{}", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::RepoBuilder;
+
+    #[tokio::test]
+    async fn test_checks_out_branches_and_commits() {
+        let builder = RepoBuilder::new()
+            .file("a.txt", "one\n")
+            .commit("Initial")
+            .branch("topic");
+        let initial = builder.head();
+        let default_branch = builder.git2().head().unwrap().name().unwrap().to_string();
+        let (temp_dir, repo) = builder.file("a.txt", "two\n").commit("Second").open().await;
+        let file = temp_dir.path().join("a.txt");
+
+        let target = CheckoutTarget::Ref("refs/heads/topic".to_string());
+        assert_eq!(checkout(&repo, target, false).await.unwrap(), "topic");
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "one\n");
+
+        let head = checkout(&repo, CheckoutTarget::Ref(default_branch.clone()), false)
+            .await
+            .unwrap();
+        assert_eq!(head, shorthand(&default_branch));
+        let detached = checkout(&repo, CheckoutTarget::Commit(initial), false)
+            .await
+            .unwrap();
+        assert_eq!(detached, format!("detached at {:.7}", initial.to_string()));
+        assert!(repo.open_git2().unwrap().head_detached().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_dirty_tree_needs_carrying() {
+        let builder = RepoBuilder::new()
+            .file("a.txt", "one\n")
+            .file("b.txt", "one\n")
+            .commit("Initial")
+            .branch("topic");
+        let default_branch = builder.git2().head().unwrap().name().unwrap().to_string();
+        let (temp_dir, repo) = builder
+            .file("a.txt", "two\n")
+            .commit("Second")
+            .write("b.txt", "edited\n")
+            .open()
+            .await;
+
+        let topic = CheckoutTarget::Ref("refs/heads/topic".to_string());
+        assert!(matches!(
+            checkout(&repo, topic.clone(), false).await,
+            Err(GitError::UncommittedChanges)
+        ));
+        assert_eq!(checkout(&repo, topic.clone(), true).await.unwrap(), "topic");
+        assert_eq!(
+            std::fs::read_to_string(temp_dir.path().join("b.txt")).unwrap(),
+            "edited\n"
+        );
+
+        // A file the target changes can't carry its edits over
+        std::fs::write(temp_dir.path().join("a.txt"), "edited\n").unwrap();
+        let back = CheckoutTarget::Ref(default_branch);
+        assert!(matches!(
+            checkout(&repo, back, true).await,
+            Err(GitError::CannotCheckout(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_resolve_typed_names() {
+        let builder = RepoBuilder::new().commit("Initial").branch("topic");
+        let initial = builder.head();
+        let (_temp_dir, repo) = builder.commit("Second").open().await;
+        repo.open_git2()
+            .unwrap()
+            .reference("refs/tags/v1", initial, false, "")
+            .unwrap();
+
+        let resolve = async |name: &str| resolve_target(&repo, name.to_string()).await;
+        assert_eq!(
+            resolve("topic").await.unwrap(),
+            CheckoutTarget::Ref("refs/heads/topic".to_string())
+        );
+        assert_eq!(
+            resolve("v1").await.unwrap(),
+            CheckoutTarget::Ref("refs/tags/v1".to_string())
+        );
+        assert_eq!(
+            resolve("HEAD~1").await.unwrap(),
+            CheckoutTarget::Commit(initial)
+        );
+        assert!(matches!(
+            resolve("nope").await,
+            Err(GitError::RefNotFound(_))
+        ));
+    }
+}
@@ -0,0 +1,124 @@
+use super::{error::Result, repository::Repository};
+use git2::{Oid, Revwalk};
+use std::collections::{HashMap, HashSet};
+
+/// Whether a local commit still has to land upstream, as `git cherry` tells
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CherryMark {
+    /// No upstream commit makes the same change ("+")
+    Pending,
+    /// An upstream commit makes the same change, e.g. after a rebase ("-")
+    Applied,
+}
+
+impl CherryMark {
+    /// Get the symbol `git cherry` prints for the mark
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            CherryMark::Pending => "+",
+            CherryMark::Applied => "-",
+        }
+    }
+}
+
+/// Mark the commits on HEAD but not on `upstream` by whether an upstream
+/// commit has the same patch id
+///
+/// Merge commits get no mark, like `git cherry` skips them.
+pub async fn cherry_marks(repo: &Repository, upstream: String) -> Result<HashMap<Oid, CherryMark>> {
+    let repo = repo.clone();
+
+    tokio::task::spawn_blocking(move || {
+        let git_repo = repo.open_git2()?;
+        let upstream = git_repo.revparse_single(&upstream)?.peel_to_commit()?.id();
+        let head = git_repo.head()?.peel_to_commit()?.id();
+
+        let mut upstream_only = git_repo.revwalk()?;
+        upstream_only.push(upstream)?;
+        upstream_only.hide(head)?;
+        let mut upstream_ids = HashSet::new();
+        for oid in upstream_only {
+            if let Some(patch_id) = patch_id(&git_repo, oid?)? {
+                upstream_ids.insert(patch_id);
+            }
+        }
+
+        let mut local_only = git_repo.revwalk()?;
+        local_only.push(head)?;
+        local_only.hide(upstream)?;
+        marks(&git_repo, local_only, &upstream_ids)
+    })
+    .await
+    .map_err(super::error::GitError::task_join("compare patch ids"))?
+}
+
+fn marks(
+    git_repo: &git2::Repository,
+    local_only: Revwalk,
+    upstream_ids: &HashSet<Oid>,
+) -> Result<HashMap<Oid, CherryMark>> {
+    let mut marks = HashMap::new();
+    for oid in local_only {
+        let oid = oid?;
+        if let Some(patch_id) = patch_id(git_repo, oid)? {
+            let mark = match upstream_ids.contains(&patch_id) {
+                true => CherryMark::Applied,
+                false => CherryMark::Pending,
+            };
+            marks.insert(oid, mark);
+        }
+    }
+    Ok(marks)
+}
+
+/// Get the patch id of a commit's change to its parent, or None for merges
+fn patch_id(git_repo: &git2::Repository, oid: Oid) -> Result<Option<Oid>> {
+    let commit = git_repo.find_commit(oid)?;
+    if commit.parent_count() > 1 {
+        return Ok(None);
+    }
+    let parent_tree = match commit.parent(0) {
+        Ok(parent) => Some(parent.tree()?),
+        Err(_) => None,
+    };
+    let diff = git_repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), None)?;
+    Ok(Some(diff.patchid(None)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::RepoBuilder;
+
+    #[tokio::test]
+    async fn test_cherry_marks_rebased_commits() {
+        // "upstream" picks up the same change as the first local commit
+        let builder = RepoBuilder::new()
+            .commit("Base")
+            .branch("upstream")
+            .branch("local")
+            .checkout("upstream")
+            .file("a.txt", "a\n")
+            .commit("Add a upstream")
+            .checkout("local")
+            .file("a.txt", "a\n")
+            .commit("Add a")
+            .file("b.txt", "b\n")
+            .commit("Add b");
+        let head = builder.head();
+        let (_temp_dir, repo) = builder.open().await;
+
+        let marks = cherry_marks(&repo, "upstream".to_string()).await.unwrap();
+        assert_eq!(marks.len(), 2);
+        assert_eq!(marks[&head], CherryMark::Pending);
+        assert_eq!(
+            marks
+                .values()
+                .filter(|&&m| m == CherryMark::Applied)
+                .count(),
+            1
+        );
+
+        assert!(cherry_marks(&repo, "nope".to_string()).await.is_err());
+    }
+}
@@ -0,0 +1,253 @@
+use super::{
+    error::{GitError, Result},
+    pull::is_dirty,
+    repository::Repository,
+    watch::note_local_write,
+};
+use git2::{Oid, RepositoryState};
+
+/// A cherry-pick that stopped on conflicts, left in the working tree and
+/// index for the user to resolve and commit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PickConflict {
+    pub commit: Oid,
+    pub summary: String,
+    /// Files with conflicts
+    pub paths: Vec<String>,
+}
+
+/// How a cherry-pick of one or more commits went
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PickReport {
+    /// The new commit of each pick, oldest first
+    pub picked: Vec<Oid>,
+    /// Picks whose change the branch already had, so nothing was committed
+    pub empty: usize,
+    /// The pick that stopped on conflicts, if one did
+    pub conflict: Option<PickConflict>,
+    /// Picks not started because of the conflict
+    pub remaining: usize,
+}
+
+impl PickReport {
+    /// Say what happened in one line
+    pub fn summary(&self) -> String {
+        let mut summary = match self.picked.len() {
+            0 => "Nothing cherry-picked".to_string(),
+            1 => format!("Cherry-picked {:.7}", self.picked[0].to_string()),
+            picked => format!("Cherry-picked {} commits", picked),
+        };
+        if self.empty > 0 {
+            summary.push_str(&format!(", {} already on the branch", self.empty));
+        }
+        if let Some(conflict) = &self.conflict {
+            summary = format!(
+                "{:.7} conflicts in {} files; resolve, stage and commit to finish",
+                conflict.commit.to_string(),
+                conflict.paths.len()
+            );
+            if self.remaining > 0 {
+                summary.push_str(&format!(" ({} more not picked)", self.remaining));
+            }
+        }
+        summary
+    }
+}
+
+/// Name the operation that has the repository in its state, like a merge
+/// waiting for its conflicts to be resolved, or None if there is none
+pub(super) fn operation_in_progress(git_repo: &git2::Repository) -> Option<&'static str> {
+    match git_repo.state() {
+        RepositoryState::Clean => None,
+        RepositoryState::CherryPick | RepositoryState::CherryPickSequence => Some("cherry-pick"),
+        RepositoryState::Merge => Some("merge"),
+        RepositoryState::Revert | RepositoryState::RevertSequence => Some("revert"),
+        RepositoryState::Bisect => Some("bisect"),
+        RepositoryState::ApplyMailbox => Some("git am"),
+        _ => Some("rebase"),
+    }
+}
+
+/// List the files with conflicts in `index`
+pub(super) fn conflicted_paths(index: &git2::Index) -> Result<Vec<String>> {
+    Ok(index
+        .conflicts()?
+        .flatten()
+        .filter_map(|c| c.our.or(c.their).or(c.ancestor))
+        .map(|entry| String::from_utf8_lossy(&entry.path).into_owned())
+        .collect())
+}
+
+/// Apply `ids` on top of HEAD one at a time, in order, committing each
+/// with its original author and message like `git cherry-pick`
+///
+/// A pick that conflicts stops there, with the repository left in the
+/// cherry-pick state so the status view shows the conflicted files and
+/// committing the resolution finishes it. Uncommitted changes to tracked
+/// files, merge commits and another operation in progress refuse the
+/// whole pick before anything changes.
+pub async fn cherry_pick(repo: &Repository, ids: Vec<Oid>) -> Result<PickReport> {
+    let repo = repo.clone();
+
+    tokio::task::spawn_blocking(move || {
+        let git_repo = repo.open_git2()?;
+        if let Some(operation) = operation_in_progress(&git_repo) {
+            return Err(GitError::CannotCherryPick(format!(
+                "a {} is in progress; finish it first",
+                operation
+            )));
+        }
+        if is_dirty(&git_repo)? {
+            return Err(GitError::UncommittedChanges);
+        }
+        let picks = ids
+            .iter()
+            .map(|&id| git_repo.find_commit(id))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        if let Some(merge) = picks.iter().find(|pick| pick.parent_count() != 1) {
+            return Err(GitError::CannotCherryPick(format!(
+                "{:.7} is a merge; cherry-pick it by hand",
+                merge.id().to_string()
+            )));
+        }
+
+        let signature = git_repo.signature()?;
+        let mut report = PickReport {
+            picked: Vec::new(),
+            empty: 0,
+            conflict: None,
+            remaining: 0,
+        };
+        for (i, pick) in picks.iter().enumerate() {
+            git_repo.cherrypick(pick, None)?;
+            note_local_write(repo.path());
+            let mut index = git_repo.index()?;
+            if index.has_conflicts() {
+                let paths = conflicted_paths(&index)?;
+                report.conflict = Some(PickConflict {
+                    commit: pick.id(),
+                    summary: pick.summary().unwrap_or_default().to_string(),
+                    paths,
+                });
+                report.remaining = picks.len() - i - 1;
+                return Ok(report);
+            }
+
+            let head = git_repo.head()?.peel_to_commit()?;
+            let tree_id = index.write_tree()?;
+            if tree_id == head.tree_id() {
+                report.empty += 1;
+            } else {
+                let tree = git_repo.find_tree(tree_id)?;
+                let message = pick.message().unwrap_or_default();
+                let id = git_repo.commit(
+                    Some("HEAD"),
+                    &pick.author(),
+                    &signature,
+                    message,
+                    &tree,
+                    &[&head],
+                )?;
+                report.picked.push(id);
+            }
+            git_repo.cleanup_state()?;
+        }
+        Ok(report)
+    })
+    .await
+    .map_err(GitError::task_join("cherry-pick"))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::RepoBuilder;
+
+    /// Get the commits of `topic` not on `master`, oldest first
+    fn topic_commits(git_repo: &git2::Repository) -> Vec<Oid> {
+        let mut walk = git_repo.revwalk().unwrap();
+        walk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::REVERSE)
+            .unwrap();
+        walk.push_ref("refs/heads/topic").unwrap();
+        walk.hide_ref("refs/heads/master").unwrap();
+        walk.map(|oid| oid.unwrap()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_picks_commits_in_order() {
+        let builder = RepoBuilder::new()
+            .file("a.txt", "a\n")
+            .commit("Initial")
+            .branch("topic")
+            .checkout("topic")
+            .file("b.txt", "b\n")
+            .commit("Add b")
+            .file("c.txt", "c\n")
+            .commit("Add c")
+            .checkout("master");
+        let picks = topic_commits(builder.git2());
+        let (dir, repo) = builder.open().await;
+
+        let report = cherry_pick(&repo, picks.clone()).await.unwrap();
+        assert_eq!(report.picked.len(), 2);
+        assert_eq!(report.summary(), "Cherry-picked 2 commits");
+        let git_repo = repo.open_git2().unwrap();
+        let head = git_repo.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(head.summary(), Some("Add c"));
+        assert_eq!(head.parent(0).unwrap().summary(), Some("Add b"));
+        assert!(dir.path().join("c.txt").exists());
+        assert_eq!(git_repo.state(), RepositoryState::Clean);
+
+        // Picking the same change again commits nothing
+        let report = cherry_pick(&repo, picks[1..].to_vec()).await.unwrap();
+        assert_eq!((report.picked.len(), report.empty), (0, 1));
+    }
+
+    #[tokio::test]
+    async fn test_conflict_leaves_cherry_pick_state() {
+        let builder = RepoBuilder::new()
+            .file("a.txt", "a\n")
+            .commit("Initial")
+            .branch("topic")
+            .checkout("topic")
+            .file("a.txt", "theirs\n")
+            .commit("Change a")
+            .file("b.txt", "b\n")
+            .commit("Add b")
+            .checkout("master")
+            .file("a.txt", "ours\n")
+            .commit("Also change a");
+        let picks = topic_commits(builder.git2());
+        let (dir, repo) = builder.open().await;
+
+        let report = cherry_pick(&repo, picks.clone()).await.unwrap();
+        let conflict = report.conflict.as_ref().unwrap();
+        assert_eq!(conflict.paths, ["a.txt"]);
+        assert_eq!(report.remaining, 1);
+        assert_eq!(
+            repo.open_git2().unwrap().state(),
+            RepositoryState::CherryPick
+        );
+
+        // Nothing more until the pick in progress is finished
+        let refused = cherry_pick(&repo, picks.clone()).await;
+        assert!(matches!(refused, Err(GitError::CannotCherryPick(_))));
+
+        // Committing the resolution finishes it, starting from its message
+        std::fs::write(dir.path().join("a.txt"), "both\n").unwrap();
+        crate::git::status::stage_file(&repo, "a.txt".to_string())
+            .await
+            .unwrap();
+        let message = crate::git::status::commit_template(&repo)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(message.trim_end(), "Change a");
+        crate::git::status::commit_staged(&repo, message)
+            .await
+            .unwrap();
+        assert_eq!(repo.open_git2().unwrap().state(), RepositoryState::Clean);
+        let report = cherry_pick(&repo, picks[1..].to_vec()).await.unwrap();
+        assert_eq!(report.picked.len(), 1);
+    }
+}
@@ -1,9 +1,11 @@
 use super::error::{GitError, Result};
+use super::repository::Repository;
+use crate::clock::Clock;
 use chrono::{DateTime, Local};
 use git2::{Oid, Time};
 
 /// Represents a git commit
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Commit {
     pub id: Oid,
     pub short_id: String,
@@ -22,26 +24,14 @@ impl Commit {
         let short_id = id.to_string()[..7].to_string();
 
         let author = commit.author();
-        let author_name = author
-            .name()
-            .ok_or(GitError::InvalidUtf8)?
-            .to_string();
-        let author_email = author
-            .email()
-            .ok_or(GitError::InvalidUtf8)?
-            .to_string();
+        let author_name = author.name().ok_or(GitError::InvalidUtf8)?.to_string();
+        let author_email = author.email().ok_or(GitError::InvalidUtf8)?.to_string();
 
         let date = time_to_datetime(author.when());
 
-        let summary = commit
-            .summary()
-            .ok_or(GitError::InvalidUtf8)?
-            .to_string();
+        let summary = commit.summary().ok_or(GitError::InvalidUtf8)?.to_string();
 
-        let message = commit
-            .message()
-            .ok_or(GitError::InvalidUtf8)?
-            .to_string();
+        let message = commit.message().ok_or(GitError::InvalidUtf8)?.to_string();
 
         Ok(Self {
             id,
@@ -55,18 +45,87 @@ impl Commit {
         })
     }
 
+    /// The message after the summary line, trimmed
+    pub fn body(&self) -> &str {
+        self.message
+            .trim()
+            .strip_prefix(self.summary.as_str())
+            .unwrap_or_default()
+            .trim()
+    }
+
     /// Format the date in a human-readable way
     pub fn date_str(&self) -> String {
         self.date.format("%Y-%m-%d %H:%M").to_string()
     }
 
-    /// Format the date in a relative way (e.g., "2 hours ago")
-    pub fn relative_date(&self) -> String {
-        let now = Local::now();
-        let duration = now.signed_duration_since(self.date);
+    /// Fill in a copy format: `%H` and `%h` are the full and short hash,
+    /// `%s` the summary, `%b` the body, `%an` and `%ae` the author's name
+    /// and email, `%ad` the date, `%(url)` the commit's page under
+    /// `web_url` and `%%` a percent sign. Anything else is kept as typed.
+    pub fn format(&self, template: &str, web_url: Option<&str>) -> String {
+        self.format_with(template, web_url, &[])
+    }
+
+    /// Fill in a copy format like [`Commit::format`], along with `extra`
+    /// placeholders and their values
+    pub fn format_with(
+        &self,
+        template: &str,
+        web_url: Option<&str>,
+        extra: &[(&str, &str)],
+    ) -> String {
+        let url = web_url
+            .map(|base| format!("{}/commit/{}", base, self.id))
+            .unwrap_or_default();
+        let body = self.body();
+        let placeholders = [
+            ("%(url)", url.as_str()),
+            ("%an", self.author.as_str()),
+            ("%ae", self.author_email.as_str()),
+            ("%ad", &self.date.format("%Y-%m-%d").to_string()),
+            ("%H", &self.id.to_string()),
+            ("%h", self.short_id.as_str()),
+            ("%s", self.summary.as_str()),
+            ("%b", body),
+            ("%%", "%"),
+        ];
+
+        let mut formatted = String::new();
+        let mut rest = template;
+        while let Some(start) = rest.find('%') {
+            formatted.push_str(&rest[..start]);
+            rest = &rest[start..];
+            match extra
+                .iter()
+                .chain(placeholders.iter())
+                .find(|(placeholder, _)| rest.starts_with(placeholder))
+            {
+                Some((placeholder, value)) => {
+                    formatted.push_str(value);
+                    rest = &rest[placeholder.len()..];
+                }
+                None => {
+                    formatted.push('%');
+                    rest = &rest[1..];
+                }
+            }
+        }
+        formatted.push_str(rest);
+        formatted
+    }
+
+    /// Format the date relative to the clock's time (e.g., "2 hours ago")
+    pub fn relative_date(&self, clock: &dyn Clock) -> String {
+        let duration = clock.now().signed_duration_since(self.date);
 
         let format_unit = |count: i64, unit: &str| {
-            format!("{} {}{} ago", count, unit, if count == 1 { "" } else { "s" })
+            format!(
+                "{} {}{} ago",
+                count,
+                unit,
+                if count == 1 { "" } else { "s" }
+            )
         };
 
         if duration.num_seconds() < 60 {
@@ -102,10 +161,20 @@ fn time_to_datetime(time: Time) -> DateTime<Local> {
         .with_timezone(&Local)
 }
 
+/// Load one commit by id
+pub async fn load_commit(repo: &Repository, id: Oid) -> Result<Commit> {
+    let repo = repo.clone();
+
+    tokio::task::spawn_blocking(move || Commit::from_git2(&repo.open_git2()?.find_commit(id)?))
+        .await
+        .map_err(GitError::task_join("load the commit"))?
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use chrono::Datelike;
+    use crate::clock::FixedClock;
+    use chrono::{Datelike, Duration, TimeZone};
 
     #[test]
     fn test_short_id_length() {
@@ -122,15 +191,72 @@ mod tests {
     }
 
     #[test]
-    fn test_relative_date_pluralization() {
-        // Test that the pluralization works correctly
-        let format_unit = |count: i64, unit: &str| {
-            format!("{} {}{} ago", count, unit, if count == 1 { "" } else { "s" })
+    fn test_relative_date() {
+        let now = Local.with_ymd_and_hms(2024, 6, 15, 12, 0, 0).unwrap();
+        let clock = FixedClock(now);
+        let commit = |date: DateTime<Local>| Commit {
+            id: Oid::zero(),
+            short_id: "0000000".to_string(),
+            author: "Test".to_string(),
+            author_email: "test@example.com".to_string(),
+            date,
+            summary: String::new(),
+            message: String::new(),
+            refs: vec![],
+        };
+
+        assert_eq!(commit(now).relative_date(&clock), "just now");
+        assert_eq!(
+            commit(now - Duration::minutes(1)).relative_date(&clock),
+            "1 minute ago"
+        );
+        assert_eq!(
+            commit(now - Duration::hours(5)).relative_date(&clock),
+            "5 hours ago"
+        );
+        assert_eq!(
+            commit(now - Duration::days(1)).relative_date(&clock),
+            "1 day ago"
+        );
+        assert_eq!(
+            commit(now - Duration::weeks(2)).relative_date(&clock),
+            "2 weeks ago"
+        );
+        assert_eq!(
+            commit(now - Duration::days(90)).relative_date(&clock),
+            "3 months ago"
+        );
+        assert_eq!(
+            commit(now - Duration::days(800)).relative_date(&clock),
+            "2 years ago"
+        );
+    }
+
+    #[test]
+    fn test_format() {
+        let commit = Commit {
+            id: Oid::from_str("1234567890abcdef1234567890abcdef12345678").unwrap(),
+            short_id: "1234567".to_string(),
+            author: "Ada".to_string(),
+            author_email: "ada@example.com".to_string(),
+            date: Local.with_ymd_and_hms(2024, 6, 15, 12, 0, 0).unwrap(),
+            summary: "Fix parser".to_string(),
+            message: "Fix parser\n\nIt dropped the last line.\n".to_string(),
+            refs: vec![],
         };
 
-        assert_eq!(format_unit(1, "minute"), "1 minute ago");
-        assert_eq!(format_unit(2, "minute"), "2 minutes ago");
-        assert_eq!(format_unit(1, "hour"), "1 hour ago");
-        assert_eq!(format_unit(24, "hour"), "24 hours ago");
+        assert_eq!(
+            commit.format("%h (\"%s\", %ad)", None),
+            "1234567 (\"Fix parser\", 2024-06-15)"
+        );
+        assert_eq!(
+            commit.format("%an <%ae>: %b", None),
+            "Ada <ada@example.com>: It dropped the last line."
+        );
+        assert_eq!(
+            commit.format("[%h](%(url))", Some("https://example.com/repo")),
+            "[1234567](https://example.com/repo/commit/1234567890abcdef1234567890abcdef12345678)"
+        );
+        assert_eq!(commit.format("100%% %x %", None), "100% %x %");
     }
 }
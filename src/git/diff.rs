@@ -1,5 +1,12 @@
-use super::{error::Result, repository::Repository};
-use git2::{Diff as Git2Diff, DiffDelta, DiffOptions, Oid};
+use super::{
+    error::{GitError, Result},
+    repository::Repository,
+    watch::note_local_write,
+};
+use git2::{ApplyLocation, Diff as Git2Diff, DiffDelta, DiffOptions, Odb, Oid, Patch};
+use std::collections::HashSet;
+use std::ops::RangeInclusive;
+use std::path::Path;
 
 /// Type of diff line
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -48,7 +55,13 @@ pub struct DiffHunk {
 }
 
 impl DiffHunk {
-    pub fn new(header: String, old_start: u32, old_lines: u32, new_start: u32, new_lines: u32) -> Self {
+    pub fn new(
+        header: String,
+        old_start: u32,
+        old_lines: u32,
+        new_start: u32,
+        new_lines: u32,
+    ) -> Self {
         Self {
             header,
             old_start,
@@ -58,6 +71,175 @@ impl DiffHunk {
             lines: Vec::new(),
         }
     }
+
+    /// Get the hunk that undoes this one: additions become deletions and the
+    /// old and new sides swap
+    pub fn reversed(&self) -> DiffHunk {
+        let mut hunk = DiffHunk::new(
+            format!(
+                "@@ -{},{} +{},{} @@\n",
+                self.new_start, self.new_lines, self.old_start, self.old_lines
+            ),
+            self.new_start,
+            self.new_lines,
+            self.old_start,
+            self.old_lines,
+        );
+        for line in &self.lines {
+            let line_type = match line.line_type {
+                LineType::Addition => LineType::Deletion,
+                LineType::Deletion => LineType::Addition,
+                other => other,
+            };
+            hunk.lines.push(DiffLine::new(
+                line_type,
+                line.content.clone(),
+                line.new_lineno,
+                line.old_lineno,
+            ));
+        }
+
+        // Keep deletions ahead of additions within each run of changes, as
+        // diffs are written; libgit2 rejects patches that interleave them
+        // around a missing final newline
+        let mut start = 0;
+        while start < hunk.lines.len() {
+            let end = hunk.lines[start..]
+                .iter()
+                .position(|l| l.line_type == LineType::Context)
+                .map_or(hunk.lines.len(), |i| start + i);
+            hunk.lines[start..end].sort_by_key(|l| l.line_type != LineType::Deletion);
+            start = end + 1;
+        }
+        hunk
+    }
+
+    /// Get a hunk with only the changes on lines `selected` (indices into
+    /// `lines`), for staging part of a hunk; `None` if none are changes
+    ///
+    /// Deletions left out stay in the file as context and additions left out
+    /// are dropped, as in `git add -p`'s edit mode.
+    pub fn select_lines(&self, selected: RangeInclusive<usize>) -> Option<DiffHunk> {
+        let mut lines = Vec::new();
+        for (index, line) in self.lines.iter().enumerate() {
+            let chosen = selected.contains(&index);
+            match line.line_type {
+                LineType::Addition if !chosen => continue,
+                LineType::Deletion if !chosen => lines.push(DiffLine::new(
+                    LineType::Context,
+                    line.content.clone(),
+                    line.old_lineno,
+                    line.old_lineno,
+                )),
+                _ => lines.push(line.clone()),
+            }
+        }
+        self.with_lines(lines)
+    }
+
+    /// Read back this hunk after the user edited it as text: an optional
+    /// "@@" header, then one line per diff line starting with ' ', '+' or
+    /// '-'; lines starting with '#' are ignored
+    ///
+    /// The line counts are recomputed as `git add -e` does. `None` means
+    /// nothing is left to stage.
+    pub fn from_edited(&self, text: &str) -> Result<Option<DiffHunk>> {
+        let mut lines: Vec<DiffLine> = Vec::new();
+        let body = text.split_inclusive('\n').filter(|l| !l.starts_with('#'));
+        for (index, text) in body.enumerate() {
+            let (line_type, content) = match text.chars().next() {
+                Some('@') if index == 0 => continue,
+                Some(' ') => (LineType::Context, &text[1..]),
+                Some('+') => (LineType::Addition, &text[1..]),
+                Some('-') => (LineType::Deletion, &text[1..]),
+                // Editors strip trailing spaces, leaving empty context lines
+                Some('\n') => (LineType::Context, text),
+                Some('\\') => {
+                    // "\ No newline at end of file" applies to the line before
+                    if let Some(last) = lines.last_mut() {
+                        last.content
+                            .truncate(last.content.trim_end_matches('\n').len());
+                    }
+                    continue;
+                }
+                _ => {
+                    let line = text.trim_end();
+                    return Err(GitError::InvalidPatch(format!(
+                        "unexpected line \"{}\"",
+                        line
+                    )));
+                }
+            };
+            lines.push(DiffLine::new(line_type, content.to_string(), None, None));
+        }
+        Ok(self.with_lines(lines))
+    }
+
+    /// Make a hunk at this one's position with different lines, recounting
+    /// both sides; `None` if none of the lines are changes
+    fn with_lines(&self, lines: Vec<DiffLine>) -> Option<DiffHunk> {
+        if lines
+            .iter()
+            .all(|l| !matches!(l.line_type, LineType::Addition | LineType::Deletion))
+        {
+            return None;
+        }
+
+        let count =
+            |keep: fn(LineType) -> bool| lines.iter().filter(|l| keep(l.line_type)).count() as u32;
+        let old_lines = count(|t| matches!(t, LineType::Context | LineType::Deletion));
+        let new_lines = count(|t| matches!(t, LineType::Context | LineType::Addition));
+        // Only this hunk is applied, so nothing before it shifts the new side
+        let new_start = match self.old_lines {
+            0 => self.old_start + 1,
+            _ => self.old_start,
+        };
+
+        let mut hunk = DiffHunk::new(
+            format!(
+                "@@ -{},{} +{},{} @@\n",
+                self.old_start, old_lines, new_start, new_lines
+            ),
+            self.old_start,
+            old_lines,
+            new_start,
+            new_lines,
+        );
+        hunk.lines = lines;
+        Some(hunk)
+    }
+
+    /// Format this hunk as a patch against `path` that libgit2 can apply
+    pub fn to_patch(&self, path: &str) -> String {
+        format!(
+            "diff --git a/{path} b/{path}\n--- a/{path}\n+++ b/{path}\n{}",
+            self.to_text(),
+            path = path
+        )
+    }
+
+    /// Format the hunk part of a patch: its "@@" header and lines, as read
+    /// back by `from_edited`
+    pub fn to_text(&self) -> String {
+        let mut patch = format!(
+            "@@ -{},{} +{},{} @@\n",
+            self.old_start, self.old_lines, self.new_start, self.new_lines
+        );
+        for line in &self.lines {
+            let prefix = match line.line_type {
+                LineType::Addition => '+',
+                LineType::Deletion => '-',
+                LineType::Context => ' ',
+                LineType::FileHeader | LineType::HunkHeader => continue,
+            };
+            patch.push(prefix);
+            patch.push_str(&line.content);
+            if !line.content.ends_with('\n') {
+                patch.push_str("\n\\ No newline at end of file\n");
+            }
+        }
+        patch
+    }
 }
 
 /// File status in a diff
@@ -83,6 +265,9 @@ pub struct DiffFile {
     pub additions: usize,
     pub deletions: usize,
     pub is_binary: bool,
+    /// Size of the file's content when it was over the size limit, so its
+    /// hunks were never read
+    pub skipped_bytes: Option<u64>,
 }
 
 impl DiffFile {
@@ -95,6 +280,7 @@ impl DiffFile {
             additions: 0,
             deletions: 0,
             is_binary: false,
+            skipped_bytes: None,
         }
     }
 
@@ -111,6 +297,44 @@ impl DiffFile {
     pub fn stats_summary(&self) -> String {
         format!("+{}, -{}", self.additions, self.deletions)
     }
+
+    /// Get the number of changed (added or deleted) lines
+    pub fn changed_lines(&self) -> usize {
+        self.additions + self.deletions
+    }
+
+    /// Get the total size in bytes of the diff content for this file
+    pub fn content_size(&self) -> u64 {
+        self.hunks
+            .iter()
+            .flat_map(|h| h.lines.iter())
+            .map(|l| l.content.len() as u64)
+            .sum()
+    }
+}
+
+/// Which files are too big to read line by line
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SizeLimit {
+    /// Files whose content is over this many bytes are left without hunks;
+    /// 0 reads every file
+    pub max_file_bytes: u64,
+    /// Files read whatever their size, as they were asked for
+    pub expanded: HashSet<String>,
+}
+
+impl SizeLimit {
+    /// Leave out the content of files over `max_file_bytes`, 0 for none
+    pub fn new(max_file_bytes: u64) -> Self {
+        Self {
+            max_file_bytes,
+            expanded: HashSet::new(),
+        }
+    }
+
+    fn skips(&self, path: &str, bytes: u64) -> bool {
+        self.max_file_bytes > 0 && bytes > self.max_file_bytes && !self.expanded.contains(path)
+    }
 }
 
 /// A complete diff
@@ -147,7 +371,12 @@ impl Default for Diff {
 }
 
 /// Load a diff for a commit
-pub async fn load_commit_diff(repo: &Repository, commit_id: Oid) -> Result<Diff> {
+pub async fn load_commit_diff(
+    repo: &Repository,
+    commit_id: Oid,
+    limit: &SizeLimit,
+) -> Result<Diff> {
+    let limit = limit.clone();
     let repo_path = repo.path().to_path_buf();
 
     tokio::task::spawn_blocking(move || {
@@ -173,14 +402,78 @@ pub async fn load_commit_diff(repo: &Repository, commit_id: Oid) -> Result<Diff>
             )?
         };
 
-        parse_git2_diff(&diff)
+        parse_git2_diff(&git_repo, &diff, &limit)
+    })
+    .await
+    .map_err(GitError::task_join("load a commit diff"))?
+}
+
+/// Load the diff between two commits, from the tree of `from` to the tree
+/// of `to`
+pub async fn load_range_diff(
+    repo: &Repository,
+    from: Oid,
+    to: Oid,
+    limit: &SizeLimit,
+) -> Result<Diff> {
+    let limit = limit.clone();
+    let repo_path = repo.path().to_path_buf();
+
+    tokio::task::spawn_blocking(move || {
+        let git_repo = git2::Repository::open(repo_path)?;
+        let from_tree = git_repo.find_commit(from)?.tree()?;
+        let to_tree = git_repo.find_commit(to)?.tree()?;
+
+        let mut diff_options = DiffOptions::new();
+        diff_options.context_lines(3);
+        let diff = git_repo.diff_tree_to_tree(
+            Some(&from_tree),
+            Some(&to_tree),
+            Some(&mut diff_options),
+        )?;
+
+        parse_git2_diff(&git_repo, &diff, &limit)
+    })
+    .await
+    .map_err(GitError::task_join("load a range diff"))?
+}
+
+/// Count the lines each commit adds and removes against its first parent,
+/// like `git log --shortstat`
+pub async fn diff_stats(
+    repo: &Repository,
+    commit_ids: Vec<Oid>,
+) -> Result<Vec<(Oid, (usize, usize))>> {
+    let repo_path = repo.path().to_path_buf();
+
+    tokio::task::spawn_blocking(move || {
+        let git_repo = git2::Repository::open(repo_path)?;
+
+        let mut stats = Vec::with_capacity(commit_ids.len());
+        for id in commit_ids {
+            let commit = git_repo.find_commit(id)?;
+            let parent_tree = match commit.parent(0) {
+                Ok(parent) => Some(parent.tree()?),
+                Err(_) => None,
+            };
+            let diff =
+                git_repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), None)?;
+            let diff_stats = diff.stats()?;
+            stats.push((id, (diff_stats.insertions(), diff_stats.deletions())));
+        }
+        Ok(stats)
     })
     .await
-    .map_err(|_| super::error::GitError::RepoNotFound)?
+    .map_err(GitError::task_join("count commit changes"))?
 }
 
 /// Load a diff for staged changes (HEAD vs index) for a specific path
-pub async fn load_staged_diff(repo: &Repository, path: Option<String>) -> Result<Diff> {
+pub async fn load_staged_diff(
+    repo: &Repository,
+    path: Option<String>,
+    limit: &SizeLimit,
+) -> Result<Diff> {
+    let limit = limit.clone();
     let repo_path = repo.path().to_path_buf();
 
     tokio::task::spawn_blocking(move || {
@@ -195,14 +488,19 @@ pub async fn load_staged_diff(repo: &Repository, path: Option<String>) -> Result
 
         let diff = git_repo.diff_tree_to_index(Some(&head), None, Some(&mut diff_options))?;
 
-        parse_git2_diff(&diff)
+        parse_git2_diff(&git_repo, &diff, &limit)
     })
     .await
-    .map_err(|_| super::error::GitError::RepoNotFound)?
+    .map_err(GitError::task_join("load the staged diff"))?
 }
 
 /// Load a diff for unstaged changes (index vs workdir) for a specific path
-pub async fn load_unstaged_diff(repo: &Repository, path: Option<String>) -> Result<Diff> {
+pub async fn load_unstaged_diff(
+    repo: &Repository,
+    path: Option<String>,
+    limit: &SizeLimit,
+) -> Result<Diff> {
+    let limit = limit.clone();
     let repo_path = repo.path().to_path_buf();
 
     tokio::task::spawn_blocking(move || {
@@ -217,152 +515,463 @@ pub async fn load_unstaged_diff(repo: &Repository, path: Option<String>) -> Resu
 
         let diff = git_repo.diff_index_to_workdir(None, Some(&mut diff_options))?;
 
-        parse_git2_diff(&diff)
+        parse_git2_diff(&git_repo, &diff, &limit)
     })
     .await
-    .map_err(|_| super::error::GitError::RepoNotFound)?
+    .map_err(GitError::task_join("load the unstaged diff"))?
 }
 
-/// Parse a git2 diff into our Diff structure
-fn parse_git2_diff(git2_diff: &Git2Diff) -> Result<Diff> {
-    use std::cell::RefCell;
-
-    let diff = RefCell::new(Diff::new());
-
-    git2_diff.foreach(
-        &mut |delta: DiffDelta, _progress: f32| {
-            let old_path = delta.old_file().path().map(|p| p.to_string_lossy().to_string());
-            let new_path = delta.new_file().path().map(|p| p.to_string_lossy().to_string());
-
-            let status = match delta.status() {
-                git2::Delta::Added => FileStatus::Added,
-                git2::Delta::Deleted => FileStatus::Deleted,
-                git2::Delta::Modified => FileStatus::Modified,
-                git2::Delta::Renamed => FileStatus::Renamed,
-                git2::Delta::Copied => FileStatus::Copied,
-                git2::Delta::Ignored => FileStatus::Ignored,
-                git2::Delta::Untracked => FileStatus::Untracked,
-                git2::Delta::Typechange => FileStatus::Typechange,
-                _ => FileStatus::Modified,
-            };
+/// Undo one hunk of the unstaged changes to `path` in the working directory
+///
+/// `hunk` comes from `load_unstaged_diff`; if the file has changed since, the
+/// hunk no longer applies and nothing is touched.
+pub async fn discard_hunk(repo: &Repository, path: String, hunk: DiffHunk) -> Result<()> {
+    let repo_path = repo.path().to_path_buf();
 
-            let file = DiffFile::new(old_path, new_path, status);
-            diff.borrow_mut().files.push(file);
-            true
-        },
-        None,
-        Some(&mut |_delta: DiffDelta, hunk: git2::DiffHunk| {
-            let mut diff_mut = diff.borrow_mut();
-            let file = diff_mut.files.last_mut().unwrap();
-
-            let header = String::from_utf8_lossy(hunk.header()).to_string();
-            let diff_hunk = DiffHunk::new(
-                header,
-                hunk.old_start(),
-                hunk.old_lines(),
-                hunk.new_start(),
-                hunk.new_lines(),
-            );
-            file.hunks.push(diff_hunk);
-            true
-        }),
-        Some(&mut |_delta: DiffDelta, _hunk: Option<git2::DiffHunk>, line: git2::DiffLine| {
-            let mut diff_mut = diff.borrow_mut();
-            let file = diff_mut.files.last_mut().unwrap();
-            let hunk = file.hunks.last_mut().unwrap();
-
-            let line_type = match line.origin() {
-                '+' => {
-                    file.additions += 1;
-                    LineType::Addition
-                }
-                '-' => {
-                    file.deletions += 1;
-                    LineType::Deletion
-                }
-                ' ' => LineType::Context,
-                'F' => LineType::FileHeader,
-                'H' => LineType::HunkHeader,
-                _ => LineType::Context,
-            };
+    tokio::task::spawn_blocking(move || {
+        let git_repo = git2::Repository::open(repo_path)?;
 
-            let content = String::from_utf8_lossy(line.content()).to_string();
+        // Reversing an untracked file's only hunk would empty it, not remove it
+        ensure_tracked(&git_repo, path.clone())?;
+        apply_patch(
+            &git_repo,
+            &hunk.reversed().to_patch(&path),
+            ApplyLocation::WorkDir,
+        )
+    })
+    .await
+    .map_err(GitError::task_join("discard a hunk"))?
+}
 
-            let diff_line = DiffLine::new(
-                line_type,
-                content,
-                line.old_lineno(),
-                line.new_lineno(),
-            );
+/// Stage one hunk, or part of one from `DiffHunk::select_lines`, of the
+/// unstaged changes to `path`
+pub async fn stage_hunk(repo: &Repository, path: String, hunk: DiffHunk) -> Result<()> {
+    let repo_path = repo.path().to_path_buf();
 
-            hunk.lines.push(diff_line);
-            true
-        }),
-    )?;
+    tokio::task::spawn_blocking(move || {
+        let git_repo = git2::Repository::open(&repo_path)?;
+
+        // A patch can't add a file the index doesn't know about yet
+        ensure_tracked(&git_repo, path.clone())?;
+        apply_patch(&git_repo, &hunk.to_patch(&path), ApplyLocation::Index)?;
+        note_local_write(&repo_path);
+        Ok(())
+    })
+    .await
+    .map_err(GitError::task_join("stage a hunk"))?
+}
 
-    Ok(diff.into_inner())
+/// Fail with `GitError::Untracked` unless the index has an entry for `path`
+fn ensure_tracked(git_repo: &git2::Repository, path: String) -> Result<()> {
+    match git_repo.index()?.get_path(Path::new(&path), 0) {
+        Some(_) => Ok(()),
+        None => Err(GitError::Untracked(path)),
+    }
+}
+
+/// Apply a patch made by `DiffHunk::to_patch` to the working directory or index
+fn apply_patch(git_repo: &git2::Repository, patch: &str, location: ApplyLocation) -> Result<()> {
+    let diff = Git2Diff::from_buffer(patch.as_bytes())?;
+    git_repo.apply(&diff, location, None)?;
+    Ok(())
+}
+
+/// Parse a git2 diff into our Diff structure, leaving out the hunks of
+/// files over the size limit before any of their lines are read
+fn parse_git2_diff(
+    git_repo: &git2::Repository,
+    git2_diff: &Git2Diff,
+    limit: &SizeLimit,
+) -> Result<Diff> {
+    let odb = git_repo.odb()?;
+    let mut diff = Diff::new();
+
+    for (index, delta) in git2_diff.deltas().enumerate() {
+        let old_path = delta
+            .old_file()
+            .path()
+            .map(|p| p.to_string_lossy().to_string());
+        let new_path = delta
+            .new_file()
+            .path()
+            .map(|p| p.to_string_lossy().to_string());
+
+        let status = match delta.status() {
+            git2::Delta::Added => FileStatus::Added,
+            git2::Delta::Deleted => FileStatus::Deleted,
+            git2::Delta::Modified => FileStatus::Modified,
+            git2::Delta::Renamed => FileStatus::Renamed,
+            git2::Delta::Copied => FileStatus::Copied,
+            git2::Delta::Ignored => FileStatus::Ignored,
+            git2::Delta::Untracked => FileStatus::Untracked,
+            git2::Delta::Typechange => FileStatus::Typechange,
+            _ => FileStatus::Modified,
+        };
+
+        let mut file = DiffFile::new(old_path, new_path, status);
+        let bytes = delta_size(git_repo, &odb, &delta);
+        if limit.skips(file.path(), bytes) {
+            // Only the line counts are read; no line is copied out
+            if let Some(patch) = Patch::from_diff(git2_diff, index)? {
+                let (_, additions, deletions) = patch.line_stats()?;
+                file.additions = additions;
+                file.deletions = deletions;
+            }
+            file.skipped_bytes = Some(bytes);
+            diff.files.push(file);
+            continue;
+        }
+
+        if let Some(patch) = Patch::from_diff(git2_diff, index)? {
+            for hunk_index in 0..patch.num_hunks() {
+                let (hunk, line_count) = patch.hunk(hunk_index)?;
+                let mut diff_hunk = DiffHunk::new(
+                    String::from_utf8_lossy(hunk.header()).to_string(),
+                    hunk.old_start(),
+                    hunk.old_lines(),
+                    hunk.new_start(),
+                    hunk.new_lines(),
+                );
+                for line_index in 0..line_count {
+                    let line = patch.line_in_hunk(hunk_index, line_index)?;
+                    let line_type = match line.origin() {
+                        '+' => {
+                            file.additions += 1;
+                            LineType::Addition
+                        }
+                        '-' => {
+                            file.deletions += 1;
+                            LineType::Deletion
+                        }
+                        ' ' => LineType::Context,
+                        'F' => LineType::FileHeader,
+                        'H' => LineType::HunkHeader,
+                        // "\ No newline at end of file" markers; the missing
+                        // newline already shows in the content of the line before
+                        '=' | '>' | '<' => continue,
+                        _ => LineType::Context,
+                    };
+
+                    let content = String::from_utf8_lossy(line.content()).to_string();
+                    diff_hunk.lines.push(DiffLine::new(
+                        line_type,
+                        content,
+                        line.old_lineno(),
+                        line.new_lineno(),
+                    ));
+                }
+                file.hunks.push(diff_hunk);
+            }
+        }
+        diff.files.push(file);
+    }
+
+    Ok(diff)
+}
+
+/// Get the size of the bigger side of a file's change from the object
+/// headers, or the working tree for content not in the object database
+fn delta_size(git_repo: &git2::Repository, odb: &Odb, delta: &DiffDelta) -> u64 {
+    [delta.old_file(), delta.new_file()]
+        .into_iter()
+        .map(|file| {
+            if !file.id().is_zero()
+                && let Ok((size, _)) = odb.read_header(file.id())
+            {
+                return size as u64;
+            }
+            match (file.path(), git_repo.workdir()) {
+                (Some(path), Some(workdir)) => {
+                    std::fs::metadata(workdir.join(path)).map_or(0, |m| m.len())
+                }
+                _ => 0,
+            }
+        })
+        .max()
+        .unwrap_or(0)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::testing::RepoBuilder;
     use tempfile::TempDir;
 
     async fn create_test_repo_with_diff() -> (TempDir, Repository, Oid) {
-        let temp_dir = TempDir::new().unwrap();
-        let repo_path = temp_dir.path();
-
-        let git_repo = git2::Repository::init(repo_path).unwrap();
-        let sig = git2::Signature::now("Test", "test@example.com").unwrap();
-
-        // Create initial commit
-        std::fs::write(repo_path.join("test.txt"), "line1\nline2\nline3\n").unwrap();
-        let mut index = git_repo.index().unwrap();
-        index.add_path(std::path::Path::new("test.txt")).unwrap();
-        index.write().unwrap();
-        let tree_id = index.write_tree().unwrap();
-        let tree = git_repo.find_tree(tree_id).unwrap();
-        git_repo
-            .commit(Some("HEAD"), &sig, &sig, "Initial", &tree, &[])
-            .unwrap();
-
-        // Create second commit with changes
-        std::fs::write(repo_path.join("test.txt"), "line1\nmodified\nline3\nline4\n").unwrap();
-        let mut index = git_repo.index().unwrap();
-        index.add_path(std::path::Path::new("test.txt")).unwrap();
-        index.write().unwrap();
-        let tree_id = index.write_tree().unwrap();
-        let tree = git_repo.find_tree(tree_id).unwrap();
-        let parent = git_repo.head().unwrap().peel_to_commit().unwrap();
-        let commit_id = git_repo
-            .commit(Some("HEAD"), &sig, &sig, "Second", &tree, &[&parent])
-            .unwrap();
-
-        let repo = Repository::open(repo_path).await.unwrap();
+        let builder = RepoBuilder::new()
+            .file("test.txt", "line1\nline2\nline3\n")
+            .commit("Initial")
+            .file("test.txt", "line1\nmodified\nline3\nline4\n")
+            .commit("Second");
+        let commit_id = builder.head();
+        let (temp_dir, repo) = builder.open().await;
         (temp_dir, repo, commit_id)
     }
 
     #[tokio::test]
     async fn test_load_commit_diff() {
         let (_temp_dir, repo, commit_id) = create_test_repo_with_diff().await;
-        let diff = load_commit_diff(&repo, commit_id).await.unwrap();
+        let diff = load_commit_diff(&repo, commit_id, &SizeLimit::default())
+            .await
+            .unwrap();
 
         assert_eq!(diff.files.len(), 1);
         assert_eq!(diff.files[0].path(), "test.txt");
         assert_eq!(diff.files[0].status, FileStatus::Modified);
-        assert!(diff.files[0].hunks.len() > 0);
+        assert!(!diff.files[0].hunks.is_empty());
     }
 
     #[tokio::test]
     async fn test_diff_stats() {
         let (_temp_dir, repo, commit_id) = create_test_repo_with_diff().await;
-        let diff = load_commit_diff(&repo, commit_id).await.unwrap();
+        let diff = load_commit_diff(&repo, commit_id, &SizeLimit::default())
+            .await
+            .unwrap();
 
         let (additions, deletions) = diff.total_stats();
         assert!(additions > 0);
         assert!(deletions > 0);
     }
 
+    #[tokio::test]
+    async fn test_load_range_diff_spans_commits() {
+        let builder = RepoBuilder::new().file("a.txt", "a\n").commit("First");
+        let first = builder.head();
+        let builder = builder
+            .file("b.txt", "b\n")
+            .commit("Second")
+            .file("a.txt", "a\nmore\n")
+            .commit("Third");
+        let third = builder.head();
+        let (_temp_dir, repo) = builder.open().await;
+
+        let diff = load_range_diff(&repo, first, third, &SizeLimit::default())
+            .await
+            .unwrap();
+        let paths: Vec<&str> = diff.files.iter().map(|f| f.path()).collect();
+        assert_eq!(paths, ["a.txt", "b.txt"]);
+        assert_eq!(diff.total_stats(), (2, 0));
+    }
+
+    #[tokio::test]
+    async fn test_diff_stats_per_commit() {
+        let builder = RepoBuilder::new()
+            .file("a.txt", "one\ntwo\n")
+            .commit("First");
+        let first = builder.head();
+        let builder = builder.file("a.txt", "one\n2\nthree\n").commit("Second");
+        let second = builder.head();
+        let (_temp_dir, repo) = builder.open().await;
+
+        let stats = diff_stats(&repo, vec![second, first]).await.unwrap();
+        assert_eq!(stats, [(second, (2, 1)), (first, (2, 0))]);
+    }
+
+    #[tokio::test]
+    async fn test_file_content_size() {
+        let (_temp_dir, repo, commit_id) = create_test_repo_with_diff().await;
+        let diff = load_commit_diff(&repo, commit_id, &SizeLimit::default())
+            .await
+            .unwrap();
+
+        let file = &diff.files[0];
+        assert_eq!(file.changed_lines(), file.additions + file.deletions);
+        assert!(file.content_size() > 0);
+    }
+
+    #[tokio::test]
+    async fn test_oversized_file_left_unread() {
+        let (_temp_dir, repo) = RepoBuilder::new()
+            .file("big.txt", &numbered(100, &[]))
+            .file("small.txt", "one\n")
+            .commit("Initial")
+            .write("big.txt", &numbered(100, &[50]))
+            .write("small.txt", "two\n")
+            .open()
+            .await;
+
+        let mut limit = SizeLimit::new(100);
+        let diff = load_unstaged_diff(&repo, None, &limit).await.unwrap();
+        let big = diff
+            .files
+            .iter()
+            .find(|file| file.path() == "big.txt")
+            .unwrap();
+        assert!(big.skipped_bytes.unwrap() > 100);
+        assert!(big.hunks.is_empty());
+        assert_eq!((big.additions, big.deletions), (1, 1));
+        let small = diff
+            .files
+            .iter()
+            .find(|file| file.path() == "small.txt")
+            .unwrap();
+        assert_eq!(small.skipped_bytes, None);
+        assert_eq!(small.additions, 1);
+
+        limit.expanded.insert("big.txt".to_string());
+        let diff = load_unstaged_diff(&repo, None, &limit).await.unwrap();
+        let big = diff
+            .files
+            .iter()
+            .find(|file| file.path() == "big.txt")
+            .unwrap();
+        assert_eq!(big.skipped_bytes, None);
+        assert_eq!((big.additions, big.deletions), (1, 1));
+    }
+
+    fn numbered(lines: usize, changed: &[usize]) -> String {
+        (1..=lines)
+            .map(|i| match changed.contains(&i) {
+                true => format!("changed {}\n", i),
+                false => format!("line {}\n", i),
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_discard_hunk_leaves_other_hunks() {
+        let (temp_dir, repo) = RepoBuilder::new()
+            .file("test.txt", &numbered(30, &[]))
+            .commit("Initial")
+            .write("test.txt", &numbered(30, &[2, 25]))
+            .open()
+            .await;
+
+        let diff = load_unstaged_diff(&repo, Some("test.txt".to_string()), &SizeLimit::default())
+            .await
+            .unwrap();
+        let hunks = &diff.files[0].hunks;
+        assert_eq!(hunks.len(), 2);
+
+        discard_hunk(&repo, "test.txt".to_string(), hunks[0].clone())
+            .await
+            .unwrap();
+        let content = std::fs::read_to_string(temp_dir.path().join("test.txt")).unwrap();
+        assert_eq!(content, numbered(30, &[25]));
+    }
+
+    #[tokio::test]
+    async fn test_discard_hunk_without_trailing_newline() {
+        let (temp_dir, repo) = RepoBuilder::new()
+            .file("test.txt", "one\ntwo")
+            .commit("Initial")
+            .write("test.txt", "one\nthree")
+            .open()
+            .await;
+
+        let diff = load_unstaged_diff(&repo, Some("test.txt".to_string()), &SizeLimit::default())
+            .await
+            .unwrap();
+        let hunk = diff.files[0].hunks[0].clone();
+        assert!(hunk.lines.iter().all(|l| !l.content.contains("No newline")));
+
+        discard_hunk(&repo, "test.txt".to_string(), hunk)
+            .await
+            .unwrap();
+        let content = std::fs::read_to_string(temp_dir.path().join("test.txt")).unwrap();
+        assert_eq!(content, "one\ntwo");
+    }
+
+    #[tokio::test]
+    async fn test_discard_hunk_refuses_untracked_files() {
+        let (temp_dir, repo) = RepoBuilder::new()
+            .file("README.md", "readme\n")
+            .commit("Initial")
+            .write("new.txt", "new\n")
+            .open()
+            .await;
+
+        // Untracked files come without hunks, so make up the one git would show
+        let mut hunk = DiffHunk::new("@@ -0,0 +1 @@\n".to_string(), 0, 0, 1, 1);
+        hunk.lines.push(DiffLine::new(
+            LineType::Addition,
+            "new\n".to_string(),
+            None,
+            Some(1),
+        ));
+        let result = discard_hunk(&repo, "new.txt".to_string(), hunk).await;
+        assert!(matches!(result, Err(GitError::Untracked(_))));
+        assert!(temp_dir.path().join("new.txt").exists());
+    }
+
+    #[tokio::test]
+    async fn test_stage_selected_lines() {
+        let (temp_dir, repo) = RepoBuilder::new()
+            .file("test.txt", "a\nb\nc\n")
+            .commit("Initial")
+            .write("test.txt", "a\nB\nc\nd\n")
+            .open()
+            .await;
+
+        let diff = load_unstaged_diff(&repo, Some("test.txt".to_string()), &SizeLimit::default())
+            .await
+            .unwrap();
+        let hunk = &diff.files[0].hunks[0];
+        let types: Vec<LineType> = hunk.lines.iter().map(|l| l.line_type).collect();
+        assert_eq!(
+            types,
+            [
+                LineType::Context,
+                LineType::Deletion,
+                LineType::Addition,
+                LineType::Context,
+                LineType::Addition
+            ]
+        );
+
+        // Context alone selects nothing
+        assert!(hunk.select_lines(0..=0).is_none());
+
+        // Stage only the appended "d", leaving the b -> B edit unstaged
+        let partial = hunk.select_lines(4..=4).unwrap();
+        stage_hunk(&repo, "test.txt".to_string(), partial)
+            .await
+            .unwrap();
+
+        let git_repo = git2::Repository::open(temp_dir.path()).unwrap();
+        let entry = git_repo
+            .index()
+            .unwrap()
+            .get_path(Path::new("test.txt"), 0)
+            .unwrap();
+        let blob = git_repo.find_blob(entry.id).unwrap();
+        assert_eq!(blob.content(), b"a\nb\nc\nd\n");
+
+        let diff = load_unstaged_diff(&repo, Some("test.txt".to_string()), &SizeLimit::default())
+            .await
+            .unwrap();
+        assert_eq!(diff.files[0].additions, 1);
+        assert_eq!(diff.files[0].deletions, 1);
+    }
+
+    #[test]
+    fn test_edited_hunk_is_recounted() {
+        let mut hunk = DiffHunk::new("@@ -3,2 +3,2 @@\n".to_string(), 3, 2, 3, 2);
+        for (line_type, content) in [
+            (LineType::Context, "a\n"),
+            (LineType::Deletion, "b\n"),
+            (LineType::Addition, "B\n"),
+        ] {
+            hunk.lines
+                .push(DiffLine::new(line_type, content.to_string(), None, None));
+        }
+        assert_eq!(hunk.to_text(), "@@ -3,2 +3,2 @@\n a\n-b\n+B\n");
+
+        // Keep b and add two lines instead of replacing it
+        let edited = hunk
+            .from_edited("@@ -3,2 +3,2 @@\n a\n b\n+B\n+C\n# comment\n")
+            .unwrap()
+            .unwrap();
+        assert_eq!(edited.to_text(), "@@ -3,2 +3,4 @@\n a\n b\n+B\n+C\n");
+
+        // Context only, or nothing at all, leaves nothing to stage
+        assert!(hunk.from_edited(" a\n b\n").unwrap().is_none());
+        assert!(hunk.from_edited("# all gone\n").unwrap().is_none());
+
+        let invalid = hunk.from_edited(" a\nb\n");
+        assert!(matches!(invalid, Err(GitError::InvalidPatch(_))));
+    }
+
     #[test]
     fn test_diff_line_types() {
         let line = DiffLine::new(LineType::Addition, "+test".to_string(), None, Some(1));
@@ -0,0 +1,287 @@
+use super::{
+    diff::{self, Diff, SizeLimit},
+    error::{GitError, Result},
+    repository::Repository,
+    watch::local_writes,
+};
+use git2::Oid;
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+use std::time::SystemTime;
+
+/// Rough bytes of parsed diffs kept so reopening them is instant
+pub const DIFF_CACHE_BYTES: usize = 32 * 1024 * 1024;
+
+/// Recently loaded diffs, shared by every view
+static DIFF_CACHE: LazyLock<Mutex<DiffCache>> =
+    LazyLock::new(|| Mutex::new(DiffCache::new(DIFF_CACHE_BYTES)));
+
+/// Which diff to load
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum DiffKey {
+    /// A commit against its first parent
+    Commit(Oid),
+    /// The tree of `from` to the tree of `to`
+    Range { from: Oid, to: Oid },
+    /// A file's staged changes
+    Staged(String),
+    /// A file's unstaged changes
+    Unstaged(String),
+}
+
+/// What a working tree diff was read from: the things the watcher checks,
+/// plus the file itself. A diff whose stamp changed since is stale; commit
+/// diffs never go stale.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Stamp {
+    head: Option<Oid>,
+    index: Option<SystemTime>,
+    file: Option<(SystemTime, u64)>,
+    local_writes: u64,
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    repo: PathBuf,
+    key: DiffKey,
+    limit: SizeLimit,
+    stamp: Stamp,
+    diff: Diff,
+    bytes: usize,
+}
+
+impl Entry {
+    /// Whether this is the diff of `key` loaded with the same size limit
+    fn matches(&self, repo: &Path, key: &DiffKey, limit: &SizeLimit) -> bool {
+        self.repo == repo && self.key == *key && self.limit == *limit
+    }
+}
+
+/// Parsed diffs, forgetting the least recently used past a size limit
+#[derive(Debug, Clone)]
+struct DiffCache {
+    /// Most recently used first
+    entries: VecDeque<Entry>,
+    bytes: usize,
+    limit: usize,
+}
+
+impl DiffCache {
+    fn new(limit: usize) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            bytes: 0,
+            limit,
+        }
+    }
+
+    /// Get a diff loaded before, unless what it was read from has changed
+    fn get(
+        &mut self,
+        repo: &Path,
+        key: &DiffKey,
+        limit: &SizeLimit,
+        stamp: &Stamp,
+    ) -> Option<Diff> {
+        let index = self
+            .entries
+            .iter()
+            .position(|entry| entry.matches(repo, key, limit))?;
+        let entry = self.entries.remove(index)?;
+        if entry.stamp != *stamp {
+            self.bytes -= entry.bytes;
+            return None;
+        }
+        let diff = entry.diff.clone();
+        self.entries.push_front(entry);
+        Some(diff)
+    }
+
+    fn insert(&mut self, repo: &Path, key: DiffKey, limit: SizeLimit, stamp: Stamp, diff: Diff) {
+        let bytes = approximate_size(&diff);
+        if bytes > self.limit {
+            return;
+        }
+        if let Some(index) = self
+            .entries
+            .iter()
+            .position(|entry| entry.matches(repo, &key, &limit))
+            && let Some(old) = self.entries.remove(index)
+        {
+            self.bytes -= old.bytes;
+        }
+        self.bytes += bytes;
+        self.entries.push_front(Entry {
+            repo: repo.to_path_buf(),
+            key,
+            limit,
+            stamp,
+            diff,
+            bytes,
+        });
+        while self.bytes > self.limit
+            && let Some(oldest) = self.entries.pop_back()
+        {
+            self.bytes -= oldest.bytes;
+        }
+    }
+}
+
+/// Estimate the memory a parsed diff takes
+fn approximate_size(diff: &Diff) -> usize {
+    diff.files
+        .iter()
+        .map(|file| {
+            let lines: usize = file
+                .hunks
+                .iter()
+                .map(|hunk| hunk.header.len() + hunk.lines.len() * size_of::<diff::DiffLine>())
+                .sum();
+            size_of::<diff::DiffFile>() + lines + file.content_size() as usize
+        })
+        .sum()
+}
+
+/// Read what a working tree diff depends on; commit diffs depend on nothing
+/// that changes
+fn stamp(git_repo: &git2::Repository, repo_path: &Path, key: &DiffKey) -> Stamp {
+    let path = match key {
+        DiffKey::Commit(_) | DiffKey::Range { .. } => return Stamp::default(),
+        DiffKey::Staged(path) | DiffKey::Unstaged(path) => path,
+    };
+    let modified = |path: &Path| std::fs::metadata(path).and_then(|m| Ok((m.modified()?, m.len())));
+    let file = match (key, git_repo.workdir()) {
+        (DiffKey::Unstaged(_), Some(workdir)) => modified(&workdir.join(path)).ok(),
+        _ => None,
+    };
+    Stamp {
+        head: git_repo.head().ok().and_then(|head| head.target()),
+        index: modified(&git_repo.path().join("index"))
+            .ok()
+            .map(|(time, _)| time),
+        file,
+        local_writes: local_writes(repo_path),
+    }
+}
+
+/// Load a diff, reusing the one loaded last time with the same size limit
+/// if nothing it was read from has changed since
+pub async fn load_diff(repo: &Repository, key: DiffKey, limit: &SizeLimit) -> Result<Diff> {
+    let repo_path = repo.path().to_path_buf();
+    let (stamp, cached) = {
+        let key = key.clone();
+        let limit = limit.clone();
+        tokio::task::spawn_blocking(move || {
+            let git_repo = git2::Repository::open(&repo_path)?;
+            let stamp = stamp(&git_repo, &repo_path, &key);
+            let cached = DIFF_CACHE
+                .lock()
+                .ok()
+                .and_then(|mut cache| cache.get(&repo_path, &key, &limit, &stamp));
+            Ok::<_, GitError>((stamp, cached))
+        })
+        .await
+        .map_err(GitError::task_join("check the diff cache"))??
+    };
+    if let Some(diff) = cached {
+        return Ok(diff);
+    }
+
+    let diff = match &key {
+        DiffKey::Commit(id) => diff::load_commit_diff(repo, *id, limit).await?,
+        DiffKey::Range { from, to } => diff::load_range_diff(repo, *from, *to, limit).await?,
+        DiffKey::Staged(path) => diff::load_staged_diff(repo, Some(path.clone()), limit).await?,
+        DiffKey::Unstaged(path) => {
+            diff::load_unstaged_diff(repo, Some(path.clone()), limit).await?
+        }
+    };
+    if let Ok(mut cache) = DIFF_CACHE.lock() {
+        cache.insert(repo.path(), key, limit.clone(), stamp, diff.clone());
+    }
+    Ok(diff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::RepoBuilder;
+
+    #[test]
+    fn test_evicts_least_recently_used_past_limit() {
+        let repo = Path::new("/repo");
+        let [a, b, c] =
+            [1u8, 2, 3].map(|byte| DiffKey::Commit(Oid::from_bytes(&[byte; 20]).unwrap()));
+        let mut diff = Diff::new();
+        diff.files.push(diff::DiffFile::new(
+            None,
+            Some("a.txt".to_string()),
+            diff::FileStatus::Added,
+        ));
+        let size = approximate_size(&diff);
+
+        let limit = SizeLimit::default();
+        let mut cache = DiffCache::new(size * 2);
+        cache.insert(
+            repo,
+            a.clone(),
+            limit.clone(),
+            Stamp::default(),
+            diff.clone(),
+        );
+        cache.insert(
+            repo,
+            b.clone(),
+            limit.clone(),
+            Stamp::default(),
+            diff.clone(),
+        );
+        assert!(cache.get(repo, &a, &limit, &Stamp::default()).is_some());
+        cache.insert(repo, c.clone(), limit.clone(), Stamp::default(), diff);
+        assert!(cache.get(repo, &b, &limit, &Stamp::default()).is_none());
+        assert!(cache.get(repo, &a, &limit, &Stamp::default()).is_some());
+        assert!(
+            cache
+                .get(Path::new("/other"), &a, &limit, &Stamp::default())
+                .is_none()
+        );
+        assert!(
+            cache
+                .get(repo, &a, &SizeLimit::new(1), &Stamp::default())
+                .is_none()
+        );
+
+        // A changed stamp drops the entry
+        let changed = Stamp {
+            local_writes: 1,
+            ..Stamp::default()
+        };
+        assert!(cache.get(repo, &c, &limit, &changed).is_none());
+        assert!(cache.get(repo, &c, &limit, &Stamp::default()).is_none());
+        assert_eq!(cache.bytes, size);
+    }
+
+    #[tokio::test]
+    async fn test_worktree_diff_reloads_after_edit() {
+        let builder = RepoBuilder::new().file("a.txt", "one\n").commit("Initial");
+        let commit = builder.head();
+        let (temp_dir, repo) = builder.write("a.txt", "two\n").open().await;
+
+        let key = DiffKey::Unstaged("a.txt".to_string());
+        let limit = SizeLimit::default();
+        let diff = load_diff(&repo, key.clone(), &limit).await.unwrap();
+        assert_eq!(diff.files[0].additions, 1);
+        assert_eq!(
+            load_diff(&repo, DiffKey::Commit(commit), &limit)
+                .await
+                .unwrap()
+                .files
+                .len(),
+            1
+        );
+
+        std::fs::write(temp_dir.path().join("a.txt"), "two\nthree\nfour\n").unwrap();
+        let diff = load_diff(&repo, key, &limit).await.unwrap();
+        assert_eq!(diff.files[0].additions, 3);
+    }
+}
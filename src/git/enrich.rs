@@ -0,0 +1,89 @@
+use git2::Oid;
+use std::future::Future;
+use std::sync::{Arc, LazyLock};
+use tokio::sync::Semaphore;
+use tokio::sync::mpsc::{self, error::TryRecvError};
+
+/// Batches computing per-commit extras at once, across every kind of extra,
+/// so they never crowd out the history walk
+pub const ENRICH_WORKERS: usize = 4;
+
+/// Commits per batch; small enough that rows fill in steadily
+const BATCH_SIZE: usize = 8;
+
+static WORKERS: LazyLock<Arc<Semaphore>> =
+    LazyLock::new(|| Arc::new(Semaphore::new(ENRICH_WORKERS)));
+
+/// Compute an extra for each commit, like its nearest tag or diffstat, on
+/// the shared worker pool, a batch at a time
+///
+/// `load` computes a batch; each batch's results are sent as it completes,
+/// and the channel closes once every batch has.
+pub fn enrich<T, F, Fut>(ids: Vec<Oid>, load: F) -> mpsc::UnboundedReceiver<Vec<(Oid, T)>>
+where
+    T: Send + 'static,
+    F: Fn(Vec<Oid>) -> Fut + Clone + Send + 'static,
+    Fut: Future<Output = Vec<(Oid, T)>> + Send,
+{
+    let (tx, rx) = mpsc::unbounded_channel();
+    for batch in ids.chunks(BATCH_SIZE) {
+        let (tx, load, batch) = (tx.clone(), load.clone(), batch.to_vec());
+        tokio::spawn(async move {
+            let Ok(_permit) = WORKERS.acquire().await else {
+                return;
+            };
+            let _ = tx.send(load(batch).await);
+        });
+    }
+    rx
+}
+
+/// Take the results that arrived so far, dropping the receiver once every
+/// batch is in
+pub fn drain<T>(receiver: &mut Option<mpsc::UnboundedReceiver<Vec<T>>>) -> Vec<T> {
+    let mut results = Vec::new();
+    while let Some(rx) = receiver {
+        match rx.try_recv() {
+            Ok(batch) => results.extend(batch),
+            Err(TryRecvError::Empty) => break,
+            Err(TryRecvError::Disconnected) => *receiver = None,
+        }
+    }
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[tokio::test]
+    async fn test_batches_run_on_bounded_pool() {
+        let ids: Vec<Oid> = (0..50u8)
+            .map(|byte| Oid::from_bytes(&[byte; 20]).unwrap())
+            .collect();
+        let running = Arc::new(AtomicUsize::new(0));
+        let most = Arc::new(AtomicUsize::new(0));
+
+        let (counter, peak) = (Arc::clone(&running), Arc::clone(&most));
+        let mut receiver = Some(enrich(ids.clone(), move |batch| {
+            let (counter, peak) = (Arc::clone(&counter), Arc::clone(&peak));
+            async move {
+                peak.fetch_max(counter.fetch_add(1, Ordering::SeqCst) + 1, Ordering::SeqCst);
+                tokio::time::sleep(tokio::time::Duration::from_millis(5)).await;
+                counter.fetch_sub(1, Ordering::SeqCst);
+                batch.into_iter().map(|id| (id, id.as_bytes()[0])).collect()
+            }
+        }));
+
+        let mut results = Vec::new();
+        while receiver.is_some() {
+            tokio::time::sleep(tokio::time::Duration::from_millis(5)).await;
+            results.extend(drain(&mut receiver));
+        }
+        results.sort();
+        assert_eq!(results.len(), ids.len());
+        assert_eq!(results[49], (ids[49], 49));
+        assert!(most.load(Ordering::SeqCst) <= ENRICH_WORKERS);
+    }
+}
@@ -1,3 +1,5 @@
+use super::ssh::SshDiagnostics;
+use std::path::{Path, PathBuf};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -9,7 +11,7 @@ pub enum GitError {
     NotARepo,
 
     #[error("Git error: {0}")]
-    Git(#[from] git2::Error),
+    Git(git2::Error),
 
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
@@ -22,6 +24,207 @@ pub enum GitError {
 
     #[error("Invalid UTF-8 in git data")]
     InvalidUtf8,
+
+    #[error("Nothing staged to commit")]
+    NothingToCommit,
+
+    #[error("No local changes to stash")]
+    NothingToStash,
+
+    #[error("No commit to amend")]
+    NothingToAmend,
+
+    #[error("HEAD is already on {0}")]
+    AlreadyPushed(String),
+
+    #[error("Background task to {operation} did not finish: {source}")]
+    TaskJoin {
+        operation: &'static str,
+        #[source]
+        source: tokio::task::JoinError,
+    },
+
+    #[error("{} is locked by another git process", path.display())]
+    LockContention { path: PathBuf },
+
+    #[error("{}", describe_conflict(paths))]
+    MergeConflict { paths: Vec<String> },
+
+    #[error("{}", describe_auth_failure(remote.as_deref()))]
+    AuthFailed { remote: Option<String> },
+
+    #[error("HEAD is detached at {short_id}")]
+    DetachedHead { short_id: String },
+
+    #[error("{0} is not tracked by git")]
+    Untracked(String),
+
+    #[error("Edited hunk is invalid: {0}")]
+    InvalidPatch(String),
+
+    #[error("Can't split: {0}")]
+    CannotSplit(String),
+
+    #[error("Can't absorb: {0}")]
+    CannotAbsorb(String),
+
+    #[error("Invalid pattern {pattern}: {message}")]
+    InvalidPattern { pattern: String, message: String },
+
+    #[error("Can't rebase: {0}")]
+    CannotRebase(String),
+
+    #[error("Uncommitted changes to tracked files")]
+    UncommittedChanges,
+
+    #[error("Can't check out: {0}")]
+    CannotCheckout(String),
+
+    #[error("Can't cherry-pick: {0}")]
+    CannotCherryPick(String),
+
+    #[error("Can't revert: {0}")]
+    CannotRevert(String),
+
+    #[error("Can't merge: {0}")]
+    CannotMerge(String),
+
+    #[error("Can't pull: {0}")]
+    CannotPull(String),
+
+    /// A continue, skip or abort git refused, with the reason it gave
+    #[error("{command} failed: {message}")]
+    StepFailed { command: String, message: String },
+
+    #[error("No remote to push to")]
+    NoRemote,
+
+    #[error("Can't run hook: {0}")]
+    CannotRunHook(String),
+
+    /// A push the remote refused or that never reached it; SSH remotes
+    /// carry what the transport saw
+    #[error("Push to {remote} failed: {message}")]
+    PushFailed {
+        remote: String,
+        message: String,
+        ssh: Option<Box<SshDiagnostics>>,
+    },
+}
+
+impl GitError {
+    /// Map the failure of a `spawn_blocking` task running `operation`
+    pub fn task_join(operation: &'static str) -> impl FnOnce(tokio::task::JoinError) -> GitError {
+        move |source| GitError::TaskJoin { operation, source }
+    }
+
+    /// Suggest what the user can do about the error, if anything
+    pub fn hint(&self) -> Option<&'static str> {
+        match self {
+            GitError::LockContention { .. } => {
+                Some("wait for the other git command to finish, or remove the lock file")
+            }
+            GitError::MergeConflict { .. } => Some("resolve the conflicts and stage the files"),
+            GitError::AuthFailed { .. } => Some("check your credentials for the remote"),
+            GitError::DetachedHead { .. } => Some("check out a branch first"),
+            GitError::Untracked(_) => Some("stage or delete the whole file instead"),
+            GitError::InvalidPatch(_) => Some("start every line with ' ', '+' or '-'"),
+            GitError::NothingToCommit => Some("stage files with u first"),
+            GitError::NoRemote => Some("add one with git remote add"),
+            GitError::UncommittedChanges => Some("commit or stash them first"),
+            GitError::AlreadyPushed(_) => {
+                Some("amending rewrites history others may have; confirm to amend anyway")
+            }
+            GitError::CannotCheckout(_) => Some("commit or stash the changes first"),
+            _ => None,
+        }
+    }
+}
+
+/// Sort libgit2 errors into the variants the UI reacts to
+impl From<git2::Error> for GitError {
+    fn from(error: git2::Error) -> Self {
+        match error.code() {
+            git2::ErrorCode::Locked => GitError::LockContention {
+                path: locked_path(error.message()),
+            },
+            git2::ErrorCode::Conflict
+            | git2::ErrorCode::MergeConflict
+            | git2::ErrorCode::Unmerged => GitError::MergeConflict { paths: Vec::new() },
+            git2::ErrorCode::Auth => GitError::AuthFailed { remote: None },
+            _ => GitError::Git(error),
+        }
+    }
+}
+
+/// Pull the lock file out of a message like
+/// "failed to create locked file '/repo/.git/index.lock': File exists"
+fn locked_path(message: &str) -> PathBuf {
+    message
+        .split('\'')
+        .nth(1)
+        .map(Path::new)
+        .unwrap_or(Path::new("index.lock"))
+        .to_path_buf()
+}
+
+fn describe_conflict(paths: &[String]) -> String {
+    if paths.is_empty() {
+        "Unresolved merge conflicts".to_string()
+    } else {
+        format!("Unresolved merge conflicts in {}", paths.join(", "))
+    }
+}
+
+fn describe_auth_failure(remote: Option<&str>) -> String {
+    match remote {
+        Some(remote) => format!("Authentication failed for {}", remote),
+        None => "Authentication failed".to_string(),
+    }
 }
 
 pub type Result<T> = std::result::Result<T, GitError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_git2_errors_are_classified() {
+        let locked = git2::Error::new(
+            git2::ErrorCode::Locked,
+            git2::ErrorClass::Index,
+            "failed to create locked file '/repo/.git/index.lock': File exists",
+        );
+        let error = GitError::from(locked);
+        assert!(
+            matches!(&error, GitError::LockContention { path } if path == Path::new("/repo/.git/index.lock"))
+        );
+        assert!(error.hint().is_some());
+
+        let auth = git2::Error::new(git2::ErrorCode::Auth, git2::ErrorClass::Ssh, "denied");
+        assert!(matches!(
+            GitError::from(auth),
+            GitError::AuthFailed { remote: None }
+        ));
+
+        let other = git2::Error::from_str("something else");
+        assert!(matches!(GitError::from(other), GitError::Git(_)));
+    }
+
+    #[test]
+    fn test_messages_include_context() {
+        let conflict = GitError::MergeConflict {
+            paths: vec!["a.txt".to_string(), "b.txt".to_string()],
+        };
+        assert_eq!(
+            conflict.to_string(),
+            "Unresolved merge conflicts in a.txt, b.txt"
+        );
+
+        let auth = GitError::AuthFailed {
+            remote: Some("origin".to_string()),
+        };
+        assert_eq!(auth.to_string(), "Authentication failed for origin");
+    }
+}
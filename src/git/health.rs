@@ -0,0 +1,363 @@
+use super::{
+    error::{GitError, Result},
+    repository::Repository,
+};
+use git2::{ObjectType, Oid};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// How old a lock file must be before no running git command is likely to
+/// own it
+pub const STALE_LOCK_AGE: Duration = Duration::from_secs(10 * 60);
+
+/// An object some ref's history needs that the object database lacks
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissingObject {
+    pub id: Oid,
+    /// The ref, reflog or index whose history needs it
+    pub needed_by: String,
+}
+
+/// An object that can't be read back, as when its content no longer
+/// matches its hash
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CorruptObject {
+    pub id: Oid,
+    pub message: String,
+}
+
+/// An object nothing refers to, not even another unreachable object
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DanglingObject {
+    pub id: Oid,
+    pub kind: ObjectType,
+}
+
+/// A ref that can't be resolved to an object
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BrokenRef {
+    pub name: String,
+    pub reason: String,
+}
+
+/// A lock file left behind by a git process that died holding it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StaleLock {
+    pub path: PathBuf,
+    pub age: Duration,
+}
+
+/// What a health check found, like `git fsck` with lock files
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HealthReport {
+    /// Objects in the object database
+    pub objects: usize,
+    pub corrupt: Vec<CorruptObject>,
+    pub missing: Vec<MissingObject>,
+    pub broken_refs: Vec<BrokenRef>,
+    pub stale_locks: Vec<StaleLock>,
+    /// Harmless on their own, but `gc` or `prune` removes them
+    pub dangling: Vec<DanglingObject>,
+}
+
+impl HealthReport {
+    /// Whether the repository has no problems worth fixing; dangling
+    /// objects don't count
+    pub fn is_healthy(&self) -> bool {
+        self.corrupt.is_empty()
+            && self.missing.is_empty()
+            && self.broken_refs.is_empty()
+            && self.stale_locks.is_empty()
+    }
+}
+
+/// Get the objects an object refers to directly: a commit's tree and
+/// parents, a tree's entries other than submodules, a tag's target
+fn referenced(git_repo: &git2::Repository, id: Oid, kind: ObjectType) -> Vec<Oid> {
+    match kind {
+        ObjectType::Commit => git_repo.find_commit(id).map_or_else(
+            |_| Vec::new(),
+            |commit| {
+                std::iter::once(commit.tree_id())
+                    .chain(commit.parent_ids())
+                    .collect()
+            },
+        ),
+        ObjectType::Tree => git_repo.find_tree(id).map_or_else(
+            |_| Vec::new(),
+            |tree| {
+                tree.iter()
+                    .filter(|entry| entry.kind() != Some(ObjectType::Commit))
+                    .map(|entry| entry.id())
+                    .collect()
+            },
+        ),
+        ObjectType::Tag => git_repo
+            .find_tag(id)
+            .map_or_else(|_| Vec::new(), |tag| vec![tag.target_id()]),
+        _ => Vec::new(),
+    }
+}
+
+/// Mark everything reachable from `root`, noting objects that are missing
+fn mark_reachable(
+    git_repo: &git2::Repository,
+    odb: &git2::Odb,
+    root: Oid,
+    name: &str,
+    reachable: &mut HashSet<Oid>,
+    missing: &mut Vec<MissingObject>,
+) {
+    let mut pending = vec![root];
+    while let Some(id) = pending.pop() {
+        if id.is_zero() || !reachable.insert(id) {
+            continue;
+        }
+        match odb.read_header(id) {
+            Ok((_, kind)) => pending.extend(referenced(git_repo, id, kind)),
+            Err(_) => missing.push(MissingObject {
+                id,
+                needed_by: name.to_string(),
+            }),
+        }
+    }
+}
+
+/// Find lock files under the git directory older than `STALE_LOCK_AGE`,
+/// leaving out the object database
+fn find_stale_locks(dir: &Path, now: SystemTime, locks: &mut Vec<StaleLock>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if metadata.is_dir() {
+            if entry.file_name() != "objects" {
+                find_stale_locks(&path, now, locks);
+            }
+        } else if path.extension().is_some_and(|ext| ext == "lock")
+            && let Ok(age) = metadata
+                .modified()
+                .map(|modified| now.duration_since(modified).unwrap_or_default())
+            && age >= STALE_LOCK_AGE
+        {
+            locks.push(StaleLock { path, age });
+        }
+    }
+}
+
+/// Check the repository's health: read back every object, walk every ref,
+/// reflog and the index for missing objects, and look for broken refs,
+/// stale lock files and dangling objects
+pub async fn check_health(repo: &Repository) -> Result<HealthReport> {
+    let repo = repo.clone();
+
+    tokio::task::spawn_blocking(move || {
+        let git_repo = repo.open_git2()?;
+        let odb = git_repo.odb()?;
+        let mut report = HealthReport::default();
+
+        let mut all = Vec::new();
+        odb.foreach(|id| {
+            all.push(*id);
+            true
+        })?;
+        report.objects = all.len();
+        for &id in &all {
+            if let Err(e) = odb.read(id) {
+                report.corrupt.push(CorruptObject {
+                    id,
+                    message: e.message().to_string(),
+                });
+            }
+        }
+
+        let mut reachable = HashSet::new();
+        let mut names = Vec::new();
+        for reference in git_repo.references()? {
+            let reference = match reference {
+                Ok(reference) => reference,
+                Err(e) => {
+                    report.broken_refs.push(BrokenRef {
+                        name: "(unreadable)".to_string(),
+                        reason: e.message().to_string(),
+                    });
+                    continue;
+                }
+            };
+            let name = String::from_utf8_lossy(reference.name_bytes()).into_owned();
+            match reference.resolve() {
+                Ok(resolved) => match resolved.target() {
+                    Some(id) if odb.exists(id) => {
+                        mark_reachable(
+                            &git_repo,
+                            &odb,
+                            id,
+                            &name,
+                            &mut reachable,
+                            &mut report.missing,
+                        );
+                    }
+                    Some(id) => report.broken_refs.push(BrokenRef {
+                        name: name.clone(),
+                        reason: format!("points to missing object {}", id),
+                    }),
+                    None => {}
+                },
+                Err(_) => report.broken_refs.push(BrokenRef {
+                    name: name.clone(),
+                    reason: format!(
+                        "points to {}, which doesn't exist",
+                        reference.symbolic_target().unwrap_or("nothing")
+                    ),
+                }),
+            }
+            names.push(name);
+        }
+        // A detached HEAD has no ref of its own; an unborn one reaches nothing
+        if let Ok(head) = git_repo.head()
+            && let Some(id) = head.target()
+        {
+            mark_reachable(
+                &git_repo,
+                &odb,
+                id,
+                "HEAD",
+                &mut reachable,
+                &mut report.missing,
+            );
+        }
+
+        names.push("HEAD".to_string());
+        for name in &names {
+            let Ok(reflog) = git_repo.reflog(name) else {
+                continue;
+            };
+            let label = format!("reflog of {}", name);
+            for entry in reflog.iter() {
+                for id in [entry.id_old(), entry.id_new()] {
+                    // Reflogs keep naming commits after they're pruned
+                    if odb.exists(id) {
+                        mark_reachable(
+                            &git_repo,
+                            &odb,
+                            id,
+                            &label,
+                            &mut reachable,
+                            &mut report.missing,
+                        );
+                    }
+                }
+            }
+        }
+        if let Ok(index) = git_repo.index() {
+            for entry in index.iter() {
+                mark_reachable(
+                    &git_repo,
+                    &odb,
+                    entry.id,
+                    "index",
+                    &mut reachable,
+                    &mut report.missing,
+                );
+            }
+        }
+
+        let unreachable: Vec<(Oid, ObjectType)> = all
+            .iter()
+            .filter(|id| !reachable.contains(id))
+            .filter_map(|&id| odb.read_header(id).ok().map(|(_, kind)| (id, kind)))
+            .collect();
+        let referenced_by_unreachable: HashSet<Oid> = unreachable
+            .iter()
+            .flat_map(|&(id, kind)| referenced(&git_repo, id, kind))
+            .collect();
+        report.dangling = unreachable
+            .into_iter()
+            .filter(|(id, _)| !referenced_by_unreachable.contains(id))
+            .map(|(id, kind)| DanglingObject { id, kind })
+            .collect();
+        report.dangling.sort_by_key(|object| object.id);
+
+        find_stale_locks(git_repo.path(), SystemTime::now(), &mut report.stale_locks);
+        report.stale_locks.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(report)
+    })
+    .await
+    .map_err(GitError::task_join("check the repository's health"))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::RepoBuilder;
+    use std::fs::File;
+
+    #[tokio::test]
+    async fn test_healthy_repository() {
+        let (_temp_dir, repo) = RepoBuilder::new()
+            .file("a.txt", "a\n")
+            .commit("Initial")
+            .open()
+            .await;
+
+        let report = check_health(&repo).await.unwrap();
+        assert!(report.is_healthy());
+        assert!(report.dangling.is_empty());
+        assert_eq!(report.objects, 3);
+    }
+
+    #[tokio::test]
+    async fn test_finds_dangling_objects_broken_refs_and_stale_locks() {
+        let (_temp_dir, repo) = RepoBuilder::new()
+            .file("a.txt", "a\n")
+            .commit("Initial")
+            .open()
+            .await;
+        let git_repo = repo.open_git2().unwrap();
+        let git_dir = git_repo.path().to_path_buf();
+
+        // A commit and the new blob in its tree: only the commit dangles
+        let blob = git_repo.blob(b"orphan\n").unwrap();
+        let mut builder = git_repo.treebuilder(None).unwrap();
+        builder.insert("orphan.txt", blob, 0o100644).unwrap();
+        let tree = git_repo.find_tree(builder.write().unwrap()).unwrap();
+        let signature = git_repo.signature().unwrap();
+        let commit = git_repo
+            .commit(None, &signature, &signature, "Orphan", &tree, &[])
+            .unwrap();
+        let loose = git_repo.blob(b"loose\n").unwrap();
+
+        std::fs::write(
+            git_dir.join("refs/heads/broken"),
+            format!("{}\n", "1".repeat(40)),
+        )
+        .unwrap();
+        let lock = File::create(git_dir.join("index.lock")).unwrap();
+        lock.set_modified(SystemTime::now() - STALE_LOCK_AGE * 2)
+            .unwrap();
+        File::create(git_dir.join("HEAD.lock")).unwrap();
+
+        let report = check_health(&repo).await.unwrap();
+        assert!(!report.is_healthy());
+        let mut dangling = vec![(commit, ObjectType::Commit), (loose, ObjectType::Blob)];
+        dangling.sort_by_key(|(id, _)| *id);
+        assert_eq!(
+            report
+                .dangling
+                .iter()
+                .map(|object| (object.id, object.kind))
+                .collect::<Vec<_>>(),
+            dangling
+        );
+        assert_eq!(report.broken_refs.len(), 1);
+        assert_eq!(report.broken_refs[0].name, "refs/heads/broken");
+        assert_eq!(report.stale_locks.len(), 1);
+        assert!(report.stale_locks[0].path.ends_with("index.lock"));
+        assert!(report.missing.is_empty() && report.corrupt.is_empty());
+    }
+}
@@ -0,0 +1,262 @@
+use super::{
+    error::{GitError, Result},
+    maintenance::{GitOutput, spawn_streaming},
+    repository::Repository,
+};
+use std::path::{Path, PathBuf};
+use tokio::sync::mpsc;
+
+/// A script in the hooks directory
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hook {
+    /// The file name, which is the event it hooks, like `pre-commit`
+    pub name: String,
+    pub path: PathBuf,
+    pub executable: bool,
+    pub script: String,
+}
+
+impl Hook {
+    /// Whether this is one of the `.sample` scripts `git init` writes,
+    /// which git never runs
+    pub fn is_sample(&self) -> bool {
+        self.name.ends_with(".sample")
+    }
+
+    /// Whether git runs this hook
+    pub fn is_enabled(&self) -> bool {
+        self.executable && !self.is_sample()
+    }
+}
+
+/// The hooks directory and the hooks in it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hooks {
+    pub dir: PathBuf,
+    /// Real hooks by name, then samples
+    pub hooks: Vec<Hook>,
+}
+
+/// Find where git looks for hooks: `core.hooksPath`, relative to the work
+/// tree when relative, else `hooks` in the git directory
+fn hooks_dir(git_repo: &git2::Repository) -> PathBuf {
+    let configured = git_repo
+        .config()
+        .ok()
+        .and_then(|config| config.get_path("core.hooksPath").ok());
+    match configured {
+        Some(path) if path.is_absolute() => path,
+        Some(path) => git_repo.workdir().unwrap_or(git_repo.path()).join(path),
+        None => git_repo.path().join("hooks"),
+    }
+}
+
+#[cfg(unix)]
+fn is_executable(metadata: &std::fs::Metadata) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    metadata.permissions().mode() & 0o111 != 0
+}
+
+/// Git for Windows runs hooks whatever their permissions
+#[cfg(not(unix))]
+fn is_executable(_metadata: &std::fs::Metadata) -> bool {
+    true
+}
+
+/// List the hooks directory, with each script's content
+pub async fn load_hooks(repo: &Repository) -> Result<Hooks> {
+    let repo = repo.clone();
+
+    tokio::task::spawn_blocking(move || {
+        let git_repo = repo.open_git2()?;
+        let dir = hooks_dir(&git_repo);
+        let mut hooks = Vec::new();
+        // A missing directory just has no hooks
+        if let Ok(entries) = std::fs::read_dir(&dir) {
+            for entry in entries.flatten() {
+                let path = entry.path();
+                let Ok(metadata) = std::fs::metadata(&path) else {
+                    continue;
+                };
+                if !metadata.is_file() {
+                    continue;
+                }
+                let script = std::fs::read(&path)
+                    .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())?;
+                hooks.push(Hook {
+                    name: entry.file_name().to_string_lossy().into_owned(),
+                    path,
+                    executable: is_executable(&metadata),
+                    script,
+                });
+            }
+        }
+        hooks.sort_by(|a, b| {
+            a.is_sample()
+                .cmp(&b.is_sample())
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        Ok(Hooks { dir, hooks })
+    })
+    .await
+    .map_err(GitError::task_join("list the hooks"))?
+}
+
+/// Set or clear a hook's executable bits, which decide whether git runs it
+pub async fn set_executable(path: PathBuf, executable: bool) -> Result<()> {
+    tokio::task::spawn_blocking(move || set_mode(&path, executable))
+        .await
+        .map_err(GitError::task_join("change a hook's permissions"))?
+}
+
+#[cfg(unix)]
+fn set_mode(path: &Path, executable: bool) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    let mut permissions = std::fs::metadata(path)?.permissions();
+    let mode = permissions.mode();
+    // Executable by whoever can read it, like chmod +x under a umask
+    permissions.set_mode(match executable {
+        true => mode | ((mode & 0o444) >> 2),
+        false => mode & !0o111,
+    });
+    std::fs::set_permissions(path, permissions)?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn set_mode(_path: &Path, _executable: bool) -> Result<()> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "hooks have no executable bit here",
+    )
+    .into())
+}
+
+/// Work out the arguments git would pass the hook, preparing any file they
+/// name: message hooks get `COMMIT_EDITMSG`, holding the last commit's
+/// message when no commit has written it yet
+fn hook_args(git_repo: &git2::Repository, name: &str) -> Result<Vec<String>> {
+    let message_file = || -> Result<String> {
+        let path = git_repo.path().join("COMMIT_EDITMSG");
+        if !path.exists() {
+            let message = git_repo
+                .head()
+                .and_then(|head| head.peel_to_commit())
+                .map(|commit| commit.message().unwrap_or_default().to_string())
+                .unwrap_or_else(|_| "Test commit\n".to_string());
+            std::fs::write(&path, message)?;
+        }
+        Ok(path.to_string_lossy().into_owned())
+    };
+    Ok(match name {
+        "commit-msg" | "applypatch-msg" => vec![message_file()?],
+        "prepare-commit-msg" => vec![message_file()?, "message".to_string()],
+        "pre-push" => {
+            let remote = git_repo.find_remote("origin").ok();
+            let url = remote.as_ref().and_then(|r| r.url()).unwrap_or_default();
+            vec!["origin".to_string(), url.to_string()]
+        }
+        "post-checkout" => vec!["HEAD".to_string(), "HEAD".to_string(), "1".to_string()],
+        _ => Vec::new(),
+    })
+}
+
+/// Run a hook against the repository as it is now, the way git would for
+/// its event, streaming what it prints
+///
+/// A hook git wouldn't run fails here too, so a hook that silently never
+/// runs shows up.
+pub async fn test_hook(
+    repo: &Repository,
+    hook: &Hook,
+) -> Result<mpsc::UnboundedReceiver<GitOutput>> {
+    if hook.is_sample() {
+        return Err(GitError::CannotRunHook(format!(
+            "git never runs {}; copy it without .sample to use it",
+            hook.name
+        )));
+    }
+    if !hook.executable {
+        return Err(GitError::CannotRunHook(format!(
+            "{} isn't executable, so git skips it",
+            hook.name
+        )));
+    }
+
+    let repo = repo.clone();
+    let name = hook.name.clone();
+    let (dir, args) = tokio::task::spawn_blocking(move || {
+        let git_repo = repo.open_git2()?;
+        // Git runs hooks from the top of the work tree
+        let dir = git_repo.workdir().unwrap_or(git_repo.path()).to_path_buf();
+        Ok::<_, GitError>((dir, hook_args(&git_repo, &name)?))
+    })
+    .await
+    .map_err(GitError::task_join("prepare a hook"))??;
+
+    let mut command = tokio::process::Command::new(&hook.path);
+    command.args(&args).current_dir(dir);
+    Ok(spawn_streaming(command, hook.name.clone()))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::testing::RepoBuilder;
+
+    async fn collect(mut rx: mpsc::UnboundedReceiver<GitOutput>) -> Vec<GitOutput> {
+        let mut output = Vec::new();
+        while let Some(line) = rx.recv().await {
+            output.push(line);
+        }
+        output
+    }
+
+    #[tokio::test]
+    async fn test_lists_toggles_and_runs_hooks() {
+        let (_temp_dir, repo) = RepoBuilder::new().commit("Initial").open().await;
+        let dir = repo.open_git2().unwrap().path().join("hooks");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("commit-msg"),
+            "#!/bin/sh\necho checking\ngrep -q Initial \"$1\"\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("pre-commit.sample"), "#!/bin/sh\n").unwrap();
+
+        let hooks = load_hooks(&repo).await.unwrap();
+        let names: Vec<&str> = hooks.hooks.iter().map(|h| h.name.as_str()).collect();
+        assert!(names.starts_with(&["commit-msg"]));
+        let hook = hooks.hooks[0].clone();
+        assert!(!hook.is_enabled());
+        assert!(
+            test_hook(&repo, &hook)
+                .await
+                .unwrap_err()
+                .to_string()
+                .contains("isn't executable")
+        );
+        let sample = hooks
+            .hooks
+            .iter()
+            .find(|h| h.name == "pre-commit.sample")
+            .unwrap();
+        assert!(test_hook(&repo, sample).await.is_err());
+
+        set_executable(hook.path.clone(), true).await.unwrap();
+        let hook = load_hooks(&repo).await.unwrap().hooks.remove(0);
+        assert!(hook.is_enabled());
+
+        let output = collect(test_hook(&repo, &hook).await.unwrap()).await;
+        assert_eq!(
+            output,
+            [
+                GitOutput::Line("checking".to_string()),
+                GitOutput::Exited(Ok(()))
+            ]
+        );
+
+        set_executable(hook.path.clone(), false).await.unwrap();
+        assert!(!load_hooks(&repo).await.unwrap().hooks[0].executable);
+    }
+}
@@ -0,0 +1,177 @@
+use super::{
+    error::{GitError, Result},
+    remote::web_url_in,
+    repository::Repository,
+};
+use git2::Oid;
+use regex::Regex;
+
+/// Git config key of a repository's own link patterns, each set to a
+/// pattern and a URL template separated by a space
+pub const LINK_CONFIG_KEY: &str = "rust-tig.link";
+
+/// References to issues or pull requests in commit messages and the pages
+/// they link to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkPattern {
+    /// Regular expression of the reference, like `#(\d+)`
+    pub pattern: String,
+    /// Page of a reference: `%(0)` is filled in with the whole reference,
+    /// `%(1)` and on with its groups, and `%(url)` with the repository's
+    /// web page
+    pub url: String,
+}
+
+/// A reference found in a commit message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IssueLink {
+    /// The reference as written, like `#123`
+    pub text: String,
+    pub url: String,
+}
+
+/// Find the references in a commit's message, with the repository's own
+/// patterns from its git config tried before `patterns`
+pub async fn issue_links(
+    repo: &Repository,
+    commit_id: Oid,
+    patterns: Vec<LinkPattern>,
+) -> Result<Vec<IssueLink>> {
+    let repo = repo.clone();
+
+    tokio::task::spawn_blocking(move || {
+        let git_repo = repo.open_git2()?;
+        let message = git_repo
+            .find_commit(commit_id)?
+            .message()
+            .unwrap_or_default()
+            .to_string();
+
+        let mut all_patterns = Vec::new();
+        git_repo
+            .config()?
+            .multivar(LINK_CONFIG_KEY, None)?
+            .for_each(|entry| {
+                if let Some((pattern, url)) = entry
+                    .value()
+                    .and_then(|value| value.trim().rsplit_once(char::is_whitespace))
+                {
+                    all_patterns.push(LinkPattern {
+                        pattern: pattern.trim().to_string(),
+                        url: url.to_string(),
+                    });
+                }
+            })?;
+        all_patterns.extend(patterns);
+
+        find_links(&message, &all_patterns, web_url_in(&git_repo)?.as_deref())
+    })
+    .await
+    .map_err(GitError::task_join("find issue links"))?
+}
+
+/// Find the references in `message`, in the order they appear and each
+/// once; references needing `%(url)` are skipped without a web page
+pub fn find_links(
+    message: &str,
+    patterns: &[LinkPattern],
+    web_url: Option<&str>,
+) -> Result<Vec<IssueLink>> {
+    let mut found: Vec<(usize, IssueLink)> = Vec::new();
+    for link in patterns {
+        let regex = Regex::new(&link.pattern).map_err(|e| GitError::InvalidPattern {
+            pattern: link.pattern.clone(),
+            message: e.to_string(),
+        })?;
+        if web_url.is_none() && link.url.contains("%(url)") {
+            continue;
+        }
+        for captures in regex.captures_iter(message) {
+            let whole = captures.get(0).expect("a match has group 0");
+            if found.iter().any(|(_, found)| found.text == whole.as_str()) {
+                continue;
+            }
+            let mut url = link.url.replace("%(url)", web_url.unwrap_or_default());
+            for (i, group) in captures.iter().enumerate() {
+                url = url.replace(
+                    &format!("%({})", i),
+                    group.map_or("", |group| group.as_str()),
+                );
+            }
+            found.push((
+                whole.start(),
+                IssueLink {
+                    text: whole.as_str().to_string(),
+                    url,
+                },
+            ));
+        }
+    }
+    found.sort_by_key(|(start, _)| *start);
+    Ok(found.into_iter().map(|(_, link)| link).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::RepoBuilder;
+
+    fn pattern(pattern: &str, url: &str) -> LinkPattern {
+        LinkPattern {
+            pattern: pattern.to_string(),
+            url: url.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_find_links() {
+        let patterns = [
+            pattern(r"#(\d+)", "%(url)/issues/%(1)"),
+            pattern(
+                r"\b[A-Z][A-Z0-9]+-\d+\b",
+                "https://jira.example.com/browse/%(0)",
+            ),
+        ];
+        let message = "Fix crash (PROJ-12)\n\nCloses #7 and #7 again, see #8.";
+
+        let links = find_links(message, &patterns, Some("https://github.com/o/r")).unwrap();
+        let texts: Vec<&str> = links.iter().map(|link| link.text.as_str()).collect();
+        assert_eq!(texts, ["PROJ-12", "#7", "#8"]);
+        assert_eq!(links[0].url, "https://jira.example.com/browse/PROJ-12");
+        assert_eq!(links[1].url, "https://github.com/o/r/issues/7");
+
+        // Without a web page only the links that don't need one are found
+        assert_eq!(find_links(message, &patterns, None).unwrap().len(), 1);
+        assert!(matches!(
+            find_links(message, &[pattern("(", "x")], None),
+            Err(GitError::InvalidPattern { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_repository_patterns_come_from_git_config() {
+        let builder = RepoBuilder::new().commit("Fix login\n\nSee ENG-42 and #3");
+        let head = builder.head();
+        let mut config = builder.git2().config().unwrap();
+        config
+            .set_multivar(
+                LINK_CONFIG_KEY,
+                "^$",
+                "ENG-([0-9]+) https://tracker.example.com/%(1)",
+            )
+            .unwrap();
+        drop(config);
+        let (_temp_dir, repo) = builder.open().await;
+
+        let links = issue_links(&repo, head, vec![pattern(r"#(\d+)", "%(url)/issues/%(1)")])
+            .await
+            .unwrap();
+        assert_eq!(
+            links,
+            vec![IssueLink {
+                text: "ENG-42".to_string(),
+                url: "https://tracker.example.com/42".to_string(),
+            }]
+        );
+    }
+}
@@ -0,0 +1,272 @@
+use super::{
+    commit::Commit,
+    error::{GitError, Result},
+    repository::Repository,
+};
+use git2::{ObjectType, Oid};
+use std::collections::{HashMap, HashSet};
+
+/// Where a lost commit was found
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LostSource {
+    /// A reflog entry, like `HEAD@{2}`, with its message
+    Reflog { entry: String, message: String },
+    /// Only in the object database, as after a dropped stash
+    Unreachable,
+}
+
+/// A commit no branch, tag or other ref reaches any more
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LostCommit {
+    pub commit: Commit,
+    pub source: LostSource,
+}
+
+/// Check whether a commit matches a query: a hash prefix, or text in the
+/// message, ignoring case; an empty query matches everything
+fn matches(commit: &Commit, query: &str) -> bool {
+    let query = query.trim().to_lowercase();
+    if query.is_empty() {
+        return true;
+    }
+    let is_hash = query.len() >= 4 && query.chars().all(|c| c.is_ascii_hexdigit());
+    (is_hash && commit.id.to_string().starts_with(&query))
+        || commit.message.to_lowercase().contains(&query)
+}
+
+/// Collect every object reachable from a ref or HEAD: the commits, and the
+/// trees and blobs they hold, each tree read once however many commits
+/// share it
+fn reachable_objects(git_repo: &git2::Repository) -> Result<HashSet<Oid>> {
+    let mut walk = git_repo.revwalk()?;
+    walk.push_glob("*")?;
+    if let Ok(head) = git_repo.refname_to_id("HEAD") {
+        walk.push(head)?;
+    }
+    let commits = walk.collect::<std::result::Result<Vec<Oid>, _>>()?;
+
+    let mut reachable: HashSet<Oid> = commits.iter().copied().collect();
+    let mut trees = Vec::new();
+    for oid in commits {
+        let tree = git_repo.find_commit(oid)?.tree_id();
+        if reachable.insert(tree) {
+            trees.push(tree);
+        }
+    }
+    while let Some(oid) = trees.pop() {
+        for entry in git_repo.find_tree(oid)?.iter() {
+            if reachable.insert(entry.id()) && entry.kind() == Some(ObjectType::Tree) {
+                trees.push(entry.id());
+            }
+        }
+    }
+    Ok(reachable)
+}
+
+/// Search for commits that are no longer reachable from any ref, like
+/// `git fsck --lost-found`, whose hash starts with `query` or whose message
+/// contains it
+///
+/// Reflogs are searched first, so commits dropped by a reset or rebase say
+/// how they were left behind; the object database turns up the rest, such
+/// as dropped stashes. Newest first.
+pub async fn find_lost(repo: &Repository, query: String) -> Result<Vec<LostCommit>> {
+    let repo = repo.clone();
+
+    tokio::task::spawn_blocking(move || {
+        let git_repo = repo.open_git2()?;
+
+        let reachable = reachable_objects(&git_repo)?;
+
+        let mut sources: HashMap<Oid, LostSource> = HashMap::new();
+        let mut names = vec!["HEAD".to_string()];
+        for reference in git_repo.references()?.flatten() {
+            names.extend(reference.name().map(str::to_string));
+        }
+        for name in &names {
+            let Ok(reflog) = git_repo.reflog(name) else {
+                continue;
+            };
+            let short = name.strip_prefix("refs/heads/").unwrap_or(name);
+            for (i, entry) in reflog.iter().enumerate() {
+                for oid in [entry.id_new(), entry.id_old()] {
+                    if oid.is_zero() || reachable.contains(&oid) || sources.contains_key(&oid) {
+                        continue;
+                    }
+                    sources.insert(
+                        oid,
+                        LostSource::Reflog {
+                            entry: format!("{}@{{{}}}", short, i),
+                            message: entry.message().unwrap_or_default().to_string(),
+                        },
+                    );
+                }
+            }
+        }
+
+        // Only the few objects nothing reaches are left to read the type of
+        let mut unreachable = Vec::new();
+        git_repo.odb()?.foreach(|oid| {
+            if !reachable.contains(oid) && !sources.contains_key(oid) {
+                unreachable.push(*oid);
+            }
+            true
+        })?;
+        let odb = git_repo.odb()?;
+        for oid in unreachable {
+            if odb
+                .read_header(oid)
+                .is_ok_and(|(_, kind)| kind == ObjectType::Commit)
+            {
+                sources.insert(oid, LostSource::Unreachable);
+            }
+        }
+
+        let mut lost = Vec::new();
+        for (oid, source) in sources {
+            // Reflogs can outlive the objects they name
+            let Ok(commit) = git_repo.find_commit(oid) else {
+                continue;
+            };
+            let commit = Commit::from_git2(&commit)?;
+            if matches(&commit, &query) {
+                lost.push(LostCommit { commit, source });
+            }
+        }
+        lost.sort_by(|a, b| {
+            b.commit
+                .date
+                .cmp(&a.commit.date)
+                .then(a.commit.id.cmp(&b.commit.id))
+        });
+        Ok(lost)
+    })
+    .await
+    .map_err(GitError::task_join("search for lost commits"))?
+}
+
+/// Create branch `name` at a lost commit, so it is reachable again
+pub async fn recover(repo: &Repository, oid: Oid, name: String) -> Result<()> {
+    let repo = repo.clone();
+
+    tokio::task::spawn_blocking(move || {
+        let git_repo = repo.open_git2()?;
+        let commit = git_repo.find_commit(oid)?;
+        git_repo.branch(&name, &commit, false)?;
+        Ok(())
+    })
+    .await
+    .map_err(GitError::task_join("recover a commit"))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::RepoBuilder;
+    use git2::ResetType;
+
+    #[tokio::test]
+    async fn test_finds_reset_and_dangling_commits() {
+        let builder = RepoBuilder::new()
+            .file("a.txt", "a\n")
+            .commit("Keep")
+            .file("b.txt", "b\n")
+            .commit("Precious work");
+        let keep = builder
+            .git2()
+            .head()
+            .unwrap()
+            .peel_to_commit()
+            .unwrap()
+            .parent_id(0)
+            .unwrap();
+        let (_temp_dir, repo) = builder.open().await;
+        let git_repo = repo.open_git2().unwrap();
+        let precious = git_repo.head().unwrap().target().unwrap();
+        git_repo
+            .reset(
+                &git_repo.find_object(keep, None).unwrap(),
+                ResetType::Hard,
+                None,
+            )
+            .unwrap();
+
+        // A commit no reflog mentions
+        let tree = git_repo.find_commit(keep).unwrap().tree().unwrap();
+        let signature = git_repo.signature().unwrap();
+        let dangling = git_repo
+            .commit(None, &signature, &signature, "Stray", &tree, &[])
+            .unwrap();
+
+        let lost = find_lost(&repo, String::new()).await.unwrap();
+        let ids: Vec<Oid> = lost.iter().map(|l| l.commit.id).collect();
+        assert!(ids.contains(&precious) && ids.contains(&dangling));
+        assert!(!ids.contains(&keep));
+
+        let found = find_lost(&repo, "PRECIOUS".to_string()).await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert!(
+            matches!(&found[0].source, LostSource::Reflog { entry, .. } if entry.starts_with("HEAD@{"))
+        );
+        let found = find_lost(&repo, dangling.to_string()[..8].to_string())
+            .await
+            .unwrap();
+        assert_eq!(found[0].source, LostSource::Unreachable);
+
+        recover(&repo, precious, "rescued".to_string())
+            .await
+            .unwrap();
+        assert!(
+            find_lost(&repo, "precious".to_string())
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert!(
+            recover(&repo, dangling, "rescued".to_string())
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_only_unreachable_objects_are_read() {
+        let mut builder = RepoBuilder::new();
+        for i in 0..200 {
+            builder = builder.file(&format!("dir{}/file{}.txt", i % 10, i), &format!("{}\n", i));
+        }
+        let builder = builder
+            .commit("Many files")
+            .file("dir0/file0.txt", "changed\n")
+            .commit("Change one");
+        let (_temp_dir, repo) = builder.open().await;
+        let git_repo = repo.open_git2().unwrap();
+        let stray_blob = git_repo.blob(b"stray\n").unwrap();
+        let tree = git_repo.head().unwrap().peel_to_tree().unwrap();
+        let signature = git_repo.signature().unwrap();
+        let dangling = git_repo
+            .commit(None, &signature, &signature, "Stray", &tree, &[])
+            .unwrap();
+
+        let reachable = reachable_objects(&git_repo).unwrap();
+        let mut left = Vec::new();
+        git_repo
+            .odb()
+            .unwrap()
+            .foreach(|oid| {
+                if !reachable.contains(oid) {
+                    left.push(*oid);
+                }
+                true
+            })
+            .unwrap();
+        left.sort();
+        let mut expected = vec![stray_blob, dangling];
+        expected.sort();
+        assert_eq!(left, expected);
+
+        let lost = find_lost(&repo, String::new()).await.unwrap();
+        let ids: Vec<Oid> = lost.iter().map(|l| l.commit.id).collect();
+        assert_eq!(ids, [dangling]);
+    }
+}
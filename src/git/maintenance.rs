@@ -0,0 +1,168 @@
+use super::repository::Repository;
+use std::process::Stdio;
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::process::Command;
+use tokio::sync::mpsc;
+
+/// A maintenance task run through the git command line, since libgit2
+/// can't repack or prune
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Maintenance {
+    /// `git gc`: pack loose objects and refs, and prune old unreachable ones
+    Gc,
+    /// `git prune`: delete unreachable loose objects older than two weeks
+    Prune,
+    /// `git maintenance run`: the tasks `maintenance.*` configures, gc by
+    /// default
+    Run,
+}
+
+impl Maintenance {
+    /// The git arguments that run the task
+    pub fn args(self) -> &'static [&'static str] {
+        match self {
+            Maintenance::Gc => &["gc"],
+            Maintenance::Prune => &["prune", "--verbose"],
+            Maintenance::Run => &["maintenance", "run"],
+        }
+    }
+
+    /// The command as typed at a shell, for titles and messages
+    pub fn command_line(self) -> String {
+        format!("git {}", self.args().join(" "))
+    }
+}
+
+/// Output from a git command as it runs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GitOutput {
+    /// A finished line of output
+    Line(String),
+    /// A progress line ending in a carriage return, replacing the last
+    /// progress line
+    Progress(String),
+    /// The command finished, with why it failed if it did
+    Exited(std::result::Result<(), String>),
+}
+
+/// Forward a stream's output a line at a time, sending lines that end in a
+/// carriage return as progress
+async fn forward(mut reader: impl AsyncRead + Unpin, tx: mpsc::UnboundedSender<GitOutput>) {
+    let mut pending = Vec::new();
+    let mut buffer = [0u8; 4096];
+    let mut last = 0u8;
+    while let Ok(read) = reader.read(&mut buffer).await
+        && read > 0
+    {
+        for &byte in &buffer[..read] {
+            match byte {
+                // The newline of a CRLF ends nothing new
+                b'\n' if last == b'\r' => {}
+                b'\n' | b'\r' => {
+                    let text = String::from_utf8_lossy(&pending).into_owned();
+                    pending.clear();
+                    let _ = tx.send(match byte {
+                        b'\n' => GitOutput::Line(text),
+                        _ => GitOutput::Progress(text),
+                    });
+                }
+                _ => pending.push(byte),
+            }
+            last = byte;
+        }
+    }
+    if !pending.is_empty() {
+        let _ = tx.send(GitOutput::Line(
+            String::from_utf8_lossy(&pending).into_owned(),
+        ));
+    }
+}
+
+/// Run a git command in the repository, streaming its standard output and
+/// error as it runs and ending with `GitOutput::Exited`
+pub fn spawn_git(repo: &Repository, args: &[&str]) -> mpsc::UnboundedReceiver<GitOutput> {
+    let mut command = Command::new("git");
+    command.arg("-C").arg(repo.path()).args(args);
+    spawn_streaming(command, format!("git {}", args.join(" ")))
+}
+
+/// Run a command, streaming its standard output and error as it runs and
+/// ending with `GitOutput::Exited`; `command_line` names it in errors
+pub fn spawn_streaming(
+    mut command: Command,
+    command_line: String,
+) -> mpsc::UnboundedReceiver<GitOutput> {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let spawned = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn();
+
+    tokio::spawn(async move {
+        let mut child = match spawned {
+            Ok(child) => child,
+            Err(e) => {
+                let _ = tx.send(GitOutput::Exited(Err(format!(
+                    "Could not run {}: {}",
+                    command_line, e
+                ))));
+                return;
+            }
+        };
+        let stdout = child
+            .stdout
+            .take()
+            .map(|out| tokio::spawn(forward(out, tx.clone())));
+        let stderr = child
+            .stderr
+            .take()
+            .map(|err| tokio::spawn(forward(err, tx.clone())));
+        for reader in [stdout, stderr].into_iter().flatten() {
+            let _ = reader.await;
+        }
+        let exited = match child.wait().await {
+            Ok(status) if status.success() => Ok(()),
+            Ok(status) => Err(format!("{} failed ({})", command_line, status)),
+            Err(e) => Err(format!("{} failed: {}", command_line, e)),
+        };
+        let _ = tx.send(GitOutput::Exited(exited));
+    });
+    rx
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::RepoBuilder;
+
+    async fn collect(mut rx: mpsc::UnboundedReceiver<GitOutput>) -> Vec<GitOutput> {
+        let mut output = Vec::new();
+        while let Some(line) = rx.recv().await {
+            output.push(line);
+        }
+        output
+    }
+
+    #[tokio::test]
+    async fn test_streams_output_and_exit() {
+        let (_temp_dir, repo) = RepoBuilder::new().commit("Initial").open().await;
+
+        let output = collect(spawn_git(&repo, &["log", "--format=%s"])).await;
+        assert_eq!(
+            output,
+            [
+                GitOutput::Line("Initial".to_string()),
+                GitOutput::Exited(Ok(()))
+            ]
+        );
+
+        let output = collect(spawn_git(&repo, &["frobnicate"])).await;
+        let Some(GitOutput::Exited(Err(message))) = output.last() else {
+            panic!("expected a failure");
+        };
+        assert!(message.starts_with("git frobnicate failed"));
+        assert!(output.len() > 1);
+    }
+}
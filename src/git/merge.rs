@@ -0,0 +1,170 @@
+use super::{
+    cherry_pick::{conflicted_paths, operation_in_progress},
+    error::{GitError, Result},
+    pull::is_dirty,
+    repository::Repository,
+    watch::note_local_write,
+};
+use git2::{Oid, build::CheckoutBuilder};
+
+/// What merging a branch into HEAD did
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MergeOutcome {
+    /// HEAD already has every commit of the branch
+    UpToDate,
+    /// HEAD was behind the branch and moved up to this commit
+    FastForward(Oid),
+    /// The merge is staged, waiting to be committed with its message
+    Staged,
+    /// The merge stopped on conflicts in these files, left in the working
+    /// tree and index to resolve, stage and commit
+    Conflicted(Vec<String>),
+}
+
+/// Merge the branch or tag `name`, a full reference name, into HEAD, like
+/// `git merge --no-commit`
+///
+/// A HEAD behind the branch is fast-forwarded. Otherwise the merge is
+/// left in the merge state, staged or with conflicts, and committing from
+/// the status view starts from its message and makes the merge commit.
+/// Uncommitted changes to tracked files and another operation in progress
+/// refuse the merge before anything changes.
+pub async fn merge(repo: &Repository, name: String) -> Result<MergeOutcome> {
+    let repo = repo.clone();
+
+    tokio::task::spawn_blocking(move || {
+        let git_repo = repo.open_git2()?;
+        if let Some(operation) = operation_in_progress(&git_repo) {
+            return Err(GitError::CannotMerge(format!(
+                "a {} is in progress; finish it first",
+                operation
+            )));
+        }
+        if is_dirty(&git_repo)? {
+            return Err(GitError::UncommittedChanges);
+        }
+        let reference = git_repo.find_reference(&name)?;
+        let theirs = git_repo.reference_to_annotated_commit(&reference)?;
+        let (analysis, _) = git_repo.merge_analysis(&[&theirs])?;
+
+        if analysis.is_up_to_date() {
+            return Ok(MergeOutcome::UpToDate);
+        }
+        if analysis.is_unborn() {
+            return Err(GitError::CannotMerge("HEAD has no commits yet".to_string()));
+        }
+        if analysis.is_fast_forward() {
+            let target = theirs.id();
+            git_repo.checkout_tree(
+                &git_repo.find_object(target, None)?,
+                Some(CheckoutBuilder::new().safe()),
+            )?;
+            let head = git_repo.head()?;
+            let message = format!(
+                "merge {}: Fast-forward",
+                reference.shorthand().unwrap_or(&name)
+            );
+            match head.is_branch() {
+                true => {
+                    head.resolve()?.set_target(target, &message)?;
+                }
+                false => git_repo.set_head_detached(target)?,
+            }
+            note_local_write(repo.path());
+            return Ok(MergeOutcome::FastForward(target));
+        }
+
+        git_repo.merge(&[&theirs], None, None)?;
+        note_local_write(repo.path());
+        let index = git_repo.index()?;
+        match index.has_conflicts() {
+            true => Ok(MergeOutcome::Conflicted(conflicted_paths(&index)?)),
+            false => Ok(MergeOutcome::Staged),
+        }
+    })
+    .await
+    .map_err(GitError::task_join("merge"))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::RepoBuilder;
+    use git2::RepositoryState;
+
+    #[tokio::test]
+    async fn test_fast_forward_when_behind() {
+        let builder = RepoBuilder::new()
+            .file("a.txt", "a\n")
+            .commit("Initial")
+            .branch("topic")
+            .checkout("topic")
+            .file("b.txt", "b\n")
+            .commit("Add b")
+            .checkout("master");
+        let topic = builder.git2().refname_to_id("refs/heads/topic").unwrap();
+        let (dir, repo) = builder.open().await;
+        let git_repo = repo.open_git2().unwrap();
+
+        let outcome = merge(&repo, "refs/heads/topic".to_string()).await.unwrap();
+        assert_eq!(outcome, MergeOutcome::FastForward(topic));
+        assert_eq!(git_repo.head().unwrap().target(), Some(topic));
+        assert!(dir.path().join("b.txt").exists());
+        let outcome = merge(&repo, "refs/heads/topic".to_string()).await.unwrap();
+        assert_eq!(outcome, MergeOutcome::UpToDate);
+    }
+
+    #[tokio::test]
+    async fn test_diverged_merge_is_committed_from_its_message() {
+        let (dir, repo) = RepoBuilder::new()
+            .file("a.txt", "a\n")
+            .commit("Initial")
+            .branch("topic")
+            .checkout("topic")
+            .file("b.txt", "b\n")
+            .commit("Add b")
+            .checkout("master")
+            .file("c.txt", "c\n")
+            .commit("Add c")
+            .open()
+            .await;
+        let git_repo = repo.open_git2().unwrap();
+
+        let outcome = merge(&repo, "refs/heads/topic".to_string()).await.unwrap();
+        assert_eq!(outcome, MergeOutcome::Staged);
+        assert_eq!(git_repo.state(), RepositoryState::Merge);
+        let message = crate::git::status::commit_template(&repo)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(message, "Merge branch 'topic'");
+        let id = crate::git::status::commit_staged(&repo, message)
+            .await
+            .unwrap();
+        assert_eq!(git_repo.find_commit(id).unwrap().parent_count(), 2);
+        assert_eq!(git_repo.state(), RepositoryState::Clean);
+        assert!(dir.path().join("b.txt").exists());
+    }
+
+    #[tokio::test]
+    async fn test_conflicted_merge_leaves_merge_state() {
+        let (_dir, repo) = RepoBuilder::new()
+            .file("a.txt", "a\n")
+            .commit("Initial")
+            .branch("topic")
+            .checkout("topic")
+            .file("a.txt", "theirs\n")
+            .commit("Change a")
+            .checkout("master")
+            .file("a.txt", "ours\n")
+            .commit("Also change a")
+            .open()
+            .await;
+
+        let outcome = merge(&repo, "refs/heads/topic".to_string()).await.unwrap();
+        assert_eq!(outcome, MergeOutcome::Conflicted(vec!["a.txt".to_string()]));
+        assert_eq!(repo.open_git2().unwrap().state(), RepositoryState::Merge);
+        let refused = merge(&repo, "refs/heads/topic".to_string()).await;
+        assert!(matches!(refused, Err(GitError::CannotMerge(_))));
+    }
+}
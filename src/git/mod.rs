@@ -1,15 +1,69 @@
 // Git operations and repository management
 
+pub mod absorb;
+pub mod blame;
+pub mod changelog;
+pub mod checkout;
+pub mod cherry;
+pub mod cherry_pick;
 pub mod commit;
 pub mod diff;
+pub mod diff_cache;
+pub mod enrich;
 pub mod error;
+pub mod health;
+pub mod hooks;
+pub mod links;
+pub mod lost;
+pub mod maintenance;
+pub mod merge;
+pub mod operation;
+pub mod precommit;
+pub mod pull;
+pub mod refs;
+pub mod remote;
 pub mod repository;
+pub mod reset;
+pub mod revert;
+pub mod split;
+pub mod ssh;
+pub mod stack;
+pub mod stash;
 pub mod status;
 pub mod walker;
+pub mod watch;
 
+pub use absorb::{AbsorbHunk, AbsorbPlan};
+pub use blame::{Blame, BlameLine};
+pub use changelog::{ChangelogSection, ChangelogTemplate, Conventional};
+pub use checkout::CheckoutTarget;
+pub use cherry::CherryMark;
+pub use cherry_pick::{PickConflict, PickReport};
 pub use commit::Commit;
-pub use diff::{Diff, DiffFile, DiffHunk, DiffLine, FileStatus, LineType};
+pub use diff::{Diff, DiffFile, DiffHunk, DiffLine, FileStatus, LineType, SizeLimit};
 pub use error::{GitError, Result};
+pub use health::{
+    BrokenRef, CorruptObject, DanglingObject, HealthReport, MissingObject, StaleLock,
+};
+pub use hooks::{Hook, Hooks};
+pub use links::{IssueLink, LinkPattern};
+pub use lost::{LostCommit, LostSource};
+pub use maintenance::{GitOutput, Maintenance};
+pub use merge::MergeOutcome;
+pub use operation::{Operation, Step};
+pub use precommit::{Finding, PrecommitOptions};
+pub use pull::{PullReport, RebasedCommit};
+pub use refs::{RefEntry, RefInfo, RefKind, RemoteInfo};
+pub use remote::{
+    CredentialPrompt, CredentialRequest, FetchReport, PushOptions, PushReport, PushedRef,
+};
 pub use repository::Repository;
-pub use status::{EntryStatus, Status, StatusEntry};
-pub use walker::CommitWalker;
+pub use reset::ResetMode;
+pub use revert::RevertOutcome;
+pub use split::SplitState;
+pub use ssh::SshDiagnostics;
+pub use stack::{Stack, StackBranch};
+pub use stash::StashEntry;
+pub use status::{EntryStatus, IndexOp, Status, StatusEntry, StatusLoadOptions, UntrackedDir};
+pub use walker::{CommitWalker, RefFilter};
+pub use watch::{RepoChange, RepoSnapshot};
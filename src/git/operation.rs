@@ -0,0 +1,237 @@
+use super::{
+    error::{GitError, Result},
+    repository::Repository,
+    watch::note_local_write,
+};
+use git2::RepositoryState;
+use std::process::Stdio;
+use tokio::process::Command;
+
+/// An operation git stopped in the middle of, e.g. on conflicts, with
+/// `MERGE_HEAD`, `CHERRY_PICK_HEAD`, `REVERT_HEAD` or a rebase directory
+/// in the git directory
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    Merge,
+    CherryPick,
+    Revert,
+    Rebase,
+}
+
+impl Operation {
+    /// The git command that runs the operation, like "cherry-pick"
+    pub fn command(self) -> &'static str {
+        match self {
+            Operation::Merge => "merge",
+            Operation::CherryPick => "cherry-pick",
+            Operation::Revert => "revert",
+            Operation::Rebase => "rebase",
+        }
+    }
+
+    /// Say what is going on, like "merging"
+    pub fn label(self) -> &'static str {
+        match self {
+            Operation::Merge => "merging",
+            Operation::CherryPick => "cherry-picking",
+            Operation::Revert => "reverting",
+            Operation::Rebase => "rebasing",
+        }
+    }
+
+    /// Check whether the commit being applied can be left out; a merge has
+    /// nothing to skip to
+    pub fn can_skip(self) -> bool {
+        self != Operation::Merge
+    }
+}
+
+/// What to do with the operation in progress
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+    /// Commit the resolved changes and carry on with what is left
+    Continue,
+    /// Leave the commit being applied out and carry on
+    Skip,
+    /// Put everything back as it was before the operation started
+    Abort,
+}
+
+impl Step {
+    fn flag(self) -> &'static str {
+        match self {
+            Step::Continue => "--continue",
+            Step::Skip => "--skip",
+            Step::Abort => "--abort",
+        }
+    }
+}
+
+/// Find the operation the repository is in the middle of, if any
+///
+/// Bisects and `git am` sessions aren't operations rust-tig can finish, so
+/// they read as none.
+pub async fn in_progress(repo: &Repository) -> Result<Option<Operation>> {
+    let repo = repo.clone();
+
+    tokio::task::spawn_blocking(move || {
+        let git_repo = repo.open_git2()?;
+        Ok(match git_repo.state() {
+            RepositoryState::Merge => Some(Operation::Merge),
+            RepositoryState::CherryPick | RepositoryState::CherryPickSequence => {
+                Some(Operation::CherryPick)
+            }
+            RepositoryState::Revert | RepositoryState::RevertSequence => Some(Operation::Revert),
+            RepositoryState::Rebase
+            | RepositoryState::RebaseInteractive
+            | RepositoryState::RebaseMerge => Some(Operation::Rebase),
+            _ => None,
+        })
+    })
+    .await
+    .map_err(GitError::task_join("read the operation in progress"))?
+}
+
+/// Continue, skip or abort `operation` through the git command line, like
+/// `git merge --continue`
+///
+/// libgit2 can start these operations but not resume one, and git keeps
+/// what a rebase or a sequence of picks has left to do in files only it
+/// reads, so git finishes them whoever started them. Commits keep the
+/// message git prepared; the editor is never opened.
+pub async fn step(repo: &Repository, operation: Operation, step: Step) -> Result<()> {
+    let command_line = format!("git {} {}", operation.command(), step.flag());
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo.path())
+        .args([operation.command(), step.flag()])
+        .env("GIT_EDITOR", "true")
+        .stdin(Stdio::null())
+        .output()
+        .await?;
+    note_local_write(repo.path());
+
+    match output.status.success() {
+        true => Ok(()),
+        false => {
+            // git explains itself in its last lines, on either stream
+            let text = format!(
+                "{}\n{}",
+                String::from_utf8_lossy(&output.stdout),
+                String::from_utf8_lossy(&output.stderr)
+            );
+            let reason = text
+                .lines()
+                .map(str::trim)
+                .rfind(|line| !line.is_empty())
+                .unwrap_or_default();
+            Err(GitError::StepFailed {
+                command: command_line,
+                message: reason.to_string(),
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::RepoBuilder;
+
+    /// Open a repository with a cherry-pick of a change to a.txt stopped
+    /// on conflicts
+    async fn conflicted_pick() -> (tempfile::TempDir, Repository) {
+        let builder = RepoBuilder::new()
+            .file("a.txt", "a\n")
+            .commit("Initial")
+            .branch("topic")
+            .checkout("topic")
+            .file("a.txt", "theirs\n")
+            .commit("Change a")
+            .checkout("master")
+            .file("a.txt", "ours\n")
+            .commit("Also change a");
+        let pick = builder.git2().refname_to_id("refs/heads/topic").unwrap();
+        let (dir, repo) = builder.open().await;
+        let report = crate::git::cherry_pick::cherry_pick(&repo, vec![pick])
+            .await
+            .unwrap();
+        assert!(report.conflict.is_some());
+        (dir, repo)
+    }
+
+    #[tokio::test]
+    async fn test_continue_commits_the_resolution() {
+        let (dir, repo) = conflicted_pick().await;
+        assert_eq!(
+            in_progress(&repo).await.unwrap(),
+            Some(Operation::CherryPick)
+        );
+
+        let refused = step(&repo, Operation::CherryPick, Step::Continue).await;
+        assert!(
+            matches!(refused, Err(GitError::StepFailed { .. })),
+            "{:?}",
+            refused
+        );
+
+        std::fs::write(dir.path().join("a.txt"), "both\n").unwrap();
+        crate::git::status::stage_file(&repo, "a.txt".to_string())
+            .await
+            .unwrap();
+        step(&repo, Operation::CherryPick, Step::Continue)
+            .await
+            .unwrap();
+        assert_eq!(in_progress(&repo).await.unwrap(), None);
+        let git_repo = repo.open_git2().unwrap();
+        let head = git_repo.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(head.summary(), Some("Change a"));
+        assert_eq!(head.author().name(), Some("Test User"));
+    }
+
+    #[tokio::test]
+    async fn test_skip_and_abort_keep_head() {
+        for action in [Step::Skip, Step::Abort] {
+            let (dir, repo) = conflicted_pick().await;
+            let head = repo.open_git2().unwrap().head().unwrap().target();
+
+            step(&repo, Operation::CherryPick, action).await.unwrap();
+            assert_eq!(in_progress(&repo).await.unwrap(), None);
+            assert_eq!(repo.open_git2().unwrap().head().unwrap().target(), head);
+            assert_eq!(
+                std::fs::read_to_string(dir.path().join("a.txt")).unwrap(),
+                "ours\n"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_merge_in_progress() {
+        let (_dir, repo) = RepoBuilder::new()
+            .file("a.txt", "a\n")
+            .commit("Initial")
+            .branch("topic")
+            .checkout("topic")
+            .file("b.txt", "b\n")
+            .commit("Add b")
+            .checkout("master")
+            .file("c.txt", "c\n")
+            .commit("Add c")
+            .open()
+            .await;
+        crate::git::merge::merge(&repo, "refs/heads/topic".to_string())
+            .await
+            .unwrap();
+        assert_eq!(in_progress(&repo).await.unwrap(), Some(Operation::Merge));
+        assert!(!Operation::Merge.can_skip());
+
+        step(&repo, Operation::Merge, Step::Continue).await.unwrap();
+        let git_repo = repo.open_git2().unwrap();
+        let head = git_repo.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(
+            (head.summary(), head.parent_count()),
+            (Some("Merge branch 'topic'"), 2)
+        );
+        assert_eq!(in_progress(&repo).await.unwrap(), None);
+    }
+}
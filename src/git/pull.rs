@@ -0,0 +1,453 @@
+use super::{
+    error::{GitError, Result},
+    remote::{CredentialPrompt, callbacks, upstream_counts},
+    repository::Repository,
+    ssh::SshDiagnostics,
+    watch::note_local_write,
+};
+use git2::{BranchType, FetchOptions, Oid, Sort, StatusOptions, build::CheckoutBuilder};
+use std::cell::RefCell;
+
+/// A local commit replayed onto the upstream by a rebasing pull
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RebasedCommit {
+    pub old: Oid,
+    /// None when the upstream already had the same change, so the commit
+    /// was dropped
+    pub new: Option<Oid>,
+    pub summary: String,
+}
+
+/// What a pull did to the checked out branch
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PullReport {
+    pub branch: String,
+    /// The upstream's short name, like `origin/main`
+    pub upstream: String,
+    /// Upstream commits the branch didn't have before
+    pub incoming: usize,
+    /// Local commits replayed onto the upstream, oldest first; empty for
+    /// a fast-forward
+    pub rebased: Vec<RebasedCommit>,
+    /// Whether uncommitted changes were stashed around the pull
+    pub stashed: bool,
+    /// Whether the stashed changes clashed with the pulled ones and were
+    /// left in the stash
+    pub stash_kept: bool,
+    /// Commits the branch is ahead of and behind its upstream afterwards
+    pub counts: Option<(usize, usize)>,
+}
+
+impl PullReport {
+    /// Say what happened in one line
+    pub fn summary(&self) -> String {
+        let mut summary = if self.incoming == 0 {
+            format!("{} is up to date with {}", self.branch, self.upstream)
+        } else if self.rebased.is_empty() {
+            format!(
+                "Fast-forwarded {} by {} commits from {}",
+                self.branch, self.incoming, self.upstream
+            )
+        } else {
+            let dropped = self
+                .rebased
+                .iter()
+                .filter(|commit| commit.new.is_none())
+                .count();
+            let mut summary = format!(
+                "Rebased {} commits of {} onto {} ({} new upstream)",
+                self.rebased.len() - dropped,
+                self.branch,
+                self.upstream,
+                self.incoming
+            );
+            if dropped > 0 {
+                summary.push_str(&format!(", dropped {} already upstream", dropped));
+            }
+            summary
+        };
+        if self.stash_kept {
+            summary.push_str("; stashed changes conflict, left in the stash");
+        } else if self.stashed {
+            summary.push_str("; restored stashed changes");
+        }
+        summary
+    }
+}
+
+/// Check whether the pull setting `pull.rebase` asks for a rebase
+///
+/// Values other than false, such as `merges` or `interactive`, still
+/// rebase, just not the way they ask.
+fn rebase_by_default(config: &git2::Config) -> bool {
+    match config.get_string("pull.rebase") {
+        Ok(value) => !matches!(
+            value.to_ascii_lowercase().as_str(),
+            "false" | "no" | "off" | "0" | ""
+        ),
+        Err(_) => false,
+    }
+}
+
+/// Check whether tracked files have uncommitted changes
+pub(super) fn is_dirty(git_repo: &git2::Repository) -> Result<bool> {
+    let mut options = StatusOptions::new();
+    options.include_untracked(false).include_ignored(false);
+    Ok(!git_repo.statuses(Some(&mut options))?.is_empty())
+}
+
+/// Replay the commits in `base..head` onto `onto`, like `git rebase --onto`,
+/// without touching the working tree; fails on merges and conflicts
+pub(super) fn replay(
+    git_repo: &git2::Repository,
+    head: Oid,
+    base: Oid,
+    onto: Oid,
+) -> Result<(Oid, Vec<RebasedCommit>)> {
+    let mut walk = git_repo.revwalk()?;
+    walk.set_sorting(Sort::TOPOLOGICAL | Sort::REVERSE)?;
+    walk.push(head)?;
+    walk.hide(base)?;
+
+    let signature = git_repo.signature()?;
+    let mut tip = git_repo.find_commit(onto)?;
+    let mut rebased = Vec::new();
+    for oid in walk {
+        let pick = git_repo.find_commit(oid?)?;
+        let summary = pick.summary().unwrap_or_default().to_string();
+        if pick.parent_count() != 1 {
+            return Err(GitError::CannotRebase(format!(
+                "{:.7} is a merge; rebase it by hand",
+                pick.id().to_string()
+            )));
+        }
+        let mut index = git_repo.cherrypick_commit(&pick, &tip, 0, None)?;
+        if index.has_conflicts() {
+            let mut paths = Vec::new();
+            for conflict in index.conflicts()? {
+                let conflict = conflict?;
+                if let Some(entry) = conflict.our.or(conflict.their) {
+                    paths.push(String::from_utf8_lossy(&entry.path).into_owned());
+                }
+            }
+            return Err(GitError::MergeConflict { paths });
+        }
+        let tree_id = index.write_tree_to(git_repo)?;
+        // Like git rebase, drop commits whose change is already upstream
+        if tree_id == tip.tree_id() {
+            rebased.push(RebasedCommit {
+                old: pick.id(),
+                new: None,
+                summary,
+            });
+            continue;
+        }
+        let tree = git_repo.find_tree(tree_id)?;
+        let message = pick.message().unwrap_or_default();
+        let id = git_repo.commit(None, &pick.author(), &signature, message, &tree, &[&tip])?;
+        rebased.push(RebasedCommit {
+            old: pick.id(),
+            new: Some(id),
+            summary,
+        });
+        tip = git_repo.find_commit(id)?;
+    }
+    Ok((tip.id(), rebased))
+}
+
+/// Fetch the checked out branch's upstream remote and bring the branch up
+/// to date with its upstream, like `git pull --autostash`
+///
+/// A branch behind its upstream is fast-forwarded. One that has diverged
+/// is rebased onto the upstream when `rebase` says so, or when it's None
+/// and `pull.rebase` is set; otherwise the pull fails. Uncommitted changes
+/// to tracked files are stashed first and applied again afterwards; when
+/// they clash with the pulled changes they stay in the stash. Nothing is
+/// touched when a rebase would conflict.
+pub async fn pull(
+    repo: &Repository,
+    rebase: Option<bool>,
+    prompt: Option<CredentialPrompt>,
+) -> Result<PullReport> {
+    let repo = repo.clone();
+
+    tokio::task::spawn_blocking(move || {
+        let mut git_repo = repo.open_git2()?;
+        let head = git_repo.head()?;
+        if !head.is_branch() {
+            let short_id = head.target().map(|oid| oid.to_string()).unwrap_or_default();
+            return Err(GitError::DetachedHead {
+                short_id: short_id.chars().take(7).collect(),
+            });
+        }
+        let branch = head.shorthand().ok_or(GitError::InvalidUtf8)?.to_string();
+        let refname = head.name().ok_or(GitError::InvalidUtf8)?.to_string();
+        let head_id = head.peel_to_commit()?.id();
+        drop(head);
+
+        let remote_name = git_repo
+            .branch_upstream_remote(&refname)
+            .map_err(|_| GitError::CannotPull(format!("{} has no upstream", branch)))?;
+        let remote_name = remote_name
+            .as_str()
+            .ok_or(GitError::InvalidUtf8)?
+            .to_string();
+        {
+            let config = git_repo.config()?;
+            let mut remote = git_repo.find_remote(&remote_name)?;
+            let url = remote.url().unwrap_or_default().to_string();
+            let diagnostics = RefCell::new(SshDiagnostics::new(&remote_name, &url));
+            let mut options = FetchOptions::new();
+            options.remote_callbacks(callbacks(&config, prompt.as_ref(), &diagnostics));
+            remote
+                .fetch::<&str>(&[], Some(&mut options), None)
+                .map_err(|e| {
+                    GitError::CannotPull(format!(
+                        "fetching {} failed: {}",
+                        remote_name,
+                        e.message()
+                    ))
+                })?;
+        }
+
+        let upstream_branch = git_repo
+            .find_branch(&branch, BranchType::Local)?
+            .upstream()?;
+        let upstream = upstream_branch
+            .name()?
+            .ok_or(GitError::InvalidUtf8)?
+            .to_string();
+        let upstream_id = upstream_branch
+            .get()
+            .target()
+            .ok_or_else(|| GitError::RefNotFound(upstream.clone()))?;
+        drop(upstream_branch);
+
+        let (ahead, behind) = git_repo.graph_ahead_behind(head_id, upstream_id)?;
+        let mut report = PullReport {
+            branch: branch.clone(),
+            upstream: upstream.clone(),
+            incoming: behind,
+            rebased: Vec::new(),
+            stashed: false,
+            stash_kept: false,
+            counts: Some((ahead, behind)),
+        };
+        if behind == 0 {
+            return Ok(report);
+        }
+
+        let new_tip = match ahead {
+            0 => upstream_id,
+            _ if rebase.unwrap_or_else(|| {
+                git_repo
+                    .config()
+                    .is_ok_and(|config| rebase_by_default(&config))
+            }) =>
+            {
+                let (tip, rebased) = replay(&git_repo, head_id, upstream_id, upstream_id)?;
+                report.rebased = rebased;
+                tip
+            }
+            _ => {
+                return Err(GitError::CannotPull(format!(
+                    "{} and {} have diverged ({} and {} commits); pull with --rebase",
+                    branch, upstream, ahead, behind
+                )));
+            }
+        };
+
+        if is_dirty(&git_repo)? {
+            let signature = git_repo.signature()?;
+            git_repo.stash_save(&signature, "rust-tig: autostash before pull", None)?;
+            report.stashed = true;
+        }
+
+        let message = match report.rebased.is_empty() {
+            true => format!("pull: fast-forward to {}", upstream),
+            false => format!("pull --rebase: onto {}", upstream),
+        };
+        git_repo.checkout_tree(
+            &git_repo.find_object(new_tip, None)?,
+            Some(CheckoutBuilder::new().safe()),
+        )?;
+        git_repo.reference(&refname, new_tip, true, &message)?;
+
+        if report.stashed && git_repo.stash_pop(0, None).is_err() {
+            // A failed pop keeps the stash entry; put the tree back as pulled
+            git_repo.checkout_head(Some(CheckoutBuilder::new().force()))?;
+            report.stash_kept = true;
+        }
+        report.counts = upstream_counts(&git_repo)?;
+        note_local_write(repo.path());
+        Ok(report)
+    })
+    .await
+    .map_err(GitError::task_join("pull"))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::remote::{push, push_defaults};
+    use crate::testing::RepoBuilder;
+
+    /// Open a repository whose branch tracks a bare `origin`, plus the
+    /// bare repository to commit "their" changes to
+    async fn cloned() -> (
+        tempfile::TempDir,
+        tempfile::TempDir,
+        Repository,
+        git2::Repository,
+    ) {
+        let origin_dir = tempfile::TempDir::new().unwrap();
+        let origin = git2::Repository::init_bare(origin_dir.path()).unwrap();
+        let (temp_dir, repo) = RepoBuilder::new()
+            .file("a.txt", "one\ntwo\nthree\n")
+            .commit("Initial")
+            .open()
+            .await;
+        repo.open_git2()
+            .unwrap()
+            .remote("origin", origin_dir.path().to_str().unwrap())
+            .unwrap();
+        push(&repo, push_defaults(&repo).await.unwrap(), None)
+            .await
+            .unwrap();
+        (origin_dir, temp_dir, repo, origin)
+    }
+
+    /// Commit a file on the bare repository's only branch
+    fn commit_theirs(origin: &git2::Repository, path: &str, content: &str) -> Oid {
+        let branch = origin.branches(None).unwrap().next().unwrap().unwrap().0;
+        let refname = branch.get().name().unwrap().to_string();
+        let parent = branch.get().peel_to_commit().unwrap();
+        let mut builder = origin.treebuilder(Some(&parent.tree().unwrap())).unwrap();
+        builder
+            .insert(path, origin.blob(content.as_bytes()).unwrap(), 0o100644)
+            .unwrap();
+        let tree = origin.find_tree(builder.write().unwrap()).unwrap();
+        let signature = git2::Signature::now("Other", "other@example.com").unwrap();
+        origin
+            .commit(
+                Some(&refname),
+                &signature,
+                &signature,
+                "Theirs",
+                &tree,
+                &[&parent],
+            )
+            .unwrap()
+    }
+
+    /// Commit a file in the local repository
+    fn commit_ours(repo: &Repository, path: &str, content: &str) {
+        std::fs::write(repo.path().join(path), content).unwrap();
+        let git_repo = repo.open_git2().unwrap();
+        let mut index = git_repo.index().unwrap();
+        index.add_path(std::path::Path::new(path)).unwrap();
+        index.write().unwrap();
+        let tree = git_repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let parent = git_repo.head().unwrap().peel_to_commit().unwrap();
+        let signature = git_repo.signature().unwrap();
+        git_repo
+            .commit(
+                Some("HEAD"),
+                &signature,
+                &signature,
+                "Ours",
+                &tree,
+                &[&parent],
+            )
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_fast_forward_with_autostash() {
+        let (_origin_dir, temp_dir, repo, origin) = cloned().await;
+        let theirs = commit_theirs(&origin, "b.txt", "b\n");
+        std::fs::write(temp_dir.path().join("a.txt"), "one\ntwo\nthree\nfour\n").unwrap();
+
+        let report = pull(&repo, None, None).await.unwrap();
+        assert_eq!(report.incoming, 1);
+        assert!(report.rebased.is_empty());
+        assert!(report.stashed && !report.stash_kept);
+        assert!(
+            report.summary().starts_with("Fast-forwarded"),
+            "{}",
+            report.summary()
+        );
+
+        let git_repo = repo.open_git2().unwrap();
+        assert_eq!(git_repo.head().unwrap().target(), Some(theirs));
+        assert!(temp_dir.path().join("b.txt").exists());
+        assert_eq!(
+            std::fs::read_to_string(temp_dir.path().join("a.txt")).unwrap(),
+            "one\ntwo\nthree\nfour\n"
+        );
+        assert_eq!(report.counts, Some((0, 0)));
+
+        let report = pull(&repo, None, None).await.unwrap();
+        assert!(report.summary().contains("up to date"));
+    }
+
+    #[tokio::test]
+    async fn test_diverged_branch_rebases_when_asked() {
+        let (_origin_dir, _temp_dir, repo, origin) = cloned().await;
+        let theirs = commit_theirs(&origin, "b.txt", "b\n");
+        commit_ours(&repo, "c.txt", "c\n");
+
+        let error = pull(&repo, None, None).await.unwrap_err();
+        assert!(error.to_string().contains("diverged"), "{}", error);
+
+        let report = pull(&repo, Some(true), None).await.unwrap();
+        assert_eq!(report.rebased.len(), 1);
+        assert_eq!(report.rebased[0].summary, "Ours");
+        assert_eq!(report.counts, Some((1, 0)));
+        let git_repo = repo.open_git2().unwrap();
+        let head = git_repo.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(Some(head.id()), report.rebased[0].new);
+        assert_eq!(head.parent_id(0).unwrap(), theirs);
+    }
+
+    #[tokio::test]
+    async fn test_pull_rebase_config_and_dropped_commits() {
+        let (_origin_dir, _temp_dir, repo, origin) = cloned().await;
+        repo.open_git2()
+            .unwrap()
+            .config()
+            .unwrap()
+            .set_str("pull.rebase", "true")
+            .unwrap();
+        // The same change on both sides, then one more upstream
+        commit_theirs(&origin, "b.txt", "b\n");
+        commit_theirs(&origin, "d.txt", "d\n");
+        commit_ours(&repo, "b.txt", "b\n");
+
+        let report = pull(&repo, None, None).await.unwrap();
+        assert_eq!(report.rebased.len(), 1);
+        assert_eq!(report.rebased[0].new, None);
+        assert!(
+            report.summary().contains("dropped 1 already upstream"),
+            "{}",
+            report.summary()
+        );
+        assert_eq!(report.counts, Some((0, 0)));
+    }
+
+    #[tokio::test]
+    async fn test_conflicting_rebase_touches_nothing() {
+        let (_origin_dir, _temp_dir, repo, origin) = cloned().await;
+        commit_theirs(&origin, "a.txt", "ONE\n");
+        commit_ours(&repo, "a.txt", "uno\n");
+        let before = repo.open_git2().unwrap().head().unwrap().target();
+
+        let error = pull(&repo, Some(true), None).await.unwrap_err();
+        assert!(
+            matches!(&error, GitError::MergeConflict { paths } if paths == &["a.txt"]),
+            "{}",
+            error
+        );
+        assert_eq!(repo.open_git2().unwrap().head().unwrap().target(), before);
+    }
+}
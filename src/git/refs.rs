@@ -0,0 +1,552 @@
+use super::{commit::Commit, error::Result, repository::Repository};
+use git2::{DescribeFormatOptions, DescribeOptions, ErrorCode, Oid, ReferenceType};
+use std::collections::HashMap;
+
+/// Kind of reference
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RefKind {
+    LocalBranch,
+    RemoteBranch,
+    Tag,
+    Other,
+}
+
+impl RefKind {
+    /// Classify a full reference name
+    pub fn from_name(name: &str) -> Self {
+        if name.starts_with("refs/heads/") {
+            RefKind::LocalBranch
+        } else if name.starts_with("refs/remotes/") {
+            RefKind::RemoteBranch
+        } else if name.starts_with("refs/tags/") {
+            RefKind::Tag
+        } else {
+            RefKind::Other
+        }
+    }
+
+    /// Get a short label for the kind
+    pub fn label(&self) -> &'static str {
+        match self {
+            RefKind::LocalBranch => "branch",
+            RefKind::RemoteBranch => "remote",
+            RefKind::Tag => "tag",
+            RefKind::Other => "ref",
+        }
+    }
+}
+
+/// A reference pointing at a commit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RefInfo {
+    /// Full name, e.g. "refs/remotes/origin/main"
+    pub name: String,
+    /// Short name, e.g. "origin/main"
+    pub shorthand: String,
+    pub kind: RefKind,
+}
+
+/// A reference together with the commit it resolves to
+#[derive(Debug, Clone)]
+pub struct RefEntry {
+    pub info: RefInfo,
+    pub commit: Commit,
+    /// Commits reachable from the ref but not from HEAD
+    pub ahead: usize,
+    /// Commits reachable from HEAD but not from the ref
+    pub behind: usize,
+    /// Newest commit both the ref and HEAD reach
+    pub merge_base: Option<Oid>,
+    /// For remote-tracking branches, the remote whose fetch refspecs
+    /// write the ref
+    pub remote: Option<String>,
+}
+
+/// A configured remote
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteInfo {
+    pub name: String,
+    pub url: String,
+    /// Fetch refspecs, like `+refs/heads/*:refs/remotes/origin/*`
+    pub fetch_refspecs: Vec<String>,
+}
+
+/// Find the remote whose fetch refspecs map onto the ref `name`
+fn owning_remote(remotes: &[git2::Remote], name: &str) -> Option<String> {
+    remotes
+        .iter()
+        .find(|remote| {
+            remote
+                .refspecs()
+                .any(|spec| spec.direction() == git2::Direction::Fetch && spec.dst_matches(name))
+        })
+        .and_then(|remote| remote.name().map(str::to_string))
+}
+
+/// Load the configured remotes, sorted by name
+pub async fn load_remotes(repo: &Repository) -> Result<Vec<RemoteInfo>> {
+    let repo_path = repo.path().to_path_buf();
+
+    tokio::task::spawn_blocking(move || {
+        let git_repo = git2::Repository::open(repo_path)?;
+        let mut remotes = Vec::new();
+        for name in git_repo.remotes()?.iter().flatten() {
+            let remote = git_repo.find_remote(name)?;
+            let fetch_refspecs = remote
+                .fetch_refspecs()?
+                .iter()
+                .flatten()
+                .map(str::to_string)
+                .collect();
+            remotes.push(RemoteInfo {
+                name: name.to_string(),
+                url: remote.url().unwrap_or_default().to_string(),
+                fetch_refspecs,
+            });
+        }
+        remotes.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(remotes)
+    })
+    .await
+    .map_err(super::error::GitError::task_join("load remotes"))?
+}
+
+/// Load all branches, remote branches and tags with their commits
+pub async fn load_refs(repo: &Repository) -> Result<Vec<RefEntry>> {
+    let repo_path = repo.path().to_path_buf();
+
+    tokio::task::spawn_blocking(move || {
+        let git_repo = git2::Repository::open(repo_path)?;
+        let head = git_repo.head().ok().and_then(|h| h.target());
+        let remote_names = git_repo.remotes()?;
+        let remotes: Vec<git2::Remote> = remote_names
+            .iter()
+            .flatten()
+            .filter_map(|name| git_repo.find_remote(name).ok())
+            .collect();
+        let mut entries = Vec::new();
+
+        for reference in git_repo.references()?.flatten() {
+            // Symbolic refs (e.g. origin/HEAD) duplicate the ref they point at
+            if reference.kind() == Some(ReferenceType::Symbolic) {
+                continue;
+            }
+            let Some(name) = reference.name() else {
+                continue;
+            };
+            let Ok(commit) = reference.peel_to_commit() else {
+                continue;
+            };
+
+            let (ahead, behind) = match head {
+                Some(head) => git_repo.graph_ahead_behind(commit.id(), head)?,
+                None => (0, 0),
+            };
+            // Unrelated histories have no merge base
+            let merge_base = head.and_then(|head| git_repo.merge_base(commit.id(), head).ok());
+
+            let kind = RefKind::from_name(name);
+            entries.push(RefEntry {
+                info: RefInfo {
+                    name: name.to_string(),
+                    shorthand: reference.shorthand().unwrap_or(name).to_string(),
+                    kind,
+                },
+                commit: Commit::from_git2(&commit)?,
+                ahead,
+                behind,
+                merge_base,
+                remote: match kind {
+                    RefKind::RemoteBranch => owning_remote(&remotes, name),
+                    _ => None,
+                },
+            });
+        }
+
+        Ok(entries)
+    })
+    .await
+    .map_err(super::error::GitError::task_join("load refs"))?
+}
+
+/// Load every reference that resolves to a commit, including annotated tags
+pub async fn load_refs_for_commit(repo: &Repository, commit_id: Oid) -> Result<Vec<RefInfo>> {
+    let repo_path = repo.path().to_path_buf();
+
+    tokio::task::spawn_blocking(move || {
+        let git_repo = git2::Repository::open(repo_path)?;
+        let mut refs = Vec::new();
+
+        for reference in git_repo.references()?.flatten() {
+            let Some(name) = reference.name() else {
+                continue;
+            };
+            // Peeling follows annotated tags through to their commit
+            let Ok(commit) = reference.peel_to_commit() else {
+                continue;
+            };
+            if commit.id() != commit_id {
+                continue;
+            }
+
+            refs.push(RefInfo {
+                name: name.to_string(),
+                shorthand: reference.shorthand().unwrap_or(name).to_string(),
+                kind: RefKind::from_name(name),
+            });
+        }
+
+        refs.sort_by(|a, b| a.kind.cmp(&b.kind).then_with(|| a.name.cmp(&b.name)));
+        Ok(refs)
+    })
+    .await
+    .map_err(super::error::GitError::task_join("load refs for a commit"))?
+}
+
+/// Load every branch and tag that contains a commit, i.e. points at it or
+/// at one of its descendants
+pub async fn load_refs_containing(repo: &Repository, commit_id: Oid) -> Result<Vec<RefInfo>> {
+    let repo_path = repo.path().to_path_buf();
+
+    tokio::task::spawn_blocking(move || {
+        let git_repo = git2::Repository::open(repo_path)?;
+        let mut contains: HashMap<Oid, bool> = HashMap::new();
+        let mut refs = Vec::new();
+
+        for reference in git_repo.references()?.flatten() {
+            if reference.kind() == Some(ReferenceType::Symbolic) {
+                continue;
+            }
+            let Some(name) = reference.name() else {
+                continue;
+            };
+            let kind = RefKind::from_name(name);
+            if kind == RefKind::Other {
+                continue;
+            }
+            let Ok(commit) = reference.peel_to_commit() else {
+                continue;
+            };
+
+            // Many refs share a tip, so each tip is only checked once
+            let tip = commit.id();
+            let contained = match contains.get(&tip) {
+                Some(&contained) => contained,
+                None => {
+                    let contained =
+                        tip == commit_id || git_repo.graph_descendant_of(tip, commit_id)?;
+                    contains.insert(tip, contained);
+                    contained
+                }
+            };
+            if contained {
+                refs.push(RefInfo {
+                    name: name.to_string(),
+                    shorthand: reference.shorthand().unwrap_or(name).to_string(),
+                    kind,
+                });
+            }
+        }
+
+        refs.sort_by(|a, b| a.kind.cmp(&b.kind).then_with(|| a.name.cmp(&b.name)));
+        Ok(refs)
+    })
+    .await
+    .map_err(super::error::GitError::task_join(
+        "find refs containing a commit",
+    ))?
+}
+
+/// Look up the commit a revision such as "HEAD~3" or a tag name points at
+pub async fn resolve_commit(repo: &Repository, revision: String) -> Result<Commit> {
+    let repo_path = repo.path().to_path_buf();
+
+    tokio::task::spawn_blocking(move || {
+        let git_repo = git2::Repository::open(repo_path)?;
+        let commit = git_repo.revparse_single(&revision)?.peel_to_commit()?;
+        Commit::from_git2(&commit)
+    })
+    .await
+    .map_err(super::error::GitError::task_join("resolve a revision"))?
+}
+
+/// Find the newest common ancestor of two revisions, such as branch names,
+/// tags or commit hashes
+pub async fn merge_base(repo: &Repository, first: String, second: String) -> Result<Oid> {
+    let repo_path = repo.path().to_path_buf();
+
+    tokio::task::spawn_blocking(move || {
+        let git_repo = git2::Repository::open(repo_path)?;
+        let first = git_repo.revparse_single(&first)?.peel_to_commit()?.id();
+        let second = git_repo.revparse_single(&second)?.peel_to_commit()?.id();
+        Ok(git_repo.merge_base(first, second)?)
+    })
+    .await
+    .map_err(super::error::GitError::task_join("find the merge base"))?
+}
+
+/// Name commits after their nearest tag, like `git describe --tags`
+///
+/// Commits no tag can reach describe as `None`.
+pub async fn describe_commits(
+    repo: &Repository,
+    commit_ids: Vec<Oid>,
+) -> Result<Vec<(Oid, Option<String>)>> {
+    let repo_path = repo.path().to_path_buf();
+
+    tokio::task::spawn_blocking(move || {
+        let git_repo = git2::Repository::open(repo_path)?;
+
+        // Without tags every describe would walk the whole history for nothing
+        if git_repo.tag_names(None)?.is_empty() {
+            return Ok(commit_ids.into_iter().map(|id| (id, None)).collect());
+        }
+
+        let mut options = DescribeOptions::new();
+        options.describe_tags();
+        let mut format = DescribeFormatOptions::new();
+        format.abbreviated_size(7);
+
+        let mut described = Vec::with_capacity(commit_ids.len());
+        for id in commit_ids {
+            let commit = git_repo.find_commit(id)?;
+            let name = match commit.as_object().describe(&options) {
+                Ok(describe) => Some(describe.format(Some(&format))?),
+                Err(e) if e.code() == ErrorCode::NotFound => None,
+                Err(e) => return Err(e.into()),
+            };
+            described.push((id, name));
+        }
+        Ok(described)
+    })
+    .await
+    .map_err(super::error::GitError::task_join("describe commits"))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::RepoBuilder;
+
+    #[tokio::test]
+    async fn test_load_refs_for_commit() {
+        let builder = RepoBuilder::new().commit("Initial").tag("light");
+        let commit_id = builder.head();
+
+        // Annotated tags and remote branches have no builder step
+        let git_repo = builder.git2();
+        let sig = git2::Signature::now("Test", "test@example.com").unwrap();
+        let commit = git_repo.find_commit(commit_id).unwrap();
+        git_repo
+            .tag("v1.0", commit.as_object(), &sig, "Release", false)
+            .unwrap();
+        git_repo
+            .reference("refs/remotes/origin/main", commit_id, false, "test")
+            .unwrap();
+        drop(commit);
+
+        let (_temp_dir, repo) = builder.open().await;
+        let refs = load_refs_for_commit(&repo, commit_id).await.unwrap();
+        let names: Vec<&str> = refs.iter().map(|r| r.name.as_str()).collect();
+
+        assert_eq!(refs[0].kind, RefKind::LocalBranch);
+        assert!(names.contains(&"refs/remotes/origin/main"));
+        assert!(names.contains(&"refs/tags/v1.0"));
+        assert!(names.contains(&"refs/tags/light"));
+        assert_eq!(refs.last().unwrap().kind, RefKind::Tag);
+    }
+
+    #[tokio::test]
+    async fn test_load_refs_ahead_behind() {
+        let builder = RepoBuilder::new().commit("Initial").branch("feature");
+        let default_branch = builder
+            .git2()
+            .head()
+            .unwrap()
+            .shorthand()
+            .unwrap()
+            .to_string();
+        let (_temp_dir, repo) = builder
+            .checkout("feature")
+            .commit("Feature")
+            .checkout(&default_branch)
+            .open()
+            .await;
+        let refs = load_refs(&repo).await.unwrap();
+        let feature = refs.iter().find(|r| r.info.shorthand == "feature").unwrap();
+
+        assert_eq!(feature.info.kind, RefKind::LocalBranch);
+        assert_eq!(feature.commit.summary, "Feature");
+        assert_eq!((feature.ahead, feature.behind), (1, 0));
+        let base = refs
+            .iter()
+            .find(|r| r.info.shorthand == default_branch)
+            .unwrap();
+        assert_eq!(feature.merge_base, Some(base.commit.id));
+    }
+
+    #[tokio::test]
+    async fn test_remote_branches_belong_to_their_refspec() {
+        let (_temp_dir, repo) = RepoBuilder::new().commit("Initial").open().await;
+        let git_repo = repo.open_git2().unwrap();
+        git_repo.remote("origin", "/srv/origin.git").unwrap();
+        // A remote whose name has a slash, fetched somewhere unusual
+        git_repo
+            .remote_with_fetch(
+                "team/shared",
+                "/srv/shared.git",
+                "+refs/heads/*:refs/remotes/shared/*",
+            )
+            .unwrap();
+        let head = git_repo.head().unwrap().target().unwrap();
+        git_repo
+            .reference("refs/remotes/origin/main", head, false, "test")
+            .unwrap();
+        git_repo
+            .reference("refs/remotes/shared/topic", head, false, "test")
+            .unwrap();
+        git_repo
+            .reference("refs/remotes/stale/old", head, false, "test")
+            .unwrap();
+
+        let refs = load_refs(&repo).await.unwrap();
+        let remote_of = |name: &str| {
+            refs.iter()
+                .find(|r| r.info.name == name)
+                .unwrap()
+                .remote
+                .clone()
+        };
+        assert_eq!(
+            remote_of("refs/remotes/origin/main").as_deref(),
+            Some("origin")
+        );
+        assert_eq!(
+            remote_of("refs/remotes/shared/topic").as_deref(),
+            Some("team/shared")
+        );
+        assert_eq!(remote_of("refs/remotes/stale/old"), None);
+
+        let remotes = load_remotes(&repo).await.unwrap();
+        assert_eq!(remotes[0].name, "origin");
+        assert_eq!(
+            remotes[1].fetch_refspecs,
+            ["+refs/heads/*:refs/remotes/shared/*"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_resolve_commit() {
+        let builder = RepoBuilder::new()
+            .commit("First")
+            .tag("v1")
+            .commit("Second");
+        let (_temp_dir, repo) = builder.open().await;
+
+        let commit = resolve_commit(&repo, "HEAD~1".to_string()).await.unwrap();
+        assert_eq!(commit.summary, "First");
+        let commit = resolve_commit(&repo, "v1".to_string()).await.unwrap();
+        assert_eq!(commit.summary, "First");
+        assert!(resolve_commit(&repo, "nope".to_string()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_merge_base_of_diverged_branches() {
+        let builder = RepoBuilder::new()
+            .commit("Base")
+            .branch("left")
+            .branch("right");
+        let base = builder.head();
+        let (_temp_dir, repo) = builder
+            .checkout("left")
+            .commit("Left")
+            .checkout("right")
+            .commit("Right")
+            .open()
+            .await;
+
+        let found = merge_base(&repo, "left".to_string(), "right".to_string())
+            .await
+            .unwrap();
+        assert_eq!(found, base);
+        let found = merge_base(&repo, "left".to_string(), base.to_string())
+            .await
+            .unwrap();
+        assert_eq!(found, base);
+        assert!(
+            merge_base(&repo, "left".to_string(), "nope".to_string())
+                .await
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_ref_kind_from_name() {
+        assert_eq!(RefKind::from_name("refs/heads/main"), RefKind::LocalBranch);
+        assert_eq!(
+            RefKind::from_name("refs/remotes/origin/main"),
+            RefKind::RemoteBranch
+        );
+        assert_eq!(RefKind::from_name("refs/tags/v1"), RefKind::Tag);
+        assert_eq!(RefKind::from_name("refs/stash"), RefKind::Other);
+    }
+
+    #[tokio::test]
+    async fn test_describe_commits_by_nearest_tag() {
+        let builder = RepoBuilder::new().commit("Untagged");
+        let untagged = builder.head();
+        let builder = builder.commit("Release").tag("v1.0");
+        let tagged = builder.head();
+        let builder = builder.commit("After release");
+        let after = builder.head();
+        let (_temp_dir, repo) = builder.open().await;
+
+        let described = describe_commits(&repo, vec![tagged, after, untagged])
+            .await
+            .unwrap();
+        assert_eq!(described[0], (tagged, Some("v1.0".to_string())));
+        let after_name = described[1].1.as_deref().unwrap();
+        assert!(after_name.starts_with("v1.0-1-g"), "{}", after_name);
+        assert_eq!(described[2], (untagged, None));
+    }
+
+    #[tokio::test]
+    async fn test_describe_without_tags() {
+        let builder = RepoBuilder::new().commit("Initial");
+        let head = builder.head();
+        let (_temp_dir, repo) = builder.open().await;
+
+        assert_eq!(
+            describe_commits(&repo, vec![head]).await.unwrap(),
+            [(head, None)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_load_refs_containing() {
+        let builder = RepoBuilder::new().commit("Base");
+        let base = builder.head();
+        let builder = builder
+            .branch("trunk")
+            .branch("feature")
+            .checkout("feature")
+            .commit("Feature work")
+            .tag("v1.0")
+            .checkout("trunk")
+            .commit("Trunk work");
+        let trunk_tip = builder.head();
+        let (_temp_dir, repo) = builder.open().await;
+
+        let names =
+            |refs: Vec<RefInfo>| -> Vec<String> { refs.into_iter().map(|r| r.shorthand).collect() };
+        // The initial branch stays at the base commit too
+        let containing_base = names(load_refs_containing(&repo, base).await.unwrap());
+        assert_eq!(containing_base.len(), 4);
+        assert!(containing_base.iter().any(|name| name == "feature"));
+        assert!(containing_base.iter().any(|name| name == "trunk"));
+        assert_eq!(containing_base.last().unwrap(), "v1.0");
+
+        let containing_trunk = names(load_refs_containing(&repo, trunk_tip).await.unwrap());
+        assert_eq!(containing_trunk, ["trunk"]);
+    }
+}
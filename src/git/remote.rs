@@ -0,0 +1,848 @@
+use super::{
+    error::{GitError, Result},
+    repository::Repository,
+    ssh::{self, SshDiagnostics},
+};
+use git2::{
+    CertificateCheckStatus, Cred, CredentialType, Direction, FetchOptions, FetchPrune, Oid,
+    PushOptions as Git2PushOptions, RemoteCallbacks,
+};
+use std::cell::RefCell;
+use std::sync::mpsc as std_mpsc;
+use tokio::sync::mpsc;
+
+/// How a round of fetches went
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FetchReport {
+    /// Number of remotes fetched from
+    pub remotes: usize,
+    /// `<remote>: <error>` for each remote that couldn't be fetched
+    pub failures: Vec<String>,
+    /// Whether any remote-tracking ref moved, appeared or was pruned
+    pub refs_changed: bool,
+    /// Commits the checked out branch is ahead of and behind its upstream
+    pub upstream: Option<(usize, usize)>,
+    /// What happened on each SSH remote that failed
+    pub ssh_diagnostics: Vec<SshDiagnostics>,
+}
+
+/// A remote asking for a username and password, or a token in place of
+/// the password, that no credential helper could supply
+#[derive(Debug)]
+pub struct CredentialRequest {
+    pub url: String,
+    /// Username from the URL, if it has one
+    pub username: Option<String>,
+    reply: std_mpsc::Sender<Option<(String, String)>>,
+}
+
+impl CredentialRequest {
+    /// Ask for credentials for `url`, the answer going to `reply`
+    pub(crate) fn new(
+        url: String,
+        username: Option<String>,
+        reply: std_mpsc::Sender<Option<(String, String)>>,
+    ) -> Self {
+        Self {
+            url,
+            username,
+            reply,
+        }
+    }
+
+    /// Send the username and password back to the waiting fetch
+    pub fn answer(self, username: String, password: String) {
+        let _ = self.reply.send(Some((username, password)));
+    }
+
+    /// Let the fetch fail instead
+    pub fn cancel(self) {
+        let _ = self.reply.send(None);
+    }
+}
+
+/// Where credential requests go to be put to the user
+pub type CredentialPrompt = mpsc::UnboundedSender<CredentialRequest>;
+
+/// Ask the user through `prompt`, blocking the fetch until they answer
+fn ask(
+    prompt: &CredentialPrompt,
+    url: &str,
+    username: Option<&str>,
+) -> std::result::Result<Cred, git2::Error> {
+    let (reply, answer) = std_mpsc::channel();
+    let request = CredentialRequest::new(url.to_string(), username.map(str::to_string), reply);
+    let cancelled = || git2::Error::from_str("authentication cancelled");
+    prompt.send(request).map_err(|_| cancelled())?;
+    match answer.recv() {
+        Ok(Some((username, password))) => Cred::userpass_plaintext(&username, &password),
+        _ => Err(cancelled()),
+    }
+}
+
+/// Answer credential requests from the ssh agent or the configured
+/// credential helper, then, given a prompt, by asking the user
+///
+/// Keys offered and the host key seen go into `diagnostics`; host keys are
+/// still checked against known_hosts by libgit2.
+pub(super) fn callbacks<'a>(
+    config: &'a git2::Config,
+    prompt: Option<&'a CredentialPrompt>,
+    diagnostics: &'a RefCell<SshDiagnostics>,
+) -> RemoteCallbacks<'a> {
+    let mut attempts = 0;
+    let mut callbacks = RemoteCallbacks::new();
+    callbacks.certificate_check(|cert, host| {
+        if let Some(hostkey) = cert.as_hostkey() {
+            diagnostics.borrow_mut().record_host_key(host, hostkey);
+        }
+        Ok(CertificateCheckStatus::CertificatePassthrough)
+    });
+    callbacks.credentials(move |url, username, allowed| {
+        // libgit2 asks again after each rejected credential
+        attempts += 1;
+        if attempts > 3 {
+            return Err(git2::Error::from_str("no usable credentials"));
+        }
+        if allowed.contains(CredentialType::SSH_KEY) {
+            let username = username.unwrap_or("git");
+            let mut diagnostics = diagnostics.borrow_mut();
+            diagnostics.username = Some(username.to_string());
+            diagnostics.key_offers += 1;
+            return Cred::ssh_key_from_agent(username);
+        }
+        if allowed.contains(CredentialType::USER_PASS_PLAINTEXT) {
+            // The helper gets the first try; a rejected answer comes back
+            // here, and so goes to the user
+            let helped = match attempts {
+                1 => Cred::credential_helper(config, url, username).ok(),
+                _ => None,
+            };
+            return match (helped, prompt) {
+                (Some(cred), _) => Ok(cred),
+                (None, Some(prompt)) => ask(prompt, url, username),
+                (None, None) => Err(git2::Error::from_str("no credential helper answered")),
+            };
+        }
+        Cred::default()
+    });
+    callbacks
+}
+
+/// Get the remote-tracking refs and where they point
+fn remote_refs(git_repo: &git2::Repository) -> Result<Vec<(String, Option<git2::Oid>)>> {
+    let mut refs = Vec::new();
+    for reference in git_repo.references_glob("refs/remotes/*")? {
+        let reference = reference?;
+        refs.push((
+            reference.name().unwrap_or_default().to_string(),
+            reference.target(),
+        ));
+    }
+    refs.sort();
+    Ok(refs)
+}
+
+/// Count the commits the checked out branch is ahead of and behind its
+/// upstream, or None without an upstream
+pub(super) fn upstream_counts(git_repo: &git2::Repository) -> Result<Option<(usize, usize)>> {
+    let head = match git_repo.head() {
+        Ok(head) if head.is_branch() => head,
+        _ => return Ok(None),
+    };
+    let Some(name) = head.name() else {
+        return Ok(None);
+    };
+    let upstream = match git_repo.branch_upstream_name(name) {
+        Ok(upstream) => upstream,
+        Err(e) if e.code() == git2::ErrorCode::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let upstream = git_repo.find_reference(upstream.as_str().unwrap_or_default())?;
+    match (head.target(), upstream.target()) {
+        (Some(local), Some(remote)) => Ok(Some(git_repo.graph_ahead_behind(local, remote)?)),
+        _ => Ok(None),
+    }
+}
+
+/// Get how far the checked out branch is ahead of and behind its upstream
+pub async fn upstream_ahead_behind(repo: &Repository) -> Result<Option<(usize, usize)>> {
+    let repo = repo.clone();
+
+    tokio::task::spawn_blocking(move || upstream_counts(&repo.open_git2()?))
+        .await
+        .map_err(GitError::task_join("compare with the upstream"))?
+}
+
+/// Fetch every remote with its configured refspecs, like `git fetch --all`,
+/// optionally pruning remote-tracking branches gone from the remote
+///
+/// A remote that can't be reached is reported in the result rather than
+/// stopping the others. With a `prompt`, credentials no helper supplies are
+/// asked for there; without one the remote fails.
+pub async fn fetch_all(
+    repo: &Repository,
+    prune: bool,
+    prompt: Option<CredentialPrompt>,
+) -> Result<FetchReport> {
+    let repo = repo.clone();
+
+    tokio::task::spawn_blocking(move || {
+        let git_repo = repo.open_git2()?;
+        let names = git_repo.remotes()?;
+        let names: Vec<&str> = names.iter().flatten().collect();
+        fetch_remotes(&git_repo, &names, prune, prompt)
+    })
+    .await
+    .map_err(GitError::task_join("fetch the remotes"))?
+}
+
+/// Fetch one remote, like `git fetch <remote>`, reporting as `fetch_all`
+/// does
+pub async fn fetch_remote(
+    repo: &Repository,
+    name: String,
+    prune: bool,
+    prompt: Option<CredentialPrompt>,
+) -> Result<FetchReport> {
+    let repo = repo.clone();
+
+    tokio::task::spawn_blocking(move || fetch_remotes(&repo.open_git2()?, &[&name], prune, prompt))
+        .await
+        .map_err(GitError::task_join("fetch a remote"))?
+}
+
+/// Fetch the named remotes one after another
+fn fetch_remotes(
+    git_repo: &git2::Repository,
+    names: &[&str],
+    prune: bool,
+    prompt: Option<CredentialPrompt>,
+) -> Result<FetchReport> {
+    let config = git_repo.config()?;
+    let before = remote_refs(git_repo)?;
+
+    let mut failures = Vec::new();
+    let mut ssh_diagnostics = Vec::new();
+    for &name in names {
+        let mut remote = match git_repo.find_remote(name) {
+            Ok(remote) => remote,
+            Err(e) => {
+                failures.push(format!("{}: {}", name, e.message()));
+                continue;
+            }
+        };
+        let url = remote.url().unwrap_or_default().to_string();
+        let diagnostics = RefCell::new(SshDiagnostics::new(name, &url));
+        let mut options = FetchOptions::new();
+        options.remote_callbacks(callbacks(&config, prompt.as_ref(), &diagnostics));
+        options.prune(if prune {
+            FetchPrune::On
+        } else {
+            FetchPrune::Unspecified
+        });
+        let fetched = remote.fetch::<&str>(&[], Some(&mut options), None);
+        // The callbacks hold on to the diagnostics until the options go
+        drop(options);
+        if let Err(e) = fetched {
+            failures.push(format!("{}: {}", name, e.message()));
+            if ssh::is_ssh_url(&url) {
+                let mut diagnostics = diagnostics.into_inner();
+                diagnostics.error = e.message().to_string();
+                ssh_diagnostics.push(diagnostics);
+            }
+        }
+    }
+
+    Ok(FetchReport {
+        remotes: names.len(),
+        failures,
+        refs_changed: remote_refs(git_repo)? != before,
+        upstream: upstream_counts(git_repo)?,
+        ssh_diagnostics,
+    })
+}
+
+/// What `:push` sends where
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PushOptions {
+    pub remote: String,
+    /// Local branch to push, without `refs/heads/`
+    pub branch: String,
+    /// Branch to update on the remote, without `refs/heads/`
+    pub remote_branch: String,
+    /// Make the remote branch the local branch's upstream afterwards
+    pub set_upstream: bool,
+    /// Overwrite the remote branch, but only if it is still where the
+    /// last fetch saw it, like `--force-with-lease`
+    pub force_with_lease: bool,
+    /// Overwrite the remote branch wherever it is, like `--force`
+    pub force: bool,
+    /// Push every tag as well
+    pub tags: bool,
+}
+
+/// What a push did to one ref on the remote
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PushedRef {
+    /// Full name of the remote ref, like `refs/heads/main`
+    pub name: String,
+    /// Where the remote ref was; None if the push created it
+    pub old: Option<Oid>,
+    /// Where the push moved it; None if the push deleted it
+    pub new: Option<Oid>,
+    /// Why the remote refused to update it, if it did
+    pub rejected: Option<String>,
+}
+
+impl PushedRef {
+    /// The ref's name without `refs/heads/` or `refs/tags/`
+    pub fn short_name(&self) -> &str {
+        ["refs/heads/", "refs/tags/"]
+            .iter()
+            .find_map(|prefix| self.name.strip_prefix(prefix))
+            .unwrap_or(&self.name)
+    }
+}
+
+/// What a push did to each ref it sent
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PushReport {
+    pub remote: String,
+    /// Each ref the push sent, in the order the remote answered
+    pub refs: Vec<PushedRef>,
+}
+
+impl PushReport {
+    /// The refs the remote refused to update
+    pub fn rejected(&self) -> impl Iterator<Item = &PushedRef> {
+        self.refs.iter().filter(|pushed| pushed.rejected.is_some())
+    }
+
+    /// Say what happened in one line
+    pub fn summary(&self) -> String {
+        let rejected = self.rejected().count();
+        match (rejected, self.refs.len()) {
+            (0, 0) => format!("Everything is up to date on {}", self.remote),
+            (0, 1) => format!("Pushed {} to {}", self.refs[0].short_name(), self.remote),
+            (0, total) => format!("Pushed {} refs to {}", total, self.remote),
+            (rejected, total) => format!("{} rejected {} of {} refs", self.remote, rejected, total),
+        }
+    }
+}
+
+/// Work out what to push by default: the checked out branch to its
+/// upstream, or to a branch of the same name on `origin` (or the only
+/// remote), setting that as the upstream
+pub async fn push_defaults(repo: &Repository) -> Result<PushOptions> {
+    let repo = repo.clone();
+
+    tokio::task::spawn_blocking(move || {
+        let git_repo = repo.open_git2()?;
+        let head = git_repo.head()?;
+        if !head.is_branch() {
+            let short_id = head.target().map(|oid| oid.to_string()).unwrap_or_default();
+            return Err(GitError::DetachedHead {
+                short_id: short_id.chars().take(7).collect(),
+            });
+        }
+        let branch = head.shorthand().ok_or(GitError::InvalidUtf8)?.to_string();
+
+        let config = git_repo.config()?;
+        let upstream_remote = config.get_string(&format!("branch.{}.remote", branch)).ok();
+        let upstream_branch = config
+            .get_string(&format!("branch.{}.merge", branch))
+            .ok()
+            .and_then(|merge| merge.strip_prefix("refs/heads/").map(str::to_string));
+        if let (Some(remote), Some(remote_branch)) = (upstream_remote, upstream_branch) {
+            return Ok(PushOptions {
+                remote,
+                branch,
+                remote_branch,
+                set_upstream: false,
+                force_with_lease: false,
+                force: false,
+                tags: false,
+            });
+        }
+
+        let remotes = git_repo.remotes()?;
+        let remote = match remotes.iter().flatten().find(|name| *name == "origin") {
+            Some(origin) => origin.to_string(),
+            None => remotes
+                .iter()
+                .flatten()
+                .next()
+                .ok_or(GitError::NoRemote)?
+                .to_string(),
+        };
+        Ok(PushOptions {
+            remote,
+            remote_branch: branch.clone(),
+            branch,
+            set_upstream: true,
+            force_with_lease: false,
+            force: false,
+            tags: false,
+        })
+    })
+    .await
+    .map_err(GitError::task_join("work out what to push"))?
+}
+
+/// Push a branch, and tags if asked, as `options` says
+///
+/// Credentials no helper supplies are asked for through `prompt`. The
+/// lease of `force_with_lease` is checked just before pushing, so a push
+/// to the remote in between the two can still be overwritten. Refs the
+/// remote refuses are reported rather than failing the push, since the
+/// others may have gone through; the upstream is only set if the branch
+/// itself was accepted.
+pub async fn push(
+    repo: &Repository,
+    options: PushOptions,
+    prompt: Option<CredentialPrompt>,
+) -> Result<PushReport> {
+    let repo = repo.clone();
+
+    tokio::task::spawn_blocking(move || {
+        let git_repo = repo.open_git2()?;
+        let config = git_repo.config()?;
+        let mut remote = git_repo.find_remote(&options.remote)?;
+        let url = remote.url().unwrap_or_default().to_string();
+        let diagnostics = RefCell::new(SshDiagnostics::new(&options.remote, &url));
+        // Transport failures on SSH remotes carry what the callbacks saw
+        let failed = |message: String, diagnostics: Option<SshDiagnostics>| GitError::PushFailed {
+            remote: options.remote.clone(),
+            ssh: diagnostics
+                .filter(|_| ssh::is_ssh_url(&url))
+                .map(|mut diagnostics| {
+                    diagnostics.error = message.clone();
+                    Box::new(diagnostics)
+                }),
+            message,
+        };
+
+        let local_ref = format!("refs/heads/{}", options.branch);
+        let remote_ref = format!("refs/heads/{}", options.remote_branch);
+        if options.force_with_lease && !options.force {
+            let tracking = format!("refs/remotes/{}/{}", options.remote, options.remote_branch);
+            let expected = git_repo
+                .find_reference(&tracking)
+                .ok()
+                .and_then(|r| r.target());
+            let callbacks = callbacks(&config, prompt.as_ref(), &diagnostics);
+            let actual = remote
+                .connect_auth(Direction::Push, Some(callbacks), None)
+                .and_then(|connection| {
+                    let heads = connection.list()?;
+                    Ok(heads
+                        .iter()
+                        .find(|head| head.name() == remote_ref)
+                        .map(|head| head.oid()))
+                })
+                .map_err(|e| failed(e.message().to_string(), Some(diagnostics.borrow().clone())))?;
+            if actual.is_some() && actual != expected {
+                return Err(failed(
+                    format!(
+                        "{} moved since the last fetch; fetch and look first",
+                        options.remote_branch
+                    ),
+                    None,
+                ));
+            }
+        }
+
+        let force = options.force || options.force_with_lease;
+        let mut refspecs = vec![format!(
+            "{}{}:{}",
+            if force { "+" } else { "" },
+            local_ref,
+            remote_ref
+        )];
+        if options.tags {
+            for tag in git_repo.tag_names(None)?.iter().flatten() {
+                refspecs.push(format!("refs/tags/{tag}:refs/tags/{tag}"));
+            }
+        }
+
+        // The negotiation says where each ref moves, the remote's answer
+        // whether it did
+        let refs = RefCell::new(Vec::<PushedRef>::new());
+        let mut callbacks = callbacks(&config, prompt.as_ref(), &diagnostics);
+        callbacks.push_negotiation(|updates| {
+            let known = |oid: Oid| Some(oid).filter(|oid| !oid.is_zero());
+            refs.borrow_mut()
+                .extend(updates.iter().map(|update| PushedRef {
+                    name: update.dst_refname().unwrap_or_default().to_string(),
+                    old: known(update.src()),
+                    new: known(update.dst()),
+                    rejected: None,
+                }));
+            Ok(())
+        });
+        callbacks.push_update_reference(|reference, status| {
+            let mut refs = refs.borrow_mut();
+            match refs.iter_mut().find(|pushed| pushed.name == reference) {
+                Some(pushed) => pushed.rejected = status.map(str::to_string),
+                None => refs.push(PushedRef {
+                    name: reference.to_string(),
+                    old: None,
+                    new: None,
+                    rejected: status.map(str::to_string),
+                }),
+            }
+            Ok(())
+        });
+        let mut push_options = Git2PushOptions::new();
+        push_options.remote_callbacks(callbacks);
+        let pushed = remote.push(&refspecs, Some(&mut push_options));
+        drop(push_options);
+        pushed.map_err(|e| failed(e.message().to_string(), Some(diagnostics.borrow().clone())))?;
+        let report = PushReport {
+            remote: options.remote.clone(),
+            refs: refs.into_inner(),
+        };
+
+        let branch_rejected = report.rejected().any(|pushed| pushed.name == remote_ref);
+        if options.set_upstream && !branch_rejected {
+            let mut config = git_repo.config()?;
+            config.set_str(
+                &format!("branch.{}.remote", options.branch),
+                &options.remote,
+            )?;
+            config.set_str(&format!("branch.{}.merge", options.branch), &remote_ref)?;
+        }
+        Ok(report)
+    })
+    .await
+    .map_err(GitError::task_join("push"))?
+}
+
+/// Turn a remote URL into the web page of the repository, like
+/// `git@github.com:owner/repo.git` into `https://github.com/owner/repo`;
+/// None for local paths
+fn web_url_of(url: &str) -> Option<String> {
+    let rest = match url.split_once("://") {
+        Some(("http" | "https" | "ssh" | "git", rest)) => rest,
+        Some(_) => return None,
+        // scp-like syntax, user@host:path
+        None => &url.replacen(':', "/", 1),
+    };
+    let rest = rest
+        .rsplit_once('@')
+        .map_or(rest, |(_, host_and_path)| host_and_path);
+    let (host, path) = rest.split_once('/')?;
+    // A port belongs to the transport, not the web server
+    let host = host.split_once(':').map_or(host, |(host, _)| host);
+    let path = path.trim_end_matches('/').trim_end_matches(".git");
+    (!host.is_empty() && !path.is_empty()).then(|| format!("https://{}/{}", host, path))
+}
+
+/// Get the web page of the repository on `origin`, or the only remote, for
+/// linking to commits; None without a remote on a web host
+pub async fn web_url(repo: &Repository) -> Result<Option<String>> {
+    let repo = repo.clone();
+
+    tokio::task::spawn_blocking(move || web_url_in(&repo.open_git2()?))
+        .await
+        .map_err(GitError::task_join("read the remote URL"))?
+}
+
+/// Get the web page of the repository, as [`web_url`] does, from an open
+/// repository
+pub(super) fn web_url_in(git_repo: &git2::Repository) -> Result<Option<String>> {
+    let remotes = git_repo.remotes()?;
+    let name = match remotes.iter().flatten().find(|name| *name == "origin") {
+        Some(origin) => origin,
+        None => match remotes.iter().flatten().next() {
+            Some(name) => name,
+            None => return Ok(None),
+        },
+    };
+    let remote = git_repo.find_remote(name)?;
+    Ok(remote.url().and_then(web_url_of))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::RepoBuilder;
+
+    #[test]
+    fn test_web_url_of_remote_urls() {
+        let github = Some("https://github.com/owner/repo".to_string());
+        assert_eq!(web_url_of("git@github.com:owner/repo.git"), github);
+        assert_eq!(web_url_of("https://github.com/owner/repo.git"), github);
+        assert_eq!(web_url_of("https://user@github.com/owner/repo/"), github);
+        assert_eq!(web_url_of("ssh://git@github.com:22/owner/repo.git"), github);
+        assert_eq!(web_url_of("/srv/repo.git"), None);
+        assert_eq!(web_url_of("file:///srv/repo.git"), None);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_all_updates_and_prunes() {
+        let upstream = RepoBuilder::new()
+            .file("a.txt", "a\n")
+            .commit("Initial")
+            .branch("feature");
+        let (_temp_dir, repo) = RepoBuilder::new().open().await;
+        let git_repo = repo.open_git2().unwrap();
+        git_repo
+            .remote("origin", upstream.path().to_str().unwrap())
+            .unwrap();
+        git_repo
+            .remote("gone", "/nonexistent/rust-tig-remote")
+            .unwrap();
+
+        let report = fetch_all(&repo, true, None).await.unwrap();
+        assert_eq!(report.remotes, 2);
+        assert!(report.refs_changed);
+        assert_eq!(report.failures.len(), 1);
+        assert!(report.failures[0].starts_with("gone: "));
+        assert!(report.ssh_diagnostics.is_empty());
+        assert!(
+            git_repo
+                .find_reference("refs/remotes/origin/feature")
+                .is_ok()
+        );
+
+        let report = fetch_all(&repo, true, None).await.unwrap();
+        assert!(!report.refs_changed);
+
+        upstream
+            .git2()
+            .find_branch("feature", git2::BranchType::Local)
+            .unwrap()
+            .delete()
+            .unwrap();
+        let report = fetch_all(&repo, true, None).await.unwrap();
+        assert!(report.refs_changed);
+        assert!(
+            git_repo
+                .find_reference("refs/remotes/origin/feature")
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_upstream_ahead_behind() {
+        let upstream = RepoBuilder::new()
+            .file("a.txt", "a\n")
+            .commit("Initial")
+            .file("b.txt", "b\n")
+            .commit("Add b");
+        let (_temp_dir, repo) = RepoBuilder::new().open().await;
+        let git_repo = repo.open_git2().unwrap();
+        git_repo
+            .remote("origin", upstream.path().to_str().unwrap())
+            .unwrap();
+        assert_eq!(upstream_ahead_behind(&repo).await.unwrap(), None);
+
+        let report = fetch_all(&repo, false, None).await.unwrap();
+        assert_eq!(report.upstream, None);
+
+        // Track the upstream branch from its first commit, then commit on top
+        let branch = upstream
+            .git2()
+            .head()
+            .unwrap()
+            .shorthand()
+            .unwrap()
+            .to_string();
+        let remote_tip = git_repo
+            .find_reference(&format!("refs/remotes/origin/{}", branch))
+            .unwrap()
+            .peel_to_commit()
+            .unwrap();
+        let first = remote_tip.parent(0).unwrap();
+        let mut work = git_repo.branch("work", &first, false).unwrap();
+        work.set_upstream(Some(&format!("origin/{}", branch)))
+            .unwrap();
+        git_repo.set_head("refs/heads/work").unwrap();
+        let tree = first.tree().unwrap();
+        let signature = git_repo.signature().unwrap();
+        git_repo
+            .commit(
+                Some("HEAD"),
+                &signature,
+                &signature,
+                "Local",
+                &tree,
+                &[&first],
+            )
+            .unwrap();
+
+        assert_eq!(upstream_ahead_behind(&repo).await.unwrap(), Some((1, 1)));
+    }
+
+    #[tokio::test]
+    async fn test_failed_ssh_fetch_is_diagnosed() {
+        let (_temp_dir, repo) = RepoBuilder::new().open().await;
+        let git_repo = repo.open_git2().unwrap();
+        // Nothing listens on port 1, so the host never sends a key
+        git_repo
+            .remote("origin", "ssh://git@127.0.0.1:1/repo.git")
+            .unwrap();
+
+        let report = fetch_all(&repo, false, None).await.unwrap();
+        assert_eq!(report.ssh_diagnostics.len(), 1);
+        let diagnostics = &report.ssh_diagnostics[0];
+        assert_eq!(diagnostics.remote, "origin");
+        assert!(!diagnostics.error.is_empty());
+        assert_eq!(diagnostics.key_offers, 0);
+        assert_eq!(diagnostics.host_key, None);
+    }
+
+    /// Open a repository with one commit and an empty bare `origin`
+    async fn repo_with_bare_origin() -> (tempfile::TempDir, tempfile::TempDir, Repository) {
+        let origin_dir = tempfile::TempDir::new().unwrap();
+        git2::Repository::init_bare(origin_dir.path()).unwrap();
+        let (temp_dir, repo) = RepoBuilder::new()
+            .file("a.txt", "a\n")
+            .commit("Initial")
+            .open()
+            .await;
+        repo.open_git2()
+            .unwrap()
+            .remote("origin", origin_dir.path().to_str().unwrap())
+            .unwrap();
+        (origin_dir, temp_dir, repo)
+    }
+
+    #[tokio::test]
+    async fn test_push_sets_upstream_and_tags() {
+        let (origin_dir, _temp_dir, repo) = repo_with_bare_origin().await;
+        let git_repo = repo.open_git2().unwrap();
+        let head = git_repo.head().unwrap().peel_to_commit().unwrap();
+        git_repo
+            .tag_lightweight("v1", head.as_object(), false)
+            .unwrap();
+
+        let mut options = push_defaults(&repo).await.unwrap();
+        let branch = options.branch.clone();
+        assert_eq!(options.remote, "origin");
+        assert_eq!(options.remote_branch, branch);
+        assert!(options.set_upstream);
+
+        options.tags = true;
+        let report = push(&repo, options, None).await.unwrap();
+        let origin = git2::Repository::open_bare(origin_dir.path()).unwrap();
+        assert_eq!(
+            origin
+                .refname_to_id(&format!("refs/heads/{}", branch))
+                .unwrap(),
+            head.id()
+        );
+        assert!(origin.find_reference("refs/tags/v1").is_ok());
+        let names: Vec<&str> = report.refs.iter().map(PushedRef::short_name).collect();
+        assert_eq!(names, [branch.as_str(), "v1"]);
+        assert_eq!(
+            (report.refs[0].old, report.refs[0].new),
+            (None, Some(head.id()))
+        );
+        assert_eq!(report.summary(), "Pushed 2 refs to origin");
+
+        // Now it has an upstream, the defaults follow it
+        let options = push_defaults(&repo).await.unwrap();
+        assert!(!options.set_upstream);
+        assert_eq!(upstream_ahead_behind(&repo).await.unwrap(), Some((0, 0)));
+    }
+
+    #[tokio::test]
+    async fn test_refused_refs_are_reported() {
+        let (origin_dir, _temp_dir, repo) = repo_with_bare_origin().await;
+        let git_repo = repo.open_git2().unwrap();
+        let head = git_repo.head().unwrap().peel_to_commit().unwrap();
+        git_repo
+            .tag_lightweight("v1", head.as_object(), false)
+            .unwrap();
+        // Someone else is in the middle of writing the tag on the remote
+        std::fs::create_dir_all(origin_dir.path().join("refs/tags")).unwrap();
+        std::fs::write(origin_dir.path().join("refs/tags/v1.lock"), "").unwrap();
+
+        let options = PushOptions {
+            tags: true,
+            ..push_defaults(&repo).await.unwrap()
+        };
+        let report = push(&repo, options.clone(), None).await.unwrap();
+        let rejected: Vec<&str> = report.rejected().map(PushedRef::short_name).collect();
+        assert_eq!(rejected, ["v1"]);
+        assert_eq!(report.summary(), "origin rejected 1 of 2 refs");
+        // The branch went through, so it is the upstream now
+        assert!(!push_defaults(&repo).await.unwrap().set_upstream);
+    }
+
+    #[tokio::test]
+    async fn test_rewritten_branch_needs_a_lease() {
+        let (origin_dir, _temp_dir, repo) = repo_with_bare_origin().await;
+        let options = push_defaults(&repo).await.unwrap();
+        push(&repo, options.clone(), None).await.unwrap();
+
+        // Rewrite the pushed commit
+        let git_repo = repo.open_git2().unwrap();
+        let head = git_repo.head().unwrap().peel_to_commit().unwrap();
+        head.amend(Some("HEAD"), None, None, None, Some("Rewritten"), None)
+            .unwrap();
+
+        let error = push(&repo, options.clone(), None).await.unwrap_err();
+        assert!(matches!(error, GitError::PushFailed { .. }), "{}", error);
+
+        // Someone else moved the branch since our last fetch
+        let origin = git2::Repository::open_bare(origin_dir.path()).unwrap();
+        let pushed = origin.find_commit(head.id()).unwrap();
+        let signature = git2::Signature::now("Other", "other@example.com").unwrap();
+        let theirs = origin
+            .commit(
+                None,
+                &signature,
+                &signature,
+                "Theirs",
+                &pushed.tree().unwrap(),
+                &[&pushed],
+            )
+            .unwrap();
+        origin
+            .reference(
+                &format!("refs/heads/{}", options.branch),
+                theirs,
+                true,
+                "test",
+            )
+            .unwrap();
+
+        let lease = PushOptions {
+            force_with_lease: true,
+            ..options.clone()
+        };
+        let error = push(&repo, lease.clone(), None).await.unwrap_err();
+        assert!(
+            error.to_string().contains("moved since the last fetch"),
+            "{}",
+            error
+        );
+
+        fetch_all(&repo, false, None).await.unwrap();
+        push(&repo, lease, None).await.unwrap();
+        let pushed = origin
+            .refname_to_id(&format!("refs/heads/{}", options.branch))
+            .unwrap();
+        assert_eq!(pushed, git_repo.head().unwrap().target().unwrap());
+    }
+
+    #[test]
+    fn test_ask_waits_for_the_answer() {
+        let (prompt, mut requests) = mpsc::unbounded_channel();
+        let asking = std::thread::spawn(move || {
+            let answered = ask(&prompt, "https://example.com/repo.git", Some("alice")).is_ok();
+            let cancelled = ask(&prompt, "https://example.com/repo.git", None).is_err();
+            (answered, cancelled)
+        });
+
+        let request = requests.blocking_recv().unwrap();
+        assert_eq!(request.username.as_deref(), Some("alice"));
+        request.answer("alice".to_string(), "token".to_string());
+        requests.blocking_recv().unwrap().cancel();
+        assert_eq!(asking.join().unwrap(), (true, true));
+    }
+}
@@ -6,7 +6,7 @@ use std::path::{Path, PathBuf};
 ///
 /// Note: git2::Repository is not Send/Sync, so we store the path
 /// and open a fresh repository handle in each async operation
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Repository {
     path: PathBuf,
 }
@@ -20,7 +20,7 @@ impl Repository {
         // Verify we can open it
         tokio::task::spawn_blocking(move || Git2Repo::open(path_clone))
             .await
-            .map_err(|_| GitError::RepoNotFound)??;
+            .map_err(GitError::task_join("open the repository"))??;
 
         Ok(Self { path })
     }
@@ -34,11 +34,9 @@ impl Repository {
     pub async fn discover_from<P: AsRef<Path>>(start_path: P) -> Result<Self> {
         let start_path = start_path.as_ref().to_path_buf();
 
-        let repo_path = tokio::task::spawn_blocking(move || {
-            Git2Repo::discover(&start_path)
-        })
-        .await
-        .map_err(|_| GitError::NotARepo)??;
+        let repo_path = tokio::task::spawn_blocking(move || Git2Repo::discover(&start_path))
+            .await
+            .map_err(GitError::task_join("discover the repository"))??;
 
         Self::open(repo_path.path()).await
     }
@@ -49,16 +47,24 @@ impl Repository {
     }
 
     /// Get the current HEAD reference name (e.g., "refs/heads/main")
+    ///
+    /// Fails with `DetachedHead` when no branch is checked out.
     pub async fn head_name(&self) -> Result<Option<String>> {
         let path = self.path.clone();
 
         tokio::task::spawn_blocking(move || {
             let repo = Git2Repo::open(path)?;
             let head = repo.head()?;
+            if repo.head_detached()? {
+                let oid = head.target().map(|oid| oid.to_string()).unwrap_or_default();
+                return Err(GitError::DetachedHead {
+                    short_id: oid.chars().take(7).collect(),
+                });
+            }
             Ok(head.name().map(|s| s.to_string()))
         })
         .await
-        .map_err(|_| GitError::RepoNotFound)?
+        .map_err(GitError::task_join("read HEAD"))?
     }
 
     /// Get the short name of the current branch (e.g., "main" instead of "refs/heads/main")
@@ -67,7 +73,17 @@ impl Repository {
 
         tokio::task::spawn_blocking(move || {
             let repo = Git2Repo::open(path)?;
-            let head = repo.head()?;
+            let head = match repo.head() {
+                Ok(head) => head,
+                // Before the first commit HEAD points at a branch that doesn't exist yet
+                Err(e) if e.code() == git2::ErrorCode::UnbornBranch => {
+                    let head = repo.find_reference("HEAD")?;
+                    let target = head.symbolic_target().unwrap_or_default();
+                    let name = target.strip_prefix("refs/heads/").unwrap_or(target);
+                    return Ok(Some(name.to_string()));
+                }
+                Err(e) => return Err(e.into()),
+            };
             if let Some(name) = head.shorthand() {
                 Ok(Some(name.to_string()))
             } else {
@@ -75,7 +91,7 @@ impl Repository {
             }
         })
         .await
-        .map_err(|_| GitError::RepoNotFound)?
+        .map_err(GitError::task_join("read the current branch"))?
     }
 
     /// Check if the repository is empty (no commits)
@@ -87,7 +103,7 @@ impl Repository {
             Ok(repo.is_empty()?)
         })
         .await
-        .map_err(|_| GitError::RepoNotFound)?
+        .map_err(GitError::task_join("check for commits"))?
     }
 
     /// Get the workdir path
@@ -99,7 +115,7 @@ impl Repository {
             Ok(repo.workdir().map(|p| p.to_path_buf()))
         })
         .await
-        .map_err(|_| GitError::RepoNotFound)?
+        .map_err(GitError::task_join("find the workdir"))?
     }
 
     /// Open a git2::Repository for synchronous operations
@@ -112,33 +128,11 @@ impl Repository {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::testing::RepoBuilder;
     use tempfile::TempDir;
 
     async fn create_test_repo() -> (TempDir, Repository) {
-        let temp_dir = TempDir::new().unwrap();
-        let repo_path = temp_dir.path();
-
-        // Create a git repository
-        let repo = Git2Repo::init(repo_path).unwrap();
-
-        // Create an initial commit
-        let sig = git2::Signature::now("Test User", "test@example.com").unwrap();
-        let tree_id = {
-            let mut index = repo.index().unwrap();
-            index.write_tree().unwrap()
-        };
-        let tree = repo.find_tree(tree_id).unwrap();
-        repo.commit(
-            Some("HEAD"),
-            &sig,
-            &sig,
-            "Initial commit",
-            &tree,
-            &[],
-        ).unwrap();
-
-        let our_repo = Repository::open(repo_path).await.unwrap();
-        (temp_dir, our_repo)
+        RepoBuilder::new().commit("Initial commit").open().await
     }
 
     #[tokio::test]
@@ -157,6 +151,34 @@ mod tests {
         assert!(branch_name == "master" || branch_name == "main");
     }
 
+    #[tokio::test]
+    async fn test_current_branch_before_first_commit() {
+        let temp_dir = TempDir::new().unwrap();
+        let git_repo = Git2Repo::init(temp_dir.path()).unwrap();
+        git_repo.set_head("refs/heads/trunk").unwrap();
+
+        let repo = Repository::open(temp_dir.path()).await.unwrap();
+        assert_eq!(
+            repo.current_branch().await.unwrap(),
+            Some("trunk".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_head_name_when_detached() {
+        let (temp_dir, repo) = create_test_repo().await;
+        assert!(repo.head_name().await.unwrap().is_some());
+
+        let git_repo = Git2Repo::open(temp_dir.path()).unwrap();
+        let head = git_repo.head().unwrap().target().unwrap();
+        git_repo.set_head_detached(head).unwrap();
+
+        let result = repo.head_name().await;
+        assert!(
+            matches!(result, Err(GitError::DetachedHead { short_id }) if head.to_string().starts_with(&short_id))
+        );
+    }
+
     #[tokio::test]
     async fn test_is_empty() {
         let (_temp_dir, repo) = create_test_repo().await;
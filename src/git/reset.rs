@@ -0,0 +1,110 @@
+use super::{
+    error::{GitError, Result},
+    repository::Repository,
+    watch::note_local_write,
+};
+use git2::{Oid, ResetType};
+
+/// How much of the work a reset keeps, like `git reset --soft`, `--mixed`
+/// and `--hard`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResetMode {
+    /// Move the branch only; the index and working tree keep the changes,
+    /// staged
+    Soft,
+    /// Move the branch and reset the index; the changes stay in the
+    /// working tree, unstaged
+    Mixed,
+    /// Move the branch and reset the index and working tree, discarding
+    /// uncommitted changes to tracked files
+    Hard,
+}
+
+impl ResetMode {
+    /// Name the mode for messages, like "soft"
+    pub fn label(self) -> &'static str {
+        match self {
+            ResetMode::Soft => "soft",
+            ResetMode::Mixed => "mixed",
+            ResetMode::Hard => "hard",
+        }
+    }
+
+    fn reset_type(self) -> ResetType {
+        match self {
+            ResetMode::Soft => ResetType::Soft,
+            ResetMode::Mixed => ResetType::Mixed,
+            ResetMode::Hard => ResetType::Hard,
+        }
+    }
+}
+
+/// Reset the checked out branch to commit `id`, returning the name of what
+/// moved, like "main", or "HEAD" when it is detached
+///
+/// Mixed and hard resets also end a merge, cherry-pick or revert in
+/// progress, as `git reset` does; libgit2 refuses a soft one during a merge.
+pub async fn reset(repo: &Repository, id: Oid, mode: ResetMode) -> Result<String> {
+    let repo = repo.clone();
+
+    tokio::task::spawn_blocking(move || {
+        let git_repo = repo.open_git2()?;
+        let target = git_repo.find_commit(id)?;
+        git_repo.reset(target.as_object(), mode.reset_type(), None)?;
+        note_local_write(repo.path());
+
+        let head = git_repo.head()?;
+        Ok(match git_repo.head_detached()? {
+            true => "HEAD".to_string(),
+            false => head.shorthand().unwrap_or("HEAD").to_string(),
+        })
+    })
+    .await
+    .map_err(GitError::task_join("reset"))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::RepoBuilder;
+
+    #[tokio::test]
+    async fn test_reset_modes_keep_what_they_should() {
+        let builder = RepoBuilder::new().file("a.txt", "one\n").commit("Initial");
+        let initial = builder.head();
+        let (dir, repo) = builder.file("a.txt", "two\n").commit("Second").open().await;
+        let git_repo = repo.open_git2().unwrap();
+        let second = git_repo.head().unwrap().target().unwrap();
+        let staged = |git_repo: &git2::Repository| {
+            let head = git_repo.head().unwrap().peel_to_tree().unwrap();
+            let diff = git_repo
+                .diff_tree_to_index(Some(&head), None, None)
+                .unwrap();
+            diff.deltas().len()
+        };
+
+        // Soft keeps the change staged
+        assert_eq!(
+            reset(&repo, initial, ResetMode::Soft).await.unwrap(),
+            "master"
+        );
+        assert_eq!(git_repo.head().unwrap().target(), Some(initial));
+        assert_eq!(staged(&git_repo), 1);
+
+        // Mixed keeps it in the working tree only
+        reset(&repo, second, ResetMode::Soft).await.unwrap();
+        reset(&repo, initial, ResetMode::Mixed).await.unwrap();
+        assert_eq!(staged(&git_repo), 0);
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("a.txt")).unwrap(),
+            "two\n"
+        );
+
+        // Hard discards it
+        reset(&repo, initial, ResetMode::Hard).await.unwrap();
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("a.txt")).unwrap(),
+            "one\n"
+        );
+    }
+}
@@ -0,0 +1,168 @@
+use super::{
+    cherry_pick::{conflicted_paths, operation_in_progress},
+    error::{GitError, Result},
+    pull::is_dirty,
+    repository::Repository,
+    watch::note_local_write,
+};
+use git2::Oid;
+
+/// What reverting a commit did
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RevertOutcome {
+    /// The revert was committed as this commit
+    Committed(Oid),
+    /// The revert is staged, waiting to be committed with its message
+    Staged,
+    /// The revert stopped on conflicts in these files, left in the working
+    /// tree and index to resolve, stage and commit
+    Conflicted(Vec<String>),
+}
+
+/// Undo the changes of commit `id` on top of HEAD, like `git revert`,
+/// committing the result when `commit` is set and else leaving it staged
+///
+/// The repository stays in the revert state while the revert is staged or
+/// conflicted; committing from the status view starts from its message and
+/// finishes it. Uncommitted changes to tracked files, merge commits and
+/// another operation in progress refuse the revert before anything changes.
+pub async fn revert(repo: &Repository, id: Oid, commit: bool) -> Result<RevertOutcome> {
+    let repo = repo.clone();
+
+    tokio::task::spawn_blocking(move || {
+        let git_repo = repo.open_git2()?;
+        if let Some(operation) = operation_in_progress(&git_repo) {
+            return Err(GitError::CannotRevert(format!(
+                "a {} is in progress; finish it first",
+                operation
+            )));
+        }
+        if is_dirty(&git_repo)? {
+            return Err(GitError::UncommittedChanges);
+        }
+        let reverted = git_repo.find_commit(id)?;
+        if reverted.parent_count() != 1 {
+            return Err(GitError::CannotRevert(format!(
+                "{:.7} is a merge; revert it by hand",
+                id.to_string()
+            )));
+        }
+
+        git_repo.revert(&reverted, None)?;
+        note_local_write(repo.path());
+        let mut index = git_repo.index()?;
+        if index.has_conflicts() {
+            return Ok(RevertOutcome::Conflicted(conflicted_paths(&index)?));
+        }
+        let head = git_repo.head()?.peel_to_commit()?;
+        let tree_id = index.write_tree()?;
+        if tree_id == head.tree_id() {
+            git_repo.cleanup_state()?;
+            return Err(GitError::CannotRevert(format!(
+                "the changes of {:.7} are already undone",
+                id.to_string()
+            )));
+        }
+        if !commit {
+            return Ok(RevertOutcome::Staged);
+        }
+
+        let message = git_repo.message()?;
+        let tree = git_repo.find_tree(tree_id)?;
+        let signature = git_repo.signature()?;
+        let new_id = git_repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            message.trim_end(),
+            &tree,
+            &[&head],
+        )?;
+        git_repo.cleanup_state()?;
+        Ok(RevertOutcome::Committed(new_id))
+    })
+    .await
+    .map_err(GitError::task_join("revert"))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::RepoBuilder;
+    use git2::RepositoryState;
+
+    #[tokio::test]
+    async fn test_revert_commits_or_stages() {
+        let builder = RepoBuilder::new()
+            .file("a.txt", "a\n")
+            .commit("Initial")
+            .file("b.txt", "b\n")
+            .commit("Add b");
+        let add_b = builder.head();
+        let (dir, repo) = builder.open().await;
+
+        let RevertOutcome::Committed(new_id) = revert(&repo, add_b, true).await.unwrap() else {
+            panic!("revert was not committed");
+        };
+        let git_repo = repo.open_git2().unwrap();
+        let head = git_repo.find_commit(new_id).unwrap();
+        assert_eq!(head.summary(), Some("Revert \"Add b\""));
+        assert!(
+            head.message()
+                .unwrap()
+                .contains(&format!("This reverts commit {}.", add_b))
+        );
+        assert!(!dir.path().join("b.txt").exists());
+        assert_eq!(git_repo.state(), RepositoryState::Clean);
+
+        // Undoing the revert is staged for the message to be edited first
+        assert_eq!(
+            revert(&repo, new_id, false).await.unwrap(),
+            RevertOutcome::Staged
+        );
+        assert_eq!(git_repo.state(), RepositoryState::Revert);
+        let message = crate::git::status::commit_template(&repo)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(
+            message.starts_with("Revert \"Revert \"Add b\"\""),
+            "{}",
+            message
+        );
+        crate::git::status::commit_staged(&repo, message)
+            .await
+            .unwrap();
+        assert_eq!(git_repo.state(), RepositoryState::Clean);
+        assert!(dir.path().join("b.txt").exists());
+
+        // Reverting what is already undone changes nothing
+        assert!(matches!(
+            revert(&repo, new_id, true).await,
+            Err(GitError::CannotRevert(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_conflicted_revert_leaves_revert_state() {
+        let builder = RepoBuilder::new()
+            .file("a.txt", "a\n")
+            .commit("Initial")
+            .file("a.txt", "b\n")
+            .commit("Change a");
+        let change = builder.head();
+        let builder = builder.file("a.txt", "c\n").commit("Change a again");
+        let (_dir, repo) = builder.open().await;
+
+        let outcome = revert(&repo, change, true).await.unwrap();
+        assert_eq!(
+            outcome,
+            RevertOutcome::Conflicted(vec!["a.txt".to_string()])
+        );
+        assert_eq!(repo.open_git2().unwrap().state(), RepositoryState::Revert);
+        assert!(matches!(
+            revert(&repo, change, true).await,
+            Err(GitError::CannotRevert(_))
+        ));
+    }
+}
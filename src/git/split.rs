@@ -0,0 +1,349 @@
+use super::{
+    error::{GitError, Result},
+    repository::Repository,
+    watch::note_local_write,
+};
+use git2::{Oid, ResetType, Sort, StatusOptions, build::CheckoutBuilder};
+use std::fs;
+use std::path::Path;
+
+/// File in the git directory that remembers a split in progress
+const STATE_FILE: &str = "rust-tig-split";
+
+/// A commit being split into several, as with `git rebase -i` stopped at
+/// `edit` followed by `git reset HEAD^`
+///
+/// While it lasts HEAD is detached at the commit's parent with the commit's
+/// changes left in the working tree, ready to be staged and committed a
+/// piece at a time. The branch doesn't move until the split finishes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SplitState {
+    /// Full name of the branch being rewritten
+    pub branch: String,
+    /// The commit being split
+    pub commit: Oid,
+    /// Commits after it on the branch, oldest first, to replay on top of
+    /// the new ones
+    pub replay: Vec<Oid>,
+}
+
+impl SplitState {
+    /// Write the state in lines of `<key> <value>`
+    fn to_text(&self) -> String {
+        let mut text = format!("branch {}\ncommit {}\n", self.branch, self.commit);
+        for oid in &self.replay {
+            text.push_str(&format!("replay {}\n", oid));
+        }
+        text
+    }
+
+    /// Read the state back, or None when the file is damaged
+    fn from_text(text: &str) -> Option<Self> {
+        let mut branch = None;
+        let mut commit = None;
+        let mut replay = Vec::new();
+        for line in text.lines() {
+            let (key, value) = line.split_once(' ')?;
+            match key {
+                "branch" => branch = Some(value.to_string()),
+                "commit" => commit = Some(Oid::from_str(value).ok()?),
+                "replay" => replay.push(Oid::from_str(value).ok()?),
+                _ => return None,
+            }
+        }
+        Some(Self {
+            branch: branch?,
+            commit: commit?,
+            replay,
+        })
+    }
+}
+
+/// Read the split in progress, if any
+fn read_state(git_dir: &Path) -> Result<Option<SplitState>> {
+    match fs::read_to_string(git_dir.join(STATE_FILE)) {
+        Ok(text) => SplitState::from_text(&text)
+            .map(Some)
+            .ok_or_else(|| GitError::CannotSplit(format!("{} is damaged", STATE_FILE))),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Fail unless the tracked files match HEAD, untracked files aside
+fn ensure_clean(git_repo: &git2::Repository) -> Result<()> {
+    let mut options = StatusOptions::new();
+    options.include_untracked(false).include_ignored(false);
+    if !git_repo.statuses(Some(&mut options))?.is_empty() {
+        return Err(GitError::CannotSplit(
+            "tracked files have uncommitted changes".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Get the split in progress, if any
+pub async fn split_state(repo: &Repository) -> Result<Option<SplitState>> {
+    let repo = repo.clone();
+
+    tokio::task::spawn_blocking(move || read_state(repo.open_git2()?.path()))
+        .await
+        .map_err(GitError::task_join("read the split state"))?
+}
+
+/// Start splitting `commit_id`, a commit on the checked out branch
+///
+/// Checks out the commit's parent with the commit's changes unstaged in the
+/// working tree. Merges, root commits and commits off the branch can't be
+/// split, and neither can anything while tracked files have changes.
+pub async fn start_split(repo: &Repository, commit_id: Oid) -> Result<SplitState> {
+    let repo = repo.clone();
+
+    tokio::task::spawn_blocking(move || {
+        let git_repo = repo.open_git2()?;
+        if read_state(git_repo.path())?.is_some() {
+            return Err(GitError::CannotSplit(
+                "a split is already in progress".to_string(),
+            ));
+        }
+        let head = git_repo.head()?;
+        if git_repo.head_detached()? {
+            let short_id = head.target().map(|oid| oid.to_string()).unwrap_or_default();
+            return Err(GitError::DetachedHead {
+                short_id: short_id.chars().take(7).collect(),
+            });
+        }
+        let branch = head.name().ok_or(GitError::InvalidUtf8)?.to_string();
+        ensure_clean(&git_repo)?;
+
+        let commit = git_repo.find_commit(commit_id)?;
+        let short_id = &commit_id.to_string()[..7];
+        if commit.parent_count() != 1 {
+            return Err(GitError::CannotSplit(format!(
+                "{} is a merge or root commit",
+                short_id
+            )));
+        }
+        let head_id = head.peel_to_commit()?.id();
+        if head_id != commit_id && !git_repo.graph_descendant_of(head_id, commit_id)? {
+            return Err(GitError::CannotSplit(format!(
+                "{} is not on {}",
+                short_id, branch
+            )));
+        }
+
+        // Everything after the commit gets replayed, so it has to be a
+        // straight line of single-parent commits
+        let mut walk = git_repo.revwalk()?;
+        walk.set_sorting(Sort::TOPOLOGICAL | Sort::REVERSE)?;
+        walk.push(head_id)?;
+        walk.hide(commit_id)?;
+        let replay = walk.collect::<std::result::Result<Vec<_>, _>>()?;
+        for oid in &replay {
+            if git_repo.find_commit(*oid)?.parent_count() != 1 {
+                return Err(GitError::CannotSplit(format!(
+                    "merges follow {} on the branch",
+                    short_id
+                )));
+            }
+        }
+
+        let mut checkout = CheckoutBuilder::new();
+        git_repo.checkout_tree(commit.as_object(), Some(checkout.safe()))?;
+        git_repo.set_head_detached(commit_id)?;
+        git_repo.reset(commit.parent(0)?.as_object(), ResetType::Mixed, None)?;
+
+        let state = SplitState {
+            branch,
+            commit: commit_id,
+            replay,
+        };
+        fs::write(git_repo.path().join(STATE_FILE), state.to_text())?;
+        note_local_write(repo.path());
+        Ok(state)
+    })
+    .await
+    .map_err(GitError::task_join("start a split"))?
+}
+
+/// Finish the split in progress once every change of the split commit is
+/// committed: replay the later commits and move the branch to the result
+///
+/// Returns the new tip of the branch.
+pub async fn finish_split(repo: &Repository) -> Result<Oid> {
+    let repo = repo.clone();
+
+    tokio::task::spawn_blocking(move || {
+        let git_repo = repo.open_git2()?;
+        let state = read_state(git_repo.path())?
+            .ok_or_else(|| GitError::CannotSplit("no split in progress".to_string()))?;
+        ensure_clean(&git_repo)?;
+
+        let mut tip = git_repo.head()?.peel_to_commit()?;
+        if tip.tree_id() != git_repo.find_commit(state.commit)?.tree_id() {
+            return Err(GitError::CannotSplit(format!(
+                "some of {:.7}'s changes aren't committed yet",
+                state.commit.to_string()
+            )));
+        }
+
+        let signature = git_repo.signature()?;
+        for oid in &state.replay {
+            let pick = git_repo.find_commit(*oid)?;
+            let mut index = git_repo.cherrypick_commit(&pick, &tip, 0, None)?;
+            if index.has_conflicts() {
+                return Err(GitError::MergeConflict { paths: Vec::new() });
+            }
+            let tree = git_repo.find_tree(index.write_tree_to(&git_repo)?)?;
+            let message = pick.message().unwrap_or_default();
+            let id = git_repo.commit(None, &pick.author(), &signature, message, &tree, &[&tip])?;
+            tip = git_repo.find_commit(id)?;
+        }
+
+        git_repo.checkout_tree(tip.as_object(), Some(CheckoutBuilder::new().safe()))?;
+        git_repo.reference(&state.branch, tip.id(), true, "rust-tig: split a commit")?;
+        git_repo.set_head(&state.branch)?;
+        fs::remove_file(git_repo.path().join(STATE_FILE))?;
+        note_local_write(repo.path());
+        Ok(tip.id())
+    })
+    .await
+    .map_err(GitError::task_join("finish a split"))?
+}
+
+/// Give up on the split in progress, checking the untouched branch out again
+///
+/// Commits made during the split are dropped, and so are changes left in
+/// tracked files.
+pub async fn abort_split(repo: &Repository) -> Result<()> {
+    let repo = repo.clone();
+
+    tokio::task::spawn_blocking(move || {
+        let git_repo = repo.open_git2()?;
+        let state = read_state(git_repo.path())?
+            .ok_or_else(|| GitError::CannotSplit("no split in progress".to_string()))?;
+
+        git_repo.set_head(&state.branch)?;
+        git_repo.checkout_head(Some(CheckoutBuilder::new().force()))?;
+        let head = git_repo.head()?.peel_to_commit()?;
+        git_repo.reset(head.as_object(), ResetType::Mixed, None)?;
+        fs::remove_file(git_repo.path().join(STATE_FILE))?;
+        note_local_write(repo.path());
+        Ok(())
+    })
+    .await
+    .map_err(GitError::task_join("abort a split"))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::RepoBuilder;
+
+    /// Stage `path` from the working tree and commit it
+    fn commit_path(git_repo: &git2::Repository, path: &str, message: &str) {
+        let mut index = git_repo.index().unwrap();
+        index.add_path(Path::new(path)).unwrap();
+        index.write().unwrap();
+        let tree = git_repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let parent = git_repo.head().unwrap().peel_to_commit().unwrap();
+        let signature = git_repo.signature().unwrap();
+        git_repo
+            .commit(
+                Some("HEAD"),
+                &signature,
+                &signature,
+                message,
+                &tree,
+                &[&parent],
+            )
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_split_then_finish_replays_later_commits() {
+        let builder = RepoBuilder::new()
+            .file("base.txt", "base\n")
+            .commit("Base")
+            .file("a.txt", "a\n")
+            .file("b.txt", "b\n")
+            .commit("Add a and b");
+        let target = builder.head();
+        let builder = builder.file("c.txt", "c\n").commit("Add c");
+        let (temp_dir, repo) = builder.open().await;
+
+        let state = start_split(&repo, target).await.unwrap();
+        assert_eq!(state.replay.len(), 1);
+        assert_eq!(split_state(&repo).await.unwrap(), Some(state));
+        assert!(!temp_dir.path().join("c.txt").exists());
+
+        // Finishing early would lose b.txt
+        let git_repo = repo.open_git2().unwrap();
+        commit_path(&git_repo, "a.txt", "Add a");
+        assert!(matches!(
+            finish_split(&repo).await,
+            Err(GitError::CannotSplit(_))
+        ));
+        commit_path(&git_repo, "b.txt", "Add b");
+
+        let tip = finish_split(&repo).await.unwrap();
+        assert_eq!(split_state(&repo).await.unwrap(), None);
+        assert!(!git_repo.head_detached().unwrap());
+        assert_eq!(git_repo.head().unwrap().target(), Some(tip));
+        assert!(temp_dir.path().join("c.txt").exists());
+
+        let mut walk = git_repo.revwalk().unwrap();
+        walk.push_head().unwrap();
+        let summaries: Vec<String> = walk
+            .map(|oid| {
+                git_repo
+                    .find_commit(oid.unwrap())
+                    .unwrap()
+                    .summary()
+                    .unwrap()
+                    .to_string()
+            })
+            .collect();
+        assert_eq!(summaries, ["Add c", "Add b", "Add a", "Base"]);
+    }
+
+    #[tokio::test]
+    async fn test_abort_split_restores_branch() {
+        let builder = RepoBuilder::new()
+            .commit("Base")
+            .file("a.txt", "a\n")
+            .commit("Add a");
+        let head = builder.head();
+        let (temp_dir, repo) = builder.open().await;
+
+        start_split(&repo, head).await.unwrap();
+        assert!(matches!(
+            start_split(&repo, head).await,
+            Err(GitError::CannotSplit(_))
+        ));
+        abort_split(&repo).await.unwrap();
+
+        let git_repo = repo.open_git2().unwrap();
+        assert_eq!(git_repo.head().unwrap().target(), Some(head));
+        assert!(!git_repo.head_detached().unwrap());
+        assert!(git_repo.statuses(None).unwrap().is_empty());
+        assert!(temp_dir.path().join("a.txt").exists());
+        assert_eq!(split_state(&repo).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_split_refusals() {
+        let builder = RepoBuilder::new().commit("Root");
+        let root = builder.head();
+        let builder = builder.file("a.txt", "a\n").commit("Add a");
+        let head = builder.head();
+        let (temp_dir, repo) = builder.write("a.txt", "changed\n").open().await;
+
+        let error = start_split(&repo, head).await.unwrap_err();
+        assert!(error.to_string().contains("uncommitted changes"));
+
+        fs::write(temp_dir.path().join("a.txt"), "a\n").unwrap();
+        let error = start_split(&repo, root).await.unwrap_err();
+        assert!(error.to_string().contains("root commit"));
+    }
+}
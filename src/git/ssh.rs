@@ -0,0 +1,285 @@
+use std::path::PathBuf;
+
+/// Whether an ssh-agent looks reachable through `SSH_AUTH_SOCK`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AgentStatus {
+    NotSet,
+    /// The variable names a socket that doesn't exist
+    Missing(PathBuf),
+    Reachable(PathBuf),
+}
+
+impl AgentStatus {
+    /// Look at `SSH_AUTH_SOCK`
+    pub fn detect() -> Self {
+        match std::env::var_os("SSH_AUTH_SOCK") {
+            Some(path) if !path.is_empty() => {
+                let path = PathBuf::from(path);
+                match path.exists() {
+                    true => AgentStatus::Reachable(path),
+                    false => AgentStatus::Missing(path),
+                }
+            }
+            _ => AgentStatus::NotSet,
+        }
+    }
+}
+
+/// What `~/.ssh/known_hosts` says about the key a host presented
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KnownHost {
+    /// The key is listed
+    Known,
+    /// The host is listed with a different key of the same type
+    Mismatch,
+    /// The host isn't listed by name; hashed entries can't be told apart
+    Unknown {
+        hashed_entries: usize,
+    },
+    NoFile,
+}
+
+/// The key a host presented during the handshake
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HostKey {
+    /// Key type, such as `ssh-ed25519`
+    pub key_type: String,
+    /// `SHA256:...`, as `ssh-keygen -l` prints it
+    pub fingerprint: String,
+    pub known: KnownHost,
+}
+
+/// What went on during a failed SSH fetch, gathered from the transport
+/// callbacks, to explain an otherwise generic error
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SshDiagnostics {
+    pub remote: String,
+    pub url: String,
+    pub error: String,
+    pub agent: AgentStatus,
+    /// Username the agent's keys were offered for
+    pub username: Option<String>,
+    /// How many times the agent's keys were offered
+    pub key_offers: usize,
+    /// None when the connection failed before the host sent a key
+    pub host_key: Option<HostKey>,
+}
+
+impl SshDiagnostics {
+    /// Start diagnosing a fetch from `url`
+    pub fn new(remote: &str, url: &str) -> Self {
+        Self {
+            remote: remote.to_string(),
+            url: url.to_string(),
+            error: String::new(),
+            agent: AgentStatus::detect(),
+            username: None,
+            key_offers: 0,
+            host_key: None,
+        }
+    }
+
+    /// Note the key `host` presented and look it up in `~/.ssh/known_hosts`
+    pub fn record_host_key(&mut self, host: &str, cert: &git2::cert::CertHostkey) {
+        let Some(key_type) = cert.hostkey_type() else {
+            return;
+        };
+        let key_type = key_type.name().to_string();
+        let fingerprint = cert
+            .hash_sha256()
+            .map(|hash| format!("SHA256:{}", base64(hash).trim_end_matches('=')))
+            .unwrap_or_default();
+        let path = dirs::home_dir().map(|home| home.join(".ssh").join("known_hosts"));
+        let known = match (
+            path.and_then(|p| std::fs::read_to_string(p).ok()),
+            cert.hostkey(),
+        ) {
+            (Some(text), Some(key)) => check_known_hosts(&text, host, &key_type, &base64(key)),
+            _ => KnownHost::NoFile,
+        };
+        self.host_key = Some(HostKey {
+            key_type,
+            fingerprint,
+            known,
+        });
+    }
+
+    /// Describe the findings, one line each
+    pub fn lines(&self) -> Vec<String> {
+        let agent = match &self.agent {
+            AgentStatus::NotSet => "not running (SSH_AUTH_SOCK is not set)".to_string(),
+            AgentStatus::Missing(path) => {
+                format!("unreachable ({} does not exist)", path.display())
+            }
+            AgentStatus::Reachable(path) => format!("reachable at {}", path.display()),
+        };
+        let keys = match (self.key_offers, &self.username) {
+            (0, _) => "never asked for; the connection failed earlier".to_string(),
+            (1, Some(user)) => format!("agent keys offered for {}", user),
+            (n, Some(user)) => format!("agent keys offered for {} {} times, all rejected", user, n),
+            (_, None) => "agent keys offered".to_string(),
+        };
+        let host = match &self.host_key {
+            None => "never received; is the host reachable?".to_string(),
+            Some(key) => {
+                let verdict = match &key.known {
+                    KnownHost::Known => "listed in known_hosts".to_string(),
+                    KnownHost::Mismatch => {
+                        "DOES NOT MATCH known_hosts; the host was reinstalled, or someone is in the way"
+                            .to_string()
+                    }
+                    KnownHost::Unknown { hashed_entries: 0 } => "not in known_hosts".to_string(),
+                    KnownHost::Unknown { hashed_entries } => {
+                        format!("not in known_hosts by name ({} hashed entries not checked)", hashed_entries)
+                    }
+                    KnownHost::NoFile => "no ~/.ssh/known_hosts to check against".to_string(),
+                };
+                format!("{} {}, {}", key.key_type, key.fingerprint, verdict)
+            }
+        };
+        vec![
+            format!("{} ({}): {}", self.remote, self.url, self.error),
+            format!("  ssh-agent: {}", agent),
+            format!("  keys:      {}", keys),
+            format!("  host key:  {}", host),
+        ]
+    }
+}
+
+/// Check whether a URL goes over SSH, including scp-like `user@host:path`
+pub fn is_ssh_url(url: &str) -> bool {
+    if let Some((scheme, _)) = url.split_once("://") {
+        return matches!(scheme, "ssh" | "git+ssh" | "ssh+git");
+    }
+    // scp-like syntax has a colon before any slash
+    match (url.find(':'), url.find('/')) {
+        (Some(colon), Some(slash)) => colon < slash,
+        (Some(_), None) => true,
+        _ => false,
+    }
+}
+
+/// Look up `host`'s key in the text of a known_hosts file
+fn check_known_hosts(text: &str, host: &str, key_type: &str, key: &str) -> KnownHost {
+    let mut hashed_entries = 0;
+    let mut listed = false;
+    for line in text.lines() {
+        let mut fields = line.split_whitespace();
+        let (Some(hosts), Some(line_type), Some(line_key)) =
+            (fields.next(), fields.next(), fields.next())
+        else {
+            continue;
+        };
+        if hosts.starts_with('#') || hosts.starts_with('@') {
+            continue;
+        }
+        if line_key == key {
+            return KnownHost::Known;
+        }
+        if hosts.starts_with("|1|") {
+            hashed_entries += 1;
+            continue;
+        }
+        // Entries for another port read `[host]:port`
+        let names_host = hosts.split(',').any(|name| {
+            name == host
+                || name
+                    .strip_prefix('[')
+                    .and_then(|n| n.split_once("]:"))
+                    .is_some_and(|(n, _)| n == host)
+        });
+        if names_host && line_type == key_type {
+            listed = true;
+        }
+    }
+    match listed {
+        true => KnownHost::Mismatch,
+        false => KnownHost::Unknown { hashed_entries },
+    }
+}
+
+/// Encode bytes as standard base64 with padding, as known_hosts stores keys
+pub fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::new();
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, b)| n | (*b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            match i <= chunk.len() {
+                true => out.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char),
+                false => out.push('='),
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base64() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+    }
+
+    #[test]
+    fn test_is_ssh_url() {
+        assert!(is_ssh_url("git@github.com:me/repo.git"));
+        assert!(is_ssh_url("ssh://git@example.com:2222/repo.git"));
+        assert!(!is_ssh_url("https://github.com/me/repo.git"));
+        assert!(!is_ssh_url("/srv/git/repo.git"));
+        assert!(!is_ssh_url("file:///srv/git/repo.git"));
+    }
+
+    #[test]
+    fn test_check_known_hosts() {
+        let text = "\
+# comment
+github.com,140.82.112.3 ssh-ed25519 AAAAgood
+[git.example.com]:2222 ssh-ed25519 AAAAother
+|1|c2FsdA==|aGFzaA== ssh-rsa AAAAhashed
+";
+        assert_eq!(
+            check_known_hosts(text, "github.com", "ssh-ed25519", "AAAAgood"),
+            KnownHost::Known
+        );
+        assert_eq!(
+            check_known_hosts(text, "github.com", "ssh-ed25519", "AAAAevil"),
+            KnownHost::Mismatch
+        );
+        assert_eq!(
+            check_known_hosts(text, "git.example.com", "ssh-ed25519", "AAAAnew"),
+            KnownHost::Mismatch
+        );
+        // A different key type isn't a mismatch; ssh would just add it
+        assert_eq!(
+            check_known_hosts(text, "github.com", "ssh-rsa", "AAAArsa"),
+            KnownHost::Unknown { hashed_entries: 1 }
+        );
+    }
+
+    #[test]
+    fn test_lines() {
+        let mut diagnostics = SshDiagnostics::new("origin", "git@example.com:repo.git");
+        diagnostics.error = "authentication failed".to_string();
+        diagnostics.agent = AgentStatus::NotSet;
+        diagnostics.username = Some("git".to_string());
+        diagnostics.key_offers = 2;
+        let lines = diagnostics.lines();
+        assert_eq!(
+            lines[0],
+            "origin (git@example.com:repo.git): authentication failed"
+        );
+        assert!(lines[1].contains("SSH_AUTH_SOCK is not set"));
+        assert!(lines[2].contains("2 times, all rejected"));
+        assert!(lines[3].contains("never received"));
+    }
+}
@@ -0,0 +1,428 @@
+use super::{
+    commit::Commit,
+    error::{GitError, Result},
+    pull::{is_dirty, replay},
+    repository::Repository,
+    watch::note_local_write,
+};
+use git2::{BranchType, Oid, build::CheckoutBuilder};
+use std::collections::HashMap;
+
+/// Config key under `branch.<name>` naming the branch it's stacked on
+const PARENT_KEY: &str = "rust-tig-parent";
+/// Config key under `branch.<name>` holding the parent's tip the branch's
+/// own commits start from
+const BASE_KEY: &str = "rust-tig-base";
+
+/// A branch in a stack of dependent branches
+#[derive(Debug, Clone)]
+pub struct StackBranch {
+    pub name: String,
+    /// Branch it's stacked on; the trunk for the bottom of a stack
+    pub parent: String,
+    /// The parent's tip when the branch was last stacked on it; the
+    /// branch's own commits come after
+    pub base: Oid,
+    pub tip: Oid,
+    /// The branch's own commits, newest first
+    pub commits: Vec<Commit>,
+    /// Whether the parent moved on since, so the branch should be restacked
+    pub needs_restack: bool,
+    /// 1 for branches stacked on the trunk, 2 for branches on those, ...
+    pub depth: usize,
+    /// Whether the branch is checked out
+    pub is_head: bool,
+}
+
+/// The local branches stacked on a trunk branch
+#[derive(Debug, Clone)]
+pub struct Stack {
+    pub trunk: String,
+    /// Every branch followed by the branches stacked on it, depth first
+    pub branches: Vec<StackBranch>,
+}
+
+impl Stack {
+    /// Get the branch and every branch stacked on it, directly or not, in
+    /// the order they have to be restacked
+    fn with_descendants(&self, name: &str) -> Vec<&StackBranch> {
+        let Some(start) = self.branches.iter().position(|branch| branch.name == name) else {
+            return Vec::new();
+        };
+        let depth = self.branches[start].depth;
+        let rest = self.branches[start + 1..]
+            .iter()
+            .take_while(|branch| branch.depth > depth);
+        std::iter::once(&self.branches[start]).chain(rest).collect()
+    }
+}
+
+/// Pick the trunk: `init.defaultBranch`, else main, else master
+fn find_trunk(git_repo: &git2::Repository) -> Result<String> {
+    let configured = git_repo.config()?.get_string("init.defaultBranch").ok();
+    configured
+        .into_iter()
+        .chain(["main".to_string(), "master".to_string()])
+        .find(|name| git_repo.find_branch(name, BranchType::Local).is_ok())
+        .ok_or_else(|| GitError::RefNotFound("a trunk branch (main or master)".to_string()))
+}
+
+/// Read the recorded parent and base of a branch, if they still hold: the
+/// parent exists and the base is in the branch's history
+fn recorded(
+    git_repo: &git2::Repository,
+    config: &git2::Config,
+    name: &str,
+    tip: Oid,
+    tips: &HashMap<String, Oid>,
+) -> Option<(String, Oid)> {
+    let parent = config
+        .get_string(&format!("branch.{}.{}", name, PARENT_KEY))
+        .ok()?;
+    let base = Oid::from_str(
+        &config
+            .get_string(&format!("branch.{}.{}", name, BASE_KEY))
+            .ok()?,
+    )
+    .ok()?;
+    let holds = parent != name
+        && tips.contains_key(&parent)
+        && (base == tip || git_repo.graph_descendant_of(tip, base).unwrap_or(false));
+    holds.then_some((parent, base))
+}
+
+/// Work out which branch `name` is stacked on: the nearest other branch
+/// whose tip it contains, else the trunk from where they forked; None for
+/// branches the trunk already contains
+fn infer_parent(
+    git_repo: &git2::Repository,
+    name: &str,
+    tip: Oid,
+    trunk: &str,
+    tips: &HashMap<String, Oid>,
+) -> Result<Option<(String, Oid)>> {
+    let mut nearest: Option<(usize, &String, Oid)> = None;
+    for (other, &other_tip) in tips {
+        if other == name || other_tip == tip || !git_repo.graph_descendant_of(tip, other_tip)? {
+            continue;
+        }
+        let (distance, _) = git_repo.graph_ahead_behind(tip, other_tip)?;
+        // Ties go to the first name, so the answer doesn't depend on
+        // hash map order
+        if nearest.is_none_or(|(best, best_name, _)| (distance, other) < (best, best_name)) {
+            nearest = Some((distance, other, other_tip));
+        }
+    }
+    if let Some((_, parent, base)) = nearest {
+        return Ok(Some((parent.clone(), base)));
+    }
+
+    let trunk_tip = tips[trunk];
+    if tip == trunk_tip || git_repo.graph_descendant_of(trunk_tip, tip)? {
+        return Ok(None);
+    }
+    Ok(git_repo
+        .merge_base(tip, trunk_tip)
+        .ok()
+        .map(|base| (trunk.to_string(), base)))
+}
+
+/// Work out the stack from the relations a restack recorded, inferring the
+/// rest without writing anything
+fn build_stack(git_repo: &git2::Repository) -> Result<Stack> {
+    let trunk = find_trunk(git_repo)?;
+    let head = git_repo
+        .head()
+        .ok()
+        .filter(|head| head.is_branch())
+        .and_then(|head| head.shorthand().map(str::to_string));
+
+    let mut tips = HashMap::new();
+    for branch in git_repo.branches(Some(BranchType::Local))? {
+        let (branch, _) = branch?;
+        if let (Some(name), Some(tip)) = (branch.name()?, branch.get().target()) {
+            tips.insert(name.to_string(), tip);
+        }
+    }
+
+    let config = git_repo.config()?;
+    let mut parents: HashMap<String, (String, Oid)> = HashMap::new();
+    for (name, &tip) in &tips {
+        if *name == trunk {
+            continue;
+        }
+        if let Some(relation) = recorded(git_repo, &config, name, tip, &tips) {
+            parents.insert(name.clone(), relation);
+        } else if let Some(relation) = infer_parent(git_repo, name, tip, &trunk, &tips)? {
+            parents.insert(name.clone(), relation);
+        }
+    }
+
+    // Walk down from the trunk, depth first so a branch's stack follows it
+    // directly; branches in a cycle of recorded parents are never reached
+    let mut branches = Vec::new();
+    let mut pending = vec![(trunk.clone(), 0)];
+    while let Some((name, depth)) = pending.pop() {
+        if let Some((parent, base)) = parents.get(&name) {
+            let tip = tips[&name];
+            let mut walk = git_repo.revwalk()?;
+            walk.push(tip)?;
+            walk.hide(*base)?;
+            let commits = walk
+                .map(|oid| Commit::from_git2(&git_repo.find_commit(oid?)?))
+                .collect::<Result<Vec<_>>>()?;
+            branches.push(StackBranch {
+                needs_restack: tips[parent] != *base,
+                is_head: head.as_ref() == Some(&name),
+                name: name.clone(),
+                parent: parent.clone(),
+                base: *base,
+                tip,
+                commits,
+                depth,
+            });
+        }
+        let mut children: Vec<&String> = parents
+            .iter()
+            .filter(|(_, (parent, _))| *parent == name)
+            .map(|(child, _)| child)
+            .collect();
+        // Popped in reverse, so the first name comes out first
+        children.sort_by(|a, b| b.cmp(a));
+        pending.extend(children.into_iter().map(|child| (child.clone(), depth + 1)));
+    }
+
+    Ok(Stack { trunk, branches })
+}
+
+/// Load the stacks of local branches on the trunk branch
+pub async fn load_stack(repo: &Repository) -> Result<Stack> {
+    let repo = repo.clone();
+
+    tokio::task::spawn_blocking(move || build_stack(&repo.open_git2()?))
+        .await
+        .map_err(GitError::task_join("load the branch stack"))?
+}
+
+/// Rebase `name`'s own commits onto its parent's tip, then do the same for
+/// every branch stacked on it in turn
+///
+/// Returns the branches that moved and how many commits each replayed.
+/// Nothing is touched when any rebase would conflict, or when a branch to
+/// move is checked out with uncommitted changes. Otherwise each branch's
+/// parent and base are recorded in the git config, so it still knows its
+/// own commits after its parent is rewritten.
+pub async fn restack(repo: &Repository, name: String) -> Result<Vec<(String, usize)>> {
+    let repo = repo.clone();
+
+    tokio::task::spawn_blocking(move || {
+        let git_repo = repo.open_git2()?;
+        let stack = build_stack(&git_repo)?;
+        let branches = stack.with_descendants(&name);
+        if branches.is_empty() {
+            return Err(GitError::CannotRebase(format!(
+                "{} is not stacked on {}",
+                name, stack.trunk
+            )));
+        }
+
+        // Work out every new tip in memory first
+        let mut moved: HashMap<&str, (Oid, Oid, usize)> = HashMap::new();
+        for branch in &branches {
+            let onto = match moved.get(branch.parent.as_str()) {
+                Some(&(tip, _, _)) => tip,
+                None => git_repo.refname_to_id(&format!("refs/heads/{}", branch.parent))?,
+            };
+            if onto == branch.base {
+                continue;
+            }
+            let (tip, rebased) = replay(&git_repo, branch.tip, branch.base, onto)?;
+            moved.insert(&branch.name, (tip, onto, rebased.len()));
+        }
+        if branches
+            .iter()
+            .any(|b| b.is_head && moved.contains_key(b.name.as_str()))
+            && is_dirty(&git_repo)?
+        {
+            return Err(GitError::CannotRebase(
+                "the checked out branch has uncommitted changes".to_string(),
+            ));
+        }
+
+        let mut config = git_repo.config()?;
+        let mut report = Vec::new();
+        for branch in &branches {
+            let base = match moved.get(branch.name.as_str()) {
+                Some(&(tip, onto, count)) => {
+                    if branch.is_head {
+                        let target = git_repo.find_object(tip, None)?;
+                        git_repo.checkout_tree(&target, Some(CheckoutBuilder::new().safe()))?;
+                    }
+                    let message = format!("rust-tig: restack onto {}", branch.parent);
+                    git_repo.reference(
+                        &format!("refs/heads/{}", branch.name),
+                        tip,
+                        true,
+                        &message,
+                    )?;
+                    report.push((branch.name.clone(), count));
+                    onto
+                }
+                None => branch.base,
+            };
+            config.set_str(
+                &format!("branch.{}.{}", branch.name, PARENT_KEY),
+                &branch.parent,
+            )?;
+            config.set_str(
+                &format!("branch.{}.{}", branch.name, BASE_KEY),
+                &base.to_string(),
+            )?;
+        }
+        note_local_write(repo.path());
+        Ok(report)
+    })
+    .await
+    .map_err(GitError::task_join("restack branches"))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::RepoBuilder;
+
+    /// Build trunk <- a <- b, with b checked out
+    async fn stacked() -> (tempfile::TempDir, Repository, String) {
+        let builder = RepoBuilder::new().file("base.txt", "base\n").commit("Base");
+        let trunk = builder
+            .git2()
+            .head()
+            .unwrap()
+            .shorthand()
+            .unwrap()
+            .to_string();
+        let (temp_dir, repo) = builder
+            .branch("a")
+            .checkout("a")
+            .file("a.txt", "a\n")
+            .commit("A")
+            .branch("b")
+            .checkout("b")
+            .file("b.txt", "b\n")
+            .commit("B1")
+            .file("b.txt", "b\nb\n")
+            .commit("B2")
+            .open()
+            .await;
+        (temp_dir, repo, trunk)
+    }
+
+    fn summary(stack: &Stack) -> Vec<(String, String, usize, bool)> {
+        stack
+            .branches
+            .iter()
+            .map(|b| {
+                (
+                    b.name.clone(),
+                    b.parent.clone(),
+                    b.commits.len(),
+                    b.needs_restack,
+                )
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_stack_is_inferred_and_remembered() {
+        let (_temp_dir, repo, trunk) = stacked().await;
+        let git_repo = repo.open_git2().unwrap();
+        let stack = load_stack(&repo).await.unwrap();
+        assert_eq!(stack.trunk, trunk);
+        assert_eq!(
+            summary(&stack),
+            [
+                ("a".to_string(), trunk.clone(), 1, false),
+                ("b".to_string(), "a".to_string(), 2, false)
+            ]
+        );
+        assert_eq!(stack.branches[1].depth, 2);
+        assert!(stack.branches[1].is_head);
+        // Loading only reads
+        let recorded = format!("branch.b.{}", PARENT_KEY);
+        assert!(git_repo.config().unwrap().get_string(&recorded).is_err());
+
+        // Once a restack recorded them, rewriting a leaves b's own commits
+        // where they were
+        assert!(restack(&repo, "a".to_string()).await.unwrap().is_empty());
+        assert_eq!(
+            git_repo.config().unwrap().get_string(&recorded).unwrap(),
+            "a"
+        );
+        let a = git_repo
+            .find_branch("a", BranchType::Local)
+            .unwrap()
+            .get()
+            .peel_to_commit()
+            .unwrap();
+        a.amend(
+            Some("refs/heads/a"),
+            None,
+            None,
+            None,
+            Some("A amended"),
+            None,
+        )
+        .unwrap();
+        let stack = load_stack(&repo).await.unwrap();
+        assert_eq!(
+            summary(&stack)[1],
+            ("b".to_string(), "a".to_string(), 2, true)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_restack_moves_descendants() {
+        let (_temp_dir, repo, trunk) = stacked().await;
+
+        // The trunk moves on; a and b both follow it
+        let git_repo = repo.open_git2().unwrap();
+        let trunk_commit = git_repo
+            .find_branch(&trunk, BranchType::Local)
+            .unwrap()
+            .get()
+            .peel_to_commit()
+            .unwrap();
+        let signature = git_repo.signature().unwrap();
+        let trunk_tip = git_repo
+            .commit(
+                Some(&format!("refs/heads/{}", trunk)),
+                &signature,
+                &signature,
+                "Trunk",
+                &trunk_commit.tree().unwrap(),
+                &[&trunk_commit],
+            )
+            .unwrap();
+        assert!(load_stack(&repo).await.unwrap().branches[0].needs_restack);
+
+        let report = restack(&repo, "a".to_string()).await.unwrap();
+        assert_eq!(report, [("a".to_string(), 1), ("b".to_string(), 2)]);
+        let stack = load_stack(&repo).await.unwrap();
+        assert!(stack.branches.iter().all(|b| !b.needs_restack));
+        assert_eq!(stack.branches[0].base, trunk_tip);
+        assert!(
+            git_repo
+                .graph_descendant_of(stack.branches[1].tip, trunk_tip)
+                .unwrap()
+        );
+        // b is checked out, so its files follow
+        assert_eq!(
+            git_repo.head().unwrap().target(),
+            Some(stack.branches[1].tip)
+        );
+        assert!(git_repo.workdir().unwrap().join("b.txt").exists());
+
+        assert!(restack(&repo, "a".to_string()).await.unwrap().is_empty());
+        assert!(restack(&repo, "nope".to_string()).await.is_err());
+    }
+}
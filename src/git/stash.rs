@@ -0,0 +1,169 @@
+use super::{
+    commit::Commit,
+    error::{GitError, Result},
+    repository::Repository,
+    watch::note_local_write,
+};
+use git2::{Oid, StashFlags};
+
+/// An entry of `git stash list`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StashEntry {
+    /// Position in the stash, 0 being the newest, as in `stash@{0}`
+    pub index: usize,
+    /// The stash commit, whose first parent is where the changes were made
+    pub commit: Commit,
+    /// The message, like "WIP on main: 1234567 Summary"
+    pub message: String,
+}
+
+impl StashEntry {
+    /// The name git gives the entry, like `stash@{0}`
+    pub fn name(&self) -> String {
+        format!("stash@{{{}}}", self.index)
+    }
+}
+
+/// List the stash, newest first
+pub async fn stash_list(repo: &Repository) -> Result<Vec<StashEntry>> {
+    let repo = repo.clone();
+
+    tokio::task::spawn_blocking(move || {
+        let mut git_repo = repo.open_git2()?;
+        let mut stashes = Vec::new();
+        git_repo.stash_foreach(|index, message, id| {
+            stashes.push((index, message.to_string(), *id));
+            true
+        })?;
+        stashes
+            .into_iter()
+            .map(|(index, message, id)| {
+                let commit = Commit::from_git2(&git_repo.find_commit(id)?)?;
+                Ok(StashEntry {
+                    index,
+                    commit,
+                    message,
+                })
+            })
+            .collect()
+    })
+    .await
+    .map_err(GitError::task_join("list the stash"))?
+}
+
+/// Stash the uncommitted changes, untracked files too if asked, returning
+/// the new stash commit
+pub async fn stash_push(
+    repo: &Repository,
+    message: Option<String>,
+    include_untracked: bool,
+) -> Result<Oid> {
+    let repo = repo.clone();
+
+    tokio::task::spawn_blocking(move || {
+        let mut git_repo = repo.open_git2()?;
+        let signature = git_repo.signature()?;
+        let flags = match include_untracked {
+            true => StashFlags::INCLUDE_UNTRACKED,
+            false => StashFlags::DEFAULT,
+        };
+        let id = git_repo
+            .stash_save2(&signature, message.as_deref(), Some(flags))
+            .map_err(|e| match e.code() {
+                git2::ErrorCode::NotFound => GitError::NothingToStash,
+                _ => e.into(),
+            })?;
+        note_local_write(repo.path());
+        Ok(id)
+    })
+    .await
+    .map_err(GitError::task_join("stash the changes"))?
+}
+
+/// Apply a stash entry to the working tree and drop it
+pub async fn stash_pop(repo: &Repository, index: usize) -> Result<()> {
+    let repo = repo.clone();
+
+    tokio::task::spawn_blocking(move || {
+        let mut git_repo = repo.open_git2()?;
+        // A pop that conflicts keeps the entry, like git
+        let result = git_repo.stash_pop(index, None);
+        note_local_write(repo.path());
+        Ok(result?)
+    })
+    .await
+    .map_err(GitError::task_join("pop the stash"))?
+}
+
+/// Apply a stash entry to the working tree, keeping it in the stash
+pub async fn stash_apply(repo: &Repository, index: usize) -> Result<()> {
+    let repo = repo.clone();
+
+    tokio::task::spawn_blocking(move || {
+        let mut git_repo = repo.open_git2()?;
+        let result = git_repo.stash_apply(index, None);
+        note_local_write(repo.path());
+        Ok(result?)
+    })
+    .await
+    .map_err(GitError::task_join("apply the stash"))?
+}
+
+/// Delete a stash entry; its commit stays findable with `:lost` until
+/// pruned
+pub async fn stash_drop(repo: &Repository, index: usize) -> Result<()> {
+    let repo = repo.clone();
+
+    tokio::task::spawn_blocking(move || {
+        let mut git_repo = repo.open_git2()?;
+        git_repo.stash_drop(index)?;
+        Ok(())
+    })
+    .await
+    .map_err(GitError::task_join("drop the stash entry"))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::RepoBuilder;
+
+    #[tokio::test]
+    async fn test_push_list_apply_pop_and_drop() {
+        let (temp_dir, repo) = RepoBuilder::new()
+            .file("a.txt", "one\n")
+            .commit("Initial")
+            .write("a.txt", "two\n")
+            .open()
+            .await;
+        let file = temp_dir.path().join("a.txt");
+
+        assert!(stash_list(&repo).await.unwrap().is_empty());
+        stash_push(&repo, Some("first".to_string()), false)
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "one\n");
+        assert!(matches!(
+            stash_push(&repo, None, false).await,
+            Err(GitError::NothingToStash)
+        ));
+
+        std::fs::write(temp_dir.path().join("new.txt"), "new\n").unwrap();
+        stash_push(&repo, None, true).await.unwrap();
+        assert!(!temp_dir.path().join("new.txt").exists());
+
+        let stashes = stash_list(&repo).await.unwrap();
+        assert_eq!(stashes.len(), 2);
+        assert_eq!(stashes[1].name(), "stash@{1}");
+        assert!(stashes[1].message.ends_with("first"));
+
+        stash_apply(&repo, 1).await.unwrap();
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "two\n");
+        assert_eq!(stash_list(&repo).await.unwrap().len(), 2);
+        stash_drop(&repo, 1).await.unwrap();
+
+        stash_pop(&repo, 0).await.unwrap();
+        assert!(temp_dir.path().join("new.txt").exists());
+        assert!(stash_list(&repo).await.unwrap().is_empty());
+    }
+}
@@ -1,5 +1,11 @@
-use super::{error::Result, repository::Repository};
-use git2::{Status as Git2Status, StatusOptions};
+use super::{
+    error::{GitError, Result},
+    repository::Repository,
+    watch::note_local_write,
+};
+use git2::{IndexEntryExtendedFlag, Oid, Status as Git2Status, StatusOptions};
+use std::collections::HashSet;
+use std::path::Path;
 
 /// Status of a file in the working directory
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -28,6 +34,8 @@ pub enum EntryStatus {
     Ignored,
     /// File is conflicted
     Conflicted,
+    /// File is excluded from the working tree by sparse-checkout
+    SkipWorktree,
 }
 
 impl EntryStatus {
@@ -70,6 +78,7 @@ impl EntryStatus {
             EntryStatus::WorktreeTypeChange => " T",
             EntryStatus::Ignored => "!!",
             EntryStatus::Conflicted => "UU",
+            EntryStatus::SkipWorktree => "S ",
         }
     }
 
@@ -88,6 +97,7 @@ impl EntryStatus {
             EntryStatus::WorktreeTypeChange => "typechange",
             EntryStatus::Ignored => "ignored",
             EntryStatus::Conflicted => "conflicted",
+            EntryStatus::SkipWorktree => "not in sparse checkout",
         }
     }
 }
@@ -110,6 +120,21 @@ impl StatusEntry {
     }
 }
 
+/// Untracked directories holding at least this many files are summarized
+/// as one entry
+pub const UNTRACKED_SUMMARY_MIN_FILES: usize = 50;
+
+/// A directory with no tracked files in it, summarized by its file count
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UntrackedDir {
+    /// Directory path with a trailing `/`
+    pub path: String,
+    /// Number of untracked files under it
+    pub files: usize,
+    /// Whether its files are listed in `untracked` instead of the directory
+    pub expanded: bool,
+}
+
 /// Repository status information
 #[derive(Debug, Clone)]
 pub struct Status {
@@ -117,6 +142,12 @@ pub struct Status {
     pub unstaged: Vec<StatusEntry>,
     pub untracked: Vec<StatusEntry>,
     pub conflicted: Vec<StatusEntry>,
+    /// Tracked files left out of the working tree by sparse-checkout;
+    /// not counted as changes
+    pub sparse: Vec<StatusEntry>,
+    /// Untracked directories with many files, sorted by path; each is one
+    /// entry in `untracked` unless it was expanded
+    pub untracked_dirs: Vec<UntrackedDir>,
 }
 
 impl Status {
@@ -126,12 +157,25 @@ impl Status {
             unstaged: Vec::new(),
             untracked: Vec::new(),
             conflicted: Vec::new(),
+            sparse: Vec::new(),
+            untracked_dirs: Vec::new(),
         }
     }
 
     /// Get total count of changes
     pub fn total_count(&self) -> usize {
-        self.staged.len() + self.unstaged.len() + self.untracked.len() + self.conflicted.len()
+        self.staged.len() + self.unstaged.len() + self.untracked_count() + self.conflicted.len()
+    }
+
+    /// Count untracked files, including those of summarized directories
+    pub fn untracked_count(&self) -> usize {
+        let folded: usize = self
+            .untracked_dirs
+            .iter()
+            .filter(|dir| !dir.expanded)
+            .map(|dir| dir.files.saturating_sub(1))
+            .sum();
+        self.untracked.len() + folded
     }
 
     /// Check if there are any changes
@@ -200,31 +244,134 @@ fn parse_status_flags(flags: Git2Status) -> Vec<EntryStatus> {
     statuses
 }
 
+/// How much work a status scan does, for trading detail against speed on
+/// large repositories
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatusLoadOptions {
+    /// Pair up deleted and added files as renames
+    pub detect_renames: bool,
+    /// List every file inside untracked directories instead of just the
+    /// directory
+    pub recurse_untracked_dirs: bool,
+    /// Paths left out of the scan; a trailing `/` excludes a directory
+    pub exclude_paths: Vec<String>,
+    /// Summarized untracked directories whose files are listed anyway
+    pub expanded_untracked: HashSet<String>,
+    /// Write refreshed file stat data back to the index so the next scan
+    /// can skip unchanged files
+    pub update_index: bool,
+}
+
+impl Default for StatusLoadOptions {
+    fn default() -> Self {
+        Self {
+            detect_renames: false,
+            recurse_untracked_dirs: true,
+            exclude_paths: Vec::new(),
+            expanded_untracked: HashSet::new(),
+            update_index: false,
+        }
+    }
+}
+
+impl StatusLoadOptions {
+    /// Check whether `path` falls under one of the excluded paths
+    fn excludes(&self, path: &str) -> bool {
+        self.exclude_paths.iter().any(|excluded| {
+            let excluded = excluded.trim_matches('/');
+            !excluded.is_empty()
+                && path
+                    .strip_prefix(excluded)
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+        })
+    }
+}
+
 /// Load the repository status asynchronously
 pub async fn load_status(repo: &Repository) -> Result<Status> {
+    load_status_with(repo, StatusLoadOptions::default()).await
+}
+
+/// Load the repository status with tuned scan options
+pub async fn load_status_with(repo: &Repository, options: StatusLoadOptions) -> Result<Status> {
     let repo_path = repo.path().to_path_buf();
 
     tokio::task::spawn_blocking(move || {
-        let git_repo = git2::Repository::open(repo_path)?;
+        let git_repo = git2::Repository::open(&repo_path)?;
         let mut status = Status::new();
 
+        // Untracked directories are listed whole and walked below, so big
+        // ones never turn into one entry per file
         let mut opts = StatusOptions::new();
         opts.include_untracked(true);
-        opts.recurse_untracked_dirs(true);
+        opts.recurse_untracked_dirs(false);
         opts.exclude_submodules(true);
+        opts.renames_head_to_index(options.detect_renames);
+        opts.renames_index_to_workdir(options.detect_renames);
+        opts.update_index(options.update_index);
+        // In-memory ignore rules keep libgit2 from walking excluded
+        // untracked directories at all; tracked files under them are
+        // filtered out below
+        for excluded in &options.exclude_paths {
+            git_repo.add_ignore_rule(&format!("/{}", excluded.trim_start_matches('/')))?;
+        }
 
+        let index_path = git_repo.path().join("index");
+        let index_mtime = || {
+            std::fs::metadata(&index_path)
+                .and_then(|m| m.modified())
+                .ok()
+        };
+        let mtime_before = index_mtime();
         let statuses = git_repo.statuses(Some(&mut opts))?;
 
-        for entry in statuses.iter() {
-            let path = entry
-                .path()
-                .unwrap_or("<unknown>")
-                .to_string();
+        // Sparse-checkout marks the files it leaves out as skip-worktree;
+        // their absence is expected, not a deletion
+        let index = git_repo.index()?;
+        let sparse_paths: HashSet<String> = index
+            .iter()
+            .filter(|e| {
+                IndexEntryExtendedFlag::from_bits_truncate(e.flags_extended).is_skip_worktree()
+            })
+            .map(|e| String::from_utf8_lossy(&e.path).into_owned())
+            .collect();
+
+        // Refreshing the stat cache rewrites the index; that is not an
+        // outside change for the watcher
+        if options.update_index && index_mtime() != mtime_before {
+            note_local_write(&repo_path);
+        }
 
+        for entry in statuses.iter() {
             let flags = entry.status();
+
+            // A renamed entry is listed under its new name
+            let renamed_to = if flags.is_index_renamed() {
+                entry
+                    .head_to_index()
+                    .and_then(|d| d.new_file().path().map(|p| p.to_path_buf()))
+            } else if flags.is_wt_renamed() {
+                entry
+                    .index_to_workdir()
+                    .and_then(|d| d.new_file().path().map(|p| p.to_path_buf()))
+            } else {
+                None
+            };
+            let path = match renamed_to {
+                Some(path) => path.to_string_lossy().into_owned(),
+                None => entry.path().unwrap_or("<unknown>").to_string(),
+            };
+            if options.excludes(&path) {
+                continue;
+            }
+
             let entry_statuses = parse_status_flags(flags);
 
             for entry_status in entry_statuses {
+                if entry_status == EntryStatus::WorktreeDeleted && sparse_paths.contains(&path) {
+                    continue;
+                }
+
                 let status_entry = StatusEntry::new(path.clone(), entry_status, false);
 
                 if flags.is_conflicted() {
@@ -232,6 +379,12 @@ pub async fn load_status(repo: &Repository) -> Result<Status> {
                 } else if entry_status.is_staged() {
                     status.staged.push(status_entry);
                 } else if entry_status == EntryStatus::WorktreeNew {
+                    if options.recurse_untracked_dirs && path.ends_with('/') {
+                        let (files, dir) = untracked_files(&git_repo, &path, &options)?;
+                        status.untracked.extend(files);
+                        status.untracked_dirs.extend(dir);
+                        continue;
+                    }
                     status.untracked.push(status_entry);
                 } else if entry_status.is_unstaged() {
                     status.unstaged.push(status_entry);
@@ -239,115 +392,370 @@ pub async fn load_status(repo: &Repository) -> Result<Status> {
             }
         }
 
+        let mut sparse_paths: Vec<String> = sparse_paths.into_iter().collect();
+        sparse_paths.sort();
+        status.sparse = sparse_paths
+            .into_iter()
+            .map(|path| StatusEntry::new(path, EntryStatus::SkipWorktree, false))
+            .collect();
+
         Ok(status)
     })
     .await
-    .map_err(|_| super::error::GitError::RepoNotFound)?
+    .map_err(GitError::task_join("load status"))?
+}
+
+/// List the files of an untracked directory, or just the directory and a
+/// count of its files when it has enough to be worth folding; past that
+/// many the files are only counted
+fn untracked_files(
+    git_repo: &git2::Repository,
+    dir: &str,
+    options: &StatusLoadOptions,
+) -> Result<(Vec<StatusEntry>, Option<UntrackedDir>)> {
+    let Some(workdir) = git_repo.workdir() else {
+        return Ok((Vec::new(), None));
+    };
+    let entry = |path| StatusEntry::new(path, EntryStatus::WorktreeNew, false);
+    // Nested repositories are listed whole, as git does
+    let is_repo = |path: &str| workdir.join(path).join(".git").exists();
+    if is_repo(dir) {
+        return Ok((vec![entry(dir.to_string())], None));
+    }
+
+    let expanded = options.expanded_untracked.contains(dir);
+    let mut files = Vec::new();
+    let mut count = 0;
+    let mut pending = vec![dir.to_string()];
+    while let Some(current) = pending.pop() {
+        for item in std::fs::read_dir(workdir.join(&current))? {
+            let item = item?;
+            let is_dir = item.file_type()?.is_dir();
+            let name = item.file_name().to_string_lossy().into_owned();
+            let path = match is_dir {
+                true => format!("{}{}/", current, name),
+                false => format!("{}{}", current, name),
+            };
+            if git_repo.is_path_ignored(Path::new(&path))? {
+                continue;
+            }
+            if is_dir && !is_repo(&path) {
+                pending.push(path);
+                continue;
+            }
+            count += 1;
+            if expanded || files.len() < UNTRACKED_SUMMARY_MIN_FILES {
+                files.push(path);
+            }
+        }
+    }
+
+    let summary = (count >= UNTRACKED_SUMMARY_MIN_FILES).then(|| UntrackedDir {
+        path: dir.to_string(),
+        files: count,
+        expanded,
+    });
+    if summary.is_some() && !expanded {
+        return Ok((vec![entry(dir.to_string())], summary));
+    }
+    files.sort();
+    Ok((files.into_iter().map(entry).collect(), summary))
+}
+
+/// A change to one path of the index
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IndexOp {
+    /// Add the working directory version of the path, or of every file
+    /// under it when it ends with `/`
+    Stage(String),
+    /// Reset the path to its version in HEAD
+    Unstage(String),
 }
 
 /// Stage a file (add to index)
 pub async fn stage_file(repo: &Repository, path: String) -> Result<()> {
+    apply_index_ops(repo, vec![IndexOp::Stage(path)]).await
+}
+
+/// Unstage a file (reset from index to HEAD)
+pub async fn unstage_file(repo: &Repository, path: String) -> Result<()> {
+    apply_index_ops(repo, vec![IndexOp::Unstage(path)]).await
+}
+
+/// Apply index changes in order and write the index once at the end
+///
+/// Batching keeps quick successive stage keys from racing each other on
+/// the index file; if any change fails, none are written.
+pub async fn apply_index_ops(repo: &Repository, ops: Vec<IndexOp>) -> Result<()> {
     let repo_path = repo.path().to_path_buf();
 
     tokio::task::spawn_blocking(move || {
-        let git_repo = git2::Repository::open(repo_path)?;
+        let git_repo = git2::Repository::open(&repo_path)?;
         let mut index = git_repo.index()?;
+        let mut head_tree = None;
+
+        for op in &ops {
+            match op {
+                IndexOp::Stage(path) if path.ends_with('/') => {
+                    // Add every file in the directory
+                    index.add_all([path.as_str()], git2::IndexAddOption::DEFAULT, None)?;
+                }
+                IndexOp::Stage(path) => {
+                    // Add the file to the index
+                    index.add_path(std::path::Path::new(path))?;
+                }
+                IndexOp::Unstage(path) => {
+                    if head_tree.is_none() {
+                        head_tree = Some(git_repo.head()?.peel_to_commit()?.tree()?);
+                    }
+                    if let Some(tree) = &head_tree {
+                        unstage_path(&mut index, tree, path)?;
+                    }
+                }
+            }
+        }
 
-        // Add the file to the index
-        index.add_path(std::path::Path::new(&path))?;
         index.write()?;
+        note_local_write(&repo_path);
 
         Ok(())
     })
     .await
-    .map_err(|_| super::error::GitError::RepoNotFound)?
+    .map_err(GitError::task_join("update the index"))?
 }
 
-/// Unstage a file (reset from index to HEAD)
-pub async fn unstage_file(repo: &Repository, path: String) -> Result<()> {
+/// Reset the index entry for `path` to match `head_tree`
+fn unstage_path(index: &mut git2::Index, head_tree: &git2::Tree, path: &str) -> Result<()> {
+    // Get the object from HEAD for this path
+    let head_entry = head_tree.get_path(std::path::Path::new(path));
+
+    if let Ok(entry) = head_entry {
+        // File exists in HEAD, restore it to index
+        index.add(&git2::IndexEntry {
+            ctime: git2::IndexTime::new(0, 0),
+            mtime: git2::IndexTime::new(0, 0),
+            dev: 0,
+            ino: 0,
+            mode: entry.filemode() as u32,
+            uid: 0,
+            gid: 0,
+            file_size: 0,
+            id: entry.id(),
+            flags: 0,
+            flags_extended: 0,
+            path: path.as_bytes().to_vec(),
+        })?;
+    } else {
+        // File doesn't exist in HEAD, remove from index
+        index.remove_path(std::path::Path::new(path))?;
+    }
+    Ok(())
+}
+
+/// Commit the staged changes on top of HEAD, or as the root commit of an
+/// empty repository. Author and committer come from the git config, except
+/// that committing a conflicted cherry-pick's resolution keeps the picked
+/// commit's author. Committing finishes a cherry-pick, revert or merge
+/// waiting on the staged changes; a merge's commit has the merged commits
+/// as further parents.
+pub async fn commit_staged(repo: &Repository, message: String) -> Result<Oid> {
     let repo_path = repo.path().to_path_buf();
 
     tokio::task::spawn_blocking(move || {
-        let git_repo = git2::Repository::open(repo_path)?;
+        let mut git_repo = git2::Repository::open(&repo_path)?;
+        let mut index = git_repo.index()?;
+        if index.has_conflicts() {
+            let paths = index
+                .conflicts()?
+                .flatten()
+                .filter_map(|c| c.our.or(c.their).or(c.ancestor))
+                .map(|entry| String::from_utf8_lossy(&entry.path).into_owned())
+                .collect();
+            return Err(GitError::MergeConflict { paths });
+        }
+        let tree_id = index.write_tree()?;
+        let mut merged = Vec::new();
+        if git_repo.state() == git2::RepositoryState::Merge {
+            git_repo.mergehead_foreach(|id| {
+                merged.push(*id);
+                true
+            })?;
+        }
+
+        let parent = match git_repo.head() {
+            Ok(head) => Some(head.peel_to_commit()?),
+            Err(e) if e.code() == git2::ErrorCode::UnbornBranch => None,
+            Err(e) => return Err(e.into()),
+        };
+
+        // Refuse empty commits: nothing in the index, or the same tree as
+        // HEAD; a merge may keep HEAD's tree and still record the merge
+        let state = git_repo.state();
+        let unchanged = match &parent {
+            Some(parent) => parent.tree_id() == tree_id,
+            None => index.is_empty(),
+        };
+        if unchanged && state != git2::RepositoryState::Merge {
+            return Err(GitError::NothingToCommit);
+        }
+
+        let tree = git_repo.find_tree(tree_id)?;
+        let signature = git_repo.signature()?;
+        let author = match state == git2::RepositoryState::CherryPick {
+            true => git_repo
+                .find_reference("CHERRY_PICK_HEAD")
+                .and_then(|pick| pick.peel_to_commit())
+                .map_or_else(|_| signature.clone(), |pick| pick.author().to_owned()),
+            false => signature.clone(),
+        };
+        let merged = merged
+            .into_iter()
+            .map(|id| git_repo.find_commit(id))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        let parents: Vec<&git2::Commit> = parent.iter().chain(&merged).collect();
+        let oid = git_repo.commit(Some("HEAD"), &author, &signature, &message, &tree, &parents)?;
+        if matches!(
+            state,
+            git2::RepositoryState::CherryPick
+                | git2::RepositoryState::Revert
+                | git2::RepositoryState::Merge
+        ) {
+            git_repo.cleanup_state()?;
+        }
+        note_local_write(&repo_path);
 
-        // Get HEAD tree
-        let head = git_repo.head()?;
-        let head_commit = head.peel_to_commit()?;
-        let head_tree = head_commit.tree()?;
+        Ok(oid)
+    })
+    .await
+    .map_err(GitError::task_join("commit the staged files"))?
+}
 
-        // Get the object from HEAD for this path
-        let head_entry = head_tree.get_path(std::path::Path::new(&path));
+/// Get HEAD's message to start an amend from, and the remote-tracking
+/// branch that already has HEAD, if any
+pub async fn amend_start(repo: &Repository) -> Result<(String, Option<String>)> {
+    let repo_path = repo.path().to_path_buf();
 
-        // Reset the index entry to match HEAD
+    tokio::task::spawn_blocking(move || {
+        let git_repo = git2::Repository::open(&repo_path)?;
+        let head = head_to_amend(&git_repo)?;
+        let message = head.message().unwrap_or_default().trim_end().to_string();
+        Ok((message, pushed_to(&git_repo, head.id())?))
+    })
+    .await
+    .map_err(GitError::task_join("read the commit to amend"))?
+}
+
+/// Replace HEAD with a commit of the index and `message`, keeping its
+/// author and parents. A HEAD some remote-tracking branch already has is
+/// only rewritten with `allow_pushed`.
+pub async fn amend_head(repo: &Repository, message: String, allow_pushed: bool) -> Result<Oid> {
+    let repo_path = repo.path().to_path_buf();
+
+    tokio::task::spawn_blocking(move || {
+        let git_repo = git2::Repository::open(&repo_path)?;
+        let head = head_to_amend(&git_repo)?;
+        if !allow_pushed && let Some(remote) = pushed_to(&git_repo, head.id())? {
+            return Err(GitError::AlreadyPushed(remote));
+        }
         let mut index = git_repo.index()?;
+        if index.has_conflicts() {
+            return Err(GitError::MergeConflict { paths: Vec::new() });
+        }
+        let tree = git_repo.find_tree(index.write_tree()?)?;
+        let signature = git_repo.signature()?;
+        let oid = head.amend(
+            Some("HEAD"),
+            None,
+            Some(&signature),
+            None,
+            Some(&message),
+            Some(&tree),
+        )?;
+        note_local_write(&repo_path);
 
-        if let Ok(entry) = head_entry {
-            // File exists in HEAD, restore it to index
-            index.add(&git2::IndexEntry {
-                ctime: git2::IndexTime::new(0, 0),
-                mtime: git2::IndexTime::new(0, 0),
-                dev: 0,
-                ino: 0,
-                mode: entry.filemode() as u32,
-                uid: 0,
-                gid: 0,
-                file_size: 0,
-                id: entry.id(),
-                flags: 0,
-                flags_extended: 0,
-                path: path.as_bytes().to_vec(),
-            })?;
-        } else {
-            // File doesn't exist in HEAD, remove from index
-            index.remove_path(std::path::Path::new(&path))?;
+        Ok(oid)
+    })
+    .await
+    .map_err(GitError::task_join("amend the last commit"))?
+}
+
+fn head_to_amend(git_repo: &git2::Repository) -> Result<git2::Commit<'_>> {
+    match git_repo.head() {
+        Ok(head) => Ok(head.peel_to_commit()?),
+        Err(e) if e.code() == git2::ErrorCode::UnbornBranch => Err(GitError::NothingToAmend),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Find a remote-tracking branch that has the commit `id`
+fn pushed_to(git_repo: &git2::Repository, id: Oid) -> Result<Option<String>> {
+    for reference in git_repo.references_glob("refs/remotes/*")?.flatten() {
+        let Some(tip) = reference.target() else {
+            continue; // refs/remotes/origin/HEAD
+        };
+        if tip == id || git_repo.graph_descendant_of(tip, id)? {
+            return Ok(reference.shorthand().map(str::to_string));
         }
+    }
+    Ok(None)
+}
 
-        index.write()?;
+/// Read the file `commit.template` names in the git config, without its
+/// comment lines, or None when no template is set
+///
+/// A relative path is taken from the top of the working tree. While a
+/// cherry-pick or revert waits to be committed, its message is used
+/// instead.
+pub async fn commit_template(repo: &Repository) -> Result<Option<String>> {
+    let repo_path = repo.path().to_path_buf();
 
-        Ok(())
+    tokio::task::spawn_blocking(move || {
+        let git_repo = git2::Repository::open(&repo_path)?;
+        let pending = match git_repo.state() {
+            git2::RepositoryState::CherryPick
+            | git2::RepositoryState::Revert
+            | git2::RepositoryState::Merge => git_repo.message().ok(),
+            _ => None,
+        };
+        let text = match pending {
+            Some(message) => message,
+            None => {
+                let path = match git_repo.config()?.get_path("commit.template") {
+                    Ok(path) => path,
+                    Err(e) if e.code() == git2::ErrorCode::NotFound => return Ok(None),
+                    Err(e) => return Err(e.into()),
+                };
+                let path = match git_repo.workdir() {
+                    Some(workdir) if path.is_relative() => workdir.join(path),
+                    _ => path,
+                };
+                std::fs::read_to_string(path)?
+            }
+        };
+        let lines: Vec<&str> = text.lines().filter(|line| !line.starts_with('#')).collect();
+        Ok(Some(lines.join("\n").trim_end_matches('\n').to_string()))
     })
     .await
-    .map_err(|_| super::error::GitError::RepoNotFound)?
+    .map_err(GitError::task_join("read the commit template"))?
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::testing::RepoBuilder;
     use std::fs;
+    use std::path::Path;
     use tempfile::TempDir;
 
     async fn create_test_repo_with_changes() -> (TempDir, Repository) {
-        let temp_dir = TempDir::new().unwrap();
-        let repo_path = temp_dir.path();
-
-        let git_repo = git2::Repository::init(repo_path).unwrap();
-        let sig = git2::Signature::now("Test", "test@example.com").unwrap();
-
-        // Create initial commit
-        fs::write(repo_path.join("existing.txt"), "existing content\n").unwrap();
-        let mut index = git_repo.index().unwrap();
-        index.add_path(std::path::Path::new("existing.txt")).unwrap();
-        index.write().unwrap();
-        let tree_id = index.write_tree().unwrap();
-        let tree = git_repo.find_tree(tree_id).unwrap();
-        git_repo
-            .commit(Some("HEAD"), &sig, &sig, "Initial", &tree, &[])
-            .unwrap();
-
-        // Create staged changes
-        fs::write(repo_path.join("staged.txt"), "staged content\n").unwrap();
-        let mut index = git_repo.index().unwrap();
-        index.add_path(std::path::Path::new("staged.txt")).unwrap();
-        index.write().unwrap();
-
-        // Create unstaged changes
-        fs::write(repo_path.join("existing.txt"), "modified content\n").unwrap();
-
-        // Create untracked file
-        fs::write(repo_path.join("untracked.txt"), "untracked content\n").unwrap();
-
-        let repo = Repository::open(repo_path).await.unwrap();
-        (temp_dir, repo)
+        RepoBuilder::new()
+            .file("existing.txt", "existing content\n")
+            .commit("Initial")
+            .file("staged.txt", "staged content\n")
+            .write("existing.txt", "modified content\n")
+            .write("untracked.txt", "untracked content\n")
+            .open()
+            .await
     }
 
     #[tokio::test]
@@ -356,8 +764,8 @@ mod tests {
         let status = load_status(&repo).await.unwrap();
 
         assert!(status.has_changes());
-        assert!(status.staged.len() > 0);
-        assert!(status.untracked.len() > 0);
+        assert!(!status.staged.is_empty());
+        assert!(!status.untracked.is_empty());
     }
 
     #[tokio::test]
@@ -372,6 +780,119 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_load_options_trim_the_scan() {
+        let (_temp_dir, repo) = RepoBuilder::new()
+            .file("kept.txt", "kept\n")
+            .commit("Initial")
+            .write("kept.txt", "changed\n")
+            .write("vendor/a.txt", "a\n")
+            .write("vendor/b.txt", "b\n")
+            .write("build/out/c.txt", "c\n")
+            .open()
+            .await;
+
+        let status = load_status(&repo).await.unwrap();
+        assert_eq!(status.untracked.len(), 3);
+
+        let options = StatusLoadOptions {
+            recurse_untracked_dirs: false,
+            exclude_paths: vec!["vendor/".to_string()],
+            ..StatusLoadOptions::default()
+        };
+        let status = load_status_with(&repo, options).await.unwrap();
+        let untracked: Vec<&str> = status.untracked.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(untracked, ["build/"]);
+        assert_eq!(status.unstaged.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_load_options_detect_renames() {
+        let (_temp_dir, repo) = RepoBuilder::new()
+            .file("old.txt", "same content\n")
+            .commit("Initial")
+            .open()
+            .await;
+        let git_repo = git2::Repository::open(repo.path()).unwrap();
+        let mut index = git_repo.index().unwrap();
+        fs::rename(repo.path().join("old.txt"), repo.path().join("new.txt")).unwrap();
+        index.remove_path(Path::new("old.txt")).unwrap();
+        index.add_path(Path::new("new.txt")).unwrap();
+        index.write().unwrap();
+
+        let status = load_status(&repo).await.unwrap();
+        assert_eq!(status.staged.len(), 2);
+
+        let options = StatusLoadOptions {
+            detect_renames: true,
+            update_index: true,
+            ..StatusLoadOptions::default()
+        };
+        let status = load_status_with(&repo, options).await.unwrap();
+        assert_eq!(status.staged.len(), 1);
+        assert_eq!(status.staged[0].path, "new.txt");
+        assert_eq!(status.staged[0].status, EntryStatus::IndexRenamed);
+    }
+
+    #[tokio::test]
+    async fn test_big_untracked_directories_are_summarized() {
+        let builder = RepoBuilder::new()
+            .file("src/lib.rs", "lib\n")
+            .commit("Initial")
+            .write("src/new.rs", "new\n");
+        let (_temp_dir, repo) = (0..UNTRACKED_SUMMARY_MIN_FILES)
+            .fold(builder, |builder, i| {
+                builder.write(&format!("node_modules/pkg{}/file{}.js", i % 7, i), "js\n")
+            })
+            .open()
+            .await;
+
+        let status = load_status(&repo).await.unwrap();
+        let untracked: Vec<&str> = status.untracked.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(untracked, ["node_modules/", "src/new.rs"]);
+        assert_eq!(status.untracked_count(), UNTRACKED_SUMMARY_MIN_FILES + 1);
+        assert_eq!(
+            status.untracked_dirs,
+            [UntrackedDir {
+                path: "node_modules/".to_string(),
+                files: UNTRACKED_SUMMARY_MIN_FILES,
+                expanded: false,
+            }]
+        );
+
+        // An expanded directory lists its files
+        let options = StatusLoadOptions {
+            expanded_untracked: HashSet::from(["node_modules/".to_string()]),
+            ..StatusLoadOptions::default()
+        };
+        let status = load_status_with(&repo, options).await.unwrap();
+        assert_eq!(status.untracked.len(), UNTRACKED_SUMMARY_MIN_FILES + 1);
+        assert!(status.untracked[0].path.starts_with("node_modules/pkg0/"));
+        assert_eq!(status.untracked_count(), UNTRACKED_SUMMARY_MIN_FILES + 1);
+        assert!(status.untracked_dirs[0].expanded);
+
+        // A summarized directory stages in one go
+        apply_index_ops(&repo, vec![IndexOp::Stage("node_modules/".to_string())])
+            .await
+            .unwrap();
+        let status = load_status(&repo).await.unwrap();
+        assert_eq!(status.staged.len(), UNTRACKED_SUMMARY_MIN_FILES);
+        assert!(status.untracked_dirs.is_empty());
+    }
+
+    #[test]
+    fn test_excluded_paths_match_whole_components() {
+        let options = StatusLoadOptions {
+            exclude_paths: vec!["vendor/".to_string(), "docs/big".to_string()],
+            ..StatusLoadOptions::default()
+        };
+        assert!(options.excludes("vendor/lib.rs"));
+        assert!(options.excludes("docs/big"));
+        assert!(options.excludes("docs/big/file.md"));
+        assert!(!options.excludes("vendored.txt"));
+        assert!(!options.excludes("docs/bigger.md"));
+    }
+
     #[test]
     fn test_entry_status_short_code() {
         assert_eq!(EntryStatus::IndexNew.short_code(), "A ");
@@ -392,4 +913,175 @@ mod tests {
         assert!(EntryStatus::WorktreeDeleted.is_unstaged());
         assert!(!EntryStatus::IndexModified.is_unstaged());
     }
+
+    #[tokio::test]
+    async fn test_commit_staged_creates_root_commit() {
+        let (temp_dir, repo) = RepoBuilder::new().open().await;
+        let git_repo = git2::Repository::open(temp_dir.path()).unwrap();
+
+        // An empty index has nothing to commit
+        let result = commit_staged(&repo, "Initial".to_string()).await;
+        assert!(matches!(result, Err(GitError::NothingToCommit)));
+
+        fs::write(temp_dir.path().join("first.txt"), "first\n").unwrap();
+        stage_file(&repo, "first.txt".to_string()).await.unwrap();
+        let oid = commit_staged(&repo, "Initial".to_string()).await.unwrap();
+
+        assert!(!repo.is_empty().await.unwrap());
+        let commit = git_repo.find_commit(oid).unwrap();
+        assert_eq!(commit.parent_count(), 0);
+        assert_eq!(commit.summary(), Some("Initial"));
+
+        // Committing again without new changes is refused
+        let result = commit_staged(&repo, "Again".to_string()).await;
+        assert!(matches!(result, Err(GitError::NothingToCommit)));
+    }
+
+    #[tokio::test]
+    async fn test_commit_template_from_config() {
+        let builder = RepoBuilder::new().write(".gitmessage", "# Explain why\nfeat: \n\nRefs: \n");
+        let (_temp_dir, repo) = builder.open().await;
+        assert_eq!(commit_template(&repo).await.unwrap(), None);
+
+        let git_repo = repo.open_git2().unwrap();
+        git_repo
+            .config()
+            .unwrap()
+            .set_str("commit.template", ".gitmessage")
+            .unwrap();
+        assert_eq!(
+            commit_template(&repo).await.unwrap().as_deref(),
+            Some("feat: \n\nRefs: ")
+        );
+
+        git_repo
+            .config()
+            .unwrap()
+            .set_str("commit.template", "missing")
+            .unwrap();
+        assert!(commit_template(&repo).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_sparse_excluded_files_are_not_deleted() {
+        let (temp_dir, repo) = create_test_repo_with_changes().await;
+        let git_repo = git2::Repository::open(temp_dir.path()).unwrap();
+
+        // Mark the committed file skip-worktree and remove it, as sparse-checkout would
+        let mut index = git_repo.index().unwrap();
+        let mut entry = index
+            .get_path(std::path::Path::new("existing.txt"), 0)
+            .unwrap();
+        entry.flags_extended |= IndexEntryExtendedFlag::SKIP_WORKTREE.bits();
+        index.add(&entry).unwrap();
+        index.write().unwrap();
+        fs::remove_file(temp_dir.path().join("existing.txt")).unwrap();
+
+        let status = load_status(&repo).await.unwrap();
+        assert!(!status.unstaged.iter().any(|e| e.path == "existing.txt"));
+        assert_eq!(status.sparse.len(), 1);
+        assert_eq!(status.sparse[0].path, "existing.txt");
+        assert_eq!(status.sparse[0].status, EntryStatus::SkipWorktree);
+    }
+
+    #[tokio::test]
+    async fn test_stage_reports_lock_contention() {
+        let (temp_dir, repo) = create_test_repo_with_changes().await;
+        let lock = temp_dir.path().join(".git").join("index.lock");
+        fs::write(&lock, "").unwrap();
+
+        let result = stage_file(&repo, "untracked.txt".to_string()).await;
+        assert!(
+            matches!(result, Err(GitError::LockContention { path }) if path.ends_with("index.lock"))
+        );
+
+        fs::remove_file(&lock).unwrap();
+        stage_file(&repo, "untracked.txt".to_string())
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_index_ops_apply_in_order() {
+        let (temp_dir, repo) = RepoBuilder::new()
+            .file("kept.txt", "kept\n")
+            .commit("Initial")
+            .write("a.txt", "a\n")
+            .write("b.txt", "b\n")
+            .file("kept.txt", "changed\n")
+            .open()
+            .await;
+
+        let ops = vec![
+            IndexOp::Stage("a.txt".to_string()),
+            IndexOp::Stage("b.txt".to_string()),
+            IndexOp::Unstage("b.txt".to_string()),
+            IndexOp::Unstage("kept.txt".to_string()),
+        ];
+        apply_index_ops(&repo, ops).await.unwrap();
+
+        let status = load_status(&repo).await.unwrap();
+        let staged: Vec<&str> = status.staged.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(staged, ["a.txt"]);
+
+        // A failing change leaves the index as it was
+        let ops = vec![
+            IndexOp::Stage("b.txt".to_string()),
+            IndexOp::Stage("missing.txt".to_string()),
+        ];
+        assert!(apply_index_ops(&repo, ops).await.is_err());
+        let index = git2::Repository::open(temp_dir.path())
+            .unwrap()
+            .index()
+            .unwrap();
+        assert!(index.get_path(std::path::Path::new("b.txt"), 0).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_commit_refuses_merge_conflicts() {
+        let (_temp_dir, repo) = RepoBuilder::new()
+            .file("a.txt", "base\n")
+            .commit("Base")
+            .conflict("a.txt", "ours\n", "theirs\n")
+            .open()
+            .await;
+
+        let result = commit_staged(&repo, "Merge".to_string()).await;
+        assert!(matches!(result, Err(GitError::MergeConflict { paths }) if paths == ["a.txt"]));
+    }
+
+    #[tokio::test]
+    async fn test_amend_keeps_parents_and_refuses_pushed() {
+        let builder = RepoBuilder::new()
+            .file("a.txt", "a\n")
+            .commit("Base")
+            .file("b.txt", "b\n")
+            .commit("Add b");
+        let base = builder.git2().revparse_single("HEAD~1").unwrap().id();
+        let (_temp_dir, repo) = builder.file("c.txt", "c\n").open().await;
+
+        assert_eq!(
+            amend_start(&repo).await.unwrap(),
+            ("Add b".to_string(), None)
+        );
+        let amended = amend_head(&repo, "Add b and c".to_string(), false)
+            .await
+            .unwrap();
+        let git_repo = repo.open_git2().unwrap();
+        let commit = git_repo.find_commit(amended).unwrap();
+        assert_eq!(commit.message(), Some("Add b and c"));
+        assert_eq!(commit.parent_id(0).unwrap(), base);
+        assert!(commit.tree().unwrap().get_name("c.txt").is_some());
+
+        git_repo
+            .reference("refs/remotes/origin/main", amended, false, "test")
+            .unwrap();
+        assert_eq!(
+            amend_start(&repo).await.unwrap().1,
+            Some("origin/main".to_string())
+        );
+        let refused = amend_head(&repo, "Again".to_string(), false).await;
+        assert!(matches!(refused, Err(GitError::AlreadyPushed(remote)) if remote == "origin/main"));
+        assert!(amend_head(&repo, "Again".to_string(), true).await.is_ok());
+    }
 }
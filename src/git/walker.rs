@@ -1,12 +1,67 @@
 use super::{commit::Commit, error::Result, repository::Repository};
-use git2::{Oid, Sort};
+use git2::{DiffOptions, Oid, Sort};
 use std::collections::HashMap;
 use tokio::sync::mpsc;
 
+/// Chunks a walk gets ahead of its reader before pausing, bounding how much
+/// history is loaded but not yet shown; the main view takes them all on
+/// every redraw, so this also caps how fast history loads
+pub const WALK_BUFFER_CHUNKS: usize = 16;
+
+/// Which refs decorate commits, and which the walk takes in or leaves out
+/// besides HEAD's history
+///
+/// Patterns are full ref names: `*` and `?` match like shell globs, `/`
+/// included, and a name without them matches that ref and everything
+/// under it, so `refs/pull` covers `refs/pull/12/head`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RefFilter {
+    /// Refs whose names are left off the commits they point at
+    pub hide_decorations: Vec<String>,
+    /// Refs whose history is listed along with HEAD's
+    pub walk: Vec<String>,
+    /// Refs whose history is left out
+    pub hide: Vec<String>,
+}
+
+impl RefFilter {
+    fn decorates(&self, name: &str) -> bool {
+        !self
+            .hide_decorations
+            .iter()
+            .any(|pattern| ref_matches(pattern, name))
+    }
+}
+
+/// Check whether a full ref name matches a `RefFilter` pattern
+fn ref_matches(pattern: &str, name: &str) -> bool {
+    if !pattern.contains(['*', '?']) {
+        let pattern = pattern.trim_end_matches('/');
+        return name == pattern
+            || name
+                .strip_prefix(pattern)
+                .is_some_and(|rest| rest.starts_with('/'));
+    }
+    glob_matches(pattern.as_bytes(), name.as_bytes())
+}
+
+fn glob_matches(pattern: &[u8], name: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some((b'*', rest)) => (0..=name.len()).any(|skip| glob_matches(rest, &name[skip..])),
+        Some((b'?', rest)) => !name.is_empty() && glob_matches(rest, &name[1..]),
+        Some((c, rest)) => name.first() == Some(c) && glob_matches(rest, &name[1..]),
+    }
+}
+
 /// Async commit walker that loads commits in chunks
 pub struct CommitWalker {
     repo: Repository,
     chunk_size: usize,
+    path: Option<String>,
+    hidden: Option<String>,
+    listed: Option<Vec<Oid>>,
+    ref_filter: RefFilter,
 }
 
 impl CommitWalker {
@@ -15,6 +70,10 @@ impl CommitWalker {
         Self {
             repo,
             chunk_size: 100,
+            path: None,
+            hidden: None,
+            listed: None,
+            ref_filter: RefFilter::default(),
         }
     }
 
@@ -24,38 +83,60 @@ impl CommitWalker {
         self
     }
 
-    /// Walk commits starting from HEAD and send them through the channel
-    pub async fn walk(&self, tx: mpsc::UnboundedSender<Vec<Commit>>) -> Result<()> {
+    /// Only yield commits that change something under `path`
+    pub fn with_path<S: Into<String>>(mut self, path: S) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+
+    /// Leave out commits reachable from the revision `hidden`, walking
+    /// `hidden..HEAD`
+    pub fn with_hidden<S: Into<String>>(mut self, hidden: S) -> Self {
+        self.hidden = Some(hidden.into());
+        self
+    }
+
+    /// Yield exactly these commits, in this order, instead of walking from
+    /// HEAD
+    pub fn with_commits(mut self, ids: Vec<Oid>) -> Self {
+        self.listed = Some(ids);
+        self
+    }
+
+    /// Set which refs decorate commits and which histories join or leave
+    /// the walk
+    pub fn with_ref_filter(mut self, ref_filter: RefFilter) -> Self {
+        self.ref_filter = ref_filter;
+        self
+    }
+
+    /// Walk commits starting from HEAD and send them through the channel,
+    /// waiting whenever it is full
+    pub async fn walk(&self, tx: mpsc::Sender<Vec<Commit>>) -> Result<()> {
         let repo = self.repo.clone();
         let chunk_size = self.chunk_size;
+        let path = self.path.clone();
+        let hidden = self.hidden.clone();
+        let listed = self.listed.clone();
+        let ref_filter = self.ref_filter.clone();
 
         tokio::task::spawn_blocking(move || {
             let git_repo = repo.open_git2()?;
 
-            // Get all references to populate commit refs
-            let mut refs_map: HashMap<Oid, Vec<String>> = HashMap::new();
-            if let Ok(references) = git_repo.references() {
-                for reference in references.flatten() {
-                    if let (Some(name), Some(target)) = (reference.shorthand(), reference.target()) {
-                        refs_map
-                            .entry(target)
-                            .or_insert_with(Vec::new)
-                            .push(name.to_string());
-                    }
-                }
-            }
-
-            // Set up the revwalk
-            let mut revwalk = git_repo.revwalk()?;
-            revwalk.set_sorting(Sort::TIME | Sort::TOPOLOGICAL)?;
-            revwalk.push_head()?;
-
+            let refs_map = decorations(&git_repo, &ref_filter);
+            let ids = commit_ids(&git_repo, hidden.as_deref(), listed, &ref_filter)?;
             let mut commits = Vec::with_capacity(chunk_size);
 
-            for oid in revwalk {
+            for oid in ids {
                 let oid = oid?;
                 let git_commit = git_repo.find_commit(oid)?;
 
+                if let Some(path) = &path
+                    && !touches_path(&git_repo, &git_commit, path)?
+                {
+                    continue;
+                }
+
                 let mut commit = Commit::from_git2(&git_commit)?;
 
                 // Add refs if this commit has any
@@ -67,22 +148,22 @@ impl CommitWalker {
 
                 // Send a chunk when we reach the chunk size
                 if commits.len() >= chunk_size {
-                    if tx.send(commits.clone()).is_err() {
+                    let chunk = std::mem::replace(&mut commits, Vec::with_capacity(chunk_size));
+                    if tx.blocking_send(chunk).is_err() {
                         break; // Receiver dropped
                     }
-                    commits.clear();
                 }
             }
 
             // Send remaining commits
             if !commits.is_empty() {
-                let _ = tx.send(commits);
+                let _ = tx.blocking_send(commits);
             }
 
             Ok::<(), super::error::GitError>(())
         })
         .await
-        .map_err(|_| super::error::GitError::RepoNotFound)??;
+        .map_err(super::error::GitError::task_join("walk commits"))??;
 
         Ok(())
     }
@@ -90,32 +171,28 @@ impl CommitWalker {
     /// Load all commits at once (for small repositories)
     pub async fn load_all(&self) -> Result<Vec<Commit>> {
         let repo = self.repo.clone();
+        let path = self.path.clone();
+        let hidden = self.hidden.clone();
+        let listed = self.listed.clone();
+        let ref_filter = self.ref_filter.clone();
 
         tokio::task::spawn_blocking(move || {
             let git_repo = repo.open_git2()?;
 
-            // Get all references
-            let mut refs_map: HashMap<Oid, Vec<String>> = HashMap::new();
-            if let Ok(references) = git_repo.references() {
-                for reference in references.flatten() {
-                    if let (Some(name), Some(target)) = (reference.shorthand(), reference.target()) {
-                        refs_map
-                            .entry(target)
-                            .or_insert_with(Vec::new)
-                            .push(name.to_string());
-                    }
-                }
-            }
-
-            let mut revwalk = git_repo.revwalk()?;
-            revwalk.set_sorting(Sort::TIME | Sort::TOPOLOGICAL)?;
-            revwalk.push_head()?;
-
+            let refs_map = decorations(&git_repo, &ref_filter);
+            let ids = commit_ids(&git_repo, hidden.as_deref(), listed, &ref_filter)?;
             let mut commits = Vec::new();
 
-            for oid in revwalk {
+            for oid in ids {
                 let oid = oid?;
                 let git_commit = git_repo.find_commit(oid)?;
+
+                if let Some(path) = &path
+                    && !touches_path(&git_repo, &git_commit, path)?
+                {
+                    continue;
+                }
+
                 let mut commit = Commit::from_git2(&git_commit)?;
 
                 if let Some(refs) = refs_map.get(&oid) {
@@ -128,50 +205,111 @@ impl CommitWalker {
             Ok(commits)
         })
         .await
-        .map_err(|_| super::error::GitError::RepoNotFound)?
+        .map_err(super::error::GitError::task_join("load commits"))?
+    }
+}
+
+/// Map each commit to the short names of the refs pointing at it, leaving
+/// out the ones the filter hides
+fn decorations(git_repo: &git2::Repository, ref_filter: &RefFilter) -> HashMap<Oid, Vec<String>> {
+    let mut refs_map: HashMap<Oid, Vec<String>> = HashMap::new();
+    if let Ok(references) = git_repo.references() {
+        for reference in references.flatten() {
+            if let (Some(name), Some(shorthand), Some(target)) =
+                (reference.name(), reference.shorthand(), reference.target())
+                && ref_filter.decorates(name)
+            {
+                refs_map
+                    .entry(target)
+                    .or_default()
+                    .push(shorthand.to_string());
+            }
+        }
+    }
+    refs_map
+}
+
+/// Commits of the refs matching any of `patterns`
+fn matching_commits(git_repo: &git2::Repository, patterns: &[String]) -> Result<Vec<Oid>> {
+    if patterns.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut ids = Vec::new();
+    for reference in git_repo.references()?.flatten() {
+        if let Some(name) = reference.name()
+            && patterns.iter().any(|pattern| ref_matches(pattern, name))
+            && let Ok(commit) = reference.peel_to_commit()
+        {
+            ids.push(commit.id());
+        }
+    }
+    Ok(ids)
+}
+
+/// Ids of the commits to show, in order
+type CommitIds<'r> = Box<dyn Iterator<Item = std::result::Result<Oid, git2::Error>> + 'r>;
+
+/// Get the listed commits, or else set up a walk from HEAD, and the refs
+/// the filter adds, that leaves out what `hidden` and the refs the filter
+/// hides reach
+fn commit_ids<'r>(
+    git_repo: &'r git2::Repository,
+    hidden: Option<&str>,
+    listed: Option<Vec<Oid>>,
+    ref_filter: &RefFilter,
+) -> Result<CommitIds<'r>> {
+    if let Some(listed) = listed {
+        return Ok(Box::new(listed.into_iter().map(Ok)));
+    }
+
+    let mut revwalk = git_repo.revwalk()?;
+    revwalk.set_sorting(Sort::TIME | Sort::TOPOLOGICAL)?;
+    revwalk.push_head()?;
+    for id in matching_commits(git_repo, &ref_filter.walk)? {
+        revwalk.push(id)?;
+    }
+    for id in matching_commits(git_repo, &ref_filter.hide)? {
+        revwalk.hide(id)?;
+    }
+    if let Some(hidden) = hidden {
+        revwalk.hide(git_repo.revparse_single(hidden)?.peel_to_commit()?.id())?;
     }
+    Ok(Box::new(revwalk))
+}
+
+/// Check whether a commit changes anything under `path` relative to its first parent
+fn touches_path(repo: &git2::Repository, commit: &git2::Commit, path: &str) -> Result<bool> {
+    let tree = commit.tree()?;
+    let parent_tree = if commit.parent_count() > 0 {
+        Some(commit.parent(0)?.tree()?)
+    } else {
+        None
+    };
+
+    let mut diff_options = DiffOptions::new();
+    diff_options.pathspec(path);
+    let diff =
+        repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), Some(&mut diff_options))?;
+
+    Ok(diff.deltas().len() > 0)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::testing::RepoBuilder;
     use tempfile::TempDir;
 
     async fn create_test_repo_with_commits() -> (TempDir, Repository) {
-        let temp_dir = TempDir::new().unwrap();
-        let repo_path = temp_dir.path();
-
-        let git_repo = git2::Repository::init(repo_path).unwrap();
-        let sig = git2::Signature::now("Test User", "test@example.com").unwrap();
-
-        // Create multiple commits
-        for i in 0..5 {
-            let tree_id = {
-                let mut index = git_repo.index().unwrap();
-                // Create a file to make commits different
-                let path = repo_path.join(format!("file{}.txt", i));
-                std::fs::write(&path, format!("content {}", i)).unwrap();
-                index.add_path(std::path::Path::new(&format!("file{}.txt", i))).unwrap();
-                index.write().unwrap();
-                index.write_tree().unwrap()
-            };
-            let tree = git_repo.find_tree(tree_id).unwrap();
-            let message = format!("Commit {}", i);
-
-            if i == 0 {
-                git_repo
-                    .commit(Some("HEAD"), &sig, &sig, &message, &tree, &[])
-                    .unwrap();
-            } else {
-                let parent = git_repo.head().unwrap().peel_to_commit().unwrap();
-                git_repo
-                    .commit(Some("HEAD"), &sig, &sig, &message, &tree, &[&parent])
-                    .unwrap();
-            };
-        }
-
-        let our_repo = Repository::open(repo_path).await.unwrap();
-        (temp_dir, our_repo)
+        // Each commit adds a file so the commits differ
+        (0..5)
+            .fold(RepoBuilder::new(), |builder, i| {
+                builder
+                    .file(&format!("file{}.txt", i), &format!("content {}", i))
+                    .commit(&format!("Commit {}", i))
+            })
+            .open()
+            .await
     }
 
     #[tokio::test]
@@ -183,8 +321,8 @@ mod tests {
         assert_eq!(commits.len(), 5);
 
         // Verify commits are in reverse chronological order
-        for i in 0..5 {
-            assert!(commits[i].summary.contains(&format!("Commit {}", 4 - i)));
+        for (i, commit) in commits.iter().enumerate() {
+            assert!(commit.summary.contains(&format!("Commit {}", 4 - i)));
         }
     }
 
@@ -193,7 +331,7 @@ mod tests {
         let (_temp_dir, repo) = create_test_repo_with_commits().await;
         let walker = CommitWalker::new(repo).with_chunk_size(2);
 
-        let (tx, mut rx) = mpsc::unbounded_channel();
+        let (tx, mut rx) = mpsc::channel(1);
 
         tokio::spawn(async move {
             walker.walk(tx).await.unwrap();
@@ -208,6 +346,107 @@ mod tests {
         assert_eq!(total_commits, 5);
     }
 
+    #[tokio::test]
+    async fn test_path_filter() {
+        let (_temp_dir, repo) = create_test_repo_with_commits().await;
+        let walker = CommitWalker::new(repo).with_path("file3.txt");
+
+        let commits = walker.load_all().await.unwrap();
+        assert_eq!(commits.len(), 1);
+        assert_eq!(commits[0].summary, "Commit 3");
+    }
+
+    #[tokio::test]
+    async fn test_hidden_revision() {
+        let (_temp_dir, repo) = create_test_repo_with_commits().await;
+        let walker = CommitWalker::new(repo).with_hidden("HEAD~2");
+
+        let commits = walker.load_all().await.unwrap();
+        let summaries: Vec<&str> = commits.iter().map(|c| c.summary.as_str()).collect();
+        assert_eq!(summaries, ["Commit 4", "Commit 3"]);
+    }
+
+    #[test]
+    fn test_ref_patterns() {
+        assert!(ref_matches("refs/pull", "refs/pull/12/head"));
+        assert!(ref_matches("refs/stash", "refs/stash"));
+        assert!(!ref_matches("refs/stash", "refs/stashed"));
+        assert!(ref_matches(
+            "refs/remotes/*/ci-*",
+            "refs/remotes/origin/ci-build"
+        ));
+        assert!(!ref_matches(
+            "refs/remotes/*/ci-*",
+            "refs/remotes/origin/main"
+        ));
+        assert!(ref_matches("refs/tags/v?", "refs/tags/v1"));
+        assert!(!ref_matches("refs/tags/v?", "refs/tags/v10"));
+    }
+
+    #[tokio::test]
+    async fn test_ref_filter_walks_hides_and_decorates() {
+        let builder = RepoBuilder::new().commit("Base").branch("side").tag("base");
+        let default_branch = builder
+            .git2()
+            .head()
+            .unwrap()
+            .shorthand()
+            .unwrap()
+            .to_string();
+        let builder = builder
+            .checkout("side")
+            .commit("Side")
+            .branch("ci-side")
+            .tag("keep");
+        let (_temp_dir, repo) = builder.checkout(&default_branch).open().await;
+        let git_repo = repo.open_git2().unwrap();
+        let base = git_repo.revparse_single("base").unwrap().id();
+        git_repo
+            .reference("refs/pull/1/head", base, false, "test")
+            .unwrap();
+        drop(git_repo);
+
+        // HEAD alone only reaches the base
+        assert_eq!(
+            CommitWalker::new(repo.clone())
+                .load_all()
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+
+        let filter = RefFilter {
+            hide_decorations: vec!["refs/pull".to_string(), "refs/heads/ci-*".to_string()],
+            walk: vec!["refs/heads/side".to_string()],
+            hide: vec!["refs/tags/base".to_string()],
+        };
+        let commits = CommitWalker::new(repo)
+            .with_ref_filter(filter)
+            .load_all()
+            .await
+            .unwrap();
+        assert_eq!(commits.len(), 1);
+        assert_eq!(commits[0].summary, "Side");
+        let mut refs = commits[0].refs.clone();
+        refs.sort();
+        assert_eq!(refs, ["keep", "side"]);
+    }
+
+    #[tokio::test]
+    async fn test_listed_commits_keep_their_order() {
+        let (_temp_dir, repo) = create_test_repo_with_commits().await;
+        let all = CommitWalker::new(repo.clone()).load_all().await.unwrap();
+        let ids = vec![all[3].id, all[0].id, all[4].id];
+
+        let commits = CommitWalker::new(repo)
+            .with_commits(ids.clone())
+            .load_all()
+            .await
+            .unwrap();
+        assert_eq!(commits.iter().map(|c| c.id).collect::<Vec<_>>(), ids);
+    }
+
     #[tokio::test]
     async fn test_commit_has_refs() {
         let (_temp_dir, repo) = create_test_repo_with_commits().await;
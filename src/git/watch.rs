@@ -0,0 +1,177 @@
+use super::{error::Result, repository::Repository};
+use git2::Oid;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+use std::time::SystemTime;
+
+/// Per-repository count of writes rust-tig made itself, so the watcher can
+/// tell our own changes from ones made in another terminal
+static LOCAL_WRITES: LazyLock<Mutex<HashMap<PathBuf, u64>>> = LazyLock::new(Default::default);
+
+/// Record that rust-tig changed HEAD or the index of the repository at `path`
+pub fn note_local_write(path: &Path) {
+    if let Ok(mut writes) = LOCAL_WRITES.lock() {
+        *writes.entry(path.to_path_buf()).or_default() += 1;
+    }
+}
+
+/// Get the number of local writes recorded for the repository at `path`
+pub(super) fn local_writes(path: &Path) -> u64 {
+    LOCAL_WRITES
+        .lock()
+        .map(|writes| writes.get(path).copied().unwrap_or(0))
+        .unwrap_or(0)
+}
+
+/// The parts of a repository that go stale when changed from outside
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepoSnapshot {
+    /// Full name of the checked out branch, `None` when detached
+    pub head_name: Option<String>,
+    /// Commit HEAD points at, `None` before the first commit
+    pub head_oid: Option<Oid>,
+    /// Modification time of the index file
+    pub index_mtime: Option<SystemTime>,
+    /// Count of local writes when the snapshot was taken
+    pub local_writes: u64,
+}
+
+/// A change made to the repository outside rust-tig
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RepoChange {
+    /// A different branch was checked out
+    BranchSwitched {
+        from: Option<String>,
+        to: Option<String>,
+    },
+    /// HEAD moved on the same branch (commit, reset, pull, ...)
+    HeadMoved { to: Option<Oid> },
+    /// Files were staged or unstaged
+    IndexChanged,
+}
+
+impl RepoChange {
+    /// Describe the change for the banner
+    pub fn describe(&self) -> String {
+        let short = |name: &Option<String>| {
+            name.as_deref()
+                .map(|n| n.strip_prefix("refs/heads/").unwrap_or(n).to_string())
+                .unwrap_or_else(|| "detached HEAD".to_string())
+        };
+        match self {
+            RepoChange::BranchSwitched { from, to } => {
+                format!(
+                    "Switched from {} to {} outside rust-tig",
+                    short(from),
+                    short(to)
+                )
+            }
+            RepoChange::HeadMoved { to: Some(oid) } => {
+                format!("HEAD moved to {} outside rust-tig", &oid.to_string()[..7])
+            }
+            RepoChange::HeadMoved { to: None } => "HEAD changed outside rust-tig".to_string(),
+            RepoChange::IndexChanged => "Index changed outside rust-tig".to_string(),
+        }
+    }
+}
+
+impl RepoSnapshot {
+    /// Short name of the checked out branch, "HEAD" when detached
+    pub fn branch(&self) -> String {
+        match &self.head_name {
+            Some(name) => name.strip_prefix("refs/heads/").unwrap_or(name).to_string(),
+            None => "HEAD".to_string(),
+        }
+    }
+
+    /// Find what changed since an `earlier` snapshot, ignoring changes made
+    /// by rust-tig itself in between
+    pub fn change_since(&self, earlier: &RepoSnapshot) -> Option<RepoChange> {
+        if self.local_writes != earlier.local_writes {
+            return None;
+        }
+        if self.head_name != earlier.head_name {
+            Some(RepoChange::BranchSwitched {
+                from: earlier.head_name.clone(),
+                to: self.head_name.clone(),
+            })
+        } else if self.head_oid != earlier.head_oid {
+            Some(RepoChange::HeadMoved { to: self.head_oid })
+        } else if self.index_mtime != earlier.index_mtime {
+            Some(RepoChange::IndexChanged)
+        } else {
+            None
+        }
+    }
+}
+
+/// Take a snapshot of HEAD and the index
+pub async fn snapshot(repo: &Repository) -> Result<RepoSnapshot> {
+    let repo_path = repo.path().to_path_buf();
+
+    tokio::task::spawn_blocking(move || {
+        // Read the counter first so a write finishing during the snapshot
+        // shows up as a local write next time
+        let local_writes = local_writes(&repo_path);
+        let git_repo = git2::Repository::open(repo_path)?;
+
+        let head = git_repo.find_reference("HEAD")?;
+        let head_name = head.symbolic_target().map(|s| s.to_string());
+        let head_oid = head.resolve().ok().and_then(|r| r.target());
+
+        let index_mtime = std::fs::metadata(git_repo.path().join("index"))
+            .and_then(|m| m.modified())
+            .ok();
+
+        Ok(RepoSnapshot {
+            head_name,
+            head_oid,
+            index_mtime,
+            local_writes,
+        })
+    })
+    .await
+    .map_err(super::error::GitError::task_join("snapshot the repository"))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::RepoBuilder;
+    use tempfile::TempDir;
+
+    async fn create_test_repo() -> (TempDir, git2::Repository, Repository) {
+        let (temp_dir, repo) = RepoBuilder::new().commit("Initial").open().await;
+        let git_repo = git2::Repository::open(temp_dir.path()).unwrap();
+        (temp_dir, git_repo, repo)
+    }
+
+    #[tokio::test]
+    async fn test_detects_branch_switch() {
+        let (_temp_dir, git_repo, repo) = create_test_repo().await;
+        let before = snapshot(&repo).await.unwrap();
+        assert_eq!(snapshot(&repo).await.unwrap().change_since(&before), None);
+
+        let head = git_repo.head().unwrap().peel_to_commit().unwrap();
+        git_repo.branch("feature", &head, false).unwrap();
+        git_repo.set_head("refs/heads/feature").unwrap();
+
+        let change = snapshot(&repo).await.unwrap().change_since(&before);
+        assert!(matches!(change, Some(RepoChange::BranchSwitched { .. })));
+        assert!(change.unwrap().describe().contains("to feature"));
+    }
+
+    #[tokio::test]
+    async fn test_ignores_local_writes() {
+        let (_temp_dir, git_repo, repo) = create_test_repo().await;
+        let before = snapshot(&repo).await.unwrap();
+
+        let head = git_repo.head().unwrap().peel_to_commit().unwrap();
+        git_repo.branch("other", &head, false).unwrap();
+        git_repo.set_head("refs/heads/other").unwrap();
+        note_local_write(repo.path());
+
+        assert_eq!(snapshot(&repo).await.unwrap().change_since(&before), None);
+    }
+}
@@ -1,7 +1,14 @@
 // Library interface for rust-tig
 // Exposes modules for integration tests and potential library usage
 
+pub mod clock;
 pub mod config;
+pub mod crash;
+pub mod error;
 pub mod git;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+#[cfg(feature = "testsupport")]
+pub mod testsupport;
 pub mod ui;
 pub mod views;
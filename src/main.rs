@@ -1,5 +1,10 @@
-use anyhow::Result;
-use rust_tig::ui::{self, App, EventHandler};
+use anyhow::{Context, Result};
+use rust_tig::config::ColorMode;
+use rust_tig::crash;
+use rust_tig::ui::{self, App, EventHandler, EventSource, Recorder, Replay, Tui};
+use rust_tig::views::EditRequest;
+use std::io::Read;
+use std::path::PathBuf;
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -8,6 +13,13 @@ async fn main() -> Result<()> {
         // Restore terminal before showing panic
         let _ = ui::terminal::restore();
         eprintln!("{}", panic_info);
+
+        if let Some(dir) = crash::report_dir() {
+            match crash::write_report(&dir, &panic_info.to_string()) {
+                Ok(path) => eprintln!("Crash report written to {}", path.display()),
+                Err(e) => eprintln!("Failed to write crash report: {}", e),
+            }
+        }
     }));
 
     // Run the application
@@ -21,11 +33,48 @@ async fn main() -> Result<()> {
 }
 
 async fn run() -> Result<()> {
+    // Check the arguments before taking over the terminal
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let start = match ui::start::parse_args(&args) {
+        Ok(start) => start,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    };
+
+    // Read a piped revision list while stdin is still ours; keys then come
+    // from the terminal itself
+    let commit_list = match args.iter().any(|arg| arg == "--stdin") {
+        true => {
+            let mut text = String::new();
+            std::io::stdin()
+                .read_to_string(&mut text)
+                .context("Failed to read stdin")?;
+            let ids = ui::start::parse_revision_list(&text);
+            if ids.is_empty() {
+                eprintln!("No commit ids on stdin; pipe in full hashes, e.g. from git rev-list");
+                std::process::exit(2);
+            }
+            Some(ids)
+        }
+        false => None,
+    };
+
     // Initialize terminal
     let mut terminal = ui::terminal::init()?;
 
     // Create application and event handler
-    let mut app = App::new();
+    let mut app = App::new().with_start(start.view);
+    if let Some(path) = start.path {
+        app = app.with_repo_path(path);
+    }
+    if let Some(ids) = commit_list {
+        app = app.with_commit_list(ids);
+    }
+    if no_color_requested() {
+        app = app.with_color_mode(ColorMode::None);
+    }
 
     // Initialize the app with the repository
     if let Err(e) = app.init().await {
@@ -34,10 +83,45 @@ async fn run() -> Result<()> {
         return Ok(()); // Exit gracefully
     }
 
-    let mut event_handler = EventHandler::new();
-    event_handler.start();
+    let record = path_arg("--record");
+    match path_arg("--replay") {
+        Some(path) => {
+            let replay = Replay::load(&path)
+                .with_context(|| format!("Failed to load session {}", path.display()))?;
+            run_recorded(&mut app, &mut terminal, replay, record).await?;
+        }
+        None => {
+            let event_handler = EventHandler::new();
+            event_handler.start();
+            run_recorded(&mut app, &mut terminal, event_handler, record).await?;
+        }
+    }
+
+    // Restore terminal
+    ui::terminal::restore()?;
 
-    // Main event loop
+    Ok(())
+}
+
+/// Run the event loop, writing the events to `record` if given
+async fn run_recorded<S: EventSource + Send>(
+    app: &mut App,
+    terminal: &mut Tui,
+    events: S,
+    record: Option<PathBuf>,
+) -> Result<()> {
+    match record {
+        Some(path) => {
+            let recorder = Recorder::new(events, &path)
+                .with_context(|| format!("Failed to create session {}", path.display()))?;
+            event_loop(app, terminal, recorder).await
+        }
+        None => event_loop(app, terminal, events).await,
+    }
+}
+
+/// Main event loop; ends when the app quits or the events run out
+async fn event_loop(app: &mut App, terminal: &mut Tui, mut events: impl EventSource) -> Result<()> {
     while app.is_running() {
         // Update application state
         app.update()?;
@@ -47,14 +131,53 @@ async fn run() -> Result<()> {
             app.render(frame);
         })?;
 
-        // Handle events
-        if let Some(event) = event_handler.next().await {
-            app.handle_event(event)?;
+        // Handle events, keeping what is typed into prompts out of recordings
+        events.set_private(app.captures_text());
+        match events.next().await {
+            Some(event) => app.handle_event(event)?,
+            None => break,
+        }
+
+        if let Some(request) = app.take_pending_edit() {
+            edit_in_terminal(terminal, &mut events, request).await?;
+        }
+        if let Some(text) = app.take_pending_copy() {
+            ui::terminal::copy_to_clipboard(&text)?;
         }
     }
+    Ok(())
+}
 
-    // Restore terminal
+/// Hand the terminal to the user's editor for a view's edit request
+async fn edit_in_terminal(
+    terminal: &mut Tui,
+    events: &mut impl EventSource,
+    request: EditRequest,
+) -> Result<()> {
+    events.set_paused(true);
     ui::terminal::restore()?;
 
+    let (text, file_name) = (request.text.clone(), request.file_name);
+    let result = tokio::task::spawn_blocking(move || ui::editor::edit_text(&text, file_name))
+        .await
+        .context("Editor task failed")?;
+
+    ui::terminal::resume(terminal)?;
+    events.set_paused(false);
+    request.finish(result);
     Ok(())
 }
+
+/// Get the path following a flag, as in `--record session.log`
+fn path_arg(flag: &str) -> Option<PathBuf> {
+    let mut args = std::env::args().skip(1);
+    args.find(|arg| arg == flag)?;
+    args.next().map(PathBuf::from)
+}
+
+/// Check for `--no-color` or a non-empty NO_COLOR environment variable
+/// (see https://no-color.org)
+fn no_color_requested() -> bool {
+    std::env::args().skip(1).any(|arg| arg == "--no-color")
+        || std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+}
@@ -0,0 +1,233 @@
+// Git repository fixtures for tests
+// Compiled for unit tests, and for other crates with the `testing` feature
+
+use crate::git::Repository;
+use git2::{IndexEntry, IndexTime, Oid, Signature};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+/// Builds a throwaway git repository step by step
+///
+/// Every step runs immediately and panics on failure, so a broken fixture
+/// fails the test at the line that set it up:
+///
+/// ```no_run
+/// # async fn example() {
+/// use rust_tig::testing::RepoBuilder;
+///
+/// let (_dir, repo) = RepoBuilder::new()
+///     .file("README.md", "hello\n")
+///     .commit("Initial")
+///     .branch("feature")
+///     .tag("v1.0")
+///     .write("README.md", "changed\n")
+///     .open()
+///     .await;
+/// # }
+/// ```
+pub struct RepoBuilder {
+    dir: TempDir,
+    repo: git2::Repository,
+}
+
+impl RepoBuilder {
+    /// Create an empty repository in a new temporary directory
+    pub fn new() -> Self {
+        let dir = TempDir::new().unwrap();
+        let repo = git2::Repository::init(dir.path()).unwrap();
+
+        // Commits made by the code under test read the signature from config
+        let mut config = repo.config().unwrap();
+        config.set_str("user.name", "Test User").unwrap();
+        config.set_str("user.email", "test@example.com").unwrap();
+        drop(config);
+
+        Self { dir, repo }
+    }
+
+    /// Get the path of the working directory
+    pub fn path(&self) -> &Path {
+        self.dir.path()
+    }
+
+    /// Get the underlying git2 repository for steps the builder doesn't cover
+    pub fn git2(&self) -> &git2::Repository {
+        &self.repo
+    }
+
+    /// Get the commit HEAD points at
+    pub fn head(&self) -> Oid {
+        self.repo.head().unwrap().target().unwrap()
+    }
+
+    /// Write a file to the working directory without staging it
+    pub fn write(self, path: &str, content: &str) -> Self {
+        let full_path = self.dir.path().join(path);
+        if let Some(parent) = full_path.parent() {
+            fs::create_dir_all(parent).unwrap();
+        }
+        fs::write(full_path, content).unwrap();
+        self
+    }
+
+    /// Stage a file, or its removal if it no longer exists
+    pub fn stage(self, path: &str) -> Self {
+        let mut index = self.repo.index().unwrap();
+        if self.dir.path().join(path).exists() {
+            index.add_path(Path::new(path)).unwrap();
+        } else {
+            index.remove_path(Path::new(path)).unwrap();
+        }
+        index.write().unwrap();
+        self
+    }
+
+    /// Write a file and stage it
+    pub fn file(self, path: &str, content: &str) -> Self {
+        self.write(path, content).stage(path)
+    }
+
+    /// Commit the index on top of HEAD; the first commit has no parent
+    pub fn commit(self, message: &str) -> Self {
+        let sig = Signature::now("Test User", "test@example.com").unwrap();
+        let tree_id = self.repo.index().unwrap().write_tree().unwrap();
+        let tree = self.repo.find_tree(tree_id).unwrap();
+        let parent = self.repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+        let parents: Vec<&git2::Commit> = parent.iter().collect();
+        self.repo
+            .commit(Some("HEAD"), &sig, &sig, message, &tree, &parents)
+            .unwrap();
+        drop(parent);
+        drop(tree);
+        self
+    }
+
+    /// Create a branch at HEAD without checking it out
+    pub fn branch(self, name: &str) -> Self {
+        let head = self.repo.head().unwrap().peel_to_commit().unwrap();
+        self.repo.branch(name, &head, false).unwrap();
+        drop(head);
+        self
+    }
+
+    /// Check out a local branch, overwriting the working directory
+    pub fn checkout(self, name: &str) -> Self {
+        let reference = format!("refs/heads/{}", name);
+        self.repo.set_head(&reference).unwrap();
+        self.repo
+            .checkout_head(Some(git2::build::CheckoutBuilder::new().force()))
+            .unwrap();
+        self
+    }
+
+    /// Create a lightweight tag at HEAD
+    pub fn tag(self, name: &str) -> Self {
+        let head = self.repo.head().unwrap().peel_to_commit().unwrap();
+        self.repo
+            .tag_lightweight(name, head.as_object(), false)
+            .unwrap();
+        drop(head);
+        self
+    }
+
+    /// Leave `path` in a merge conflict between `ours` and `theirs`, the way
+    /// a failed merge does: both sides in the index and markers in the file
+    pub fn conflict(self, path: &str, ours: &str, theirs: &str) -> Self {
+        let mut index = self.repo.index().unwrap();
+        let _ = index.remove_path(Path::new(path));
+
+        for (stage, content) in [(2u16, ours), (3u16, theirs)] {
+            let id = self.repo.blob(content.as_bytes()).unwrap();
+            index
+                .add(&IndexEntry {
+                    ctime: IndexTime::new(0, 0),
+                    mtime: IndexTime::new(0, 0),
+                    dev: 0,
+                    ino: 0,
+                    mode: 0o100644,
+                    uid: 0,
+                    gid: 0,
+                    file_size: content.len() as u32,
+                    id,
+                    flags: (stage << 12) | (path.len().min(0xfff) as u16),
+                    flags_extended: 0,
+                    path: path.as_bytes().to_vec(),
+                })
+                .unwrap();
+        }
+        index.write().unwrap();
+
+        let markers = format!("<<<<<<< ours\n{}=======\n{}>>>>>>> theirs\n", ours, theirs);
+        self.write(path, &markers)
+    }
+
+    /// Finish building and keep only the directory
+    pub fn into_dir(self) -> TempDir {
+        self.dir
+    }
+
+    /// Open the repository, keeping the directory alive with it
+    pub async fn open(self) -> (TempDir, Repository) {
+        let dir = self.into_dir();
+        let repo = Repository::open(dir.path()).await.unwrap();
+        (dir, repo)
+    }
+}
+
+impl Default for RepoBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_builds_history_and_refs() {
+        let builder = RepoBuilder::new()
+            .file("a.txt", "one\n")
+            .commit("First")
+            .branch("feature")
+            .file("a.txt", "two\n")
+            .commit("Second")
+            .tag("v1");
+        let head = builder.head();
+
+        let git_repo = builder.git2();
+        assert_eq!(git_repo.find_commit(head).unwrap().parent_count(), 1);
+        assert!(
+            git_repo
+                .find_branch("feature", git2::BranchType::Local)
+                .is_ok()
+        );
+        assert_eq!(git_repo.refname_to_id("refs/tags/v1").unwrap(), head);
+
+        let builder = builder.checkout("feature");
+        assert_eq!(
+            fs::read_to_string(builder.path().join("a.txt")).unwrap(),
+            "one\n"
+        );
+
+        let (_dir, repo) = builder.open().await;
+        assert_eq!(
+            repo.current_branch().await.unwrap(),
+            Some("feature".to_string())
+        );
+    }
+
+    #[test]
+    fn test_conflict_leaves_unmerged_entries() {
+        let builder = RepoBuilder::new()
+            .file("a.txt", "base\n")
+            .commit("Base")
+            .conflict("a.txt", "ours\n", "theirs\n");
+
+        let index = builder.git2().index().unwrap();
+        assert!(index.has_conflicts());
+        let content = fs::read_to_string(builder.path().join("a.txt")).unwrap();
+        assert!(content.contains("<<<<<<< ours"));
+    }
+}
@@ -0,0 +1,80 @@
+// Synthetic repositories for benchmarks
+// Enabled with the `testsupport` feature
+
+use crate::testing::RepoBuilder;
+use git2::Oid;
+use tempfile::TempDir;
+
+/// Build `lines` numbered lines, tagging each with `version`
+fn numbered_lines(lines: usize, version: usize) -> String {
+    (0..lines)
+        .map(|i| format!("line {} of version {}\n", i, version))
+        .collect()
+}
+
+/// Create a repository with `commits` linear commits, each touching one of
+/// `files` files in turn
+pub fn repo_with_history(commits: usize, files: usize) -> TempDir {
+    let files = files.max(1);
+    (0..commits)
+        .fold(RepoBuilder::new(), |builder, i| {
+            builder
+                .file(
+                    &format!("file{}.txt", i % files),
+                    &format!("content {}\n", i),
+                )
+                .commit(&format!("Commit {}", i))
+        })
+        .into_dir()
+}
+
+/// Create a repository whose HEAD commit rewrites every line of a file with
+/// `lines` lines; returns the directory and the HEAD commit
+pub fn repo_with_large_diff(lines: usize) -> (TempDir, Oid) {
+    let builder = RepoBuilder::new()
+        .file("large.txt", &numbered_lines(lines, 0))
+        .commit("Add large file")
+        .file("large.txt", &numbered_lines(lines, 1))
+        .commit("Rewrite large file");
+    let head = builder.head();
+    (builder.into_dir(), head)
+}
+
+/// Create a repository with one commit and `count` untracked files
+pub fn repo_with_untracked(count: usize) -> TempDir {
+    (0..count)
+        .fold(
+            RepoBuilder::new()
+                .file("README.md", "bench\n")
+                .commit("Initial"),
+            |builder, i| builder.write(&format!("untracked/new{}.txt", i), "new\n"),
+        )
+        .into_dir()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use git2::Repository;
+
+    #[test]
+    fn test_generated_repos() {
+        let dir = repo_with_history(5, 2);
+        let repo = Repository::open(dir.path()).unwrap();
+        let mut revwalk = repo.revwalk().unwrap();
+        revwalk.push_head().unwrap();
+        assert_eq!(revwalk.count(), 5);
+
+        let (dir, head) = repo_with_large_diff(10);
+        let repo = Repository::open(dir.path()).unwrap();
+        assert_eq!(repo.head().unwrap().target(), Some(head));
+
+        let dir = repo_with_untracked(3);
+        assert_eq!(
+            std::fs::read_dir(dir.path().join("untracked"))
+                .unwrap()
+                .count(),
+            3
+        );
+    }
+}
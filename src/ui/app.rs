@@ -1,18 +1,78 @@
+use super::browser;
+use super::command::{self, Command, Outcome};
+use super::credentials::CredentialInput;
 use super::event::Event;
+use super::keymap::{KeyPress, KeySequencer, Keymap};
+use super::push_dialog::{PushChoice, PushDialog};
+use super::start::StartView;
 use crate::{
-    config::{ColorScheme, Config},
-    git::Repository,
-    views::{Action, DiffView, HelpView, MainView, StatusView, ViewManager, ViewType},
+    clock::{SharedClock, system_clock},
+    config::{
+        ColorMode, ColorScheme, Config, DEFAULT_CLOCK_FORMAT, ReviewNotes, SessionState, Settings,
+        SharedReviewNotes, Symbols, apply_color_mode, parse_color,
+    },
+    crash,
+    error::AppError,
+    git::{
+        ChangelogSection, ChangelogTemplate, CheckoutTarget, CredentialPrompt, CredentialRequest,
+        FetchReport, LinkPattern, MergeOutcome, PrecommitOptions, PullReport, PushReport,
+        RefFilter, RepoChange, RepoSnapshot, Repository, ResetMode, Result as GitResult,
+        RevertOutcome, SshDiagnostics, StatusLoadOptions, watch,
+    },
+    views::{
+        Action, BlameView, DEFAULT_SPLIT, DiffView, EditRequest, HealthView, HelpView, HooksView,
+        LostView, MainView, OnboardingView, OutputView, RefsView, ReviewView, SPLIT_STEP,
+        StackView, StashView, StatusView, ViewManager, ViewType,
+        history::InputHistory,
+        popup::centered_rect,
+        scroll_memory::{ScrollMemory, SharedScrollMemory},
+        spell::SpellChecker,
+    },
 };
 use anyhow::Result;
+use chrono::{DateTime, Local};
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use git2::Oid;
 use ratatui::{
+    Frame,
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Style},
     text::{Line, Span},
-    widgets::Paragraph,
-    Frame,
+    widgets::{Clear, Paragraph},
 };
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+/// How often to check for changes made to the repository outside rust-tig
+const WATCH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How long a toast stays on screen
+const TOAST_DURATION: Duration = Duration::from_secs(4);
+
+/// A short message in the corner of the view area
+struct Toast {
+    message: String,
+    /// Whether the message reports a failure rather than a result
+    is_error: bool,
+    shown: Instant,
+}
+
+/// What the status bar says about the background fetches
+struct FetchStatus {
+    finished: DateTime<Local>,
+    /// The first remote that couldn't be fetched, and why
+    failure: Option<String>,
+    /// Commits the checked out branch is ahead of and behind its upstream
+    upstream: Option<(usize, usize)>,
+}
+
+/// Smallest terminal the views are laid out for
+const MIN_WIDTH: u16 = 40;
+const MIN_HEIGHT: u16 = 24;
 
 /// Main application state
 pub struct App {
@@ -22,6 +82,71 @@ pub struct App {
     branch: Option<String>,
     error: Option<String>,
     colors: ColorScheme,
+    settings: Settings,
+    session: SessionState,
+    keys: KeySequencer,
+    /// Latest snapshot of HEAD and the index
+    snapshot: Option<RepoSnapshot>,
+    last_watch: Option<Instant>,
+    watch_receiver: Option<mpsc::UnboundedReceiver<RepoSnapshot>>,
+    /// When the last background fetch started
+    last_fetch: Option<Instant>,
+    fetch_receiver: Option<mpsc::UnboundedReceiver<GitResult<FetchReport>>>,
+    fetch_status: Option<FetchStatus>,
+    /// Where fetches send requests for credentials no helper had
+    credential_prompt: CredentialPrompt,
+    credential_requests: mpsc::UnboundedReceiver<CredentialRequest>,
+    /// Credentials being typed for a remote
+    credential_input: Option<CredentialInput>,
+    /// Why SSH remotes failed in the last `:fetch`, shown until a key is pressed
+    ssh_diagnostics: Option<Vec<SshDiagnostics>>,
+    /// The commits the last `:pull` rebased, shown until a key is pressed
+    pull_report: Option<PullReport>,
+    /// The refs a push sent, shown when there was more than the branch or
+    /// the remote refused any
+    push_report: Option<PushReport>,
+    /// Where and how to push, being set up after `:push`
+    push_dialog: Option<PushDialog>,
+    /// Change made outside rust-tig that the views don't reflect yet
+    external_change: Option<RepoChange>,
+    /// Recoverable error or command result being shown
+    toast: Option<Toast>,
+    /// Text typed at the `:` prompt while it is open
+    command_line: Option<String>,
+    /// Earlier `:` lines, stepped through with Up and Down
+    command_history: InputHistory,
+    command_receiver: Option<mpsc::UnboundedReceiver<Result<Outcome, String>>>,
+    /// What the command in flight is doing, like "pushing"
+    command_label: &'static str,
+    clock: SharedClock,
+    /// Text a view wants edited, waiting for the main loop to run the editor
+    pending_edit: Option<EditRequest>,
+    /// Text a view copied, waiting for the main loop to send to the terminal
+    pending_copy: Option<String>,
+    /// Whether quitting waits on the user confirming, as work is in flight
+    confirm_quit: bool,
+    /// A checkout waiting on whether to carry uncommitted changes along
+    confirm_checkout: Option<CheckoutTarget>,
+    /// Commit asked about reverting, and its summary
+    confirm_revert: Option<(Oid, String)>,
+    /// Commit asked about resetting to, and its summary
+    confirm_reset: Option<(Oid, String)>,
+    /// Commit to hard reset to once confirmed
+    confirm_hard_reset: Option<Oid>,
+    /// View opened on top of the main view at startup
+    start: StartView,
+    /// Where to look for the repository instead of the current directory
+    repo_path: Option<PathBuf>,
+    /// Commits the main view lists instead of the history, from `--stdin`
+    commit_list: Option<Vec<Oid>>,
+    /// Dictionary for commit messages, when spell checking is on
+    spell_checker: Option<Arc<SpellChecker>>,
+    /// Where each commit's diff was left scrolled, for flipping between
+    /// commits in the split preview
+    scroll_memory: SharedScrollMemory,
+    /// Notes written on diffs while reviewing, shared with the diff and
+    /// review views
+    review_notes: SharedReviewNotes,
 }
 
 impl App {
@@ -29,28 +154,108 @@ impl App {
         // Load config, fall back to defaults if config fails to load
         let config = Config::load().unwrap_or_default();
         let colors = ColorScheme::from_config(&config.colors);
+        let spell_checker = match config.settings.spell_check {
+            true => SpellChecker::load(config.settings.spell_dictionary.as_deref().map(Path::new))
+                .map(Arc::new),
+            false => None,
+        };
+
+        let (credential_prompt, credential_requests) = mpsc::unbounded_channel();
 
         Self {
             running: true,
-            view_manager: ViewManager::new(),
+            view_manager: ViewManager::new().with_split(config.settings.split_diff),
             repo: None,
             branch: None,
             error: None,
             colors,
+            session: SessionState::default(),
+            keys: KeySequencer::new(
+                Keymap::new(),
+                Duration::from_millis(config.settings.key_sequence_timeout_ms),
+            ),
+            settings: config.settings,
+            snapshot: None,
+            last_watch: None,
+            watch_receiver: None,
+            last_fetch: None,
+            fetch_receiver: None,
+            fetch_status: None,
+            credential_prompt,
+            credential_requests,
+            credential_input: None,
+            ssh_diagnostics: None,
+            pull_report: None,
+            push_report: None,
+            push_dialog: None,
+            external_change: None,
+            toast: None,
+            command_line: None,
+            command_history: InputHistory::default(),
+            command_receiver: None,
+            command_label: "",
+            clock: system_clock(),
+            pending_edit: None,
+            pending_copy: None,
+            confirm_quit: false,
+            confirm_checkout: None,
+            confirm_revert: None,
+            confirm_reset: None,
+            confirm_hard_reset: None,
+            start: StartView::Main,
+            repo_path: None,
+            commit_list: None,
+            spell_checker,
+            scroll_memory: ScrollMemory::shared(),
+            review_notes: ReviewNotes::default().shared(),
         }
     }
 
+    /// Override the configured color mode (e.g. from `--no-color`)
+    pub fn with_color_mode(mut self, mode: ColorMode) -> Self {
+        self.settings.color_mode = mode;
+        self
+    }
+
+    /// Use a different clock for relative dates and the status bar clock
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Open another view on top of the main view at startup
+    pub fn with_start(mut self, start: StartView) -> Self {
+        self.start = start;
+        self
+    }
+
+    /// Open the repository at or above `path` rather than the one around
+    /// the current directory
+    pub fn with_repo_path(mut self, path: PathBuf) -> Self {
+        self.repo_path = Some(path);
+        self
+    }
+
+    /// List exactly these commits in the main view, in order
+    pub fn with_commit_list(mut self, ids: Vec<Oid>) -> Self {
+        self.commit_list = Some(ids);
+        self
+    }
+
     /// Initialize the application with a repository
     pub async fn init(&mut self) -> Result<()> {
-        match Repository::discover().await {
+        let discovered = match &self.repo_path {
+            Some(path) => Repository::discover_from(path).await,
+            None => Repository::discover().await,
+        };
+        match discovered {
             Ok(repo) => {
-                // Get branch name
-                self.branch = repo.current_branch().await.ok().flatten();
-
-                self.repo = Some(repo.clone());
-                // Create and push the main view
-                let main_view = MainView::new(repo, self.colors.clone());
-                self.view_manager.push(Box::new(main_view))?;
+                let branch = repo.current_branch().await.ok().flatten();
+                let empty = repo.is_empty().await?;
+                self.load_repo(repo.clone(), branch, empty)?;
+                if !empty {
+                    self.open_start_view(repo).await?;
+                }
                 Ok(())
             }
             Err(e) => {
@@ -60,11 +265,239 @@ impl App {
         }
     }
 
+    /// Load the session and notes kept for `repo` and start over with its
+    /// history, or the onboarding view when it has no commits yet
+    fn load_repo(&mut self, repo: Repository, branch: Option<String>, empty: bool) -> Result<()> {
+        self.branch = branch;
+
+        // Session state is optional, fall back to empty if it fails to load
+        self.session = SessionState::load_for_repo(repo.path()).unwrap_or_default();
+        self.view_manager
+            .set_split_ratios(self.session.split_ratios.clone());
+        *self.review_notes.borrow_mut() =
+            ReviewNotes::load_for_repo(repo.path()).unwrap_or_default();
+
+        self.repo = Some(repo.clone());
+        // A repository without commits has no history to walk yet
+        if empty {
+            let onboarding_view = OnboardingView::new(repo, self.colors.clone())
+                .with_commit_history(self.session.commit_messages.clone())
+                .with_spell_checker(self.spell_checker.clone())
+                .with_precommit_checks(self.precommit_options())
+                .with_symbols(self.symbols());
+            self.view_manager.reset(Box::new(onboarding_view))
+        } else {
+            let main_view = self.main_view(repo);
+            self.view_manager.reset(Box::new(main_view))
+        }
+    }
+
+    /// Close every view of the repository open now and start over in
+    /// another one, unless a view is still working on this one
+    fn switch_repo(&mut self, repo: Repository, branch: Option<String>, empty: bool) -> Result<()> {
+        let busy = self.view_manager.busy();
+        if !busy.is_empty() {
+            let message = format!(
+                "Still {}; open another repository once done",
+                busy.join(", ")
+            );
+            self.show_toast(message, true);
+            return Ok(());
+        }
+
+        // What was watched, fetched or remembered belongs to the old one
+        self.snapshot = None;
+        self.last_watch = None;
+        self.watch_receiver = None;
+        self.last_fetch = None;
+        self.fetch_receiver = None;
+        self.fetch_status = None;
+        self.external_change = None;
+        self.command_receiver = None;
+        self.scroll_memory = ScrollMemory::shared();
+
+        let workdir = repo
+            .open_git2()
+            .ok()
+            .and_then(|git_repo| git_repo.workdir().map(Path::to_path_buf));
+        let path: PathBuf = workdir
+            .as_deref()
+            .unwrap_or(repo.path())
+            .components()
+            .collect();
+        self.load_repo(repo, branch, empty)?;
+        self.show_toast(format!("Opened {}", path.display()), false);
+        Ok(())
+    }
+
+    /// Open the view asked for on the command line; a revision or file
+    /// that can't be found is reported over the main view
+    async fn open_start_view(&mut self, repo: Repository) -> Result<()> {
+        let action = match self.start.clone() {
+            StartView::Main => return Ok(()),
+            StartView::Status => Action::PushView(ViewType::Status),
+            StartView::Refs => Action::PushView(ViewType::Refs),
+            StartView::Show(revision) => {
+                match crate::git::refs::resolve_commit(&repo, revision.clone()).await {
+                    Ok(commit) => Action::OpenDiff {
+                        repo,
+                        commit_id: commit.id,
+                        summary: commit.summary,
+                    },
+                    Err(e) => {
+                        self.show_toast(format!("Can't show {}: {}", revision, e), true);
+                        return Ok(());
+                    }
+                }
+            }
+            StartView::Blame { path, line } => {
+                let workdir = repo.workdir().await?;
+                let path = repo_relative_path(workdir.as_deref(), &path);
+                let blame_view = self
+                    .blame_view(repo, path, None)
+                    .with_line(line.unwrap_or(1));
+                self.view_manager.push(Box::new(blame_view))?;
+                return Ok(());
+            }
+        };
+        self.handle_action(action)
+    }
+
+    /// Create a blame view with the configured colors
+    fn blame_view(&self, repo: Repository, path: String, revision: Option<Oid>) -> BlameView {
+        let heat = self
+            .settings
+            .blame_heat
+            .iter()
+            .map(|c| parse_color(c))
+            .collect();
+        BlameView::new(repo, path, revision, self.colors.clone())
+            .with_heat(heat)
+            .with_author_colors(self.settings.author_colors)
+            .with_title_template(self.settings.titles.blame.clone())
+            .with_symbols(self.symbols())
+    }
+
+    /// Create the status view with the configured settings
+    fn status_view(&self, repo: Repository) -> StatusView {
+        StatusView::new(repo, self.colors.clone())
+            .with_sparse_display(self.settings.sparse_paths)
+            .with_load_options(self.status_load_options())
+            .with_commit_history(self.session.commit_messages.clone())
+            .with_spell_checker(self.spell_checker.clone())
+            .with_precommit_checks(self.precommit_options())
+            .with_title_template(self.settings.titles.status.clone())
+            .with_symbols(self.symbols())
+    }
+
+    /// Create the main view with the configured settings
+    fn main_view(&self, repo: Repository) -> MainView {
+        let mut main_view = MainView::new(repo, self.colors.clone());
+        if let Some(ids) = &self.commit_list {
+            main_view = main_view.with_commit_list(ids.clone());
+        }
+        main_view
+            .with_search_case(self.settings.search_case)
+            .with_search_history(self.session.search_history.clone())
+            .with_describe_column(self.settings.describe_column)
+            .with_diffstat_column(self.settings.diffstat_column)
+            .with_max_diff_file_bytes(self.settings.max_diff_file_bytes)
+            .with_author_colors(self.settings.author_colors)
+            .with_conventional_commits(self.settings.conventional_commits)
+            .with_column_widths(self.settings.column_widths)
+            .with_compact_width(self.settings.compact_width)
+            .with_title_template(self.settings.titles.main.clone())
+            .with_ref_filter(self.ref_filter())
+            .with_copy_formats(self.settings.copy_formats.clone())
+            .with_changelog(self.changelog_template())
+            .with_symbols(self.symbols())
+            .with_clock(self.clock.clone())
+    }
+
+    /// Get the glyphs to draw with, following the symbol and color settings
+    fn symbols(&self) -> Symbols {
+        Symbols::from_settings(&self.settings)
+    }
+
+    /// Get the ref patterns of the main view's walk from the settings
+    fn ref_filter(&self) -> RefFilter {
+        let refs = &self.settings.refs;
+        RefFilter {
+            hide_decorations: refs.hide_decorations.clone(),
+            walk: refs.walk.clone(),
+            hide: refs.hide.clone(),
+        }
+    }
+
+    /// Get the status scan options from the settings
+    fn status_load_options(&self) -> StatusLoadOptions {
+        let status = &self.settings.status;
+        StatusLoadOptions {
+            detect_renames: status.detect_renames,
+            recurse_untracked_dirs: status.recurse_untracked_dirs,
+            exclude_paths: status.exclude_paths.clone(),
+            update_index: status.update_index,
+            ..StatusLoadOptions::default()
+        }
+    }
+
+    /// Get the patterns of issue references in commit messages from the
+    /// settings
+    fn link_patterns(&self) -> Vec<LinkPattern> {
+        self.settings
+            .issue_links
+            .iter()
+            .map(|link| LinkPattern {
+                pattern: link.pattern.clone(),
+                url: link.url.clone(),
+            })
+            .collect()
+    }
+
+    /// Build the changelog template of the main view from the settings
+    fn changelog_template(&self) -> ChangelogTemplate {
+        let changelog = &self.settings.changelog;
+        ChangelogTemplate {
+            sections: changelog
+                .sections
+                .iter()
+                .map(|section| ChangelogSection {
+                    title: section.title.clone(),
+                    types: section
+                        .types
+                        .iter()
+                        .map(|kind| kind.to_ascii_lowercase())
+                        .collect(),
+                })
+                .collect(),
+            other: Some(changelog.other.clone()).filter(|other| !other.is_empty()),
+            heading: changelog.heading.clone(),
+            entry: changelog.entry.clone(),
+        }
+    }
+
+    /// Build the pre-commit check options from the settings, None when off
+    fn precommit_options(&self) -> Option<PrecommitOptions> {
+        let checks = &self.settings.precommit_checks;
+        checks.enabled.then(|| PrecommitOptions {
+            max_file_bytes: checks.max_file_bytes,
+            secret_patterns: checks.secret_patterns.clone(),
+        })
+    }
+
     /// Check if the application should keep running
     pub fn is_running(&self) -> bool {
         self.running
     }
 
+    /// Check whether keys go into a text input; what is typed there may be
+    /// private (commit messages, searches, passwords)
+    pub fn captures_text(&self) -> bool {
+        self.view_manager.captures_input()
+            || self.credential_input.is_some()
+            || self.command_line.is_some()
+    }
+
     /// Handle an event
     pub fn handle_event(&mut self, event: Event) -> Result<()> {
         match event {
@@ -77,38 +510,542 @@ impl App {
 
     /// Handle a key event
     fn handle_key(&mut self, key: KeyEvent) -> Result<()> {
+        if self.captures_text() {
+            crash::breadcrumb("key (text input)");
+        } else {
+            crash::breadcrumb(format!("key {:?} {:?}", key.code, key.modifiers));
+        }
+
         // Global keybindings (Ctrl+C to quit)
         if key.code == KeyCode::Char('c') && key.modifiers == KeyModifiers::CONTROL {
             self.running = false;
             return Ok(());
         }
 
+        if let Some(input) = self.credential_input.take() {
+            self.credential_input = input.handle_key(key);
+            return Ok(());
+        }
+        if self.ssh_diagnostics.take().is_some()
+            || self.pull_report.take().is_some()
+            || self.push_report.take().is_some()
+        {
+            return Ok(());
+        }
+        if let Some(dialog) = self.push_dialog.take() {
+            match dialog.handle_key(key) {
+                PushChoice::Open(dialog) => self.push_dialog = Some(dialog),
+                PushChoice::Cancel => {}
+                PushChoice::Push(options) => self.start_command(Command::Push(options)),
+            }
+            return Ok(());
+        }
+
+        // Answer to "check out anyway?"; anything but y leaves HEAD alone
+        if let Some(target) = self.confirm_checkout.take() {
+            if key.code == KeyCode::Char('y') {
+                self.start_command(Command::Checkout {
+                    target,
+                    carry_changes: true,
+                });
+            }
+            return Ok(());
+        }
+
+        // Answer to "revert it?": commit right away, or stage it to edit
+        // the message first; anything else leaves it
+        if let Some((id, _)) = self.confirm_revert.take() {
+            match key.code {
+                KeyCode::Char('y') => self.start_command(Command::Revert { id, commit: true }),
+                KeyCode::Char('e') => self.start_command(Command::Revert { id, commit: false }),
+                _ => {}
+            }
+            return Ok(());
+        }
+
+        // Answer to "reset how?"; a hard reset asks once more, as it throws
+        // away uncommitted work
+        if let Some((id, _)) = self.confirm_reset.take() {
+            match key.code {
+                KeyCode::Char('s') => self.start_command(Command::Reset {
+                    id,
+                    mode: ResetMode::Soft,
+                }),
+                KeyCode::Char('m') => self.start_command(Command::Reset {
+                    id,
+                    mode: ResetMode::Mixed,
+                }),
+                KeyCode::Char('h') => self.confirm_hard_reset = Some(id),
+                _ => {}
+            }
+            return Ok(());
+        }
+        if let Some(id) = self.confirm_hard_reset.take() {
+            if key.code == KeyCode::Char('y') {
+                self.start_command(Command::Reset {
+                    id,
+                    mode: ResetMode::Hard,
+                });
+            }
+            return Ok(());
+        }
+
+        // Answer to "quit anyway?"; anything but y keeps going
+        if self.confirm_quit {
+            self.confirm_quit = false;
+            if key.code == KeyCode::Char('y') {
+                self.running = false;
+            }
+            return Ok(());
+        }
+
+        if self.command_line.is_some() {
+            return self.handle_command_key(key);
+        }
+        if key.code == KeyCode::Char(':') && !self.view_manager.captures_input() {
+            self.command_line = Some(String::new());
+            self.command_history = InputHistory::new(self.session.command_history.clone());
+            return Ok(());
+        }
+
+        // Quit from any view
+        if key.code == KeyCode::Char('Q') && !self.view_manager.captures_input() {
+            self.request_quit();
+            return Ok(());
+        }
+
+        // Reload everything, e.g. after the repository changed outside rust-tig
+        if matches!(key.code, KeyCode::F(5) | KeyCode::Char('R'))
+            && !self.view_manager.captures_input()
+        {
+            return self.refresh();
+        }
+
+        // Move keys to the other pane of a split
+        if (key.code == KeyCode::Tab
+            || (key.code == KeyCode::Char('w') && key.modifiers.contains(KeyModifiers::CONTROL)))
+            && self.view_manager.split_ratio().is_some()
+            && !self.view_manager.captures_input()
+        {
+            self.view_manager.toggle_focus();
+            return Ok(());
+        }
+
+        // Give the focused pane of a split the whole screen, or restore the split
+        if key.code == KeyCode::Char('z')
+            && self.view_manager.split_name().is_some()
+            && !self.view_manager.captures_input()
+        {
+            self.view_manager.toggle_zoom();
+            return Ok(());
+        }
+
+        // Move the divider of a split, or put it back in the middle
+        if let Some(ratio) = self.view_manager.split_ratio()
+            && !self.view_manager.captures_input()
+        {
+            let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+            let percent = match key.code {
+                KeyCode::Char('<') => Some(ratio.saturating_sub(SPLIT_STEP)),
+                KeyCode::Up if ctrl => Some(ratio.saturating_sub(SPLIT_STEP)),
+                KeyCode::Char('>') => Some(ratio + SPLIT_STEP),
+                KeyCode::Down if ctrl => Some(ratio + SPLIT_STEP),
+                KeyCode::Char('=') => Some(DEFAULT_SPLIT),
+                _ => None,
+            };
+            if let Some(percent) = percent {
+                self.resize_split(percent);
+                return Ok(());
+            }
+        }
+
+        // Resolve counts and multi-key bindings unless the view is taking text input
+        let press = match self.view_manager.focused_view_type() {
+            Some(view_type) if !self.view_manager.captures_input() => {
+                match self.keys.feed(view_type, key, Instant::now()) {
+                    Some(press) => press,
+                    None => return Ok(()),
+                }
+            }
+            _ => KeyPress { key, count: None },
+        };
+
         // Delegate to view manager and handle actions
-        let action = self.view_manager.handle_key(key)?;
-        self.handle_action(action)?;
+        let result = match press.count {
+            Some(count) => self.view_manager.handle_key_with_count(press.key, count),
+            None => self.view_manager.handle_key(press.key),
+        };
+        match result {
+            Ok(action) => self.handle_action(action)?,
+            Err(error) => self.report(error)?,
+        }
+
+        Ok(())
+    }
+
+    /// Edit the `:` prompt, running the command on Enter
+    fn handle_command_key(&mut self, key: KeyEvent) -> Result<()> {
+        let Some(line) = &mut self.command_line else {
+            return Ok(());
+        };
+        match key.code {
+            KeyCode::Esc => self.command_line = None,
+            // Backspace on an empty prompt closes it, like vim
+            KeyCode::Backspace if line.pop().is_none() => self.command_line = None,
+            KeyCode::Backspace => self.command_history.reset(),
+            KeyCode::Up => {
+                if let Some(entry) = self.command_history.older(line) {
+                    *line = entry.to_string();
+                }
+            }
+            KeyCode::Down => {
+                if let Some(entry) = self.command_history.newer() {
+                    *line = entry.to_string();
+                }
+            }
+            KeyCode::Enter => {
+                let line = self.command_line.take().unwrap_or_default();
+                if !line.trim().is_empty() {
+                    self.session.record_command(line.trim());
+                    if let Some(repo) = &self.repo {
+                        // Losing history is not worth interrupting the user for
+                        let _ = self.session.save_for_repo(repo.path());
+                    }
+                    self.run_command(&line);
+                }
+            }
+            KeyCode::Char(c) => {
+                self.command_history.reset();
+                line.push(c);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Parse and start a command typed at the `:` prompt
+    fn run_command(&mut self, line: &str) {
+        // Arguments may be private (commit messages, search terms)
+        let name = line.split_whitespace().next().unwrap_or_default();
+        crash::breadcrumb(format!("command {}", name));
+        let selection = self
+            .view_manager
+            .focused()
+            .map(|view| view.selection())
+            .unwrap_or_default();
+        let line = match command::expand_alias(
+            line,
+            &self.settings.aliases,
+            &selection,
+            self.branch.as_deref(),
+        ) {
+            Ok(line) => line,
+            Err(message) => return self.show_toast(message, true),
+        };
+        match command::parse(&line) {
+            Ok(command) => self.start_command(command),
+            Err(message) => self.show_toast(message, true),
+        }
+    }
 
+    /// Run a command in the background
+    fn start_command(&mut self, command: Command) {
+        // The dialog doesn't offer it, but a plain force push stays off
+        // unless the config allows it, whatever asks for one
+        let force = match &command {
+            Command::Push(options) => options.force,
+            Command::PushWith { force, .. } => *force,
+            _ => false,
+        };
+        if force && !self.settings.allow_force_push {
+            return self.show_toast(
+                "Force push is off; set allow_force_push to use it".to_string(),
+                true,
+            );
+        }
+        let Some(repo) = self.repo.clone() else {
+            return;
+        };
+        // One at a time, so no result is lost and no two of them write to
+        // the repository at once
+        if self.command_receiver.is_some() {
+            let message = format!("Still {}; wait for it to finish", self.command_label);
+            return self.show_toast(message, true);
+        }
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.command_receiver = Some(rx);
+        self.command_label = command.label();
+        let prompt = self.credential_prompt.clone();
+        tokio::spawn(async move {
+            let _ = tx.send(command::run(&repo, command, prompt).await);
+        });
+    }
+
+    /// Act on a finished command
+    fn finish_command(&mut self, result: Result<Outcome, String>) -> Result<()> {
+        match result {
+            Ok(Outcome::SelectCommit { oid, message }) => {
+                if self.view_manager.select_commit(oid)? {
+                    self.show_toast(message, false);
+                } else {
+                    self.show_toast("No commit list to select in".to_string(), true);
+                }
+            }
+            Ok(Outcome::Fetched(report)) => {
+                if report.failures.is_empty() {
+                    let message = match report.remotes {
+                        1 => "Fetched 1 remote".to_string(),
+                        n => format!("Fetched {} remotes", n),
+                    };
+                    self.show_toast(message, false);
+                }
+                if !report.ssh_diagnostics.is_empty() {
+                    self.ssh_diagnostics = Some(report.ssh_diagnostics.clone());
+                }
+                self.record_fetch(Ok(report));
+            }
+            Ok(Outcome::Pulled(report)) => {
+                self.show_toast(report.summary(), report.stash_kept);
+                if let Some(status) = &mut self.fetch_status {
+                    status.upstream = report.counts;
+                }
+                if report.incoming > 0 {
+                    self.view_manager.refresh_all();
+                }
+                if !report.rebased.is_empty() {
+                    self.pull_report = Some(report);
+                }
+            }
+            Ok(Outcome::PushDialog(options)) => {
+                self.push_dialog = Some(PushDialog::new(options, self.settings.allow_force_push));
+            }
+            Ok(Outcome::Pushed { report, upstream }) => {
+                let rejected = report.rejected().next().is_some();
+                self.show_toast(report.summary(), rejected);
+                if rejected || report.refs.len() > 1 {
+                    self.push_report = Some(report);
+                }
+                if let Some(status) = &mut self.fetch_status {
+                    status.upstream = upstream;
+                }
+                self.view_manager.refresh_all();
+            }
+            Ok(Outcome::PushFailed { message, ssh }) => {
+                self.show_toast(message, true);
+                self.ssh_diagnostics = Some(vec![ssh]);
+            }
+            Ok(Outcome::CheckedOut(message)) => {
+                self.show_toast(message, false);
+                self.view_manager.refresh_all();
+            }
+            Ok(Outcome::ConfirmCheckout(target)) => self.confirm_checkout = Some(target),
+            Ok(Outcome::Reverted { id, outcome }) => {
+                self.view_manager.refresh_all();
+                let short_id = format!("{:.7}", id.to_string());
+                let open_status = match outcome {
+                    RevertOutcome::Committed(new_id) => {
+                        self.show_toast(
+                            format!("Reverted {} as {:.7}", short_id, new_id.to_string()),
+                            false,
+                        );
+                        None
+                    }
+                    RevertOutcome::Staged => Some(true),
+                    RevertOutcome::Conflicted(paths) => {
+                        let message = format!(
+                            "Reverting {} conflicts in {} files; resolve, stage and commit to finish",
+                            short_id,
+                            paths.len()
+                        );
+                        self.show_toast(message, true);
+                        Some(false)
+                    }
+                };
+                // The revert is finished by committing from there
+                if let (Some(prompt), Some(repo)) = (open_status, &self.repo) {
+                    let status_view = self.status_view(repo.clone()).with_commit_prompt(prompt);
+                    self.view_manager.push(Box::new(status_view))?;
+                }
+            }
+            Ok(Outcome::Merged { name, outcome }) => {
+                self.view_manager.refresh_all();
+                let short_name = crate::git::checkout::shorthand(&name).to_string();
+                let open_status = match outcome {
+                    MergeOutcome::UpToDate => {
+                        self.show_toast(format!("Already up to date with {}", short_name), false);
+                        None
+                    }
+                    MergeOutcome::FastForward(id) => {
+                        let message =
+                            format!("Fast-forwarded to {} ({:.7})", short_name, id.to_string());
+                        self.show_toast(message, false);
+                        None
+                    }
+                    MergeOutcome::Staged => {
+                        self.show_toast(
+                            format!("Merged {}; commit to finish the merge", short_name),
+                            false,
+                        );
+                        Some(true)
+                    }
+                    MergeOutcome::Conflicted(paths) => {
+                        let message = format!(
+                            "Merging {} conflicts in {} files; resolve, stage and commit to finish",
+                            short_name,
+                            paths.len()
+                        );
+                        self.show_toast(message, true);
+                        Some(false)
+                    }
+                };
+                // The merge commit is made from there
+                if let (Some(prompt), Some(repo)) = (open_status, &self.repo) {
+                    let status_view = self.status_view(repo.clone()).with_commit_prompt(prompt);
+                    self.view_manager.push(Box::new(status_view))?;
+                }
+            }
+            Ok(Outcome::Reset { id, mode, branch }) => {
+                self.show_toast(
+                    format!(
+                        "Reset {} to {:.7} ({})",
+                        branch,
+                        id.to_string(),
+                        mode.label()
+                    ),
+                    false,
+                );
+                self.view_manager.refresh_all();
+            }
+            Ok(Outcome::CherryPicked(report)) => {
+                self.show_toast(report.summary(), report.conflict.is_some());
+                self.view_manager.refresh_all();
+                // The conflicted files are resolved and committed from there
+                if report.conflict.is_some() {
+                    self.handle_action(Action::PushView(ViewType::Status))?;
+                }
+            }
+            Ok(Outcome::LostCommits { query, commits }) => {
+                if let Some(repo) = &self.repo {
+                    let lost_view =
+                        LostView::new(repo.clone(), self.colors.clone(), query, commits)
+                            .with_symbols(self.symbols());
+                    self.view_manager.push(Box::new(lost_view))?;
+                }
+            }
+            Ok(Outcome::OpenView(view_type)) => self.handle_action(Action::PushView(view_type))?,
+            Ok(Outcome::Maintenance(task)) => {
+                let mut active = self.view_manager.busy();
+                if self.fetch_receiver.is_some() {
+                    active.push("fetching");
+                }
+                if !active.is_empty() {
+                    let warning = format!(
+                        "{} runs while still {}; either may fail on a locked file",
+                        task.command_line(),
+                        active.join(", ")
+                    );
+                    self.show_toast(warning, true);
+                }
+                if let Some(repo) = &self.repo {
+                    let output_view = OutputView::new(repo.clone(), self.colors.clone(), task)
+                        .with_symbols(self.symbols());
+                    self.view_manager.push(Box::new(output_view))?;
+                }
+            }
+            Ok(Outcome::Opened {
+                repo,
+                branch,
+                empty,
+            }) => self.switch_repo(repo, branch, empty)?,
+            Err(message) => self.show_toast(message, true),
+        }
         Ok(())
     }
 
+    fn show_toast(&mut self, message: String, is_error: bool) {
+        self.toast = Some(Toast {
+            message,
+            is_error,
+            shown: Instant::now(),
+        });
+    }
+
     /// Handle an action from a view
     fn handle_action(&mut self, action: Action) -> Result<()> {
+        if action != Action::None {
+            crash::breadcrumb(format!("action {}", action.name()));
+        }
         match action {
             Action::Quit => {
-                self.running = false;
+                self.request_quit();
+            }
+            Action::SwitchView(ViewType::Main) => {
+                if let Some(repo) = &self.repo {
+                    let main_view = self.main_view(repo.clone());
+                    self.view_manager.switch(Box::new(main_view))?;
+                }
             }
             Action::SwitchView(_view_type) => {
-                // TODO: Implement view switching
+                // TODO: Implement switching to other views
             }
             Action::PushView(view_type) => {
                 match view_type {
                     ViewType::Status => {
                         if let Some(repo) = &self.repo {
-                            let status_view = StatusView::new(repo.clone(), self.colors.clone());
+                            let status_view = self.status_view(repo.clone());
                             self.view_manager.push(Box::new(status_view))?;
                         }
                     }
+                    ViewType::Refs => {
+                        if let Some(repo) = &self.repo {
+                            let refs_view = RefsView::new(repo.clone(), self.colors.clone())
+                                .with_symbols(self.symbols())
+                                .with_clock(self.clock.clone());
+                            self.view_manager.push(Box::new(refs_view))?;
+                        }
+                    }
+                    ViewType::Stack => {
+                        if let Some(repo) = &self.repo {
+                            let stack_view = StackView::new(repo.clone(), self.colors.clone())
+                                .with_symbols(self.symbols());
+                            self.view_manager.push(Box::new(stack_view))?;
+                        }
+                    }
+                    ViewType::Health => {
+                        if let Some(repo) = &self.repo {
+                            let health_view = HealthView::new(repo.clone(), self.colors.clone())
+                                .with_symbols(self.symbols());
+                            self.view_manager.push(Box::new(health_view))?;
+                        }
+                    }
+                    ViewType::Hooks => {
+                        if let Some(repo) = &self.repo {
+                            let hooks_view = HooksView::new(repo.clone(), self.colors.clone())
+                                .with_symbols(self.symbols());
+                            self.view_manager.push(Box::new(hooks_view))?;
+                        }
+                    }
+                    ViewType::Stash => {
+                        if let Some(repo) = &self.repo {
+                            let stash_view = StashView::new(repo.clone(), self.colors.clone())
+                                .with_symbols(self.symbols());
+                            self.view_manager.push(Box::new(stash_view))?;
+                        }
+                    }
+                    ViewType::Review => {
+                        if let Some(repo) = &self.repo {
+                            let review_view = ReviewView::new(
+                                repo.clone(),
+                                Rc::clone(&self.review_notes),
+                                self.colors.clone(),
+                            )
+                            .with_symbols(self.symbols());
+                            self.view_manager.push(Box::new(review_view))?;
+                        }
+                    }
                     ViewType::Help => {
-                        let help_view = HelpView::new();
+                        let help_view = HelpView::new().with_symbols(self.symbols());
                         self.view_manager.push(Box::new(help_view))?;
                     }
                     _ => {
@@ -116,6 +1053,10 @@ impl App {
                     }
                 }
             }
+            Action::ShowHelp(from) => {
+                let help_view = HelpView::for_view(from).with_symbols(self.symbols());
+                self.view_manager.push(Box::new(help_view))?;
+            }
             Action::PopView => {
                 self.view_manager.pop().ok(); // Ignore error if can't pop
             }
@@ -124,64 +1065,702 @@ impl App {
                 commit_id,
                 summary,
             } => {
-                let diff_view = DiffView::new(repo, commit_id, summary, self.colors.clone());
+                let mut diff_view = DiffView::new(repo, commit_id, summary, self.colors.clone())
+                    .with_max_file_bytes(self.settings.max_diff_file_bytes)
+                    .with_title_template(self.settings.titles.diff.clone())
+                    .with_sticky_headers(self.settings.sticky_diff_headers)
+                    .with_link_patterns(self.link_patterns())
+                    .with_review_notes(Rc::clone(&self.review_notes))
+                    .with_viewed_files(&self.session.viewed_files)
+                    .with_symbols(self.symbols());
+                if self.settings.split_diff {
+                    diff_view = diff_view.with_scroll_memory(Rc::clone(&self.scroll_memory));
+                }
                 self.view_manager.push(Box::new(diff_view))?;
             }
+            Action::OpenRangeDiff { repo, from, to } => {
+                let diff_view = DiffView::new_range(repo, from, to, self.colors.clone())
+                    .with_max_file_bytes(self.settings.max_diff_file_bytes)
+                    .with_title_template(self.settings.titles.diff.clone())
+                    .with_sticky_headers(self.settings.sticky_diff_headers)
+                    .with_review_notes(Rc::clone(&self.review_notes))
+                    .with_viewed_files(&self.session.viewed_files)
+                    .with_symbols(self.symbols());
+                self.view_manager.push(Box::new(diff_view))?;
+            }
+            Action::OpenBlame {
+                repo,
+                path,
+                revision,
+            } => {
+                let blame_view = self.blame_view(repo, path, revision);
+                self.view_manager.push(Box::new(blame_view))?;
+            }
             Action::OpenStagedDiff { repo, path } => {
-                let diff_view = DiffView::new_staged(repo, path, self.colors.clone());
+                let diff_view = DiffView::new_staged(repo, path, self.colors.clone())
+                    .with_max_file_bytes(self.settings.max_diff_file_bytes)
+                    .with_title_template(self.settings.titles.diff.clone())
+                    .with_sticky_headers(self.settings.sticky_diff_headers)
+                    .with_symbols(self.symbols());
                 self.view_manager.push(Box::new(diff_view))?;
             }
             Action::OpenUnstagedDiff { repo, path } => {
-                let diff_view = DiffView::new_unstaged(repo, path, self.colors.clone());
+                let diff_view = DiffView::new_unstaged(repo, path, self.colors.clone())
+                    .with_max_file_bytes(self.settings.max_diff_file_bytes)
+                    .with_title_template(self.settings.titles.diff.clone())
+                    .with_sticky_headers(self.settings.sticky_diff_headers)
+                    .with_symbols(self.symbols());
                 self.view_manager.push(Box::new(diff_view))?;
             }
+            Action::Compare(upstream) => {
+                if let Some(repo) = &self.repo {
+                    let main_view = self.main_view(repo.clone()).with_comparison(upstream);
+                    self.view_manager.push(Box::new(main_view))?;
+                }
+            }
+            Action::Fetch(remote) => self.start_command(Command::Fetch {
+                prune: false,
+                remote: Some(remote),
+            }),
+            Action::Checkout(target) => self.start_command(Command::Checkout {
+                target,
+                carry_changes: false,
+            }),
+            Action::CherryPick(ids) => self.start_command(Command::CherryPick(ids)),
+            Action::Revert { id, summary } => self.confirm_revert = Some((id, summary)),
+            Action::Reset { id, summary } => self.confirm_reset = Some((id, summary)),
+            Action::Merge(name) => self.start_command(Command::Merge(name)),
+            Action::Copy(text) => {
+                let message = match text.lines().count() {
+                    0 | 1 => format!("Copied {}", text.trim()),
+                    lines => format!("Copied {} lines", lines),
+                };
+                self.show_toast(message, false);
+                self.pending_copy = Some(text);
+            }
+            Action::OpenUrl(url) => match browser::open_url(&url) {
+                Ok(()) => self.show_toast(format!("Opened {}", url), false),
+                Err(e) => self.show_toast(format!("Can't open {}: {}", url, e), true),
+            },
+            Action::Refresh => return self.refresh(),
+            Action::RecordSearch(query) => {
+                self.session.record_search(&query);
+                if let Some(repo) = &self.repo {
+                    // Losing history is not worth interrupting the user for
+                    let _ = self.session.save_for_repo(repo.path());
+                }
+            }
+            Action::RecordCommitMessage(message) => {
+                self.session.record_commit_message(&message);
+                if let Some(repo) = &self.repo {
+                    let _ = self.session.save_for_repo(repo.path());
+                }
+            }
+            Action::MarkViewed { diff, path, viewed } => {
+                self.session.set_viewed(&diff, &path, viewed);
+                if let Some(repo) = &self.repo {
+                    let _ = self.session.save_for_repo(repo.path());
+                }
+            }
+            Action::SaveReviewNotes => {
+                if let Some(repo) = &self.repo {
+                    // Like the session, a note that wasn't saved is not worth
+                    // interrupting the review for
+                    let _ = self.review_notes.borrow().save_for_repo(repo.path());
+                }
+            }
+            Action::EditText(request) => {
+                self.pending_edit = Some(request);
+            }
             Action::None => {}
         }
         Ok(())
     }
 
-    /// Update application state
-    pub fn update(&mut self) -> Result<()> {
-        self.view_manager.update()?;
-        Ok(())
+    /// Give the upper pane of the split on screen `percent` of the area,
+    /// remembering it for the next session
+    fn resize_split(&mut self, percent: u16) {
+        if self.view_manager.set_split_ratio(percent).is_some() {
+            self.session.split_ratios = self.view_manager.split_ratios().clone();
+            if let Some(repo) = &self.repo {
+                let _ = self.session.save_for_repo(repo.path());
+            }
+        }
     }
 
-    /// Render the application
-    pub fn render(&mut self, frame: &mut Frame) {
-        let area = frame.area();
-
-        // Show error screen if there's an error
-        if let Some(error) = &self.error {
-            self.render_error(frame, area, error);
-            return;
+    /// Say what views and the command in flight are still doing
+    fn busy(&self) -> Vec<&'static str> {
+        let mut busy = self.view_manager.busy();
+        if self.command_receiver.is_some() {
+            busy.push(self.command_label);
         }
+        busy
+    }
 
-        // Create layout with status bar
-        let chunks = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints([
-                Constraint::Min(1),    // Main content
-                Constraint::Length(1), // Status bar
-            ])
-            .split(area);
+    /// Quit, unless views or a command are still working and the user
+    /// should confirm
+    fn request_quit(&mut self) {
+        if self.busy().is_empty() {
+            self.running = false;
+        } else {
+            self.confirm_quit = true;
+        }
+    }
 
-        // Render current view
-        self.view_manager.draw(frame, chunks[0]);
+    /// Take the text waiting to be edited, if any; the caller runs the
+    /// editor and finishes the request
+    pub fn take_pending_edit(&mut self) -> Option<EditRequest> {
+        self.pending_edit.take()
+    }
 
-        // Render status bar
-        self.render_status_bar(frame, chunks[1]);
+    /// Take the text waiting to be copied, if any; the caller sends it to
+    /// the terminal's clipboard
+    pub fn take_pending_copy(&mut self) -> Option<String> {
+        self.pending_copy.take()
     }
 
-    /// Render error screen
-    fn render_error(&self, frame: &mut Frame, area: Rect, error: &str) {
-        let text = vec![
-            Line::from(""),
-            Line::from(Span::styled(
-                "Error",
-                Style::default().fg(Color::Red),
-            )),
-            Line::from(""),
-            Line::from(error),
+    /// Update application state
+    pub fn update(&mut self) -> Result<()> {
+        self.keys.expire(Instant::now());
+        self.watch_repo();
+        self.auto_fetch();
+        if self
+            .toast
+            .as_ref()
+            .is_some_and(|t| t.shown.elapsed() >= TOAST_DURATION)
+        {
+            self.toast = None;
+        }
+        if self.credential_input.is_none()
+            && let Ok(request) = self.credential_requests.try_recv()
+        {
+            self.credential_input = Some(CredentialInput::new(request));
+        }
+        if let Some(receiver) = &mut self.command_receiver
+            && let Ok(result) = receiver.try_recv()
+        {
+            self.command_receiver = None;
+            self.finish_command(result)?;
+        }
+        if let Err(error) = self.view_manager.update() {
+            self.report(error)?;
+        }
+        let action = self.view_manager.poll_action();
+        self.handle_action(action)?;
+        Ok(())
+    }
+
+    /// Show a recoverable error as a toast; fatal errors end the app
+    fn report(&mut self, error: AppError) -> Result<()> {
+        match error {
+            AppError::Recoverable(message) => {
+                self.show_toast(message, true);
+                Ok(())
+            }
+            AppError::Fatal(error) => Err(error),
+        }
+    }
+
+    /// Periodically snapshot HEAD and the index to notice outside changes
+    fn watch_repo(&mut self) {
+        if let Some(receiver) = &mut self.watch_receiver
+            && let Ok(snapshot) = receiver.try_recv()
+        {
+            self.watch_receiver = None;
+            match &self.snapshot {
+                Some(previous) => {
+                    if let Some(change) = snapshot.change_since(previous) {
+                        self.external_change = Some(change);
+                    }
+                }
+                // First snapshot since starting or refreshing
+                None => self.branch = Some(snapshot.branch()),
+            }
+            self.snapshot = Some(snapshot);
+        }
+
+        let due = self
+            .last_watch
+            .is_none_or(|last| last.elapsed() >= WATCH_INTERVAL);
+        if let Some(repo) = &self.repo
+            && due
+            && self.watch_receiver.is_none()
+        {
+            let (tx, rx) = mpsc::unbounded_channel();
+            self.watch_receiver = Some(rx);
+            self.last_watch = Some(Instant::now());
+
+            let repo = repo.clone();
+            tokio::spawn(async move {
+                // A failed snapshot just means no news this round
+                if let Ok(snapshot) = watch::snapshot(&repo).await {
+                    let _ = tx.send(snapshot);
+                }
+            });
+        }
+    }
+
+    /// Show how a fetch went in the status bar, reloading the views when a
+    /// remote-tracking branch moved
+    fn record_fetch(&mut self, result: GitResult<FetchReport>) {
+        let upstream = self
+            .fetch_status
+            .as_ref()
+            .and_then(|status| status.upstream);
+        let (failure, upstream) = match result {
+            Ok(report) => {
+                if report.refs_changed {
+                    self.view_manager.refresh_all();
+                }
+                (report.failures.into_iter().next(), report.upstream)
+            }
+            Err(e) => (Some(e.to_string()), upstream),
+        };
+        // Say why once, when fetching starts failing; the status bar keeps
+        // showing that it does
+        let was_failing = self
+            .fetch_status
+            .as_ref()
+            .is_some_and(|status| status.failure.is_some());
+        if let Some(failure) = &failure
+            && !was_failing
+        {
+            self.show_toast(format!("Fetch failed: {}", failure), true);
+        }
+        self.fetch_status = Some(FetchStatus {
+            finished: self.clock.now(),
+            failure,
+            upstream,
+        });
+    }
+
+    /// Fetch every remote in the background each `auto_fetch.interval_secs`,
+    /// without asking for credentials
+    fn auto_fetch(&mut self) {
+        if let Some(receiver) = &mut self.fetch_receiver
+            && let Ok(result) = receiver.try_recv()
+        {
+            self.fetch_receiver = None;
+            self.record_fetch(result);
+        }
+
+        let interval = Duration::from_secs(self.settings.auto_fetch.interval_secs);
+        let due = self
+            .last_fetch
+            .is_none_or(|last| last.elapsed() >= interval);
+        if let Some(repo) = &self.repo
+            && !interval.is_zero()
+            && due
+            && self.fetch_receiver.is_none()
+        {
+            let (tx, rx) = mpsc::unbounded_channel();
+            self.fetch_receiver = Some(rx);
+            self.last_fetch = Some(Instant::now());
+
+            let repo = repo.clone();
+            let prune = self.settings.auto_fetch.prune;
+            tokio::spawn(async move {
+                let _ = tx.send(crate::git::remote::fetch_all(&repo, prune, None).await);
+            });
+        }
+    }
+
+    /// Bring every view back in sync with the repository: walk the history
+    /// again, re-read the status, refs and diffs, and the current branch
+    fn refresh(&mut self) -> Result<()> {
+        let Some(repo) = self.repo.clone() else {
+            return Ok(());
+        };
+
+        // Without commits there is no history to refresh, so start over
+        if self.snapshot.as_ref().is_some_and(|s| s.head_oid.is_none()) {
+            let onboarding_view = OnboardingView::new(repo, self.colors.clone())
+                .with_commit_history(self.session.commit_messages.clone())
+                .with_spell_checker(self.spell_checker.clone())
+                .with_precommit_checks(self.precommit_options())
+                .with_symbols(self.symbols());
+            self.view_manager.reset(Box::new(onboarding_view))?;
+        } else {
+            self.view_manager.refresh_all();
+        }
+        self.external_change = None;
+
+        // Take a new snapshot right away; it brings the branch name with it
+        self.snapshot = None;
+        self.watch_receiver = None;
+        self.last_watch = None;
+        Ok(())
+    }
+
+    /// Render the application
+    pub fn render(&mut self, frame: &mut Frame) {
+        let area = frame.area();
+
+        // Show error screen if there's an error
+        if let Some(error) = &self.error {
+            self.render_error(frame, area, error);
+        } else if area.width < MIN_WIDTH || area.height < MIN_HEIGHT {
+            self.render_too_small(frame, area);
+        } else {
+            self.render_views(frame, area);
+        }
+
+        // Degrade colors the terminal can't show
+        apply_color_mode(frame.buffer_mut(), self.settings.color_mode);
+    }
+
+    /// Render the current view, status bar and key hints
+    fn render_views(&mut self, frame: &mut Frame, area: Rect) {
+        // Create layout with status bar
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Min(1),    // Main content
+                Constraint::Length(1), // Status bar
+            ])
+            .split(area);
+
+        // Warn that the views are stale, above the current view
+        let mut content = chunks[0];
+        if let Some(change) = &self.external_change {
+            let [banner, rest] = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Length(1), Constraint::Min(1)])
+                .areas(content);
+            let text = format!(" {}. Press R to reload ", change.describe());
+            frame.render_widget(
+                Paragraph::new(text).style(Style::default().fg(Color::Black).bg(Color::Yellow)),
+                banner,
+            );
+            content = rest;
+        }
+
+        // Render current view
+        self.view_manager.draw(frame, content);
+
+        // Render status bar
+        self.render_status_bar(frame, chunks[1]);
+
+        if let Some(toast) = &self.toast {
+            self.render_toast(frame, content, toast);
+        }
+
+        // Show possible continuations of a pending key sequence
+        if !self.keys.pending().is_empty() {
+            self.render_key_hints(frame, content);
+        }
+
+        if let Some(input) = &self.credential_input {
+            input.draw(frame, content, &self.symbols());
+        }
+
+        if let Some(report) = &self.pull_report {
+            self.render_pull_report(frame, content, report);
+        }
+
+        if let Some(report) = &self.push_report {
+            self.render_push_report(frame, content, report);
+        }
+
+        if let Some(dialog) = &self.push_dialog {
+            dialog.draw(frame, content, &self.symbols());
+        }
+
+        if let Some(diagnostics) = &self.ssh_diagnostics {
+            self.render_ssh_diagnostics(frame, content, diagnostics);
+        }
+
+        if let Some(target) = &self.confirm_checkout {
+            self.render_checkout_prompt(frame, content, target);
+        }
+
+        if let Some((id, summary)) = &self.confirm_revert {
+            self.render_revert_prompt(frame, content, *id, summary);
+        }
+
+        if let Some((id, summary)) = &self.confirm_reset {
+            let text = format!(
+                " Reset to {:.7} \"{}\"? (s: soft, m: mixed, h: hard, Esc) ",
+                id.to_string(),
+                summary
+            );
+            self.render_reset_prompt(frame, content, text);
+        }
+
+        if let Some(id) = self.confirm_hard_reset {
+            let text = format!(
+                " Hard reset to {:.7} discards uncommitted changes. Reset anyway? (y/n) ",
+                id.to_string()
+            );
+            self.render_reset_prompt(frame, content, text);
+        }
+
+        if self.confirm_quit {
+            self.render_quit_prompt(frame, content);
+        }
+    }
+
+    /// Render the question whether to check out over uncommitted changes
+    fn render_checkout_prompt(&self, frame: &mut Frame, area: Rect, target: &CheckoutTarget) {
+        let text = format!(
+            " Uncommitted changes. Check out {} and carry them along? (y/n)",
+            target.describe()
+        );
+        let popup = centered_rect(text.chars().count() as u16 + 3, 3, area);
+        frame.render_widget(Clear, popup);
+        frame.render_widget(
+            Paragraph::new(text)
+                .style(Style::default().fg(Color::Yellow))
+                .block(self.symbols().block().title(" Checkout ")),
+            popup,
+        );
+    }
+
+    /// Render the question whether to revert a commit, and how
+    fn render_revert_prompt(&self, frame: &mut Frame, area: Rect, id: Oid, summary: &str) {
+        let text = format!(
+            " Revert {:.7} \"{}\"? (y: commit, e: edit message first, n) ",
+            id.to_string(),
+            summary
+        );
+        let popup = centered_rect(text.chars().count() as u16 + 2, 3, area);
+        frame.render_widget(Clear, popup);
+        frame.render_widget(
+            Paragraph::new(text)
+                .style(Style::default().fg(Color::Yellow))
+                .block(self.symbols().block().title(" Revert ")),
+            popup,
+        );
+    }
+
+    /// Render a question about resetting the branch
+    fn render_reset_prompt(&self, frame: &mut Frame, area: Rect, text: String) {
+        let popup = centered_rect(text.chars().count() as u16 + 2, 3, area);
+        frame.render_widget(Clear, popup);
+        frame.render_widget(
+            Paragraph::new(text)
+                .style(Style::default().fg(Color::Yellow))
+                .block(self.symbols().block().title(" Reset ")),
+            popup,
+        );
+    }
+
+    /// Render the question whether to quit while work is in flight
+    fn render_quit_prompt(&self, frame: &mut Frame, area: Rect) {
+        let busy = self.busy();
+        let text = match busy.is_empty() {
+            true => " Quit? (y/n)".to_string(),
+            false => format!(" Still {}. Quit anyway? (y/n)", busy.join(", ")),
+        };
+        let popup = centered_rect(text.chars().count() as u16 + 3, 3, area);
+        frame.render_widget(Clear, popup);
+        frame.render_widget(
+            Paragraph::new(text)
+                .style(Style::default().fg(Color::Yellow))
+                .block(self.symbols().block().title(" Quit ")),
+            popup,
+        );
+    }
+
+    /// Render what went wrong on each failed SSH remote
+    fn render_ssh_diagnostics(
+        &self,
+        frame: &mut Frame,
+        area: Rect,
+        diagnostics: &[SshDiagnostics],
+    ) {
+        let mut lines: Vec<Line> = Vec::new();
+        for remote in diagnostics {
+            let mut remote_lines = remote.lines().into_iter();
+            if let Some(first) = remote_lines.next() {
+                lines.push(Line::from(Span::styled(
+                    format!(" {} ", first),
+                    Style::default().fg(self.colors.deleted),
+                )));
+            }
+            lines.extend(remote_lines.map(|line| Line::from(format!(" {} ", line))));
+            lines.push(Line::from(""));
+        }
+        lines.push(Line::from(" Any key closes "));
+
+        let width = lines.iter().map(Line::width).max().unwrap_or(0) as u16 + 2;
+        let popup = centered_rect(width, lines.len() as u16 + 2, area);
+        frame.render_widget(Clear, popup);
+        frame.render_widget(
+            Paragraph::new(lines).block(self.symbols().block().title(" SSH diagnostics ")),
+            popup,
+        );
+    }
+
+    /// Render each commit a pull rebased, old and new ids side by side
+    fn render_pull_report(&self, frame: &mut Frame, area: Rect, report: &PullReport) {
+        let hash = Style::default().fg(self.colors.commit_hash);
+        let mut lines = vec![
+            Line::from(format!(" {} ", report.summary())),
+            Line::from(""),
+        ];
+        let arrow = format!(" {} ", self.symbols().arrow);
+        for commit in &report.rebased {
+            let new = match commit.new {
+                Some(new) => Span::styled(format!("{:.7}", new.to_string()), hash),
+                None => Span::raw("dropped"),
+            };
+            lines.push(Line::from(vec![
+                Span::raw(" "),
+                Span::styled(format!("{:.7}", commit.old.to_string()), hash),
+                Span::raw(arrow.clone()),
+                new,
+                Span::raw(format!(" {} ", commit.summary)),
+            ]));
+        }
+        lines.push(Line::from(""));
+        lines.push(Line::from(" Any key closes "));
+
+        let width = lines.iter().map(Line::width).max().unwrap_or(0) as u16 + 2;
+        let popup = centered_rect(width, lines.len() as u16 + 2, area);
+        frame.render_widget(Clear, popup);
+        frame.render_widget(
+            Paragraph::new(lines).block(self.symbols().block().title(" Pulled with rebase ")),
+            popup,
+        );
+    }
+
+    /// Render each ref a push sent, where it moved or why it was refused
+    fn render_push_report(&self, frame: &mut Frame, area: Rect, report: &PushReport) {
+        let hash = Style::default().fg(self.colors.commit_hash);
+        let short = |oid: Option<Oid>| match oid {
+            Some(oid) => Span::styled(format!("{:.7}", oid.to_string()), hash),
+            None => Span::raw("-"),
+        };
+        let mut lines = vec![
+            Line::from(format!(" {} ", report.summary())),
+            Line::from(""),
+        ];
+        for pushed in &report.refs {
+            let mut spans = vec![Span::raw(format!(" {} ", pushed.short_name()))];
+            match &pushed.rejected {
+                Some(reason) => spans.push(Span::styled(
+                    format!("rejected: {} ", reason),
+                    Style::default().fg(self.colors.deleted),
+                )),
+                None => {
+                    spans.push(short(pushed.old));
+                    spans.push(Span::raw(".."));
+                    spans.push(short(pushed.new));
+                    spans.push(Span::raw(" "));
+                }
+            }
+            lines.push(Line::from(spans));
+        }
+        lines.push(Line::from(""));
+        lines.push(Line::from(" Any key closes "));
+
+        let width = lines.iter().map(Line::width).max().unwrap_or(0) as u16 + 2;
+        let popup = centered_rect(width, lines.len() as u16 + 2, area);
+        frame.render_widget(Clear, popup);
+        frame.render_widget(
+            Paragraph::new(lines).block(
+                self.symbols()
+                    .block()
+                    .title(format!(" Pushed to {} ", report.remote)),
+            ),
+            popup,
+        );
+    }
+
+    /// Render a toast in the bottom right corner of the view area
+    fn render_toast(&self, frame: &mut Frame, area: Rect, toast: &Toast) {
+        let message = &toast.message;
+        let width = (message.chars().count() as u16 + 4).min(area.width);
+        let height = 3.min(area.height);
+        let popup = Rect::new(
+            area.x + area.width - width,
+            area.y + area.height - height,
+            width,
+            height,
+        );
+
+        let (title, color) = match toast.is_error {
+            true => (" Error ", self.colors.deleted),
+            false => (" Info ", self.colors.added),
+        };
+        let block = self.symbols().block().title(title);
+        frame.render_widget(Clear, popup);
+        frame.render_widget(
+            Paragraph::new(format!(" {}", message))
+                .style(Style::default().fg(color))
+                .block(block),
+            popup,
+        );
+    }
+
+    /// Render a popup listing the keys that can follow the pending prefix
+    fn render_key_hints(&self, frame: &mut Frame, area: Rect) {
+        let Some(view_type) = self.view_manager.focused_view_type() else {
+            return;
+        };
+        let hints = self
+            .keys
+            .keymap()
+            .continuations(view_type, self.keys.pending());
+
+        let lines: Vec<Line> = hints
+            .iter()
+            .map(|(key, description)| {
+                Line::from(vec![
+                    Span::styled(
+                        format!(" {:<6}", key.to_string()),
+                        Style::default().fg(Color::Green),
+                    ),
+                    Span::raw(format!("{} ", description)),
+                ])
+            })
+            .collect();
+
+        let prefix = self
+            .keys
+            .pending()
+            .iter()
+            .map(|k| k.to_string())
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        // Anchor the popup to the bottom right corner of the view area
+        let content_width = lines.iter().map(Line::width).max().unwrap_or(0);
+        let width = (content_width.max(prefix.len() + 2) as u16 + 2).min(area.width);
+        let height = (lines.len() as u16 + 2).min(area.height);
+        let popup = Rect::new(
+            area.x + area.width - width,
+            area.y + area.height - height,
+            width,
+            height,
+        );
+
+        let block = self.symbols().block().title(format!(" {} ", prefix));
+        frame.render_widget(Clear, popup);
+        frame.render_widget(Paragraph::new(lines).block(block), popup);
+    }
+
+    /// Render a placeholder instead of views squeezed past readability
+    fn render_too_small(&self, frame: &mut Frame, area: Rect) {
+        let text = format!(
+            "Terminal too small (need {}x{}, have {}x{})",
+            MIN_WIDTH, MIN_HEIGHT, area.width, area.height
+        );
+        let popup = centered_rect(text.chars().count() as u16, 1, area);
+        frame.render_widget(
+            Paragraph::new(text).style(Style::default().fg(Color::Yellow)),
+            popup,
+        );
+    }
+
+    /// Render error screen
+    fn render_error(&self, frame: &mut Frame, area: Rect, error: &str) {
+        let text = vec![
+            Line::from(""),
+            Line::from(Span::styled("Error", Style::default().fg(Color::Red))),
+            Line::from(""),
+            Line::from(error),
             Line::from(""),
             Line::from("Press Ctrl+C to quit"),
         ];
@@ -192,28 +1771,122 @@ impl App {
 
     /// Render status bar
     fn render_status_bar(&self, frame: &mut Frame, area: Rect) {
+        // The prompt takes the whole row while a command is being typed
+        if let Some(line) = &self.command_line {
+            let prompt = Line::from(vec![
+                Span::raw(":"),
+                Span::raw(line.as_str()),
+                Span::raw("_"),
+            ]);
+            frame.render_widget(Paragraph::new(prompt).style(self.colors.status_bar), area);
+            return;
+        }
+
         let branch = self
             .branch
-            .as_ref()
-            .map(|b| b.clone())
+            .clone()
             .unwrap_or_else(|| "No branch".to_string());
 
         let view_title = self.view_manager.current_title();
+        // Narrow terminals keep the view, branch and count, dropping the rest
+        let compact = area.width < self.settings.compact_width;
 
-        let status = Line::from(vec![
+        let mut spans = vec![
             Span::raw(" "),
             Span::styled(view_title, Style::default().fg(Color::Yellow)),
             Span::raw(" | "),
-            Span::styled(format!("\u{e0a0} {}", branch), Style::default().fg(Color::Cyan)),
-            Span::raw(" | "),
-            Span::raw("q:quit | s:status | ?:help"),
-        ]);
+            Span::styled(
+                format!("{}{}", self.symbols().branch, branch),
+                Style::default().fg(Color::Cyan),
+            ),
+        ];
+
+        // Where the branch stands against its upstream as of the last fetch
+        if let Some(status) = &self.fetch_status {
+            let symbols = self.symbols();
+            if let Some((ahead, behind)) = status.upstream
+                && (ahead, behind) != (0, 0)
+            {
+                spans.push(Span::styled(
+                    format!(
+                        " {}{}{}{}",
+                        symbols.ascending, ahead, symbols.descending, behind
+                    ),
+                    Style::default().fg(Color::Cyan),
+                ));
+            }
+            let time = status.finished.format("%H:%M");
+            match status.failure {
+                Some(_) => {
+                    let failed = if compact {
+                        "fetch failed".to_string()
+                    } else {
+                        format!("fetch failed {}", time)
+                    };
+                    spans.push(Span::raw(" | "));
+                    spans.push(Span::styled(failed, Style::default().fg(Color::Red)));
+                }
+                None if !compact => {
+                    spans.push(Span::raw(" | "));
+                    spans.push(Span::raw(format!("{}{}", symbols.fetched, time)));
+                }
+                None => {}
+            }
+        }
+
+        if !compact {
+            spans.push(Span::raw(" | "));
+            spans.push(Span::raw("q:quit | s:status | ?:help"));
+        }
+
+        // Echo a count being typed, like vim's showcmd
+        if let Some(count) = self.keys.count() {
+            spans.push(Span::raw(" | "));
+            spans.push(Span::styled(
+                count.to_string(),
+                Style::default().fg(Color::Yellow),
+            ));
+        }
+
+        let status = Line::from(spans);
 
         let paragraph = Paragraph::new(status).style(self.colors.status_bar);
         frame.render_widget(paragraph, area);
+
+        // Clock on the right; the UI redraws on every tick so it stays current
+        if !self.settings.clock_format.is_empty() && !compact {
+            // A format chrono can't parse fails to display, which `format!`
+            // would turn into a panic
+            let now = self.clock.now();
+            let mut clock = String::new();
+            if write!(clock, "{} ", now.format(&self.settings.clock_format)).is_err() {
+                clock = format!("{} ", now.format(DEFAULT_CLOCK_FORMAT));
+            }
+            let width = (clock.chars().count() as u16).min(area.width);
+            let clock_area = Rect::new(area.x + area.width - width, area.y, width, 1);
+            frame.render_widget(
+                Paragraph::new(clock).style(self.colors.status_bar),
+                clock_area,
+            );
+        }
     }
 }
 
+/// Turn a path given relative to the current directory into one relative to
+/// the repository's working directory, as git does
+fn repo_relative_path(workdir: Option<&std::path::Path>, path: &str) -> String {
+    let relative = workdir.and_then(|workdir| {
+        let absolute = std::env::current_dir()
+            .ok()?
+            .join(path)
+            .canonicalize()
+            .ok()?;
+        let relative = absolute.strip_prefix(workdir.canonicalize().ok()?).ok()?;
+        Some(relative.to_string_lossy().replace('\\', "/"))
+    });
+    relative.unwrap_or_else(|| path.to_string())
+}
+
 impl Default for App {
     fn default() -> Self {
         Self::new()
@@ -223,6 +1896,7 @@ impl Default for App {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ui::keymap::Key;
     use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 
     #[test]
@@ -238,4 +1912,762 @@ mod tests {
         app.handle_event(event).unwrap();
         assert!(!app.is_running());
     }
+
+    #[test]
+    fn test_status_bar_clock() {
+        use crate::clock::FixedClock;
+        use chrono::{Local, TimeZone};
+        use ratatui::{Terminal, backend::TestBackend};
+        use std::sync::Arc;
+
+        let now = Local.with_ymd_and_hms(2024, 3, 9, 14, 5, 0).unwrap();
+        let mut app = App::new().with_clock(Arc::new(FixedClock(now)));
+        app.settings.clock_format = "[%H:%M]".to_string();
+        let mut terminal = Terminal::new(TestBackend::new(80, 24)).unwrap();
+        terminal.draw(|frame| app.render(frame)).unwrap();
+
+        let buffer = terminal.backend().buffer();
+        let status: String = (0..80).map(|x| buffer[(x, 23)].symbol()).collect();
+        assert!(status.trim_end().ends_with("[14:05]"));
+
+        // A format that doesn't parse falls back to the default
+        app.settings.clock_format = "%Q %H".to_string();
+        terminal.draw(|frame| app.render(frame)).unwrap();
+        let buffer = terminal.backend().buffer();
+        let status: String = (0..80).map(|x| buffer[(x, 23)].symbol()).collect();
+        assert!(status.trim_end().ends_with("14:05"));
+    }
+
+    #[test]
+    fn test_no_color_mode_reverses_status_bar() {
+        use ratatui::{Terminal, backend::TestBackend, style::Modifier};
+
+        let mut app = App::new().with_color_mode(ColorMode::None);
+        let mut terminal = Terminal::new(TestBackend::new(80, 24)).unwrap();
+        terminal.draw(|frame| app.render(frame)).unwrap();
+
+        let buffer = terminal.backend().buffer();
+        assert!(
+            buffer
+                .content
+                .iter()
+                .all(|cell| cell.fg == Color::Reset && cell.bg == Color::Reset)
+        );
+        assert!(buffer[(0, 23)].modifier.contains(Modifier::REVERSED));
+        assert!(buffer.content.iter().all(|cell| cell.symbol().is_ascii()));
+    }
+
+    #[test]
+    fn test_status_bar_shows_fetch_status() {
+        use chrono::TimeZone;
+        use ratatui::{Terminal, backend::TestBackend};
+
+        let mut app = App::new();
+        app.settings.clock_format = String::new();
+        app.fetch_status = Some(FetchStatus {
+            finished: Local.with_ymd_and_hms(2024, 3, 9, 14, 5, 0).unwrap(),
+            failure: None,
+            upstream: Some((2, 1)),
+        });
+        let mut terminal = Terminal::new(TestBackend::new(80, 24)).unwrap();
+        terminal.draw(|frame| app.render(frame)).unwrap();
+        let status: String = (0..80)
+            .map(|x| terminal.backend().buffer()[(x, 23)].symbol())
+            .collect();
+        assert!(status.contains("↑2↓1 | ⇣14:05"), "{:?}", status);
+
+        app.fetch_status.as_mut().unwrap().failure = Some("origin: timed out".to_string());
+        terminal.draw(|frame| app.render(frame)).unwrap();
+        let status: String = (0..80)
+            .map(|x| terminal.backend().buffer()[(x, 23)].symbol())
+            .collect();
+        assert!(status.contains("fetch failed 14:05"), "{:?}", status);
+    }
+
+    #[test]
+    fn test_compact_status_bar() {
+        use ratatui::{Terminal, backend::TestBackend};
+
+        let mut app = App::new();
+        app.fetch_status = Some(FetchStatus {
+            finished: Local::now(),
+            failure: None,
+            upstream: Some((2, 1)),
+        });
+        let mut terminal = Terminal::new(TestBackend::new(60, 24)).unwrap();
+        terminal.draw(|frame| app.render(frame)).unwrap();
+        let status: String = (0..60)
+            .map(|x| terminal.backend().buffer()[(x, 23)].symbol())
+            .collect();
+        assert_eq!(
+            status.trim_end(),
+            format!(" rust-tig | {}No branch ↑2↓1", app.symbols().branch)
+        );
+    }
+
+    #[test]
+    fn test_ssh_diagnostics_panel_closes_on_any_key() {
+        use ratatui::{Terminal, backend::TestBackend};
+
+        let mut app = App::new();
+        let mut diagnostics = SshDiagnostics::new("origin", "git@example.com:repo.git");
+        diagnostics.error = "authentication required".to_string();
+        app.ssh_diagnostics = Some(vec![diagnostics]);
+        let mut terminal = Terminal::new(TestBackend::new(100, 24)).unwrap();
+        terminal.draw(|frame| app.render(frame)).unwrap();
+        let text: String = terminal
+            .backend()
+            .buffer()
+            .content
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        assert!(text.contains("SSH diagnostics"));
+        assert!(text.contains("origin (git@example.com:repo.git): authentication required"));
+
+        app.handle_event(Event::Key(KeyEvent::from(KeyCode::Char('j'))))
+            .unwrap();
+        assert!(app.ssh_diagnostics.is_none());
+        assert!(app.is_running());
+    }
+
+    #[tokio::test]
+    async fn test_dirty_checkout_asks_first() {
+        let builder = crate::testing::RepoBuilder::new()
+            .file("a.txt", "one\n")
+            .commit("Initial");
+        let initial = builder.head();
+        let (dir, repo) = builder
+            .file("a.txt", "two\n")
+            .commit("Second")
+            .write("a.txt", "edited\n")
+            .open()
+            .await;
+        let mut app = App::new();
+        app.repo = Some(repo);
+
+        app.handle_action(Action::Checkout(CheckoutTarget::Commit(initial)))
+            .unwrap();
+        while app.confirm_checkout.is_none() {
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            if let Some(Ok(result)) = app.command_receiver.as_mut().map(|rx| rx.try_recv()) {
+                app.command_receiver = None;
+                app.finish_command(result).unwrap();
+            }
+        }
+
+        // Anything but y leaves HEAD and the edit alone
+        app.handle_event(Event::Key(KeyEvent::from(KeyCode::Char('n'))))
+            .unwrap();
+        assert!(app.confirm_checkout.is_none());
+        assert!(app.command_receiver.is_none());
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("a.txt")).unwrap(),
+            "edited\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_staged_revert_opens_commit_prompt() {
+        let builder = crate::testing::RepoBuilder::new()
+            .file("a.txt", "one\n")
+            .commit("Initial");
+        let (dir, repo) = builder.file("b.txt", "two\n").commit("Add b").open().await;
+        let add_b = repo.open_git2().unwrap().head().unwrap().target().unwrap();
+        let mut app = App::new();
+        app.repo = Some(repo);
+
+        app.handle_action(Action::Revert {
+            id: add_b,
+            summary: "Add b".to_string(),
+        })
+        .unwrap();
+        assert!(app.confirm_revert.is_some());
+        app.handle_event(Event::Key(KeyEvent::from(KeyCode::Char('e'))))
+            .unwrap();
+        assert!(app.confirm_revert.is_none());
+        while app.command_receiver.is_some() {
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            if let Some(Ok(result)) = app.command_receiver.as_mut().map(|rx| rx.try_recv()) {
+                app.command_receiver = None;
+                app.finish_command(result).unwrap();
+            }
+        }
+
+        // Staged, not committed, and the status view waits to commit it
+        assert!(!dir.path().join("b.txt").exists());
+        assert_eq!(app.view_manager.current_view_type(), Some(ViewType::Status));
+        let git_repo = app.repo.as_ref().unwrap().open_git2().unwrap();
+        assert_eq!(git_repo.state(), git2::RepositoryState::Revert);
+        assert_eq!(git_repo.head().unwrap().target(), Some(add_b));
+    }
+
+    #[tokio::test]
+    async fn test_hard_reset_asks_twice() {
+        let builder = crate::testing::RepoBuilder::new()
+            .file("a.txt", "one\n")
+            .commit("Initial");
+        let initial = builder.head();
+        let (dir, repo) = builder
+            .file("a.txt", "two\n")
+            .commit("Second")
+            .write("a.txt", "edited\n")
+            .open()
+            .await;
+        let mut app = App::new();
+        app.repo = Some(repo);
+        let reset = Action::Reset {
+            id: initial,
+            summary: "Initial".to_string(),
+        };
+
+        // Backing out of the second question leaves everything alone
+        app.handle_action(reset.clone()).unwrap();
+        app.handle_event(Event::Key(KeyEvent::from(KeyCode::Char('h'))))
+            .unwrap();
+        assert_eq!(app.confirm_hard_reset, Some(initial));
+        app.handle_event(Event::Key(KeyEvent::from(KeyCode::Char('n'))))
+            .unwrap();
+        assert!(app.command_receiver.is_none());
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("a.txt")).unwrap(),
+            "edited\n"
+        );
+
+        app.handle_action(reset).unwrap();
+        app.handle_event(Event::Key(KeyEvent::from(KeyCode::Char('h'))))
+            .unwrap();
+        app.handle_event(Event::Key(KeyEvent::from(KeyCode::Char('y'))))
+            .unwrap();
+        while app.command_receiver.is_some() {
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            if let Some(Ok(result)) = app.command_receiver.as_mut().map(|rx| rx.try_recv()) {
+                app.command_receiver = None;
+                app.finish_command(result).unwrap();
+            }
+        }
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("a.txt")).unwrap(),
+            "one\n"
+        );
+        let git_repo = app.repo.as_ref().unwrap().open_git2().unwrap();
+        assert_eq!(git_repo.head().unwrap().target(), Some(initial));
+    }
+
+    #[tokio::test]
+    async fn test_force_push_needs_the_setting() {
+        let (_dir, repo) = crate::testing::RepoBuilder::new()
+            .commit("Initial")
+            .open()
+            .await;
+        let mut app = App::new();
+        app.repo = Some(repo);
+        let options = crate::git::PushOptions {
+            remote: "origin".to_string(),
+            branch: "main".to_string(),
+            remote_branch: "main".to_string(),
+            set_upstream: false,
+            force_with_lease: false,
+            force: true,
+            tags: false,
+        };
+
+        app.start_command(Command::Push(options.clone()));
+        assert!(app.command_receiver.is_none());
+        assert!(app.toast.as_ref().is_some_and(|toast| toast.is_error));
+        app.run_command("push --force");
+        assert!(app.command_receiver.is_none());
+
+        app.settings.allow_force_push = true;
+        app.start_command(Command::Push(options));
+        assert!(app.command_receiver.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_one_command_at_a_time() {
+        let (_dir, repo) = crate::testing::RepoBuilder::new()
+            .commit("Initial")
+            .open()
+            .await;
+        let mut app = App::new();
+        app.repo = Some(repo);
+        app.start_command(Command::Fetch {
+            prune: false,
+            remote: None,
+        });
+        assert!(app.command_receiver.is_some());
+
+        app.start_command(Command::Review);
+        let toast = app.toast.as_ref().unwrap();
+        assert_eq!(
+            (toast.message.as_str(), toast.is_error),
+            ("Still fetching; wait for it to finish", true)
+        );
+
+        // Quitting asks first while it runs
+        app.request_quit();
+        assert!(app.is_running() && app.confirm_quit);
+        assert_eq!(app.busy(), ["fetching"]);
+    }
+
+    #[test]
+    fn test_refused_push_shows_each_ref() {
+        use crate::git::PushedRef;
+        use ratatui::{Terminal, backend::TestBackend};
+
+        let mut app = App::new();
+        app.view_manager.push(Box::new(HelpView::new())).unwrap();
+        let pushed = |name: &str, rejected: Option<&str>| PushedRef {
+            name: name.to_string(),
+            old: None,
+            new: Some(Oid::from_str("1234567890123456789012345678901234567890").unwrap()),
+            rejected: rejected.map(str::to_string),
+        };
+        let report = PushReport {
+            remote: "origin".to_string(),
+            refs: vec![
+                pushed("refs/heads/main", None),
+                pushed("refs/tags/v1", Some("failed to lock file")),
+            ],
+        };
+        app.finish_command(Ok(Outcome::Pushed {
+            report,
+            upstream: None,
+        }))
+        .unwrap();
+
+        let toast = app.toast.as_ref().unwrap();
+        assert!(toast.is_error);
+        assert_eq!(toast.message, "origin rejected 1 of 2 refs");
+        let mut terminal = Terminal::new(TestBackend::new(80, 24)).unwrap();
+        terminal.draw(|frame| app.render(frame)).unwrap();
+        let buffer = terminal.backend().buffer();
+        let screen: String = (0..24)
+            .flat_map(|y| (0..80).map(move |x| (x, y)))
+            .map(|p| buffer[p].symbol())
+            .collect();
+        assert!(screen.contains("main -..1234567"));
+        assert!(screen.contains("v1 rejected: failed to lock file"));
+
+        app.handle_key(KeyEvent::from(KeyCode::Esc)).unwrap();
+        assert!(app.push_report.is_none());
+    }
+
+    #[test]
+    fn test_external_change_banner() {
+        use ratatui::{Terminal, backend::TestBackend};
+
+        let mut app = App::new();
+        app.external_change = Some(RepoChange::IndexChanged);
+        let mut terminal = Terminal::new(TestBackend::new(80, 24)).unwrap();
+        terminal.draw(|frame| app.render(frame)).unwrap();
+
+        let buffer = terminal.backend().buffer();
+        let banner: String = (0..80).map(|x| buffer[(x, 0)].symbol()).collect();
+        assert!(banner.contains("Index changed outside rust-tig. Press R to reload"));
+    }
+
+    #[test]
+    fn test_too_small_placeholder() {
+        use ratatui::{Terminal, backend::TestBackend};
+
+        let mut app = App::new();
+        app.view_manager.push(Box::new(HelpView::new())).unwrap();
+        for (width, height) in [(39, 24), (40, 23), (20, 3)] {
+            let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+            terminal.draw(|frame| app.render(frame)).unwrap();
+
+            let buffer = terminal.backend().buffer();
+            let text: String = buffer.content.iter().map(|cell| cell.symbol()).collect();
+            assert!(
+                text.contains("Terminal too small"),
+                "{}x{}: {:?}",
+                width,
+                height,
+                text
+            );
+        }
+
+        // Even a single cell renders without panicking
+        let mut terminal = Terminal::new(TestBackend::new(1, 1)).unwrap();
+        terminal.draw(|frame| app.render(frame)).unwrap();
+    }
+
+    #[test]
+    fn test_recoverable_errors_become_toasts() {
+        let mut app = App::new();
+        app.report(AppError::recoverable("Nothing to unstage"))
+            .unwrap();
+        assert!(app.is_running());
+        assert_eq!(
+            app.toast.as_ref().map(|t| t.message.as_str()),
+            Some("Nothing to unstage")
+        );
+        assert!(app.toast.as_ref().is_some_and(|t| t.is_error));
+
+        let fatal = app.report(anyhow::anyhow!("terminal gone").into());
+        assert!(fatal.is_err());
+    }
+
+    /// A view with work in flight
+    struct BusyView;
+
+    impl crate::views::View for BusyView {
+        fn handle_key(&mut self, _key: KeyEvent) -> crate::error::AppResult<Action> {
+            Ok(Action::Quit)
+        }
+
+        fn update(&mut self) -> crate::error::AppResult<()> {
+            Ok(())
+        }
+
+        fn draw(&mut self, _frame: &mut Frame, _area: Rect) {}
+
+        fn title(&self) -> &str {
+            "Busy"
+        }
+
+        fn view_type(&self) -> ViewType {
+            ViewType::Status
+        }
+
+        fn busy(&self) -> Option<&'static str> {
+            Some("committing")
+        }
+    }
+
+    /// A view counting how often it was refreshed
+    struct CountingView(std::sync::Arc<std::sync::atomic::AtomicUsize>);
+
+    impl crate::views::View for CountingView {
+        fn handle_key(&mut self, _key: KeyEvent) -> crate::error::AppResult<Action> {
+            Ok(Action::None)
+        }
+
+        fn update(&mut self) -> crate::error::AppResult<()> {
+            Ok(())
+        }
+
+        fn draw(&mut self, _frame: &mut Frame, _area: Rect) {}
+
+        fn title(&self) -> &str {
+            "Counting"
+        }
+
+        fn view_type(&self) -> ViewType {
+            ViewType::Refs
+        }
+
+        fn refresh(&mut self) {
+            self.0.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_refresh_reaches_every_view() {
+        use std::sync::atomic::Ordering;
+
+        let (_dir, repo) = crate::testing::RepoBuilder::new()
+            .commit("Initial")
+            .open()
+            .await;
+        let mut app = App::new();
+        app.repo = Some(repo);
+        app.external_change = Some(RepoChange::IndexChanged);
+
+        let below = std::sync::Arc::default();
+        let above = std::sync::Arc::default();
+        app.view_manager
+            .push(Box::new(CountingView(std::sync::Arc::clone(&below))))
+            .unwrap();
+        app.view_manager
+            .push(Box::new(CountingView(std::sync::Arc::clone(&above))))
+            .unwrap();
+
+        app.handle_event(Event::Key(KeyEvent::from(KeyCode::F(5))))
+            .unwrap();
+        app.handle_event(Event::Key(KeyEvent::from(KeyCode::Char('R'))))
+            .unwrap();
+        assert_eq!(below.load(Ordering::Relaxed), 2);
+        assert_eq!(above.load(Ordering::Relaxed), 2);
+        assert!(app.external_change.is_none());
+    }
+
+    #[test]
+    fn test_command_prompt_recalls_history() {
+        let mut app = App::new();
+        let type_line = |app: &mut App, line: &str| {
+            for c in line.chars() {
+                app.handle_event(Event::Key(KeyEvent::from(KeyCode::Char(c))))
+                    .unwrap();
+            }
+        };
+        type_line(&mut app, ":fetch");
+        app.handle_event(Event::Key(KeyEvent::from(KeyCode::Enter)))
+            .unwrap();
+        type_line(&mut app, ":pull");
+        app.handle_event(Event::Key(KeyEvent::from(KeyCode::Enter)))
+            .unwrap();
+        assert_eq!(app.session.command_history, ["fetch", "pull"]);
+
+        type_line(&mut app, ":pu");
+        app.handle_event(Event::Key(KeyEvent::from(KeyCode::Up)))
+            .unwrap();
+        assert_eq!(app.command_line.as_deref(), Some("pull"));
+        app.handle_event(Event::Key(KeyEvent::from(KeyCode::Up)))
+            .unwrap();
+        assert_eq!(app.command_line.as_deref(), Some("fetch"));
+        app.handle_event(Event::Key(KeyEvent::from(KeyCode::Down)))
+            .unwrap();
+        app.handle_event(Event::Key(KeyEvent::from(KeyCode::Down)))
+            .unwrap();
+        assert_eq!(app.command_line.as_deref(), Some("pu"));
+    }
+
+    #[tokio::test]
+    async fn test_merge_base_command_selects_commit() {
+        let builder = crate::testing::RepoBuilder::new()
+            .commit("Base")
+            .branch("left");
+        let base = builder.head();
+        let (_dir, repo) = builder.checkout("left").commit("Left").open().await;
+        let mut app = App::new();
+        app.repo = Some(repo.clone());
+        app.view_manager
+            .push(Box::new(app.main_view(repo)))
+            .unwrap();
+        app.view_manager.push(Box::new(HelpView::new())).unwrap();
+
+        let line = format!(":merge-base HEAD {}", base);
+        for c in line.chars() {
+            app.handle_event(Event::Key(KeyEvent::from(KeyCode::Char(c))))
+                .unwrap();
+        }
+        assert_eq!(app.command_line.as_deref(), Some(&line[1..]));
+        app.handle_event(Event::Key(KeyEvent::from(KeyCode::Enter)))
+            .unwrap();
+        assert!(app.command_line.is_none());
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        app.update().unwrap();
+        assert_eq!(app.view_manager.current_view_type(), Some(ViewType::Main));
+        let toast = app.toast.as_ref().unwrap();
+        assert!(!toast.is_error, "{}", toast.message);
+        assert!(toast.message.contains(&base.to_string()[..7]));
+
+        // Unknown commands are reported without running anything
+        for c in ":nope".chars() {
+            app.handle_event(Event::Key(KeyEvent::from(KeyCode::Char(c))))
+                .unwrap();
+        }
+        app.handle_event(Event::Key(KeyEvent::from(KeyCode::Enter)))
+            .unwrap();
+        assert!(app.toast.as_ref().is_some_and(|t| t.is_error));
+    }
+
+    #[tokio::test]
+    async fn test_start_views() {
+        let builder = crate::testing::RepoBuilder::new()
+            .file("src/a.txt", "one\n")
+            .commit("First")
+            .commit("Second");
+        let (dir, repo) = builder.open().await;
+
+        for (start, expected) in [
+            (StartView::Status, ViewType::Status),
+            (StartView::Refs, ViewType::Refs),
+            (StartView::Show("HEAD~1".to_string()), ViewType::Diff),
+            (
+                StartView::Blame {
+                    path: "src/a.txt".to_string(),
+                    line: Some(1),
+                },
+                ViewType::Blame,
+            ),
+        ] {
+            let mut app = App::new().with_start(start);
+            app.repo = Some(repo.clone());
+            app.view_manager
+                .push(Box::new(app.main_view(repo.clone())))
+                .unwrap();
+            app.open_start_view(repo.clone()).await.unwrap();
+            assert_eq!(app.view_manager.current_view_type(), Some(expected));
+        }
+
+        // A revision that doesn't exist leaves the main view up
+        let mut app = App::new().with_start(StartView::Show("nope".to_string()));
+        app.view_manager
+            .push(Box::new(app.main_view(repo.clone())))
+            .unwrap();
+        app.open_start_view(repo).await.unwrap();
+        assert_eq!(app.view_manager.current_view_type(), Some(ViewType::Main));
+        assert!(app.toast.as_ref().is_some_and(|t| t.is_error));
+
+        let file = dir.path().join("src").join("a.txt");
+        let path = repo_relative_path(Some(dir.path()), file.to_str().unwrap());
+        assert_eq!(path, "src/a.txt");
+        assert_eq!(repo_relative_path(None, "src/a.txt"), "src/a.txt");
+    }
+
+    #[tokio::test]
+    async fn test_open_switches_repository() {
+        let (_dir, repo) = crate::testing::RepoBuilder::new()
+            .commit("Initial")
+            .open()
+            .await;
+        let (other_dir, other) = crate::testing::RepoBuilder::new().open().await;
+        let mut app = App::new();
+        app.repo = Some(repo.clone());
+        app.view_manager
+            .push(Box::new(app.main_view(repo.clone())))
+            .unwrap();
+        app.handle_action(Action::PushView(ViewType::Status))
+            .unwrap();
+        app.external_change = Some(RepoChange::IndexChanged);
+        // A command still running on the old repository is let go
+        let (_tx, rx) = mpsc::unbounded_channel();
+        app.command_receiver = Some(rx);
+
+        let opened = Outcome::Opened {
+            repo: other.clone(),
+            branch: None,
+            empty: true,
+        };
+        app.finish_command(Ok(opened)).unwrap();
+        assert_eq!(app.repo, Some(other));
+        assert_eq!(app.view_manager.len(), 1);
+        assert_eq!(
+            app.view_manager.current_view_type(),
+            Some(ViewType::Onboarding)
+        );
+        assert!(app.external_change.is_none());
+        assert!(app.command_receiver.is_none());
+        let toast = app.toast.as_ref().unwrap();
+        assert_eq!(
+            toast.message,
+            format!("Opened {}", other_dir.path().display())
+        );
+
+        // Work in flight keeps the repository open
+        app.view_manager.push(Box::new(BusyView)).unwrap();
+        let opened = Outcome::Opened {
+            repo: repo.clone(),
+            branch: Some("master".to_string()),
+            empty: false,
+        };
+        app.finish_command(Ok(opened)).unwrap();
+        assert_ne!(app.repo, Some(repo));
+        assert!(app.toast.as_ref().is_some_and(|t| t.is_error));
+    }
+
+    #[test]
+    fn test_shift_q_quits_from_any_view() {
+        let mut app = App::new();
+        app.handle_event(Event::Key(KeyEvent::from(KeyCode::Char('Q'))))
+            .unwrap();
+        assert!(!app.is_running());
+    }
+
+    #[tokio::test]
+    async fn test_gc_warns_about_work_in_flight() {
+        let (_dir, repo) = crate::testing::RepoBuilder::new()
+            .commit("Initial")
+            .open()
+            .await;
+        let mut app = App::new();
+        app.repo = Some(repo);
+        app.view_manager.push(Box::new(BusyView)).unwrap();
+
+        app.finish_command(Ok(Outcome::Maintenance(crate::git::Maintenance::Gc)))
+            .unwrap();
+        let toast = app.toast.as_ref().unwrap();
+        assert!(toast.is_error);
+        assert_eq!(
+            toast.message,
+            "git gc runs while still committing; either may fail on a locked file"
+        );
+        assert_eq!(app.view_manager.current_view_type(), Some(ViewType::Output));
+    }
+
+    #[test]
+    fn test_quit_waits_for_confirmation_while_busy() {
+        use ratatui::{Terminal, backend::TestBackend};
+
+        let mut app = App::new();
+        app.view_manager.push(Box::new(BusyView)).unwrap();
+
+        // Declining keeps the app running
+        app.handle_event(Event::Key(KeyEvent::from(KeyCode::Char('Q'))))
+            .unwrap();
+        assert!(app.is_running());
+        assert!(app.confirm_quit);
+
+        let mut terminal = Terminal::new(TestBackend::new(80, 24)).unwrap();
+        terminal.draw(|frame| app.render(frame)).unwrap();
+        let buffer = terminal.backend().buffer();
+        let text: String = buffer.content.iter().map(|cell| cell.symbol()).collect();
+        assert!(text.contains("Still committing. Quit anyway? (y/n)"));
+
+        app.handle_event(Event::Key(KeyEvent::from(KeyCode::Char('n'))))
+            .unwrap();
+        assert!(app.is_running());
+        assert!(!app.confirm_quit);
+
+        // A view's own quit action asks too
+        app.handle_event(Event::Key(KeyEvent::from(KeyCode::Char('x'))))
+            .unwrap();
+        assert!(app.confirm_quit);
+        app.handle_event(Event::Key(KeyEvent::from(KeyCode::Char('y'))))
+            .unwrap();
+        assert!(!app.is_running());
+    }
+
+    #[test]
+    fn test_edit_requests_wait_for_the_main_loop() {
+        let mut app = App::new();
+        let (request, mut reply) = EditRequest::new("text\n".to_string(), "edit.txt");
+        app.handle_action(Action::EditText(request)).unwrap();
+
+        let request = app.take_pending_edit().unwrap();
+        assert_eq!(request.text, "text\n");
+        assert!(app.take_pending_edit().is_none());
+
+        request.finish(Ok("edited\n".to_string()));
+        assert_eq!(reply.try_recv().unwrap().unwrap(), "edited\n");
+    }
+
+    #[test]
+    fn test_copies_wait_for_the_main_loop() {
+        let mut app = App::new();
+        app.handle_action(Action::Copy("[1234567 Fix](url)".to_string()))
+            .unwrap();
+        assert_eq!(
+            app.toast.as_ref().unwrap().message,
+            "Copied [1234567 Fix](url)"
+        );
+        assert_eq!(
+            app.take_pending_copy().as_deref(),
+            Some("[1234567 Fix](url)")
+        );
+        assert!(app.take_pending_copy().is_none());
+    }
+
+    #[test]
+    fn test_key_sequence_pending_and_cancel() {
+        let mut app = App::new();
+        app.view_manager.push(Box::new(HelpView::new())).unwrap();
+        let key = |c| Event::Key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+
+        // A prefix key waits for the rest of the sequence
+        app.handle_event(key('g')).unwrap();
+        assert_eq!(app.keys.pending(), [Key::char('g')]);
+
+        // An unbound continuation cancels without reaching the view
+        app.handle_event(key('x')).unwrap();
+        assert!(app.keys.pending().is_empty());
+
+        app.handle_event(key('g')).unwrap();
+        app.handle_event(key('g')).unwrap();
+        assert!(app.keys.pending().is_empty());
+    }
 }
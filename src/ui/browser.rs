@@ -0,0 +1,75 @@
+use std::io;
+use std::process::{Command, Stdio};
+
+/// Get the command that opens a URL: $BROWSER, then the platform's opener
+pub fn browser_command() -> String {
+    std::env::var("BROWSER")
+        .ok()
+        .filter(|value| !value.trim().is_empty())
+        .unwrap_or_else(|| {
+            match std::env::consts::OS {
+                "macos" => "open",
+                "windows" => "explorer",
+                _ => "xdg-open",
+            }
+            .to_string()
+        })
+}
+
+/// Open `url` in the user's browser without waiting for it
+pub fn open_url(url: &str) -> io::Result<()> {
+    open_url_with(&browser_command(), url)
+}
+
+/// Open `url` with `browser`, a command line that takes the URL as its last
+/// argument; its output is discarded so it can't draw over the UI
+pub fn open_url_with(browser: &str, url: &str) -> io::Result<()> {
+    let mut words = browser.split_whitespace();
+    let program = words
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no browser configured"))?;
+
+    let mut child = Command::new(program)
+        .args(words)
+        .arg(url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| io::Error::new(e.kind(), format!("failed to run {}: {}", program, e)))?;
+    // Reap it once it exits, which openers like xdg-open do right away
+    std::thread::spawn(move || child.wait());
+    Ok(())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    #[test]
+    fn test_open_url_runs_browser() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("url");
+        let script = dir.path().join("browser.sh");
+        std::fs::write(
+            &script,
+            format!("#!/bin/sh\necho \"$1\" > {}\n", out.display()),
+        )
+        .unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        open_url_with(script.to_str().unwrap(), "https://example.com/issues/7").unwrap();
+        for _ in 0..50 {
+            if std::fs::read_to_string(&out).is_ok_and(|url| !url.is_empty()) {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        assert_eq!(
+            std::fs::read_to_string(&out).unwrap(),
+            "https://example.com/issues/7\n"
+        );
+        assert!(open_url_with("", "https://example.com").is_err());
+    }
+}
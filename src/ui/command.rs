@@ -0,0 +1,706 @@
+use crate::git::{
+    CheckoutTarget, CredentialPrompt, FetchReport, GitError, LostCommit, Maintenance, MergeOutcome,
+    PickReport, PullReport, PushOptions, PushReport, Repository, ResetMode, RevertOutcome,
+    SshDiagnostics,
+};
+use crate::views::{Selection, ViewType};
+use git2::Oid;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// A command typed at the `:` prompt
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// Find the merge base of two revisions and select it
+    MergeBase(String, String),
+    /// Fetch one remote, or every remote without one, pruning gone
+    /// branches with `--prune`
+    Fetch { prune: bool, remote: Option<String> },
+    /// Bring the checked out branch up to date with its upstream, rebasing
+    /// or not as asked, or as `pull.rebase` says when None
+    Pull { rebase: Option<bool> },
+    /// Work out what to push and open the push dialog
+    PushDialog,
+    /// Push as the dialog set it up
+    Push(PushOptions),
+    /// Push where the dialog would, with the flags typed at the prompt
+    /// instead of asking
+    PushWith {
+        force_with_lease: bool,
+        force: bool,
+        set_upstream: bool,
+        tags: bool,
+    },
+    /// Check out a branch or commit, carrying uncommitted changes along
+    /// only once the user agreed to
+    Checkout {
+        target: CheckoutTarget,
+        carry_changes: bool,
+    },
+    /// Check out a branch, tag or revision by the name typed at the prompt
+    CheckoutName(String),
+    /// Cherry-pick commits onto HEAD, oldest first
+    CherryPick(Vec<Oid>),
+    /// Revert a commit, committing the revert or leaving it staged
+    Revert { id: Oid, commit: bool },
+    /// Reset the checked out branch to a commit
+    Reset { id: Oid, mode: ResetMode },
+    /// Merge a reference, by its full name, into HEAD
+    Merge(String),
+    /// Search unreachable commits for a hash prefix or message text
+    FindLost(String),
+    /// Check the repository's health
+    Fsck,
+    /// Run `git gc`, or `git maintenance run`, in the background
+    Gc(Maintenance),
+    /// List the hooks
+    Hooks,
+    /// List the review notes
+    Review,
+    /// Switch to the repository at or above a path
+    Open(String),
+}
+
+impl Command {
+    /// Say what the command is busy doing, like "pushing"
+    pub fn label(&self) -> &'static str {
+        match self {
+            Command::MergeBase(..) => "finding a merge base",
+            Command::Fetch { .. } => "fetching",
+            Command::Pull { .. } => "pulling",
+            Command::PushDialog => "preparing a push",
+            Command::Push(_) | Command::PushWith { .. } => "pushing",
+            Command::Checkout { .. } | Command::CheckoutName(_) => "checking out",
+            Command::CherryPick(_) => "cherry-picking",
+            Command::Revert { .. } => "reverting",
+            Command::Reset { .. } => "resetting",
+            Command::Merge(_) => "merging",
+            Command::FindLost(_) => "searching for lost commits",
+            Command::Fsck | Command::Gc(_) | Command::Hooks | Command::Review => {
+                "running a command"
+            }
+            Command::Open(_) => "opening a repository",
+        }
+    }
+}
+
+/// What running a command asks the app to do
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    /// Select a commit in the main view, telling the user why
+    SelectCommit { oid: Oid, message: String },
+    /// Remotes were fetched
+    Fetched(FetchReport),
+    /// The branch was pulled
+    Pulled(PullReport),
+    /// Let the user adjust what to push
+    PushDialog(PushOptions),
+    /// A push reached the remote, which updated or refused each ref;
+    /// the branch is left this far ahead of and behind its upstream
+    Pushed {
+        report: PushReport,
+        upstream: Option<(usize, usize)>,
+    },
+    /// A push to an SSH remote failed; show what the transport saw
+    PushFailed {
+        message: String,
+        ssh: SshDiagnostics,
+    },
+    /// HEAD moved to a branch or commit
+    CheckedOut(String),
+    /// Ask whether to check out with uncommitted changes in the way
+    ConfirmCheckout(CheckoutTarget),
+    /// Commits were cherry-picked, or one stopped on conflicts
+    CherryPicked(PickReport),
+    /// A commit was reverted, or its revert staged or stopped on conflicts
+    Reverted { id: Oid, outcome: RevertOutcome },
+    /// The named branch, or HEAD, was reset to a commit
+    Reset {
+        id: Oid,
+        mode: ResetMode,
+        branch: String,
+    },
+    /// A reference was merged, fast-forwarded to, or its merge staged or
+    /// stopped on conflicts
+    Merged { name: String, outcome: MergeOutcome },
+    /// Show the lost commits a search found
+    LostCommits {
+        query: String,
+        commits: Vec<LostCommit>,
+    },
+    /// Open a view that loads itself
+    OpenView(ViewType),
+    /// Run a maintenance task, following its output in a pager
+    Maintenance(Maintenance),
+    /// Another repository was opened, to start over in
+    Opened {
+        repo: Repository,
+        branch: Option<String>,
+        /// Whether it has no commits yet
+        empty: bool,
+    },
+}
+
+/// Names of the built-in commands, which aliases can't shadow
+const COMMANDS: &[&str] = &[
+    "merge-base",
+    "fetch",
+    "pull",
+    "push",
+    "checkout",
+    "lost",
+    "fsck",
+    "gc",
+    "hooks",
+    "review",
+    "open",
+];
+
+/// Expand a command line whose first word is an alias, filling in its
+/// placeholders from the selection and the checked out branch and adding
+/// any words typed after it; other lines are returned as they are
+pub fn expand_alias(
+    line: &str,
+    aliases: &HashMap<String, String>,
+    selection: &Selection,
+    branch: Option<&str>,
+) -> Result<String, String> {
+    let line = line.trim();
+    let (name, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+    let Some(expansion) = aliases.get(name).filter(|_| !COMMANDS.contains(&name)) else {
+        return Ok(line.to_string());
+    };
+
+    let placeholders = [
+        (
+            "%(commit)",
+            selection.commit.map(|oid| oid.to_string()),
+            "a selected commit",
+        ),
+        (
+            "%(ref)",
+            selection.refname.clone(),
+            "a selected branch or tag",
+        ),
+        (
+            "%(branch)",
+            branch.map(str::to_string),
+            "a checked out branch",
+        ),
+        ("%(file)", selection.file.clone(), "a selected file"),
+    ];
+    let mut expanded = expansion.clone();
+    for (placeholder, value, needs) in placeholders {
+        if expanded.contains(placeholder) {
+            let Some(value) = value else {
+                return Err(format!(
+                    "{} uses {}, which needs {}",
+                    name, placeholder, needs
+                ));
+            };
+            expanded = expanded.replace(placeholder, &value);
+        }
+    }
+    if !rest.trim().is_empty() {
+        expanded = format!("{} {}", expanded, rest.trim());
+    }
+    Ok(expanded)
+}
+
+/// Parse a command line, without its leading ':'
+pub fn parse(line: &str) -> Result<Command, String> {
+    let mut words = line.split_whitespace();
+    let Some(name) = words.next() else {
+        return Err("No command given".to_string());
+    };
+    let args: Vec<&str> = words.collect();
+
+    match name {
+        "merge-base" => match args.as_slice() {
+            [first, second] => Ok(Command::MergeBase(first.to_string(), second.to_string())),
+            _ => Err("Usage: merge-base <rev> <rev>".to_string()),
+        },
+        "fetch" => {
+            let prune = args.first() == Some(&"--prune");
+            match &args[prune as usize..] {
+                [] => Ok(Command::Fetch {
+                    prune,
+                    remote: None,
+                }),
+                [remote] if !remote.starts_with('-') => Ok(Command::Fetch {
+                    prune,
+                    remote: Some(remote.to_string()),
+                }),
+                _ => Err("Usage: fetch [--prune] [remote]".to_string()),
+            }
+        }
+        "pull" => match args.as_slice() {
+            [] => Ok(Command::Pull { rebase: None }),
+            ["--rebase"] => Ok(Command::Pull { rebase: Some(true) }),
+            ["--no-rebase"] => Ok(Command::Pull {
+                rebase: Some(false),
+            }),
+            _ => Err("Usage: pull [--rebase | --no-rebase]".to_string()),
+        },
+        "push" if args.is_empty() => Ok(Command::PushDialog),
+        "push" => {
+            let usage = || {
+                "Usage: push [--force-with-lease | --force] [--set-upstream] [--tags]".to_string()
+            };
+            let (mut force_with_lease, mut force, mut set_upstream, mut tags) =
+                (false, false, false, false);
+            for arg in args {
+                let flag = match arg {
+                    "--force-with-lease" => &mut force_with_lease,
+                    "--force" | "-f" => &mut force,
+                    "--set-upstream" | "-u" => &mut set_upstream,
+                    "--tags" => &mut tags,
+                    _ => return Err(usage()),
+                };
+                *flag = true;
+            }
+            match force_with_lease && force {
+                true => Err(usage()),
+                false => Ok(Command::PushWith {
+                    force_with_lease,
+                    force,
+                    set_upstream,
+                    tags,
+                }),
+            }
+        }
+        "checkout" => match args.as_slice() {
+            [name] => Ok(Command::CheckoutName(name.to_string())),
+            _ => Err("Usage: checkout <ref>".to_string()),
+        },
+        "lost" => Ok(Command::FindLost(args.join(" "))),
+        "fsck" => match args.as_slice() {
+            [] => Ok(Command::Fsck),
+            _ => Err("Usage: fsck".to_string()),
+        },
+        "gc" => match args.as_slice() {
+            [] => Ok(Command::Gc(Maintenance::Gc)),
+            ["--maintenance"] => Ok(Command::Gc(Maintenance::Run)),
+            _ => Err("Usage: gc [--maintenance]".to_string()),
+        },
+        "hooks" => match args.as_slice() {
+            [] => Ok(Command::Hooks),
+            _ => Err("Usage: hooks".to_string()),
+        },
+        "review" => match args.as_slice() {
+            [] => Ok(Command::Review),
+            _ => Err("Usage: review".to_string()),
+        },
+        "open" => match args.is_empty() {
+            true => Err("Usage: open <path>".to_string()),
+            false => Ok(Command::Open(args.join(" "))),
+        },
+        _ => Err(format!("Unknown command: {}", name)),
+    }
+}
+
+/// Run a command against the repository, asking `prompt` for credentials a
+/// remote needs
+pub async fn run(
+    repo: &Repository,
+    command: Command,
+    prompt: CredentialPrompt,
+) -> Result<Outcome, String> {
+    match command {
+        Command::MergeBase(first, second) => {
+            let oid = crate::git::refs::merge_base(repo, first.clone(), second.clone())
+                .await
+                .map_err(|e| format!("No merge base of {} and {}: {}", first, second, e))?;
+            Ok(Outcome::SelectCommit {
+                oid,
+                message: format!(
+                    "Merge base of {} and {}: {:.7}",
+                    first,
+                    second,
+                    oid.to_string()
+                ),
+            })
+        }
+        Command::Fetch { prune, remote } => {
+            let fetched = match remote {
+                Some(remote) => {
+                    crate::git::remote::fetch_remote(repo, remote, prune, Some(prompt)).await
+                }
+                None => crate::git::remote::fetch_all(repo, prune, Some(prompt)).await,
+            };
+            fetched
+                .map(Outcome::Fetched)
+                .map_err(|e| format!("Fetch failed: {}", e))
+        }
+        Command::Pull { rebase } => crate::git::pull::pull(repo, rebase, Some(prompt))
+            .await
+            .map(Outcome::Pulled)
+            .map_err(|e| e.to_string()),
+        Command::PushDialog => crate::git::remote::push_defaults(repo)
+            .await
+            .map(Outcome::PushDialog)
+            .map_err(|e| format!("Cannot push: {}", e)),
+        Command::Push(options) => push(repo, options, prompt).await,
+        Command::PushWith {
+            force_with_lease,
+            force,
+            set_upstream,
+            tags,
+        } => {
+            let defaults = crate::git::remote::push_defaults(repo)
+                .await
+                .map_err(|e| format!("Cannot push: {}", e))?;
+            let options = PushOptions {
+                force_with_lease,
+                force,
+                set_upstream: defaults.set_upstream || set_upstream,
+                tags,
+                ..defaults
+            };
+            push(repo, options, prompt).await
+        }
+        Command::Checkout {
+            target,
+            carry_changes,
+        } => checkout(repo, target, carry_changes).await,
+        Command::CheckoutName(name) => {
+            let target = crate::git::checkout::resolve_target(repo, name.clone())
+                .await
+                .map_err(|e| format!("Can't check out {}: {}", name, e))?;
+            checkout(repo, target, false).await
+        }
+        Command::CherryPick(ids) => crate::git::cherry_pick::cherry_pick(repo, ids)
+            .await
+            .map(Outcome::CherryPicked)
+            .map_err(|e| format!("Cherry-pick failed: {}", e)),
+        Command::Revert { id, commit } => crate::git::revert::revert(repo, id, commit)
+            .await
+            .map(|outcome| Outcome::Reverted { id, outcome })
+            .map_err(|e| format!("Revert failed: {}", e)),
+        Command::Reset { id, mode } => crate::git::reset::reset(repo, id, mode)
+            .await
+            .map(|branch| Outcome::Reset { id, mode, branch })
+            .map_err(|e| format!("Reset failed: {}", e)),
+        Command::Merge(name) => crate::git::merge::merge(repo, name.clone())
+            .await
+            .map(|outcome| Outcome::Merged { name, outcome })
+            .map_err(|e| format!("Merge failed: {}", e)),
+        Command::FindLost(query) => {
+            let commits = crate::git::lost::find_lost(repo, query.clone())
+                .await
+                .map_err(|e| format!("Lost commit search failed: {}", e))?;
+            match commits.is_empty() {
+                true if query.is_empty() => Err("No lost commits".to_string()),
+                true => Err(format!("No lost commits match {}", query)),
+                false => Ok(Outcome::LostCommits { query, commits }),
+            }
+        }
+        Command::Fsck => Ok(Outcome::OpenView(ViewType::Health)),
+        Command::Gc(task) => Ok(Outcome::Maintenance(task)),
+        Command::Hooks => Ok(Outcome::OpenView(ViewType::Hooks)),
+        Command::Review => Ok(Outcome::OpenView(ViewType::Review)),
+        Command::Open(path) => open_repository(repo, &path)
+            .await
+            .map_err(|e| format!("Can't open {}: {}", path, e)),
+    }
+}
+
+/// Push as `options` say, reporting an SSH failure with what the
+/// transport saw
+async fn push(
+    repo: &Repository,
+    options: PushOptions,
+    prompt: CredentialPrompt,
+) -> Result<Outcome, String> {
+    match crate::git::remote::push(repo, options, Some(prompt)).await {
+        Ok(report) => Ok(Outcome::Pushed {
+            report,
+            upstream: crate::git::remote::upstream_ahead_behind(repo)
+                .await
+                .ok()
+                .flatten(),
+        }),
+        Err(GitError::PushFailed {
+            remote,
+            message,
+            ssh: Some(ssh),
+        }) => Ok(Outcome::PushFailed {
+            message: format!("Push to {} failed: {}", remote, message),
+            ssh: *ssh,
+        }),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Check out `target`, asking first when uncommitted changes are in the
+/// way and `carry_changes` isn't set
+async fn checkout(
+    repo: &Repository,
+    target: CheckoutTarget,
+    carry_changes: bool,
+) -> Result<Outcome, String> {
+    match crate::git::checkout::checkout(repo, target.clone(), carry_changes).await {
+        Ok(head) => Ok(Outcome::CheckedOut(format!("HEAD is now {}", head))),
+        Err(GitError::UncommittedChanges) => Ok(Outcome::ConfirmCheckout(target)),
+        Err(e) => Err(format!("Checkout of {} failed: {}", target.describe(), e)),
+    }
+}
+
+/// Open the repository at or above `path`, which may start with `~`; a
+/// relative path is taken from the working tree of `current`
+async fn open_repository(current: &Repository, path: &str) -> crate::git::Result<Outcome> {
+    let path = match (path.strip_prefix('~'), dirs::home_dir()) {
+        (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with('/') => {
+            home.join(rest.trim_start_matches('/'))
+        }
+        _ => PathBuf::from(path),
+    };
+    let path = match (path.is_relative(), current.workdir().await?) {
+        (true, Some(workdir)) => workdir.join(path),
+        _ => path,
+    };
+    let repo = Repository::discover_from(path).await?;
+    Ok(Outcome::Opened {
+        branch: repo.current_branch().await.ok().flatten(),
+        empty: repo.is_empty().await?,
+        repo,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::RepoBuilder;
+
+    #[test]
+    fn test_parse_merge_base() {
+        assert_eq!(
+            parse("merge-base main  feature"),
+            Ok(Command::MergeBase(
+                "main".to_string(),
+                "feature".to_string()
+            ))
+        );
+        assert!(parse("merge-base main").unwrap_err().starts_with("Usage"));
+        assert_eq!(
+            parse("frobnicate"),
+            Err("Unknown command: frobnicate".to_string())
+        );
+        assert_eq!(
+            parse("fetch --prune"),
+            Ok(Command::Fetch {
+                prune: true,
+                remote: None
+            })
+        );
+        assert_eq!(
+            parse("fetch --prune upstream"),
+            Ok(Command::Fetch {
+                prune: true,
+                remote: Some("upstream".to_string())
+            })
+        );
+        assert!(
+            parse("fetch origin --prune")
+                .unwrap_err()
+                .starts_with("Usage")
+        );
+        assert_eq!(parse("push"), Ok(Command::PushDialog));
+        assert_eq!(
+            parse("push -u --force-with-lease"),
+            Ok(Command::PushWith {
+                force_with_lease: true,
+                force: false,
+                set_upstream: true,
+                tags: false,
+            })
+        );
+        assert!(
+            parse("push --force --force-with-lease")
+                .unwrap_err()
+                .starts_with("Usage")
+        );
+        assert!(parse("push origin").unwrap_err().starts_with("Usage"));
+        assert_eq!(
+            parse("checkout topic"),
+            Ok(Command::CheckoutName("topic".to_string()))
+        );
+        assert!(parse("checkout").unwrap_err().starts_with("Usage"));
+        assert_eq!(parse("pull"), Ok(Command::Pull { rebase: None }));
+        assert_eq!(
+            parse("pull --no-rebase"),
+            Ok(Command::Pull {
+                rebase: Some(false)
+            })
+        );
+        assert!(parse("pull origin main").unwrap_err().starts_with("Usage"));
+        assert_eq!(
+            parse("lost fix  typo"),
+            Ok(Command::FindLost("fix typo".to_string()))
+        );
+        assert_eq!(parse("fsck"), Ok(Command::Fsck));
+        assert_eq!(parse("gc --maintenance"), Ok(Command::Gc(Maintenance::Run)));
+        assert!(parse("gc --aggressive").unwrap_err().starts_with("Usage"));
+        assert_eq!(parse("review"), Ok(Command::Review));
+        assert_eq!(
+            parse("open ~/My Projects"),
+            Ok(Command::Open("~/My Projects".to_string()))
+        );
+        assert!(parse("open").is_err());
+        assert!(parse("   ").is_err());
+    }
+
+    #[test]
+    fn test_expand_alias() {
+        let aliases = HashMap::from([
+            (
+                "mb".to_string(),
+                "merge-base %(commit) %(branch)".to_string(),
+            ),
+            ("up".to_string(), "fetch --prune".to_string()),
+            ("fetch".to_string(), "pull".to_string()),
+        ]);
+        let oid = Oid::from_str("0123456789012345678901234567890123456789").unwrap();
+        let selection = Selection {
+            commit: Some(oid),
+            ..Selection::default()
+        };
+
+        assert_eq!(
+            expand_alias("mb", &aliases, &selection, Some("main")),
+            Ok(format!("merge-base {} main", oid))
+        );
+        assert_eq!(
+            expand_alias("up  origin", &aliases, &selection, None),
+            Ok("fetch --prune origin".to_string())
+        );
+        // Built-in commands win over aliases of the same name
+        assert_eq!(
+            expand_alias("fetch", &aliases, &selection, None),
+            Ok("fetch".to_string())
+        );
+        assert_eq!(
+            expand_alias("mb", &aliases, &Selection::default(), Some("main")),
+            Err("mb uses %(commit), which needs a selected commit".to_string())
+        );
+        for name in COMMANDS {
+            assert_ne!(parse(name), Err(format!("Unknown command: {}", name)));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_run_merge_base() {
+        let builder = RepoBuilder::new().commit("Base").branch("left");
+        let base = builder.head();
+        let (_temp_dir, repo) = builder.checkout("left").commit("Left").open().await;
+
+        let (prompt, _requests) = tokio::sync::mpsc::unbounded_channel();
+        let command = parse(&format!("merge-base left {}", base)).unwrap();
+        let Ok(Outcome::SelectCommit { oid, message }) = run(&repo, command, prompt.clone()).await
+        else {
+            panic!("expected a commit to select");
+        };
+        assert_eq!(oid, base);
+        assert!(message.contains(&base.to_string()[..7]));
+
+        let command = parse("merge-base left nope").unwrap();
+        assert!(
+            run(&repo, command, prompt)
+                .await
+                .unwrap_err()
+                .contains("nope")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_checkout_and_push_aliases() {
+        let origin_dir = tempfile::TempDir::new().unwrap();
+        git2::Repository::init_bare(origin_dir.path()).unwrap();
+        let builder = RepoBuilder::new().commit("Initial").branch("topic");
+        let default_branch = builder
+            .git2()
+            .head()
+            .unwrap()
+            .shorthand()
+            .unwrap()
+            .to_string();
+        let (_temp_dir, repo) = builder.checkout("topic").open().await;
+        let git_repo = repo.open_git2().unwrap();
+        git_repo
+            .remote("origin", origin_dir.path().to_str().unwrap())
+            .unwrap();
+        let aliases = HashMap::from([
+            ("co".to_string(), "checkout".to_string()),
+            ("please".to_string(), "push --force-with-lease".to_string()),
+        ]);
+        let (prompt, _requests) = tokio::sync::mpsc::unbounded_channel();
+        let run_line = async |line: &str| {
+            let line = expand_alias(line, &aliases, &Selection::default(), None).unwrap();
+            run(&repo, parse(&line).unwrap(), prompt.clone()).await
+        };
+
+        let Ok(Outcome::Pushed { report, .. }) = run_line("push --set-upstream").await else {
+            panic!("expected the branch to be pushed");
+        };
+        assert_eq!(report.rejected().count(), 0);
+
+        // Rewriting the pushed commit needs the lease
+        let head = git_repo.head().unwrap().peel_to_commit().unwrap();
+        let rewritten = head
+            .amend(Some("HEAD"), None, None, None, Some("Rewritten"), None)
+            .unwrap();
+        let Ok(Outcome::Pushed { report, .. }) = run_line("please").await else {
+            panic!("expected the rewritten branch to be pushed");
+        };
+        assert_eq!(report.rejected().count(), 0);
+        let origin = git2::Repository::open_bare(origin_dir.path()).unwrap();
+        assert_eq!(origin.refname_to_id("refs/heads/topic").unwrap(), rewritten);
+
+        let checked_out = run_line(&format!("co {}", default_branch)).await;
+        assert_eq!(
+            checked_out,
+            Ok(Outcome::CheckedOut(format!(
+                "HEAD is now {}",
+                default_branch
+            )))
+        );
+        assert!(run_line("co nope").await.unwrap_err().contains("nope"));
+    }
+
+    #[tokio::test]
+    async fn test_run_open_from_the_working_tree() {
+        let (_dir, repo) = RepoBuilder::new().commit("Initial").open().await;
+        let (other_dir, other) = RepoBuilder::new()
+            .commit("Initial")
+            .branch("topic")
+            .checkout("topic")
+            .open()
+            .await;
+        std::fs::create_dir(other_dir.path().join("src")).unwrap();
+        let name = other_dir.path().file_name().unwrap().to_str().unwrap();
+
+        let (prompt, _requests) = tokio::sync::mpsc::unbounded_channel();
+        let command = parse(&format!("open ../{}/src", name)).unwrap();
+        let Ok(Outcome::Opened {
+            repo: opened,
+            branch,
+            empty,
+        }) = run(&repo, command, prompt.clone()).await
+        else {
+            panic!("expected the other repository to open");
+        };
+        assert_eq!(
+            opened.workdir().await.unwrap(),
+            other.workdir().await.unwrap()
+        );
+        assert_eq!((branch.as_deref(), empty), (Some("topic"), false));
+
+        let command = parse("open ../does-not-exist").unwrap();
+        assert!(
+            run(&repo, command, prompt)
+                .await
+                .unwrap_err()
+                .starts_with("Can't open ../does-not-exist")
+        );
+    }
+}
@@ -0,0 +1,158 @@
+use crate::config::Symbols;
+use crate::git::CredentialRequest;
+use crate::views::popup::centered_rect;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    Frame,
+    layout::Rect,
+    text::Line,
+    widgets::{Clear, Paragraph},
+};
+
+/// The username and password typed for a remote, the password masked
+pub struct CredentialInput {
+    request: CredentialRequest,
+    username: String,
+    password: String,
+    /// Whether keys go to the password rather than the username
+    on_password: bool,
+}
+
+impl CredentialInput {
+    /// Start the prompt, at the password when the URL names the user
+    pub fn new(request: CredentialRequest) -> Self {
+        let username = request.username.clone().unwrap_or_default();
+        Self {
+            on_password: !username.is_empty(),
+            request,
+            username,
+            password: String::new(),
+        }
+    }
+
+    /// Handle a key, giving the prompt back while it stays open
+    ///
+    /// Enter moves from the username to the password, then sends both;
+    /// Esc cancels the fetch's request.
+    pub fn handle_key(mut self, key: KeyEvent) -> Option<Self> {
+        let field = match self.on_password {
+            true => &mut self.password,
+            false => &mut self.username,
+        };
+        match key.code {
+            KeyCode::Char(c) => field.push(c),
+            KeyCode::Backspace => {
+                field.pop();
+            }
+            KeyCode::Enter if !self.on_password => self.on_password = true,
+            KeyCode::Enter => {
+                self.request.answer(self.username, self.password);
+                return None;
+            }
+            KeyCode::Esc => {
+                self.request.cancel();
+                return None;
+            }
+            _ => {}
+        }
+        Some(self)
+    }
+
+    /// Render the prompt centered in `area`
+    pub fn draw(&self, frame: &mut Frame, area: Rect, symbols: &Symbols) {
+        let cursor = |active: bool| if active { "_" } else { "" };
+        let lines = vec![
+            Line::from(format!(" {} ", self.request.url)),
+            Line::from(""),
+            Line::from(format!(
+                " Username: {}{}",
+                self.username,
+                cursor(!self.on_password)
+            )),
+            Line::from(format!(
+                " Password: {}{}",
+                "*".repeat(self.password.chars().count()),
+                cursor(self.on_password)
+            )),
+            Line::from(""),
+            Line::from(" Enter next/send, Esc cancel "),
+        ];
+
+        let width = lines.iter().map(Line::width).max().unwrap_or(0).max(40) as u16 + 2;
+        let popup = centered_rect(width, lines.len() as u16 + 2, area);
+        frame.render_widget(Clear, popup);
+        frame.render_widget(
+            Paragraph::new(lines).block(symbols.block().title(" Credentials ")),
+            popup,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::remote::CredentialPrompt;
+    use tokio::sync::mpsc;
+
+    /// Ask for credentials on another thread, as a fetch does
+    fn ask(
+        prompt: CredentialPrompt,
+        username: Option<&str>,
+    ) -> std::thread::JoinHandle<Option<(String, String)>> {
+        let username = username.map(str::to_string);
+        std::thread::spawn(move || {
+            let (reply, answer) = std::sync::mpsc::channel();
+            prompt
+                .send(CredentialRequest::new(
+                    "https://example.com/r.git".to_string(),
+                    username,
+                    reply,
+                ))
+                .unwrap();
+            answer.recv().unwrap()
+        })
+    }
+
+    fn type_keys(mut input: Option<CredentialInput>, keys: &[KeyCode]) -> Option<CredentialInput> {
+        for key in keys {
+            input = input?.handle_key(KeyEvent::from(*key));
+        }
+        input
+    }
+
+    #[test]
+    fn test_username_then_password() {
+        let (prompt, mut requests) = mpsc::unbounded_channel();
+        let asking = ask(prompt, None);
+        let input = Some(CredentialInput::new(requests.blocking_recv().unwrap()));
+
+        let keys = [
+            KeyCode::Char('b'),
+            KeyCode::Char('o'),
+            KeyCode::Backspace,
+            KeyCode::Char('b'),
+        ];
+        let input = type_keys(input, &keys);
+        let input = type_keys(
+            input,
+            &[KeyCode::Enter, KeyCode::Char('p'), KeyCode::Char('w')],
+        );
+        assert!(input.is_some());
+        assert!(type_keys(input, &[KeyCode::Enter]).is_none());
+        assert_eq!(
+            asking.join().unwrap(),
+            Some(("bb".to_string(), "pw".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_username_from_url_and_cancel() {
+        let (prompt, mut requests) = mpsc::unbounded_channel();
+        let asking = ask(prompt, Some("alice"));
+        let input = CredentialInput::new(requests.blocking_recv().unwrap());
+        assert!(input.on_password);
+
+        assert!(type_keys(Some(input), &[KeyCode::Char('x'), KeyCode::Esc]).is_none());
+        assert_eq!(asking.join().unwrap(), None);
+    }
+}
@@ -0,0 +1,76 @@
+use std::fs;
+use std::io;
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Numbers the temporary directories so concurrent edits don't collide
+static EDITS: AtomicUsize = AtomicUsize::new(0);
+
+/// Get the user's editor command: $VISUAL, then $EDITOR, then vi
+pub fn editor_command() -> String {
+    ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|value| !value.trim().is_empty())
+        .unwrap_or_else(|| "vi".to_string())
+}
+
+/// Edit `text` with the user's editor, returning what it was saved as
+///
+/// The caller must hand over the terminal first; the editor runs in the
+/// foreground until it exits.
+pub fn edit_text(text: &str, file_name: &str) -> io::Result<String> {
+    edit_text_with(&editor_command(), text, file_name)
+}
+
+/// Edit `text` in a temporary file with `editor`, a command line such as
+/// "code --wait" that takes the file as its last argument
+pub fn edit_text_with(editor: &str, text: &str, file_name: &str) -> io::Result<String> {
+    let mut words = editor.split_whitespace();
+    let program = words
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no editor configured"))?;
+
+    let dir = std::env::temp_dir().join(format!(
+        "rust-tig-{}-{}",
+        std::process::id(),
+        EDITS.fetch_add(1, Ordering::Relaxed)
+    ));
+    fs::create_dir_all(&dir)?;
+    let path = dir.join(file_name);
+    fs::write(&path, text)?;
+
+    let status = Command::new(program).args(words).arg(&path).status();
+    let result = match status {
+        Ok(status) if status.success() => fs::read_to_string(&path),
+        Ok(status) => Err(io::Error::other(format!(
+            "{} exited with {}",
+            program, status
+        ))),
+        Err(e) => Err(io::Error::new(
+            e.kind(),
+            format!("failed to run {}: {}", program, e),
+        )),
+    };
+
+    let _ = fs::remove_dir_all(&dir);
+    result
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edit_text_returns_saved_file() {
+        let edited = edit_text_with("sed -i s/old/new/", "old line\n", "edit-test.txt").unwrap();
+        assert_eq!(edited, "new line\n");
+    }
+
+    #[test]
+    fn test_failing_editor_is_an_error() {
+        let error = edit_text_with("false", "text\n", "fail-test.txt").unwrap_err();
+        assert!(error.to_string().contains("false exited"));
+        assert!(edit_text_with("  ", "text\n", "empty-test.txt").is_err());
+    }
+}
@@ -1,8 +1,15 @@
-use anyhow::Result;
 use crossterm::event::{self, Event as CrosstermEvent, KeyEvent, MouseEvent};
+use std::collections::VecDeque;
+use std::future::Future;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
 use tokio::sync::mpsc;
 
+/// Events waiting for the main loop before the terminal reader pauses;
+/// typing can't get far enough ahead of the UI to matter
+pub const EVENT_BUFFER: usize = 64;
+
 /// Application events
 #[derive(Debug, Clone)]
 pub enum Event {
@@ -12,56 +19,112 @@ pub enum Event {
     Tick,
 }
 
+/// Where the main loop gets its events from: the terminal, a recorded
+/// session, or a test script
+pub trait EventSource {
+    /// Receive the next event, or `None` once the source is exhausted
+    fn next(&mut self) -> impl Future<Output = Option<Event>> + Send;
+
+    /// Stop or resume reading the terminal while another program uses it
+    fn set_paused(&mut self, paused: bool) {
+        let _ = paused;
+    }
+
+    /// Mark whether keys are going into a text or password prompt, whose
+    /// contents a recording should leave out
+    fn set_private(&mut self, private: bool) {
+        let _ = private;
+    }
+}
+
 /// Event handler that polls for terminal events
+///
+/// Events queue on a bounded channel, so the reader waits for a busy main
+/// loop rather than queueing without limit. At most one tick waits at a
+/// time: ticks only ask for a redraw, and a queued one already does.
 pub struct EventHandler {
-    sender: mpsc::UnboundedSender<Event>,
-    receiver: mpsc::UnboundedReceiver<Event>,
+    sender: mpsc::Sender<Event>,
+    receiver: mpsc::Receiver<Event>,
+    paused: Arc<AtomicBool>,
+    /// Set while a tick sits in the channel
+    tick_queued: Arc<AtomicBool>,
 }
 
 impl EventHandler {
     pub fn new() -> Self {
-        let (sender, receiver) = mpsc::unbounded_channel();
-        Self { sender, receiver }
+        let (sender, receiver) = mpsc::channel(EVENT_BUFFER);
+        Self {
+            sender,
+            receiver,
+            paused: Arc::new(AtomicBool::new(false)),
+            tick_queued: Arc::new(AtomicBool::new(false)),
+        }
     }
 
     /// Spawn a task to poll for events
     pub fn start(&self) {
         let sender = self.sender.clone();
+        let paused = self.paused.clone();
+        let tick_queued = self.tick_queued.clone();
         tokio::spawn(async move {
             loop {
+                // Leave input alone while an editor has the terminal
+                if paused.load(Ordering::Relaxed) {
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                    continue;
+                }
+
                 // Poll for events with a timeout
                 if let Ok(true) = event::poll(Duration::from_millis(100)) {
-                    match event::read() {
-                        Ok(CrosstermEvent::Key(key)) => {
-                            if sender.send(Event::Key(key)).is_err() {
-                                break;
-                            }
-                        }
-                        Ok(CrosstermEvent::Mouse(mouse)) => {
-                            if sender.send(Event::Mouse(mouse)).is_err() {
-                                break;
-                            }
-                        }
-                        Ok(CrosstermEvent::Resize(w, h)) => {
-                            if sender.send(Event::Resize(w, h)).is_err() {
-                                break;
-                            }
-                        }
-                        _ => {}
+                    if paused.load(Ordering::Relaxed) {
+                        continue;
                     }
-                } else {
-                    // Send tick event
-                    if sender.send(Event::Tick).is_err() {
+                    let event = match event::read() {
+                        Ok(CrosstermEvent::Key(key)) => Some(Event::Key(key)),
+                        Ok(CrosstermEvent::Mouse(mouse)) => Some(Event::Mouse(mouse)),
+                        Ok(CrosstermEvent::Resize(w, h)) => Some(Event::Resize(w, h)),
+                        _ => None,
+                    };
+                    if let Some(event) = event
+                        && sender.send(event).await.is_err()
+                    {
                         break;
                     }
+                } else if !send_tick(&sender, &tick_queued) {
+                    break;
                 }
             }
         });
     }
+}
+
+/// Queue a tick unless one is already waiting or the channel is full,
+/// returning false once the main loop is gone
+fn send_tick(sender: &mpsc::Sender<Event>, tick_queued: &AtomicBool) -> bool {
+    if tick_queued.swap(true, Ordering::AcqRel) {
+        return !sender.is_closed();
+    }
+    match sender.try_send(Event::Tick) {
+        Ok(()) => true,
+        Err(mpsc::error::TrySendError::Full(_)) => {
+            tick_queued.store(false, Ordering::Release);
+            true
+        }
+        Err(mpsc::error::TrySendError::Closed(_)) => false,
+    }
+}
+
+impl EventSource for EventHandler {
+    async fn next(&mut self) -> Option<Event> {
+        let event = self.receiver.recv().await;
+        if let Some(Event::Tick) = event {
+            self.tick_queued.store(false, Ordering::Release);
+        }
+        event
+    }
 
-    /// Receive the next event
-    pub async fn next(&mut self) -> Option<Event> {
-        self.receiver.recv().await
+    fn set_paused(&mut self, paused: bool) {
+        self.paused.store(paused, Ordering::Relaxed);
     }
 }
 
@@ -71,6 +134,26 @@ impl Default for EventHandler {
     }
 }
 
+/// Events from a fixed list, for tests
+#[derive(Debug, Clone, Default)]
+pub struct ScriptedEvents {
+    events: VecDeque<Event>,
+}
+
+impl ScriptedEvents {
+    pub fn new(events: impl IntoIterator<Item = Event>) -> Self {
+        Self {
+            events: events.into_iter().collect(),
+        }
+    }
+}
+
+impl EventSource for ScriptedEvents {
+    async fn next(&mut self) -> Option<Event> {
+        self.events.pop_front()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -78,6 +161,35 @@ mod tests {
     #[tokio::test]
     async fn test_event_handler_creation() {
         let handler = EventHandler::new();
-        assert!(handler.sender.is_closed() == false);
+        assert!(!handler.sender.is_closed());
+    }
+
+    #[tokio::test]
+    async fn test_ticks_merge_while_one_waits() {
+        let mut handler = EventHandler::new();
+        assert!(send_tick(&handler.sender, &handler.tick_queued));
+        assert!(send_tick(&handler.sender, &handler.tick_queued));
+        handler.sender.send(Event::Resize(80, 24)).await.unwrap();
+        assert!(matches!(handler.next().await, Some(Event::Tick)));
+        assert!(matches!(handler.next().await, Some(Event::Resize(80, 24))));
+
+        // Once the tick is taken, the next one queues again
+        assert!(send_tick(&handler.sender, &handler.tick_queued));
+        assert!(matches!(handler.next().await, Some(Event::Tick)));
+
+        // A full channel drops ticks rather than waiting
+        for _ in 0..EVENT_BUFFER {
+            handler.sender.send(Event::Resize(80, 24)).await.unwrap();
+        }
+        assert!(send_tick(&handler.sender, &handler.tick_queued));
+        assert!(!handler.tick_queued.load(Ordering::Acquire));
+    }
+
+    #[tokio::test]
+    async fn test_scripted_events_run_out() {
+        let mut events = ScriptedEvents::new([Event::Tick, Event::Resize(80, 24)]);
+        assert!(matches!(events.next().await, Some(Event::Tick)));
+        assert!(matches!(events.next().await, Some(Event::Resize(80, 24))));
+        assert!(events.next().await.is_none());
     }
 }
@@ -0,0 +1,498 @@
+use crate::views::ViewType;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// A key press, ignoring event kind and the Shift modifier on characters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Key {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl Key {
+    /// A plain character key
+    pub const fn char(c: char) -> Self {
+        Self {
+            code: KeyCode::Char(c),
+            modifiers: KeyModifiers::NONE,
+        }
+    }
+
+    /// Convert back to a key event for dispatch to a view
+    pub fn to_event(self) -> KeyEvent {
+        KeyEvent::new(self.code, self.modifiers)
+    }
+
+    /// Parse a single key, e.g. "g", "SPC", "Enter" or "C-d"
+    pub fn parse(s: &str) -> Option<Self> {
+        let (modifiers, name) = if let Some(rest) = s.strip_prefix("C-") {
+            (KeyModifiers::CONTROL, rest)
+        } else if let Some(rest) = s.strip_prefix("M-") {
+            (KeyModifiers::ALT, rest)
+        } else {
+            (KeyModifiers::NONE, s)
+        };
+
+        let code = match name {
+            "SPC" | "Space" => KeyCode::Char(' '),
+            "RET" | "Enter" => KeyCode::Enter,
+            "ESC" | "Esc" => KeyCode::Esc,
+            "TAB" | "Tab" => KeyCode::Tab,
+            _ => {
+                let mut chars = name.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => KeyCode::Char(c),
+                    _ => return None,
+                }
+            }
+        };
+
+        Some(Self { code, modifiers })
+    }
+
+    /// Parse a space-separated key sequence, e.g. "SPC g p"
+    pub fn parse_sequence(s: &str) -> Option<Vec<Self>> {
+        let keys: Option<Vec<Self>> = s.split_whitespace().map(Self::parse).collect();
+        keys.filter(|k| !k.is_empty())
+    }
+}
+
+impl From<KeyEvent> for Key {
+    fn from(event: KeyEvent) -> Self {
+        let modifiers = match event.code {
+            // Shift is already reflected in the character itself
+            KeyCode::Char(_) => event.modifiers - KeyModifiers::SHIFT,
+            _ => event.modifiers,
+        };
+        Self {
+            code: event.code,
+            modifiers,
+        }
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "Ctrl+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "Alt+")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "Space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::Enter => write!(f, "Enter"),
+            KeyCode::Esc => write!(f, "Esc"),
+            KeyCode::Tab => write!(f, "Tab"),
+            KeyCode::Up => write!(f, "Up"),
+            KeyCode::Down => write!(f, "Down"),
+            KeyCode::PageUp => write!(f, "PageUp"),
+            KeyCode::PageDown => write!(f, "PageDown"),
+            code => write!(f, "{:?}", code),
+        }
+    }
+}
+
+/// A key sequence that resolves to a key the view already handles
+#[derive(Debug, Clone)]
+pub struct Binding {
+    /// View the binding applies to, or None for all views
+    pub view: Option<ViewType>,
+    pub keys: Vec<Key>,
+    pub target: Key,
+    pub description: &'static str,
+}
+
+/// Result of looking up a (partial) key sequence
+#[derive(Debug, Clone, PartialEq)]
+pub enum KeyMatch {
+    /// No binding starts with the sequence
+    None,
+    /// The sequence is the prefix of one or more bindings
+    Prefix,
+    /// The sequence completes a binding
+    Complete(Key),
+}
+
+/// Registry of multi-key bindings
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: Vec<Binding>,
+}
+
+impl Keymap {
+    /// Create a keymap with the default bindings
+    pub fn new() -> Self {
+        Self::empty()
+            .bind(None, "g g", "g", "Go to top")
+            .bind(None, "g e", "G", "Go to bottom")
+            .bind(Some(ViewType::Main), "g s", "s", "Go to status view")
+            .bind(Some(ViewType::Main), "g r", "r", "Go to refs view")
+            .bind(Some(ViewType::Main), "g b", "b", "Go to branch stack view")
+            .bind(Some(ViewType::Main), "g y", "y", "Go to stash view")
+    }
+
+    /// Create a keymap without any bindings
+    pub fn empty() -> Self {
+        Self {
+            bindings: Vec::new(),
+        }
+    }
+
+    /// Add a binding from a key sequence to a key, both in `Key::parse` notation
+    ///
+    /// Panics on malformed notation, since bindings are declared in code.
+    pub fn bind(
+        mut self,
+        view: Option<ViewType>,
+        keys: &str,
+        target: &str,
+        description: &'static str,
+    ) -> Self {
+        let keys =
+            Key::parse_sequence(keys).unwrap_or_else(|| panic!("invalid key sequence: {}", keys));
+        let target = Key::parse(target).unwrap_or_else(|| panic!("invalid key: {}", target));
+        self.bindings.push(Binding {
+            view,
+            keys,
+            target,
+            description,
+        });
+        self
+    }
+
+    /// Bindings that apply to a view
+    fn bindings_for(&self, view: ViewType) -> impl Iterator<Item = &Binding> {
+        self.bindings
+            .iter()
+            .filter(move |b| b.view.is_none_or(|v| v == view))
+    }
+
+    /// Look up a key sequence for a view
+    pub fn lookup(&self, view: ViewType, keys: &[Key]) -> KeyMatch {
+        let mut result = KeyMatch::None;
+        for binding in self.bindings_for(view) {
+            if binding.keys == keys {
+                return KeyMatch::Complete(binding.target);
+            }
+            if binding.keys.starts_with(keys) {
+                result = KeyMatch::Prefix;
+            }
+        }
+        result
+    }
+
+    /// List the keys that may follow a prefix, with what they do
+    pub fn continuations(&self, view: ViewType, prefix: &[Key]) -> Vec<(Key, &'static str)> {
+        self.bindings_for(view)
+            .filter(|b| b.keys.len() > prefix.len() && b.keys.starts_with(prefix))
+            .map(|b| {
+                let description = if b.keys.len() == prefix.len() + 1 {
+                    b.description
+                } else {
+                    "+prefix"
+                };
+                (b.keys[prefix.len()], description)
+            })
+            .fold(Vec::new(), |mut entries, (key, description)| {
+                if !entries.iter().any(|(k, _)| *k == key) {
+                    entries.push((key, description));
+                }
+                entries
+            })
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Largest accepted numeric count prefix
+const MAX_COUNT: usize = 9999;
+
+/// A resolved key press to pass to a view
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyPress {
+    pub key: KeyEvent,
+    /// Numeric prefix typed before the key (e.g. 10 for `10j`)
+    pub count: Option<usize>,
+}
+
+/// State machine that turns key presses into the keys views receive,
+/// holding back keys while they form a count or the prefix of a binding
+#[derive(Debug, Clone)]
+pub struct KeySequencer {
+    keymap: Keymap,
+    timeout: Duration,
+    pending: Vec<Key>,
+    count: Option<usize>,
+    last_key_at: Option<Instant>,
+}
+
+impl KeySequencer {
+    /// Create a sequencer that abandons a pending sequence after `timeout`
+    pub fn new(keymap: Keymap, timeout: Duration) -> Self {
+        Self {
+            keymap,
+            timeout,
+            pending: Vec::new(),
+            count: None,
+            last_key_at: None,
+        }
+    }
+
+    /// Get the keymap used to resolve sequences
+    pub fn keymap(&self) -> &Keymap {
+        &self.keymap
+    }
+
+    /// Get the keys of the sequence entered so far
+    pub fn pending(&self) -> &[Key] {
+        &self.pending
+    }
+
+    /// Get the numeric count entered so far
+    pub fn count(&self) -> Option<usize> {
+        self.count
+    }
+
+    /// Feed a key press
+    /// Returns the key to pass to the view, or None while a count or sequence is pending
+    pub fn feed(&mut self, view: ViewType, key: KeyEvent, now: Instant) -> Option<KeyPress> {
+        self.expire(now);
+
+        let pressed = Key::from(key);
+        if self.pending.is_empty()
+            && pressed.modifiers.is_empty()
+            && let KeyCode::Char(c) = pressed.code
+            && let Some(digit) = c.to_digit(10)
+            && (digit > 0 || self.count.is_some())
+        {
+            let count = self.count.unwrap_or(0) * 10 + digit as usize;
+            self.count = Some(count.min(MAX_COUNT));
+            return None;
+        }
+
+        // Esc abandons a count instead of reaching the view
+        if pressed.code == KeyCode::Esc && self.pending.is_empty() && self.count.is_some() {
+            self.cancel();
+            return None;
+        }
+
+        self.pending.push(pressed);
+        match self.keymap.lookup(view, &self.pending) {
+            KeyMatch::Prefix => {
+                self.last_key_at = Some(now);
+                None
+            }
+            KeyMatch::Complete(target) => {
+                let count = self.count;
+                self.cancel();
+                Some(KeyPress {
+                    key: target.to_event(),
+                    count,
+                })
+            }
+            KeyMatch::None => {
+                // A key that starts no binding goes straight to the view,
+                // one that breaks off a sequence just cancels it
+                let started = self.pending.len() > 1;
+                let count = self.count;
+                self.cancel();
+                if started {
+                    None
+                } else {
+                    Some(KeyPress { key, count })
+                }
+            }
+        }
+    }
+
+    /// Abandon a pending sequence whose timeout has passed
+    /// Returns true if a sequence was abandoned
+    pub fn expire(&mut self, now: Instant) -> bool {
+        let expired = self
+            .last_key_at
+            .is_some_and(|at| now.duration_since(at) >= self.timeout);
+        if expired {
+            self.cancel();
+        }
+        expired
+    }
+
+    /// Abandon any pending count and sequence
+    pub fn cancel(&mut self) {
+        self.pending.clear();
+        self.count = None;
+        self.last_key_at = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup_prefix_and_complete() {
+        let keymap = Keymap::new();
+        let g = Key::char('g');
+
+        assert_eq!(keymap.lookup(ViewType::Main, &[g]), KeyMatch::Prefix);
+        assert_eq!(
+            keymap.lookup(ViewType::Main, &[g, g]),
+            KeyMatch::Complete(g)
+        );
+        assert_eq!(
+            keymap.lookup(ViewType::Main, &[g, Key::char('x')]),
+            KeyMatch::None
+        );
+        assert_eq!(
+            keymap.lookup(ViewType::Main, &[Key::char('j')]),
+            KeyMatch::None
+        );
+
+        // View-specific bindings only apply to their view
+        let gs = [g, Key::char('s')];
+        assert_eq!(
+            keymap.lookup(ViewType::Main, &gs),
+            KeyMatch::Complete(Key::char('s'))
+        );
+        assert_eq!(keymap.lookup(ViewType::Diff, &gs), KeyMatch::None);
+    }
+
+    #[test]
+    fn test_parse_key_notation() {
+        assert_eq!(Key::parse("g"), Some(Key::char('g')));
+        assert_eq!(Key::parse("SPC"), Some(Key::char(' ')));
+        assert_eq!(
+            Key::parse("C-d"),
+            Some(Key::from(KeyEvent::new(
+                KeyCode::Char('d'),
+                KeyModifiers::CONTROL
+            )))
+        );
+        assert_eq!(Key::parse("Enter").map(|k| k.code), Some(KeyCode::Enter));
+        assert_eq!(Key::parse("gg"), None);
+
+        assert_eq!(
+            Key::parse_sequence("SPC g p"),
+            Some(vec![Key::char(' '), Key::char('g'), Key::char('p')])
+        );
+        assert_eq!(Key::parse_sequence(""), None);
+    }
+
+    fn press(c: char) -> KeyEvent {
+        KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE)
+    }
+
+    fn resolved(c: char, count: Option<usize>) -> KeyPress {
+        KeyPress {
+            key: press(c),
+            count,
+        }
+    }
+
+    #[test]
+    fn test_sequencer_count_prefix() {
+        let mut keys = KeySequencer::new(Keymap::new(), Duration::from_secs(1));
+        let now = Instant::now();
+
+        assert_eq!(keys.feed(ViewType::Main, press('1'), now), None);
+        assert_eq!(keys.feed(ViewType::Main, press('0'), now), None);
+        assert_eq!(keys.count(), Some(10));
+        assert_eq!(
+            keys.feed(ViewType::Main, press('j'), now),
+            Some(resolved('j', Some(10)))
+        );
+        assert_eq!(keys.count(), None);
+
+        // A count carries through a multi-key binding
+        keys.feed(ViewType::Main, press('3'), now);
+        keys.feed(ViewType::Main, press('g'), now);
+        assert_eq!(
+            keys.feed(ViewType::Main, press('e'), now),
+            Some(resolved('G', Some(3)))
+        );
+
+        // A leading zero is not a count, and Esc abandons one
+        assert_eq!(
+            keys.feed(ViewType::Main, press('0'), now),
+            Some(resolved('0', None))
+        );
+        keys.feed(ViewType::Main, press('5'), now);
+        let esc = KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE);
+        assert_eq!(keys.feed(ViewType::Main, esc, now), None);
+        assert_eq!(keys.count(), None);
+    }
+
+    #[test]
+    fn test_sequencer_long_sequence() {
+        let keymap = Keymap::empty().bind(None, "SPC g p", "s", "Status");
+        let mut keys = KeySequencer::new(keymap, Duration::from_secs(1));
+        let now = Instant::now();
+
+        assert_eq!(keys.feed(ViewType::Main, press(' '), now), None);
+        assert_eq!(keys.feed(ViewType::Main, press('g'), now), None);
+        assert_eq!(keys.pending().len(), 2);
+        assert_eq!(
+            keys.feed(ViewType::Main, press('p'), now),
+            Some(resolved('s', None))
+        );
+        assert!(keys.pending().is_empty());
+
+        // Unbound keys pass straight through
+        assert_eq!(
+            keys.feed(ViewType::Main, press('j'), now),
+            Some(resolved('j', None))
+        );
+
+        // Breaking off a sequence swallows the key
+        keys.feed(ViewType::Main, press(' '), now);
+        assert_eq!(keys.feed(ViewType::Main, press('x'), now), None);
+        assert!(keys.pending().is_empty());
+    }
+
+    #[test]
+    fn test_sequencer_timeout() {
+        let keymap = Keymap::new();
+        let mut keys = KeySequencer::new(keymap, Duration::from_millis(500));
+        let start = Instant::now();
+
+        keys.feed(ViewType::Main, press('g'), start);
+        assert!(!keys.expire(start + Duration::from_millis(100)));
+        assert_eq!(keys.pending().len(), 1);
+
+        // After the timeout the prefix is dropped and the next key starts fresh
+        let later = start + Duration::from_millis(600);
+        assert_eq!(
+            keys.feed(ViewType::Main, press('j'), later),
+            Some(resolved('j', None))
+        );
+        assert!(keys.pending().is_empty());
+    }
+
+    #[test]
+    fn test_continuations() {
+        let keymap = Keymap::new();
+        let main = keymap.continuations(ViewType::Main, &[Key::char('g')]);
+        assert_eq!(main.len(), 6);
+        assert_eq!(main[0], (Key::char('g'), "Go to top"));
+
+        let diff = keymap.continuations(ViewType::Diff, &[Key::char('g')]);
+        assert_eq!(diff.len(), 2);
+    }
+
+    #[test]
+    fn test_key_from_event_and_display() {
+        let shifted = KeyEvent::new(KeyCode::Char('G'), KeyModifiers::SHIFT);
+        assert_eq!(Key::from(shifted), Key::char('G'));
+
+        let ctrl_d = Key::from(KeyEvent::new(KeyCode::Char('d'), KeyModifiers::CONTROL));
+        assert_eq!(ctrl_d.to_string(), "Ctrl+d");
+        assert_eq!(Key::char(' ').to_string(), "Space");
+    }
+}
@@ -1,9 +1,19 @@
 // UI components and terminal management
 
 pub mod app;
+pub mod browser;
+pub mod command;
+pub mod credentials;
+pub mod editor;
 pub mod event;
+pub mod keymap;
+pub mod push_dialog;
+pub mod recording;
+pub mod start;
 pub mod terminal;
 
 pub use app::App;
-pub use event::{Event, EventHandler};
+pub use event::{Event, EventHandler, EventSource, ScriptedEvents};
+pub use recording::{Recorder, Replay};
+pub use start::StartView;
 pub use terminal::Tui;
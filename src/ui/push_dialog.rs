@@ -0,0 +1,235 @@
+use crate::config::Symbols;
+use crate::git::PushOptions;
+use crate::views::popup::centered_rect;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::Line,
+    widgets::{Clear, Paragraph},
+};
+
+/// A line of the push dialog
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Remote,
+    RemoteBranch,
+    SetUpstream,
+    ForceWithLease,
+    Tags,
+    Force,
+}
+
+/// What a key did to the dialog
+pub enum PushChoice {
+    /// The dialog stays open
+    Open(PushDialog),
+    Cancel,
+    Push(PushOptions),
+}
+
+/// Lets the user adjust where and how to push before pushing
+pub struct PushDialog {
+    options: PushOptions,
+    fields: Vec<Field>,
+    focus: usize,
+}
+
+impl PushDialog {
+    /// Open the dialog on the defaults, offering a plain force push only
+    /// when `allow_force` is set
+    pub fn new(options: PushOptions, allow_force: bool) -> Self {
+        let mut fields = vec![
+            Field::Remote,
+            Field::RemoteBranch,
+            Field::SetUpstream,
+            Field::ForceWithLease,
+            Field::Tags,
+        ];
+        if allow_force {
+            fields.push(Field::Force);
+        }
+        Self {
+            options: PushOptions {
+                force: false,
+                ..options
+            },
+            fields,
+            focus: 0,
+        }
+    }
+
+    /// Handle a key: Tab and the arrows move between lines, typing edits
+    /// the remote and branch, Space toggles, Enter pushes and Esc cancels
+    pub fn handle_key(mut self, key: KeyEvent) -> PushChoice {
+        let field = self.fields[self.focus];
+        match key.code {
+            KeyCode::Esc => return PushChoice::Cancel,
+            KeyCode::Enter
+                if !self.options.remote.is_empty() && !self.options.remote_branch.is_empty() =>
+            {
+                return PushChoice::Push(self.options);
+            }
+            KeyCode::Tab | KeyCode::Down => self.focus = (self.focus + 1) % self.fields.len(),
+            KeyCode::BackTab | KeyCode::Up => {
+                self.focus = (self.focus + self.fields.len() - 1) % self.fields.len()
+            }
+            KeyCode::Char(' ') => {
+                if let Some(flag) = self.flag(field) {
+                    *flag = !*flag;
+                }
+            }
+            KeyCode::Char(c) => {
+                if let Some(text) = self.text(field) {
+                    text.push(c);
+                }
+            }
+            KeyCode::Backspace => {
+                if let Some(text) = self.text(field) {
+                    text.pop();
+                }
+            }
+            _ => {}
+        }
+        PushChoice::Open(self)
+    }
+
+    fn text(&mut self, field: Field) -> Option<&mut String> {
+        match field {
+            Field::Remote => Some(&mut self.options.remote),
+            Field::RemoteBranch => Some(&mut self.options.remote_branch),
+            _ => None,
+        }
+    }
+
+    fn flag(&mut self, field: Field) -> Option<&mut bool> {
+        match field {
+            Field::SetUpstream => Some(&mut self.options.set_upstream),
+            Field::ForceWithLease => Some(&mut self.options.force_with_lease),
+            Field::Tags => Some(&mut self.options.tags),
+            Field::Force => Some(&mut self.options.force),
+            _ => None,
+        }
+    }
+
+    /// Render the dialog centered in `area`
+    pub fn draw(&self, frame: &mut Frame, area: Rect, symbols: &Symbols) {
+        let options = &self.options;
+        let check = |on: bool| if on { "[x]" } else { "[ ]" };
+        let mut lines = vec![
+            Line::from(format!(" Push {} ", options.branch)),
+            Line::from(""),
+        ];
+        for (i, field) in self.fields.iter().enumerate() {
+            let focused = i == self.focus;
+            let cursor = if focused { "_" } else { "" };
+            let text = match field {
+                Field::Remote => format!("Remote:        {}{}", options.remote, cursor),
+                Field::RemoteBranch => {
+                    format!("Remote branch: {}{}", options.remote_branch, cursor)
+                }
+                Field::SetUpstream => format!("{} Set as upstream", check(options.set_upstream)),
+                Field::ForceWithLease => {
+                    format!("{} Force with lease", check(options.force_with_lease))
+                }
+                Field::Tags => format!("{} Include tags", check(options.tags)),
+                Field::Force => {
+                    format!("{} Force (overwrites unseen commits)", check(options.force))
+                }
+            };
+            let style = match focused {
+                true => Style::default().add_modifier(Modifier::REVERSED),
+                false => Style::default(),
+            };
+            lines.push(Line::styled(format!(" {} ", text), style));
+        }
+        lines.push(Line::from(""));
+        lines.push(Line::from(
+            " Tab next, Space toggle, Enter push, Esc cancel ",
+        ));
+
+        let width = lines.iter().map(Line::width).max().unwrap_or(0).max(40) as u16 + 2;
+        let popup = centered_rect(width, lines.len() as u16 + 2, area);
+        frame.render_widget(Clear, popup);
+        frame.render_widget(
+            Paragraph::new(lines).block(symbols.block().title(" Push ")),
+            popup,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn defaults() -> PushOptions {
+        PushOptions {
+            remote: "origin".to_string(),
+            branch: "main".to_string(),
+            remote_branch: "main".to_string(),
+            set_upstream: true,
+            force_with_lease: false,
+            force: false,
+            tags: false,
+        }
+    }
+
+    fn type_keys(mut dialog: PushDialog, keys: &[KeyCode]) -> PushChoice {
+        for key in keys {
+            match dialog.handle_key(KeyEvent::from(*key)) {
+                PushChoice::Open(open) => dialog = open,
+                done => return done,
+            }
+        }
+        PushChoice::Open(dialog)
+    }
+
+    #[test]
+    fn test_edit_and_toggle() {
+        let dialog = PushDialog::new(defaults(), false);
+        let keys = [
+            KeyCode::Tab,
+            KeyCode::Backspace,
+            KeyCode::Backspace,
+            KeyCode::Backspace,
+            KeyCode::Backspace,
+            KeyCode::Char('d'),
+            KeyCode::Char('e'),
+            KeyCode::Char('v'),
+            KeyCode::Down,
+            KeyCode::Char(' '),
+            KeyCode::Down,
+            KeyCode::Char(' '),
+            KeyCode::Enter,
+        ];
+        let PushChoice::Push(options) = type_keys(dialog, &keys) else {
+            panic!("expected a push");
+        };
+        assert_eq!(options.remote_branch, "dev");
+        assert!(!options.set_upstream);
+        assert!(options.force_with_lease);
+        assert!(!options.force);
+    }
+
+    #[test]
+    fn test_force_only_when_allowed() {
+        let up = [KeyCode::Up, KeyCode::Char(' '), KeyCode::Enter];
+        let PushChoice::Push(options) = type_keys(PushDialog::new(defaults(), false), &up) else {
+            panic!("expected a push");
+        };
+        // Up wraps to "Include tags" without the force line
+        assert!(!options.force);
+        assert!(options.tags);
+
+        let PushChoice::Push(options) = type_keys(PushDialog::new(defaults(), true), &up) else {
+            panic!("expected a push");
+        };
+        assert!(options.force);
+
+        assert!(matches!(
+            type_keys(PushDialog::new(defaults(), true), &[KeyCode::Esc]),
+            PushChoice::Cancel
+        ));
+    }
+}
@@ -0,0 +1,296 @@
+use super::event::{Event, EventSource};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// How often a replay sends ticks while waiting for the next recorded event
+const REPLAY_TICK: Duration = Duration::from_millis(100);
+
+/// Written in place of characters typed into a prompt, so a replay still
+/// types the same number of them
+const REDACTED_CHAR: char = '*';
+
+/// Encode an event as a line of a session file, e.g. "1520 key 2 c:d"
+///
+/// Ticks and mouse events are not recorded: the app ignores mouse input and
+/// replays generate their own ticks.
+pub fn encode(elapsed: Duration, event: &Event) -> Option<String> {
+    let ms = elapsed.as_millis();
+    match event {
+        Event::Key(key) => Some(format!(
+            "{} key {} {}",
+            ms,
+            key.modifiers.bits(),
+            encode_key_code(key.code)?
+        )),
+        Event::Resize(width, height) => Some(format!("{} resize {} {}", ms, width, height)),
+        Event::Tick | Event::Mouse(_) => None,
+    }
+}
+
+/// Decode a line written by `encode`
+pub fn decode(line: &str) -> Option<(Duration, Event)> {
+    // The key code comes last so a space character survives the split
+    let mut parts = line.splitn(4, ' ');
+    let ms: u64 = parts.next()?.parse().ok()?;
+    let event = match parts.next()? {
+        "key" => {
+            let modifiers = KeyModifiers::from_bits(parts.next()?.parse().ok()?)?;
+            let code = decode_key_code(parts.next()?)?;
+            Event::Key(KeyEvent::new(code, modifiers))
+        }
+        "resize" => Event::Resize(parts.next()?.parse().ok()?, parts.next()?.parse().ok()?),
+        _ => return None,
+    };
+    Some((Duration::from_millis(ms), event))
+}
+
+fn encode_key_code(code: KeyCode) -> Option<String> {
+    let name = match code {
+        KeyCode::Char(c) => return Some(format!("c:{}", c)),
+        KeyCode::F(n) => return Some(format!("f:{}", n)),
+        KeyCode::Enter => "Enter",
+        KeyCode::Esc => "Esc",
+        KeyCode::Tab => "Tab",
+        KeyCode::BackTab => "BackTab",
+        KeyCode::Backspace => "Backspace",
+        KeyCode::Delete => "Delete",
+        KeyCode::Insert => "Insert",
+        KeyCode::Up => "Up",
+        KeyCode::Down => "Down",
+        KeyCode::Left => "Left",
+        KeyCode::Right => "Right",
+        KeyCode::Home => "Home",
+        KeyCode::End => "End",
+        KeyCode::PageUp => "PageUp",
+        KeyCode::PageDown => "PageDown",
+        _ => return None,
+    };
+    Some(name.to_string())
+}
+
+fn decode_key_code(s: &str) -> Option<KeyCode> {
+    if let Some(c) = s.strip_prefix("c:") {
+        let mut chars = c.chars();
+        return match (chars.next(), chars.next()) {
+            (Some(c), None) => Some(KeyCode::Char(c)),
+            _ => None,
+        };
+    }
+    if let Some(n) = s.strip_prefix("f:") {
+        return n.parse().ok().map(KeyCode::F);
+    }
+    let code = match s {
+        "Enter" => KeyCode::Enter,
+        "Esc" => KeyCode::Esc,
+        "Tab" => KeyCode::Tab,
+        "BackTab" => KeyCode::BackTab,
+        "Backspace" => KeyCode::Backspace,
+        "Delete" => KeyCode::Delete,
+        "Insert" => KeyCode::Insert,
+        "Up" => KeyCode::Up,
+        "Down" => KeyCode::Down,
+        "Left" => KeyCode::Left,
+        "Right" => KeyCode::Right,
+        "Home" => KeyCode::Home,
+        "End" => KeyCode::End,
+        "PageUp" => KeyCode::PageUp,
+        "PageDown" => KeyCode::PageDown,
+        _ => return None,
+    };
+    Some(code)
+}
+
+/// Hide the text of a key typed into a prompt, keeping keys like Enter and
+/// Ctrl-C that move through it
+fn redact(event: &Event) -> Event {
+    match event {
+        Event::Key(key)
+            if matches!(key.code, KeyCode::Char(_))
+                && !key
+                    .modifiers
+                    .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) =>
+        {
+            Event::Key(KeyEvent::new(
+                KeyCode::Char(REDACTED_CHAR),
+                KeyModifiers::NONE,
+            ))
+        }
+        event => event.clone(),
+    }
+}
+
+/// Passes events through from another source, writing them to a session file
+pub struct Recorder<S> {
+    source: S,
+    writer: BufWriter<File>,
+    start: Instant,
+    /// Set while keys go into a prompt
+    private: bool,
+}
+
+impl<S: EventSource + Send> Recorder<S> {
+    /// Record the events of `source` to a new file at `path`
+    pub fn new(source: S, path: &Path) -> io::Result<Self> {
+        Ok(Self {
+            source,
+            writer: BufWriter::new(File::create(path)?),
+            start: Instant::now(),
+            private: false,
+        })
+    }
+}
+
+impl<S: EventSource + Send> EventSource for Recorder<S> {
+    async fn next(&mut self) -> Option<Event> {
+        let event = self.source.next().await?;
+        let recorded = match self.private {
+            true => redact(&event),
+            false => event.clone(),
+        };
+        if let Some(line) = encode(self.start.elapsed(), &recorded) {
+            // Flush every line so the session survives a crash
+            let _ = writeln!(self.writer, "{}", line).and_then(|_| self.writer.flush());
+        }
+        Some(event)
+    }
+
+    fn set_paused(&mut self, paused: bool) {
+        self.source.set_paused(paused);
+    }
+
+    fn set_private(&mut self, private: bool) {
+        self.private = private;
+        self.source.set_private(private);
+    }
+}
+
+/// Plays back a recorded session at its original pace
+pub struct Replay {
+    events: std::vec::IntoIter<(Duration, Event)>,
+    pending: Option<(Duration, Event)>,
+    start: Instant,
+}
+
+impl Replay {
+    /// Load a session file, skipping lines that don't parse
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        Ok(Self::new(content.lines().filter_map(decode).collect()))
+    }
+
+    /// Replay `events`, each at its offset from the start of the replay
+    pub fn new(events: Vec<(Duration, Event)>) -> Self {
+        Self {
+            events: events.into_iter(),
+            pending: None,
+            start: Instant::now(),
+        }
+    }
+}
+
+impl EventSource for Replay {
+    async fn next(&mut self) -> Option<Event> {
+        let (at, event) = match self.pending.take() {
+            Some(pending) => pending,
+            None => self.events.next()?,
+        };
+
+        let elapsed = self.start.elapsed();
+        if at <= elapsed {
+            return Some(event);
+        }
+
+        // Keep the app ticking while waiting, like the terminal does
+        tokio::time::sleep((at - elapsed).min(REPLAY_TICK)).await;
+        self.pending = Some((at, event));
+        Some(Event::Tick)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::event::ScriptedEvents;
+    use tempfile::TempDir;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> Event {
+        Event::Key(KeyEvent::new(code, modifiers))
+    }
+
+    #[test]
+    fn test_encode_decode_round_trip() {
+        let events = [
+            key(KeyCode::Char('j'), KeyModifiers::NONE),
+            key(KeyCode::Char(' '), KeyModifiers::NONE),
+            key(KeyCode::Char('d'), KeyModifiers::CONTROL),
+            key(KeyCode::F(5), KeyModifiers::NONE),
+            key(KeyCode::PageDown, KeyModifiers::SHIFT),
+            Event::Resize(120, 40),
+        ];
+        for event in events {
+            let line = encode(Duration::from_millis(1520), &event).unwrap();
+            let (at, decoded) = decode(&line).unwrap();
+            assert_eq!(at, Duration::from_millis(1520));
+            assert_eq!(format!("{:?}", decoded), format!("{:?}", event));
+        }
+
+        assert!(encode(Duration::ZERO, &Event::Tick).is_none());
+        assert!(decode("not an event").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_record_then_replay() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("session.log");
+
+        let script = ScriptedEvents::new([
+            key(KeyCode::Char('j'), KeyModifiers::NONE),
+            Event::Tick,
+            key(KeyCode::Enter, KeyModifiers::NONE),
+        ]);
+        let mut recorder = Recorder::new(script, &path).unwrap();
+        while recorder.next().await.is_some() {}
+        drop(recorder);
+
+        let mut replay = Replay::load(&path).unwrap();
+        let mut keys = Vec::new();
+        while let Some(event) = replay.next().await {
+            if let Event::Key(key) = event {
+                keys.push(key.code);
+            }
+        }
+        assert_eq!(keys, [KeyCode::Char('j'), KeyCode::Enter]);
+    }
+
+    #[tokio::test]
+    async fn test_prompt_keys_are_redacted() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("session.log");
+
+        let script = ScriptedEvents::new([
+            key(KeyCode::Char('s'), KeyModifiers::NONE),
+            key(KeyCode::Char('P'), KeyModifiers::SHIFT),
+            key(KeyCode::Char('c'), KeyModifiers::CONTROL),
+            key(KeyCode::Enter, KeyModifiers::NONE),
+        ]);
+        let mut recorder = Recorder::new(script, &path).unwrap();
+        recorder.set_private(true);
+        // The app still gets the real keys
+        assert_eq!(
+            format!("{:?}", recorder.next().await.unwrap()),
+            format!("{:?}", key(KeyCode::Char('s'), KeyModifiers::NONE))
+        );
+        while recorder.next().await.is_some() {}
+        drop(recorder);
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        let keys: Vec<&str> = content
+            .lines()
+            .map(|line| line.split_once(" key ").unwrap().1)
+            .collect();
+        assert_eq!(keys, ["0 c:*", "0 c:*", "2 c:c", "0 Enter"]);
+    }
+}
@@ -0,0 +1,188 @@
+use git2::Oid;
+use std::path::{Path, PathBuf};
+
+/// View to open at startup, on top of the main view
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum StartView {
+    /// Just the commit history
+    #[default]
+    Main,
+    Status,
+    Refs,
+    /// The diff of a revision, as in `rust-tig show HEAD~3`
+    Show(String),
+    /// Blame of a file, as in `rust-tig blame src/main.rs +120`
+    Blame {
+        path: String,
+        line: Option<usize>,
+    },
+}
+
+/// What the command line asks to start with
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct StartArgs {
+    pub view: StartView,
+    /// Directory to look for the repository from, instead of the current
+    /// one, as in `rust-tig ~/src/project`
+    pub path: Option<PathBuf>,
+}
+
+/// Flags followed by a value that is not a start target
+const VALUE_FLAGS: [&str; 2] = ["--record", "--replay"];
+
+/// Work out the start view and repository from the command line
+/// arguments, without the program name
+///
+/// Accepts `--view=status`, `--view=refs` and `--view=main`, or a
+/// subcommand: `show <rev>` or `blame <path> [+line]`, optionally after
+/// the path of a directory in the repository. Other flags are left for the
+/// caller.
+pub fn parse_args(args: &[String]) -> Result<StartArgs, String> {
+    let mut view = None;
+    let mut words = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if VALUE_FLAGS.contains(&arg.as_str()) {
+            args.next();
+        } else if let Some(name) = arg.strip_prefix("--view=") {
+            view = Some(match name {
+                "main" => StartView::Main,
+                "status" => StartView::Status,
+                "refs" => StartView::Refs,
+                _ => {
+                    return Err(format!(
+                        "Unknown view: {} (expected main, status or refs)",
+                        name
+                    ));
+                }
+            });
+        } else if !arg.starts_with("--") {
+            words.push(arg.as_str());
+        }
+    }
+
+    // A first word naming a directory is where the repository is, unless
+    // it is a subcommand
+    let path = match words.first() {
+        Some(&word) if !["show", "blame"].contains(&word) && Path::new(word).is_dir() => {
+            words.remove(0);
+            Some(PathBuf::from(word))
+        }
+        _ => None,
+    };
+
+    let command = match words.as_slice() {
+        [] => None,
+        ["show"] => Some(StartView::Show("HEAD".to_string())),
+        ["show", revision] => Some(StartView::Show(revision.to_string())),
+        ["blame", path] => Some(StartView::Blame {
+            path: path.to_string(),
+            line: None,
+        }),
+        ["blame", path, line] => {
+            let line = line
+                .strip_prefix('+')
+                .and_then(|n| n.parse().ok())
+                .filter(|&n| n > 0)
+                .ok_or_else(|| format!("Expected a line like +120, got {}", line))?;
+            Some(StartView::Blame {
+                path: path.to_string(),
+                line: Some(line),
+            })
+        }
+        ["show", ..] => return Err("Usage: rust-tig show [<rev>]".to_string()),
+        ["blame", ..] => return Err("Usage: rust-tig blame <path> [+<line>]".to_string()),
+        [word, ..] => return Err(format!("Unknown command: {}", word)),
+    };
+
+    match (view, command) {
+        (Some(_), Some(_)) => Err("--view can't be combined with show or blame".to_string()),
+        (view, command) => Ok(StartArgs {
+            view: command.or(view).unwrap_or_default(),
+            path,
+        }),
+    }
+}
+
+/// Read the commit ids piped in with `--stdin`, in order: the first word of
+/// each line that is a full hash, as `git rev-list` and
+/// `git log --format=%H` print them
+pub fn parse_revision_list(text: &str) -> Vec<Oid> {
+    text.lines()
+        .filter_map(|line| line.split_whitespace().next())
+        .filter(|word| word.len() == 40 && word.bytes().all(|b| b.is_ascii_hexdigit()))
+        .filter_map(|word| Oid::from_str(word).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<StartView, String> {
+        parse_args(&args.iter().map(|a| a.to_string()).collect::<Vec<_>>()).map(|start| start.view)
+    }
+
+    #[test]
+    fn test_views_and_commands() {
+        assert_eq!(parse(&[]), Ok(StartView::Main));
+        assert_eq!(
+            parse(&["--view=status", "--no-color"]),
+            Ok(StartView::Status)
+        );
+        assert_eq!(parse(&["--view=refs"]), Ok(StartView::Refs));
+        assert_eq!(
+            parse(&["show", "HEAD~3"]),
+            Ok(StartView::Show("HEAD~3".to_string()))
+        );
+        assert_eq!(parse(&["show"]), Ok(StartView::Show("HEAD".to_string())));
+        assert_eq!(
+            parse(&["--record", "session.log", "blame", "src/main.rs", "+120"]),
+            Ok(StartView::Blame {
+                path: "src/main.rs".to_string(),
+                line: Some(120)
+            })
+        );
+    }
+
+    #[test]
+    fn test_repository_path() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().to_str().unwrap();
+        let start =
+            parse_args(&[path.to_string(), "show".to_string(), "v1.0".to_string()]).unwrap();
+        assert_eq!(start.path.as_deref(), Some(dir.path()));
+        assert_eq!(start.view, StartView::Show("v1.0".to_string()));
+        assert_eq!(parse(&[path]), Ok(StartView::Main));
+        assert_eq!(parse_args(&[]).unwrap().path, None);
+
+        // A file is no repository path, nor a command
+        let file = dir.path().join("a.txt");
+        std::fs::write(&file, "a").unwrap();
+        let file = file.to_str().unwrap();
+        assert_eq!(parse(&[file]), Err(format!("Unknown command: {}", file)));
+    }
+
+    #[test]
+    fn test_revision_list() {
+        let a = "0123456789abcdef0123456789abcdef01234567";
+        let b = "89abcdef0123456789abcdef0123456789abcdef";
+        let text = format!("{}\n\n{} {}\nabc1234 short\nnot a hash\n", b, a, b);
+        let ids = parse_revision_list(&text);
+        assert_eq!(ids, [Oid::from_str(b).unwrap(), Oid::from_str(a).unwrap()]);
+    }
+
+    #[test]
+    fn test_bad_arguments() {
+        assert!(parse(&["--view=log"]).unwrap_err().contains("Unknown view"));
+        assert!(parse(&["blame"]).unwrap_err().starts_with("Usage"));
+        assert!(
+            parse(&["blame", "a.rs", "120"])
+                .unwrap_err()
+                .contains("+120")
+        );
+        assert!(parse(&["blame", "a.rs", "+0"]).is_err());
+        assert_eq!(parse(&["log"]), Err("Unknown command: log".to_string()));
+        assert!(parse(&["--view=refs", "show"]).is_err());
+    }
+}
@@ -1,10 +1,10 @@
 use anyhow::Result;
 use crossterm::{
     execute,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+    terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
 };
-use ratatui::{backend::CrosstermBackend, Terminal};
-use std::io;
+use ratatui::{Terminal, backend::CrosstermBackend};
+use std::io::{self, Write};
 
 pub type Tui = Terminal<CrosstermBackend<io::Stdout>>;
 
@@ -18,6 +18,15 @@ pub fn init() -> Result<Tui> {
     Ok(terminal)
 }
 
+/// Take the terminal back after handing it to another program, redrawing
+/// everything on the next frame
+pub fn resume(terminal: &mut Tui) -> Result<()> {
+    enable_raw_mode()?;
+    execute!(io::stdout(), EnterAlternateScreen)?;
+    terminal.clear()?;
+    Ok(())
+}
+
 /// Restore the terminal to normal mode
 pub fn restore() -> Result<()> {
     disable_raw_mode()?;
@@ -25,6 +34,19 @@ pub fn restore() -> Result<()> {
     Ok(())
 }
 
+/// Put text on the clipboard with the OSC 52 escape sequence, which most
+/// terminals honor, over SSH too
+pub fn copy_to_clipboard(text: &str) -> Result<()> {
+    let mut stdout = io::stdout();
+    write!(
+        stdout,
+        "\x1b]52;c;{}\x07",
+        crate::git::ssh::base64(text.as_bytes())
+    )?;
+    stdout.flush()?;
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
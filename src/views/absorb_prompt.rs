@@ -0,0 +1,164 @@
+use super::popup::centered_rect;
+use crate::config::{ColorScheme, Symbols};
+use crate::error::{AppError, AppResult};
+use crate::git::{AbsorbPlan, Repository, Result as GitResult};
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::Style,
+    text::{Line, Span},
+    widgets::{Clear, Paragraph},
+};
+use tokio::sync::mpsc;
+
+/// Matches the staged hunks to earlier commits and asks before turning
+/// them into fixup commits
+#[derive(Default)]
+pub struct AbsorbPrompt {
+    receiver: Option<mpsc::UnboundedReceiver<GitResult<AbsorbPlan>>>,
+    /// The plan waiting on the user
+    plan: Option<AbsorbPlan>,
+}
+
+impl AbsorbPrompt {
+    /// Start matching the staged hunks in the background
+    pub fn start(&mut self, repo: &Repository) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.receiver = Some(rx);
+        let repo = repo.clone();
+        tokio::spawn(async move {
+            let _ = tx.send(crate::git::absorb::plan_absorb(&repo).await);
+        });
+    }
+
+    /// Pick up the plan once it's made, failing when no hunk has a target
+    pub fn update(&mut self) -> AppResult<()> {
+        let Some(receiver) = &mut self.receiver else {
+            return Ok(());
+        };
+        let Ok(plan) = receiver.try_recv() else {
+            return Ok(());
+        };
+        self.receiver = None;
+        let plan = plan?;
+        if plan.hunks.is_empty() {
+            return Err(AppError::recoverable("Nothing staged to absorb"));
+        }
+        if plan.targets().is_empty() {
+            return Err(AppError::recoverable(
+                "No staged hunk belongs to a single earlier commit",
+            ));
+        }
+        self.plan = Some(plan);
+        Ok(())
+    }
+
+    /// Check whether the plan is waiting on the user
+    pub fn is_confirming(&self) -> bool {
+        self.plan.is_some()
+    }
+
+    /// Check whether the staged hunks are being matched
+    pub fn is_planning(&self) -> bool {
+        self.receiver.is_some()
+    }
+
+    /// Answer the prompt: `y` returns the plan to carry out, `n` or Esc
+    /// drops it
+    pub fn handle_key(&mut self, key: KeyEvent) -> Option<AbsorbPlan> {
+        match key.code {
+            KeyCode::Char('y') => self.plan.take(),
+            KeyCode::Char('n') | KeyCode::Esc => {
+                self.plan = None;
+                None
+            }
+            _ => None,
+        }
+    }
+
+    /// Render each hunk with its target centered in `area`
+    pub fn draw(&self, frame: &mut Frame, area: Rect, colors: &ColorScheme, symbols: &Symbols) {
+        let Some(plan) = &self.plan else {
+            return;
+        };
+        let mut lines: Vec<Line> = plan
+            .hunks
+            .iter()
+            .map(|hunk| {
+                let target = match hunk.target.and_then(|id| plan.commits.get(&id)) {
+                    Some(commit) => Span::styled(
+                        format!("{} {}", commit.short_id, commit.summary),
+                        Style::default().fg(colors.commit_hash),
+                    ),
+                    None => Span::raw("stays staged"),
+                };
+                Line::from(vec![
+                    Span::raw(format!(" {} {} {} ", hunk.path, hunk.header, symbols.arrow)),
+                    target,
+                    Span::raw(" "),
+                ])
+            })
+            .collect();
+        lines.push(Line::from(""));
+        lines.push(Line::from(" y commit fixups, n cancel "));
+
+        let width = lines.iter().map(Line::width).max().unwrap_or(0) as u16 + 2;
+        let popup = centered_rect(width, lines.len() as u16 + 2, area);
+        frame.render_widget(Clear, popup);
+        frame.render_widget(
+            Paragraph::new(lines).block(symbols.block().title("Absorb into fixup commits?")),
+            popup,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::RepoBuilder;
+
+    #[tokio::test]
+    async fn test_plan_waits_for_confirmation() {
+        let (_temp_dir, repo) = RepoBuilder::new()
+            .file("a.txt", "one\n")
+            .commit("Add a")
+            .file("a.txt", "ONE\n")
+            .open()
+            .await;
+
+        let mut prompt = AbsorbPrompt::default();
+        prompt.start(&repo);
+        assert!(prompt.is_planning());
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        prompt.update().unwrap();
+        assert!(prompt.is_confirming());
+
+        assert!(
+            prompt
+                .handle_key(KeyEvent::from(KeyCode::Char('x')))
+                .is_none()
+        );
+        let plan = prompt
+            .handle_key(KeyEvent::from(KeyCode::Char('y')))
+            .unwrap();
+        assert_eq!(plan.hunks.len(), 1);
+        assert!(!prompt.is_confirming());
+    }
+
+    #[tokio::test]
+    async fn test_nothing_to_absorb() {
+        let (_temp_dir, repo) = RepoBuilder::new()
+            .file("a.txt", "one\n")
+            .commit("Add a")
+            .file("b.txt", "new\n")
+            .open()
+            .await;
+
+        let mut prompt = AbsorbPrompt::default();
+        prompt.start(&repo);
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        assert!(prompt.update().is_err());
+        assert!(!prompt.is_confirming());
+    }
+}
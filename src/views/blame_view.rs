@@ -0,0 +1,485 @@
+use super::scrollbar::render_scrollbar;
+use super::title::render_title;
+use super::view::{Action, Selection, View, ViewType};
+use crate::config::{ColorScheme, Symbols, TitleTemplates, author_color, gradient_color};
+use crate::error::{AppError, AppResult};
+use crate::git::{Blame, BlameLine, Repository};
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use git2::Oid;
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{List, ListItem, ListState, Paragraph},
+};
+use tokio::sync::mpsc;
+
+/// Width of the author name in the gutter
+const AUTHOR_WIDTH: usize = 12;
+
+/// A blame left for one at an older revision, to go back to
+struct BlameState {
+    path: String,
+    revision: Option<Oid>,
+    blame: Blame,
+    list_state: ListState,
+}
+
+/// Blame view showing who last changed each line of a file
+pub struct BlameView {
+    repo: Repository,
+    path: String,
+    /// Commit to blame at; HEAD when None
+    revision: Option<Oid>,
+    blame: Option<Blame>,
+    list_state: ListState,
+    loading: bool,
+    error: Option<String>,
+    receiver: Option<mpsc::UnboundedReceiver<Result<Blame>>>,
+    /// Gutter colors from the file's oldest commit to its newest
+    heat: Vec<Color>,
+    author_colors: bool,
+    colors: ColorScheme,
+    symbols: Symbols,
+    /// Template of the title, filled in by `render_title`
+    title_template: String,
+    /// Blames left by going back through a line's history, newest last
+    history: Vec<BlameState>,
+}
+
+impl BlameView {
+    /// Create a blame view of `path` as of `revision`, or HEAD
+    pub fn new(repo: Repository, path: String, revision: Option<Oid>, colors: ColorScheme) -> Self {
+        let mut list_state = ListState::default();
+        list_state.select(Some(0));
+
+        Self {
+            repo,
+            path,
+            revision,
+            blame: None,
+            list_state,
+            loading: false,
+            error: None,
+            receiver: None,
+            heat: Vec::new(),
+            author_colors: false,
+            colors,
+            symbols: Symbols::default(),
+            title_template: TitleTemplates::default().blame,
+            history: Vec::new(),
+        }
+    }
+
+    /// Set the glyphs used for borders and the scrollbar
+    pub fn with_symbols(mut self, symbols: Symbols) -> Self {
+        self.symbols = symbols;
+        self
+    }
+
+    /// Set the template of the title
+    pub fn with_title_template(mut self, title_template: String) -> Self {
+        self.title_template = title_template;
+        self
+    }
+
+    /// Color the gutter by commit age along these colors, oldest first
+    pub fn with_heat(mut self, heat: Vec<Color>) -> Self {
+        self.heat = heat;
+        self
+    }
+
+    /// Select a line once the blame loads, counting from 1
+    pub fn with_line(mut self, line: usize) -> Self {
+        self.list_state.select(Some(line.saturating_sub(1)));
+        self
+    }
+
+    /// Give each author a stable color of their own
+    pub fn with_author_colors(mut self, author_colors: bool) -> Self {
+        self.author_colors = author_colors;
+        self
+    }
+
+    /// Start blaming the file asynchronously
+    pub fn start_loading(&mut self) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.receiver = Some(rx);
+        self.loading = true;
+
+        let repo = self.repo.clone();
+        let path = self.path.clone();
+        let revision = self.revision;
+
+        tokio::spawn(async move {
+            let result = crate::git::blame::load_blame(&repo, path, revision)
+                .await
+                .map_err(|e| anyhow::anyhow!(e));
+            let _ = tx.send(result);
+        });
+    }
+
+    /// Get the line under the cursor
+    fn selected_line(&self) -> Option<&BlameLine> {
+        let blame = self.blame.as_ref()?;
+        self.list_state.selected().and_then(|i| blame.lines.get(i))
+    }
+
+    fn line_count(&self) -> usize {
+        self.blame.as_ref().map_or(0, |b| b.lines.len())
+    }
+
+    fn select_down(&mut self, amount: usize) {
+        let last = self.line_count().saturating_sub(1);
+        let selected = self.list_state.selected().unwrap_or(0);
+        self.list_state.select(Some((selected + amount).min(last)));
+    }
+
+    fn select_up(&mut self, amount: usize) {
+        let selected = self.list_state.selected().unwrap_or(0);
+        self.list_state
+            .select(Some(selected.saturating_sub(amount)));
+    }
+
+    /// Blame the file again as of the parent of the commit that last changed
+    /// the selected line, at the line it came from, like tig's `,`
+    fn blame_parent(&mut self) -> AppResult<()> {
+        let (Some(blame), Some(line)) = (&self.blame, self.selected_line()) else {
+            return Ok(());
+        };
+        let Some(parent) = blame.parent(line) else {
+            let short_id = format!("{:.7}", line.commit_id.to_string());
+            return Err(AppError::recoverable(format!(
+                "{} has no parent to blame",
+                short_id
+            )));
+        };
+        let path = line.orig_path.clone();
+        let mut list_state = ListState::default();
+        list_state.select(Some(line.orig_line_no.saturating_sub(1)));
+
+        if let Some(blame) = self.blame.take() {
+            self.history.push(BlameState {
+                path: std::mem::replace(&mut self.path, path),
+                revision: self.revision.replace(parent),
+                blame,
+                list_state: std::mem::replace(&mut self.list_state, list_state),
+            });
+        }
+        self.error = None;
+        self.start_loading();
+        Ok(())
+    }
+
+    /// Go back to the blame left by the last `blame_parent`
+    fn blame_back(&mut self) {
+        let Some(state) = self.history.pop() else {
+            return;
+        };
+        self.path = state.path;
+        self.revision = state.revision;
+        self.blame = Some(state.blame);
+        self.list_state = state.list_state;
+        self.error = None;
+        self.loading = false;
+        self.receiver = None;
+    }
+
+    /// Get the gutter color of a commit made at `time`, hotter for newer
+    /// commits relative to the rest of the file
+    fn heat_color(&self, time: i64, (oldest, newest): (i64, i64)) -> Option<Color> {
+        let t = match newest > oldest {
+            true => (time - oldest) as f64 / (newest - oldest) as f64,
+            false => 1.0,
+        };
+        gradient_color(&self.heat, t)
+    }
+
+    /// Create a list item for a line, its gutter colored by the commit's
+    /// age within `span`, the file's oldest and newest commit times
+    fn create_list_item(
+        &self,
+        blame: &Blame,
+        line: &BlameLine,
+        span: (i64, i64),
+    ) -> ListItem<'static> {
+        let Some(commit) = blame.commit(line) else {
+            return ListItem::new(line.content.clone());
+        };
+        let time = commit.date.timestamp();
+        let gutter = self.heat_color(time, span).unwrap_or(self.colors.date);
+        let author_fg = match self.author_colors {
+            true => author_color(&commit.author_email),
+            false => gutter,
+        };
+        let author: String = commit.author.chars().take(AUTHOR_WIDTH).collect();
+
+        ListItem::new(Line::from(vec![
+            Span::styled(format!("{} ", commit.short_id), Style::default().fg(gutter)),
+            Span::styled(
+                format!("{:<width$} ", author, width = AUTHOR_WIDTH),
+                Style::default().fg(author_fg),
+            ),
+            Span::styled(
+                format!("{} ", commit.date.format("%Y-%m-%d")),
+                Style::default().fg(gutter),
+            ),
+            Span::styled(
+                format!("{:>5} ", line.line_no),
+                Style::default().fg(Color::DarkGray),
+            ),
+            Span::raw(line.content.replace('\t', "    ")),
+        ]))
+    }
+}
+
+impl View for BlameView {
+    fn handle_key(&mut self, key: KeyEvent) -> AppResult<Action> {
+        self.handle_key_with_count(key, 1)
+    }
+
+    fn handle_key_with_count(&mut self, key: KeyEvent, count: usize) -> AppResult<Action> {
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(Action::PopView),
+            KeyCode::Char('j') | KeyCode::Down => self.select_down(count),
+            KeyCode::Char('k') | KeyCode::Up => self.select_up(count),
+            KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.select_down(10 * count)
+            }
+            KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.select_up(10 * count)
+            }
+            KeyCode::PageDown => self.select_down(20 * count),
+            KeyCode::PageUp => self.select_up(20 * count),
+            KeyCode::Char('g') => self.list_state.select(Some(0)),
+            KeyCode::Char('G') => self
+                .list_state
+                .select(Some(self.line_count().saturating_sub(1))),
+            KeyCode::Enter => {
+                // Show the commit that last changed the line
+                if let Some(blame) = &self.blame
+                    && let Some(commit) = self.selected_line().and_then(|l| blame.commit(l))
+                {
+                    return Ok(Action::OpenDiff {
+                        repo: self.repo.clone(),
+                        commit_id: commit.id,
+                        summary: commit.summary.clone(),
+                    });
+                }
+            }
+            KeyCode::Char(',') => self.blame_parent()?,
+            KeyCode::Backspace => self.blame_back(),
+            KeyCode::Char('?') => return Ok(Action::ShowHelp(ViewType::Blame)),
+            _ => {}
+        }
+        Ok(Action::None)
+    }
+
+    fn update(&mut self) -> AppResult<()> {
+        if let Some(receiver) = &mut self.receiver
+            && let Ok(result) = receiver.try_recv()
+        {
+            self.loading = false;
+            self.receiver = None;
+            match result {
+                Ok(blame) => {
+                    let last = blame.lines.len().saturating_sub(1);
+                    let selected = self.list_state.selected().unwrap_or(0).min(last);
+                    self.list_state.select(Some(selected));
+                    self.blame = Some(blame);
+                }
+                Err(e) => {
+                    let back = match self.history.is_empty() {
+                        true => "",
+                        false => "; Backspace goes back",
+                    };
+                    self.error = Some(format!("Failed to blame {}: {}{}", self.path, e, back));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn draw(&mut self, frame: &mut Frame, area: Rect) {
+        if let Some(error) = &self.error {
+            let paragraph = Paragraph::new(error.as_str())
+                .block(self.symbols.block().title("Blame - Error"))
+                .style(Style::default().fg(self.colors.deleted));
+            frame.render_widget(paragraph, area);
+            return;
+        }
+
+        let Some(blame) = &self.blame else {
+            let paragraph = Paragraph::new("Blaming...")
+                .block(self.symbols.block().title(format!("Blame - {}", self.path)))
+                .style(Style::default().fg(self.colors.modified));
+            frame.render_widget(paragraph, area);
+            return;
+        };
+
+        let times = blame.commits.values().map(|c| c.date.timestamp());
+        let span = (times.clone().min().unwrap_or(0), times.max().unwrap_or(0));
+        let items: Vec<ListItem> = blame
+            .lines
+            .iter()
+            .map(|line| self.create_list_item(blame, line, span))
+            .collect();
+
+        let revision = match self.revision {
+            Some(id) => format!(" @ {:.7}", id.to_string()),
+            None => String::new(),
+        };
+        let title = render_title(
+            &self.title_template,
+            &[
+                ("path", blame.path.clone()),
+                ("revision", revision),
+                ("lines", blame.lines.len().to_string()),
+                ("commits", blame.commits.len().to_string()),
+            ],
+        );
+        let list = List::new(items)
+            .block(self.symbols.block().title(title))
+            .highlight_style(self.colors.selected.add_modifier(Modifier::BOLD));
+
+        frame.render_stateful_widget(list, area, &mut self.list_state);
+        let selected = self.list_state.selected().unwrap_or(0);
+        render_scrollbar(frame, area, &self.symbols, self.line_count(), selected);
+    }
+
+    fn title(&self) -> &str {
+        "Blame"
+    }
+
+    fn view_type(&self) -> ViewType {
+        ViewType::Blame
+    }
+
+    fn selection(&self) -> Selection {
+        Selection {
+            commit: self.selected_line().map(|line| line.commit_id),
+            refname: None,
+            file: Some(self.path.clone()),
+        }
+    }
+
+    fn refresh(&mut self) {
+        self.error = None;
+        self.start_loading();
+    }
+
+    fn on_activate(&mut self) -> Result<()> {
+        if self.blame.is_none() && !self.loading {
+            self.start_loading();
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::RepoBuilder;
+    use ratatui::{Terminal, backend::TestBackend};
+
+    fn test_color_scheme() -> ColorScheme {
+        use crate::config::Config;
+        ColorScheme::from_config(&Config::default().colors)
+    }
+
+    #[tokio::test]
+    async fn test_blame_gutter_heat() {
+        let builder = RepoBuilder::new().file("a.txt", "old\n").commit("Old");
+        let (_temp_dir, repo) = builder
+            .file("a.txt", "old\nnew\n")
+            .commit("New")
+            .open()
+            .await;
+
+        let heat = vec![Color::Rgb(0, 0, 255), Color::Rgb(255, 0, 0)];
+        let mut view =
+            BlameView::new(repo, "a.txt".to_string(), None, test_color_scheme()).with_heat(heat);
+        view.start_loading();
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        view.update().unwrap();
+
+        let mut terminal = Terminal::new(TestBackend::new(80, 6)).unwrap();
+        terminal
+            .draw(|frame| view.draw(frame, frame.area()))
+            .unwrap();
+        let buffer = terminal.backend().buffer();
+        let screen: String = buffer.content.iter().map(|cell| cell.symbol()).collect();
+        assert!(screen.contains("Blame - a.txt - 2 lines, 2 commits"));
+        assert!(screen.contains("Test User"));
+
+        // Commits made within the same second are all equally hot; the
+        // first line is selected and takes the highlight style instead
+        assert_eq!(buffer[(1, 2)].fg, Color::Rgb(255, 0, 0));
+
+        let action = view.handle_key(KeyEvent::from(KeyCode::Char('j'))).unwrap();
+        assert_eq!(action, Action::None);
+        let action = view.handle_key(KeyEvent::from(KeyCode::Enter)).unwrap();
+        assert!(matches!(action, Action::OpenDiff { summary, .. } if summary == "New"));
+    }
+
+    #[tokio::test]
+    async fn test_blame_parent_and_back() {
+        let builder = RepoBuilder::new().file("a.txt", "one\n").commit("First");
+        let first = builder.head();
+        let builder = builder.file("a.txt", "one\nTWO\n").commit("Second");
+        let second = builder.head();
+        let (_temp_dir, repo) = builder
+            .file("a.txt", "zero\none\n2\n")
+            .commit("Third")
+            .open()
+            .await;
+        let mut view =
+            BlameView::new(repo, "a.txt".to_string(), None, test_color_scheme()).with_line(3);
+        view.start_loading();
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        view.update().unwrap();
+
+        // Line 3 was changed by Third; before that it was line 2 of Second
+        view.handle_key(KeyEvent::from(KeyCode::Char(','))).unwrap();
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        view.update().unwrap();
+        assert_eq!(view.revision, Some(second));
+        let line = view.selected_line().unwrap();
+        assert_eq!((line.content.as_str(), line.commit_id), ("TWO", second));
+
+        // First had no line 2; the selection stays on the last line
+        view.handle_key(KeyEvent::from(KeyCode::Char(','))).unwrap();
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        view.update().unwrap();
+        assert_eq!(view.revision, Some(first));
+        assert_eq!(view.selected_line().unwrap().commit_id, first);
+        let refused = view.handle_key(KeyEvent::from(KeyCode::Char(',')));
+        assert!(
+            matches!(refused, Err(AppError::Recoverable(message)) if message.contains("no parent"))
+        );
+
+        view.handle_key(KeyEvent::from(KeyCode::Backspace)).unwrap();
+        view.handle_key(KeyEvent::from(KeyCode::Backspace)).unwrap();
+        assert_eq!(view.revision, None);
+        assert_eq!(view.list_state.selected(), Some(2));
+        assert_eq!(view.selected_line().unwrap().content, "2");
+    }
+
+    #[tokio::test]
+    async fn test_heat_runs_from_oldest_to_newest() {
+        let (_temp_dir, repo) = RepoBuilder::new().commit("Initial").open().await;
+        let view = BlameView::new(repo, "a.txt".to_string(), None, test_color_scheme())
+            .with_heat(vec![Color::Rgb(0, 0, 0), Color::Rgb(200, 200, 200)]);
+        assert_eq!(view.heat_color(0, (0, 100)), Some(Color::Rgb(0, 0, 0)));
+        assert_eq!(
+            view.heat_color(50, (0, 100)),
+            Some(Color::Rgb(100, 100, 100))
+        );
+        assert_eq!(
+            view.heat_color(100, (0, 100)),
+            Some(Color::Rgb(200, 200, 200))
+        );
+    }
+}
@@ -0,0 +1,201 @@
+use super::history::InputHistory;
+use super::spell::SpellChecker;
+use crate::config::Symbols;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    style::{Modifier, Style},
+    text::Span,
+};
+use std::sync::Arc;
+
+/// What a key did to the commit message prompt
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommitPromptEvent {
+    /// Still typing
+    None,
+    /// Esc closed the prompt
+    Cancel,
+    /// Enter submitted this message, trimmed and not empty
+    Submit(String),
+    /// Enter was pressed with nothing typed
+    Empty,
+}
+
+/// A commit message typed on one line, with Up/Down recall of the messages
+/// submitted before
+#[derive(Debug, Clone)]
+pub struct CommitPrompt {
+    text: String,
+    history: InputHistory,
+    /// Underlines misspelled words when set
+    spell_checker: Option<Arc<SpellChecker>>,
+    symbols: Symbols,
+}
+
+impl CommitPrompt {
+    /// Open the prompt with `text`, e.g. the commit template, and earlier
+    /// messages ordered oldest first
+    pub fn new(text: String, history: InputHistory) -> Self {
+        Self {
+            text,
+            history,
+            spell_checker: None,
+            symbols: Symbols::default(),
+        }
+    }
+
+    /// Set the glyphs, including the one marking line breaks
+    pub fn with_symbols(mut self, symbols: Symbols) -> Self {
+        self.symbols = symbols;
+        self
+    }
+
+    /// Underline the words `spell_checker` doesn't know
+    pub fn with_spell_checker(mut self, spell_checker: Option<Arc<SpellChecker>>) -> Self {
+        self.spell_checker = spell_checker;
+        self
+    }
+
+    /// Get the history with any submitted message added, to keep for the
+    /// next prompt
+    pub fn into_history(self) -> InputHistory {
+        self.history
+    }
+
+    /// The mark standing for a line break
+    fn line_break(&self) -> String {
+        format!(" {} ", self.symbols.enter)
+    }
+
+    /// Show the message on one line, with line breaks marked
+    pub fn display(&self) -> String {
+        format!("{}_", self.text.replace('\n', &self.line_break()))
+    }
+
+    /// Show the message on one line like `display`, with misspelled words
+    /// underlined
+    pub fn spans(&self) -> Vec<Span<'static>> {
+        let ranges = match &self.spell_checker {
+            Some(checker) => checker.misspelled_ranges(&self.text),
+            None => Vec::new(),
+        };
+        let line_break = self.line_break();
+        let plain = |text: &str| Span::raw(text.replace('\n', &line_break));
+        let misspelled = Style::default().add_modifier(Modifier::UNDERLINED);
+
+        let mut spans = Vec::new();
+        let mut end = 0;
+        for range in ranges {
+            spans.push(plain(&self.text[end..range.start]));
+            spans.push(Span::styled(
+                self.text[range.clone()].to_string(),
+                misspelled,
+            ));
+            end = range.end;
+        }
+        spans.push(plain(&self.text[end..]));
+        spans.push(Span::raw("_"));
+        spans
+    }
+
+    /// Handle a key typed at the prompt
+    pub fn handle_key(&mut self, key: KeyEvent) -> CommitPromptEvent {
+        match key.code {
+            KeyCode::Char(c) => {
+                self.history.reset();
+                self.text.push(c);
+            }
+            KeyCode::Backspace => {
+                self.history.reset();
+                self.text.pop();
+            }
+            KeyCode::Up => {
+                if let Some(entry) = self.history.older(&self.text) {
+                    self.text = entry.to_string();
+                }
+            }
+            KeyCode::Down => {
+                if let Some(entry) = self.history.newer() {
+                    self.text = entry.to_string();
+                }
+            }
+            KeyCode::Esc => return CommitPromptEvent::Cancel,
+            KeyCode::Enter => {
+                let message = self.text.trim().to_string();
+                if message.is_empty() {
+                    return CommitPromptEvent::Empty;
+                }
+                // Kept before committing, so a failed commit loses nothing
+                self.history.push(&message);
+                return CommitPromptEvent::Submit(message);
+            }
+            _ => {}
+        }
+        CommitPromptEvent::None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn type_text(prompt: &mut CommitPrompt, text: &str) {
+        for c in text.chars() {
+            prompt.handle_key(KeyEvent::from(KeyCode::Char(c)));
+        }
+    }
+
+    #[test]
+    fn test_recall_and_submit() {
+        let history = InputHistory::new(vec!["Fix parser".to_string()]);
+        let mut prompt = CommitPrompt::new("feat: ".to_string(), history);
+        type_text(&mut prompt, "add x");
+        assert_eq!(prompt.display(), "feat: add x_");
+
+        prompt.handle_key(KeyEvent::from(KeyCode::Up));
+        assert_eq!(prompt.display(), "Fix parser_");
+        prompt.handle_key(KeyEvent::from(KeyCode::Down));
+        assert_eq!(prompt.display(), "feat: add x_");
+
+        let event = prompt.handle_key(KeyEvent::from(KeyCode::Enter));
+        assert_eq!(event, CommitPromptEvent::Submit("feat: add x".to_string()));
+        assert_eq!(
+            prompt.into_history().entries(),
+            ["Fix parser", "feat: add x"]
+        );
+    }
+
+    #[test]
+    fn test_misspelled_words_are_underlined() {
+        let checker = Arc::new(SpellChecker::from_words("fix\nthe\n"));
+        let prompt = CommitPrompt::new("Fix teh\nthe".to_string(), InputHistory::default())
+            .with_spell_checker(Some(checker));
+        let spans = prompt.spans();
+        let underlined: Vec<&str> = spans
+            .iter()
+            .filter(|span| span.style.add_modifier.contains(Modifier::UNDERLINED))
+            .map(|span| span.content.as_ref())
+            .collect();
+        assert_eq!(underlined, ["teh"]);
+        let text: String = spans.iter().map(|span| span.content.as_ref()).collect();
+        assert_eq!(text, prompt.display());
+    }
+
+    #[test]
+    fn test_empty_and_multiline_messages() {
+        let prompt = CommitPrompt::new("Title\n\nBody\n".to_string(), InputHistory::default());
+        assert_eq!(prompt.display(), "Title ↵  ↵ Body ↵ _");
+        let prompt = prompt.with_symbols(Symbols::ASCII);
+        assert_eq!(prompt.display(), "Title \\n  \\n Body \\n _");
+
+        let mut prompt = CommitPrompt::new("  ".to_string(), InputHistory::default());
+        assert_eq!(
+            prompt.handle_key(KeyEvent::from(KeyCode::Enter)),
+            CommitPromptEvent::Empty
+        );
+        assert_eq!(
+            prompt.handle_key(KeyEvent::from(KeyCode::Esc)),
+            CommitPromptEvent::Cancel
+        );
+    }
+}
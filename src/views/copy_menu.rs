@@ -0,0 +1,169 @@
+use super::popup::centered_rect;
+use crate::config::{ColorScheme, Symbols};
+use crate::git::{Commit, Repository};
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Clear, Paragraph},
+};
+use tokio::sync::mpsc;
+
+/// What a key did to the copy menu
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CopyMenuEvent {
+    /// Still choosing
+    None,
+    /// Esc closed the menu
+    Cancel,
+    /// Copy this text
+    Copy(String),
+}
+
+/// Menu of a commit written out in each of the configured copy formats,
+/// like `%h (%s, %an)`, to pick one to copy
+pub struct CopyMenu {
+    commit: Commit,
+    formats: Vec<String>,
+    /// The commit in each format, once the remote's web page is known
+    choices: Option<Vec<String>>,
+    selected: usize,
+    receiver: Option<mpsc::UnboundedReceiver<Option<String>>>,
+}
+
+impl CopyMenu {
+    /// Open the menu for a commit, looking up the remote's web page for
+    /// `%(url)` first
+    pub fn open(repo: Repository, commit: Commit, formats: Vec<String>) -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            // Without a web page, %(url) is left empty
+            let _ = tx.send(crate::git::remote::web_url(&repo).await.ok().flatten());
+        });
+
+        Self {
+            commit,
+            formats,
+            choices: None,
+            selected: 0,
+            receiver: Some(rx),
+        }
+    }
+
+    /// Fill in the formats once the web page is known, returning the text
+    /// to copy straight away when there is only one format
+    pub fn update(&mut self) -> Option<String> {
+        let receiver = self.receiver.as_mut()?;
+        let web_url = receiver.try_recv().ok()?;
+        self.receiver = None;
+        let choices: Vec<String> = self
+            .formats
+            .iter()
+            .map(|format| self.commit.format(format, web_url.as_deref()))
+            .collect();
+        let only = match choices.as_slice() {
+            [only] => Some(only.clone()),
+            _ => None,
+        };
+        self.choices = Some(choices);
+        only
+    }
+
+    /// Handle a key: j/k move, Enter or a choice's number copies it
+    pub fn handle_key(&mut self, key: KeyEvent) -> CopyMenuEvent {
+        let count = self.choices.as_ref().map_or(0, Vec::len);
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => return CopyMenuEvent::Cancel,
+            KeyCode::Char('j') | KeyCode::Down => {
+                self.selected = (self.selected + 1).min(count.saturating_sub(1))
+            }
+            KeyCode::Char('k') | KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Enter => return self.copy(self.selected),
+            KeyCode::Char(c @ '1'..='9') => return self.copy(c as usize - '1' as usize),
+            _ => {}
+        }
+        CopyMenuEvent::None
+    }
+
+    fn copy(&self, index: usize) -> CopyMenuEvent {
+        match self.choices.as_ref().and_then(|choices| choices.get(index)) {
+            Some(text) => CopyMenuEvent::Copy(text.clone()),
+            None => CopyMenuEvent::None,
+        }
+    }
+
+    /// Render the menu centered in `area`
+    pub fn draw(&self, frame: &mut Frame, area: Rect, colors: &ColorScheme, symbols: &Symbols) {
+        let lines: Vec<Line> = match &self.choices {
+            Some(choices) => choices
+                .iter()
+                .enumerate()
+                .map(|(i, choice)| {
+                    let style = match i == self.selected {
+                        true => colors.selected.add_modifier(Modifier::BOLD),
+                        false => Style::default(),
+                    };
+                    Line::from(vec![
+                        Span::styled(
+                            format!(" {} ", i + 1),
+                            Style::default().fg(colors.commit_hash),
+                        ),
+                        Span::styled(
+                            format!("{} ", choice.replace('\n', &format!(" {} ", symbols.enter))),
+                            style,
+                        ),
+                    ])
+                })
+                .collect(),
+            None => vec![Line::from(" Loading... ")],
+        };
+
+        let width = lines.iter().map(Line::width).max().unwrap_or(0).max(20) as u16 + 2;
+        let popup = centered_rect(width, lines.len() as u16 + 2, area);
+        frame.render_widget(Clear, popup);
+        frame.render_widget(
+            Paragraph::new(lines).block(
+                symbols
+                    .block()
+                    .title(format!("Copy {}", self.commit.short_id)),
+            ),
+            popup,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::RepoBuilder;
+
+    #[tokio::test]
+    async fn test_pick_a_format() {
+        let (_temp_dir, repo) = RepoBuilder::new().commit("Fix parser").open().await;
+        let commit = crate::git::CommitWalker::new(repo.clone())
+            .load_all()
+            .await
+            .unwrap()
+            .remove(0);
+        let formats = vec!["%h".to_string(), "%s".to_string()];
+        let mut menu = CopyMenu::open(repo, commit.clone(), formats);
+        assert_eq!(
+            menu.handle_key(KeyEvent::from(KeyCode::Enter)),
+            CopyMenuEvent::None
+        );
+
+        tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
+        assert_eq!(menu.update(), None);
+        menu.handle_key(KeyEvent::from(KeyCode::Char('j')));
+        assert_eq!(
+            menu.handle_key(KeyEvent::from(KeyCode::Enter)),
+            CopyMenuEvent::Copy("Fix parser".to_string())
+        );
+        assert_eq!(
+            menu.handle_key(KeyEvent::from(KeyCode::Char('1'))),
+            CopyMenuEvent::Copy(commit.short_id)
+        );
+    }
+}
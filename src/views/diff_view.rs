@@ -1,24 +1,63 @@
-use super::view::{Action, View};
-use crate::config::ColorScheme;
-use crate::git::{Diff, DiffFile, DiffHunk, DiffLine, LineType, Repository};
+use super::message::render_body;
+use super::minimap::render_minimap;
+use super::popup::centered_rect;
+use super::scroll_memory::SharedScrollMemory;
+use super::scrollbar::render_scrollbar;
+use super::title::render_title;
+use super::view::{Action, EditRequest, Selection, View, ViewType};
+use crate::config::{
+    ColorScheme, ReviewNote, SharedReviewNotes, StickyHeaders, Symbols, TitleTemplates,
+};
+use crate::error::{AppError, AppResult};
+use crate::git::diff_cache::DiffKey;
+use crate::git::{
+    Diff, DiffFile, DiffHunk, DiffLine, IssueLink, LineType, LinkPattern, Repository,
+    Result as GitResult, SizeLimit,
+};
 use anyhow::Result;
-use crossterm::event::{KeyCode, KeyEvent};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use git2::Oid;
 use ratatui::{
+    Frame,
     layout::Rect,
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState},
-    Frame,
+    widgets::{Clear, Paragraph},
 };
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::ops::Range;
 use tokio::sync::mpsc;
 
+/// Instructions appended to a hunk opened in the editor
+const EDIT_HUNK_HELP: &str = "\
+# Edit the hunk, then save and quit to stage it.
+# To leave a '-' line unstaged, make it a ' ' line.
+# To leave a '+' line unstaged, delete it.
+# Lines starting with # are ignored; delete every line to cancel.
+";
+
 /// Source of the diff
 #[derive(Debug, Clone)]
 enum DiffSource {
-    Commit { id: Oid, summary: String },
-    StagedFile { path: String },
-    UnstagedFile { path: String },
+    Commit {
+        id: Oid,
+        summary: String,
+    },
+    Range {
+        from: Oid,
+        to: Oid,
+    },
+    StagedFile {
+        path: String,
+    },
+    UnstagedFile {
+        path: String,
+    },
+    /// Every staged file
+    Staged,
+    /// Every file with unstaged changes
+    Unstaged,
 }
 
 /// Diff view showing changes for a commit or file
@@ -32,11 +71,94 @@ pub struct DiffView {
     error: Option<String>,
     receiver: Option<mpsc::UnboundedReceiver<Result<Diff>>>,
     colors: ColorScheme,
+    symbols: Symbols,
+    max_file_bytes: u64,
+    expanded_files: HashSet<String>,
+    large_files: Vec<(usize, String)>, // (summary line index, path)
+    /// Rendered lines of each file, from its header to the blank line after it
+    files: Vec<Range<usize>>,
+    /// Rendered lines listing the files with their changes, one per file
+    stat: Range<usize>,
+    /// File to scroll to once the diff loads
+    focus: Option<String>,
+    /// Headers pinned to the top once scrolled past
+    sticky: StickyHeaders,
+    hunks: Vec<HunkSpan>,
+    /// Rendered line indices of added and deleted lines, for the minimap
+    changes: Vec<(usize, LineType)>,
+    confirm_discard: bool,
+    selection: Option<LineSelection>,
+    patch_result: Option<mpsc::UnboundedReceiver<GitResult<()>>>,
+    edit_reply: Option<(DiffHunk, mpsc::UnboundedReceiver<io::Result<String>>)>,
+    /// Notes on files and hunks, shared with the review view
+    review_notes: Option<SharedReviewNotes>,
+    /// The note being written in the editor
+    note_reply: Option<(ReviewNote, mpsc::UnboundedReceiver<io::Result<String>>)>,
+    /// Set once a note was written, so the notes are saved
+    notes_changed: bool,
+    /// Show which files were viewed, folding them to their header
+    review_mode: bool,
+    /// Paths of the files marked viewed
+    viewed: HashSet<String>,
+    visible_height: usize,
+    /// Nearest tag of the commit, like `git describe --tags`
+    describe: Option<String>,
+    describe_receiver: Option<mpsc::UnboundedReceiver<Option<String>>>,
+    /// Where other commits' diffs were left scrolled, shared between views
+    scroll_memory: Option<SharedScrollMemory>,
+    /// Patterns of issue and pull request references in commit messages
+    link_patterns: Vec<LinkPattern>,
+    /// References in the commit's message, opened in the browser with `o`
+    links: Vec<IssueLink>,
+    links_receiver: Option<mpsc::UnboundedReceiver<GitResult<Vec<IssueLink>>>>,
+    /// The reference selected in the list `o` opens when there are several
+    link_picker: Option<usize>,
+    /// The commit message after its summary, with its Markdown formatted
+    /// unless `m` switched to the raw text
+    body: String,
+    body_receiver: Option<mpsc::UnboundedReceiver<String>>,
+    raw_body: bool,
+    /// Template of the title, filled in by `render_title`
+    title_template: String,
+}
+
+/// Where a hunk sits in the rendered lines
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct HunkSpan {
+    header_line: usize,
+    end_line: usize,
+    file: usize,
+    hunk: usize,
+}
+
+/// Lines picked within one hunk for staging, as indices into its lines
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct LineSelection {
+    span: HunkSpan,
+    anchor: usize,
+    cursor: usize,
+}
+
+impl LineSelection {
+    /// Get the selected lines, in order
+    fn range(&self) -> std::ops::RangeInclusive<usize> {
+        self.anchor.min(self.cursor)..=self.anchor.max(self.cursor)
+    }
+
+    /// Get the rendered line index of a line of the hunk
+    fn rendered_line(&self, index: usize) -> usize {
+        self.span.header_line + 1 + index
+    }
 }
 
 impl DiffView {
     /// Create a new diff view for a commit
-    pub fn new(repo: Repository, commit_id: Oid, commit_summary: String, colors: ColorScheme) -> Self {
+    pub fn new(
+        repo: Repository,
+        commit_id: Oid,
+        commit_summary: String,
+        colors: ColorScheme,
+    ) -> Self {
         Self {
             repo,
             source: DiffSource::Commit {
@@ -50,9 +172,47 @@ impl DiffView {
             error: None,
             receiver: None,
             colors,
+            symbols: Symbols::default(),
+            max_file_bytes: 0,
+            expanded_files: HashSet::new(),
+            large_files: Vec::new(),
+            files: Vec::new(),
+            sticky: StickyHeaders::default(),
+            hunks: Vec::new(),
+            changes: Vec::new(),
+            confirm_discard: false,
+            selection: None,
+            patch_result: None,
+            edit_reply: None,
+            review_notes: None,
+            review_mode: false,
+            stat: 0..0,
+            focus: None,
+            viewed: HashSet::new(),
+            note_reply: None,
+            notes_changed: false,
+            visible_height: 0,
+            describe: None,
+            describe_receiver: None,
+            scroll_memory: None,
+            link_patterns: Vec::new(),
+            links: Vec::new(),
+            links_receiver: None,
+            link_picker: None,
+            body: String::new(),
+            body_receiver: None,
+            raw_body: false,
+            title_template: TitleTemplates::default().diff,
         }
     }
 
+    /// Create a diff view of the changes from commit `from` to commit `to`
+    pub fn new_range(repo: Repository, from: Oid, to: Oid, colors: ColorScheme) -> Self {
+        let mut view = Self::new(repo, to, String::new(), colors);
+        view.source = DiffSource::Range { from, to };
+        view
+    }
+
     /// Create a new diff view for staged changes
     pub fn new_staged(repo: Repository, path: String, colors: ColorScheme) -> Self {
         Self {
@@ -65,6 +225,37 @@ impl DiffView {
             error: None,
             receiver: None,
             colors,
+            symbols: Symbols::default(),
+            max_file_bytes: 0,
+            expanded_files: HashSet::new(),
+            large_files: Vec::new(),
+            files: Vec::new(),
+            sticky: StickyHeaders::default(),
+            hunks: Vec::new(),
+            changes: Vec::new(),
+            confirm_discard: false,
+            selection: None,
+            patch_result: None,
+            edit_reply: None,
+            review_notes: None,
+            review_mode: false,
+            stat: 0..0,
+            focus: None,
+            viewed: HashSet::new(),
+            note_reply: None,
+            notes_changed: false,
+            visible_height: 0,
+            describe: None,
+            describe_receiver: None,
+            scroll_memory: None,
+            link_patterns: Vec::new(),
+            links: Vec::new(),
+            links_receiver: None,
+            link_picker: None,
+            body: String::new(),
+            body_receiver: None,
+            raw_body: false,
+            title_template: TitleTemplates::default().diff,
         }
     }
 
@@ -80,9 +271,105 @@ impl DiffView {
             error: None,
             receiver: None,
             colors,
+            symbols: Symbols::default(),
+            max_file_bytes: 0,
+            expanded_files: HashSet::new(),
+            large_files: Vec::new(),
+            files: Vec::new(),
+            sticky: StickyHeaders::default(),
+            hunks: Vec::new(),
+            changes: Vec::new(),
+            confirm_discard: false,
+            selection: None,
+            patch_result: None,
+            edit_reply: None,
+            review_notes: None,
+            review_mode: false,
+            stat: 0..0,
+            focus: None,
+            viewed: HashSet::new(),
+            note_reply: None,
+            notes_changed: false,
+            visible_height: 0,
+            describe: None,
+            describe_receiver: None,
+            scroll_memory: None,
+            link_patterns: Vec::new(),
+            links: Vec::new(),
+            links_receiver: None,
+            link_picker: None,
+            body: String::new(),
+            body_receiver: None,
+            raw_body: false,
+            title_template: TitleTemplates::default().diff,
         }
     }
 
+    /// Set the glyphs used for borders and the scrollbar
+    pub fn with_symbols(mut self, symbols: Symbols) -> Self {
+        self.symbols = symbols;
+        self
+    }
+
+    /// Remember where each commit's diff was left scrolled, and pick up
+    /// there when it is opened again
+    pub fn with_scroll_memory(mut self, memory: SharedScrollMemory) -> Self {
+        self.scroll_memory = Some(memory);
+        self
+    }
+
+    /// Get the commit whose diff is shown, if it is a single commit's
+    fn commit_id(&self) -> Option<Oid> {
+        match &self.source {
+            DiffSource::Commit { id, .. } => Some(*id),
+            _ => None,
+        }
+    }
+
+    /// Pin the file header, and the hunk header too, at the top while
+    /// their content scrolls
+    pub fn with_sticky_headers(mut self, sticky: StickyHeaders) -> Self {
+        self.sticky = sticky;
+        self
+    }
+
+    /// Let `a` write review notes on the files and hunks, into `notes`
+    pub fn with_review_notes(mut self, notes: SharedReviewNotes) -> Self {
+        self.review_notes = Some(notes);
+        self.rebuild_lines();
+        self
+    }
+
+    /// Start from the files of this diff marked viewed before, by diff as
+    /// in the session, turning review mode on if there are any
+    pub fn with_viewed_files(mut self, viewed: &HashMap<String, Vec<String>>) -> Self {
+        if let Some(paths) = self.review_key().and_then(|key| viewed.get(&key)) {
+            self.viewed = paths.iter().cloned().collect();
+            self.review_mode = !self.viewed.is_empty();
+            self.rebuild_lines();
+        }
+        self
+    }
+
+    /// Set the template of the title
+    pub fn with_title_template(mut self, title_template: String) -> Self {
+        self.title_template = title_template;
+        self
+    }
+
+    /// Skip reading files larger than this many bytes (0 disables)
+    pub fn with_max_file_bytes(mut self, bytes: u64) -> Self {
+        self.max_file_bytes = bytes;
+        self
+    }
+
+    /// Set the patterns of issue references `o` opens from the commit
+    /// message
+    pub fn with_link_patterns(mut self, link_patterns: Vec<LinkPattern>) -> Self {
+        self.link_patterns = link_patterns;
+        self
+    }
+
     /// Start loading the diff asynchronously
     pub fn start_loading(&mut self) {
         let (tx, rx) = mpsc::unbounded_channel();
@@ -91,48 +378,664 @@ impl DiffView {
 
         let repo = self.repo.clone();
         let source = self.source.clone();
+        let limit = SizeLimit {
+            max_file_bytes: self.max_file_bytes,
+            expanded: self.expanded_files.clone(),
+        };
 
         tokio::spawn(async move {
-            let result = match source {
-                DiffSource::Commit { id, .. } => {
-                    crate::git::diff::load_commit_diff(&repo, id).await
+            let key = match source {
+                DiffSource::Commit { id, .. } => DiffKey::Commit(id),
+                DiffSource::Range { from, to } => DiffKey::Range { from, to },
+                DiffSource::StagedFile { path } => DiffKey::Staged(path),
+                DiffSource::UnstagedFile { path } => DiffKey::Unstaged(path),
+                // Any file can make a whole working tree diff stale, so these
+                // skip the cache
+                DiffSource::Staged => {
+                    let result = crate::git::diff::load_staged_diff(&repo, None, &limit).await;
+                    let _ = tx.send(result.map_err(|e| anyhow::anyhow!(e)));
+                    return;
                 }
-                DiffSource::StagedFile { path } => {
-                    crate::git::diff::load_staged_diff(&repo, Some(path)).await
+                DiffSource::Unstaged => {
+                    let result = crate::git::diff::load_unstaged_diff(&repo, None, &limit).await;
+                    let _ = tx.send(result.map_err(|e| anyhow::anyhow!(e)));
+                    return;
+                }
+            };
+            let result = crate::git::diff_cache::load_diff(&repo, key, &limit)
+                .await
+                .map_err(|e| anyhow::anyhow!(e));
+            let _ = tx.send(result);
+        });
+
+        // Name the commit after its nearest tag; this is only a hint, so a
+        // failure just leaves it out
+        if let DiffSource::Commit { id, .. } = self.source {
+            let (tx, rx) = mpsc::unbounded_channel();
+            self.describe_receiver = Some(rx);
+            let repo = self.repo.clone();
+            tokio::spawn(async move {
+                let describe = crate::git::refs::describe_commits(&repo, vec![id])
+                    .await
+                    .ok()
+                    .and_then(|described| described.into_iter().next())
+                    .and_then(|(_, name)| name);
+                let _ = tx.send(describe);
+            });
+
+            // Like the tag, the body is extra; the diff shows without it
+            let (tx, rx) = mpsc::unbounded_channel();
+            self.body_receiver = Some(rx);
+            let repo = self.repo.clone();
+            tokio::spawn(async move {
+                if let Ok(commit) = crate::git::commit::load_commit(&repo, id).await {
+                    let _ = tx.send(commit.body().to_string());
                 }
-                DiffSource::UnstagedFile { path } => {
-                    crate::git::diff::load_unstaged_diff(&repo, Some(path)).await
+            });
+
+            let (tx, rx) = mpsc::unbounded_channel();
+            self.links_receiver = Some(rx);
+            let (repo, patterns) = (self.repo.clone(), self.link_patterns.clone());
+            tokio::spawn(async move {
+                let _ = tx.send(crate::git::links::issue_links(&repo, id, patterns).await);
+            });
+        }
+    }
+
+    /// Open the commit message's issue reference, or list them to pick from
+    /// when there are several
+    fn open_link(&mut self) -> AppResult<Action> {
+        match self.links.as_slice() {
+            [] => Err(AppError::recoverable(
+                "No issue references in the commit message",
+            )),
+            [link] => Ok(Action::OpenUrl(link.url.clone())),
+            _ => {
+                self.link_picker = Some(0);
+                Ok(Action::None)
+            }
+        }
+    }
+
+    /// Handle a key in the list of issue references: j/k move, Enter or a
+    /// reference's number opens it
+    fn handle_link_picker_key(&mut self, key: KeyEvent) -> Action {
+        let Some(selected) = self.link_picker else {
+            return Action::None;
+        };
+        let chosen = match key.code {
+            KeyCode::Char('j') | KeyCode::Down => {
+                self.link_picker = Some((selected + 1).min(self.links.len().saturating_sub(1)));
+                return Action::None;
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.link_picker = Some(selected.saturating_sub(1));
+                return Action::None;
+            }
+            KeyCode::Enter => Some(selected),
+            KeyCode::Char(c @ '1'..='9') => Some(c as usize - '1' as usize),
+            _ => None,
+        };
+        self.link_picker = None;
+        match chosen.and_then(|i| self.links.get(i)) {
+            Some(link) => Action::OpenUrl(link.url.clone()),
+            None => Action::None,
+        }
+    }
+
+    /// Rebuild the rendered lines from the loaded diff
+    fn rebuild_lines(&mut self) {
+        let Some(diff) = &self.diff else {
+            return;
+        };
+
+        let mut large_files = Vec::new();
+        let mut hunks = Vec::new();
+        let mut files = Vec::new();
+        let mut stat = 0..0;
+        let lines =
+            self.render_diff_to_lines(diff, &mut large_files, &mut hunks, &mut files, &mut stat);
+        self.changes = hunks
+            .iter()
+            .filter_map(|&span| Some((span, self.hunk_at(span)?)))
+            .flat_map(|(span, hunk)| {
+                hunk.lines
+                    .iter()
+                    .enumerate()
+                    .map(move |(index, line)| (span.header_line + 1 + index, line.line_type))
+            })
+            .filter(|(_, line_type)| matches!(line_type, LineType::Addition | LineType::Deletion))
+            .collect();
+        self.lines = lines;
+        self.large_files = large_files;
+        self.hunks = hunks;
+        self.files = files;
+        self.stat = stat;
+        self.scroll_offset = self.scroll_offset.min(self.lines.len().saturating_sub(1));
+    }
+
+    /// Expand the nearest collapsed file at or below the top of the view,
+    /// reloading the diff to read its lines
+    fn expand_large_file(&mut self) -> bool {
+        let target = self
+            .large_files
+            .iter()
+            .find(|(line, _)| *line >= self.scroll_offset)
+            .or_else(|| self.large_files.last())
+            .map(|(_, path)| path.clone());
+
+        match target {
+            Some(path) => {
+                self.expanded_files.insert(path);
+                self.start_loading();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Find the hunk under the cursor: the one at the top of the view, or
+    /// else the nearest one below it
+    fn current_hunk(&self) -> Option<HunkSpan> {
+        self.hunks
+            .iter()
+            .find(|h| h.header_line <= self.scroll_offset && self.scroll_offset < h.end_line)
+            .or_else(|| {
+                self.hunks
+                    .iter()
+                    .find(|h| h.header_line > self.scroll_offset)
+            })
+            .or_else(|| self.hunks.last())
+            .copied()
+    }
+
+    /// Get what review notes and viewed files of this diff are filed under:
+    /// the commit, or `from..to` for a range; other diffs aren't reviewed
+    fn review_key(&self) -> Option<String> {
+        match &self.source {
+            DiffSource::Commit { id, .. } => Some(id.to_string()),
+            DiffSource::Range { from, to } => Some(format!("{}..{}", from, to)),
+            DiffSource::StagedFile { .. }
+            | DiffSource::UnstagedFile { .. }
+            | DiffSource::Staged
+            | DiffSource::Unstaged => None,
+        }
+    }
+
+    /// Switch between a file's staged or unstaged changes and those of
+    /// every file, keeping to the file at the top
+    fn toggle_all_files(&mut self) -> AppResult<Action> {
+        let source = match &self.source {
+            DiffSource::StagedFile { path } => {
+                self.focus = Some(path.clone());
+                DiffSource::Staged
+            }
+            DiffSource::UnstagedFile { path } => {
+                self.focus = Some(path.clone());
+                DiffSource::Unstaged
+            }
+            DiffSource::Staged | DiffSource::Unstaged => {
+                let top = self.scroll_offset;
+                let Some(path) = self
+                    .files
+                    .iter()
+                    .position(|file| file.end > top)
+                    .and_then(|index| Some(self.diff.as_ref()?.files[index].path().to_string()))
+                else {
+                    return Ok(Action::None);
+                };
+                match self.source {
+                    DiffSource::Staged => DiffSource::StagedFile { path },
+                    _ => DiffSource::UnstagedFile { path },
                 }
             }
-            .map_err(|e| anyhow::anyhow!(e));
+            DiffSource::Commit { .. } | DiffSource::Range { .. } => {
+                return Err(AppError::recoverable(
+                    "Only staged and unstaged diffs switch to every file",
+                ));
+            }
+        };
+        self.source = source;
+        self.diff = None;
+        self.lines.clear();
+        self.hunks.clear();
+        self.files.clear();
+        self.scroll_offset = 0;
+        self.refresh();
+        Ok(Action::None)
+    }
+
+    /// Scroll to the file whose line in the list of files is at the top
+    fn jump_to_listed_file(&mut self) -> bool {
+        if !self.stat.contains(&self.scroll_offset) {
+            return false;
+        }
+        match self.files.get(self.scroll_offset - self.stat.start) {
+            Some(file) => {
+                self.scroll_offset = file.start;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Turn review mode on or off
+    fn toggle_review_mode(&mut self) -> AppResult<Action> {
+        if self.review_key().is_none() {
+            return Err(AppError::recoverable(
+                "Only the diffs of commits are reviewed file by file",
+            ));
+        }
+        self.review_mode = !self.review_mode;
+        self.rebuild_lines();
+        Ok(Action::None)
+    }
+
+    /// Mark the file at the top of the view viewed, or not viewed if it
+    /// was, and move on to the next file still to view
+    fn toggle_viewed(&mut self) -> AppResult<Action> {
+        let (Some(key), Some(diff)) = (self.review_key(), &self.diff) else {
+            return Err(AppError::recoverable(
+                "Only the diffs of commits are reviewed file by file",
+            ));
+        };
+        // The file at the top, or the first one below the commit message
+        let top = self.scroll_offset;
+        let Some(index) = self.files.iter().position(|file| file.end > top) else {
+            return Ok(Action::None);
+        };
+        let path = diff.files[index].path().to_string();
+        let viewed = !self.viewed.remove(&path);
+        if viewed {
+            self.viewed.insert(path.clone());
+        }
+        let next = match viewed {
+            true => (index + 1..diff.files.len())
+                .find(|&i| !self.viewed.contains(diff.files[i].path()))
+                .unwrap_or(index),
+            false => index,
+        };
+
+        self.review_mode = true;
+        self.rebuild_lines();
+        if let Some(file) = self.files.get(next) {
+            self.scroll_offset = file.start;
+        }
+        Ok(Action::MarkViewed {
+            diff: key,
+            path,
+            viewed,
+        })
+    }
+
+    /// Count the files marked viewed, and all the files
+    fn review_progress(&self) -> (usize, usize) {
+        let files = self.diff.as_ref().map_or(&[][..], |diff| &diff.files[..]);
+        let viewed = files
+            .iter()
+            .filter(|file| self.viewed.contains(file.path()))
+            .count();
+        (viewed, files.len())
+    }
+
+    /// Get the first line of the note on a file or hunk, if there is one
+    fn note_on(&self, path: &str, hunk: Option<&str>) -> Option<String> {
+        let notes = self.review_notes.as_ref()?.borrow();
+        let note = notes.find(&self.review_key()?, path, hunk)?;
+        Some(note.text.lines().next().unwrap_or_default().to_string())
+    }
+
+    /// Open the editor on the note of the hunk at the top of the view, or
+    /// of its file when the top is on the file's header
+    fn annotate(&mut self) -> AppResult<Action> {
+        let (Some(notes), Some(commit), Some(diff)) =
+            (&self.review_notes, self.review_key(), &self.diff)
+        else {
+            return Err(AppError::recoverable("Notes go on the diffs of commits"));
+        };
+        let top = self.scroll_offset;
+        let Some(file) = self.files.iter().position(|file| file.contains(&top)) else {
+            return Err(AppError::recoverable(
+                "Scroll a file or hunk to the top to write a note on it",
+            ));
+        };
+        let path = diff.files[file].path().to_string();
+        let hunk = self
+            .hunks
+            .iter()
+            .find(|h| h.file == file && h.header_line <= top && top < h.end_line)
+            .and_then(|&span| self.hunk_at(span))
+            .map(|hunk| hunk.header.trim_end().to_string());
+
+        let text = notes
+            .borrow()
+            .find(&commit, &path, hunk.as_deref())
+            .map(|note| note.text.clone())
+            .unwrap_or_default();
+        let summary = match &self.source {
+            DiffSource::Commit { summary, .. } => summary.clone(),
+            _ => String::new(),
+        };
+        let note = ReviewNote {
+            commit,
+            summary,
+            path,
+            hunk,
+            text: String::new(),
+        };
+        let (request, reply) = EditRequest::new(text, "review-note.md");
+        self.note_reply = Some((note, reply));
+        Ok(Action::EditText(request))
+    }
+
+    /// Get the headers to pin above the line at the top of the view: the
+    /// file's once its header is scrolled past, then the hunk's when asked
+    /// for, as indices into the rendered lines
+    fn sticky_lines(&self) -> Vec<usize> {
+        let top = self.scroll_offset;
+        let mut pinned = Vec::new();
+        if self.sticky == StickyHeaders::None {
+            return pinned;
+        }
+        if let Some(file) = self
+            .files
+            .iter()
+            .find(|file| file.start < top && top < file.end)
+        {
+            pinned.push(file.start);
+        }
+        if self.sticky == StickyHeaders::Hunk
+            && let Some(hunk) = self
+                .hunks
+                .iter()
+                .find(|h| h.header_line < top && top < h.end_line)
+        {
+            pinned.push(hunk.header_line);
+        }
+        pinned
+    }
+
+    /// Fill in the title template, with `line` the last one on screen
+    fn render_title(&self, line: usize) -> String {
+        let (commit, summary) = match &self.source {
+            DiffSource::Commit { id, summary } => {
+                (format!("{:.7}", id.to_string()), summary.clone())
+            }
+            DiffSource::Range { from, to } => (
+                format!("{:.7}..{:.7}", from.to_string(), to.to_string()),
+                String::new(),
+            ),
+            DiffSource::StagedFile { path } | DiffSource::UnstagedFile { path } => {
+                (String::new(), path.clone())
+            }
+            DiffSource::Staged => (String::new(), "staged changes".to_string()),
+            DiffSource::Unstaged => (String::new(), "unstaged changes".to_string()),
+        };
+        let hunk = self
+            .current_hunk()
+            .and_then(|current| self.hunks.iter().position(|&span| span == current))
+            .map_or(0, |i| i + 1);
+        render_title(
+            &self.title_template,
+            &[
+                ("commit", commit),
+                ("summary", summary),
+                ("line", line.to_string()),
+                ("lines", self.lines.len().to_string()),
+                (
+                    "files",
+                    self.diff
+                        .as_ref()
+                        .map_or(0, |diff| diff.files.len())
+                        .to_string(),
+                ),
+                ("hunk", hunk.to_string()),
+                ("hunks", self.hunks.len().to_string()),
+            ],
+        )
+    }
+
+    /// Scroll the next hunk to the top of the view
+    fn next_hunk(&mut self) {
+        if let Some(hunk) = self
+            .hunks
+            .iter()
+            .find(|h| h.header_line > self.scroll_offset)
+        {
+            self.scroll_offset = hunk.header_line;
+        }
+    }
+
+    /// Get the file under the cursor and the commit to blame it at, or None
+    /// to blame it at HEAD
+    fn blame_target(&self) -> Option<(String, Option<Oid>)> {
+        match &self.source {
+            DiffSource::Commit { id, .. } | DiffSource::Range { to: id, .. } => {
+                let files = &self.diff.as_ref()?.files;
+                let file = match self.current_hunk() {
+                    Some(span) => files.get(span.file)?,
+                    None => files.first()?,
+                };
+                Some((file.path().to_string(), Some(*id)))
+            }
+            DiffSource::StagedFile { path } | DiffSource::UnstagedFile { path } => {
+                Some((path.clone(), None))
+            }
+            DiffSource::Staged | DiffSource::Unstaged => {
+                let files = &self.diff.as_ref()?.files;
+                let file = files.get(self.current_hunk()?.file)?;
+                Some((file.path().to_string(), None))
+            }
+        }
+    }
+
+    /// Get the loaded hunk at `span`
+    fn hunk_at(&self, span: HunkSpan) -> Option<&DiffHunk> {
+        self.diff
+            .as_ref()?
+            .files
+            .get(span.file)?
+            .hunks
+            .get(span.hunk)
+    }
+
+    /// Check whether hunks can be staged or discarded: only unstaged changes
+    /// that have finished loading
+    fn can_edit_hunks(&self) -> bool {
+        matches!(self.source, DiffSource::UnstagedFile { .. }) && !self.loading
+    }
+
+    /// Reverse-apply the hunk under the cursor to the working directory
+    fn discard_current_hunk(&mut self) {
+        if let Some(hunk) = self
+            .current_hunk()
+            .and_then(|span| self.hunk_at(span))
+            .cloned()
+        {
+            self.apply_hunk(hunk, false);
+        }
+    }
+
+    /// Stage the hunk under the cursor
+    fn stage_current_hunk(&mut self) {
+        if let Some(hunk) = self
+            .current_hunk()
+            .and_then(|span| self.hunk_at(span))
+            .cloned()
+        {
+            self.apply_hunk(hunk, true);
+        }
+    }
+
+    /// Ask for the hunk under the cursor to be opened in the editor, to stage
+    /// whatever it's saved as
+    fn edit_current_hunk(&mut self) -> Action {
+        let Some(hunk) = self
+            .current_hunk()
+            .and_then(|span| self.hunk_at(span))
+            .cloned()
+        else {
+            return Action::None;
+        };
+        let text = format!("{}{}", hunk.to_text(), EDIT_HUNK_HELP);
+        let (request, reply) = EditRequest::new(text, "hunk-edit.diff");
+        self.edit_reply = Some((hunk, reply));
+        Action::EditText(request)
+    }
+
+    /// Start picking lines of the hunk under the cursor, beginning with its
+    /// first change
+    fn start_selection(&mut self) {
+        let Some(span) = self.current_hunk() else {
+            return;
+        };
+        let first_change = self.hunk_at(span).and_then(|hunk| {
+            hunk.lines
+                .iter()
+                .position(|l| matches!(l.line_type, LineType::Addition | LineType::Deletion))
+        });
+        let cursor = first_change.unwrap_or(0);
+        self.selection = Some(LineSelection {
+            span,
+            anchor: cursor,
+            cursor,
+        });
+        self.scroll_offset = self.scroll_offset.min(span.header_line);
+        self.follow_selection();
+    }
+
+    /// Move the selection cursor within its hunk
+    fn move_selection(&mut self, down: bool) {
+        let Some(selection) = &mut self.selection else {
+            return;
+        };
+        let last = (selection.span.end_line - selection.span.header_line).saturating_sub(2);
+        selection.cursor = match down {
+            true => (selection.cursor + 1).min(last),
+            false => selection.cursor.saturating_sub(1),
+        };
+        self.follow_selection();
+    }
+
+    /// Scroll so the selection cursor stays in view
+    fn follow_selection(&mut self) {
+        let Some(selection) = &self.selection else {
+            return;
+        };
+        let line = selection.rendered_line(selection.cursor);
+        if line < self.scroll_offset {
+            self.scroll_offset = line;
+        } else if self.visible_height > 0 && line >= self.scroll_offset + self.visible_height {
+            self.scroll_offset = line + 1 - self.visible_height;
+        }
+    }
+
+    /// Stage the selected lines and leave selection mode
+    fn stage_selection(&mut self) {
+        let Some(selection) = self.selection.take() else {
+            return;
+        };
+        if let Some(hunk) = self
+            .hunk_at(selection.span)
+            .and_then(|hunk| hunk.select_lines(selection.range()))
+        {
+            self.apply_hunk(hunk, true);
+        }
+    }
+
+    /// Stage or discard a hunk of the file in the background; `update`
+    /// reloads the diff when it's done
+    fn apply_hunk(&mut self, hunk: DiffHunk, stage: bool) {
+        let DiffSource::UnstagedFile { path } = &self.source else {
+            return;
+        };
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.patch_result = Some(rx);
+
+        let repo = self.repo.clone();
+        let path = path.clone();
+        tokio::spawn(async move {
+            let result = if stage {
+                crate::git::diff::stage_hunk(&repo, path, hunk).await
+            } else {
+                crate::git::diff::discard_hunk(&repo, path, hunk).await
+            };
             let _ = tx.send(result);
         });
     }
 
     /// Convert a Diff into styled lines for rendering
-    fn render_diff_to_lines(&self, diff: &Diff) -> Vec<Line<'static>> {
+    fn render_diff_to_lines(
+        &self,
+        diff: &Diff,
+        large_files: &mut Vec<(usize, String)>,
+        hunks: &mut Vec<HunkSpan>,
+        files: &mut Vec<Range<usize>>,
+        stat: &mut Range<usize>,
+    ) -> Vec<Line<'static>> {
         let mut lines = Vec::new();
 
         // Show header based on source
         match &self.source {
             DiffSource::Commit { id, summary } => {
-                lines.push(Line::from(vec![
-                    Span::styled(
-                        format!("commit {}", id),
-                        Style::default().fg(self.colors.commit_hash).add_modifier(Modifier::BOLD),
-                    ),
-                ]));
+                let mut header = vec![Span::styled(
+                    format!("commit {}", id),
+                    Style::default()
+                        .fg(self.colors.commit_hash)
+                        .add_modifier(Modifier::BOLD),
+                )];
+                if let Some(describe) = &self.describe {
+                    header.push(Span::styled(
+                        format!(" ({})", describe),
+                        Style::default().fg(Color::Cyan),
+                    ));
+                }
+                lines.push(Line::from(header));
                 lines.push(Line::from(Span::styled(
                     summary.clone(),
                     // Style::default().fg(Color::White),
                     Style::default().fg(self.colors.modified),
                 )));
+                if !self.links.is_empty() {
+                    let mut refs =
+                        vec![Span::styled("Refs:", Style::default().fg(self.colors.date))];
+                    for link in &self.links {
+                        refs.push(Span::raw(" "));
+                        refs.push(Span::styled(
+                            link.text.clone(),
+                            Style::default()
+                                .fg(Color::Cyan)
+                                .add_modifier(Modifier::UNDERLINED),
+                        ));
+                    }
+                    lines.push(Line::from(refs));
+                }
+                if !self.body.is_empty() {
+                    lines.push(Line::from(""));
+                    lines.extend(render_body(
+                        &self.body,
+                        &self.colors,
+                        &self.symbols,
+                        self.raw_body,
+                    ));
+                }
+            }
+            DiffSource::Range { from, to } => {
+                lines.push(Line::from(Span::styled(
+                    format!("diff {}..{}", from, to),
+                    Style::default()
+                        .fg(self.colors.commit_hash)
+                        .add_modifier(Modifier::BOLD),
+                )));
             }
             DiffSource::StagedFile { path } => {
                 lines.push(Line::from(vec![
                     Span::styled(
                         "Staged changes: ",
-                        Style::default().fg(self.colors.added).add_modifier(Modifier::BOLD),
+                        Style::default()
+                            .fg(self.colors.added)
+                            .add_modifier(Modifier::BOLD),
                     ),
                     Span::styled(
                         path.clone(),
@@ -145,21 +1048,64 @@ impl DiffView {
                 lines.push(Line::from(vec![
                     Span::styled(
                         "Unstaged changes: ",
-                        Style::default().fg(self.colors.modified).add_modifier(Modifier::BOLD),
+                        Style::default()
+                            .fg(self.colors.modified)
+                            .add_modifier(Modifier::BOLD),
                     ),
                     Span::styled(
-                        path.clone(), 
+                        path.clone(),
                         // Style::default().fg(Color::White),
                         Style::default().fg(self.colors.modified),
                     ),
                 ]));
             }
+            DiffSource::Staged | DiffSource::Unstaged => {
+                let (label, color) = match self.source {
+                    DiffSource::Staged => ("Staged changes", self.colors.added),
+                    _ => ("Unstaged changes", self.colors.modified),
+                };
+                lines.push(Line::from(Span::styled(
+                    label,
+                    Style::default().fg(color).add_modifier(Modifier::BOLD),
+                )));
+            }
         }
         lines.push(Line::from(""));
 
+        // List the files, each line leading to its file with Enter
+        if diff.files.len() > 1 {
+            let width = diff
+                .files
+                .iter()
+                .map(|file| file.path().chars().count())
+                .max()
+                .unwrap_or(0);
+            let start = lines.len();
+            for file in &diff.files {
+                lines.push(Line::from(vec![
+                    Span::styled(
+                        format!(" {:<width$} | ", file.path()),
+                        Style::default().fg(Color::Cyan),
+                    ),
+                    Span::styled(
+                        format!("+{} ", file.additions),
+                        Style::default().fg(self.colors.added),
+                    ),
+                    Span::styled(
+                        format!("-{}", file.deletions),
+                        Style::default().fg(self.colors.deleted),
+                    ),
+                ]));
+            }
+            *stat = start..lines.len();
+            lines.push(Line::from(""));
+        }
+
         // Render each file
-        for file in &diff.files {
-            self.render_file_to_lines(&mut lines, file);
+        for (index, file) in diff.files.iter().enumerate() {
+            let header_line = lines.len();
+            self.render_file_to_lines(&mut lines, index, file, large_files, hunks);
+            files.push(header_line..lines.len());
         }
 
         // Show summary at the bottom
@@ -185,7 +1131,14 @@ impl DiffView {
     }
 
     /// Render a single file to lines
-    fn render_file_to_lines(&self, lines: &mut Vec<Line<'static>>, file: &DiffFile) {
+    fn render_file_to_lines(
+        &self,
+        lines: &mut Vec<Line<'static>>,
+        file_index: usize,
+        file: &DiffFile,
+        large_files: &mut Vec<(usize, String)>,
+        hunks: &mut Vec<HunkSpan>,
+    ) {
         // File header
         let file_line = match (&file.old_path, &file.new_path) {
             (Some(old), Some(new)) if old != new => {
@@ -193,18 +1146,38 @@ impl DiffView {
             }
             (Some(old), None) => format!("diff --git a/{} (deleted)", old),
             (None, Some(new)) => format!("diff --git a/{} (new)", new),
-            (Some(path), _) | (None, Some(path)) => {
+            (Some(path), _) => {
                 format!("diff --git a/{} b/{}", path, path)
             }
             (None, None) => "diff --git (unknown)".to_string(),
         };
 
-        lines.push(Line::from(Span::styled(
+        let viewed = self.viewed.contains(file.path());
+        let mut header = Vec::new();
+        if self.review_mode {
+            let checkbox = match viewed {
+                true => "[x] ",
+                false => "[ ] ",
+            };
+            header.push(Span::styled(
+                checkbox,
+                Style::default().fg(self.colors.added),
+            ));
+        }
+        header.push(Span::styled(
             file_line,
             Style::default()
                 .fg(Color::Cyan)
                 .add_modifier(Modifier::BOLD),
-        )));
+        ));
+        header.extend(self.note_marker(file.path(), None));
+        lines.push(Line::from(header));
+
+        // Viewed files fold down to their header while reviewing
+        if self.review_mode && viewed {
+            lines.push(Line::from(""));
+            return;
+        }
 
         // File statistics
         let stats = file.stats_summary();
@@ -223,14 +1196,53 @@ impl DiffView {
             return;
         }
 
+        // Large file guard: its lines were never read, only counted
+        if file.skipped_bytes.is_some() {
+            large_files.push((lines.len(), file.path().to_string()));
+            lines.push(Line::from(Span::styled(
+                format!(
+                    "large file, {} lines changed (press Enter to expand)",
+                    file.changed_lines()
+                ),
+                Style::default()
+                    .fg(self.colors.modified)
+                    .add_modifier(Modifier::ITALIC),
+            )));
+            lines.push(Line::from(""));
+            return;
+        }
+
         // Render hunks
-        for hunk in &file.hunks {
+        for (index, hunk) in file.hunks.iter().enumerate() {
+            let header_line = lines.len();
             self.render_hunk_to_lines(lines, hunk);
+            if let Some(marker) = self.note_marker(file.path(), Some(hunk.header.trim_end()))
+                && let Some(header) = lines.get_mut(header_line)
+            {
+                header.spans.push(marker);
+            }
+            hunks.push(HunkSpan {
+                header_line,
+                end_line: lines.len(),
+                file: file_index,
+                hunk: index,
+            });
         }
 
         lines.push(Line::from(""));
     }
 
+    /// Mark a file or hunk header with the first line of its review note
+    fn note_marker(&self, path: &str, hunk: Option<&str>) -> Option<Span<'static>> {
+        let note = self.note_on(path, hunk)?;
+        Some(Span::styled(
+            format!("  {}{}", self.symbols.note, note),
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::ITALIC),
+        ))
+    }
+
     /// Render a single hunk to lines
     fn render_hunk_to_lines(&self, lines: &mut Vec<Line<'static>>, hunk: &DiffHunk) {
         // Hunk header - clone to own the string
@@ -323,7 +1335,30 @@ impl DiffView {
 }
 
 impl View for DiffView {
-    fn handle_key(&mut self, key: KeyEvent) -> Result<Action> {
+    fn handle_key(&mut self, key: KeyEvent) -> AppResult<Action> {
+        if self.confirm_discard {
+            self.confirm_discard = false;
+            if key.code == KeyCode::Char('y') {
+                self.discard_current_hunk();
+            }
+            return Ok(Action::None);
+        }
+
+        if self.link_picker.is_some() {
+            return Ok(self.handle_link_picker_key(key));
+        }
+
+        if self.selection.is_some() {
+            match key.code {
+                KeyCode::Char('j') | KeyCode::Down => self.move_selection(true),
+                KeyCode::Char('k') | KeyCode::Up => self.move_selection(false),
+                KeyCode::Char('u') => self.stage_selection(),
+                KeyCode::Char('v') | KeyCode::Esc => self.selection = None,
+                _ => {}
+            }
+            return Ok(Action::None);
+        }
+
         match key.code {
             KeyCode::Char('q') | KeyCode::Esc => Ok(Action::PopView),
             KeyCode::Char('j') | KeyCode::Down => {
@@ -342,6 +1377,14 @@ impl View for DiffView {
                 self.scroll_to_bottom();
                 Ok(Action::None)
             }
+            KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.page_down(10);
+                Ok(Action::None)
+            }
+            KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.page_up(10);
+                Ok(Action::None)
+            }
             KeyCode::PageDown => {
                 self.page_down(20);
                 Ok(Action::None)
@@ -350,31 +1393,194 @@ impl View for DiffView {
                 self.page_up(20);
                 Ok(Action::None)
             }
-            KeyCode::Char('?') => {
-                // Show help
-                Ok(Action::PushView(super::view::ViewType::Help))
-            }
-            _ => Ok(Action::None),
-        }
-    }
-
-    fn update(&mut self) -> Result<()> {
-        // Check for diff result from the receiver
-        if let Some(receiver) = &mut self.receiver {
-            if let Ok(result) = receiver.try_recv() {
-                self.loading = false;
-                match result {
-                    Ok(diff) => {
-                        self.lines = self.render_diff_to_lines(&diff);
-                        self.diff = Some(diff);
-                    }
-                    Err(e) => {
-                        self.error = Some(format!("Failed to load diff: {}", e));
-                    }
+            KeyCode::Enter => {
+                if !self.jump_to_listed_file() {
+                    self.expand_large_file();
                 }
+                Ok(Action::None)
             }
+            KeyCode::Char('f') => self.toggle_all_files(),
+            KeyCode::Char('@') => {
+                self.next_hunk();
+                Ok(Action::None)
+            }
+            KeyCode::Char('!') => {
+                self.confirm_discard = self.can_edit_hunks() && self.current_hunk().is_some();
+                Ok(Action::None)
+            }
+            KeyCode::Char('u') if self.can_edit_hunks() => {
+                self.stage_current_hunk();
+                Ok(Action::None)
+            }
+            KeyCode::Char('v') if self.can_edit_hunks() => {
+                self.start_selection();
+                Ok(Action::None)
+            }
+            KeyCode::Char('e') if self.can_edit_hunks() => Ok(self.edit_current_hunk()),
+            KeyCode::Char('o') => self.open_link(),
+            KeyCode::Char('a') => self.annotate(),
+            KeyCode::Char('w') => self.toggle_review_mode(),
+            KeyCode::Char('x') => self.toggle_viewed(),
+            KeyCode::Char('m') => {
+                self.raw_body = !self.raw_body;
+                self.rebuild_lines();
+                Ok(Action::None)
+            }
+            KeyCode::Char('b') => Ok(match self.blame_target() {
+                Some((path, revision)) => Action::OpenBlame {
+                    repo: self.repo.clone(),
+                    path,
+                    revision,
+                },
+                None => Action::None,
+            }),
+            KeyCode::Char('?') => {
+                // Show help
+                Ok(Action::ShowHelp(super::view::ViewType::Diff))
+            }
+            _ => Ok(Action::None),
+        }
+    }
+
+    fn handle_key_with_count(&mut self, key: KeyEvent, count: usize) -> AppResult<Action> {
+        if self.captures_input() {
+            return self.handle_key(key);
+        }
+        match key.code {
+            KeyCode::Char('j') | KeyCode::Down => self.scroll_down(count),
+            KeyCode::Char('k') | KeyCode::Up => self.scroll_up(count),
+            KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.page_down(10 * count)
+            }
+            KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.page_up(10 * count)
+            }
+            KeyCode::PageDown => self.page_down(20 * count),
+            KeyCode::PageUp => self.page_up(20 * count),
+            _ => return self.handle_key(key),
+        }
+        Ok(Action::None)
+    }
+
+    fn update(&mut self) -> AppResult<()> {
+        // Reload once a discard finishes, whether or not it worked
+        let mut failure = None;
+        if let Some(patch_result) = &mut self.patch_result
+            && let Ok(outcome) = patch_result.try_recv()
+        {
+            self.patch_result = None;
+            self.selection = None;
+            self.start_loading();
+            failure = outcome.err().map(AppError::from);
+        }
+
+        // File the note once the editor closes; an emptied one is deleted
+        if let Some((note, reply)) = &mut self.note_reply
+            && let Ok(result) = reply.try_recv()
+        {
+            let note = note.clone();
+            self.note_reply = None;
+            match result {
+                Ok(text) => {
+                    if let Some(notes) = &self.review_notes {
+                        notes.borrow_mut().set(ReviewNote {
+                            text: text.trim().to_string(),
+                            ..note
+                        });
+                        self.notes_changed = true;
+                        self.rebuild_lines();
+                    }
+                }
+                Err(e) => failure = Some(AppError::recoverable(format!("Editor failed: {}", e))),
+            }
+        }
+
+        // Stage the hunk as edited once the editor closes
+        if let Some((original, reply)) = &mut self.edit_reply
+            && let Ok(result) = reply.try_recv()
+        {
+            let original = original.clone();
+            self.edit_reply = None;
+            match result {
+                Ok(text) => match original.from_edited(&text) {
+                    Ok(Some(hunk)) => self.apply_hunk(hunk, true),
+                    Ok(None) => {}
+                    Err(e) => failure = Some(e.into()),
+                },
+                Err(e) => failure = Some(AppError::recoverable(format!("Editor failed: {}", e))),
+            }
+        }
+
+        if let Some(receiver) = &mut self.describe_receiver
+            && let Ok(describe) = receiver.try_recv()
+        {
+            self.describe_receiver = None;
+            self.describe = describe;
+            self.rebuild_lines();
+        }
+
+        if let Some(receiver) = &mut self.body_receiver
+            && let Ok(body) = receiver.try_recv()
+        {
+            self.body_receiver = None;
+            self.body = body;
+            self.rebuild_lines();
+        }
+
+        if let Some(receiver) = &mut self.links_receiver
+            && let Ok(result) = receiver.try_recv()
+        {
+            self.links_receiver = None;
+            match result {
+                Ok(links) => {
+                    self.links = links;
+                    self.rebuild_lines();
+                }
+                Err(e) => {
+                    failure = Some(AppError::recoverable(format!(
+                        "Can't find issue references: {}",
+                        e
+                    )))
+                }
+            }
+        }
+
+        // Check for diff result from the receiver
+        if let Some(receiver) = &mut self.receiver
+            && let Ok(result) = receiver.try_recv()
+        {
+            self.loading = false;
+            match result {
+                Ok(diff) => {
+                    // Pick up where this commit was left, unless reloading
+                    if self.diff.is_none()
+                        && let (Some(id), Some(memory)) = (self.commit_id(), &self.scroll_memory)
+                        && let Some(offset) = memory.borrow_mut().recall(id)
+                    {
+                        self.scroll_offset = offset;
+                    }
+                    self.diff = Some(diff);
+                    self.rebuild_lines();
+                    if let Some(path) = self.focus.take()
+                        && let Some(index) = self
+                            .diff
+                            .iter()
+                            .flat_map(|d| &d.files)
+                            .position(|f| f.path() == path)
+                    {
+                        self.scroll_offset = self.files[index].start;
+                    }
+                }
+                Err(e) => {
+                    self.error = Some(format!("Failed to load diff: {}", e));
+                }
+            }
+        }
+
+        match failure {
+            Some(e) => Err(e),
+            None => Ok(()),
         }
-        Ok(())
     }
 
     fn draw(&mut self, frame: &mut Frame, area: Rect) {
@@ -385,7 +1591,7 @@ impl View for DiffView {
         if self.loading {
             let loading_text = "Loading diff...";
             let paragraph = Paragraph::new(loading_text)
-                .block(Block::default().title("Diff").borders(Borders::ALL))
+                .block(self.symbols.block().title("Diff"))
                 .style(Style::default().fg(self.colors.modified));
             frame.render_widget(paragraph, area);
             return;
@@ -394,55 +1600,131 @@ impl View for DiffView {
         // Show error if any
         if let Some(error) = &self.error {
             let paragraph = Paragraph::new(error.as_str())
-                .block(Block::default().title("Diff - Error").borders(Borders::ALL))
+                .block(self.symbols.block().title("Diff - Error"))
                 .style(Style::default().fg(self.colors.deleted));
             frame.render_widget(paragraph, area);
             return;
         }
 
-        // Calculate scrollbar position
-        let scrollbar_state = if self.lines.len() > visible_height {
-            let state = ScrollbarState::default()
-                .content_length(self.lines.len())
-                .position(self.scroll_offset);
-            Some(state)
-        } else {
-            None
+        // Show diff content, marking the selected lines or else the hunk that
+        // `u` and `!` act on
+        self.visible_height = visible_height;
+        let marked_lines = match (&self.selection, &self.source) {
+            (Some(selection), _) => {
+                let (first, last) = selection.range().into_inner();
+                Some(selection.rendered_line(first)..=selection.rendered_line(last))
+            }
+            (None, DiffSource::UnstagedFile { .. }) => self
+                .current_hunk()
+                .map(|span| span.header_line..=span.header_line),
+            _ => None,
         };
-
-        // Show diff content
-        let visible_lines: Vec<Line> = self
-            .lines
+        // Pinned headers take the top rows, underlined to set them apart
+        // from the content scrolling beneath
+        let pinned = match visible_height > 2 {
+            true => self.sticky_lines(),
+            false => Vec::new(),
+        };
+        let mut visible_lines: Vec<Line> = pinned
             .iter()
-            .skip(self.scroll_offset)
-            .take(visible_height)
-            .cloned()
+            .map(|&index| self.lines[index].clone())
             .collect();
+        if let Some(last) = visible_lines.pop() {
+            visible_lines.push(last.patch_style(Modifier::UNDERLINED));
+        }
+        let content_height = visible_height - pinned.len();
+        visible_lines.extend(
+            self.lines
+                .iter()
+                .enumerate()
+                .skip(self.scroll_offset)
+                .take(content_height)
+                .map(|(index, line)| {
+                    match marked_lines.as_ref().is_some_and(|r| r.contains(&index)) {
+                        true => line.clone().patch_style(Modifier::REVERSED),
+                        false => line.clone(),
+                    }
+                }),
+        );
+
+        let shown = visible_lines.len() - pinned.len();
+        let title = self.render_title(self.scroll_offset + shown);
+
+        // Long diffs get a minimap of where the changes are, in a column
+        // just inside the scrollbar
+        let mut block = self.symbols.block().title(title);
+        if self.review_mode {
+            let (viewed, files) = self.review_progress();
+            let progress = format!(" {}/{} files reviewed ", viewed, files);
+            block = block.title_bottom(Line::from(progress).right_aligned());
+        }
+        let mut text_area = block.inner(area);
+        frame.render_widget(block, area);
+        if self.lines.len() > visible_height && text_area.width > 1 {
+            text_area.width -= 1;
+            let minimap_area = Rect {
+                x: text_area.right(),
+                width: 1,
+                ..text_area
+            };
+            render_minimap(
+                frame,
+                minimap_area,
+                &self.colors,
+                &self.changes,
+                self.lines.len(),
+            );
+        }
+        frame.render_widget(Paragraph::new(visible_lines), text_area);
 
-        let title = format!(
-            "Diff - {} / {} lines",
-            self.scroll_offset + visible_lines.len().min(visible_height),
-            self.lines.len()
+        render_scrollbar(
+            frame,
+            area,
+            &self.symbols,
+            self.lines.len(),
+            self.scroll_offset,
         );
 
-        let paragraph = Paragraph::new(visible_lines)
-            .block(Block::default().title(title).borders(Borders::ALL));
-
-        frame.render_widget(paragraph, area);
-
-        // Render scrollbar if needed
-        if let Some(mut state) = scrollbar_state {
-            let scrollbar = Scrollbar::default()
-                .orientation(ScrollbarOrientation::VerticalRight)
-                .begin_symbol(Some("↑"))
-                .end_symbol(Some("↓"));
-            frame.render_stateful_widget(
-                scrollbar,
-                area.inner(ratatui::layout::Margin {
-                    vertical: 1,
-                    horizontal: 0,
-                }),
-                &mut state,
+        if self.confirm_discard {
+            let popup = centered_rect(52, 3, area);
+            frame.render_widget(Clear, popup);
+            frame.render_widget(
+                Paragraph::new("Discard this hunk from the working tree? (y/n)")
+                    .block(self.symbols.block().title("Discard hunk"))
+                    .style(Style::default().fg(self.colors.deleted)),
+                popup,
+            );
+        }
+
+        if let Some(selected) = self.link_picker {
+            let lines: Vec<Line> = self
+                .links
+                .iter()
+                .enumerate()
+                .map(|(i, link)| {
+                    let style = match i == selected {
+                        true => self.colors.selected.add_modifier(Modifier::BOLD),
+                        false => Style::default(),
+                    };
+                    Line::from(vec![
+                        Span::styled(
+                            format!(" {} ", i + 1),
+                            Style::default().fg(self.colors.commit_hash),
+                        ),
+                        Span::styled(format!("{} ", link.text), style),
+                        Span::styled(
+                            format!("{} ", link.url),
+                            Style::default().fg(self.colors.date),
+                        ),
+                    ])
+                })
+                .collect();
+            let width = lines.iter().map(Line::width).max().unwrap_or(0).max(20) as u16 + 2;
+            let popup = centered_rect(width, lines.len() as u16 + 2, area);
+            frame.render_widget(Clear, popup);
+            frame.render_widget(
+                Paragraph::new(lines).block(self.symbols.block().title("Open reference")),
+                popup,
             );
         }
     }
@@ -451,6 +1733,48 @@ impl View for DiffView {
         "Diff"
     }
 
+    fn view_type(&self) -> ViewType {
+        ViewType::Diff
+    }
+
+    fn selection(&self) -> Selection {
+        match &self.source {
+            DiffSource::Commit { id, .. } | DiffSource::Range { to: id, .. } => Selection {
+                commit: Some(*id),
+                ..Selection::default()
+            },
+            DiffSource::StagedFile { path } | DiffSource::UnstagedFile { path } => Selection {
+                file: Some(path.clone()),
+                ..Selection::default()
+            },
+            DiffSource::Staged | DiffSource::Unstaged => Selection {
+                file: self.blame_target().map(|(path, _)| path),
+                ..Selection::default()
+            },
+        }
+    }
+
+    fn captures_input(&self) -> bool {
+        self.confirm_discard || self.selection.is_some() || self.link_picker.is_some()
+    }
+
+    fn refresh(&mut self) {
+        self.selection = None;
+        self.error = None;
+        self.start_loading();
+    }
+
+    fn poll_action(&mut self) -> Action {
+        match std::mem::take(&mut self.notes_changed) {
+            true => Action::SaveReviewNotes,
+            false => Action::None,
+        }
+    }
+
+    fn busy(&self) -> Option<&'static str> {
+        self.patch_result.as_ref().map(|_| "applying a hunk")
+    }
+
     fn on_activate(&mut self) -> Result<()> {
         // Start loading diff when view is activated
         if self.diff.is_none() && !self.loading {
@@ -458,32 +1782,30 @@ impl View for DiffView {
         }
         Ok(())
     }
+
+    fn on_deactivate(&mut self) -> Result<()> {
+        if self.diff.is_some()
+            && let (Some(id), Some(memory)) = (self.commit_id(), &self.scroll_memory)
+        {
+            memory.borrow_mut().remember(id, self.scroll_offset);
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::testing::RepoBuilder;
+    use std::rc::Rc;
     use tempfile::TempDir;
 
     async fn create_test_repo_with_commit() -> (TempDir, Repository, Oid) {
-        let temp_dir = TempDir::new().unwrap();
-        let repo_path = temp_dir.path();
-
-        let git_repo = git2::Repository::init(repo_path).unwrap();
-        let sig = git2::Signature::now("Test", "test@example.com").unwrap();
-
-        // Create initial commit
-        std::fs::write(repo_path.join("test.txt"), "line1\nline2\nline3\n").unwrap();
-        let mut index = git_repo.index().unwrap();
-        index.add_path(std::path::Path::new("test.txt")).unwrap();
-        index.write().unwrap();
-        let tree_id = index.write_tree().unwrap();
-        let tree = git_repo.find_tree(tree_id).unwrap();
-        let commit_id = git_repo
-            .commit(Some("HEAD"), &sig, &sig, "Initial commit", &tree, &[])
-            .unwrap();
-
-        let repo = Repository::open(repo_path).await.unwrap();
+        let builder = RepoBuilder::new()
+            .file("test.txt", "line1\nline2\nline3\n")
+            .commit("Initial commit");
+        let commit_id = builder.head();
+        let (temp_dir, repo) = builder.open().await;
         (temp_dir, repo, commit_id)
     }
 
@@ -495,7 +1817,12 @@ mod tests {
     #[tokio::test]
     async fn test_diff_view_creation() {
         let (_temp_dir, repo, commit_id) = create_test_repo_with_commit().await;
-        let view = DiffView::new(repo, commit_id, "Test commit".to_string(), test_color_scheme());
+        let view = DiffView::new(
+            repo,
+            commit_id,
+            "Test commit".to_string(),
+            test_color_scheme(),
+        );
         assert_eq!(view.title(), "Diff");
         assert!(view.diff.is_none());
     }
@@ -503,7 +1830,12 @@ mod tests {
     #[tokio::test]
     async fn test_diff_view_load() {
         let (_temp_dir, repo, commit_id) = create_test_repo_with_commit().await;
-        let mut view = DiffView::new(repo, commit_id, "Test commit".to_string(), test_color_scheme());
+        let mut view = DiffView::new(
+            repo,
+            commit_id,
+            "Test commit".to_string(),
+            test_color_scheme(),
+        );
         view.start_loading();
 
         // Give the background task time to complete
@@ -516,10 +1848,65 @@ mod tests {
         assert!(!view.lines.is_empty());
     }
 
+    #[tokio::test]
+    async fn test_reopened_commit_keeps_scroll_position() {
+        let (_temp_dir, repo, commit_id) = create_test_repo_with_commit().await;
+        let memory = crate::views::scroll_memory::ScrollMemory::shared();
+        let open = || {
+            DiffView::new(
+                repo.clone(),
+                commit_id,
+                "Test commit".to_string(),
+                test_color_scheme(),
+            )
+            .with_scroll_memory(Rc::clone(&memory))
+        };
+
+        let mut view = open();
+        view.on_activate().unwrap();
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        view.update().unwrap();
+        view.scroll_down(2);
+        let offset = view.scroll_offset;
+        assert!(offset > 0);
+        view.on_deactivate().unwrap();
+
+        let mut view = open();
+        view.on_activate().unwrap();
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        view.update().unwrap();
+        assert_eq!(view.scroll_offset, offset);
+    }
+
+    #[tokio::test]
+    async fn test_blame_file_under_cursor() {
+        let (_temp_dir, repo, commit_id) = create_test_repo_with_commit().await;
+        let mut view = DiffView::new(
+            repo,
+            commit_id,
+            "Test commit".to_string(),
+            test_color_scheme(),
+        );
+        view.start_loading();
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        view.update().unwrap();
+
+        let action = view.handle_key(KeyEvent::from(KeyCode::Char('b'))).unwrap();
+        assert!(matches!(
+            action,
+            Action::OpenBlame { path, revision, .. } if path == "test.txt" && revision == Some(commit_id)
+        ));
+    }
+
     #[tokio::test]
     async fn test_diff_view_scrolling() {
         let (_temp_dir, repo, commit_id) = create_test_repo_with_commit().await;
-        let mut view = DiffView::new(repo, commit_id, "Test commit".to_string(), test_color_scheme());
+        let mut view = DiffView::new(
+            repo,
+            commit_id,
+            "Test commit".to_string(),
+            test_color_scheme(),
+        );
         view.start_loading();
 
         // Give the background task time to complete
@@ -537,4 +1924,576 @@ mod tests {
         view.scroll_to_bottom();
         assert!(view.scroll_offset > 0);
     }
+
+    #[tokio::test]
+    async fn test_minimap_marks_changes_in_long_diffs() {
+        use ratatui::{Terminal, backend::TestBackend};
+
+        let original: String = (0..40).map(|i| format!("line {}\n", i)).collect();
+        let builder = RepoBuilder::new()
+            .file("long.txt", &original)
+            .commit("Initial")
+            .file("long.txt", &format!("{}added\n", original))
+            .commit("Append");
+        let commit_id = builder.head();
+        let (_temp_dir, repo) = builder.open().await;
+        let mut view = DiffView::new(repo, commit_id, "Append".to_string(), test_color_scheme());
+        view.start_loading();
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        view.update().unwrap();
+        assert_eq!(view.changes.len(), 1);
+        assert_eq!(view.changes[0].1, LineType::Addition);
+
+        // The diff fits in a tall terminal, so there is no minimap
+        let column = |height: u16, view: &mut DiffView| -> String {
+            let mut terminal = Terminal::new(TestBackend::new(40, height)).unwrap();
+            terminal
+                .draw(|frame| view.draw(frame, frame.area()))
+                .unwrap();
+            let buffer = terminal.backend().buffer();
+            (1..height - 1)
+                .map(|y| buffer[(38, y)].symbol().to_string())
+                .collect()
+        };
+        assert!(!column(40, &mut view).contains('+'));
+
+        let minimap = column(8, &mut view);
+        assert_eq!(minimap.trim(), "+");
+    }
+
+    #[tokio::test]
+    async fn test_headers_stick_while_scrolling() {
+        use ratatui::{Terminal, backend::TestBackend};
+
+        let original: String = (0..40).map(|i| format!("line {}\n", i)).collect();
+        let builder = RepoBuilder::new()
+            .file("long.txt", "")
+            .commit("Initial")
+            .file("long.txt", &original)
+            .commit("Fill");
+        let commit_id = builder.head();
+        let (_temp_dir, repo) = builder.open().await;
+        let mut view = DiffView::new(repo, commit_id, "Fill".to_string(), test_color_scheme())
+            .with_sticky_headers(StickyHeaders::Hunk);
+        view.start_loading();
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        view.update().unwrap();
+
+        let rows = |view: &mut DiffView| -> Vec<String> {
+            let mut terminal = Terminal::new(TestBackend::new(60, 8)).unwrap();
+            terminal
+                .draw(|frame| view.draw(frame, frame.area()))
+                .unwrap();
+            let buffer = terminal.backend().buffer();
+            (1..7)
+                .map(|y| {
+                    (1..58)
+                        .map(|x| buffer[(x, y)].symbol())
+                        .collect::<String>()
+                        .trim_end()
+                        .to_string()
+                })
+                .collect()
+        };
+        assert!(view.sticky_lines().is_empty());
+
+        let hunk = view.hunks[0];
+        view.scroll_offset = hunk.header_line + 10;
+        let shown = rows(&mut view);
+        assert_eq!(shown[0], "diff --git a/long.txt b/long.txt");
+        assert!(shown[1].starts_with("@@ -0,0 +1,40 @@"), "{:?}", shown);
+        assert!(shown[2].ends_with("+line 9"), "{:?}", shown);
+
+        view.sticky = StickyHeaders::File;
+        let shown = rows(&mut view);
+        assert_eq!(shown[0], "diff --git a/long.txt b/long.txt");
+        assert!(shown[1].ends_with("+line 9"), "{:?}", shown);
+
+        view.sticky = StickyHeaders::None;
+        assert!(rows(&mut view)[0].ends_with("+line 9"));
+    }
+
+    #[tokio::test]
+    async fn test_review_notes_on_hunks_and_files() {
+        let builder = RepoBuilder::new()
+            .file("a.txt", "a\nb\n")
+            .commit("Initial")
+            .file("a.txt", "a\nB\n")
+            .commit("Change b");
+        let commit_id = builder.head();
+        let (_temp_dir, repo) = builder.open().await;
+        let notes = crate::config::ReviewNotes::default().shared();
+        let mut view = DiffView::new(repo, commit_id, "Change b".to_string(), test_color_scheme())
+            .with_review_notes(notes.clone());
+        view.start_loading();
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        view.update().unwrap();
+
+        let write_note = |view: &mut DiffView, line: usize, text: &str| {
+            view.scroll_offset = line;
+            let Action::EditText(request) =
+                view.handle_key(KeyEvent::from(KeyCode::Char('a'))).unwrap()
+            else {
+                panic!("expected an edit request");
+            };
+            request.finish(Ok(text.to_string()));
+            view.update().unwrap();
+        };
+        let hunk = view.hunks[0];
+        write_note(
+            &mut view,
+            hunk.header_line + 1,
+            "Why capital?\nIt reads oddly\n",
+        );
+        assert_eq!(view.poll_action(), Action::SaveReviewNotes);
+        assert_eq!(view.poll_action(), Action::None);
+        let file_header = view.files[0].start;
+        write_note(&mut view, file_header, "Rename this file");
+
+        let notes = notes.borrow();
+        let on_hunk = notes
+            .find(&commit_id.to_string(), "a.txt", Some("@@ -1,2 +1,2 @@"))
+            .unwrap();
+        assert_eq!(
+            (on_hunk.summary.as_str(), on_hunk.text.as_str()),
+            ("Change b", "Why capital?\nIt reads oddly")
+        );
+        assert_eq!(
+            notes
+                .find(&commit_id.to_string(), "a.txt", None)
+                .unwrap()
+                .text,
+            "Rename this file"
+        );
+        let text = |line: &Line| {
+            line.spans
+                .iter()
+                .map(|span| span.content.as_ref())
+                .collect::<String>()
+        };
+        assert!(text(&view.lines[hunk.header_line]).ends_with("✎ Why capital?"));
+        assert!(text(&view.lines[file_header]).ends_with("✎ Rename this file"));
+    }
+
+    #[tokio::test]
+    async fn test_review_mode_marks_files_viewed() {
+        use ratatui::{Terminal, backend::TestBackend};
+
+        let builder = RepoBuilder::new()
+            .file("a.txt", "a\n")
+            .file("b.txt", "b\n")
+            .file("c.txt", "c\n")
+            .commit("Add files");
+        let commit_id = builder.head();
+        let (_temp_dir, repo) = builder.open().await;
+        let viewed = HashMap::from([(commit_id.to_string(), vec!["b.txt".to_string()])]);
+        let mut view = DiffView::new(
+            repo,
+            commit_id,
+            "Add files".to_string(),
+            test_color_scheme(),
+        )
+        .with_viewed_files(&viewed);
+        view.start_loading();
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        view.update().unwrap();
+        assert!(view.review_mode);
+        assert_eq!(view.review_progress(), (1, 3));
+        assert_eq!(view.files[1].len(), 2);
+
+        // Marking a.txt moves on past the viewed b.txt
+        let action = view.handle_key(KeyEvent::from(KeyCode::Char('x'))).unwrap();
+        let marked = Action::MarkViewed {
+            diff: commit_id.to_string(),
+            path: "a.txt".to_string(),
+            viewed: true,
+        };
+        assert_eq!(action, marked);
+        assert_eq!(view.scroll_offset, view.files[2].start);
+        assert_eq!(view.files[0].len(), 2);
+
+        let mut terminal = Terminal::new(TestBackend::new(60, 8)).unwrap();
+        terminal
+            .draw(|frame| view.draw(frame, frame.area()))
+            .unwrap();
+        let buffer = terminal.backend().buffer();
+        let footer: String = (0..60).map(|x| buffer[(x, 7)].symbol()).collect();
+        assert!(footer.contains(" 2/3 files reviewed "), "{}", footer);
+
+        // Unmarking keeps the file in view, unfolded
+        let action = view.handle_key(KeyEvent::from(KeyCode::Char('x'))).unwrap();
+        assert!(matches!(action, Action::MarkViewed { viewed: true, .. }));
+        let action = view.handle_key(KeyEvent::from(KeyCode::Char('x'))).unwrap();
+        assert!(matches!(action, Action::MarkViewed { viewed: false, .. }));
+        assert_eq!(view.scroll_offset, view.files[2].start);
+        assert!(view.files[2].len() > 2);
+
+        view.handle_key(KeyEvent::from(KeyCode::Char('w'))).unwrap();
+        assert!(view.files.iter().all(|file| file.len() > 2));
+    }
+
+    #[tokio::test]
+    async fn test_switch_to_every_file_at_the_same_file() {
+        let (_temp_dir, repo) = RepoBuilder::new()
+            .file("a.txt", "a\n")
+            .file("b.txt", "b\n")
+            .file("c.txt", "c\n")
+            .commit("Initial")
+            .write("a.txt", "A\n")
+            .write("b.txt", "B\n")
+            .write("c.txt", "C\n")
+            .open()
+            .await;
+        let mut view = DiffView::new_unstaged(repo, "b.txt".to_string(), test_color_scheme());
+        view.start_loading();
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        view.update().unwrap();
+        assert_eq!(view.files.len(), 1);
+
+        view.handle_key(KeyEvent::from(KeyCode::Char('f'))).unwrap();
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        view.update().unwrap();
+        assert_eq!(view.files.len(), 3);
+        assert_eq!(view.scroll_offset, view.files[1].start);
+        assert_eq!(view.selection().file.as_deref(), Some("b.txt"));
+
+        // Back to just the file at the top
+        view.scroll_offset = view.files[2].start;
+        view.handle_key(KeyEvent::from(KeyCode::Char('f'))).unwrap();
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        view.update().unwrap();
+        assert_eq!(view.selection().file.as_deref(), Some("c.txt"));
+        assert_eq!(view.files.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_enter_on_listed_file_scrolls_to_it() {
+        let builder = RepoBuilder::new()
+            .file("a.txt", "a\n")
+            .file("b.txt", "b\n")
+            .file("c.txt", "c\n")
+            .commit("Add files");
+        let commit_id = builder.head();
+        let (_temp_dir, repo) = builder.open().await;
+        let mut view = DiffView::new(
+            repo,
+            commit_id,
+            "Add files".to_string(),
+            test_color_scheme(),
+        );
+        view.start_loading();
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        view.update().unwrap();
+        assert_eq!(view.stat.len(), 3);
+        assert!(view.handle_key(KeyEvent::from(KeyCode::Char('f'))).is_err());
+
+        view.scroll_offset = view.stat.start + 2;
+        view.handle_key(KeyEvent::from(KeyCode::Enter)).unwrap();
+        assert_eq!(view.scroll_offset, view.files[2].start);
+    }
+
+    #[tokio::test]
+    async fn test_title_template() {
+        let builder = RepoBuilder::new()
+            .file("a.txt", "a\n")
+            .file("b.txt", "b\n")
+            .commit("Initial")
+            .file("a.txt", "a\nmore\n")
+            .file("b.txt", "b\nmore\n")
+            .commit("Grow");
+        let commit_id = builder.head();
+        let (_temp_dir, repo) = builder.open().await;
+        let mut view = DiffView::new(repo, commit_id, "Grow".to_string(), test_color_scheme())
+            .with_title_template(
+                "%(commit) %(summary): %(files) files, hunk %(hunk)/%(hunks)".to_string(),
+            );
+        view.start_loading();
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        view.update().unwrap();
+
+        let short_id = format!("{:.7}", commit_id.to_string());
+        assert_eq!(
+            view.render_title(5),
+            format!("{} Grow: 2 files, hunk 1/2", short_id)
+        );
+        // The first jump brings hunk 1 to the top, the second hunk 2
+        view.next_hunk();
+        view.next_hunk();
+        assert_eq!(
+            view.render_title(5),
+            format!("{} Grow: 2 files, hunk 2/2", short_id)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_commit_header_shows_nearest_tag() {
+        let builder = RepoBuilder::new()
+            .file("a.txt", "a\n")
+            .commit("Release")
+            .tag("v2.0");
+        let commit_id = builder.head();
+        let (_temp_dir, repo) = builder.open().await;
+        let mut view = DiffView::new(repo, commit_id, "Release".to_string(), test_color_scheme());
+        view.start_loading();
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        view.update().unwrap();
+
+        assert_eq!(view.describe.as_deref(), Some("v2.0"));
+        assert!(view.lines[0].to_string().ends_with("(v2.0)"));
+    }
+
+    #[tokio::test]
+    async fn test_commit_body_formatted_or_raw() {
+        let builder = RepoBuilder::new()
+            .file("a.txt", "a\n")
+            .commit("Speed up parsing\n\n- Cache `tokens`");
+        let commit_id = builder.head();
+        let (_temp_dir, repo) = builder.open().await;
+        let mut view = DiffView::new(
+            repo,
+            commit_id,
+            "Speed up parsing".to_string(),
+            test_color_scheme(),
+        );
+        view.start_loading();
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        view.update().unwrap();
+        assert_eq!(view.lines[3].to_string(), "• Cache tokens");
+
+        view.handle_key(KeyEvent::from(KeyCode::Char('m'))).unwrap();
+        assert_eq!(view.lines[3].to_string(), "- Cache `tokens`");
+    }
+
+    #[tokio::test]
+    async fn test_opens_issue_references() {
+        let builder = RepoBuilder::new()
+            .file("a.txt", "a\n")
+            .commit("Fix login (ENG-1)\n\nAlso ENG-2");
+        let commit_id = builder.head();
+        let (_temp_dir, repo) = builder.open().await;
+        let patterns = vec![LinkPattern {
+            pattern: r"ENG-\d+".to_string(),
+            url: "https://tracker.example.com/%(0)".to_string(),
+        }];
+        let mut view = DiffView::new(
+            repo,
+            commit_id,
+            "Fix login (ENG-1)".to_string(),
+            test_color_scheme(),
+        )
+        .with_link_patterns(patterns);
+        view.start_loading();
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        view.update().unwrap();
+        assert_eq!(view.lines[2].to_string(), "Refs: ENG-1 ENG-2");
+
+        // Two references are listed to pick from
+        assert_eq!(
+            view.handle_key(KeyEvent::from(KeyCode::Char('o'))).unwrap(),
+            Action::None
+        );
+        assert!(view.captures_input());
+        view.handle_key(KeyEvent::from(KeyCode::Char('j'))).unwrap();
+        assert_eq!(
+            view.handle_key(KeyEvent::from(KeyCode::Enter)).unwrap(),
+            Action::OpenUrl("https://tracker.example.com/ENG-2".to_string())
+        );
+        assert!(!view.captures_input());
+        view.handle_key(KeyEvent::from(KeyCode::Char('o'))).unwrap();
+        assert_eq!(
+            view.handle_key(KeyEvent::from(KeyCode::Char('1'))).unwrap(),
+            Action::OpenUrl("https://tracker.example.com/ENG-1".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_large_file_collapsed_until_expanded() {
+        let (_temp_dir, repo, commit_id) = create_test_repo_with_commit().await;
+        let mut view = DiffView::new(
+            repo,
+            commit_id,
+            "Test commit".to_string(),
+            test_color_scheme(),
+        )
+        .with_max_file_bytes(4);
+        view.start_loading();
+
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        view.update().unwrap();
+
+        assert_eq!(view.large_files.len(), 1);
+        assert!(view.diff.as_ref().unwrap().files[0].hunks.is_empty());
+        let placeholder = view.lines[view.large_files[0].0].to_string();
+        assert!(placeholder.starts_with("large file, "), "{}", placeholder);
+        assert!(placeholder.contains(" lines changed"), "{}", placeholder);
+        let collapsed_len = view.lines.len();
+
+        assert!(view.expand_large_file());
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        view.update().unwrap();
+        assert!(view.large_files.is_empty());
+        assert!(view.lines.len() > collapsed_len);
+        assert!(!view.expand_large_file());
+    }
+
+    #[tokio::test]
+    async fn test_discard_hunk_under_cursor() {
+        let original: String = (1..=30).map(|i| format!("line {}\n", i)).collect();
+        let modified = original
+            .replace("line 2\n", "two\n")
+            .replace("line 25\n", "twenty-five\n");
+        let (temp_dir, repo) = RepoBuilder::new()
+            .file("test.txt", &original)
+            .commit("Initial")
+            .write("test.txt", &modified)
+            .open()
+            .await;
+        let mut view = DiffView::new_unstaged(repo, "test.txt".to_string(), test_color_scheme());
+        view.start_loading();
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        view.update().unwrap();
+        assert_eq!(view.hunks.len(), 2);
+
+        // Anything but y cancels the confirmation
+        view.handle_key(KeyEvent::from(KeyCode::Char('!'))).unwrap();
+        assert!(view.captures_input());
+        view.handle_key(KeyEvent::from(KeyCode::Char('n'))).unwrap();
+        assert!(!view.captures_input());
+
+        // Move to the second hunk and discard it
+        view.handle_key(KeyEvent::from(KeyCode::Char('@'))).unwrap();
+        view.handle_key(KeyEvent::from(KeyCode::Char('@'))).unwrap();
+        assert_eq!(view.current_hunk().map(|h| h.hunk), Some(1));
+        view.handle_key(KeyEvent::from(KeyCode::Char('!'))).unwrap();
+        view.handle_key(KeyEvent::from(KeyCode::Char('y'))).unwrap();
+
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        view.update().unwrap();
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        view.update().unwrap();
+
+        let content = std::fs::read_to_string(temp_dir.path().join("test.txt")).unwrap();
+        assert_eq!(content, original.replace("line 2\n", "two\n"));
+        assert_eq!(view.hunks.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_discard_only_in_unstaged_diffs() {
+        let (_temp_dir, repo, commit_id) = create_test_repo_with_commit().await;
+        let mut view = DiffView::new(
+            repo,
+            commit_id,
+            "Test commit".to_string(),
+            test_color_scheme(),
+        );
+        view.start_loading();
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        view.update().unwrap();
+
+        view.handle_key(KeyEvent::from(KeyCode::Char('!'))).unwrap();
+        assert!(!view.captures_input());
+    }
+
+    #[tokio::test]
+    async fn test_stage_selected_lines_then_hunk() {
+        let (temp_dir, repo) = RepoBuilder::new()
+            .file("test.txt", "a\nb\nc\n")
+            .commit("Initial")
+            .write("test.txt", "a\nB\nc\nd\n")
+            .open()
+            .await;
+        let mut view = DiffView::new_unstaged(repo, "test.txt".to_string(), test_color_scheme());
+        view.start_loading();
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        view.update().unwrap();
+
+        let staged = || {
+            let git_repo = git2::Repository::open(temp_dir.path()).unwrap();
+            let index = git_repo.index().unwrap();
+            let entry = index.get_path(std::path::Path::new("test.txt"), 0).unwrap();
+            String::from_utf8(git_repo.find_blob(entry.id).unwrap().content().to_vec()).unwrap()
+        };
+
+        // Select the b -> B edit: the selection starts on the first change
+        view.handle_key(KeyEvent::from(KeyCode::Char('v'))).unwrap();
+        assert!(view.captures_input());
+        view.handle_key(KeyEvent::from(KeyCode::Char('j'))).unwrap();
+        assert_eq!(view.selection.map(|s| s.range()), Some(1..=2));
+        view.handle_key(KeyEvent::from(KeyCode::Char('u'))).unwrap();
+        assert!(!view.captures_input());
+
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        view.update().unwrap();
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        view.update().unwrap();
+        assert_eq!(staged(), "a\nB\nc\n");
+
+        // Stage the rest of the hunk
+        view.handle_key(KeyEvent::from(KeyCode::Char('u'))).unwrap();
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        view.update().unwrap();
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        view.update().unwrap();
+        assert_eq!(staged(), "a\nB\nc\nd\n");
+        assert!(view.hunks.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_stage_edited_hunk() {
+        let (temp_dir, repo) = RepoBuilder::new()
+            .file("test.txt", "a\nb\n")
+            .commit("Initial")
+            .write("test.txt", "a\nB\n")
+            .open()
+            .await;
+        let mut view = DiffView::new_unstaged(repo, "test.txt".to_string(), test_color_scheme());
+        view.start_loading();
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        view.update().unwrap();
+
+        let Action::EditText(request) =
+            view.handle_key(KeyEvent::from(KeyCode::Char('e'))).unwrap()
+        else {
+            panic!("expected an edit request");
+        };
+        assert!(request.text.starts_with("@@ -1,2 +1,2 @@\n a\n-b\n+B\n#"));
+
+        // Stage a different replacement than the working tree has
+        request.finish(Ok("@@ -1,2 +1,2 @@\n a\n-b\n+staged\n".to_string()));
+        view.update().unwrap();
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        view.update().unwrap();
+
+        let git_repo = git2::Repository::open(temp_dir.path()).unwrap();
+        let index = git_repo.index().unwrap();
+        let entry = index.get_path(std::path::Path::new("test.txt"), 0).unwrap();
+        assert_eq!(
+            git_repo.find_blob(entry.id).unwrap().content(),
+            b"a\nstaged\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_invalid_edit_is_reported() {
+        let (_temp_dir, repo) = RepoBuilder::new()
+            .file("test.txt", "a\n")
+            .commit("Initial")
+            .write("test.txt", "b\n")
+            .open()
+            .await;
+        let mut view = DiffView::new_unstaged(repo, "test.txt".to_string(), test_color_scheme());
+        view.start_loading();
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        view.update().unwrap();
+
+        let Action::EditText(request) =
+            view.handle_key(KeyEvent::from(KeyCode::Char('e'))).unwrap()
+        else {
+            panic!("expected an edit request");
+        };
+        request.finish(Ok("not a diff line\n".to_string()));
+        let error = view.update().unwrap_err();
+        assert!(error.is_recoverable());
+        assert!(error.to_string().contains("not a diff line"));
+    }
 }
@@ -0,0 +1,345 @@
+use super::popup::centered_rect;
+use super::search;
+use crate::config::{ColorScheme, Symbols};
+use crate::git::{Commit, Conventional};
+use chrono::NaiveDate;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Clear, Paragraph},
+};
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// Criteria for narrowing down the commit list
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CommitFilter {
+    /// Substring of the author name or email
+    pub author: Option<String>,
+    /// Only commits touching this path (applied by the commit walker)
+    pub path: Option<String>,
+    /// Only commits authored on or after this date
+    pub since: Option<NaiveDate>,
+    /// Only commits authored on or before this date
+    pub until: Option<NaiveDate>,
+    /// Substring of the commit message
+    pub text: Option<String>,
+    /// Conventional-commit types, like `feat`, of which the commit must
+    /// be one
+    pub types: Vec<String>,
+}
+
+impl CommitFilter {
+    /// Check if no criteria are set
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Check a commit against the author, date, and text criteria
+    ///
+    /// The path criterion needs the commit's tree, so it is applied by
+    /// the walker rather than here.
+    pub fn matches(&self, commit: &Commit) -> bool {
+        if let Some(author) = &self.author
+            && !search::matches(&commit.author, author, false)
+            && !search::matches(&commit.author_email, author, false)
+        {
+            return false;
+        }
+
+        let date = commit.date.date_naive();
+        if self.since.is_some_and(|since| date < since) {
+            return false;
+        }
+        if self.until.is_some_and(|until| date > until) {
+            return false;
+        }
+
+        if let Some(text) = &self.text
+            && !search::matches(&commit.message, text, false)
+        {
+            return false;
+        }
+
+        if !self.types.is_empty() {
+            let kind = Conventional::parse(&commit.summary).map(|c| c.kind.to_ascii_lowercase());
+            if !kind.is_some_and(|kind| self.types.contains(&kind)) {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// Short labels for the active criteria (e.g. "author:alice")
+    pub fn chips(&self) -> Vec<String> {
+        let mut chips = Vec::new();
+        if let Some(author) = &self.author {
+            chips.push(format!("author:{}", author));
+        }
+        if let Some(path) = &self.path {
+            chips.push(format!("path:{}", path));
+        }
+        if let Some(since) = &self.since {
+            chips.push(format!("since:{}", since.format(DATE_FORMAT)));
+        }
+        if let Some(until) = &self.until {
+            chips.push(format!("until:{}", until.format(DATE_FORMAT)));
+        }
+        if let Some(text) = &self.text {
+            chips.push(format!("text:{}", text));
+        }
+        if !self.types.is_empty() {
+            chips.push(format!("type:{}", self.types.join(",")));
+        }
+        chips
+    }
+}
+
+/// Result of a key press in the filter panel
+#[derive(Debug, Clone, PartialEq)]
+pub enum FilterPanelEvent {
+    /// Keep editing
+    None,
+    /// Apply the edited filter and close the panel
+    Apply(CommitFilter),
+    /// Close the panel without changing the filter
+    Cancel,
+}
+
+const FIELDS: [&str; 6] = ["Author", "Path", "Since", "Until", "Text", "Types"];
+
+/// Popup for editing a `CommitFilter`
+#[derive(Debug, Clone)]
+pub struct FilterPanel {
+    values: [String; 6],
+    focus: usize,
+    error: Option<String>,
+}
+
+impl FilterPanel {
+    /// Create a panel pre-filled with the given filter
+    pub fn new(filter: &CommitFilter) -> Self {
+        let date = |d: &Option<NaiveDate>| {
+            d.map(|d| d.format(DATE_FORMAT).to_string())
+                .unwrap_or_default()
+        };
+        Self {
+            values: [
+                filter.author.clone().unwrap_or_default(),
+                filter.path.clone().unwrap_or_default(),
+                date(&filter.since),
+                date(&filter.until),
+                filter.text.clone().unwrap_or_default(),
+                filter.types.join(", "),
+            ],
+            focus: 0,
+            error: None,
+        }
+    }
+
+    /// Handle a key press
+    pub fn handle_key(&mut self, key: KeyEvent) -> FilterPanelEvent {
+        match key.code {
+            KeyCode::Esc => return FilterPanelEvent::Cancel,
+            KeyCode::Enter => match self.build() {
+                Ok(filter) => return FilterPanelEvent::Apply(filter),
+                Err(e) => self.error = Some(e),
+            },
+            KeyCode::Tab | KeyCode::Down => self.focus = (self.focus + 1) % FIELDS.len(),
+            KeyCode::BackTab | KeyCode::Up => {
+                self.focus = (self.focus + FIELDS.len() - 1) % FIELDS.len()
+            }
+            KeyCode::Backspace => {
+                self.values[self.focus].pop();
+            }
+            KeyCode::Char(c) => self.values[self.focus].push(c),
+            _ => {}
+        }
+        FilterPanelEvent::None
+    }
+
+    /// Build a filter from the entered values
+    fn build(&self) -> Result<CommitFilter, String> {
+        let text = |i: usize| {
+            let value = self.values[i].trim();
+            (!value.is_empty()).then(|| value.to_string())
+        };
+        let date = |i: usize| {
+            text(i)
+                .map(|value| {
+                    NaiveDate::parse_from_str(&value, DATE_FORMAT)
+                        .map_err(|_| format!("{}: expected YYYY-MM-DD, got '{}'", FIELDS[i], value))
+                })
+                .transpose()
+        };
+
+        Ok(CommitFilter {
+            author: text(0),
+            path: text(1),
+            since: date(2)?,
+            until: date(3)?,
+            text: text(4),
+            types: self.values[5]
+                .split([',', ' '])
+                .filter(|kind| !kind.is_empty())
+                .map(|kind| kind.to_ascii_lowercase())
+                .collect(),
+        })
+    }
+
+    /// Render the panel centered over `area`
+    pub fn draw(&self, frame: &mut Frame, area: Rect, colors: &ColorScheme, symbols: &Symbols) {
+        let mut lines: Vec<Line> = FIELDS
+            .iter()
+            .zip(self.values.iter())
+            .enumerate()
+            .map(|(i, (label, value))| {
+                let focused = i == self.focus;
+                let label_style = if focused {
+                    Style::default()
+                        .fg(colors.commit_hash)
+                        .add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default().fg(colors.commit_hash)
+                };
+                Line::from(vec![
+                    Span::raw(if focused { "> " } else { "  " }),
+                    Span::styled(format!("{:>7}: ", label), label_style),
+                    Span::raw(value.clone()),
+                    Span::raw(if focused { "_" } else { "" }),
+                ])
+            })
+            .collect();
+
+        lines.push(Line::from(""));
+        match &self.error {
+            Some(error) => lines.push(Line::from(Span::styled(
+                error.clone(),
+                Style::default().fg(colors.deleted),
+            ))),
+            None => lines.push(Line::from(Span::styled(
+                "Tab: next field  Enter: apply  Esc: cancel",
+                Style::default().fg(colors.date),
+            ))),
+        }
+
+        let popup = centered_rect(60, lines.len() as u16 + 2, area);
+        frame.render_widget(Clear, popup);
+        frame.render_widget(
+            Paragraph::new(lines).block(symbols.block().title("Filter")),
+            popup,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::KeyModifiers;
+
+    fn commit(author: &str, date: &str, message: &str) -> Commit {
+        let date = NaiveDate::parse_from_str(date, DATE_FORMAT)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap()
+            .and_local_timezone(chrono::Local)
+            .unwrap();
+        Commit {
+            id: git2::Oid::zero(),
+            short_id: "0000000".to_string(),
+            author: author.to_string(),
+            author_email: format!("{}@example.com", author.to_lowercase()),
+            date,
+            summary: message.lines().next().unwrap_or_default().to_string(),
+            message: message.to_string(),
+            refs: vec![],
+        }
+    }
+
+    fn type_str(panel: &mut FilterPanel, s: &str) {
+        for c in s.chars() {
+            panel.handle_key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+        }
+    }
+
+    #[test]
+    fn test_filter_matches_combined_criteria() {
+        let filter = CommitFilter {
+            author: Some("alice".to_string()),
+            since: NaiveDate::from_ymd_opt(2024, 1, 1),
+            text: Some("parser".to_string()),
+            ..Default::default()
+        };
+
+        assert!(filter.matches(&commit("Alice", "2024-03-01", "Fix parser\n\nDetails")));
+        assert!(!filter.matches(&commit("Bob", "2024-03-01", "Fix parser")));
+        assert!(!filter.matches(&commit("Alice", "2023-12-31", "Fix parser")));
+        assert!(!filter.matches(&commit("Alice", "2024-03-01", "Fix lexer")));
+    }
+
+    #[test]
+    fn test_filter_by_conventional_type() {
+        let filter = CommitFilter {
+            types: vec!["feat".to_string(), "fix".to_string()],
+            ..Default::default()
+        };
+        assert!(filter.matches(&commit("Alice", "2024-03-01", "feat(parser): Accept tabs")));
+        assert!(filter.matches(&commit("Alice", "2024-03-01", "Fix!: Crash")));
+        assert!(!filter.matches(&commit("Alice", "2024-03-01", "chore: Bump")));
+        assert!(!filter.matches(&commit("Alice", "2024-03-01", "Fix the parser")));
+        assert_eq!(filter.chips(), vec!["type:feat,fix"]);
+
+        let mut panel = FilterPanel::new(&CommitFilter::default());
+        panel.handle_key(KeyEvent::new(KeyCode::Up, KeyModifiers::NONE));
+        type_str(&mut panel, "feat, Fix");
+        let event = panel.handle_key(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        assert_eq!(event, FilterPanelEvent::Apply(filter));
+    }
+
+    #[test]
+    fn test_filter_chips() {
+        let filter = CommitFilter {
+            author: Some("alice".to_string()),
+            path: Some("src/".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(filter.chips(), vec!["author:alice", "path:src/"]);
+        assert!(CommitFilter::default().chips().is_empty());
+    }
+
+    #[test]
+    fn test_panel_builds_filter() {
+        let mut panel = FilterPanel::new(&CommitFilter::default());
+        type_str(&mut panel, "alice");
+        panel.handle_key(KeyEvent::new(KeyCode::Tab, KeyModifiers::NONE));
+        panel.handle_key(KeyEvent::new(KeyCode::Tab, KeyModifiers::NONE));
+        type_str(&mut panel, "2024-01-01");
+
+        let event = panel.handle_key(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        assert_eq!(
+            event,
+            FilterPanelEvent::Apply(CommitFilter {
+                author: Some("alice".to_string()),
+                since: NaiveDate::from_ymd_opt(2024, 1, 1),
+                ..Default::default()
+            })
+        );
+    }
+
+    #[test]
+    fn test_panel_rejects_invalid_date() {
+        let mut panel = FilterPanel::new(&CommitFilter::default());
+        for _ in 0..3 {
+            panel.handle_key(KeyEvent::new(KeyCode::Up, KeyModifiers::NONE));
+        }
+        type_str(&mut panel, "yesterday");
+
+        let event = panel.handle_key(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        assert_eq!(event, FilterPanelEvent::None);
+        assert!(panel.error.is_some());
+    }
+}
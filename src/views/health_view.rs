@@ -0,0 +1,397 @@
+use super::output_view::OutputLog;
+use super::scrollbar::render_scrollbar;
+use super::view::{Action, View, ViewType};
+use crate::config::{ColorScheme, Symbols};
+use crate::error::{AppError, AppResult};
+use crate::git::{GitOutput, HealthReport, Maintenance, Repository, Result as GitResult};
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    Frame,
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::Line,
+    widgets::{List, ListItem, ListState, Paragraph},
+};
+use tokio::sync::mpsc;
+
+/// Lines of maintenance output kept for the output panel
+const OUTPUT_LINES: usize = 200;
+
+/// Height of the output panel, borders included
+const OUTPUT_HEIGHT: u16 = 10;
+
+/// A repository health check, like `git fsck`, with `git gc` and
+/// `git prune` to clean up after it
+pub struct HealthView {
+    repo: Repository,
+    report: Option<HealthReport>,
+    list_state: ListState,
+    loading: bool,
+    error: Option<String>,
+    receiver: Option<mpsc::UnboundedReceiver<GitResult<HealthReport>>>,
+    /// The maintenance task running, and its output as it arrives
+    running: Option<(Maintenance, mpsc::UnboundedReceiver<GitOutput>)>,
+    /// Output of the last maintenance task
+    output: Option<OutputLog>,
+    /// How the last maintenance task ended, shown in the title
+    notice: Option<String>,
+    colors: ColorScheme,
+    symbols: Symbols,
+}
+
+impl HealthView {
+    /// Create a new health view
+    pub fn new(repo: Repository, colors: ColorScheme) -> Self {
+        let mut list_state = ListState::default();
+        list_state.select(Some(0));
+
+        Self {
+            repo,
+            report: None,
+            list_state,
+            loading: false,
+            error: None,
+            receiver: None,
+            running: None,
+            output: None,
+            notice: None,
+            colors,
+            symbols: Symbols::default(),
+        }
+    }
+
+    /// Set the glyphs used for borders and the scrollbar
+    pub fn with_symbols(mut self, symbols: Symbols) -> Self {
+        self.symbols = symbols;
+        self
+    }
+
+    /// Start checking the repository asynchronously
+    pub fn start_loading(&mut self) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.receiver = Some(rx);
+        self.loading = true;
+
+        let repo = self.repo.clone();
+        tokio::spawn(async move {
+            let _ = tx.send(crate::git::health::check_health(&repo).await);
+        });
+    }
+
+    /// Run a maintenance task, unless one is running already
+    fn start_maintenance(&mut self, task: Maintenance) -> AppResult<()> {
+        if let Some((running, _)) = &self.running {
+            return Err(AppError::recoverable(format!(
+                "Wait for {} to finish",
+                running.command_line()
+            )));
+        }
+        self.output = Some(OutputLog::new(&task.command_line(), OUTPUT_LINES));
+        self.notice = None;
+        self.running = Some((
+            task,
+            crate::git::maintenance::spawn_git(&self.repo, task.args()),
+        ));
+        Ok(())
+    }
+
+    /// Build the report's lines: a summary, then a section per kind of
+    /// problem found
+    fn report_lines(&self, report: &HealthReport) -> Vec<Line<'static>> {
+        let heading = Style::default()
+            .fg(self.colors.commit_hash)
+            .add_modifier(Modifier::BOLD);
+        let problem = Style::default().fg(self.colors.deleted);
+        let mut lines = vec![match report.is_healthy() {
+            true => Line::styled(
+                format!("{} objects checked, no problems found", report.objects),
+                Style::default().fg(self.colors.added),
+            ),
+            false => Line::styled(
+                format!("{} objects checked, problems found", report.objects),
+                problem,
+            ),
+        }];
+
+        let mut section = |title: &str, note: &str, items: Vec<String>, style: Style| {
+            if items.is_empty() {
+                return;
+            }
+            lines.push(Line::from(""));
+            lines.push(Line::styled(
+                format!("{} ({}){}", title, items.len(), note),
+                heading,
+            ));
+            lines.extend(
+                items
+                    .into_iter()
+                    .map(|item| Line::styled(format!("  {}", item), style)),
+            );
+        };
+        section(
+            "Corrupt objects",
+            "",
+            report
+                .corrupt
+                .iter()
+                .map(|o| format!("{}  {}", o.id, o.message))
+                .collect(),
+            problem,
+        );
+        section(
+            "Missing objects",
+            "",
+            report
+                .missing
+                .iter()
+                .map(|o| format!("{}  needed by {}", o.id, o.needed_by))
+                .collect(),
+            problem,
+        );
+        section(
+            "Broken refs",
+            "",
+            report
+                .broken_refs
+                .iter()
+                .map(|r| format!("{}  {}", r.name, r.reason))
+                .collect(),
+            problem,
+        );
+        section(
+            "Stale locks",
+            ": remove them if no git command is running",
+            report
+                .stale_locks
+                .iter()
+                .map(|lock| {
+                    format!(
+                        "{}  {} minutes old",
+                        lock.path.display(),
+                        lock.age.as_secs() / 60
+                    )
+                })
+                .collect(),
+            problem,
+        );
+        section(
+            "Dangling objects",
+            ": g or p cleans them up",
+            report
+                .dangling
+                .iter()
+                .map(|o| format!("{} {}", o.kind, o.id))
+                .collect(),
+            Style::default().fg(self.colors.date),
+        );
+        lines
+    }
+
+    fn line_count(&self) -> usize {
+        self.report
+            .as_ref()
+            .map_or(0, |report| self.report_lines(report).len())
+    }
+
+    /// Move selection down by `amount`, stopping at the last line
+    fn select_down(&mut self, amount: usize) {
+        let last = self.line_count().saturating_sub(1);
+        let i = self
+            .list_state
+            .selected()
+            .map_or(0, |i| (i + amount).min(last));
+        self.list_state.select(Some(i));
+    }
+
+    /// Move selection up by `amount`, stopping at the first line
+    fn select_up(&mut self, amount: usize) {
+        let i = self
+            .list_state
+            .selected()
+            .map_or(0, |i| i.saturating_sub(amount));
+        self.list_state.select(Some(i));
+    }
+}
+
+impl View for HealthView {
+    fn handle_key(&mut self, key: KeyEvent) -> AppResult<Action> {
+        self.handle_key_with_count(key, 1)
+    }
+
+    fn handle_key_with_count(&mut self, key: KeyEvent, count: usize) -> AppResult<Action> {
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(Action::PopView),
+            KeyCode::Char('j') | KeyCode::Down => self.select_down(count),
+            KeyCode::Char('k') | KeyCode::Up => self.select_up(count),
+            KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.select_down(10 * count)
+            }
+            KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.select_up(10 * count)
+            }
+            KeyCode::PageDown => self.select_down(20 * count),
+            KeyCode::PageUp => self.select_up(20 * count),
+            KeyCode::Char('r') if !self.loading => self.refresh(),
+            KeyCode::Char('g') => self.start_maintenance(Maintenance::Gc)?,
+            KeyCode::Char('p') => self.start_maintenance(Maintenance::Prune)?,
+            KeyCode::Char('?') => return Ok(Action::ShowHelp(ViewType::Health)),
+            _ => {}
+        }
+        Ok(Action::None)
+    }
+
+    fn update(&mut self) -> AppResult<()> {
+        if let Some(receiver) = &mut self.receiver
+            && let Ok(result) = receiver.try_recv()
+        {
+            self.loading = false;
+            self.receiver = None;
+            match result {
+                Ok(report) => self.report = Some(report),
+                Err(e) => self.error = Some(format!("Failed to check the repository: {}", e)),
+            }
+        }
+
+        if let Some((task, receiver)) = &mut self.running
+            && let Some(output) = &mut self.output
+            && let Some(result) = output.drain(receiver)
+        {
+            let task = *task;
+            self.running = None;
+            // What was dangling or locked may be gone now
+            self.refresh();
+            match result {
+                Ok(()) => self.notice = Some(format!("{} finished", task.command_line())),
+                Err(message) => {
+                    self.notice = Some(format!("{} failed", task.command_line()));
+                    return Err(AppError::recoverable(message));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn draw(&mut self, frame: &mut Frame, area: Rect) {
+        let (report_area, output_area) = match self.output.is_none() {
+            true => (area, None),
+            false => {
+                let [report, output] =
+                    Layout::vertical([Constraint::Min(3), Constraint::Length(OUTPUT_HEIGHT)])
+                        .areas(area);
+                (report, Some(output))
+            }
+        };
+
+        if let Some(error) = &self.error {
+            let list = List::new(vec![ListItem::new(error.clone())])
+                .block(self.symbols.block().title("Repository Health - Error"))
+                .style(Style::default().fg(self.colors.deleted));
+            frame.render_widget(list, report_area);
+        } else if let Some(report) = &self.report {
+            let mut title = "Repository Health".to_string();
+            if self.loading {
+                title.push_str(" [checking...]");
+            }
+            if let Some(notice) = &self.notice {
+                title.push_str(&format!(" [{}]", notice));
+            }
+            let lines = self.report_lines(report);
+            let count = lines.len();
+            let list = List::new(lines.into_iter().map(ListItem::new).collect::<Vec<_>>())
+                .block(self.symbols.block().title(title))
+                .highlight_style(self.colors.selected.add_modifier(Modifier::BOLD))
+                .highlight_symbol("> ");
+            frame.render_stateful_widget(list, report_area, &mut self.list_state);
+            let selected = self.list_state.selected().unwrap_or(0);
+            render_scrollbar(frame, report_area, &self.symbols, count, selected);
+        } else {
+            let list = List::new(vec![ListItem::new("Checking every object and ref...")])
+                .block(self.symbols.block().title("Repository Health"));
+            frame.render_widget(list, report_area);
+        }
+
+        if let (Some(output), Some(output_area)) = (&self.output, output_area) {
+            let visible = OUTPUT_HEIGHT.saturating_sub(2) as usize;
+            let lines = output.lines(output.len().saturating_sub(visible), visible);
+            let title = match &self.running {
+                Some((task, _)) => format!("Output - {} running", task.command_line()),
+                None => "Output".to_string(),
+            };
+            let output = Paragraph::new(lines)
+                .block(self.symbols.block().title(title))
+                .style(Style::default().fg(Color::Gray));
+            frame.render_widget(output, output_area);
+        }
+    }
+
+    fn title(&self) -> &str {
+        "Repository Health"
+    }
+
+    fn view_type(&self) -> ViewType {
+        ViewType::Health
+    }
+
+    fn refresh(&mut self) {
+        self.error = None;
+        self.start_loading();
+    }
+
+    fn busy(&self) -> Option<&'static str> {
+        match (&self.running, self.loading) {
+            (Some(_), _) => Some("running maintenance"),
+            (None, true) => Some("checking the repository"),
+            (None, false) => None,
+        }
+    }
+
+    fn on_activate(&mut self) -> Result<()> {
+        if self.report.is_none() && !self.loading {
+            self.start_loading();
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::testing::RepoBuilder;
+
+    #[tokio::test]
+    async fn test_reports_and_runs_prune() {
+        let (_temp_dir, repo) = RepoBuilder::new().commit("Initial").open().await;
+        repo.open_git2().unwrap().blob(b"loose\n").unwrap();
+
+        let mut view = HealthView::new(repo, ColorScheme::from_config(&Config::default().colors));
+        view.on_activate().unwrap();
+        while view.report.is_none() {
+            tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+            view.update().unwrap();
+        }
+        let report = view.report.clone().unwrap();
+        assert_eq!(report.dangling.len(), 1);
+        let lines = view.report_lines(&report);
+        assert!(
+            lines
+                .iter()
+                .any(|line| line.to_string().starts_with("Dangling objects (1)"))
+        );
+
+        view.handle_key(KeyEvent::from(KeyCode::Char('p'))).unwrap();
+        assert!(view.handle_key(KeyEvent::from(KeyCode::Char('g'))).is_err());
+        assert_eq!(view.busy(), Some("running maintenance"));
+        while view.running.is_some() {
+            tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+            view.update().unwrap();
+        }
+        assert_eq!(
+            view.output.unwrap().lines(0, 1)[0].to_string(),
+            "$ git prune --verbose"
+        );
+        assert_eq!(view.notice.as_deref(), Some("git prune --verbose finished"));
+        assert!(view.loading);
+    }
+}
@@ -1,18 +1,21 @@
-use super::view::{Action, View};
-use anyhow::Result;
-use crossterm::event::{KeyCode, KeyEvent};
+use super::scrollbar::render_scrollbar;
+use super::view::{Action, View, ViewType};
+use crate::config::Symbols;
+use crate::error::AppResult;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use ratatui::{
+    Frame,
     layout::Rect,
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState},
-    Frame,
+    widgets::Paragraph,
 };
 
 /// Help view showing keybindings and usage information
 pub struct HelpView {
     scroll_offset: usize,
     lines: Vec<Line<'static>>,
+    symbols: Symbols,
 }
 
 impl HelpView {
@@ -22,7 +25,48 @@ impl HelpView {
         Self {
             scroll_offset: 0,
             lines,
+            symbols: Symbols::default(),
+        }
+    }
+
+    /// Set the glyphs used for borders and the scrollbar
+    pub fn with_symbols(mut self, symbols: Symbols) -> Self {
+        self.symbols = symbols;
+        self
+    }
+
+    /// Create a help view scrolled to the section for the given view
+    pub fn for_view(view_type: ViewType) -> Self {
+        let mut help = Self::new();
+        if let Some(offset) = help.section_offset(view_type) {
+            help.scroll_offset = offset;
         }
+        help
+    }
+
+    /// Find the line where the section for a view starts
+    fn section_offset(&self, view_type: ViewType) -> Option<usize> {
+        let heading = match view_type {
+            ViewType::Main => "Main View (Commit History)",
+            ViewType::Status => "Status View",
+            ViewType::Diff => "Diff View",
+            ViewType::Refs => "Refs View",
+            ViewType::Blame => "Blame View",
+            ViewType::Stack => "Branch Stack View",
+            ViewType::Lost => "Lost Commits View",
+            ViewType::Health => "Repository Health View",
+            ViewType::Output => "Output View",
+            ViewType::Hooks => "Hooks View",
+            ViewType::Stash => "Stash View",
+            ViewType::Review => "Review Notes View",
+            ViewType::Help | ViewType::Onboarding => return None,
+        };
+
+        self.lines.iter().position(|line| {
+            line.spans
+                .first()
+                .is_some_and(|span| span.content == heading)
+        })
     }
 
     /// Build the help content
@@ -43,12 +87,42 @@ impl HelpView {
             )),
             Line::from(vec![
                 Span::styled("  q         ", Style::default().fg(Color::Green)),
-                Span::raw("Quit the application or close current view"),
+                Span::raw("Close current view; quit from the main view"),
+            ]),
+            Line::from(vec![
+                Span::styled("  Q         ", Style::default().fg(Color::Green)),
+                Span::raw("Quit from any view"),
             ]),
             Line::from(vec![
                 Span::styled("  Ctrl+C    ", Style::default().fg(Color::Green)),
                 Span::raw("Force quit"),
             ]),
+            Line::from(vec![
+                Span::styled("  R / F5    ", Style::default().fg(Color::Green)),
+                Span::raw("Reload all views, e.g. after git commands in another terminal"),
+            ]),
+            Line::from(vec![
+                Span::styled("  < / >     ", Style::default().fg(Color::Green)),
+                Span::raw(
+                    "Shrink / grow the upper pane of a split (also Ctrl+Up / Ctrl+Down); = evens it",
+                ),
+            ]),
+            Line::from(vec![
+                Span::styled("  Tab       ", Style::default().fg(Color::Green)),
+                Span::raw("Move keys to the other pane of a split (also Ctrl+W)"),
+            ]),
+            Line::from(vec![
+                Span::styled("  z         ", Style::default().fg(Color::Green)),
+                Span::raw(
+                    "Zoom the focused pane of a split to the full screen, or restore the split",
+                ),
+            ]),
+            Line::from(vec![
+                Span::styled("  :         ", Style::default().fg(Color::Green)),
+                Span::raw(
+                    "Run a command, e.g. :merge-base main feature, :fetch --prune, :pull --rebase, :push, :checkout topic, :lost, :fsck, :gc, :hooks, :review, :open ../other",
+                ),
+            ]),
             Line::from(vec![
                 Span::styled("  ?         ", Style::default().fg(Color::Green)),
                 Span::raw("Show this help"),
@@ -57,6 +131,18 @@ impl HelpView {
                 Span::styled("  Esc       ", Style::default().fg(Color::Green)),
                 Span::raw("Close current view or exit search mode"),
             ]),
+            Line::from(vec![
+                Span::styled("  g         ", Style::default().fg(Color::Green)),
+                Span::raw("Prefix key, shows its continuations in a popup"),
+            ]),
+            Line::from(vec![
+                Span::styled("  10j       ", Style::default().fg(Color::Green)),
+                Span::raw("Counts repeat movement keys (j, k, Ctrl+D, PageDown...)"),
+            ]),
+            Line::from(vec![
+                Span::styled("  Ctrl+D/U  ", Style::default().fg(Color::Green)),
+                Span::raw("Move half a page down/up"),
+            ]),
             Line::from(""),
             Line::from(Span::styled(
                 "Main View (Commit History)",
@@ -73,13 +159,65 @@ impl HelpView {
                 Span::raw("Move selection up"),
             ]),
             Line::from(vec![
-                Span::styled("  g         ", Style::default().fg(Color::Green)),
+                Span::styled("  g g       ", Style::default().fg(Color::Green)),
                 Span::raw("Jump to first commit"),
             ]),
             Line::from(vec![
-                Span::styled("  G         ", Style::default().fg(Color::Green)),
+                Span::styled("  G / g e   ", Style::default().fg(Color::Green)),
                 Span::raw("Jump to last commit"),
             ]),
+            Line::from(vec![
+                Span::styled("  g s       ", Style::default().fg(Color::Green)),
+                Span::raw("Go to status view"),
+            ]),
+            Line::from(vec![
+                Span::styled("  Space     ", Style::default().fg(Color::Green)),
+                Span::raw("Quick look at message and diffstat"),
+            ]),
+            Line::from(vec![
+                Span::styled("  i         ", Style::default().fg(Color::Green)),
+                Span::raw("List all refs at the commit with full names"),
+            ]),
+            Line::from(vec![
+                Span::styled("  I         ", Style::default().fg(Color::Green)),
+                Span::raw("List branches and tags containing the commit"),
+            ]),
+            Line::from(vec![
+                Span::styled("  m         ", Style::default().fg(Color::Green)),
+                Span::raw("Mark the commit (again to clear the mark)"),
+            ]),
+            Line::from(vec![
+                Span::styled("  d         ", Style::default().fg(Color::Green)),
+                Span::raw("Diff the marked commit against the selected one"),
+            ]),
+            Line::from(vec![
+                Span::styled("  x         ", Style::default().fg(Color::Green)),
+                Span::raw("Add the commit to the cherry-picks (again to take it out)"),
+            ]),
+            Line::from(vec![
+                Span::styled("  P         ", Style::default().fg(Color::Green)),
+                Span::raw("Cherry-pick the added commits, or the selected one"),
+            ]),
+            Line::from(vec![
+                Span::styled("  V         ", Style::default().fg(Color::Green)),
+                Span::raw("Revert the commit (y commits, e edits the message first)"),
+            ]),
+            Line::from(vec![
+                Span::styled("  X         ", Style::default().fg(Color::Green)),
+                Span::raw("Reset the branch to the commit (soft, mixed or hard)"),
+            ]),
+            Line::from(vec![
+                Span::styled("  S         ", Style::default().fg(Color::Green)),
+                Span::raw("Split the commit into several"),
+            ]),
+            Line::from(vec![
+                Span::styled("  r / g r   ", Style::default().fg(Color::Green)),
+                Span::raw("Open refs view (branches, remotes, tags)"),
+            ]),
+            Line::from(vec![
+                Span::styled("  b / g b   ", Style::default().fg(Color::Green)),
+                Span::raw("Open branch stack view (stacked branches and their own commits)"),
+            ]),
             Line::from(vec![
                 Span::styled("  PageUp    ", Style::default().fg(Color::Green)),
                 Span::raw("Page up"),
@@ -96,10 +234,34 @@ impl HelpView {
                 Span::styled("  /         ", Style::default().fg(Color::Green)),
                 Span::raw("Start search (search commit messages)"),
             ]),
+            Line::from(vec![
+                Span::styled("  f         ", Style::default().fg(Color::Green)),
+                Span::raw("Open filter panel (author, path, dates, text, types)"),
+            ]),
+            Line::from(vec![
+                Span::styled("  F         ", Style::default().fg(Color::Green)),
+                Span::raw("Clear all filters"),
+            ]),
             Line::from(vec![
                 Span::styled("  s         ", Style::default().fg(Color::Green)),
                 Span::raw("Open status view"),
             ]),
+            Line::from(vec![
+                Span::styled("  y         ", Style::default().fg(Color::Green)),
+                Span::raw("Open stash view"),
+            ]),
+            Line::from(vec![
+                Span::styled("  Y         ", Style::default().fg(Color::Green)),
+                Span::raw("Copy the commit in a copy format"),
+            ]),
+            Line::from(vec![
+                Span::styled("  L         ", Style::default().fg(Color::Green)),
+                Span::raw("Copy the commits from the mark as a changelog"),
+            ]),
+            Line::from(vec![
+                Span::styled("  C         ", Style::default().fg(Color::Green)),
+                Span::raw("Check out the commit (detached HEAD)"),
+            ]),
             Line::from(""),
             Line::from(Span::styled(
                 "Search Mode",
@@ -115,6 +277,14 @@ impl HelpView {
                 Span::styled("  Backspace ", Style::default().fg(Color::Green)),
                 Span::raw("Delete character"),
             ]),
+            Line::from(vec![
+                Span::styled("  Ctrl+T    ", Style::default().fg(Color::Green)),
+                Span::raw("Cycle case: smart / sensitive / insensitive"),
+            ]),
+            Line::from(vec![
+                Span::styled("  Up/Down   ", Style::default().fg(Color::Green)),
+                Span::raw("Recall previous searches"),
+            ]),
             Line::from(vec![
                 Span::styled("  Enter     ", Style::default().fg(Color::Green)),
                 Span::raw("Keep search results and exit search mode"),
@@ -139,11 +309,11 @@ impl HelpView {
                 Span::raw("Move selection up"),
             ]),
             Line::from(vec![
-                Span::styled("  g         ", Style::default().fg(Color::Green)),
+                Span::styled("  g g       ", Style::default().fg(Color::Green)),
                 Span::raw("Jump to first item"),
             ]),
             Line::from(vec![
-                Span::styled("  G         ", Style::default().fg(Color::Green)),
+                Span::styled("  G / g e   ", Style::default().fg(Color::Green)),
                 Span::raw("Jump to last item"),
             ]),
             Line::from(vec![
@@ -156,11 +326,47 @@ impl HelpView {
             ]),
             Line::from(vec![
                 Span::styled("  Enter     ", Style::default().fg(Color::Green)),
-                Span::raw("View file diff"),
+                Span::raw("View file diff, expand/collapse a directory"),
             ]),
             Line::from(vec![
                 Span::styled("  u         ", Style::default().fg(Color::Green)),
-                Span::raw("Stage/unstage selected file"),
+                Span::raw("Stage/unstage selected file or directory"),
+            ]),
+            Line::from(vec![
+                Span::styled("  Space     ", Style::default().fg(Color::Green)),
+                Span::raw("Fold/unfold section or directory"),
+            ]),
+            Line::from(vec![
+                Span::styled("  t         ", Style::default().fg(Color::Green)),
+                Span::raw("Toggle directory tree"),
+            ]),
+            Line::from(vec![
+                Span::styled("  c         ", Style::default().fg(Color::Green)),
+                Span::raw("Commit the staged files"),
+            ]),
+            Line::from(vec![
+                Span::styled("  a         ", Style::default().fg(Color::Green)),
+                Span::raw("Amend the last commit with the staged files, editing its message"),
+            ]),
+            Line::from(vec![
+                Span::styled("  F         ", Style::default().fg(Color::Green)),
+                Span::raw("Absorb staged hunks into fixup commits"),
+            ]),
+            Line::from(vec![
+                Span::styled("  C         ", Style::default().fg(Color::Green)),
+                Span::raw(
+                    "Finish splitting a commit, or continue a merge, cherry-pick, revert or rebase",
+                ),
+            ]),
+            Line::from(vec![
+                Span::styled("  A         ", Style::default().fg(Color::Green)),
+                Span::raw(
+                    "Abort splitting a commit, or abort the operation in progress after asking",
+                ),
+            ]),
+            Line::from(vec![
+                Span::styled("  S         ", Style::default().fg(Color::Green)),
+                Span::raw("Skip the commit a cherry-pick, revert or rebase stopped on"),
             ]),
             Line::from(vec![
                 Span::styled("  r         ", Style::default().fg(Color::Green)),
@@ -182,11 +388,11 @@ impl HelpView {
                 Span::raw("Scroll up"),
             ]),
             Line::from(vec![
-                Span::styled("  g         ", Style::default().fg(Color::Green)),
+                Span::styled("  g g       ", Style::default().fg(Color::Green)),
                 Span::raw("Jump to top"),
             ]),
             Line::from(vec![
-                Span::styled("  G         ", Style::default().fg(Color::Green)),
+                Span::styled("  G / g e   ", Style::default().fg(Color::Green)),
                 Span::raw("Jump to bottom"),
             ]),
             Line::from(vec![
@@ -197,11 +403,312 @@ impl HelpView {
                 Span::styled("  PageDown  ", Style::default().fg(Color::Green)),
                 Span::raw("Page down"),
             ]),
+            Line::from(vec![
+                Span::styled("  Enter     ", Style::default().fg(Color::Green)),
+                Span::raw("Go to the file listed at the top, or expand a collapsed large file"),
+            ]),
+            Line::from(vec![
+                Span::styled("  f         ", Style::default().fg(Color::Green)),
+                Span::raw(
+                    "Switch a file's staged or unstaged changes to every file's, at the same file",
+                ),
+            ]),
+            Line::from(vec![
+                Span::styled("  @         ", Style::default().fg(Color::Green)),
+                Span::raw("Jump to next hunk"),
+            ]),
+            Line::from(vec![
+                Span::styled("  !         ", Style::default().fg(Color::Green)),
+                Span::raw("Discard hunk (unstaged changes)"),
+            ]),
+            Line::from(vec![
+                Span::styled("  u         ", Style::default().fg(Color::Green)),
+                Span::raw("Stage hunk (unstaged changes)"),
+            ]),
+            Line::from(vec![
+                Span::styled("  v         ", Style::default().fg(Color::Green)),
+                Span::raw("Select lines of the hunk; j/k extend, u stages them"),
+            ]),
+            Line::from(vec![
+                Span::styled("  e         ", Style::default().fg(Color::Green)),
+                Span::raw("Edit hunk in $EDITOR, then stage it"),
+            ]),
+            Line::from(vec![
+                Span::styled("  b         ", Style::default().fg(Color::Green)),
+                Span::raw("Blame the file under the cursor"),
+            ]),
+            Line::from(vec![
+                Span::styled("  o         ", Style::default().fg(Color::Green)),
+                Span::raw("Open an issue referenced in the commit message"),
+            ]),
+            Line::from(vec![
+                Span::styled("  m         ", Style::default().fg(Color::Green)),
+                Span::raw("Show the commit message raw or formatted"),
+            ]),
+            Line::from(vec![
+                Span::styled("  a         ", Style::default().fg(Color::Green)),
+                Span::raw("Write a review note on the hunk or file at the top, in $EDITOR"),
+            ]),
+            Line::from(vec![
+                Span::styled("  w         ", Style::default().fg(Color::Green)),
+                Span::raw("Toggle review mode: viewed files fold away and progress shows below"),
+            ]),
+            Line::from(vec![
+                Span::styled("  x         ", Style::default().fg(Color::Green)),
+                Span::raw(
+                    "Mark the file at the top viewed, or not, and go to the next one to view",
+                ),
+            ]),
             Line::from(vec![
                 Span::styled("  Esc       ", Style::default().fg(Color::Green)),
                 Span::raw("Close diff view"),
             ]),
             Line::from(""),
+            Line::from(Span::styled(
+                "Refs View",
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            )),
+            Line::from(vec![
+                Span::styled("  j / k     ", Style::default().fg(Color::Green)),
+                Span::raw("Move selection down / up"),
+            ]),
+            Line::from(vec![
+                Span::styled("  o         ", Style::default().fg(Color::Green)),
+                Span::raw("Cycle sort: name, date, ahead count (each both ways)"),
+            ]),
+            Line::from(vec![
+                Span::styled("  Enter     ", Style::default().fg(Color::Green)),
+                Span::raw("Show diff of the ref's commit; fold/unfold on a remote heading"),
+            ]),
+            Line::from(vec![
+                Span::styled("  Space     ", Style::default().fg(Color::Green)),
+                Span::raw("Fold/unfold the selected remote's branches"),
+            ]),
+            Line::from(vec![
+                Span::styled("  f         ", Style::default().fg(Color::Green)),
+                Span::raw("Fetch the selected remote"),
+            ]),
+            Line::from(vec![
+                Span::styled("  c         ", Style::default().fg(Color::Green)),
+                Span::raw("Compare: commits on HEAD but not the ref, + still to land, - applied"),
+            ]),
+            Line::from(vec![
+                Span::styled("  C         ", Style::default().fg(Color::Green)),
+                Span::raw("Check out the branch; tags detach HEAD"),
+            ]),
+            Line::from(vec![
+                Span::styled("  m         ", Style::default().fg(Color::Green)),
+                Span::raw("Merge the branch into HEAD, fast-forwarding when it can"),
+            ]),
+            Line::from(vec![
+                Span::styled("  Esc       ", Style::default().fg(Color::Green)),
+                Span::raw("Close refs view"),
+            ]),
+            Line::from(""),
+            Line::from(Span::styled(
+                "Branch Stack View",
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            )),
+            Line::from(vec![
+                Span::styled("  j / k     ", Style::default().fg(Color::Green)),
+                Span::raw("Move selection down / up"),
+            ]),
+            Line::from(vec![
+                Span::styled("  Enter     ", Style::default().fg(Color::Green)),
+                Span::raw("Show diff of the commit, or of the branch's newest commit"),
+            ]),
+            Line::from(vec![
+                Span::styled("  r         ", Style::default().fg(Color::Green)),
+                Span::raw("Restack: rebase the branch onto its parent, then the branches on it"),
+            ]),
+            Line::from(vec![
+                Span::styled("  Esc       ", Style::default().fg(Color::Green)),
+                Span::raw("Close branch stack view"),
+            ]),
+            Line::from(""),
+            Line::from(Span::styled(
+                "Lost Commits View",
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            )),
+            Line::from(vec![
+                Span::styled("  j / k     ", Style::default().fg(Color::Green)),
+                Span::raw("Move selection down / up"),
+            ]),
+            Line::from(vec![
+                Span::styled("  Enter     ", Style::default().fg(Color::Green)),
+                Span::raw("Show diff of the lost commit"),
+            ]),
+            Line::from(vec![
+                Span::styled("  b         ", Style::default().fg(Color::Green)),
+                Span::raw("Recover: create a branch at the commit, named at the prompt"),
+            ]),
+            Line::from(vec![
+                Span::styled("  Esc       ", Style::default().fg(Color::Green)),
+                Span::raw("Close lost commits view"),
+            ]),
+            Line::from(""),
+            Line::from(Span::styled(
+                "Repository Health View",
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            )),
+            Line::from(vec![
+                Span::styled("  r         ", Style::default().fg(Color::Green)),
+                Span::raw("Check the repository again"),
+            ]),
+            Line::from(vec![
+                Span::styled("  g         ", Style::default().fg(Color::Green)),
+                Span::raw("Run git gc, showing its output as it runs"),
+            ]),
+            Line::from(vec![
+                Span::styled("  p         ", Style::default().fg(Color::Green)),
+                Span::raw("Run git prune: delete unreachable objects older than two weeks"),
+            ]),
+            Line::from(vec![
+                Span::styled("  Esc       ", Style::default().fg(Color::Green)),
+                Span::raw("Close repository health view"),
+            ]),
+            Line::from(""),
+            Line::from(Span::styled(
+                "Output View",
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            )),
+            Line::from(vec![
+                Span::styled("  j / k     ", Style::default().fg(Color::Green)),
+                Span::raw("Scroll down / up"),
+            ]),
+            Line::from(vec![
+                Span::styled("  G         ", Style::default().fg(Color::Green)),
+                Span::raw("Follow the output as it arrives"),
+            ]),
+            Line::from(vec![
+                Span::styled("  Esc       ", Style::default().fg(Color::Green)),
+                Span::raw("Close output view; the command keeps running"),
+            ]),
+            Line::from(""),
+            Line::from(Span::styled(
+                "Hooks View",
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            )),
+            Line::from(vec![
+                Span::styled("  j / k     ", Style::default().fg(Color::Green)),
+                Span::raw("Move selection down / up"),
+            ]),
+            Line::from(vec![
+                Span::styled("  x         ", Style::default().fg(Color::Green)),
+                Span::raw("Toggle the hook's executable bit, which decides whether git runs it"),
+            ]),
+            Line::from(vec![
+                Span::styled("  t         ", Style::default().fg(Color::Green)),
+                Span::raw("Test: run the hook as git would and show its output"),
+            ]),
+            Line::from(vec![
+                Span::styled("  Ctrl-d/u  ", Style::default().fg(Color::Green)),
+                Span::raw("Scroll the script or test output"),
+            ]),
+            Line::from(vec![
+                Span::styled("  Esc       ", Style::default().fg(Color::Green)),
+                Span::raw("Close hooks view"),
+            ]),
+            Line::from(""),
+            Line::from(Span::styled(
+                "Stash View",
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            )),
+            Line::from(vec![
+                Span::styled("  j / k     ", Style::default().fg(Color::Green)),
+                Span::raw("Move selection down / up"),
+            ]),
+            Line::from(vec![
+                Span::styled("  Enter     ", Style::default().fg(Color::Green)),
+                Span::raw("Show the stashed changes"),
+            ]),
+            Line::from(vec![
+                Span::styled("  s / S     ", Style::default().fg(Color::Green)),
+                Span::raw(
+                    "Stash the uncommitted changes / with untracked files, asking for a message",
+                ),
+            ]),
+            Line::from(vec![
+                Span::styled("  a / p     ", Style::default().fg(Color::Green)),
+                Span::raw("Apply the entry / apply and drop it (pop)"),
+            ]),
+            Line::from(vec![
+                Span::styled("  d         ", Style::default().fg(Color::Green)),
+                Span::raw("Drop the entry, after asking"),
+            ]),
+            Line::from(vec![
+                Span::styled("  Esc       ", Style::default().fg(Color::Green)),
+                Span::raw("Close stash view"),
+            ]),
+            Line::from(""),
+            Line::from(Span::styled(
+                "Review Notes View",
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            )),
+            Line::from(vec![
+                Span::styled("  j / k     ", Style::default().fg(Color::Green)),
+                Span::raw("Move selection down / up"),
+            ]),
+            Line::from(vec![
+                Span::styled("  Enter     ", Style::default().fg(Color::Green)),
+                Span::raw("Show the diff the note is on"),
+            ]),
+            Line::from(vec![
+                Span::styled("  y         ", Style::default().fg(Color::Green)),
+                Span::raw("Copy every note as Markdown, grouped by commit"),
+            ]),
+            Line::from(vec![
+                Span::styled("  d         ", Style::default().fg(Color::Green)),
+                Span::raw("Delete the note, after asking"),
+            ]),
+            Line::from(vec![
+                Span::styled("  Esc       ", Style::default().fg(Color::Green)),
+                Span::raw("Close review notes view"),
+            ]),
+            Line::from(""),
+            Line::from(Span::styled(
+                "Blame View",
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            )),
+            Line::from(vec![
+                Span::styled("  j / k     ", Style::default().fg(Color::Green)),
+                Span::raw("Move selection down / up"),
+            ]),
+            Line::from(vec![
+                Span::styled("  Enter     ", Style::default().fg(Color::Green)),
+                Span::raw("Show diff of the commit that last changed the line"),
+            ]),
+            Line::from(vec![
+                Span::styled("  ,         ", Style::default().fg(Color::Green)),
+                Span::raw("Blame again before the commit that last changed the line"),
+            ]),
+            Line::from(vec![
+                Span::styled("  Backspace ", Style::default().fg(Color::Green)),
+                Span::raw("Go back to the blame before the last ,"),
+            ]),
+            Line::from(vec![
+                Span::styled("  Esc       ", Style::default().fg(Color::Green)),
+                Span::raw("Close blame view"),
+            ]),
+            Line::from(""),
             Line::from(Span::styled(
                 "About",
                 Style::default()
@@ -238,7 +745,7 @@ impl HelpView {
 }
 
 impl View for HelpView {
-    fn handle_key(&mut self, key: KeyEvent) -> Result<Action> {
+    fn handle_key(&mut self, key: KeyEvent) -> AppResult<Action> {
         match key.code {
             KeyCode::Char('q') | KeyCode::Esc => Ok(Action::PopView),
             KeyCode::Char('j') | KeyCode::Down => {
@@ -257,6 +764,14 @@ impl View for HelpView {
                 self.scroll_to_bottom();
                 Ok(Action::None)
             }
+            KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.scroll_down(10);
+                Ok(Action::None)
+            }
+            KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.scroll_up(10);
+                Ok(Action::None)
+            }
             KeyCode::PageDown => {
                 self.scroll_down(20);
                 Ok(Action::None)
@@ -269,23 +784,30 @@ impl View for HelpView {
         }
     }
 
-    fn update(&mut self) -> Result<()> {
+    fn handle_key_with_count(&mut self, key: KeyEvent, count: usize) -> AppResult<Action> {
+        match key.code {
+            KeyCode::Char('j') | KeyCode::Down => self.scroll_down(count),
+            KeyCode::Char('k') | KeyCode::Up => self.scroll_up(count),
+            KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.scroll_down(10 * count)
+            }
+            KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.scroll_up(10 * count)
+            }
+            KeyCode::PageDown => self.scroll_down(20 * count),
+            KeyCode::PageUp => self.scroll_up(20 * count),
+            _ => return self.handle_key(key),
+        }
+        Ok(Action::None)
+    }
+
+    fn update(&mut self) -> AppResult<()> {
         Ok(())
     }
 
     fn draw(&mut self, frame: &mut Frame, area: Rect) {
         let visible_height = area.height.saturating_sub(2) as usize;
 
-        // Calculate scrollbar state
-        let scrollbar_state = if self.lines.len() > visible_height {
-            let state = ScrollbarState::default()
-                .content_length(self.lines.len())
-                .position(self.scroll_offset);
-            Some(state)
-        } else {
-            None
-        };
-
         // Get visible lines
         let visible_lines: Vec<Line> = self
             .lines
@@ -301,31 +823,26 @@ impl View for HelpView {
             self.lines.len()
         );
 
-        let paragraph = Paragraph::new(visible_lines)
-            .block(Block::default().title(title).borders(Borders::ALL));
+        let paragraph = Paragraph::new(visible_lines).block(self.symbols.block().title(title));
 
         frame.render_widget(paragraph, area);
 
-        // Render scrollbar if needed
-        if let Some(mut state) = scrollbar_state {
-            let scrollbar = Scrollbar::default()
-                .orientation(ScrollbarOrientation::VerticalRight)
-                .begin_symbol(Some("↑"))
-                .end_symbol(Some("↓"));
-            frame.render_stateful_widget(
-                scrollbar,
-                area.inner(ratatui::layout::Margin {
-                    vertical: 1,
-                    horizontal: 0,
-                }),
-                &mut state,
-            );
-        }
+        render_scrollbar(
+            frame,
+            area,
+            &self.symbols,
+            self.lines.len(),
+            self.scroll_offset,
+        );
     }
 
     fn title(&self) -> &str {
         "Help"
     }
+
+    fn view_type(&self) -> ViewType {
+        ViewType::Help
+    }
 }
 
 impl Default for HelpView {
@@ -333,3 +850,18 @@ impl Default for HelpView {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_help_opens_at_view_section() {
+        let help = HelpView::for_view(ViewType::Status);
+        let line = &help.lines[help.scroll_offset];
+        assert_eq!(line.spans[0].content, "Status View");
+
+        assert_eq!(HelpView::for_view(ViewType::Help).scroll_offset, 0);
+        assert!(HelpView::for_view(ViewType::Diff).scroll_offset > 0);
+    }
+}
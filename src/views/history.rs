@@ -0,0 +1,98 @@
+use crate::config::session::push_history;
+
+/// Navigable history for a text input (Up/Down recall)
+#[derive(Debug, Clone, Default)]
+pub struct InputHistory {
+    entries: Vec<String>,
+    position: Option<usize>,
+    draft: String,
+}
+
+impl InputHistory {
+    /// Create a history from entries ordered oldest first
+    pub fn new(entries: Vec<String>) -> Self {
+        Self {
+            entries,
+            position: None,
+            draft: String::new(),
+        }
+    }
+
+    /// Get the entries, oldest first
+    pub fn entries(&self) -> &[String] {
+        &self.entries
+    }
+
+    /// Record a submitted entry and stop navigating
+    pub fn push(&mut self, entry: &str) {
+        push_history(&mut self.entries, entry);
+        self.reset();
+    }
+
+    /// Stop navigating (e.g. after the input is edited)
+    pub fn reset(&mut self) {
+        self.position = None;
+        self.draft.clear();
+    }
+
+    /// Step to an older entry; `current` is kept to restore when stepping back
+    pub fn older(&mut self, current: &str) -> Option<&str> {
+        let position = match self.position {
+            None if self.entries.is_empty() => return None,
+            None => {
+                self.draft = current.to_string();
+                self.entries.len() - 1
+            }
+            Some(0) => 0,
+            Some(i) => i - 1,
+        };
+        self.position = Some(position);
+        self.entries.get(position).map(|s| s.as_str())
+    }
+
+    /// Step to a newer entry, returning the original input past the newest
+    pub fn newer(&mut self) -> Option<&str> {
+        let position = self.position?;
+        if position + 1 < self.entries.len() {
+            self.position = Some(position + 1);
+            self.entries.get(position + 1).map(|s| s.as_str())
+        } else {
+            self.position = None;
+            Some(self.draft.as_str())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_history_navigation() {
+        let mut history = InputHistory::new(vec!["one".to_string(), "two".to_string()]);
+
+        assert_eq!(history.older("draft"), Some("two"));
+        assert_eq!(history.older("two"), Some("one"));
+        assert_eq!(history.older("one"), Some("one"));
+        assert_eq!(history.newer(), Some("two"));
+        assert_eq!(history.newer(), Some("draft"));
+        assert_eq!(history.newer(), None);
+    }
+
+    #[test]
+    fn test_history_push_moves_to_end() {
+        let mut history = InputHistory::new(vec!["one".to_string(), "two".to_string()]);
+        history.older("");
+        history.push("one");
+
+        assert_eq!(history.entries(), ["two", "one"]);
+        assert_eq!(history.older(""), Some("one"));
+    }
+
+    #[test]
+    fn test_empty_history() {
+        let mut history = InputHistory::default();
+        assert_eq!(history.older("x"), None);
+        assert_eq!(history.newer(), None);
+    }
+}
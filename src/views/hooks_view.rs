@@ -0,0 +1,390 @@
+use super::output_view::OutputLog;
+use super::scrollbar::render_scrollbar;
+use super::view::{Action, View, ViewType};
+use crate::config::{ColorScheme, Symbols};
+use crate::error::{AppError, AppResult};
+use crate::git::{GitOutput, Hook, Hooks, Repository, Result as GitResult};
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    Frame,
+    layout::{Constraint, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{List, ListItem, ListState, Paragraph},
+};
+use tokio::sync::mpsc;
+
+/// Lines of hook output kept
+const OUTPUT_LINES: usize = 1000;
+
+/// A hook's output as it runs, or why it couldn't start
+type HookStarted = GitResult<mpsc::UnboundedReceiver<GitOutput>>;
+
+/// A hook's test run: the hook, its output so far, and how it ended
+struct HookTest {
+    name: String,
+    log: OutputLog,
+    receiver: Option<mpsc::UnboundedReceiver<GitOutput>>,
+    exited: Option<std::result::Result<(), String>>,
+}
+
+/// The hooks directory, with which hooks git runs, each script, and
+/// running a hook to see whether it passes
+pub struct HooksView {
+    repo: Repository,
+    hooks: Option<Hooks>,
+    list_state: ListState,
+    loading: bool,
+    error: Option<String>,
+    receiver: Option<mpsc::UnboundedReceiver<GitResult<Hooks>>>,
+    chmod_receiver: Option<mpsc::UnboundedReceiver<GitResult<()>>>,
+    start_receiver: Option<mpsc::UnboundedReceiver<HookStarted>>,
+    /// The last test run, shown instead of the script while its hook is
+    /// selected
+    test: Option<HookTest>,
+    /// First line of the script or output shown
+    scroll: usize,
+    colors: ColorScheme,
+    symbols: Symbols,
+}
+
+impl HooksView {
+    /// Create a new hooks view
+    pub fn new(repo: Repository, colors: ColorScheme) -> Self {
+        let mut list_state = ListState::default();
+        list_state.select(Some(0));
+
+        Self {
+            repo,
+            hooks: None,
+            list_state,
+            loading: false,
+            error: None,
+            receiver: None,
+            chmod_receiver: None,
+            start_receiver: None,
+            test: None,
+            scroll: 0,
+            colors,
+            symbols: Symbols::default(),
+        }
+    }
+
+    /// Set the glyphs used for borders
+    pub fn with_symbols(mut self, symbols: Symbols) -> Self {
+        self.symbols = symbols;
+        self
+    }
+
+    /// Start listing the hooks asynchronously
+    pub fn start_loading(&mut self) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.receiver = Some(rx);
+        self.loading = true;
+
+        let repo = self.repo.clone();
+        tokio::spawn(async move {
+            let _ = tx.send(crate::git::hooks::load_hooks(&repo).await);
+        });
+    }
+
+    fn selected_hook(&self) -> Option<&Hook> {
+        self.hooks.as_ref()?.hooks.get(self.list_state.selected()?)
+    }
+
+    /// Flip the selected hook's executable bit
+    fn toggle_executable(&mut self) -> AppResult<()> {
+        let Some(hook) = self.selected_hook() else {
+            return Err(AppError::recoverable("No hook selected"));
+        };
+        let (path, executable) = (hook.path.clone(), !hook.executable);
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.chmod_receiver = Some(rx);
+        tokio::spawn(async move {
+            let _ = tx.send(crate::git::hooks::set_executable(path, executable).await);
+        });
+        Ok(())
+    }
+
+    /// Run the selected hook against the repository as it is
+    fn start_test(&mut self) -> AppResult<()> {
+        let Some(hook) = self.selected_hook().cloned() else {
+            return Err(AppError::recoverable("No hook selected"));
+        };
+        if self
+            .test
+            .as_ref()
+            .is_some_and(|test| test.receiver.is_some())
+            || self.start_receiver.is_some()
+        {
+            return Err(AppError::recoverable("Wait for the running hook to finish"));
+        }
+        self.test = Some(HookTest {
+            name: hook.name.clone(),
+            log: OutputLog::new(&hook.path.display().to_string(), OUTPUT_LINES),
+            receiver: None,
+            exited: None,
+        });
+        self.scroll = 0;
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.start_receiver = Some(rx);
+        let repo = self.repo.clone();
+        tokio::spawn(async move {
+            let _ = tx.send(crate::git::hooks::test_hook(&repo, &hook).await);
+        });
+        Ok(())
+    }
+
+    /// The test run of the selected hook, if it has one
+    fn selected_test(&self) -> Option<&HookTest> {
+        let name = &self.selected_hook()?.name;
+        self.test.as_ref().filter(|test| &test.name == name)
+    }
+
+    fn select(&mut self, i: usize) {
+        let last = self
+            .hooks
+            .as_ref()
+            .map_or(0, |hooks| hooks.hooks.len().saturating_sub(1));
+        self.list_state.select(Some(i.min(last)));
+        self.scroll = 0;
+    }
+
+    /// Describe whether git runs a hook
+    fn state(hook: &Hook) -> &'static str {
+        match (hook.is_sample(), hook.executable) {
+            (true, _) => "sample",
+            (false, true) => "enabled",
+            (false, false) => "not executable",
+        }
+    }
+
+    fn create_list_item(&self, hook: &Hook) -> ListItem<'static> {
+        let style = match (hook.is_enabled(), hook.is_sample()) {
+            (true, _) => Style::default().fg(self.colors.added),
+            (false, false) => Style::default().fg(self.colors.deleted),
+            (false, true) => Style::default().fg(self.colors.unmodified),
+        };
+        ListItem::new(Line::from(vec![
+            Span::styled(format!("{:<28}", hook.name), style),
+            Span::styled(Self::state(hook).to_string(), style),
+        ]))
+    }
+}
+
+impl View for HooksView {
+    fn handle_key(&mut self, key: KeyEvent) -> AppResult<Action> {
+        self.handle_key_with_count(key, 1)
+    }
+
+    fn handle_key_with_count(&mut self, key: KeyEvent, count: usize) -> AppResult<Action> {
+        let selected = self.list_state.selected().unwrap_or(0);
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(Action::PopView),
+            KeyCode::Char('j') | KeyCode::Down => self.select(selected + count),
+            KeyCode::Char('k') | KeyCode::Up => self.select(selected.saturating_sub(count)),
+            KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.scroll += 10 * count
+            }
+            KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.scroll = self.scroll.saturating_sub(10 * count)
+            }
+            KeyCode::Char('x') if self.chmod_receiver.is_none() => self.toggle_executable()?,
+            KeyCode::Char('t') => self.start_test()?,
+            KeyCode::Char('r') if !self.loading => self.refresh(),
+            KeyCode::Char('?') => return Ok(Action::ShowHelp(ViewType::Hooks)),
+            _ => {}
+        }
+        Ok(Action::None)
+    }
+
+    fn update(&mut self) -> AppResult<()> {
+        if let Some(receiver) = &mut self.receiver
+            && let Ok(result) = receiver.try_recv()
+        {
+            self.loading = false;
+            self.receiver = None;
+            match result {
+                Ok(hooks) => {
+                    self.hooks = Some(hooks);
+                    self.select(self.list_state.selected().unwrap_or(0));
+                }
+                Err(e) => self.error = Some(format!("Failed to list the hooks: {}", e)),
+            }
+        }
+
+        if let Some(receiver) = &mut self.chmod_receiver
+            && let Ok(result) = receiver.try_recv()
+        {
+            self.chmod_receiver = None;
+            self.start_loading();
+            result.map_err(|e| AppError::recoverable(format!("Cannot change the hook: {}", e)))?;
+        }
+
+        if let Some(receiver) = &mut self.start_receiver
+            && let Ok(result) = receiver.try_recv()
+        {
+            self.start_receiver = None;
+            match (result, &mut self.test) {
+                (Ok(output), Some(test)) => test.receiver = Some(output),
+                (Ok(_), None) => {}
+                (Err(e), _) => {
+                    self.test = None;
+                    return Err(AppError::recoverable(e.to_string()));
+                }
+            }
+        }
+
+        if let Some(test) = &mut self.test
+            && let Some(receiver) = &mut test.receiver
+            && let Some(exited) = test.log.drain(receiver)
+        {
+            test.receiver = None;
+            test.exited = Some(exited);
+        }
+        Ok(())
+    }
+
+    fn draw(&mut self, frame: &mut Frame, area: Rect) {
+        if let Some(error) = &self.error {
+            let list = List::new(vec![ListItem::new(error.clone())])
+                .block(self.symbols.block().title("Hooks - Error"))
+                .style(Style::default().fg(self.colors.deleted));
+            frame.render_widget(list, area);
+            return;
+        }
+
+        let Some(hooks) = &self.hooks else {
+            let list = List::new(vec![ListItem::new("Loading hooks...")])
+                .block(self.symbols.block().title("Hooks"));
+            frame.render_widget(list, area);
+            return;
+        };
+
+        let [list_area, script_area] =
+            Layout::vertical([Constraint::Percentage(40), Constraint::Percentage(60)]).areas(area);
+        let title = format!("Hooks in {}", hooks.dir.display());
+        let items: Vec<ListItem> = match hooks.hooks.is_empty() {
+            true => vec![ListItem::new("No hooks")],
+            false => hooks
+                .hooks
+                .iter()
+                .map(|hook| self.create_list_item(hook))
+                .collect(),
+        };
+        let list = List::new(items)
+            .block(self.symbols.block().title(title))
+            .highlight_style(self.colors.selected.add_modifier(Modifier::BOLD))
+            .highlight_symbol("> ");
+        frame.render_stateful_widget(list, list_area, &mut self.list_state);
+        let selected = self.list_state.selected().unwrap_or(0);
+        render_scrollbar(frame, list_area, &self.symbols, hooks.hooks.len(), selected);
+
+        let height = script_area.height.saturating_sub(2) as usize;
+        let (title, lines, style) = match (self.selected_test(), self.selected_hook()) {
+            (Some(test), _) => {
+                let (state, style) = match &test.exited {
+                    None => ("running".to_string(), Style::default()),
+                    Some(Ok(())) => ("passed".to_string(), Style::default().fg(self.colors.added)),
+                    Some(Err(message)) => {
+                        (message.clone(), Style::default().fg(self.colors.deleted))
+                    }
+                };
+                (
+                    format!("Test {} - {}", test.name, state),
+                    test.log.lines(self.scroll, height),
+                    style,
+                )
+            }
+            (None, Some(hook)) => {
+                let lines = hook
+                    .script
+                    .lines()
+                    .skip(self.scroll)
+                    .take(height)
+                    .map(|line| Line::from(line.to_string()));
+                (hook.name.clone(), lines.collect(), Style::default())
+            }
+            (None, None) => (String::new(), Vec::new(), Style::default()),
+        };
+        let script =
+            Paragraph::new(lines).block(self.symbols.block().title(title).border_style(style));
+        frame.render_widget(script, script_area);
+    }
+
+    fn title(&self) -> &str {
+        "Hooks"
+    }
+
+    fn view_type(&self) -> ViewType {
+        ViewType::Hooks
+    }
+
+    fn refresh(&mut self) {
+        self.error = None;
+        self.start_loading();
+    }
+
+    fn busy(&self) -> Option<&'static str> {
+        let running = self.start_receiver.is_some()
+            || self
+                .test
+                .as_ref()
+                .is_some_and(|test| test.receiver.is_some());
+        running.then_some("running a hook")
+    }
+
+    fn on_activate(&mut self) -> Result<()> {
+        if self.hooks.is_none() && !self.loading {
+            self.start_loading();
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::testing::RepoBuilder;
+
+    async fn settle(view: &mut HooksView) {
+        while view.receiver.is_some() || view.chmod_receiver.is_some() || view.busy().is_some() {
+            tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+            let _ = view.update();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_toggle_and_test_a_hook() {
+        let (_temp_dir, repo) = RepoBuilder::new().commit("Initial").open().await;
+        let dir = repo.open_git2().unwrap().path().join("hooks");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("pre-commit"),
+            "#!/bin/sh\necho lint failed\nexit 1\n",
+        )
+        .unwrap();
+
+        let mut view = HooksView::new(repo, ColorScheme::from_config(&Config::default().colors));
+        view.on_activate().unwrap();
+        settle(&mut view).await;
+        assert_eq!(
+            HooksView::state(view.selected_hook().unwrap()),
+            "not executable"
+        );
+
+        view.handle_key(KeyEvent::from(KeyCode::Char('x'))).unwrap();
+        settle(&mut view).await;
+        assert_eq!(HooksView::state(view.selected_hook().unwrap()), "enabled");
+
+        view.handle_key(KeyEvent::from(KeyCode::Char('t'))).unwrap();
+        assert_eq!(view.busy(), Some("running a hook"));
+        settle(&mut view).await;
+        let test = view.selected_test().unwrap();
+        assert_eq!(test.log.lines(1, 1)[0].to_string(), "lint failed");
+        assert!(test.exited.as_ref().is_some_and(|exited| exited.is_err()));
+    }
+}
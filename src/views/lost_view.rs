@@ -0,0 +1,344 @@
+use super::scrollbar::render_scrollbar;
+use super::view::{Action, Selection, View, ViewType};
+use crate::config::{ColorScheme, Symbols};
+use crate::error::{AppError, AppResult};
+use crate::git::{LostCommit, LostSource, Repository, Result as GitResult};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use git2::Oid;
+use ratatui::{
+    Frame,
+    layout::{Constraint, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{List, ListItem, ListState, Paragraph},
+};
+use tokio::sync::mpsc;
+
+/// The commit a recovery branched from, and the new branch's name
+type Recovered = GitResult<(Oid, String)>;
+
+/// Commits found by a lost commit search, with recovering one to a new
+/// branch
+pub struct LostView {
+    repo: Repository,
+    query: String,
+    commits: Vec<LostCommit>,
+    list_state: ListState,
+    /// The branch name being typed for the selected commit
+    branch_prompt: Option<String>,
+    recover_receiver: Option<mpsc::UnboundedReceiver<Recovered>>,
+    /// What the last recovery did, shown in the title
+    notice: Option<String>,
+    /// Set once a branch was created, so every view reloads
+    history_changed: bool,
+    colors: ColorScheme,
+    symbols: Symbols,
+}
+
+impl LostView {
+    /// Create a view of the commits a search for `query` found
+    pub fn new(
+        repo: Repository,
+        colors: ColorScheme,
+        query: String,
+        commits: Vec<LostCommit>,
+    ) -> Self {
+        let mut list_state = ListState::default();
+        list_state.select(Some(0));
+
+        Self {
+            repo,
+            query,
+            commits,
+            list_state,
+            branch_prompt: None,
+            recover_receiver: None,
+            notice: None,
+            history_changed: false,
+            colors,
+            symbols: Symbols::default(),
+        }
+    }
+
+    /// Set the glyphs used for borders and the scrollbar
+    pub fn with_symbols(mut self, symbols: Symbols) -> Self {
+        self.symbols = symbols;
+        self
+    }
+
+    fn selected_commit(&self) -> Option<&LostCommit> {
+        self.commits.get(self.list_state.selected()?)
+    }
+
+    /// Handle a key while a branch name is being typed
+    fn handle_prompt_key(&mut self, key: KeyEvent) -> AppResult<()> {
+        let Some(name) = &mut self.branch_prompt else {
+            return Ok(());
+        };
+        match key.code {
+            KeyCode::Esc => self.branch_prompt = None,
+            KeyCode::Backspace => {
+                name.pop();
+            }
+            KeyCode::Char(c) if !c.is_whitespace() => name.push(c),
+            KeyCode::Enter if !name.is_empty() => {
+                let name = self.branch_prompt.take().unwrap_or_default();
+                self.start_recover(name)?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Create a branch at the selected commit
+    fn start_recover(&mut self, name: String) -> AppResult<()> {
+        let Some(lost) = self.selected_commit() else {
+            return Err(AppError::recoverable("Select a commit to recover"));
+        };
+        let oid = lost.commit.id;
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.recover_receiver = Some(rx);
+
+        let repo = self.repo.clone();
+        tokio::spawn(async move {
+            let result = crate::git::lost::recover(&repo, oid, name.clone()).await;
+            let _ = tx.send(result.map(|()| (oid, name)));
+        });
+        Ok(())
+    }
+
+    /// Move selection down by `amount`, stopping at the last commit
+    fn select_down(&mut self, amount: usize) {
+        let last = self.commits.len().saturating_sub(1);
+        let i = self
+            .list_state
+            .selected()
+            .map_or(0, |i| (i + amount).min(last));
+        self.list_state.select(Some(i));
+    }
+
+    /// Move selection up by `amount`, stopping at the first commit
+    fn select_up(&mut self, amount: usize) {
+        let i = self
+            .list_state
+            .selected()
+            .map_or(0, |i| i.saturating_sub(amount));
+        self.list_state.select(Some(i));
+    }
+
+    fn create_list_item(&self, lost: &LostCommit) -> ListItem<'static> {
+        let commit = &lost.commit;
+        let source = match &lost.source {
+            LostSource::Reflog { entry, message } => format!("{}: {}", entry, message),
+            LostSource::Unreachable => "unreachable".to_string(),
+        };
+        ListItem::new(Line::from(vec![
+            Span::styled(
+                format!("{} ", commit.short_id),
+                Style::default().fg(self.colors.commit_hash),
+            ),
+            Span::styled(
+                format!("{} ", commit.date_str()),
+                Style::default().fg(self.colors.date),
+            ),
+            Span::raw(commit.summary.clone()),
+            Span::styled(
+                format!("  ({})", source),
+                Style::default().fg(self.colors.author),
+            ),
+        ]))
+    }
+}
+
+impl View for LostView {
+    fn handle_key(&mut self, key: KeyEvent) -> AppResult<Action> {
+        self.handle_key_with_count(key, 1)
+    }
+
+    fn handle_key_with_count(&mut self, key: KeyEvent, count: usize) -> AppResult<Action> {
+        if self.branch_prompt.is_some() {
+            self.handle_prompt_key(key)?;
+            return Ok(Action::None);
+        }
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(Action::PopView),
+            KeyCode::Char('j') | KeyCode::Down => self.select_down(count),
+            KeyCode::Char('k') | KeyCode::Up => self.select_up(count),
+            KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.select_down(10 * count)
+            }
+            KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.select_up(10 * count)
+            }
+            KeyCode::PageDown => self.select_down(20 * count),
+            KeyCode::PageUp => self.select_up(20 * count),
+            KeyCode::Char('g') => self.list_state.select(Some(0)),
+            KeyCode::Char('G') => self
+                .list_state
+                .select(Some(self.commits.len().saturating_sub(1))),
+            KeyCode::Char('b')
+                if self.recover_receiver.is_none() && self.selected_commit().is_some() =>
+            {
+                self.branch_prompt = Some(String::new());
+            }
+            KeyCode::Enter => {
+                if let Some(lost) = self.selected_commit() {
+                    return Ok(Action::OpenDiff {
+                        repo: self.repo.clone(),
+                        commit_id: lost.commit.id,
+                        summary: lost.commit.summary.clone(),
+                    });
+                }
+            }
+            KeyCode::Char('?') => return Ok(Action::ShowHelp(ViewType::Lost)),
+            _ => {}
+        }
+        Ok(Action::None)
+    }
+
+    fn update(&mut self) -> AppResult<()> {
+        if let Some(receiver) = &mut self.recover_receiver
+            && let Ok(result) = receiver.try_recv()
+        {
+            self.recover_receiver = None;
+            let (oid, name) = result
+                .map_err(|e| AppError::recoverable(format!("Cannot recover the commit: {}", e)))?;
+            // The recovered commit is reachable now, so it is no longer lost
+            self.commits.retain(|lost| lost.commit.id != oid);
+            self.select_down(0);
+            self.notice = Some(format!("recovered to {}", name));
+            self.history_changed = true;
+        }
+        Ok(())
+    }
+
+    fn poll_action(&mut self) -> Action {
+        match std::mem::take(&mut self.history_changed) {
+            true => Action::Refresh,
+            false => Action::None,
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame, area: Rect) {
+        let mut title = format!("Lost Commits - {} found", self.commits.len());
+        if !self.query.is_empty() {
+            title.push_str(&format!(" for \"{}\"", self.query));
+        }
+        if let Some(notice) = &self.notice {
+            title.push_str(&format!(" [{}]", notice));
+        }
+
+        let (list_area, prompt_area) = match self.branch_prompt.is_some() {
+            true => {
+                let [list, prompt] =
+                    Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(area);
+                (list, Some(prompt))
+            }
+            false => (area, None),
+        };
+
+        let items: Vec<ListItem> = match self.commits.is_empty() {
+            true => vec![ListItem::new("Every lost commit has been recovered")],
+            false => self
+                .commits
+                .iter()
+                .map(|lost| self.create_list_item(lost))
+                .collect(),
+        };
+        let list = List::new(items)
+            .block(self.symbols.block().title(title))
+            .highlight_style(self.colors.selected.add_modifier(Modifier::BOLD))
+            .highlight_symbol("> ");
+
+        frame.render_stateful_widget(list, list_area, &mut self.list_state);
+        let selected = self.list_state.selected().unwrap_or(0);
+        render_scrollbar(
+            frame,
+            list_area,
+            &self.symbols,
+            self.commits.len(),
+            selected,
+        );
+
+        if let (Some(name), Some(prompt_area)) = (&self.branch_prompt, prompt_area) {
+            let prompt = Paragraph::new(format!("New branch: {}_", name));
+            frame.render_widget(prompt, prompt_area);
+        }
+    }
+
+    fn title(&self) -> &str {
+        "Lost Commits"
+    }
+
+    fn view_type(&self) -> ViewType {
+        ViewType::Lost
+    }
+
+    fn selection(&self) -> Selection {
+        Selection {
+            commit: self.selected_commit().map(|lost| lost.commit.id),
+            ..Selection::default()
+        }
+    }
+
+    fn captures_input(&self) -> bool {
+        self.branch_prompt.is_some()
+    }
+
+    fn busy(&self) -> Option<&'static str> {
+        self.recover_receiver.as_ref().map(|_| "recovering")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::git::Commit;
+    use crate::testing::RepoBuilder;
+
+    #[tokio::test]
+    async fn test_recovers_selected_commit_to_a_branch() {
+        let builder = RepoBuilder::new().commit("Base");
+        let head = builder.head();
+        let commit = Commit::from_git2(&builder.git2().find_commit(head).unwrap()).unwrap();
+        let (_temp_dir, repo) = builder.open().await;
+        let lost = LostCommit {
+            commit,
+            source: LostSource::Unreachable,
+        };
+
+        let mut view = LostView::new(
+            repo.clone(),
+            ColorScheme::from_config(&Config::default().colors),
+            String::new(),
+            vec![lost],
+        );
+        assert!(matches!(
+            view.handle_key(KeyEvent::from(KeyCode::Enter)).unwrap(),
+            Action::OpenDiff { .. }
+        ));
+
+        view.handle_key(KeyEvent::from(KeyCode::Char('b'))).unwrap();
+        assert!(view.captures_input());
+        for c in "found".chars() {
+            view.handle_key(KeyEvent::from(KeyCode::Char(c))).unwrap();
+        }
+        view.handle_key(KeyEvent::from(KeyCode::Enter)).unwrap();
+        assert!(!view.captures_input());
+        assert_eq!(view.busy(), Some("recovering"));
+        while view.recover_receiver.is_some() {
+            tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+            view.update().unwrap();
+        }
+        assert!(view.commits.is_empty());
+        assert_eq!(view.notice.as_deref(), Some("recovered to found"));
+        assert_eq!(view.poll_action(), Action::Refresh);
+        assert!(
+            repo.open_git2()
+                .unwrap()
+                .find_branch("found", git2::BranchType::Local)
+                .is_ok()
+        );
+    }
+}
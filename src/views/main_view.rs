@@ -1,16 +1,47 @@
-use super::view::{Action, View};
-use crate::config::ColorScheme;
-use crate::git::{Commit, CommitWalker, Repository};
+use super::copy_menu::{CopyMenu, CopyMenuEvent};
+use super::filter::{CommitFilter, FilterPanel, FilterPanelEvent};
+use super::history::InputHistory;
+use super::popup::centered_rect;
+use super::quick_look::QuickLook;
+use super::refs_popup::RefsPopup;
+use super::scrollbar::render_scrollbar;
+use super::search;
+use super::title::{group_digits, render_title};
+use super::view::{Action, Selection, View, ViewType};
+use crate::clock::{SharedClock, system_clock};
+use crate::config::{ColorScheme, ColumnWidths, SearchCase, Symbols, TitleTemplates, author_color};
+use crate::error::{AppError, AppResult};
+use crate::git::diff_cache::DiffKey;
+use crate::git::walker::WALK_BUFFER_CHUNKS;
+use crate::git::{
+    ChangelogTemplate, CheckoutTarget, CherryMark, Commit, CommitWalker, Conventional, RefFilter,
+    Repository, Result as GitResult, SizeLimit, SplitState,
+};
+use crate::git::{changelog, enrich};
 use anyhow::Result;
-use crossterm::event::{KeyCode, KeyEvent};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use git2::Oid;
 use ratatui::{
-    layout::{Constraint, Rect},
+    Frame,
+    layout::{Alignment, Constraint, Margin, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Row, Table, TableState},
-    Frame,
+    widgets::{Row, Table, TableState},
 };
-use tokio::sync::mpsc;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::{self, error::TryRecvError};
+
+/// How long the selection rests before the diffs around it are loaded
+const PREFETCH_DELAY: Duration = Duration::from_millis(300);
+
+/// Commits on each side of the selection whose diffs are loaded ahead
+const PREFETCH_AROUND: usize = 3;
+
+/// A commit with its nearest tag, if any
+type Described = (Oid, Option<String>);
+/// A commit with the lines it adds and removes
+type DiffStat = (Oid, (usize, usize));
 
 /// Search mode state
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -19,6 +50,21 @@ enum SearchMode {
     Active,
 }
 
+/// Describe the selected row's place in the history, like
+/// ` commit 37/12,034 (0%) `, or None when there are no rows
+fn position_label(selected: usize, rows: usize) -> Option<String> {
+    if rows == 0 {
+        return None;
+    }
+    let current = selected.min(rows - 1) + 1;
+    Some(format!(
+        " commit {}/{} ({}%) ",
+        group_digits(current),
+        group_digits(rows),
+        current * 100 / rows
+    ))
+}
+
 /// Main view showing commit history
 pub struct MainView {
     repo: Repository,
@@ -27,10 +73,78 @@ pub struct MainView {
     table_state: TableState,
     loading: bool,
     error: Option<String>,
-    receiver: Option<mpsc::UnboundedReceiver<Vec<Commit>>>,
+    receiver: Option<mpsc::Receiver<Vec<Commit>>>,
     search_mode: SearchMode,
     search_query: String,
+    search_case: SearchCase,
+    search_history: InputHistory,
+    filter: CommitFilter,
+    filter_panel: Option<FilterPanel>,
+    quick_look: Option<QuickLook>,
+    refs_popup: Option<RefsPopup>,
+    /// The selected commit in each copy format, opened with `Y`
+    copy_menu: Option<CopyMenu>,
+    copy_formats: Vec<String>,
+    /// Text picked from the copy menu, for the app to copy
+    copied: Option<String>,
+    /// How `L` writes a range of commits as a changelog
+    changelog: ChangelogTemplate,
+    changelog_receiver: Option<mpsc::UnboundedReceiver<String>>,
+    /// Commit to select again once a refresh loads it
+    reselect: Option<Oid>,
+    /// Commit marked with `m`, the base `d` diffs the selection against
+    mark: Option<Oid>,
+    /// Commits toggled with `x` for `P` to cherry-pick together
+    picks: Vec<Oid>,
+    /// Nearest tags of commits, filled in as rows come into view
+    describe_column: bool,
+    describes: HashMap<Oid, Option<String>>,
+    describe_receiver: Option<mpsc::UnboundedReceiver<Vec<Described>>>,
+    /// Lines added and removed by commits, also counted as rows come into view
+    diffstat_column: bool,
+    diffstats: HashMap<Oid, (usize, usize)>,
+    diffstat_receiver: Option<mpsc::UnboundedReceiver<Vec<DiffStat>>>,
+    /// Files over this many bytes are left unread in prefetched and
+    /// previewed diffs, as the diff view would
+    max_diff_file_bytes: u64,
+    /// Color authors by a hash of their email instead of all alike
+    author_colors: bool,
+    /// Show conventional-commit types as badges before the description
+    conventional_commits: bool,
+    /// Rows that fit in the table, as of the last draw
+    visible_rows: usize,
+    /// Bounds of the author and date columns, and the widest author and
+    /// date of the loaded commits
+    column_widths: ColumnWidths,
+    author_width: usize,
+    date_width: usize,
+    /// Width below which rows show only the hash and the message, with the
+    /// date after it
+    compact_width: u16,
+    /// Template of the title, filled in by `render_title`
+    title_template: String,
+    /// Revision compared against: only commits in `upstream..HEAD` show,
+    /// marked by whether their change already landed upstream
+    comparison: Option<String>,
+    cherries: HashMap<Oid, CherryMark>,
+    cherry_receiver: Option<mpsc::UnboundedReceiver<HashMap<Oid, CherryMark>>>,
+    /// Commits to show instead of the history of HEAD, e.g. read from stdin
+    listed: Option<Vec<Oid>>,
+    /// Which refs decorate the history and which histories the walk takes
+    /// in or leaves out
+    ref_filter: RefFilter,
+    /// A split started with `S`, which opens the status view once ready
+    split_receiver: Option<mpsc::UnboundedReceiver<GitResult<SplitState>>>,
+    split_started: bool,
+    /// The selected commit and when it was selected, so the diffs around it
+    /// load once the selection rests
+    prefetch_anchor: Option<(Oid, Instant)>,
+    /// The commit whose neighbours' diffs were loaded last
+    prefetched: Option<Oid>,
+    prefetch_task: Option<tokio::task::JoinHandle<()>>,
     colors: ColorScheme,
+    symbols: Symbols,
+    clock: SharedClock,
 }
 
 impl MainView {
@@ -49,17 +163,176 @@ impl MainView {
             receiver: None,
             search_mode: SearchMode::Inactive,
             search_query: String::new(),
+            search_case: SearchCase::default(),
+            search_history: InputHistory::default(),
+            filter: CommitFilter::default(),
+            filter_panel: None,
+            quick_look: None,
+            refs_popup: None,
+            copy_menu: None,
+            copy_formats: vec!["%H".to_string()],
+            copied: None,
+            changelog: ChangelogTemplate::default(),
+            changelog_receiver: None,
+            reselect: None,
+            mark: None,
+            picks: Vec::new(),
+            describe_column: false,
+            describes: HashMap::new(),
+            describe_receiver: None,
+            diffstat_column: false,
+            max_diff_file_bytes: 0,
+            diffstats: HashMap::new(),
+            diffstat_receiver: None,
+            author_colors: false,
+            conventional_commits: false,
+            visible_rows: 0,
+            column_widths: ColumnWidths::default(),
+            author_width: 0,
+            date_width: 0,
+            compact_width: 0,
+            title_template: TitleTemplates::default().main,
+            comparison: None,
+            cherries: HashMap::new(),
+            cherry_receiver: None,
+            listed: None,
+            ref_filter: RefFilter::default(),
+            split_receiver: None,
+            split_started: false,
+            prefetch_anchor: None,
+            prefetched: None,
+            prefetch_task: None,
             colors,
+            symbols: Symbols::default(),
+            clock: system_clock(),
         }
     }
 
+    /// Set the glyphs used for borders
+    pub fn with_symbols(mut self, symbols: Symbols) -> Self {
+        self.symbols = symbols;
+        self
+    }
+
+    /// Set the clock relative commit dates are measured from
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Set the case sensitivity used by searches
+    pub fn with_search_case(mut self, search_case: SearchCase) -> Self {
+        self.search_case = search_case;
+        self
+    }
+
+    /// Set the previously submitted search queries, oldest first
+    pub fn with_search_history(mut self, entries: Vec<String>) -> Self {
+        self.search_history = InputHistory::new(entries);
+        self
+    }
+
+    /// Show each commit's nearest tag in its own column
+    pub fn with_describe_column(mut self, describe_column: bool) -> Self {
+        self.describe_column = describe_column;
+        self
+    }
+
+    /// Show how many lines each commit adds and removes
+    pub fn with_diffstat_column(mut self, diffstat_column: bool) -> Self {
+        self.diffstat_column = diffstat_column;
+        self
+    }
+
+    /// Skip reading files larger than this many bytes in the diffs it
+    /// loads (0 disables)
+    pub fn with_max_diff_file_bytes(mut self, bytes: u64) -> Self {
+        self.max_diff_file_bytes = bytes;
+        self
+    }
+
+    /// Give each author a stable color of their own
+    pub fn with_author_colors(mut self, author_colors: bool) -> Self {
+        self.author_colors = author_colors;
+        self
+    }
+
+    /// Set the bounds of the author and date columns
+    pub fn with_column_widths(mut self, column_widths: ColumnWidths) -> Self {
+        self.column_widths = column_widths;
+        self
+    }
+
+    /// Set the template of the title
+    pub fn with_title_template(mut self, title_template: String) -> Self {
+        self.title_template = title_template;
+        self
+    }
+
+    /// Switch to the compact layout in areas narrower than `compact_width`
+    pub fn with_compact_width(mut self, compact_width: u16) -> Self {
+        self.compact_width = compact_width;
+        self
+    }
+
+    /// Show conventional-commit types, like `feat(parser):`, as colored
+    /// badges
+    pub fn with_conventional_commits(mut self, conventional_commits: bool) -> Self {
+        self.conventional_commits = conventional_commits;
+        self
+    }
+
+    /// Show only the commits on HEAD but not on `upstream`, like
+    /// `git cherry`
+    pub fn with_comparison<S: Into<String>>(mut self, upstream: S) -> Self {
+        self.comparison = Some(upstream.into());
+        self
+    }
+
+    /// Set which refs decorate the history and which histories it takes in
+    /// or leaves out
+    pub fn with_ref_filter(mut self, ref_filter: RefFilter) -> Self {
+        self.ref_filter = ref_filter;
+        self
+    }
+
+    /// Set the formats the copy menu offers the selected commit in
+    pub fn with_copy_formats(mut self, copy_formats: Vec<String>) -> Self {
+        self.copy_formats = copy_formats;
+        self
+    }
+
+    /// Set how `L` writes a range of commits as a changelog
+    pub fn with_changelog(mut self, changelog: ChangelogTemplate) -> Self {
+        self.changelog = changelog;
+        self
+    }
+
+    /// Show exactly these commits, in this order, instead of the history
+    pub fn with_commit_list(mut self, ids: Vec<Oid>) -> Self {
+        self.listed = Some(ids);
+        self
+    }
+
     /// Start loading commits asynchronously
     pub fn start_loading(&mut self) {
-        let (tx, rx) = mpsc::unbounded_channel();
+        let (tx, rx) = mpsc::channel(WALK_BUFFER_CHUNKS);
         self.receiver = Some(rx);
         self.loading = true;
 
-        let walker = CommitWalker::new(self.repo.clone()).with_chunk_size(50);
+        let mut walker = CommitWalker::new(self.repo.clone())
+            .with_chunk_size(50)
+            .with_ref_filter(self.ref_filter.clone());
+        if let Some(path) = &self.filter.path {
+            walker = walker.with_path(path.clone());
+        }
+        if let Some(ids) = &self.listed {
+            walker = walker.with_commits(ids.clone());
+        }
+        if let Some(upstream) = &self.comparison {
+            walker = walker.with_hidden(upstream.clone());
+            self.start_cherry(upstream.clone());
+        }
 
         tokio::spawn(async move {
             if let Err(e) = walker.walk(tx).await {
@@ -68,85 +341,317 @@ impl MainView {
         });
     }
 
+    /// Compare patch ids with `upstream` in the background
+    fn start_cherry(&mut self, upstream: String) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.cherry_receiver = Some(rx);
+        let repo = self.repo.clone();
+
+        tokio::spawn(async move {
+            // Without marks the comparison still lists the commits
+            if let Ok(marks) = crate::git::cherry::cherry_marks(&repo, upstream).await {
+                let _ = tx.send(marks);
+            }
+        });
+    }
+
     /// Get the currently selected commit
     pub fn selected_commit(&self) -> Option<&Commit> {
-        self.table_state.selected().and_then(|i| {
-            if self.is_searching() {
-                self.filtered_commits.get(i).and_then(|&idx| self.commits.get(idx))
-            } else {
-                self.commits.get(i)
-            }
-        })
+        self.table_state.selected().and_then(|i| self.commit_at(i))
+    }
+
+    /// Get the commit shown on a row
+    fn commit_at(&self, row: usize) -> Option<&Commit> {
+        match self.is_filtered() {
+            true => self
+                .filtered_commits
+                .get(row)
+                .and_then(|&i| self.commits.get(i)),
+            false => self.commits.get(row),
+        }
+    }
+
+    /// Get the OID of the currently selected commit
+    fn selected_oid(&self) -> Option<Oid> {
+        self.selected_commit().map(|commit| commit.id)
     }
 
-    /// Get the list of commits to display (filtered or all)
-    fn displayed_commits(&self) -> Vec<&Commit> {
-        if self.is_searching() {
+    /// Select the commit with the given OID if it is displayed, otherwise the first row
+    fn select_oid(&mut self, oid: Option<Oid>) {
+        let position = oid.and_then(|oid| self.row_of(oid));
+        self.table_state.select(Some(position.unwrap_or(0)));
+    }
+
+    /// Get the row a commit is shown on, if it is displayed
+    fn row_of(&self, oid: Oid) -> Option<usize> {
+        if self.is_filtered() {
             self.filtered_commits
                 .iter()
-                .filter_map(|&i| self.commits.get(i))
-                .collect()
+                .position(|&i| self.commits.get(i).is_some_and(|c| c.id == oid))
         } else {
-            self.commits.iter().collect()
+            self.commits.iter().position(|c| c.id == oid)
         }
     }
 
-    /// Check if search mode is active
-    fn is_searching(&self) -> bool {
-        self.search_mode == SearchMode::Active && !self.search_query.is_empty()
+    /// Mark the selected commit, or clear the mark if it is the one marked
+    fn toggle_mark(&mut self) {
+        let selected = self.selected_oid();
+        self.mark = match self.mark == selected {
+            true => None,
+            false => selected,
+        };
     }
 
-    /// Update the search filter
-    fn update_search_filter(&mut self) {
-        if self.search_query.is_empty() {
-            self.filtered_commits.clear();
+    /// Add the selected commit to the commits to cherry-pick, or take it out
+    fn toggle_pick(&mut self) {
+        let Some(selected) = self.selected_oid() else {
             return;
+        };
+        match self.picks.iter().position(|&pick| pick == selected) {
+            Some(i) => {
+                self.picks.remove(i);
+            }
+            None => self.picks.push(selected),
         }
+    }
 
-        let query = self.search_query.to_lowercase();
-        self.filtered_commits = self
-            .commits
-            .iter()
-            .enumerate()
-            .filter(|(_, commit)| {
-                commit.summary.to_lowercase().contains(&query)
-                    || commit.author.to_lowercase().contains(&query)
-                    || commit.short_id.to_lowercase().contains(&query)
-            })
-            .map(|(i, _)| i)
+    /// Cherry-pick the commits toggled with `x`, or the selected one without
+    /// any, oldest first
+    fn cherry_pick(&mut self) -> Action {
+        let mut picks = match self.picks.is_empty() {
+            true => self.selected_oid().into_iter().collect(),
+            false => std::mem::take(&mut self.picks),
+        };
+        // The list is newest first
+        picks.sort_by_key(|&oid| std::cmp::Reverse(self.commits.iter().position(|c| c.id == oid)));
+        match picks.is_empty() {
+            true => Action::None,
+            false => Action::CherryPick(picks),
+        }
+    }
+
+    /// Diff the marked commit against the selected one
+    fn diff_from_mark(&self) -> AppResult<Action> {
+        let Some(from) = self.mark else {
+            return Err(AppError::recoverable(
+                "No marked commit; press m to mark one",
+            ));
+        };
+        match self.selected_oid() {
+            Some(to) if to != from => Ok(Action::OpenRangeDiff {
+                repo: self.repo.clone(),
+                from,
+                to,
+            }),
+            Some(_) => Err(AppError::recoverable(
+                "Select another commit to diff against the mark",
+            )),
+            None => Ok(Action::None),
+        }
+    }
+
+    /// Write the commits shown from the mark to the selection, or just the
+    /// selected one without a mark, as a changelog to copy
+    fn start_changelog(&mut self) -> AppResult<()> {
+        let Some(selected) = self.table_state.selected() else {
+            return Ok(());
+        };
+        let from = match self.mark {
+            Some(mark) => self
+                .row_of(mark)
+                .ok_or_else(|| AppError::recoverable("The marked commit is filtered out"))?,
+            None => selected,
+        };
+        let commits: Vec<Commit> = (from.min(selected)..=from.max(selected))
+            .filter_map(|row| self.commit_at(row).cloned())
             .collect();
 
-        // Reset selection to first result
-        if !self.filtered_commits.is_empty() {
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.changelog_receiver = Some(rx);
+        let (repo, template) = (self.repo.clone(), self.changelog.clone());
+        tokio::spawn(async move {
+            let web_url = crate::git::remote::web_url(&repo).await.ok().flatten();
+            let _ = tx.send(changelog::render(&commits, &template, web_url.as_deref()));
+        });
+        Ok(())
+    }
+
+    /// Start splitting the selected commit, to be staged and committed again
+    /// in pieces from the status view
+    fn start_split(&mut self) {
+        let Some(oid) = self.selected_oid() else {
+            return;
+        };
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.split_receiver = Some(rx);
+        let repo = self.repo.clone();
+        tokio::spawn(async move {
+            let _ = tx.send(crate::git::split::start_split(&repo, oid).await);
+        });
+    }
+
+    /// Check if a search query is narrowing the list
+    fn is_searching(&self) -> bool {
+        !self.search_query.is_empty()
+    }
+
+    /// Check if the displayed list is a subset of the loaded commits
+    fn is_filtered(&self) -> bool {
+        self.is_searching() || !self.filter.is_empty()
+    }
+
+    /// Check a commit against the filter and the search query
+    fn commit_matches(&self, commit: &Commit) -> bool {
+        if !self.filter.matches(commit) {
+            return false;
+        }
+        if !self.is_searching() {
+            return true;
+        }
+
+        let case_sensitive = self.search_case.is_case_sensitive(&self.search_query);
+        let query = self.search_query.as_str();
+        let matches = |text: &str| search::matches(text, query, case_sensitive);
+        matches(&commit.summary) || matches(&commit.author) || matches(&commit.short_id)
+    }
+
+    /// Recompute the displayed commits, keeping `selected` selected if it still matches
+    fn update_search_filter(&mut self, selected: Option<Oid>) {
+        self.filtered_commits = if self.is_filtered() {
+            self.commits
+                .iter()
+                .enumerate()
+                .filter(|(_, commit)| self.commit_matches(commit))
+                .map(|(i, _)| i)
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        // Keep the selected commit if it still matches, otherwise the first result
+        self.select_oid(selected);
+    }
+
+    /// Append newly loaded commits, filtering them as they arrive
+    ///
+    /// Only the new commits are checked, and their matches go after the
+    /// earlier ones, so the rows already shown and the selected row keep
+    /// their commits while a search runs against a walk still loading.
+    fn append_commits(&mut self, chunk: Vec<Commit>) {
+        for commit in &chunk {
+            self.author_width = self
+                .author_width
+                .max(Span::raw(commit.author.as_str()).width());
+            self.date_width = self
+                .date_width
+                .max(commit.relative_date(self.clock.as_ref()).len());
+        }
+        let start = self.commits.len();
+        self.commits.extend(chunk);
+
+        if self.is_filtered() {
+            let matching: Vec<usize> = (start..self.commits.len())
+                .filter(|&i| self.commit_matches(&self.commits[i]))
+                .collect();
+            self.filtered_commits.extend(matching);
+        }
+    }
+
+    /// Drop the loaded commits, before loading them again
+    fn clear_commits(&mut self) {
+        self.commits.clear();
+        self.filtered_commits.clear();
+        self.author_width = 0;
+        self.date_width = 0;
+    }
+
+    /// Replace the commit filter
+    ///
+    /// A changed path criterion restarts the walk, since paths are filtered
+    /// by the walker; other criteria are re-applied to the loaded commits.
+    fn apply_filter(&mut self, filter: CommitFilter) {
+        let selected = self.selected_oid();
+        let reload = filter.path != self.filter.path;
+        self.filter = filter;
+
+        if reload {
+            self.clear_commits();
             self.table_state.select(Some(0));
+            self.start_loading();
+        } else {
+            self.update_search_filter(selected);
         }
     }
 
     /// Enter search mode
     fn enter_search_mode(&mut self) {
+        let selected = self.selected_oid();
         self.search_mode = SearchMode::Active;
         self.search_query.clear();
-        self.filtered_commits.clear();
+        self.update_search_filter(selected);
     }
 
     /// Exit search mode
     fn exit_search_mode(&mut self) {
+        let selected = self.selected_oid();
         self.search_mode = SearchMode::Inactive;
         self.search_query.clear();
-        self.filtered_commits.clear();
-        self.table_state.select(Some(0));
+        self.search_history.reset();
+        self.update_search_filter(selected);
+    }
+
+    /// Finish entering a query, keeping its results, and remember it
+    fn submit_search(&mut self) -> Option<String> {
+        let selected = self.selected_oid();
+        self.search_mode = SearchMode::Inactive;
+        self.select_oid(selected);
+
+        if self.search_query.is_empty() {
+            return None;
+        }
+        self.search_history.push(&self.search_query);
+        Some(self.search_query.clone())
+    }
+
+    /// Replace the search query with an older history entry
+    fn search_history_previous(&mut self) {
+        let selected = self.selected_oid();
+        if let Some(query) = self.search_history.older(&self.search_query) {
+            self.search_query = query.to_string();
+            self.update_search_filter(selected);
+        }
+    }
+
+    /// Replace the search query with a newer history entry
+    fn search_history_next(&mut self) {
+        let selected = self.selected_oid();
+        if let Some(query) = self.search_history.newer() {
+            self.search_query = query.to_string();
+            self.update_search_filter(selected);
+        }
     }
 
     /// Add a character to the search query
     fn search_add_char(&mut self, c: char) {
+        let selected = self.selected_oid();
+        self.search_history.reset();
         self.search_query.push(c);
-        self.update_search_filter();
+        self.update_search_filter(selected);
+    }
+
+    /// Cycle the search case sensitivity and re-run the search
+    fn toggle_search_case(&mut self) {
+        let selected = self.selected_oid();
+        self.search_case = self.search_case.next();
+        self.update_search_filter(selected);
     }
 
     /// Remove the last character from the search query
     fn search_backspace(&mut self) {
+        let selected = self.selected_oid();
+        self.search_history.reset();
         self.search_query.pop();
-        self.update_search_filter();
+        self.update_search_filter(selected);
     }
 
     /// Move selection up
@@ -166,7 +671,7 @@ impl MainView {
 
     /// Move selection down
     fn select_next(&mut self) {
-        let len = if self.is_searching() {
+        let len = if self.is_filtered() {
             self.filtered_commits.len()
         } else {
             self.commits.len()
@@ -192,7 +697,7 @@ impl MainView {
 
     /// Move selection to the bottom
     fn select_last(&mut self) {
-        let len = if self.is_searching() {
+        let len = if self.is_filtered() {
             self.filtered_commits.len()
         } else {
             self.commits.len()
@@ -212,9 +717,18 @@ impl MainView {
         self.table_state.select(Some(i));
     }
 
+    /// Rows Ctrl-D and Ctrl-U move: half the table as last drawn
+    fn half_page(&self) -> usize {
+        match self.visible_rows {
+            // Not drawn yet
+            0 => 10,
+            rows => (rows / 2).max(1),
+        }
+    }
+
     /// Page down
     fn page_down(&mut self, page_size: usize) {
-        let len = if self.is_searching() {
+        let len = if self.is_filtered() {
             self.filtered_commits.len()
         } else {
             self.commits.len()
@@ -227,13 +741,139 @@ impl MainView {
         self.table_state.select(Some(i));
     }
 
+    /// Get the ids of the commits in view, as of the last draw
+    fn visible_ids(&self) -> Vec<Oid> {
+        let offset = self.table_state.offset();
+        (offset..offset + self.visible_rows)
+            .filter_map(|row| self.commit_at(row))
+            .map(|commit| commit.id)
+            .collect()
+    }
+
+    /// Once the selection has rested, load the diffs of the selected commit
+    /// and those around it into the diff cache, nearest first, so opening
+    /// one is instant
+    fn prefetch_neighbors(&mut self) {
+        let (Some(row), Some(oid)) = (self.table_state.selected(), self.selected_oid()) else {
+            return;
+        };
+        match self.prefetch_anchor {
+            Some((anchor, since)) if anchor == oid => {
+                if self.prefetched == Some(oid) || since.elapsed() < PREFETCH_DELAY {
+                    return;
+                }
+            }
+            _ => {
+                // Diffs around a commit already passed by aren't worth loading
+                if let Some(task) = self.prefetch_task.take() {
+                    task.abort();
+                }
+                self.prefetch_anchor = Some((oid, Instant::now()));
+                return;
+            }
+        }
+        self.prefetched = Some(oid);
+
+        let rows = (1..=PREFETCH_AROUND)
+            .flat_map(|distance| [row.checked_add(distance), row.checked_sub(distance)]);
+        let ids: Vec<Oid> = std::iter::once(Some(row))
+            .chain(rows)
+            .flatten()
+            .filter_map(|row| self.commit_at(row))
+            .map(|commit| commit.id)
+            .collect();
+        let repo = self.repo.clone();
+        let limit = SizeLimit::new(self.max_diff_file_bytes);
+        self.prefetch_task = Some(tokio::spawn(async move {
+            for id in ids {
+                // A diff that fails to load fails again when opened, with
+                // the error shown there
+                let _ = crate::git::diff_cache::load_diff(&repo, DiffKey::Commit(id), &limit).await;
+            }
+        }));
+    }
+
+    /// Describe the commits in view that haven't been on the enrichment
+    /// workers, filling rows in as batches finish
+    fn describe_visible_commits(&mut self) {
+        if !self.describe_column || self.describe_receiver.is_some() {
+            return;
+        }
+        let mut ids = self.visible_ids();
+        ids.retain(|id| !self.describes.contains_key(id));
+        if ids.is_empty() {
+            return;
+        }
+
+        let repo = self.repo.clone();
+        self.describe_receiver = Some(enrich::enrich(ids, move |ids| {
+            let repo = repo.clone();
+            async move {
+                // Without a result the commits read as untagged rather than
+                // being retried on every update
+                crate::git::refs::describe_commits(&repo, ids.clone())
+                    .await
+                    .unwrap_or_else(|_| ids.into_iter().map(|id| (id, None)).collect())
+            }
+        }));
+    }
+
+    /// Count the changes of the commits in view that haven't been, like
+    /// describes
+    fn count_visible_diffstats(&mut self) {
+        if !self.diffstat_column || self.diffstat_receiver.is_some() {
+            return;
+        }
+        let mut ids = self.visible_ids();
+        ids.retain(|id| !self.diffstats.contains_key(id));
+        if ids.is_empty() {
+            return;
+        }
+
+        let repo = self.repo.clone();
+        self.diffstat_receiver = Some(enrich::enrich(ids, move |ids| {
+            let repo = repo.clone();
+            async move {
+                // As with describes, a failed batch shows blank instead of
+                // being retried on every update
+                crate::git::diff::diff_stats(&repo, ids.clone())
+                    .await
+                    .unwrap_or_else(|_| ids.into_iter().map(|id| (id, (0, 0))).collect())
+            }
+        }));
+    }
+
+    /// Name the history shown, e.g. "Compare main..HEAD"
+    fn name(&self) -> String {
+        match (&self.comparison, &self.listed) {
+            (Some(upstream), _) => format!("Compare {}..HEAD", upstream),
+            (None, Some(_)) => "Main (listed)".to_string(),
+            (None, None) => "Main".to_string(),
+        }
+    }
+
     /// Create a table row for a commit
-    fn create_commit_row<'a>(&self, commit: &'a Commit) -> Row<'a> {
-        let hash = Span::styled(&commit.short_id, Style::default().fg(self.colors.commit_hash));
+    fn create_commit_row<'a>(&self, commit: &'a Commit, compact: bool) -> Row<'a> {
+        let mut hash_style = Style::default().fg(self.colors.commit_hash);
+        if self.mark == Some(commit.id) {
+            hash_style = hash_style.add_modifier(Modifier::REVERSED);
+        }
+        if self.picks.contains(&commit.id) {
+            hash_style = hash_style.add_modifier(Modifier::UNDERLINED);
+        }
+        let hash = Span::styled(&commit.short_id, hash_style);
 
-        let date = Span::styled(commit.relative_date(), Style::default().fg(self.colors.date));
+        // Relative dates are computed at draw time so they never go stale
+        let date = Span::styled(
+            commit.relative_date(self.clock.as_ref()),
+            Style::default().fg(self.colors.date),
+        );
 
-        let author = Span::styled(&commit.author, Style::default().fg(self.colors.author));
+        let author_fg = match self.author_colors {
+            true => author_color(&commit.author_email),
+            false => self.colors.author,
+        };
+        let author = self.highlight(&commit.author, Style::default().fg(author_fg));
 
         let refs = if commit.refs.is_empty() {
             Span::raw("")
@@ -246,19 +886,155 @@ impl MainView {
             )
         };
 
-        let message = Span::raw(&commit.summary);
+        let mut message = vec![refs];
+        match Conventional::parse(&commit.summary).filter(|_| self.conventional_commits) {
+            Some(conventional) => {
+                message.push(self.type_badge(&conventional));
+                message.push(Span::raw(" "));
+                message.extend(self.highlight(conventional.description, Style::default()));
+            }
+            None => message.extend(self.highlight(&commit.summary, Style::default())),
+        }
+        if compact {
+            message.push(Span::styled(
+                format!(" {} {}", self.symbols.separator, date.content),
+                date.style,
+            ));
+        }
+
+        let mut cells = Vec::new();
+        if self.comparison.is_some() {
+            let mark = match self.cherries.get(&commit.id) {
+                Some(CherryMark::Pending) => Span::styled(
+                    CherryMark::Pending.symbol(),
+                    Style::default().fg(self.colors.added),
+                ),
+                Some(CherryMark::Applied) => Span::styled(
+                    CherryMark::Applied.symbol(),
+                    Style::default().fg(self.colors.date),
+                ),
+                None => Span::raw(" "),
+            };
+            cells.push(Line::from(mark));
+        }
+        cells.push(Line::from(hash));
+        if compact {
+            cells.push(Line::from(message));
+            return Row::new(cells);
+        }
+        if self.describe_column {
+            let describe = self
+                .describes
+                .get(&commit.id)
+                .cloned()
+                .flatten()
+                .unwrap_or_default();
+            cells.push(Line::from(Span::styled(
+                describe,
+                Style::default().fg(Color::Cyan),
+            )));
+        }
+        if self.diffstat_column {
+            cells.push(self.diffstat_cell(commit.id));
+        }
+        cells.extend([Line::from(date), Line::from(author), Line::from(message)]);
+        Row::new(cells)
+    }
+
+    /// Create the badge of a conventional commit's type, like ` fix(ui) `,
+    /// in bold when it is marked as breaking
+    fn type_badge(&self, conventional: &Conventional) -> Span<'static> {
+        let kind = conventional.kind.to_ascii_lowercase();
+        let color = match kind.as_str() {
+            "feat" => self.colors.added,
+            "fix" => self.colors.deleted,
+            // Other types get a stable color of their own, like authors
+            _ => author_color(&kind),
+        };
+        let mut style = Style::default().fg(color).add_modifier(Modifier::REVERSED);
+        let mut label = match conventional.scope {
+            Some(scope) => format!(" {}({}) ", kind, scope),
+            None => format!(" {} ", kind),
+        };
+        if conventional.breaking {
+            style = style.add_modifier(Modifier::BOLD);
+            label.insert(label.len() - 1, '!');
+        }
+        Span::styled(label, style)
+    }
+
+    /// Create the `+N/-M` cell of a commit, blank until it is counted
+    fn diffstat_cell(&self, oid: Oid) -> Line<'static> {
+        match self.diffstats.get(&oid) {
+            Some(&(0, 0)) | None => Line::from(""),
+            Some(&(added, removed)) => Line::from(vec![
+                Span::styled(
+                    format!("+{}", added),
+                    Style::default().fg(self.colors.added),
+                ),
+                Span::raw("/"),
+                Span::styled(
+                    format!("-{}", removed),
+                    Style::default().fg(self.colors.deleted),
+                ),
+            ])
+            .alignment(Alignment::Right),
+        }
+    }
 
-        Row::new(vec![
-            Line::from(hash),
-            Line::from(date),
-            Line::from(author),
-            Line::from(vec![refs, message]),
-        ])
+    /// Split text into spans with search matches highlighted
+    fn highlight<'a>(&self, text: &'a str, base: Style) -> Vec<Span<'a>> {
+        let ranges = if self.is_searching() {
+            let case_sensitive = self.search_case.is_case_sensitive(&self.search_query);
+            search::match_ranges(text, &self.search_query, case_sensitive)
+        } else {
+            Vec::new()
+        };
+        search::highlight_spans(text, &ranges, base, self.colors.search_match)
     }
 }
 
 impl View for MainView {
-    fn handle_key(&mut self, key: KeyEvent) -> Result<Action> {
+    fn handle_key(&mut self, key: KeyEvent) -> AppResult<Action> {
+        // The filter panel captures all keys while open
+        if let Some(panel) = &mut self.filter_panel {
+            match panel.handle_key(key) {
+                FilterPanelEvent::Apply(filter) => {
+                    self.filter_panel = None;
+                    self.apply_filter(filter);
+                }
+                FilterPanelEvent::Cancel => self.filter_panel = None,
+                FilterPanelEvent::None => {}
+            }
+            return Ok(Action::None);
+        }
+        if let Some(menu) = &mut self.copy_menu {
+            match menu.handle_key(key) {
+                CopyMenuEvent::Copy(text) => {
+                    self.copy_menu = None;
+                    return Ok(Action::Copy(text));
+                }
+                CopyMenuEvent::Cancel => self.copy_menu = None,
+                CopyMenuEvent::None => {}
+            }
+            return Ok(Action::None);
+        }
+
+        // Any key dismisses a popup; keys other than these still apply
+        let had_quick_look = self.quick_look.take().is_some();
+        let had_refs_popup = self.refs_popup.take().is_some();
+        let closes_popup = matches!(
+            key.code,
+            KeyCode::Char(' ')
+                | KeyCode::Char('i')
+                | KeyCode::Char('I')
+                | KeyCode::Char('q')
+                | KeyCode::Esc
+        );
+        if (had_quick_look || had_refs_popup) && closes_popup {
+            return Ok(Action::None);
+        }
+
         // Handle search mode separately
         if self.search_mode == SearchMode::Active {
             match key.code {
@@ -268,13 +1044,27 @@ impl View for MainView {
                 }
                 KeyCode::Enter => {
                     // Keep the search results but exit search input mode
-                    self.search_mode = SearchMode::Inactive;
+                    return Ok(match self.submit_search() {
+                        Some(query) => Action::RecordSearch(query),
+                        None => Action::None,
+                    });
+                }
+                KeyCode::Up => {
+                    self.search_history_previous();
+                    return Ok(Action::None);
+                }
+                KeyCode::Down => {
+                    self.search_history_next();
                     return Ok(Action::None);
                 }
                 KeyCode::Backspace => {
                     self.search_backspace();
                     return Ok(Action::None);
                 }
+                KeyCode::Char('t') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    self.toggle_search_case();
+                    return Ok(Action::None);
+                }
                 KeyCode::Char(c) => {
                     self.search_add_char(c);
                     return Ok(Action::None);
@@ -285,6 +1075,9 @@ impl View for MainView {
 
         // Normal mode keybindings
         match key.code {
+            // A comparison is pushed over another view; only the main view
+            // itself quits
+            KeyCode::Char('q') if self.comparison.is_some() => Ok(Action::PopView),
             KeyCode::Char('q') => Ok(Action::Quit),
             KeyCode::Char('/') => {
                 self.enter_search_mode();
@@ -294,6 +1087,46 @@ impl View for MainView {
                 self.exit_search_mode();
                 Ok(Action::None)
             }
+            KeyCode::Char('f') => {
+                self.filter_panel = Some(FilterPanel::new(&self.filter));
+                Ok(Action::None)
+            }
+            KeyCode::Char('F') => {
+                self.apply_filter(CommitFilter::default());
+                Ok(Action::None)
+            }
+            KeyCode::Char(' ') => {
+                // Preview the selected commit without leaving the view
+                if let Some(commit) = self.selected_commit() {
+                    self.quick_look = Some(QuickLook::open(
+                        self.repo.clone(),
+                        commit.clone(),
+                        SizeLimit::new(self.max_diff_file_bytes),
+                    ));
+                }
+                Ok(Action::None)
+            }
+            KeyCode::Char('i') => {
+                // List all refs at the selected commit, untruncated
+                if let Some(commit) = self.selected_commit() {
+                    let popup =
+                        RefsPopup::open(self.repo.clone(), commit.id, commit.short_id.clone());
+                    self.refs_popup = Some(popup);
+                }
+                Ok(Action::None)
+            }
+            KeyCode::Char('I') => {
+                // List the branches and tags the selected commit has landed in
+                if let Some(commit) = self.selected_commit() {
+                    let popup = RefsPopup::open_containing(
+                        self.repo.clone(),
+                        commit.id,
+                        commit.short_id.clone(),
+                    );
+                    self.refs_popup = Some(popup);
+                }
+                Ok(Action::None)
+            }
             KeyCode::Char('j') | KeyCode::Down => {
                 self.select_next();
                 Ok(Action::None)
@@ -310,10 +1143,46 @@ impl View for MainView {
                 self.select_last();
                 Ok(Action::None)
             }
+            KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.page_down(self.half_page());
+                Ok(Action::None)
+            }
+            KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.page_up(self.half_page());
+                Ok(Action::None)
+            }
             KeyCode::PageUp => {
                 self.page_up(20);
                 Ok(Action::None)
             }
+            KeyCode::Char('m') => {
+                self.toggle_mark();
+                Ok(Action::None)
+            }
+            KeyCode::Char('d') => self.diff_from_mark(),
+            KeyCode::Char('x') => {
+                self.toggle_pick();
+                Ok(Action::None)
+            }
+            KeyCode::Char('P') => Ok(self.cherry_pick()),
+            KeyCode::Char('X') => match self.selected_commit() {
+                Some(commit) => Ok(Action::Reset {
+                    id: commit.id,
+                    summary: commit.summary.clone(),
+                }),
+                None => Ok(Action::None),
+            },
+            KeyCode::Char('V') => match self.selected_commit() {
+                Some(commit) => Ok(Action::Revert {
+                    id: commit.id,
+                    summary: commit.summary.clone(),
+                }),
+                None => Ok(Action::None),
+            },
+            KeyCode::Char('S') => {
+                self.start_split();
+                Ok(Action::None)
+            }
             KeyCode::PageDown => {
                 self.page_down(20);
                 Ok(Action::None)
@@ -334,103 +1203,370 @@ impl View for MainView {
                 // Open status view
                 Ok(Action::PushView(super::view::ViewType::Status))
             }
+            KeyCode::Char('r') => {
+                // Open refs view
+                Ok(Action::PushView(ViewType::Refs))
+            }
+            KeyCode::Char('b') => Ok(Action::PushView(ViewType::Stack)),
+            KeyCode::Char('C') => match self.selected_commit() {
+                Some(commit) => Ok(Action::Checkout(CheckoutTarget::Commit(commit.id))),
+                None => Ok(Action::None),
+            },
+            KeyCode::Char('y') => Ok(Action::PushView(ViewType::Stash)),
+            KeyCode::Char('L') => {
+                self.start_changelog()?;
+                Ok(Action::None)
+            }
+            KeyCode::Char('Y') => {
+                // Copy the selected commit in one of the copy formats
+                if let Some(commit) = self.selected_commit() {
+                    let menu = CopyMenu::open(
+                        self.repo.clone(),
+                        commit.clone(),
+                        self.copy_formats.clone(),
+                    );
+                    self.copy_menu = Some(menu);
+                }
+                Ok(Action::None)
+            }
             KeyCode::Char('?') => {
                 // Show help
-                Ok(Action::PushView(super::view::ViewType::Help))
+                Ok(Action::ShowHelp(super::view::ViewType::Main))
             }
             _ => Ok(Action::None),
         }
     }
 
-    fn update(&mut self) -> Result<()> {
-        // Check for new commits from the receiver
-        if let Some(receiver) = &mut self.receiver {
-            while let Ok(chunk) = receiver.try_recv() {
-                self.commits.extend(chunk);
+    fn handle_key_with_count(&mut self, key: KeyEvent, count: usize) -> AppResult<Action> {
+        if self.captures_input() {
+            return self.handle_key(key);
+        }
+        self.quick_look = None;
+        self.refs_popup = None;
+        match key.code {
+            KeyCode::Char('j') | KeyCode::Down => self.page_down(count),
+            KeyCode::Char('k') | KeyCode::Up => self.page_up(count),
+            KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.page_down(self.half_page() * count)
+            }
+            KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.page_up(self.half_page() * count)
             }
+            KeyCode::PageDown => self.page_down(20 * count),
+            KeyCode::PageUp => self.page_up(20 * count),
+            _ => return self.handle_key(key),
         }
-
-        Ok(())
+        Ok(Action::None)
     }
 
-    fn draw(&mut self, frame: &mut Frame, area: Rect) {
-        // Create the table rows from appropriate commits
-        let rows: Vec<Row> = if self.is_searching() {
-            self.filtered_commits
-                .iter()
-                .filter_map(|&i| self.commits.get(i))
-                .map(|commit| {
-                    self.create_commit_row(commit)
-                })
-                .collect()
+    fn update(&mut self) -> AppResult<()> {
+        // Check for new commits from the receiver; a closed channel means
+        // the walk is done
+        let mut chunks = Vec::new();
+        if let Some(receiver) = &mut self.receiver {
+            loop {
+                match receiver.try_recv() {
+                    Ok(chunk) => chunks.push(chunk),
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => {
+                        self.receiver = None;
+                        self.loading = false;
+                        break;
+                    }
+                }
+            }
+        }
+        for chunk in chunks {
+            self.append_commits(chunk);
+        }
+
+        // Find the commit that was selected before a refresh once it is
+        // shown; one the search or filter hides, or that never arrives,
+        // leaves the selection where it is
+        if let Some(oid) = self.reselect {
+            if self.row_of(oid).is_some() {
+                self.reselect = None;
+                self.select_oid(Some(oid));
+            } else if !self.loading || self.commits.iter().any(|c| c.id == oid) {
+                self.reselect = None;
+            }
+        }
+
+        let mut failure = None;
+        if let Some(receiver) = &mut self.split_receiver
+            && let Ok(result) = receiver.try_recv()
+        {
+            self.split_receiver = None;
+            match result {
+                Ok(_) => self.split_started = true,
+                Err(e) => failure = Some(e),
+            }
+        }
+
+        if let Some(receiver) = &mut self.cherry_receiver
+            && let Ok(marks) = receiver.try_recv()
+        {
+            self.cherry_receiver = None;
+            self.cherries = marks;
+        }
+
+        self.describes
+            .extend(enrich::drain(&mut self.describe_receiver));
+        self.describe_visible_commits();
+
+        self.diffstats
+            .extend(enrich::drain(&mut self.diffstat_receiver));
+        self.count_visible_diffstats();
+
+        self.prefetch_neighbors();
+
+        if let Some(quick_look) = &mut self.quick_look {
+            quick_look.update();
+        }
+        if let Some(refs_popup) = &mut self.refs_popup {
+            refs_popup.update();
+        }
+        let mut empty_changelog = false;
+        if let Some(receiver) = &mut self.changelog_receiver
+            && let Ok(text) = receiver.try_recv()
+        {
+            self.changelog_receiver = None;
+            empty_changelog = text.is_empty();
+            self.copied = Some(text).filter(|text| !text.is_empty());
+        }
+        // With one format there's nothing to pick
+        if let Some(menu) = &mut self.copy_menu
+            && let Some(text) = menu.update()
+        {
+            self.copy_menu = None;
+            self.copied = Some(text);
+        }
+
+        if empty_changelog {
+            return Err(AppError::recoverable(
+                "None of the commits belong in a changelog section",
+            ));
+        }
+        // The history is unchanged when a split can't start, so it's a toast
+        match failure {
+            Some(e) => Err(e.into()),
+            None => Ok(()),
+        }
+    }
+
+    fn poll_action(&mut self) -> Action {
+        if let Some(text) = self.copied.take() {
+            return Action::Copy(text);
+        }
+        match std::mem::take(&mut self.split_started) {
+            true => Action::PushView(ViewType::Status),
+            false => Action::None,
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame, area: Rect) {
+        let compact = area.width < self.compact_width;
+
+        // Create the table rows from appropriate commits
+        let rows: Vec<Row> = if self.is_filtered() {
+            self.filtered_commits
+                .iter()
+                .filter_map(|&i| self.commits.get(i))
+                .map(|commit| self.create_commit_row(commit, compact))
+                .collect()
         } else {
             self.commits
                 .iter()
-                .map(|commit| {
-                    self.create_commit_row(commit)
-                })
+                .map(|commit| self.create_commit_row(commit, compact))
                 .collect()
         };
 
         let displayed_count = rows.len();
 
-        let widths = [
-            Constraint::Length(8),      // Hash
-            Constraint::Length(18),     // Date
-            Constraint::Length(20),     // Author
-            Constraint::Percentage(50), // Message
-        ];
+        let mut widths = Vec::new();
+        if self.comparison.is_some() {
+            widths.push(Constraint::Length(1)); // Cherry mark
+        }
+        widths.push(Constraint::Length(8)); // Hash
+        if compact {
+            widths.push(Constraint::Fill(1)); // Message and date
+        } else {
+            if self.describe_column {
+                widths.push(Constraint::Length(16)); // Nearest tag
+            }
+            if self.diffstat_column {
+                widths.push(Constraint::Length(13)); // +N/-M
+            }
+            widths.extend([
+                Constraint::Length(self.column_widths.date.clamp(self.date_width)),
+                Constraint::Length(self.column_widths.author.clamp(self.author_width)),
+                Constraint::Percentage(50), // Message
+            ]);
+        }
+        self.visible_rows = area.height.saturating_sub(2) as usize;
 
-        // Title shows search status
-        let title = if self.is_searching() {
+        // Title shows search status and active filter chips
+        let filters: String = self
+            .filter
+            .chips()
+            .iter()
+            .map(|chip| format!(" [{}]", chip))
+            .collect();
+        let mut chips = String::new();
+        let applied = self
+            .cherries
+            .values()
+            .filter(|&&m| m == CherryMark::Applied)
+            .count();
+        if applied > 0 {
+            chips.push_str(&format!(" [{} applied upstream]", applied));
+        }
+        chips.push_str(&filters);
+        if let Some(mark) = self.mark {
+            chips.push_str(&format!(" [mark: {:.7}]", mark.to_string()));
+        }
+        if !self.picks.is_empty() {
+            chips.push_str(&format!(" [{} to pick]", self.picks.len()));
+        }
+        let title = if self.search_mode == SearchMode::Active && !self.is_searching() {
             format!(
-                "Main - {} / {} commits (filtered)",
-                displayed_count,
-                self.commits.len()
+                "Search [{}]: {}_{}",
+                self.search_case.label(),
+                self.search_query,
+                chips
             )
-        } else if self.search_mode == SearchMode::Active {
-            format!("Search: {}_", self.search_query)
         } else {
-            format!("Main - {} commits", self.commits.len())
+            let total = self.commits.len().to_string();
+            let (count, filtered) = match self.is_filtered() {
+                true => (format!("{} / {}", displayed_count, total), " (filtered)"),
+                false => (total.clone(), ""),
+            };
+            let range = match (&self.comparison, &self.listed) {
+                (Some(upstream), _) => format!("{}..HEAD", upstream),
+                (None, Some(_)) => "listed commits".to_string(),
+                (None, None) => "HEAD".to_string(),
+            };
+            render_title(
+                &self.title_template,
+                &[
+                    ("view", self.name()),
+                    ("range", range),
+                    ("count", count),
+                    ("shown", displayed_count.to_string()),
+                    ("total", total),
+                    ("filtered", filtered.to_string()),
+                    ("filters", filters),
+                    ("chips", chips),
+                ],
+            )
         };
 
+        // Footer shows where the selection is in the history
+        let selected = self.table_state.selected().unwrap_or(0);
+        let mut block = self.symbols.block().title(title);
+        if let Some(position) = position_label(selected, displayed_count) {
+            block = block.title_bottom(Line::from(position).right_aligned());
+        }
+
         let table = Table::new(rows, widths)
-            .block(Block::default().title(title).borders(Borders::ALL))
-            .row_highlight_style(
-                self.colors.selected
-                    .add_modifier(Modifier::BOLD),
-            )
+            .block(block)
+            .row_highlight_style(self.colors.selected.add_modifier(Modifier::BOLD))
             .highlight_symbol("> ");
 
         frame.render_stateful_widget(table, area, &mut self.table_state);
+        render_scrollbar(frame, area, &self.symbols, displayed_count, selected);
 
-        // Show loading indicator
+        // Show loading indicator, clamped to the area
         if self.loading && self.commits.is_empty() {
             let loading_text = "Loading commits...";
-            let x = area.x + (area.width.saturating_sub(loading_text.len() as u16)) / 2;
-            let y = area.y + area.height / 2;
-            if x < area.x + area.width && y < area.y + area.height {
-                frame.render_widget(
-                    ratatui::widgets::Paragraph::new(loading_text)
-                        .style(Style::default().fg(self.colors.modified)),
-                    Rect::new(x, y, loading_text.len() as u16, 1),
-                );
-            }
+            frame.render_widget(
+                ratatui::widgets::Paragraph::new(loading_text)
+                    .style(Style::default().fg(self.colors.modified)),
+                centered_rect(loading_text.len() as u16, 1, area),
+            );
         }
 
-        // Show error if any
+        // Show error if any, on the first row inside the border
         if let Some(error) = &self.error {
             let error_text = format!("Error: {}", error);
+            let inner = area.inner(Margin::new(1, 1));
             frame.render_widget(
                 ratatui::widgets::Paragraph::new(error_text)
                     .style(Style::default().fg(self.colors.deleted)),
-                Rect::new(area.x + 1, area.y + 1, area.width - 2, 1),
+                Rect {
+                    height: inner.height.min(1),
+                    ..inner
+                },
             );
         }
+
+        if let Some(quick_look) = &self.quick_look {
+            quick_look.draw(frame, area, &self.colors, &self.symbols);
+        }
+        if let Some(refs_popup) = &self.refs_popup {
+            refs_popup.draw(frame, area, &self.colors, &self.symbols);
+        }
+        if let Some(menu) = &self.copy_menu {
+            menu.draw(frame, area, &self.colors, &self.symbols);
+        }
+
+        if let Some(panel) = &self.filter_panel {
+            panel.draw(frame, area, &self.colors, &self.symbols);
+        }
     }
 
     fn title(&self) -> &str {
-        "Main"
+        match self.comparison {
+            Some(_) => "Compare",
+            None => "Main",
+        }
+    }
+
+    fn view_type(&self) -> ViewType {
+        ViewType::Main
+    }
+
+    fn selection(&self) -> Selection {
+        Selection {
+            commit: self.selected_oid(),
+            ..Selection::default()
+        }
+    }
+
+    fn busy(&self) -> Option<&'static str> {
+        self.split_receiver.as_ref().map(|_| "splitting")
+    }
+
+    fn captures_input(&self) -> bool {
+        self.search_mode == SearchMode::Active
+            || self.filter_panel.is_some()
+            || self.copy_menu.is_some()
+    }
+
+    fn select_commit(&mut self, oid: Oid) -> bool {
+        if self.commits.iter().any(|c| c.id == oid) {
+            self.select_oid(Some(oid));
+        } else {
+            // Still loading; update() selects it once it arrives
+            self.reselect = Some(oid);
+        }
+        true
+    }
+
+    fn refresh(&mut self) {
+        self.reselect = self.selected_oid();
+        self.clear_commits();
+        self.table_state.select(Some(0));
+        self.quick_look = None;
+        self.refs_popup = None;
+        self.copy_menu = None;
+        self.error = None;
+        // Tags and the compared branch may have moved too
+        self.describes.clear();
+        self.cherries.clear();
+        self.describe_receiver = None;
+        self.diffstat_receiver = None;
+        self.start_loading();
     }
 
     fn on_activate(&mut self) -> Result<()> {
@@ -445,22 +1581,11 @@ impl View for MainView {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::testing::RepoBuilder;
     use tempfile::TempDir;
 
     async fn create_test_repo() -> (TempDir, Repository) {
-        let temp_dir = TempDir::new().unwrap();
-        let repo_path = temp_dir.path();
-
-        let git_repo = git2::Repository::init(repo_path).unwrap();
-        let sig = git2::Signature::now("Test", "test@example.com").unwrap();
-        let tree_id = git_repo.index().unwrap().write_tree().unwrap();
-        let tree = git_repo.find_tree(tree_id).unwrap();
-        git_repo
-            .commit(Some("HEAD"), &sig, &sig, "Initial", &tree, &[])
-            .unwrap();
-
-        let repo = Repository::open(repo_path).await.unwrap();
-        (temp_dir, repo)
+        RepoBuilder::new().commit("Initial").open().await
     }
 
     fn test_color_scheme() -> ColorScheme {
@@ -468,6 +1593,614 @@ mod tests {
         ColorScheme::from_config(&Config::default().colors)
     }
 
+    #[tokio::test]
+    async fn test_draw_in_tiny_areas() {
+        use ratatui::{Terminal, backend::TestBackend};
+
+        let (_temp_dir, repo) = create_test_repo().await;
+        let mut view = MainView::new(repo, test_color_scheme());
+        view.loading = true;
+        view.error = Some("broken".to_string());
+
+        for (width, height) in [(1, 1), (2, 2), (5, 3), (30, 1)] {
+            let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+            terminal
+                .draw(|frame| view.draw(frame, frame.area()))
+                .unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_search_while_loading_stays_consistent() {
+        let (_temp_dir, repo) = RepoBuilder::new()
+            .commit("Fix a")
+            .commit("Add b")
+            .commit("Fix c")
+            .commit("Add d")
+            .open()
+            .await;
+        let mut loaded = MainView::new(repo.clone(), test_color_scheme());
+        loaded.start_loading();
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        loaded.update().unwrap();
+        let commits = loaded.commits.clone();
+
+        // Stream the walk by hand, searching between the chunks
+        let mut view = MainView::new(repo, test_color_scheme());
+        let (tx, rx) = mpsc::channel(4);
+        view.receiver = Some(rx);
+        view.loading = true;
+        tx.send(commits[..2].to_vec()).await.unwrap();
+        view.update().unwrap();
+        view.handle_key(KeyEvent::from(KeyCode::Char('/'))).unwrap();
+        for c in "Fix".chars() {
+            view.handle_key(KeyEvent::from(KeyCode::Char(c))).unwrap();
+        }
+        view.handle_key(KeyEvent::from(KeyCode::Enter)).unwrap();
+        assert_eq!(view.filtered_commits, vec![1]);
+        assert_eq!(view.selected_commit().unwrap().summary, "Fix c");
+
+        tx.send(commits[2..].to_vec()).await.unwrap();
+        view.update().unwrap();
+        assert_eq!(view.filtered_commits, vec![1, 3]);
+        assert_eq!(view.selected_commit().unwrap().summary, "Fix c");
+        let selected = view.selected_oid();
+        view.update_search_filter(selected);
+        assert_eq!(view.filtered_commits, vec![1, 3]);
+
+        // The walk ending is noticed
+        assert!(view.loading);
+        drop(tx);
+        view.update().unwrap();
+        assert!(!view.loading);
+    }
+
+    #[tokio::test]
+    async fn test_refresh_keeps_selected_commit() {
+        let (_temp_dir, repo) = RepoBuilder::new()
+            .commit("First")
+            .commit("Second")
+            .commit("Third")
+            .open()
+            .await;
+        let mut view = MainView::new(repo, test_color_scheme());
+        view.start_loading();
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        view.update().unwrap();
+        assert_eq!(view.commits.len(), 3);
+
+        view.table_state.select(Some(1));
+        let selected = view.selected_oid();
+        view.refresh();
+        assert!(view.commits.is_empty());
+
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        view.update().unwrap();
+        assert_eq!(view.commits.len(), 3);
+        assert_eq!(view.selected_oid(), selected);
+    }
+
+    #[tokio::test]
+    async fn test_prefetches_diffs_once_selection_rests() {
+        let (_temp_dir, repo) = RepoBuilder::new()
+            .commit("First")
+            .commit("Second")
+            .commit("Third")
+            .open()
+            .await;
+        let mut view = MainView::new(repo, test_color_scheme());
+        view.start_loading();
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        view.update().unwrap();
+        view.table_state.select(Some(1));
+        view.update().unwrap();
+        assert!(view.prefetch_task.is_none());
+
+        let selected = view.selected_oid().unwrap();
+        view.prefetch_anchor = Some((selected, Instant::now() - PREFETCH_DELAY));
+        view.update().unwrap();
+        assert_eq!(view.prefetched, Some(selected));
+        view.prefetch_task.take().unwrap().await.unwrap();
+
+        // Resting on the same commit doesn't load its neighbours again
+        view.update().unwrap();
+        assert!(view.prefetch_task.is_none());
+
+        view.table_state.select(Some(2));
+        view.update().unwrap();
+        assert_eq!(
+            view.prefetch_anchor.map(|(oid, _)| oid),
+            view.selected_oid()
+        );
+        assert!(view.prefetch_task.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_describe_column_shows_nearest_tag() {
+        use ratatui::{Terminal, backend::TestBackend};
+
+        let (_temp_dir, repo) = RepoBuilder::new()
+            .commit("First")
+            .tag("v0.1")
+            .commit("Second")
+            .open()
+            .await;
+        let mut view = MainView::new(repo, test_color_scheme()).with_describe_column(true);
+        view.start_loading();
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        view.update().unwrap();
+
+        // Rows are described once a draw has said how many are in view
+        let mut terminal = Terminal::new(TestBackend::new(100, 6)).unwrap();
+        terminal
+            .draw(|frame| view.draw(frame, frame.area()))
+            .unwrap();
+        view.update().unwrap();
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        view.update().unwrap();
+        assert_eq!(view.describes.len(), 2);
+
+        terminal
+            .draw(|frame| view.draw(frame, frame.area()))
+            .unwrap();
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        assert!(screen.contains("v0.1-1-g"));
+        assert!(screen.contains("v0.1 "));
+    }
+
+    #[tokio::test]
+    async fn test_conventional_commit_badges() {
+        use ratatui::{Terminal, backend::TestBackend};
+
+        let (_temp_dir, repo) = RepoBuilder::new()
+            .commit("fix(ui)!: Stop flicker")
+            .open()
+            .await;
+        let mut view = MainView::new(repo, test_color_scheme()).with_conventional_commits(true);
+        view.start_loading();
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        view.update().unwrap();
+
+        let mut terminal = Terminal::new(TestBackend::new(100, 3)).unwrap();
+        terminal
+            .draw(|frame| view.draw(frame, frame.area()))
+            .unwrap();
+        let buffer = terminal.backend().buffer();
+        let screen: String = buffer.content.iter().map(|cell| cell.symbol()).collect();
+        let badge = screen.find(" fix(ui)!  Stop flicker").unwrap();
+        let cell = &buffer.content[screen[..badge + 1].chars().count()];
+        assert!(cell.modifier.contains(Modifier::REVERSED | Modifier::BOLD));
+
+        let feat = Conventional::parse("Feat: Tabs").unwrap();
+        assert_eq!(view.type_badge(&feat).content, " feat ");
+        assert_eq!(view.type_badge(&feat).style.fg, Some(view.colors.added));
+    }
+
+    #[tokio::test]
+    async fn test_diffstat_column_counts_visible_commits() {
+        use ratatui::{Terminal, backend::TestBackend};
+
+        let (_temp_dir, repo) = RepoBuilder::new()
+            .file("a.txt", "one\ntwo\n")
+            .commit("First")
+            .file("a.txt", "one\n2\nthree\n")
+            .commit("Second")
+            .open()
+            .await;
+        let mut view = MainView::new(repo, test_color_scheme()).with_diffstat_column(true);
+        view.start_loading();
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        view.update().unwrap();
+
+        // Only a one-row table: the other commit is left uncounted
+        let mut terminal = Terminal::new(TestBackend::new(100, 3)).unwrap();
+        terminal
+            .draw(|frame| view.draw(frame, frame.area()))
+            .unwrap();
+        view.update().unwrap();
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        view.update().unwrap();
+        assert_eq!(view.diffstats.len(), 1);
+
+        terminal
+            .draw(|frame| view.draw(frame, frame.area()))
+            .unwrap();
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        assert!(screen.contains("+2/-1"));
+    }
+
+    #[tokio::test]
+    async fn test_columns_fit_loaded_authors_and_dates() {
+        use ratatui::{Terminal, backend::TestBackend};
+
+        let (_temp_dir, repo) = RepoBuilder::new().commit("First").open().await;
+        let mut view = MainView::new(repo, test_color_scheme());
+        view.start_loading();
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        view.update().unwrap();
+        assert_eq!(
+            (view.author_width, view.date_width),
+            ("Test User".len(), "just now".len())
+        );
+
+        // The message starts right after the author, not at a fixed offset
+        let mut terminal = Terminal::new(TestBackend::new(100, 3)).unwrap();
+        terminal
+            .draw(|frame| view.draw(frame, frame.area()))
+            .unwrap();
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        assert!(screen.contains("just now Test User  ["));
+
+        view.refresh();
+        assert_eq!(view.author_width, 0);
+    }
+
+    #[tokio::test]
+    async fn test_compact_rows_below_the_width() {
+        use ratatui::{Terminal, backend::TestBackend};
+
+        let (_temp_dir, repo) = RepoBuilder::new().commit("First").open().await;
+        let mut view = MainView::new(repo, test_color_scheme()).with_compact_width(80);
+        view.start_loading();
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        view.update().unwrap();
+
+        let mut terminal = Terminal::new(TestBackend::new(60, 3)).unwrap();
+        terminal
+            .draw(|frame| view.draw(frame, frame.area()))
+            .unwrap();
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        assert!(screen.contains("[master]First · just now"), "{:?}", screen);
+        assert!(!screen.contains("Test User"));
+
+        let mut terminal = Terminal::new(TestBackend::new(80, 3)).unwrap();
+        terminal
+            .draw(|frame| view.draw(frame, frame.area()))
+            .unwrap();
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        assert!(screen.contains("Test User"));
+    }
+
+    #[test]
+    fn test_position_label() {
+        assert_eq!(position_label(0, 0), None);
+        assert_eq!(
+            position_label(36, 12034).as_deref(),
+            Some(" commit 37/12,034 (0%) ")
+        );
+        assert_eq!(position_label(1, 4).as_deref(), Some(" commit 2/4 (50%) "));
+        assert_eq!(position_label(9, 4).as_deref(), Some(" commit 4/4 (100%) "));
+    }
+
+    #[tokio::test]
+    async fn test_footer_shows_position() {
+        use ratatui::{Terminal, backend::TestBackend};
+
+        let (_temp_dir, repo) = RepoBuilder::new()
+            .commit("First")
+            .commit("Second")
+            .open()
+            .await;
+        let mut view = MainView::new(repo, test_color_scheme());
+        view.start_loading();
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        view.update().unwrap();
+        view.handle_key(KeyEvent::from(KeyCode::Char('j'))).unwrap();
+
+        let mut terminal = Terminal::new(TestBackend::new(100, 5)).unwrap();
+        terminal
+            .draw(|frame| view.draw(frame, frame.area()))
+            .unwrap();
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        assert!(screen.contains("commit 2/2 (100%)"), "{:?}", screen);
+    }
+
+    #[tokio::test]
+    async fn test_cherry_pick_toggled_commits_oldest_first() {
+        let (_temp_dir, repo) = RepoBuilder::new()
+            .commit("First")
+            .commit("Second")
+            .commit("Third")
+            .open()
+            .await;
+        let mut view = MainView::new(repo, test_color_scheme());
+        view.start_loading();
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        view.update().unwrap();
+        let (third, first) = (view.commits[0].id, view.commits[2].id);
+
+        let x = KeyEvent::from(KeyCode::Char('x'));
+        let j = KeyEvent::from(KeyCode::Char('j'));
+        view.handle_key(x).unwrap();
+        view.handle_key(j).unwrap();
+        view.handle_key(x).unwrap();
+        view.handle_key(x).unwrap();
+        view.handle_key(j).unwrap();
+        view.handle_key(x).unwrap();
+        assert_eq!(view.picks, [third, first]);
+
+        let p = KeyEvent::from(KeyCode::Char('P'));
+        assert_eq!(
+            view.handle_key(p).unwrap(),
+            Action::CherryPick(vec![first, third])
+        );
+        assert!(view.picks.is_empty());
+        // Without any toggled, the selected commit is picked
+        assert_eq!(view.handle_key(p).unwrap(), Action::CherryPick(vec![first]));
+    }
+
+    #[tokio::test]
+    async fn test_revert_or_reset_selected_commit() {
+        let (_temp_dir, repo) = RepoBuilder::new()
+            .commit("First")
+            .commit("Second")
+            .open()
+            .await;
+        let mut view = MainView::new(repo, test_color_scheme());
+        view.start_loading();
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        view.update().unwrap();
+        let second = view.commits[0].id;
+
+        let action = view.handle_key(KeyEvent::from(KeyCode::Char('V'))).unwrap();
+        assert_eq!(
+            action,
+            Action::Revert {
+                id: second,
+                summary: "Second".to_string()
+            }
+        );
+        let action = view.handle_key(KeyEvent::from(KeyCode::Char('X'))).unwrap();
+        assert_eq!(
+            action,
+            Action::Reset {
+                id: second,
+                summary: "Second".to_string()
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_mark_then_diff_against_it() {
+        let (_temp_dir, repo) = RepoBuilder::new()
+            .commit("First")
+            .commit("Second")
+            .open()
+            .await;
+        let mut view = MainView::new(repo, test_color_scheme());
+        view.start_loading();
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        view.update().unwrap();
+        let (second, first) = (view.commits[0].id, view.commits[1].id);
+
+        let d = KeyEvent::from(KeyCode::Char('d'));
+        assert!(matches!(view.handle_key(d), Err(AppError::Recoverable(_))));
+
+        view.handle_key(KeyEvent::from(KeyCode::Char('m'))).unwrap();
+        assert_eq!(view.mark, Some(second));
+        assert!(matches!(view.handle_key(d), Err(AppError::Recoverable(_))));
+
+        view.handle_key(KeyEvent::from(KeyCode::Char('j'))).unwrap();
+        let action = view.handle_key(d).unwrap();
+        assert!(
+            matches!(action, Action::OpenRangeDiff { from, to, .. } if from == second && to == first)
+        );
+
+        // Marking the marked commit again clears the mark
+        view.handle_key(KeyEvent::from(KeyCode::Char('k'))).unwrap();
+        view.handle_key(KeyEvent::from(KeyCode::Char('m'))).unwrap();
+        assert_eq!(view.mark, None);
+    }
+
+    #[tokio::test]
+    async fn test_changelog_of_marked_range() {
+        let (_temp_dir, repo) = RepoBuilder::new()
+            .commit("feat: Tabs")
+            .commit("fix(ui): Flicker")
+            .commit("Tidy up")
+            .open()
+            .await;
+        let mut view = MainView::new(repo, test_color_scheme()).with_changelog(ChangelogTemplate {
+            sections: vec![crate::git::ChangelogSection {
+                title: "Fixes".to_string(),
+                types: vec!["fix".to_string()],
+            }],
+            other: None,
+            heading: "## %(title)".to_string(),
+            entry: "- %(description)".to_string(),
+        });
+        view.start_loading();
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        view.update().unwrap();
+
+        // Without a mark only the selected commit is written
+        view.handle_key(KeyEvent::from(KeyCode::Char('L'))).unwrap();
+        tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
+        assert!(matches!(view.update(), Err(AppError::Recoverable(_))));
+        assert_eq!(view.poll_action(), Action::None);
+
+        view.handle_key(KeyEvent::from(KeyCode::Char('m'))).unwrap();
+        view.handle_key(KeyEvent::from(KeyCode::Char('G'))).unwrap();
+        view.handle_key(KeyEvent::from(KeyCode::Char('L'))).unwrap();
+        tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
+        view.update().unwrap();
+        assert_eq!(
+            view.poll_action(),
+            Action::Copy("## Fixes\n\n- Flicker\n".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_split_opens_status_view() {
+        let (_temp_dir, repo) = RepoBuilder::new()
+            .commit("Base")
+            .commit("Second")
+            .open()
+            .await;
+        let mut view = MainView::new(repo, test_color_scheme());
+        view.start_loading();
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        view.update().unwrap();
+
+        view.handle_key(KeyEvent::from(KeyCode::Char('S'))).unwrap();
+        assert_eq!(view.busy(), Some("splitting"));
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        view.update().unwrap();
+        assert_eq!(view.poll_action(), Action::PushView(ViewType::Status));
+
+        // HEAD is at the root commit now, and a split is in progress anyway
+        view.refresh();
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        view.update().unwrap();
+        view.select_last();
+        view.handle_key(KeyEvent::from(KeyCode::Char('S'))).unwrap();
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        assert!(matches!(view.update(), Err(AppError::Recoverable(_))));
+    }
+
+    #[tokio::test]
+    async fn test_commit_list_replaces_history() {
+        let builder = RepoBuilder::new().commit("First");
+        let first = builder.head();
+        let builder = builder.commit("Second");
+        let second = builder.head();
+        let (_temp_dir, repo) = builder.commit("Third").open().await;
+
+        let mut view =
+            MainView::new(repo, test_color_scheme()).with_commit_list(vec![first, second]);
+        view.start_loading();
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        view.update().unwrap();
+
+        let summaries: Vec<&str> = view.commits.iter().map(|c| c.summary.as_str()).collect();
+        assert_eq!(summaries, ["First", "Second"]);
+        assert_eq!(view.name(), "Main (listed)");
+    }
+
+    #[tokio::test]
+    async fn test_comparison_marks_applied_commits() {
+        use ratatui::{Terminal, backend::TestBackend};
+
+        let (_temp_dir, repo) = RepoBuilder::new()
+            .commit("Base")
+            .branch("upstream")
+            .branch("local")
+            .checkout("upstream")
+            .file("a.txt", "a\n")
+            .commit("Pick a")
+            .checkout("local")
+            .file("a.txt", "a\n")
+            .commit("Add a")
+            .file("b.txt", "b\n")
+            .commit("Add b")
+            .open()
+            .await;
+        let mut view = MainView::new(repo, test_color_scheme()).with_comparison("upstream");
+        view.start_loading();
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        view.update().unwrap();
+
+        let summaries: Vec<&str> = view.commits.iter().map(|c| c.summary.as_str()).collect();
+        assert_eq!(summaries, ["Add b", "Add a"]);
+        assert_eq!(view.title(), "Compare");
+
+        let mut terminal = Terminal::new(TestBackend::new(100, 6)).unwrap();
+        terminal
+            .draw(|frame| view.draw(frame, frame.area()))
+            .unwrap();
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        assert!(screen.contains("Compare upstream..HEAD - 2 commits [1 applied upstream]"));
+        assert!(screen.contains("> + "));
+        assert!(screen.contains("  - "));
+
+        // q closes the comparison instead of quitting
+        assert_eq!(
+            view.handle_key(KeyEvent::from(KeyCode::Char('q'))).unwrap(),
+            Action::PopView
+        );
+    }
+
+    #[tokio::test]
+    async fn test_refs_containing_popup() {
+        use ratatui::{Terminal, backend::TestBackend};
+
+        let (_temp_dir, repo) = RepoBuilder::new()
+            .commit("First")
+            .commit("Second")
+            .tag("v0.2")
+            .open()
+            .await;
+        let mut view = MainView::new(repo, test_color_scheme());
+        view.start_loading();
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        view.update().unwrap();
+
+        // The older commit is contained in the tag on the newer one
+        view.table_state.select(Some(1));
+        view.handle_key(KeyEvent::from(KeyCode::Char('I'))).unwrap();
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        view.update().unwrap();
+
+        let mut terminal = Terminal::new(TestBackend::new(80, 12)).unwrap();
+        terminal
+            .draw(|frame| view.draw(frame, frame.area()))
+            .unwrap();
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        assert!(screen.contains("Refs containing"));
+        assert!(screen.contains("refs/tags/v0.2"));
+
+        view.handle_key(KeyEvent::from(KeyCode::Char('I'))).unwrap();
+        assert!(view.refs_popup.is_none());
+    }
+
     #[tokio::test]
     async fn test_main_view_creation() {
         let (_temp_dir, repo) = create_test_repo().await;
@@ -505,6 +2238,13 @@ mod tests {
 
         view.select_first();
         assert_eq!(view.table_state.selected(), Some(0));
+
+        // Counts move several rows at once and stop at the ends
+        let j = KeyEvent::new(KeyCode::Char('j'), KeyModifiers::NONE);
+        view.handle_key_with_count(j, 5).unwrap();
+        assert_eq!(view.table_state.selected(), Some(5));
+        view.handle_key_with_count(j, 50).unwrap();
+        assert_eq!(view.table_state.selected(), Some(9));
     }
 
     #[tokio::test]
@@ -566,6 +2306,114 @@ mod tests {
         assert_eq!(view.search_query, "");
     }
 
+    #[tokio::test]
+    async fn test_main_view_search_smart_case() {
+        let (_temp_dir, repo) = create_test_repo().await;
+        let mut view = MainView::new(repo, test_color_scheme());
+
+        for summary in ["Fix bug in parser", "fix typo"] {
+            view.commits.push(Commit {
+                id: git2::Oid::zero(),
+                short_id: "abc1234".to_string(),
+                author: "Test".to_string(),
+                author_email: "test@example.com".to_string(),
+                date: chrono::Local::now(),
+                summary: summary.to_string(),
+                message: summary.to_string(),
+                refs: vec![],
+            });
+        }
+
+        // Lowercase query is case-insensitive
+        view.enter_search_mode();
+        for c in "fix".chars() {
+            view.search_add_char(c);
+        }
+        assert_eq!(view.filtered_commits.len(), 2);
+
+        // Uppercase in the query makes it case-sensitive
+        view.exit_search_mode();
+        view.enter_search_mode();
+        for c in "Fix".chars() {
+            view.search_add_char(c);
+        }
+        assert_eq!(view.filtered_commits, vec![0]);
+
+        // Explicit toggle: smart -> sensitive -> insensitive
+        view.toggle_search_case();
+        assert_eq!(view.search_case, SearchCase::Sensitive);
+        assert_eq!(view.filtered_commits, vec![0]);
+        view.toggle_search_case();
+        assert_eq!(view.search_case, SearchCase::Insensitive);
+        assert_eq!(view.filtered_commits.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_quick_look_dismissed_by_movement() {
+        let (_temp_dir, repo) = create_test_repo().await;
+        let mut view = MainView::new(repo, test_color_scheme());
+        for i in 0..3 {
+            view.commits.push(Commit {
+                id: git2::Oid::zero(),
+                short_id: format!("commit{}", i),
+                author: "Test".to_string(),
+                author_email: "test@example.com".to_string(),
+                date: chrono::Local::now(),
+                summary: format!("Commit {}", i),
+                message: format!("Commit {}", i),
+                refs: vec![],
+            });
+        }
+
+        let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+
+        view.handle_key(key(KeyCode::Char(' '))).unwrap();
+        assert!(view.quick_look.is_some());
+
+        // Movement closes the popup and still moves
+        view.handle_key(key(KeyCode::Char('j'))).unwrap();
+        assert!(view.quick_look.is_none());
+        assert_eq!(view.table_state.selected(), Some(1));
+
+        // q only closes the popup
+        view.handle_key(key(KeyCode::Char(' '))).unwrap();
+        assert_eq!(
+            view.handle_key(key(KeyCode::Char('q'))).unwrap(),
+            Action::None
+        );
+        assert!(view.quick_look.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_main_view_search_history() {
+        let (_temp_dir, repo) = create_test_repo().await;
+        let mut view =
+            MainView::new(repo, test_color_scheme()).with_search_history(vec!["older".to_string()]);
+
+        let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+
+        view.handle_key(key(KeyCode::Char('/'))).unwrap();
+        view.handle_key(key(KeyCode::Char('f'))).unwrap();
+        let action = view.handle_key(key(KeyCode::Enter)).unwrap();
+        assert_eq!(action, Action::RecordSearch("f".to_string()));
+
+        // Up recalls the most recent query first, Down returns to the draft
+        view.handle_key(key(KeyCode::Char('/'))).unwrap();
+        view.handle_key(key(KeyCode::Char('x'))).unwrap();
+        view.handle_key(key(KeyCode::Up)).unwrap();
+        assert_eq!(view.search_query, "f");
+        view.handle_key(key(KeyCode::Up)).unwrap();
+        assert_eq!(view.search_query, "older");
+        view.handle_key(key(KeyCode::Down)).unwrap();
+        view.handle_key(key(KeyCode::Down)).unwrap();
+        assert_eq!(view.search_query, "x");
+
+        // Submitting an empty query records nothing
+        view.handle_key(key(KeyCode::Esc)).unwrap();
+        view.handle_key(key(KeyCode::Char('/'))).unwrap();
+        assert_eq!(view.handle_key(key(KeyCode::Enter)).unwrap(), Action::None);
+    }
+
     #[tokio::test]
     async fn test_main_view_search_empty_query() {
         let (_temp_dir, repo) = create_test_repo().await;
@@ -612,6 +2460,90 @@ mod tests {
         assert_eq!(view.filtered_commits.len(), 0);
     }
 
+    #[tokio::test]
+    async fn test_main_view_selection_follows_commit() {
+        let (_temp_dir, repo) = create_test_repo().await;
+        let mut view = MainView::new(repo, test_color_scheme());
+
+        for (i, summary) in ["Fix bug in parser", "Add new feature", "Fix typo"]
+            .iter()
+            .enumerate()
+        {
+            view.commits.push(Commit {
+                id: git2::Oid::from_str(&format!("{:040x}", i + 1)).unwrap(),
+                short_id: format!("{:07x}", i + 1),
+                author: "Test".to_string(),
+                author_email: "test@example.com".to_string(),
+                date: chrono::Local::now(),
+                summary: summary.to_string(),
+                message: summary.to_string(),
+                refs: vec![],
+            });
+        }
+        let typo_id = view.commits[2].id;
+
+        // Select "Fix typo" and filter: it stays selected at its filtered position
+        view.select_last();
+        view.enter_search_mode();
+        view.search_add_char('f');
+        view.search_add_char('i');
+        view.search_add_char('x');
+        assert_eq!(view.filtered_commits.len(), 2);
+        assert_eq!(view.table_state.selected(), Some(1));
+        assert_eq!(view.selected_oid(), Some(typo_id));
+
+        // Clearing the search keeps it selected in the full list
+        view.exit_search_mode();
+        assert_eq!(view.table_state.selected(), Some(2));
+        assert_eq!(view.selected_oid(), Some(typo_id));
+
+        // A filter that hides the selection falls back to the first result
+        view.enter_search_mode();
+        view.search_add_char('a');
+        view.search_add_char('d');
+        view.search_add_char('d');
+        assert_eq!(view.table_state.selected(), Some(0));
+    }
+
+    #[tokio::test]
+    async fn test_main_view_filter_applies_to_new_chunks() {
+        let (_temp_dir, repo) = create_test_repo().await;
+        let mut view = MainView::new(repo, test_color_scheme());
+
+        let commit = |author: &str, summary: &str| Commit {
+            id: git2::Oid::zero(),
+            short_id: "abc1234".to_string(),
+            author: author.to_string(),
+            author_email: format!("{}@example.com", author.to_lowercase()),
+            date: chrono::Local::now(),
+            summary: summary.to_string(),
+            message: summary.to_string(),
+            refs: vec![],
+        };
+
+        view.append_commits(vec![commit("Alice", "One"), commit("Bob", "Two")]);
+        view.apply_filter(CommitFilter {
+            author: Some("alice".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(view.filtered_commits, vec![0]);
+
+        // Commits streamed in after the filter was set are filtered too
+        view.append_commits(vec![commit("Bob", "Three"), commit("Alice", "Four")]);
+        assert_eq!(view.filtered_commits, vec![0, 3]);
+
+        // Search narrows the filtered set further
+        view.enter_search_mode();
+        view.search_add_char('F');
+        assert_eq!(view.filtered_commits, vec![3]);
+
+        // Clearing the filter leaves only the search
+        view.apply_filter(CommitFilter::default());
+        assert_eq!(view.filtered_commits, vec![3]);
+        view.exit_search_mode();
+        assert!(!view.is_filtered());
+    }
+
     #[tokio::test]
     async fn test_main_view_page_navigation() {
         let (_temp_dir, repo) = create_test_repo().await;
@@ -643,5 +2575,16 @@ mod tests {
         view.select_last();
         view.page_down(20);
         assert_eq!(view.table_state.selected(), Some(49));
+
+        // Ctrl-D and Ctrl-U move half the table as last drawn
+        view.visible_rows = 30;
+        view.select_first();
+        let ctrl = |c| KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL);
+        view.handle_key(ctrl('d')).unwrap();
+        assert_eq!(view.table_state.selected(), Some(15));
+        view.handle_key_with_count(ctrl('d'), 2).unwrap();
+        assert_eq!(view.table_state.selected(), Some(45));
+        view.handle_key(ctrl('u')).unwrap();
+        assert_eq!(view.table_state.selected(), Some(30));
     }
 }
@@ -1,25 +1,162 @@
 use super::view::{Action, View, ViewType};
-use anyhow::{anyhow, Result};
+use crate::error::AppResult;
+use anyhow::{Result, anyhow};
 use crossterm::event::KeyEvent;
-use ratatui::{layout::Rect, Frame};
+use git2::Oid;
+use ratatui::{
+    Frame,
+    layout::{Constraint, Layout, Rect},
+};
+use std::collections::HashMap;
+
+/// Percent of the area the upper pane of a split starts with
+pub const DEFAULT_SPLIT: u16 = 50;
+
+/// Percent a resize key moves the divider by
+pub const SPLIT_STEP: u16 = 5;
+
+/// Smallest and largest percent of the area the upper pane can have
+const MIN_SPLIT: u16 = 20;
+const MAX_SPLIT: u16 = 80;
 
 /// Manages a stack of views and handles view switching
 pub struct ViewManager {
     view_stack: Vec<Box<dyn View>>,
+    /// Whether a diff is shown below the view it was opened from
+    split: bool,
+    /// Percent of the area the upper pane gets, by split name like
+    /// "main/diff"
+    split_ratios: HashMap<String, u16>,
+    /// Whether the focused pane of the split fills the area until the next
+    /// view opens or closes
+    zoomed: bool,
+    /// Whether keys go to the upper pane of the split rather than the
+    /// lower one
+    focus_upper: bool,
 }
 
 impl ViewManager {
     pub fn new() -> Self {
         Self {
             view_stack: Vec::new(),
+            split: false,
+            split_ratios: HashMap::new(),
+            zoomed: false,
+            focus_upper: false,
         }
     }
 
+    /// Show diffs below the view they were opened from
+    pub fn with_split(mut self, split: bool) -> Self {
+        self.split = split;
+        self
+    }
+
+    /// Restore the upper pane's share of the area for each split
+    pub fn set_split_ratios(&mut self, ratios: HashMap<String, u16>) {
+        self.split_ratios = ratios;
+    }
+
+    /// Name the split on screen, like "main/diff", if there is one
+    pub fn split_name(&self) -> Option<String> {
+        let [.., upper, lower] = self.view_stack.as_slice() else {
+            return None;
+        };
+        let name = |view_type: ViewType| format!("{:?}", view_type).to_lowercase();
+        (self.split && lower.view_type() == ViewType::Diff && upper.view_type() != ViewType::Diff)
+            .then(|| format!("{}/{}", name(upper.view_type()), name(lower.view_type())))
+    }
+
+    /// Get the upper pane's percent of the area for the split on screen,
+    /// or None when there is no split or it is zoomed
+    pub fn split_ratio(&self) -> Option<u16> {
+        if self.zoomed {
+            return None;
+        }
+        let name = self.split_name()?;
+        Some(
+            self.split_ratios
+                .get(&name)
+                .copied()
+                .unwrap_or(DEFAULT_SPLIT),
+        )
+    }
+
+    /// Give the upper pane of the split on screen `percent` of the area,
+    /// within limits that leave both panes usable; returns the percent set
+    pub fn set_split_ratio(&mut self, percent: u16) -> Option<u16> {
+        let name = self.split_name()?;
+        let percent = percent.clamp(MIN_SPLIT, MAX_SPLIT);
+        self.split_ratios.insert(name, percent);
+        Some(percent)
+    }
+
+    /// Expand the focused pane of the split to the whole area, or put
+    /// the split back; returns whether it is zoomed now
+    pub fn toggle_zoom(&mut self) -> bool {
+        self.zoomed = !self.zoomed && self.split_name().is_some();
+        self.zoomed
+    }
+
+    /// Move keys between the panes of the split; returns whether the upper
+    /// pane has them now
+    pub fn toggle_focus(&mut self) -> bool {
+        // Only the focused pane shows while zoomed, so focus stays put
+        if self.split_ratio().is_some() {
+            self.focus_upper = !self.focus_upper;
+        }
+        self.focus_upper && self.split_name().is_some()
+    }
+
+    /// Index of the view keys go to: the current one, or the upper pane of
+    /// the split when it has focus
+    fn focused_index(&self) -> Option<usize> {
+        let last = self.view_stack.len().checked_sub(1)?;
+        match self.focus_upper && self.split_name().is_some() {
+            true => Some(last - 1),
+            false => Some(last),
+        }
+    }
+
+    /// Get the view keys go to
+    pub fn focused(&self) -> Option<&dyn View> {
+        self.focused_index().map(|i| self.view_stack[i].as_ref())
+    }
+
+    fn focused_mut(&mut self) -> Option<&mut Box<dyn View>> {
+        self.focused_index().map(|i| &mut self.view_stack[i])
+    }
+
+    /// Get the type of the view keys go to
+    pub fn focused_view_type(&self) -> Option<ViewType> {
+        self.focused().map(|v| v.view_type())
+    }
+
+    /// Get the upper pane's percent of every split resized so far
+    pub fn split_ratios(&self) -> &HashMap<String, u16> {
+        &self.split_ratios
+    }
+
     /// Push a view onto the stack
+    ///
+    /// A diff opened from the focused upper pane of a split replaces the
+    /// diff below it, so the upper pane keeps focus and previews as it goes.
     pub fn push(&mut self, view: Box<dyn View>) -> Result<()> {
+        if self.focus_upper && self.split_ratio().is_some() && view.view_type() == ViewType::Diff {
+            if let Some(mut old_view) = self.view_stack.pop() {
+                old_view.on_deactivate()?;
+            }
+            self.view_stack.push(view);
+            if let Some(new) = self.view_stack.last_mut() {
+                new.on_activate()?;
+            }
+            return Ok(());
+        }
         if let Some(current) = self.view_stack.last_mut() {
             current.on_deactivate()?;
         }
+        self.zoomed = false;
+        self.focus_upper = false;
         self.view_stack.push(view);
         if let Some(new) = self.view_stack.last_mut() {
             new.on_activate()?;
@@ -36,6 +173,8 @@ impl ViewManager {
         if let Some(mut old_view) = self.view_stack.pop() {
             old_view.on_deactivate()?;
         }
+        self.zoomed = false;
+        self.focus_upper = false;
 
         if let Some(current) = self.view_stack.last_mut() {
             current.on_activate()?;
@@ -49,6 +188,8 @@ impl ViewManager {
         if let Some(mut old_view) = self.view_stack.pop() {
             old_view.on_deactivate()?;
         }
+        self.zoomed = false;
+        self.focus_upper = false;
         self.view_stack.push(view);
         if let Some(new) = self.view_stack.last_mut() {
             new.on_activate()?;
@@ -56,6 +197,15 @@ impl ViewManager {
         Ok(())
     }
 
+    /// Drop every view and start over with `view` as the only one
+    pub fn reset(&mut self, view: Box<dyn View>) -> Result<()> {
+        if let Some(current) = self.view_stack.last_mut() {
+            current.on_deactivate()?;
+        }
+        self.view_stack.clear();
+        self.push(view)
+    }
+
     /// Get the current view
     pub fn current(&self) -> Option<&dyn View> {
         self.view_stack.last().map(|b| b.as_ref())
@@ -76,17 +226,31 @@ impl ViewManager {
         self.view_stack.len()
     }
 
-    /// Handle a key event, delegating to the current view
-    pub fn handle_key(&mut self, key: KeyEvent) -> Result<Action> {
-        if let Some(view) = self.current_mut() {
+    /// Handle a key event, delegating to the focused view
+    pub fn handle_key(&mut self, key: KeyEvent) -> AppResult<Action> {
+        if let Some(view) = self.focused_mut() {
             view.handle_key(key)
         } else {
             Ok(Action::None)
         }
     }
 
-    /// Update the current view
-    pub fn update(&mut self) -> Result<()> {
+    /// Handle a key event with a numeric count, delegating to the focused view
+    pub fn handle_key_with_count(&mut self, key: KeyEvent, count: usize) -> AppResult<Action> {
+        if let Some(view) = self.focused_mut() {
+            view.handle_key_with_count(key, count)
+        } else {
+            Ok(Action::None)
+        }
+    }
+
+    /// Update the current view, and the upper pane of the split too
+    pub fn update(&mut self) -> AppResult<()> {
+        if self.split_name().is_some()
+            && let [.., upper, _] = self.view_stack.as_mut_slice()
+        {
+            upper.update()?;
+        }
         if let Some(view) = self.current_mut() {
             view.update()
         } else {
@@ -94,18 +258,81 @@ impl ViewManager {
         }
     }
 
-    /// Render the current view
+    /// Take an action the current view, or the upper pane of the split,
+    /// requested outside of key handling
+    pub fn poll_action(&mut self) -> Action {
+        let action = match self.current_mut() {
+            Some(view) => view.poll_action(),
+            None => Action::None,
+        };
+        if action == Action::None
+            && self.split_name().is_some()
+            && let [.., upper, _] = self.view_stack.as_mut_slice()
+        {
+            return upper.poll_action();
+        }
+        action
+    }
+
+    /// Render the current view, below the view it was opened from when
+    /// split, or just the focused pane when zoomed
     pub fn draw(&mut self, frame: &mut Frame, area: Rect) {
-        if let Some(view) = self.current_mut() {
+        if let Some(ratio) = self.split_ratio()
+            && let [.., upper, lower] = self.view_stack.as_mut_slice()
+        {
+            let [upper_area, lower_area] = Layout::vertical([
+                Constraint::Percentage(ratio),
+                Constraint::Percentage(100 - ratio),
+            ])
+            .areas(area);
+            upper.draw(frame, upper_area);
+            lower.draw(frame, lower_area);
+        } else if let Some(view) = self.focused_mut() {
             view.draw(frame, area);
         }
     }
 
-    /// Get the title of the current view
+    /// Get the kind of the current view
+    pub fn current_view_type(&self) -> Option<ViewType> {
+        self.current().map(|v| v.view_type())
+    }
+
+    /// Check if the focused view is taking text input
+    pub fn captures_input(&self) -> bool {
+        self.focused().is_some_and(|v| v.captures_input())
+    }
+
+    /// Reload every open view, not just the current one
+    pub fn refresh_all(&mut self) {
+        for view in &mut self.view_stack {
+            view.refresh();
+        }
+    }
+
+    /// Select a commit in the topmost view listing commits, closing the
+    /// views above it; returns whether any view took the selection
+    pub fn select_commit(&mut self, oid: Oid) -> Result<bool> {
+        let Some(index) = self
+            .view_stack
+            .iter_mut()
+            .rposition(|v| v.select_commit(oid))
+        else {
+            return Ok(false);
+        };
+        while self.view_stack.len() > index + 1 {
+            self.pop()?;
+        }
+        Ok(true)
+    }
+
+    /// Describe the background work of every open view that hasn't finished
+    pub fn busy(&self) -> Vec<&'static str> {
+        self.view_stack.iter().filter_map(|v| v.busy()).collect()
+    }
+
+    /// Get the title of the focused view
     pub fn current_title(&self) -> &str {
-        self.current()
-            .map(|v| v.title())
-            .unwrap_or("rust-tig")
+        self.focused().map(|v| v.title()).unwrap_or("rust-tig")
     }
 }
 
@@ -118,14 +345,13 @@ impl Default for ViewManager {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
-    use ratatui::backend::TestBackend;
-    use ratatui::Terminal;
+    use crossterm::event::KeyEvent;
 
     // Mock view for testing
     struct MockView {
         title: String,
         activated: bool,
+        view_type: ViewType,
     }
 
     impl MockView {
@@ -133,16 +359,24 @@ mod tests {
             Self {
                 title: title.to_string(),
                 activated: false,
+                view_type: ViewType::Main,
+            }
+        }
+
+        fn of_type(title: &str, view_type: ViewType) -> Self {
+            Self {
+                view_type,
+                ..Self::new(title)
             }
         }
     }
 
     impl View for MockView {
-        fn handle_key(&mut self, _key: KeyEvent) -> Result<Action> {
+        fn handle_key(&mut self, _key: KeyEvent) -> AppResult<Action> {
             Ok(Action::None)
         }
 
-        fn update(&mut self) -> Result<()> {
+        fn update(&mut self) -> AppResult<()> {
             Ok(())
         }
 
@@ -152,6 +386,14 @@ mod tests {
             &self.title
         }
 
+        fn view_type(&self) -> ViewType {
+            self.view_type
+        }
+
+        fn select_commit(&mut self, _oid: Oid) -> bool {
+            self.title.starts_with("Commits")
+        }
+
         fn on_activate(&mut self) -> Result<()> {
             self.activated = true;
             Ok(())
@@ -201,6 +443,17 @@ mod tests {
         assert_eq!(manager.len(), 1);
     }
 
+    #[test]
+    fn test_reset_views() {
+        let mut manager = ViewManager::new();
+        manager.push(Box::new(MockView::new("View1"))).unwrap();
+        manager.push(Box::new(MockView::new("View2"))).unwrap();
+        manager.reset(Box::new(MockView::new("Fresh"))).unwrap();
+
+        assert_eq!(manager.len(), 1);
+        assert_eq!(manager.current_title(), "Fresh");
+    }
+
     #[test]
     fn test_switch_view() {
         let mut manager = ViewManager::new();
@@ -210,4 +463,112 @@ mod tests {
         assert_eq!(manager.len(), 1);
         assert_eq!(manager.current_title(), "View2");
     }
+
+    #[test]
+    fn test_split_diff_below_its_view() {
+        let mut manager = ViewManager::new().with_split(true);
+        manager
+            .push(Box::new(MockView::of_type("Commits", ViewType::Main)))
+            .unwrap();
+        assert_eq!(manager.split_name(), None);
+        manager
+            .push(Box::new(MockView::of_type("Diff", ViewType::Diff)))
+            .unwrap();
+        assert_eq!(manager.split_name().as_deref(), Some("main/diff"));
+        assert_eq!(manager.split_ratio(), Some(DEFAULT_SPLIT));
+
+        assert_eq!(manager.set_split_ratio(95), Some(80));
+        assert_eq!(manager.set_split_ratio(30), Some(30));
+        assert_eq!(manager.split_ratios()["main/diff"], 30);
+
+        // A diff opened from a diff isn't split
+        manager
+            .push(Box::new(MockView::of_type("Diff", ViewType::Diff)))
+            .unwrap();
+        assert_eq!(manager.split_ratio(), None);
+        assert_eq!(manager.set_split_ratio(40), None);
+
+        let mut manager = ViewManager::new();
+        manager
+            .push(Box::new(MockView::of_type("Commits", ViewType::Main)))
+            .unwrap();
+        manager
+            .push(Box::new(MockView::of_type("Diff", ViewType::Diff)))
+            .unwrap();
+        assert_eq!(manager.split_name(), None);
+        assert!(!manager.toggle_zoom());
+    }
+
+    #[test]
+    fn test_zoom_fills_area_until_views_change() {
+        let mut manager = ViewManager::new().with_split(true);
+        manager
+            .push(Box::new(MockView::of_type("Commits", ViewType::Main)))
+            .unwrap();
+        manager
+            .push(Box::new(MockView::of_type("Diff", ViewType::Diff)))
+            .unwrap();
+
+        assert!(manager.toggle_zoom());
+        assert_eq!(manager.split_ratio(), None);
+        assert!(!manager.toggle_zoom());
+        assert_eq!(manager.split_ratio(), Some(DEFAULT_SPLIT));
+
+        manager.toggle_zoom();
+        manager.pop().unwrap();
+        manager
+            .push(Box::new(MockView::of_type("Diff", ViewType::Diff)))
+            .unwrap();
+        assert_eq!(manager.split_ratio(), Some(DEFAULT_SPLIT));
+    }
+
+    #[test]
+    fn test_focus_moves_between_panes() {
+        let mut manager = ViewManager::new().with_split(true);
+        manager
+            .push(Box::new(MockView::of_type("Commits", ViewType::Main)))
+            .unwrap();
+        assert!(!manager.toggle_focus());
+        manager
+            .push(Box::new(MockView::of_type("Diff", ViewType::Diff)))
+            .unwrap();
+        assert_eq!(manager.current_title(), "Diff");
+
+        assert!(manager.toggle_focus());
+        assert_eq!(manager.current_title(), "Commits");
+        assert_eq!(manager.focused_view_type(), Some(ViewType::Main));
+
+        // A diff opened from the commits replaces the one below them
+        manager
+            .push(Box::new(MockView::of_type("Other diff", ViewType::Diff)))
+            .unwrap();
+        assert_eq!(manager.len(), 2);
+        assert_eq!(manager.current_title(), "Commits");
+        assert_eq!(manager.current().unwrap().title(), "Other diff");
+
+        // Focus stays put while zoomed
+        manager.toggle_zoom();
+        assert!(manager.toggle_focus());
+        assert_eq!(manager.current_title(), "Commits");
+        manager.toggle_zoom();
+        assert!(!manager.toggle_focus());
+        assert_eq!(manager.current_title(), "Other diff");
+    }
+
+    #[test]
+    fn test_select_commit_closes_views_above() {
+        let mut manager = ViewManager::new();
+        manager.push(Box::new(MockView::new("Commits"))).unwrap();
+        manager.push(Box::new(MockView::new("Diff"))).unwrap();
+        manager.push(Box::new(MockView::new("Help"))).unwrap();
+
+        assert!(manager.select_commit(Oid::zero()).unwrap());
+        assert_eq!(manager.len(), 1);
+        assert_eq!(manager.current_title(), "Commits");
+
+        let mut manager = ViewManager::new();
+        manager.push(Box::new(MockView::new("Help"))).unwrap();
+        assert!(!manager.select_commit(Oid::zero()).unwrap());
+        assert_eq!(manager.len(), 1);
+    }
 }
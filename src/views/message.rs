@@ -0,0 +1,154 @@
+use crate::config::{ColorScheme, Symbols};
+use ratatui::{
+    style::{Modifier, Style},
+    text::{Line, Span},
+};
+
+/// Render a commit message body, lightly formatting its Markdown: headers
+/// in bold, bullets as the table's bullet, and code spans and fenced code blocks in the
+/// hash color without their backticks
+///
+/// With `raw` set, the body is shown exactly as written.
+pub fn render_body(
+    body: &str,
+    colors: &ColorScheme,
+    symbols: &Symbols,
+    raw: bool,
+) -> Vec<Line<'static>> {
+    if raw {
+        return body
+            .lines()
+            .map(|line| Line::from(line.to_string()))
+            .collect();
+    }
+
+    let code = Style::default().fg(colors.commit_hash);
+    let mut in_fence = false;
+    let mut lines = Vec::new();
+    for line in body.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            lines.push(Line::from(Span::styled(format!("    {}", line), code)));
+            continue;
+        }
+
+        let indent = &line[..line.len() - trimmed.len()];
+        if let Some(header) = header_text(trimmed) {
+            let bold = Style::default().add_modifier(Modifier::BOLD);
+            lines.push(Line::from(inline_spans(header, bold, code)));
+        } else if let Some(item) = ["- ", "* ", "+ "]
+            .iter()
+            .find_map(|bullet| trimmed.strip_prefix(bullet))
+        {
+            let mut spans = vec![Span::raw(format!("{}{} ", indent, symbols.bullet))];
+            spans.extend(inline_spans(item, Style::default(), code));
+            lines.push(Line::from(spans));
+        } else {
+            let mut spans = vec![Span::raw(indent.to_string())];
+            spans.extend(inline_spans(trimmed, Style::default(), code));
+            lines.push(Line::from(spans));
+        }
+    }
+    lines
+}
+
+/// Get the text of an ATX header like `## Notes`
+fn header_text(line: &str) -> Option<&str> {
+    let hashes = line.len() - line.trim_start_matches('#').len();
+    match (1..=6).contains(&hashes) {
+        true => line[hashes..].strip_prefix(' ').map(str::trim),
+        false => None,
+    }
+}
+
+/// Split text into spans with `code` and `**bold**` parts styled and their
+/// markers dropped; an unclosed marker is kept as typed
+fn inline_spans(text: &str, base: Style, code: Style) -> Vec<Span<'static>> {
+    let mut spans = Vec::new();
+    let mut plain = String::new();
+    let mut rest = text;
+    while !rest.is_empty() {
+        let marked = [("`", code), ("**", base.add_modifier(Modifier::BOLD))]
+            .into_iter()
+            .find_map(|(marker, style)| {
+                let inner = rest.strip_prefix(marker)?;
+                let end = inner.find(marker).filter(|&end| end > 0)?;
+                Some((&inner[..end], style, marker.len() * 2 + end))
+            });
+        match marked {
+            Some((inner, style, len)) => {
+                if !plain.is_empty() {
+                    spans.push(Span::styled(std::mem::take(&mut plain), base));
+                }
+                spans.push(Span::styled(inner.to_string(), style));
+                rest = &rest[len..];
+            }
+            None => {
+                let c = rest.chars().next().expect("rest is not empty");
+                plain.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+    if !plain.is_empty() {
+        spans.push(Span::styled(plain, base));
+    }
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    #[test]
+    fn test_renders_markdown_lightly() {
+        let colors = ColorScheme::from_config(&Config::default().colors);
+        let body =
+            "## Why\n\nThe `parse` step was **slow**.\n  - one\n* two `x\n```\nlet a = 1;\n```";
+
+        let lines = render_body(body, &colors, &Symbols::UNICODE, false);
+        let text: Vec<String> = lines.iter().map(Line::to_string).collect();
+        assert_eq!(
+            text,
+            [
+                "Why",
+                "",
+                "The parse step was slow.",
+                "  • one",
+                "• two `x",
+                "    let a = 1;"
+            ]
+        );
+        assert!(
+            lines[0].spans[0]
+                .style
+                .add_modifier
+                .contains(Modifier::BOLD)
+        );
+        assert_eq!(lines[2].spans[2].content, "parse");
+        assert_eq!(lines[2].spans[2].style.fg, Some(colors.commit_hash));
+        assert!(
+            lines[2].spans[4]
+                .style
+                .add_modifier
+                .contains(Modifier::BOLD)
+        );
+
+        let ascii: Vec<String> = render_body(body, &colors, &Symbols::ASCII, false)
+            .iter()
+            .map(Line::to_string)
+            .collect();
+        assert_eq!(ascii[3], "  * one");
+
+        let raw: Vec<String> = render_body(body, &colors, &Symbols::UNICODE, true)
+            .iter()
+            .map(Line::to_string)
+            .collect();
+        assert_eq!(raw, body.lines().collect::<Vec<_>>());
+    }
+}
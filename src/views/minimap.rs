@@ -0,0 +1,87 @@
+use crate::config::ColorScheme;
+use crate::git::LineType;
+use ratatui::{Frame, layout::Rect, style::Style, text::Line, widgets::Paragraph};
+
+/// What a minimap row stands for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mark {
+    /// Only added lines
+    Added,
+    /// Only deleted lines
+    Deleted,
+    /// Both added and deleted lines
+    Mixed,
+}
+
+/// Scale the changed lines of a `content_length` line diff down to `rows`
+/// rows, marking each row by the changes that fall in it
+///
+/// `changes` holds rendered line indices with their line type; anything but
+/// additions and deletions is ignored.
+pub fn minimap_marks(
+    changes: &[(usize, LineType)],
+    content_length: usize,
+    rows: usize,
+) -> Vec<Option<Mark>> {
+    let mut marks = vec![None; rows];
+    if rows == 0 || content_length == 0 {
+        return marks;
+    }
+    for &(line, line_type) in changes {
+        let row = (line * rows / content_length).min(rows - 1);
+        marks[row] = match (marks[row], line_type) {
+            (None | Some(Mark::Added), LineType::Addition) => Some(Mark::Added),
+            (None | Some(Mark::Deleted), LineType::Deletion) => Some(Mark::Deleted),
+            (mark, LineType::Context | LineType::FileHeader | LineType::HunkHeader) => mark,
+            _ => Some(Mark::Mixed),
+        };
+    }
+    marks
+}
+
+/// Draw a one column minimap of where a diff changes into `area`
+pub fn render_minimap(
+    frame: &mut Frame,
+    area: Rect,
+    colors: &ColorScheme,
+    changes: &[(usize, LineType)],
+    content_length: usize,
+) {
+    let lines: Vec<Line> = minimap_marks(changes, content_length, area.height as usize)
+        .into_iter()
+        .map(|mark| match mark {
+            Some(Mark::Added) => Line::styled("+", Style::default().fg(colors.added)),
+            Some(Mark::Deleted) => Line::styled("-", Style::default().fg(colors.deleted)),
+            Some(Mark::Mixed) => Line::styled("~", Style::default().fg(colors.modified)),
+            None => Line::from(" "),
+        })
+        .collect();
+    frame.render_widget(Paragraph::new(lines), area);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_marks_scale_to_rows() {
+        let changes = [
+            (0, LineType::HunkHeader),
+            (1, LineType::Addition),
+            (50, LineType::Deletion),
+            (98, LineType::Addition),
+            (99, LineType::Deletion),
+        ];
+        let marks = minimap_marks(&changes, 100, 10);
+        assert_eq!(marks[0], Some(Mark::Added));
+        assert_eq!(marks[5], Some(Mark::Deleted));
+        assert_eq!(marks[9], Some(Mark::Mixed));
+        assert_eq!(marks.iter().filter(|m| m.is_some()).count(), 3);
+    }
+
+    #[test]
+    fn test_no_rows_or_lines() {
+        assert!(minimap_marks(&[(0, LineType::Addition)], 10, 0).is_empty());
+        assert_eq!(minimap_marks(&[], 0, 3), [None, None, None]);
+    }
+}
@@ -1,15 +1,51 @@
 // View implementations (Main, Diff, Status, etc.)
 
+pub mod absorb_prompt;
+pub mod blame_view;
+pub mod commit_check;
+pub mod commit_prompt;
+pub mod copy_menu;
 pub mod diff_view;
+pub mod filter;
+pub mod health_view;
 pub mod help_view;
+pub mod history;
+pub mod hooks_view;
+pub mod lost_view;
 pub mod main_view;
 pub mod manager;
+pub mod message;
+pub mod minimap;
+pub mod onboarding_view;
+pub mod output_view;
+pub mod popup;
+pub mod quick_look;
+pub mod refs_popup;
+pub mod refs_view;
+pub mod review_view;
+pub mod scroll_memory;
+pub mod scrollbar;
+pub mod search;
+pub mod spell;
+pub mod stack_view;
+pub mod stash_view;
 pub mod status_view;
+pub mod title;
 pub mod view;
 
+pub use blame_view::BlameView;
 pub use diff_view::DiffView;
+pub use health_view::HealthView;
 pub use help_view::HelpView;
+pub use hooks_view::HooksView;
+pub use lost_view::LostView;
 pub use main_view::MainView;
-pub use manager::ViewManager;
+pub use manager::{DEFAULT_SPLIT, SPLIT_STEP, ViewManager};
+pub use onboarding_view::OnboardingView;
+pub use output_view::OutputView;
+pub use refs_view::RefsView;
+pub use review_view::ReviewView;
+pub use stack_view::StackView;
+pub use stash_view::StashView;
 pub use status_view::StatusView;
-pub use view::{Action, View, ViewType};
+pub use view::{Action, EditRequest, Selection, View, ViewType};
@@ -0,0 +1,451 @@
+use super::commit_check::CommitCheck;
+use super::commit_prompt::{CommitPrompt, CommitPromptEvent};
+use super::history::InputHistory;
+use super::spell::SpellChecker;
+use super::view::{Action, View, ViewType};
+use crate::config::{ColorScheme, Symbols};
+use crate::error::AppResult;
+use crate::git::{GitError, PrecommitOptions, Repository, Status};
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+use git2::Oid;
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Paragraph, Wrap},
+};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+/// How often to check whether HEAD exists yet
+const PROBE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Staged files listed before the rest are summarized
+const MAX_LISTED_FILES: usize = 10;
+
+/// What the repository looks like right now
+struct Probe {
+    has_commits: bool,
+    staged: Vec<String>,
+    /// Text from `commit.template`, if set and readable
+    template: Option<String>,
+}
+
+/// Onboarding view for a repository without commits
+///
+/// Explains the state, offers to commit staged files, and switches to the
+/// main view as soon as HEAD exists (whether committed here or elsewhere).
+pub struct OnboardingView {
+    repo: Repository,
+    staged: Vec<String>,
+    template: Option<String>,
+    commit_prompt: Option<CommitPrompt>,
+    commit_history: InputHistory,
+    spell_checker: Option<Arc<SpellChecker>>,
+    commit_check: CommitCheck,
+    error: Option<String>,
+    has_commits: bool,
+    last_probe: Option<Instant>,
+    probe_receiver: Option<mpsc::UnboundedReceiver<Result<Probe>>>,
+    commit_receiver: Option<mpsc::UnboundedReceiver<Result<Oid>>>,
+    colors: ColorScheme,
+    symbols: Symbols,
+}
+
+impl OnboardingView {
+    /// Create a new onboarding view
+    pub fn new(repo: Repository, colors: ColorScheme) -> Self {
+        Self {
+            repo,
+            staged: Vec::new(),
+            template: None,
+            commit_prompt: None,
+            commit_history: InputHistory::default(),
+            spell_checker: None,
+            commit_check: CommitCheck::default(),
+            error: None,
+            has_commits: false,
+            last_probe: None,
+            probe_receiver: None,
+            commit_receiver: None,
+            colors,
+            symbols: Symbols::default(),
+        }
+    }
+
+    /// Set the glyphs used for borders
+    pub fn with_symbols(mut self, symbols: Symbols) -> Self {
+        self.symbols = symbols;
+        self
+    }
+
+    /// Set the commit messages Up recalls, oldest first
+    pub fn with_commit_history(mut self, entries: Vec<String>) -> Self {
+        self.commit_history = InputHistory::new(entries);
+        self
+    }
+
+    /// Underline misspelled words in commit messages
+    pub fn with_spell_checker(mut self, spell_checker: Option<Arc<SpellChecker>>) -> Self {
+        self.spell_checker = spell_checker;
+        self
+    }
+
+    /// Check the staged files for large files and secrets before committing
+    pub fn with_precommit_checks(mut self, options: Option<PrecommitOptions>) -> Self {
+        self.commit_check = CommitCheck::new(options);
+        self
+    }
+
+    /// Check for commits and staged files in the background
+    fn start_probe(&mut self) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.probe_receiver = Some(rx);
+        self.last_probe = Some(Instant::now());
+
+        let repo = self.repo.clone();
+
+        tokio::spawn(async move {
+            let result = async {
+                let has_commits = !repo.is_empty().await?;
+                let status: Status = crate::git::status::load_status(&repo).await?;
+                let staged = status.staged.into_iter().map(|e| e.path).collect();
+                let template = crate::git::status::commit_template(&repo)
+                    .await
+                    .ok()
+                    .flatten();
+                Ok::<_, GitError>(Probe {
+                    has_commits,
+                    staged,
+                    template,
+                })
+            }
+            .await
+            .map_err(|e| anyhow::anyhow!(e));
+            let _ = tx.send(result);
+        });
+    }
+
+    /// Commit the staged files with the typed message
+    fn start_commit(&mut self, message: String) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.commit_receiver = Some(rx);
+
+        let repo = self.repo.clone();
+
+        tokio::spawn(async move {
+            let result = crate::git::status::commit_staged(&repo, message)
+                .await
+                .map_err(|e| anyhow::anyhow!(e));
+            let _ = tx.send(result);
+        });
+    }
+
+    /// Handle a key while the commit message is being typed
+    fn handle_message_key(&mut self, key: KeyEvent) -> Action {
+        let Some(prompt) = &mut self.commit_prompt else {
+            return Action::None;
+        };
+        let event = prompt.handle_key(key);
+        if matches!(
+            event,
+            CommitPromptEvent::Cancel | CommitPromptEvent::Submit(_)
+        ) && let Some(prompt) = self.commit_prompt.take()
+        {
+            self.commit_history = prompt.into_history();
+        }
+        match event {
+            CommitPromptEvent::Submit(message) => {
+                if let Some(checked) = self.commit_check.submit(&self.repo, message.clone()) {
+                    self.start_commit(checked);
+                }
+                Action::RecordCommitMessage(message)
+            }
+            CommitPromptEvent::Empty => {
+                self.error = Some("Commit message is empty".to_string());
+                Action::None
+            }
+            CommitPromptEvent::None | CommitPromptEvent::Cancel => Action::None,
+        }
+    }
+
+    /// Build the explanation and shortcut lines
+    fn build_lines(&self) -> Vec<Line<'static>> {
+        let heading = Style::default()
+            .fg(Color::Yellow)
+            .add_modifier(Modifier::BOLD);
+        let key_style = Style::default().fg(Color::Green);
+
+        let mut lines = vec![
+            Line::from(Span::styled("This repository has no commits yet.", heading)),
+            Line::from(""),
+        ];
+
+        if self.staged.is_empty() {
+            lines.push(Line::from(
+                "Nothing is staged. Stage files in the status view, then commit them here.",
+            ));
+        } else {
+            lines.push(Line::from(format!("{} files staged:", self.staged.len())));
+            for path in self.staged.iter().take(MAX_LISTED_FILES) {
+                lines.push(Line::from(Span::styled(
+                    format!("  {}", path),
+                    Style::default().fg(self.colors.added),
+                )));
+            }
+            if self.staged.len() > MAX_LISTED_FILES {
+                lines.push(Line::from(format!(
+                    "  ... and {} more",
+                    self.staged.len() - MAX_LISTED_FILES
+                )));
+            }
+        }
+        lines.push(Line::from(""));
+
+        for (key, description) in [
+            ("s", "Open the status view to stage files"),
+            ("c", "Commit the staged files"),
+            ("r", "Refresh"),
+            ("?", "Help"),
+            ("q", "Quit"),
+        ] {
+            lines.push(Line::from(vec![
+                Span::styled(format!("  {:<4}", key), key_style),
+                Span::raw(description),
+            ]));
+        }
+
+        lines.push(Line::from(""));
+        lines.push(Line::from(
+            "The commit history opens as soon as the first commit exists.",
+        ));
+
+        if let Some(prompt) = &self.commit_prompt {
+            lines.push(Line::from(""));
+            let mut spans = vec![Span::styled("Commit message: ", key_style)];
+            spans.extend(prompt.spans());
+            lines.push(Line::from(spans));
+            lines.push(Line::from(
+                "Enter to commit, Up/Down for earlier messages, Esc to cancel",
+            ));
+        }
+
+        if let Some(error) = &self.error {
+            lines.push(Line::from(""));
+            lines.push(Line::from(Span::styled(
+                error.clone(),
+                Style::default().fg(self.colors.deleted),
+            )));
+        }
+
+        lines
+    }
+}
+
+impl View for OnboardingView {
+    fn handle_key(&mut self, key: KeyEvent) -> AppResult<Action> {
+        if self.commit_check.is_confirming() {
+            if let Some(message) = self.commit_check.handle_key(key) {
+                self.start_commit(message);
+            }
+            return Ok(Action::None);
+        }
+        if self.commit_prompt.is_some() {
+            return Ok(self.handle_message_key(key));
+        }
+
+        match key.code {
+            KeyCode::Char('q') => return Ok(Action::Quit),
+            KeyCode::Char('s') => return Ok(Action::PushView(ViewType::Status)),
+            KeyCode::Char('c') => {
+                if self.staged.is_empty() {
+                    self.error = Some("Nothing staged: press s to stage files first".to_string());
+                } else {
+                    self.error = None;
+                    let history = std::mem::take(&mut self.commit_history);
+                    let text = self.template.clone().unwrap_or_default();
+                    self.commit_prompt = Some(
+                        CommitPrompt::new(text, history)
+                            .with_spell_checker(self.spell_checker.clone())
+                            .with_symbols(self.symbols),
+                    );
+                }
+            }
+            KeyCode::Char('r') => self.start_probe(),
+            KeyCode::Char('?') => return Ok(Action::ShowHelp(ViewType::Onboarding)),
+            _ => {}
+        }
+        Ok(Action::None)
+    }
+
+    fn update(&mut self) -> AppResult<()> {
+        if let Some(receiver) = &mut self.probe_receiver
+            && let Ok(result) = receiver.try_recv()
+        {
+            self.probe_receiver = None;
+            match result {
+                Ok(probe) => {
+                    self.has_commits = probe.has_commits;
+                    self.staged = probe.staged;
+                    self.template = probe.template;
+                }
+                Err(e) => self.error = Some(format!("Failed to read repository: {}", e)),
+            }
+        }
+
+        if let Some(receiver) = &mut self.commit_receiver
+            && let Ok(result) = receiver.try_recv()
+        {
+            self.commit_receiver = None;
+            match result {
+                Ok(_) => self.has_commits = true,
+                Err(e) => self.error = Some(format!("Commit failed: {}", e)),
+            }
+        }
+
+        match self.commit_check.update() {
+            Ok(Some(message)) => self.start_commit(message),
+            Ok(None) => {}
+            Err(e) => self.error = Some(format!("Checking the staged files failed: {}", e)),
+        }
+
+        // Keep watching for a first commit made from another terminal
+        let due = self
+            .last_probe
+            .is_none_or(|last| last.elapsed() >= PROBE_INTERVAL);
+        if due && self.probe_receiver.is_none() && !self.has_commits {
+            self.start_probe();
+        }
+        Ok(())
+    }
+
+    fn poll_action(&mut self) -> Action {
+        if self.has_commits {
+            self.has_commits = false;
+            Action::SwitchView(ViewType::Main)
+        } else {
+            Action::None
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame, area: Rect) {
+        let paragraph = Paragraph::new(self.build_lines())
+            .wrap(Wrap { trim: false })
+            .block(self.symbols.block().title("Empty repository"));
+        frame.render_widget(paragraph, area);
+        self.commit_check
+            .draw(frame, area, &self.colors, &self.symbols);
+    }
+
+    fn title(&self) -> &str {
+        "Empty repository"
+    }
+
+    fn view_type(&self) -> ViewType {
+        ViewType::Onboarding
+    }
+
+    fn captures_input(&self) -> bool {
+        self.commit_prompt.is_some() || self.commit_check.is_confirming()
+    }
+
+    fn refresh(&mut self) {
+        self.start_probe();
+    }
+
+    fn busy(&self) -> Option<&'static str> {
+        if self.commit_check.is_checking() {
+            Some("checking")
+        } else {
+            self.commit_receiver.as_ref().map(|_| "committing")
+        }
+    }
+
+    fn on_activate(&mut self) -> Result<()> {
+        // Staged files may have changed in the status view
+        self.start_probe();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::testing::RepoBuilder;
+    use crossterm::event::KeyModifiers;
+    use tempfile::TempDir;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    async fn create_view() -> (TempDir, OnboardingView) {
+        let (temp_dir, repo) = RepoBuilder::new().open().await;
+        let colors = ColorScheme::from_config(&Config::default().colors);
+        (temp_dir, OnboardingView::new(repo, colors))
+    }
+
+    #[tokio::test]
+    async fn test_commit_requires_staged_files() {
+        let (_temp_dir, mut view) = create_view().await;
+
+        view.handle_key(key(KeyCode::Char('c'))).unwrap();
+        assert!(view.error.is_some());
+        assert!(!view.captures_input());
+    }
+
+    #[tokio::test]
+    async fn test_commit_message_input() {
+        let (_temp_dir, mut view) = create_view().await;
+        view.staged = vec!["README.md".to_string()];
+
+        view.handle_key(key(KeyCode::Char('c'))).unwrap();
+        assert!(view.captures_input());
+
+        // Keys are typed into the message instead of running shortcuts
+        let action = view.handle_key(key(KeyCode::Char('q'))).unwrap();
+        assert_eq!(action, Action::None);
+        assert_eq!(
+            view.commit_prompt.as_ref().map(|p| p.display()).as_deref(),
+            Some("q_")
+        );
+
+        view.handle_key(key(KeyCode::Esc)).unwrap();
+        assert!(!view.captures_input());
+    }
+
+    #[tokio::test]
+    async fn test_commit_message_starts_from_template_and_recalls_history() {
+        let (_temp_dir, view) = create_view().await;
+        let mut view = view.with_commit_history(vec!["Initial commit".to_string()]);
+        view.staged = vec!["README.md".to_string()];
+        view.template = Some("feat: ".to_string());
+
+        view.handle_key(key(KeyCode::Char('c'))).unwrap();
+        assert_eq!(
+            view.commit_prompt.as_ref().map(|p| p.display()).as_deref(),
+            Some("feat: _")
+        );
+        view.handle_key(key(KeyCode::Up)).unwrap();
+        let action = view.handle_key(key(KeyCode::Enter)).unwrap();
+        assert_eq!(
+            action,
+            Action::RecordCommitMessage("Initial commit".to_string())
+        );
+        assert_eq!(view.commit_history.entries(), ["Initial commit"]);
+    }
+
+    #[tokio::test]
+    async fn test_switches_to_main_once_head_exists() {
+        let (_temp_dir, mut view) = create_view().await;
+        assert_eq!(view.poll_action(), Action::None);
+
+        view.has_commits = true;
+        assert_eq!(view.poll_action(), Action::SwitchView(ViewType::Main));
+        assert_eq!(view.poll_action(), Action::None);
+    }
+}
@@ -0,0 +1,267 @@
+use super::scrollbar::render_scrollbar;
+use super::view::{Action, View, ViewType};
+use crate::config::{ColorScheme, Symbols};
+use crate::error::{AppError, AppResult};
+use crate::git::{GitOutput, Maintenance, Repository};
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{Frame, layout::Rect, style::Style, text::Line, widgets::Paragraph};
+use std::collections::VecDeque;
+use tokio::sync::mpsc;
+
+/// Lines of output the pager keeps
+const PAGER_LINES: usize = 10_000;
+
+/// Output of a git command, kept as a terminal would show it: a progress
+/// line is overwritten by whatever line comes next
+#[derive(Debug, Clone)]
+pub struct OutputLog {
+    lines: VecDeque<String>,
+    /// Whether the last line is progress the next one overwrites
+    in_progress: bool,
+    /// Lines kept before the oldest are dropped
+    limit: usize,
+}
+
+impl OutputLog {
+    /// Start a log with the command line that produces it, keeping at most
+    /// `limit` lines
+    pub fn new(command_line: &str, limit: usize) -> Self {
+        Self {
+            lines: VecDeque::from([format!("$ {}", command_line)]),
+            in_progress: false,
+            limit,
+        }
+    }
+
+    /// Add a piece of output, returning how the command ended once it has
+    pub fn push(&mut self, output: GitOutput) -> Option<std::result::Result<(), String>> {
+        let (line, progress) = match output {
+            GitOutput::Line(line) => (line, false),
+            GitOutput::Progress(line) => (line, true),
+            GitOutput::Exited(result) => return Some(result),
+        };
+        if self.in_progress {
+            self.lines.pop_back();
+        }
+        self.lines.push_back(line);
+        self.in_progress = progress;
+        if self.lines.len() > self.limit {
+            self.lines.pop_front();
+        }
+        None
+    }
+
+    /// Add everything received so far, returning how the command ended if
+    /// it has
+    pub fn drain(
+        &mut self,
+        receiver: &mut mpsc::UnboundedReceiver<GitOutput>,
+    ) -> Option<std::result::Result<(), String>> {
+        let mut exited = None;
+        while let Ok(output) = receiver.try_recv() {
+            exited = exited.or(self.push(output));
+        }
+        exited
+    }
+
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    /// Get up to `count` lines starting at `start`
+    pub fn lines(&self, start: usize, count: usize) -> Vec<Line<'static>> {
+        self.lines
+            .iter()
+            .skip(start)
+            .take(count)
+            .map(|line| Line::from(line.clone()))
+            .collect()
+    }
+}
+
+/// A pager following a maintenance task's output as it runs in the
+/// background
+pub struct OutputView {
+    repo: Repository,
+    task: Maintenance,
+    log: OutputLog,
+    receiver: Option<mpsc::UnboundedReceiver<GitOutput>>,
+    /// First line shown, or None to follow the end as output arrives
+    scroll: Option<usize>,
+    /// Lines the last draw had room for
+    height: usize,
+    /// How the task ended, shown in the title
+    exited: Option<std::result::Result<(), String>>,
+    colors: ColorScheme,
+    symbols: Symbols,
+}
+
+impl OutputView {
+    /// Create a pager for `task`, which starts when the view is first shown
+    pub fn new(repo: Repository, colors: ColorScheme, task: Maintenance) -> Self {
+        Self {
+            repo,
+            task,
+            log: OutputLog::new(&task.command_line(), PAGER_LINES),
+            receiver: None,
+            scroll: None,
+            height: 0,
+            exited: None,
+            colors,
+            symbols: Symbols::default(),
+        }
+    }
+
+    /// Set the glyphs used for borders and the scrollbar
+    pub fn with_symbols(mut self, symbols: Symbols) -> Self {
+        self.symbols = symbols;
+        self
+    }
+
+    /// The first line shown
+    fn top(&self) -> usize {
+        let last_page = self.log.len().saturating_sub(self.height);
+        self.scroll
+            .map_or(last_page, |scroll| scroll.min(last_page))
+    }
+
+    /// Scroll down by `amount`, following the output again at the end
+    fn scroll_down(&mut self, amount: usize) {
+        let top = self.top() + amount;
+        self.scroll = match top >= self.log.len().saturating_sub(self.height) {
+            true => None,
+            false => Some(top),
+        };
+    }
+
+    fn scroll_up(&mut self, amount: usize) {
+        self.scroll = Some(self.top().saturating_sub(amount));
+    }
+}
+
+impl View for OutputView {
+    fn handle_key(&mut self, key: KeyEvent) -> AppResult<Action> {
+        self.handle_key_with_count(key, 1)
+    }
+
+    fn handle_key_with_count(&mut self, key: KeyEvent, count: usize) -> AppResult<Action> {
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(Action::PopView),
+            KeyCode::Char('j') | KeyCode::Down => self.scroll_down(count),
+            KeyCode::Char('k') | KeyCode::Up => self.scroll_up(count),
+            KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.scroll_down(10 * count)
+            }
+            KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.scroll_up(10 * count)
+            }
+            KeyCode::PageDown | KeyCode::Char(' ') => self.scroll_down(self.height.max(1) * count),
+            KeyCode::PageUp => self.scroll_up(self.height.max(1) * count),
+            KeyCode::Char('g') => self.scroll = Some(0),
+            KeyCode::Char('G') => self.scroll = None,
+            KeyCode::Char('?') => return Ok(Action::ShowHelp(ViewType::Output)),
+            _ => {}
+        }
+        Ok(Action::None)
+    }
+
+    fn update(&mut self) -> AppResult<()> {
+        if let Some(receiver) = &mut self.receiver
+            && let Some(exited) = self.log.drain(receiver)
+        {
+            self.receiver = None;
+            self.exited = Some(exited.clone());
+            exited.map_err(AppError::recoverable)?;
+        }
+        Ok(())
+    }
+
+    fn draw(&mut self, frame: &mut Frame, area: Rect) {
+        self.height = area.height.saturating_sub(2) as usize;
+        let state = match &self.exited {
+            None => "running".to_string(),
+            Some(Ok(())) => "finished".to_string(),
+            Some(Err(message)) => message.clone(),
+        };
+        let title = format!("{} - {}", self.task.command_line(), state);
+        let style = match &self.exited {
+            Some(Err(_)) => Style::default().fg(self.colors.deleted),
+            _ => Style::default(),
+        };
+
+        let top = self.top();
+        let text = Paragraph::new(self.log.lines(top, self.height))
+            .block(self.symbols.block().title(title).border_style(style));
+        frame.render_widget(text, area);
+        render_scrollbar(frame, area, &self.symbols, self.log.len(), top);
+    }
+
+    fn title(&self) -> &str {
+        "Output"
+    }
+
+    fn view_type(&self) -> ViewType {
+        ViewType::Output
+    }
+
+    fn busy(&self) -> Option<&'static str> {
+        self.receiver.as_ref().map(|_| "running maintenance")
+    }
+
+    fn on_activate(&mut self) -> Result<()> {
+        if self.receiver.is_none() && self.exited.is_none() {
+            self.receiver = Some(crate::git::maintenance::spawn_git(
+                &self.repo,
+                self.task.args(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::testing::RepoBuilder;
+
+    #[test]
+    fn test_progress_is_overwritten() {
+        let mut log = OutputLog::new("git gc", 3);
+        assert_eq!(
+            log.push(GitOutput::Progress("Counting: 10%".to_string())),
+            None
+        );
+        log.push(GitOutput::Progress("Counting: 100%".to_string()));
+        log.push(GitOutput::Line("Counting: done".to_string()));
+        log.push(GitOutput::Line("next".to_string()));
+        log.push(GitOutput::Line("last".to_string()));
+        let lines: Vec<String> = log.lines(0, 10).iter().map(Line::to_string).collect();
+        assert_eq!(lines, ["Counting: done", "next", "last"]);
+        assert_eq!(log.push(GitOutput::Exited(Ok(()))), Some(Ok(())));
+    }
+
+    #[tokio::test]
+    async fn test_runs_task_in_background() {
+        let (_temp_dir, repo) = RepoBuilder::new().commit("Initial").open().await;
+
+        let mut view = OutputView::new(
+            repo,
+            ColorScheme::from_config(&Config::default().colors),
+            Maintenance::Gc,
+        );
+        view.on_activate().unwrap();
+        assert_eq!(view.busy(), Some("running maintenance"));
+        while view.receiver.is_some() {
+            tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+            view.update().unwrap();
+        }
+        assert_eq!(view.exited, Some(Ok(())));
+        assert_eq!(view.log.lines(0, 1)[0].to_string(), "$ git gc");
+    }
+}
@@ -0,0 +1,30 @@
+use ratatui::layout::Rect;
+
+/// Compute a rectangle of at most `width` x `height` centered within `area`
+pub fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_centered_rect() {
+        let area = Rect::new(0, 0, 100, 40);
+        assert_eq!(centered_rect(50, 10, area), Rect::new(25, 15, 50, 10));
+    }
+
+    #[test]
+    fn test_centered_rect_clamped_to_area() {
+        let area = Rect::new(2, 3, 10, 5);
+        assert_eq!(centered_rect(50, 10, area), area);
+    }
+}
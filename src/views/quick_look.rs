@@ -0,0 +1,198 @@
+use super::popup::centered_rect;
+use crate::config::{ColorScheme, Symbols};
+use crate::git::{Commit, Diff, Repository, SizeLimit};
+use anyhow::Result;
+use git2::Oid;
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Clear, Paragraph, Wrap},
+};
+use tokio::sync::mpsc;
+
+/// Lines changed in one file of a commit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileStat {
+    pub path: String,
+    pub additions: usize,
+    pub deletions: usize,
+}
+
+/// Summarize a diff into per-file line counts
+pub fn diffstat(diff: &Diff) -> Vec<FileStat> {
+    diff.files
+        .iter()
+        .map(|f| FileStat {
+            path: f.path().to_string(),
+            additions: f.additions,
+            deletions: f.deletions,
+        })
+        .collect()
+}
+
+/// Floating preview of a commit's message and diffstat
+pub struct QuickLook {
+    commit: Commit,
+    stats: Option<Vec<FileStat>>,
+    error: Option<String>,
+    receiver: Option<mpsc::UnboundedReceiver<Result<Diff>>>,
+}
+
+impl QuickLook {
+    /// Open a preview for a commit and start loading its diffstat
+    pub fn open(repo: Repository, commit: Commit, limit: SizeLimit) -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let commit_id = commit.id;
+
+        tokio::spawn(async move {
+            let key = crate::git::diff_cache::DiffKey::Commit(commit_id);
+            let result = crate::git::diff_cache::load_diff(&repo, key, &limit)
+                .await
+                .map_err(|e| anyhow::anyhow!(e));
+            let _ = tx.send(result);
+        });
+
+        Self {
+            commit,
+            stats: None,
+            error: None,
+            receiver: Some(rx),
+        }
+    }
+
+    /// Get the id of the previewed commit
+    pub fn commit_id(&self) -> Oid {
+        self.commit.id
+    }
+
+    /// Pick up the diffstat once it has loaded
+    pub fn update(&mut self) {
+        if let Some(receiver) = &mut self.receiver
+            && let Ok(result) = receiver.try_recv()
+        {
+            match result {
+                Ok(diff) => self.stats = Some(diffstat(&diff)),
+                Err(e) => self.error = Some(e.to_string()),
+            }
+            self.receiver = None;
+        }
+    }
+
+    /// Render the preview over the middle half of `area`
+    pub fn draw(&self, frame: &mut Frame, area: Rect, colors: &ColorScheme, symbols: &Symbols) {
+        let commit = &self.commit;
+        let mut lines = vec![
+            Line::from(vec![
+                Span::raw("commit "),
+                Span::styled(
+                    commit.id.to_string(),
+                    Style::default().fg(colors.commit_hash),
+                ),
+            ]),
+            Line::from(vec![
+                Span::raw("Author: "),
+                Span::styled(
+                    format!("{} <{}>", commit.author, commit.author_email),
+                    Style::default().fg(colors.author),
+                ),
+            ]),
+            Line::from(vec![
+                Span::raw("Date:   "),
+                Span::styled(commit.date_str(), Style::default().fg(colors.date)),
+            ]),
+            Line::from(""),
+        ];
+
+        lines.extend(
+            commit
+                .message
+                .trim_end()
+                .lines()
+                .map(|l| Line::from(format!("    {}", l))),
+        );
+        lines.push(Line::from(""));
+
+        match (&self.stats, &self.error) {
+            (Some(stats), _) => lines.extend(self.stat_lines(stats, colors)),
+            (None, Some(error)) => lines.push(Line::from(format!("Error: {}", error))),
+            (None, None) => lines.push(Line::from("Loading diffstat...")),
+        }
+
+        let popup = centered_rect(area.width * 4 / 5, area.height / 2, area);
+        let title = format!("{} {}", commit.short_id, commit.summary);
+        frame.render_widget(Clear, popup);
+        frame.render_widget(
+            Paragraph::new(lines)
+                .wrap(Wrap { trim: false })
+                .block(symbols.block().title(title)),
+            popup,
+        );
+    }
+
+    /// Build the diffstat lines, git style
+    fn stat_lines(&self, stats: &[FileStat], colors: &ColorScheme) -> Vec<Line<'static>> {
+        let width = stats
+            .iter()
+            .map(|s| s.path.chars().count())
+            .max()
+            .unwrap_or(0);
+
+        let mut lines: Vec<Line> = stats
+            .iter()
+            .map(|s| {
+                Line::from(vec![
+                    Span::raw(format!(" {:<width$} | ", s.path, width = width)),
+                    Span::styled(
+                        format!("+{}", s.additions),
+                        Style::default().fg(colors.added),
+                    ),
+                    Span::raw(" "),
+                    Span::styled(
+                        format!("-{}", s.deletions),
+                        Style::default().fg(colors.deleted),
+                    ),
+                ])
+            })
+            .collect();
+
+        let additions: usize = stats.iter().map(|s| s.additions).sum();
+        let deletions: usize = stats.iter().map(|s| s.deletions).sum();
+        lines.push(Line::from(Span::styled(
+            format!(
+                " {} file{} changed, {} insertions(+), {} deletions(-)",
+                stats.len(),
+                if stats.len() == 1 { "" } else { "s" },
+                additions,
+                deletions
+            ),
+            Style::default().add_modifier(Modifier::BOLD),
+        )));
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::diff::{DiffFile, FileStatus};
+
+    #[test]
+    fn test_diffstat() {
+        let mut diff = Diff::new();
+        let mut file = DiffFile::new(None, Some("src/lib.rs".to_string()), FileStatus::Added);
+        file.additions = 3;
+        file.deletions = 1;
+        diff.files.push(file);
+
+        assert_eq!(
+            diffstat(&diff),
+            vec![FileStat {
+                path: "src/lib.rs".to_string(),
+                additions: 3,
+                deletions: 1,
+            }]
+        );
+    }
+}
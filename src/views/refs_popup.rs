@@ -0,0 +1,108 @@
+use super::popup::centered_rect;
+use crate::config::{ColorScheme, Symbols};
+use crate::git::{RefInfo, Repository, Result as GitResult};
+use anyhow::Result;
+use git2::Oid;
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Clear, Paragraph},
+};
+use std::future::Future;
+use tokio::sync::mpsc;
+
+/// Popup listing refs related to a commit, with full names: the refs that
+/// point at it, or the branches and tags that contain it
+pub struct RefsPopup {
+    title: String,
+    empty_message: &'static str,
+    refs: Option<Vec<RefInfo>>,
+    error: Option<String>,
+    receiver: Option<mpsc::UnboundedReceiver<Result<Vec<RefInfo>>>>,
+}
+
+impl RefsPopup {
+    /// Open the popup for a commit and start loading the refs at it
+    pub fn open(repo: Repository, commit_id: Oid, short_id: String) -> Self {
+        Self::load(
+            format!("Refs at {}", short_id),
+            " No refs point at this commit ",
+            async move { crate::git::refs::load_refs_for_commit(&repo, commit_id).await },
+        )
+    }
+
+    /// Open the popup for a commit and start finding the branches and tags
+    /// it has been merged into
+    pub fn open_containing(repo: Repository, commit_id: Oid, short_id: String) -> Self {
+        Self::load(
+            format!("Refs containing {}", short_id),
+            " No branch or tag contains this commit ",
+            async move { crate::git::refs::load_refs_containing(&repo, commit_id).await },
+        )
+    }
+
+    /// Start loading refs in the background
+    fn load<F>(title: String, empty_message: &'static str, refs: F) -> Self
+    where
+        F: Future<Output = GitResult<Vec<RefInfo>>> + Send + 'static,
+    {
+        let (tx, rx) = mpsc::unbounded_channel();
+
+        tokio::spawn(async move {
+            let result = refs.await.map_err(|e| anyhow::anyhow!(e));
+            let _ = tx.send(result);
+        });
+
+        Self {
+            title,
+            empty_message,
+            refs: None,
+            error: None,
+            receiver: Some(rx),
+        }
+    }
+
+    /// Pick up the refs once they have loaded
+    pub fn update(&mut self) {
+        if let Some(receiver) = &mut self.receiver
+            && let Ok(result) = receiver.try_recv()
+        {
+            match result {
+                Ok(refs) => self.refs = Some(refs),
+                Err(e) => self.error = Some(e.to_string()),
+            }
+            self.receiver = None;
+        }
+    }
+
+    /// Render the popup centered in `area`
+    pub fn draw(&self, frame: &mut Frame, area: Rect, colors: &ColorScheme, symbols: &Symbols) {
+        let lines: Vec<Line> = match (&self.refs, &self.error) {
+            (Some(refs), _) if refs.is_empty() => vec![Line::from(self.empty_message)],
+            (Some(refs), _) => refs
+                .iter()
+                .map(|r| {
+                    Line::from(vec![
+                        Span::styled(
+                            format!(" {:<7}", r.kind.label()),
+                            Style::default().fg(colors.commit_hash),
+                        ),
+                        Span::styled(format!("{} ", r.name), Style::default().fg(Color::Cyan)),
+                    ])
+                })
+                .collect(),
+            (None, Some(error)) => vec![Line::from(format!(" Error: {} ", error))],
+            (None, None) => vec![Line::from(" Loading refs... ")],
+        };
+
+        let width = lines.iter().map(Line::width).max().unwrap_or(0).max(20) as u16 + 2;
+        let popup = centered_rect(width, lines.len() as u16 + 2, area);
+        frame.render_widget(Clear, popup);
+        frame.render_widget(
+            Paragraph::new(lines).block(symbols.block().title(self.title.as_str())),
+            popup,
+        );
+    }
+}
@@ -0,0 +1,710 @@
+use super::scrollbar::render_scrollbar;
+use super::view::{Action, Selection, View, ViewType};
+use crate::clock::{SharedClock, system_clock};
+use crate::config::{ColorScheme, Symbols};
+use crate::error::{AppError, AppResult};
+use crate::git::{CheckoutTarget, RefEntry, RefKind, RemoteInfo, Repository};
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{List, ListItem, ListState},
+};
+use std::collections::HashSet;
+use tokio::sync::mpsc;
+
+/// Field the refs list is sorted by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortField {
+    Name,
+    Date,
+    Ahead,
+}
+
+/// Sort order of the refs list
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RefSort {
+    pub field: SortField,
+    pub descending: bool,
+}
+
+impl Default for RefSort {
+    fn default() -> Self {
+        Self {
+            field: SortField::Name,
+            descending: false,
+        }
+    }
+}
+
+impl RefSort {
+    /// Cycle to the next sort: each field in its natural direction, then reversed
+    pub fn next(self) -> Self {
+        let (field, descending) = match (self.field, self.descending) {
+            (SortField::Name, false) => (SortField::Name, true),
+            (SortField::Name, true) => (SortField::Date, true),
+            (SortField::Date, true) => (SortField::Date, false),
+            (SortField::Date, false) => (SortField::Ahead, true),
+            (SortField::Ahead, true) => (SortField::Ahead, false),
+            (SortField::Ahead, false) => (SortField::Name, false),
+        };
+        Self { field, descending }
+    }
+
+    /// Get a label for the title, e.g. "date ↓"
+    pub fn label(&self, symbols: &Symbols) -> String {
+        let field = match self.field {
+            SortField::Name => "name",
+            SortField::Date => "date",
+            SortField::Ahead => "ahead",
+        };
+        format!("{} {}", field, symbols.sort_direction(self.descending))
+    }
+
+    /// Sort refs within their kind (branches, remotes, tags)
+    pub fn apply(&self, refs: &mut [RefEntry]) {
+        refs.sort_by(|a, b| {
+            let ordering = match self.field {
+                SortField::Name => a.info.shorthand.cmp(&b.info.shorthand),
+                SortField::Date => a.commit.date.cmp(&b.commit.date),
+                SortField::Ahead => a.ahead.cmp(&b.ahead),
+            };
+            let ordering = if self.descending {
+                ordering.reverse()
+            } else {
+                ordering
+            };
+            a.info
+                .kind
+                .cmp(&b.info.kind)
+                .then(ordering)
+                .then_with(|| a.info.shorthand.cmp(&b.info.shorthand))
+        });
+    }
+}
+
+/// Refs and remotes, loaded together
+type Loaded = Result<(Vec<RefEntry>, Vec<RemoteInfo>)>;
+
+/// A line of the refs list
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Row {
+    /// Index into the sorted refs
+    Ref(usize),
+    /// Heading over a remote's branches
+    Remote(String),
+}
+
+/// Get the remote a remote-tracking branch is listed under: the one whose
+/// refspecs write it, or else the first part of its name
+fn group_of(entry: &RefEntry) -> String {
+    match &entry.remote {
+        Some(remote) => remote.clone(),
+        None => entry
+            .info
+            .shorthand
+            .split('/')
+            .next()
+            .unwrap_or_default()
+            .to_string(),
+    }
+}
+
+/// Refs view listing branches, remote branches grouped by remote, and tags
+pub struct RefsView {
+    repo: Repository,
+    refs: Vec<RefEntry>,
+    remotes: Vec<RemoteInfo>,
+    /// What each line of the list shows
+    rows: Vec<Row>,
+    /// Remotes whose branches are folded away
+    collapsed: HashSet<String>,
+    list_state: ListState,
+    sort: RefSort,
+    loading: bool,
+    error: Option<String>,
+    receiver: Option<mpsc::UnboundedReceiver<Loaded>>,
+    colors: ColorScheme,
+    symbols: Symbols,
+    clock: SharedClock,
+}
+
+impl RefsView {
+    /// Create a new refs view
+    pub fn new(repo: Repository, colors: ColorScheme) -> Self {
+        let mut list_state = ListState::default();
+        list_state.select(Some(0));
+
+        Self {
+            repo,
+            refs: Vec::new(),
+            remotes: Vec::new(),
+            rows: Vec::new(),
+            collapsed: HashSet::new(),
+            list_state,
+            sort: RefSort::default(),
+            loading: false,
+            error: None,
+            receiver: None,
+            colors,
+            symbols: Symbols::default(),
+            clock: system_clock(),
+        }
+    }
+
+    /// Set the glyphs used for borders and the sort direction
+    pub fn with_symbols(mut self, symbols: Symbols) -> Self {
+        self.symbols = symbols;
+        self
+    }
+
+    /// Set the clock relative commit dates are measured from
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Start loading refs asynchronously
+    pub fn start_loading(&mut self) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.receiver = Some(rx);
+        self.loading = true;
+
+        let repo = self.repo.clone();
+
+        tokio::spawn(async move {
+            let result = match crate::git::refs::load_refs(&repo).await {
+                Ok(refs) => crate::git::refs::load_remotes(&repo)
+                    .await
+                    .map(|remotes| (refs, remotes)),
+                Err(e) => Err(e),
+            };
+            let _ = tx.send(result.map_err(|e| anyhow::anyhow!(e)));
+        });
+    }
+
+    /// Get the currently selected row
+    fn selected_row(&self) -> Option<&Row> {
+        self.list_state.selected().and_then(|i| self.rows.get(i))
+    }
+
+    /// Get the currently selected ref, None on a remote's heading
+    fn selected_ref(&self) -> Option<&RefEntry> {
+        match self.selected_row() {
+            Some(Row::Ref(i)) => self.refs.get(*i),
+            _ => None,
+        }
+    }
+
+    /// Get the remote of the selected heading or remote branch
+    fn selected_remote(&self) -> Option<String> {
+        match self.selected_row()? {
+            Row::Remote(name) => Some(name.clone()),
+            Row::Ref(i) => self
+                .refs
+                .get(*i)
+                .filter(|r| r.info.kind == RefKind::RemoteBranch)
+                .map(group_of),
+        }
+    }
+
+    /// Name a row so it can be found again after the rows change
+    fn row_key(&self, row: &Row) -> String {
+        match row {
+            Row::Ref(i) => self.refs[*i].info.name.clone(),
+            Row::Remote(name) => format!("remote {}", name),
+        }
+    }
+
+    /// Lay out the rows: local branches, each remote's heading followed by
+    /// its branches unless folded, then tags; the selection stays put
+    fn rebuild_rows(&mut self) {
+        let selected = self.selected_row().cloned().map(|row| self.row_key(&row));
+
+        let mut groups: Vec<String> = self.remotes.iter().map(|r| r.name.clone()).collect();
+        groups.extend(
+            self.refs
+                .iter()
+                .filter(|r| r.info.kind == RefKind::RemoteBranch)
+                .map(group_of),
+        );
+        groups.sort();
+        groups.dedup();
+
+        let indexes = |kind: fn(RefKind) -> bool| {
+            self.refs
+                .iter()
+                .enumerate()
+                .filter(move |(_, r)| kind(r.info.kind))
+                .map(|(i, _)| i)
+        };
+        let mut rows: Vec<Row> = indexes(|kind| kind < RefKind::RemoteBranch)
+            .map(Row::Ref)
+            .collect();
+        for group in groups {
+            let folded = self.collapsed.contains(&group);
+            let members: Vec<usize> = indexes(|kind| kind == RefKind::RemoteBranch)
+                .filter(|&i| group_of(&self.refs[i]) == group)
+                .collect();
+            rows.push(Row::Remote(group));
+            if !folded {
+                rows.extend(members.into_iter().map(Row::Ref));
+            }
+        }
+        rows.extend(indexes(|kind| kind > RefKind::RemoteBranch).map(Row::Ref));
+        self.rows = rows;
+
+        let index = selected
+            .and_then(|key| self.rows.iter().position(|row| self.row_key(row) == key))
+            .unwrap_or(0);
+        self.list_state.select(Some(index));
+    }
+
+    /// Fold or unfold the selected remote, moving the selection to its heading
+    fn toggle_remote(&mut self) {
+        let Some(remote) = self.selected_remote() else {
+            return;
+        };
+        if !self.collapsed.remove(&remote) {
+            self.collapsed.insert(remote.clone());
+        }
+        let heading = Row::Remote(remote);
+        if let Some(index) = self.rows.iter().position(|row| *row == heading) {
+            self.list_state.select(Some(index));
+        }
+        self.rebuild_rows();
+    }
+
+    /// Re-sort the refs, keeping the selected row selected
+    fn set_sort(&mut self, sort: RefSort) {
+        let selected = self.selected_row().cloned().map(|row| self.row_key(&row));
+        self.sort = sort;
+        self.sort.apply(&mut self.refs);
+
+        // The old indexes point at other refs now
+        self.rows.clear();
+        self.rebuild_rows();
+        let index = selected
+            .and_then(|key| self.rows.iter().position(|row| self.row_key(row) == key))
+            .unwrap_or(0);
+        self.list_state.select(Some(index));
+    }
+
+    /// Move selection down by `amount`, stopping at the last row
+    fn select_down(&mut self, amount: usize) {
+        let last = self.rows.len().saturating_sub(1);
+        let i = self
+            .list_state
+            .selected()
+            .map_or(0, |i| (i + amount).min(last));
+        self.list_state.select(Some(i));
+    }
+
+    /// Move selection up by `amount`, stopping at the first row
+    fn select_up(&mut self, amount: usize) {
+        let i = self
+            .list_state
+            .selected()
+            .map_or(0, |i| i.saturating_sub(amount));
+        self.list_state.select(Some(i));
+    }
+
+    /// Create the heading line of a remote: fold marker, name, branch
+    /// count, URL and fetch refspecs
+    fn create_remote_item(&self, name: &str) -> ListItem<'static> {
+        let marker = match self.collapsed.contains(name) {
+            true => self.symbols.collapsed,
+            false => self.symbols.expanded,
+        };
+        let branches = self
+            .refs
+            .iter()
+            .filter(|r| r.info.kind == RefKind::RemoteBranch && group_of(r) == name)
+            .count();
+        let mut spans = vec![
+            Span::styled(
+                format!("{} {} ", marker, name),
+                Style::default()
+                    .fg(self.colors.commit_hash)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(format!("({} branches) ", branches)),
+        ];
+        match self.remotes.iter().find(|r| r.name == name) {
+            Some(remote) => {
+                spans.push(Span::styled(
+                    format!("{} ", remote.url),
+                    Style::default().fg(self.colors.date),
+                ));
+                spans.push(Span::styled(
+                    remote.fetch_refspecs.join(" "),
+                    Style::default().add_modifier(Modifier::DIM),
+                ));
+            }
+            None => spans.push(Span::styled(
+                "not a configured remote",
+                Style::default().add_modifier(Modifier::DIM),
+            )),
+        }
+        ListItem::new(Line::from(spans))
+    }
+
+    /// Create a list item for a ref
+    fn create_list_item(&self, entry: &RefEntry, name_width: usize) -> ListItem<'static> {
+        let ahead_behind = if entry.ahead == 0 && entry.behind == 0 {
+            String::new()
+        } else {
+            format!("+{} -{}", entry.ahead, entry.behind)
+        };
+
+        ListItem::new(Line::from(vec![
+            Span::styled(
+                format!("{:<7}", entry.info.kind.label()),
+                Style::default().fg(self.colors.commit_hash),
+            ),
+            Span::styled(
+                format!("{:<width$} ", entry.info.shorthand, width = name_width),
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(
+                format!("{:<15}", entry.commit.relative_date(self.clock.as_ref())),
+                Style::default().fg(self.colors.date),
+            ),
+            Span::styled(
+                format!("{:<10}", ahead_behind),
+                Style::default().fg(self.colors.modified),
+            ),
+            Span::raw(entry.commit.summary.clone()),
+        ]))
+    }
+}
+
+impl View for RefsView {
+    fn handle_key(&mut self, key: KeyEvent) -> AppResult<Action> {
+        self.handle_key_with_count(key, 1)
+    }
+
+    fn handle_key_with_count(&mut self, key: KeyEvent, count: usize) -> AppResult<Action> {
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(Action::PopView),
+            KeyCode::Char('j') | KeyCode::Down => self.select_down(count),
+            KeyCode::Char('k') | KeyCode::Up => self.select_up(count),
+            KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.select_down(10 * count)
+            }
+            KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.select_up(10 * count)
+            }
+            KeyCode::PageDown => self.select_down(20 * count),
+            KeyCode::PageUp => self.select_up(20 * count),
+            KeyCode::Char('g') => self.list_state.select(Some(0)),
+            KeyCode::Char('G') => self
+                .list_state
+                .select(Some(self.rows.len().saturating_sub(1))),
+            KeyCode::Char('o') => self.set_sort(self.sort.next()),
+            KeyCode::Char(' ') => self.toggle_remote(),
+            KeyCode::Enter if matches!(self.selected_row(), Some(Row::Remote(_))) => {
+                self.toggle_remote()
+            }
+            KeyCode::Char('f') => match self.selected_remote() {
+                Some(remote) => return Ok(Action::Fetch(remote)),
+                None => {
+                    return Err(AppError::recoverable(
+                        "Select a remote or remote branch to fetch",
+                    ));
+                }
+            },
+            KeyCode::Enter => {
+                if let Some(entry) = self.selected_ref() {
+                    return Ok(Action::OpenDiff {
+                        repo: self.repo.clone(),
+                        commit_id: entry.commit.id,
+                        summary: entry.commit.summary.clone(),
+                    });
+                }
+            }
+            KeyCode::Char('C') => match self.selected_ref() {
+                Some(entry) => {
+                    return Ok(Action::Checkout(CheckoutTarget::Ref(
+                        entry.info.name.clone(),
+                    )));
+                }
+                None => return Err(AppError::recoverable("Select a branch or tag to check out")),
+            },
+            KeyCode::Char('m') => match self.selected_ref() {
+                Some(entry) => return Ok(Action::Merge(entry.info.name.clone())),
+                None => return Err(AppError::recoverable("Select a branch or tag to merge")),
+            },
+            KeyCode::Char('c') => {
+                // List what HEAD has that the ref doesn't
+                if let Some(entry) = self.selected_ref() {
+                    return Ok(Action::Compare(entry.info.shorthand.clone()));
+                }
+            }
+            KeyCode::Char('?') => return Ok(Action::ShowHelp(ViewType::Refs)),
+            _ => {}
+        }
+        Ok(Action::None)
+    }
+
+    fn update(&mut self) -> AppResult<()> {
+        if let Some(receiver) = &mut self.receiver
+            && let Ok(result) = receiver.try_recv()
+        {
+            self.loading = false;
+            self.receiver = None;
+            match result {
+                Ok((refs, remotes)) => {
+                    self.refs = refs;
+                    self.remotes = remotes;
+                    self.set_sort(self.sort);
+                }
+                Err(e) => {
+                    self.error = Some(format!("Failed to load refs: {}", e));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn draw(&mut self, frame: &mut Frame, area: Rect) {
+        if let Some(error) = &self.error {
+            let list = List::new(vec![ListItem::new(error.clone())])
+                .block(self.symbols.block().title("Refs - Error"))
+                .style(Style::default().fg(self.colors.deleted));
+            frame.render_widget(list, area);
+            return;
+        }
+
+        let title = if self.loading {
+            "Refs - Loading...".to_string()
+        } else {
+            let mut title = format!(
+                "Refs - {} refs [sort: {}]",
+                self.refs.len(),
+                self.sort.label(&self.symbols)
+            );
+            if let Some(base) = self.selected_ref().and_then(|r| r.merge_base) {
+                title.push_str(&format!(" [merge-base: {:.7}]", base.to_string()));
+            }
+            title
+        };
+
+        let name_width = self
+            .refs
+            .iter()
+            .map(|r| r.info.shorthand.chars().count())
+            .max()
+            .unwrap_or(0);
+        let items: Vec<ListItem> = self
+            .rows
+            .iter()
+            .map(|row| match row {
+                Row::Ref(i) => self.create_list_item(&self.refs[*i], name_width),
+                Row::Remote(name) => self.create_remote_item(name),
+            })
+            .collect();
+
+        let list = List::new(items)
+            .block(self.symbols.block().title(title))
+            .highlight_style(self.colors.selected.add_modifier(Modifier::BOLD))
+            .highlight_symbol("> ");
+
+        frame.render_stateful_widget(list, area, &mut self.list_state);
+        let selected = self.list_state.selected().unwrap_or(0);
+        render_scrollbar(frame, area, &self.symbols, self.rows.len(), selected);
+    }
+
+    fn title(&self) -> &str {
+        "Refs"
+    }
+
+    fn view_type(&self) -> ViewType {
+        ViewType::Refs
+    }
+
+    fn selection(&self) -> Selection {
+        let selected = self.selected_ref();
+        Selection {
+            commit: selected.map(|r| r.commit.id),
+            refname: selected.map(|r| r.info.shorthand.clone()),
+            file: None,
+        }
+    }
+
+    fn refresh(&mut self) {
+        self.error = None;
+        self.start_loading();
+    }
+
+    fn on_activate(&mut self) -> Result<()> {
+        if self.refs.is_empty() && !self.loading {
+            self.start_loading();
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::{Commit, RefInfo, RefKind};
+    use chrono::{Duration, Local};
+
+    fn entry(name: &str, kind: RefKind, days_ago: i64, ahead: usize) -> RefEntry {
+        RefEntry {
+            info: RefInfo {
+                name: format!("refs/x/{}", name),
+                shorthand: name.to_string(),
+                kind,
+            },
+            commit: Commit {
+                id: git2::Oid::zero(),
+                short_id: "0000000".to_string(),
+                author: "Test".to_string(),
+                author_email: "test@example.com".to_string(),
+                date: Local::now() - Duration::days(days_ago),
+                summary: name.to_string(),
+                message: name.to_string(),
+                refs: vec![],
+            },
+            ahead,
+            behind: 0,
+            merge_base: None,
+            remote: None,
+        }
+    }
+
+    fn names(refs: &[RefEntry]) -> Vec<&str> {
+        refs.iter().map(|r| r.info.shorthand.as_str()).collect()
+    }
+
+    #[test]
+    fn test_sort_cycle_covers_fields_and_directions() {
+        let mut sort = RefSort::default();
+        let mut seen = vec![sort];
+        for _ in 0..5 {
+            sort = sort.next();
+            assert!(!seen.contains(&sort));
+            seen.push(sort);
+        }
+        assert_eq!(sort.next(), RefSort::default());
+        assert_eq!(RefSort::default().label(&Symbols::UNICODE), "name ↑");
+        assert_eq!(RefSort::default().label(&Symbols::ASCII), "name ^");
+    }
+
+    #[test]
+    fn test_sort_within_kind() {
+        let mut refs = vec![
+            entry("v1", RefKind::Tag, 30, 0),
+            entry("b-old", RefKind::LocalBranch, 10, 5),
+            entry("a-new", RefKind::LocalBranch, 1, 2),
+            entry("origin/main", RefKind::RemoteBranch, 3, 0),
+        ];
+
+        RefSort::default().apply(&mut refs);
+        assert_eq!(names(&refs), ["a-new", "b-old", "origin/main", "v1"]);
+
+        let by_date = RefSort {
+            field: SortField::Date,
+            descending: false,
+        };
+        by_date.apply(&mut refs);
+        assert_eq!(names(&refs), ["b-old", "a-new", "origin/main", "v1"]);
+
+        let by_ahead = RefSort {
+            field: SortField::Ahead,
+            descending: true,
+        };
+        by_ahead.apply(&mut refs);
+        assert_eq!(names(&refs), ["b-old", "a-new", "origin/main", "v1"]);
+    }
+
+    #[tokio::test]
+    async fn test_remote_branches_grouped_and_folded() {
+        let (_temp_dir, repo) = crate::testing::RepoBuilder::new()
+            .commit("Initial")
+            .open()
+            .await;
+        let mut view = RefsView::new(
+            repo,
+            ColorScheme::from_config(&crate::config::Config::default().colors),
+        );
+        let remote_branch = |name: &str, remote: &str| RefEntry {
+            remote: Some(remote.to_string()),
+            ..entry(name, RefKind::RemoteBranch, 1, 0)
+        };
+        view.refs = vec![
+            entry("main", RefKind::LocalBranch, 1, 0),
+            remote_branch("upstream/main", "upstream"),
+            remote_branch("origin/main", "origin"),
+            remote_branch("origin/dev", "origin"),
+            entry("v1", RefKind::Tag, 1, 0),
+        ];
+        view.remotes = ["fork", "origin", "upstream"]
+            .map(|name| RemoteInfo {
+                name: name.to_string(),
+                url: format!("/srv/{}.git", name),
+                fetch_refspecs: vec![format!("+refs/heads/*:refs/remotes/{}/*", name)],
+            })
+            .to_vec();
+        view.set_sort(RefSort::default());
+
+        let lines = |view: &RefsView| -> Vec<String> {
+            view.rows
+                .iter()
+                .map(|row| match row {
+                    Row::Ref(i) => view.refs[*i].info.shorthand.clone(),
+                    Row::Remote(name) => format!("[{}]", name),
+                })
+                .collect()
+        };
+        assert_eq!(
+            lines(&view),
+            [
+                "main",
+                "[fork]",
+                "[origin]",
+                "origin/dev",
+                "origin/main",
+                "[upstream]",
+                "upstream/main",
+                "v1"
+            ]
+        );
+
+        // Fold origin from one of its branches, then fetch it from the heading
+        view.list_state.select(Some(4));
+        view.handle_key(KeyEvent::from(KeyCode::Char(' '))).unwrap();
+        assert_eq!(
+            lines(&view),
+            [
+                "main",
+                "[fork]",
+                "[origin]",
+                "[upstream]",
+                "upstream/main",
+                "v1"
+            ]
+        );
+        assert_eq!(view.list_state.selected(), Some(2));
+        let action = view.handle_key(KeyEvent::from(KeyCode::Char('f'))).unwrap();
+        assert_eq!(action, Action::Fetch("origin".to_string()));
+
+        view.handle_key(KeyEvent::from(KeyCode::Enter)).unwrap();
+        assert_eq!(lines(&view).len(), 8);
+        view.list_state.select(Some(0));
+        assert!(view.handle_key(KeyEvent::from(KeyCode::Char('f'))).is_err());
+
+        // Merging takes a ref, not a remote heading
+        let action = view.handle_key(KeyEvent::from(KeyCode::Char('m'))).unwrap();
+        assert_eq!(action, Action::Merge("refs/x/main".to_string()));
+        view.list_state.select(Some(1));
+        assert!(view.handle_key(KeyEvent::from(KeyCode::Char('m'))).is_err());
+    }
+}
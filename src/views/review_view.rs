@@ -0,0 +1,299 @@
+use super::popup::centered_rect;
+use super::scrollbar::render_scrollbar;
+use super::view::{Action, Selection, View, ViewType};
+use crate::config::{ColorScheme, ReviewNote, SharedReviewNotes, Symbols};
+use crate::error::{AppError, AppResult};
+use crate::git::Repository;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use git2::Oid;
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Clear, List, ListItem, ListState, Paragraph},
+};
+
+/// The review notes written on diffs, to go back to, delete, or copy as
+/// Markdown for a review
+pub struct ReviewView {
+    repo: Repository,
+    notes: SharedReviewNotes,
+    list_state: ListState,
+    /// Asking whether to delete the selected note
+    confirm_delete: bool,
+    /// Set once a note was deleted, so the notes are saved
+    notes_changed: bool,
+    colors: ColorScheme,
+    symbols: Symbols,
+}
+
+impl ReviewView {
+    /// Create a review view of `notes`
+    pub fn new(repo: Repository, notes: SharedReviewNotes, colors: ColorScheme) -> Self {
+        let mut list_state = ListState::default();
+        list_state.select(Some(0));
+
+        Self {
+            repo,
+            notes,
+            list_state,
+            confirm_delete: false,
+            notes_changed: false,
+            colors,
+            symbols: Symbols::default(),
+        }
+    }
+
+    /// Set the glyphs used for borders and the scrollbar
+    pub fn with_symbols(mut self, symbols: Symbols) -> Self {
+        self.symbols = symbols;
+        self
+    }
+
+    fn len(&self) -> usize {
+        self.notes.borrow().notes.len()
+    }
+
+    fn selected_note(&self) -> Option<ReviewNote> {
+        self.notes
+            .borrow()
+            .notes
+            .get(self.list_state.selected()?)
+            .cloned()
+    }
+
+    /// Move selection down by `amount`, stopping at the last note
+    fn select_down(&mut self, amount: usize) {
+        let last = self.len().saturating_sub(1);
+        let i = self
+            .list_state
+            .selected()
+            .map_or(0, |i| (i + amount).min(last));
+        self.list_state.select(Some(i));
+    }
+
+    /// Move selection up by `amount`, stopping at the first note
+    fn select_up(&mut self, amount: usize) {
+        let i = self
+            .list_state
+            .selected()
+            .map_or(0, |i| i.saturating_sub(amount));
+        self.list_state.select(Some(i));
+    }
+
+    /// Open the diff the selected note is on
+    fn open_diff(&self) -> AppResult<Action> {
+        let Some(note) = self.selected_note() else {
+            return Ok(Action::None);
+        };
+        let parse = |id: &str| {
+            Oid::from_str(id).map_err(|_| AppError::recoverable(format!("Not a commit: {}", id)))
+        };
+        Ok(match note.commit.split_once("..") {
+            Some((from, to)) => Action::OpenRangeDiff {
+                repo: self.repo.clone(),
+                from: parse(from)?,
+                to: parse(to)?,
+            },
+            None => Action::OpenDiff {
+                repo: self.repo.clone(),
+                commit_id: parse(&note.commit)?,
+                summary: note.summary.clone(),
+            },
+        })
+    }
+
+    /// Delete the selected note
+    fn delete_selected(&mut self) {
+        let Some(note) = self.selected_note() else {
+            return;
+        };
+        self.notes.borrow_mut().set(ReviewNote {
+            text: String::new(),
+            ..note
+        });
+        self.notes_changed = true;
+        self.select_down(0);
+    }
+
+    fn create_list_item(&self, note: &ReviewNote) -> ListItem<'static> {
+        let place = match &note.hunk {
+            Some(hunk) => format!("{} {}", note.path, hunk),
+            None => note.path.clone(),
+        };
+        ListItem::new(Line::from(vec![
+            Span::styled(
+                format!("{} ", note.short_commit()),
+                Style::default().fg(self.colors.commit_hash),
+            ),
+            Span::styled(format!("{} ", place), Style::default().fg(self.colors.date)),
+            Span::raw(note.text.lines().next().unwrap_or_default().to_string()),
+        ]))
+    }
+}
+
+impl View for ReviewView {
+    fn handle_key(&mut self, key: KeyEvent) -> AppResult<Action> {
+        self.handle_key_with_count(key, 1)
+    }
+
+    fn handle_key_with_count(&mut self, key: KeyEvent, count: usize) -> AppResult<Action> {
+        if self.confirm_delete {
+            self.confirm_delete = false;
+            if key.code == KeyCode::Char('y') {
+                self.delete_selected();
+            }
+            return Ok(Action::None);
+        }
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(Action::PopView),
+            KeyCode::Char('j') | KeyCode::Down => self.select_down(count),
+            KeyCode::Char('k') | KeyCode::Up => self.select_up(count),
+            KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.select_down(10 * count)
+            }
+            KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.select_up(10 * count)
+            }
+            KeyCode::PageDown => self.select_down(20 * count),
+            KeyCode::PageUp => self.select_up(20 * count),
+            KeyCode::Char('g') => self.list_state.select(Some(0)),
+            KeyCode::Char('G') => self.list_state.select(Some(self.len().saturating_sub(1))),
+            KeyCode::Char('d') => self.confirm_delete = self.selected_note().is_some(),
+            KeyCode::Char('y') => {
+                if self.len() == 0 {
+                    return Err(AppError::recoverable("There are no review notes to copy"));
+                }
+                return Ok(Action::Copy(self.notes.borrow().to_markdown()));
+            }
+            KeyCode::Enter => return self.open_diff(),
+            KeyCode::Char('?') => return Ok(Action::ShowHelp(ViewType::Review)),
+            _ => {}
+        }
+        Ok(Action::None)
+    }
+
+    fn update(&mut self) -> AppResult<()> {
+        // Diff views add and change notes while this one is in the stack
+        self.select_down(0);
+        Ok(())
+    }
+
+    fn poll_action(&mut self) -> Action {
+        match std::mem::take(&mut self.notes_changed) {
+            true => Action::SaveReviewNotes,
+            false => Action::None,
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame, area: Rect) {
+        let notes = self.notes.borrow();
+        let title = format!("Review Notes - {} notes", notes.notes.len());
+        let items: Vec<ListItem> = match notes.notes.is_empty() {
+            true => vec![ListItem::new(
+                "No notes yet; a in a commit's diff writes one on its file or hunk",
+            )],
+            false => notes
+                .notes
+                .iter()
+                .map(|note| self.create_list_item(note))
+                .collect(),
+        };
+        let len = notes.notes.len();
+        drop(notes);
+
+        let list = List::new(items)
+            .block(self.symbols.block().title(title))
+            .highlight_style(self.colors.selected.add_modifier(Modifier::BOLD))
+            .highlight_symbol("> ");
+        frame.render_stateful_widget(list, area, &mut self.list_state);
+        let selected = self.list_state.selected().unwrap_or(0);
+        render_scrollbar(frame, area, &self.symbols, len, selected);
+
+        if self.confirm_delete
+            && let Some(note) = self.selected_note()
+        {
+            let popup = centered_rect(52, 3, area);
+            frame.render_widget(Clear, popup);
+            frame.render_widget(
+                Paragraph::new(format!("Delete the note on {}? (y/n)", note.path))
+                    .block(self.symbols.block().title("Delete note"))
+                    .style(Style::default().fg(self.colors.deleted)),
+                popup,
+            );
+        }
+    }
+
+    fn title(&self) -> &str {
+        "Review Notes"
+    }
+
+    fn view_type(&self) -> ViewType {
+        ViewType::Review
+    }
+
+    fn selection(&self) -> Selection {
+        let note = self.selected_note();
+        Selection {
+            commit: note
+                .as_ref()
+                .and_then(|note| Oid::from_str(&note.commit).ok()),
+            file: note.map(|note| note.path),
+            ..Selection::default()
+        }
+    }
+
+    fn captures_input(&self) -> bool {
+        self.confirm_delete
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Config, ReviewNotes};
+    use crate::testing::RepoBuilder;
+
+    #[tokio::test]
+    async fn test_opens_copies_and_deletes_notes() {
+        let builder = RepoBuilder::new().file("a.txt", "a\n").commit("Initial");
+        let head = builder.head();
+        let (_dir, repo) = builder.open().await;
+        let notes = ReviewNotes::default().shared();
+        for (path, text) in [("a.txt", "Why a?"), ("b.txt", "Needs a test")] {
+            notes.borrow_mut().set(ReviewNote {
+                commit: head.to_string(),
+                summary: "Initial".to_string(),
+                path: path.to_string(),
+                hunk: None,
+                text: text.to_string(),
+            });
+        }
+        let mut view = ReviewView::new(
+            repo,
+            notes.clone(),
+            ColorScheme::from_config(&Config::default().colors),
+        );
+
+        let action = view.handle_key(KeyEvent::from(KeyCode::Enter)).unwrap();
+        assert!(matches!(action, Action::OpenDiff { commit_id, .. } if commit_id == head));
+        let Action::Copy(markdown) = view.handle_key(KeyEvent::from(KeyCode::Char('y'))).unwrap()
+        else {
+            panic!("the notes were not copied");
+        };
+        assert!(
+            markdown.contains("### `b.txt`\n\nNeeds a test\n"),
+            "{}",
+            markdown
+        );
+
+        view.handle_key(KeyEvent::from(KeyCode::Char('G'))).unwrap();
+        view.handle_key(KeyEvent::from(KeyCode::Char('d'))).unwrap();
+        assert!(view.captures_input());
+        view.handle_key(KeyEvent::from(KeyCode::Char('y'))).unwrap();
+        assert_eq!(view.poll_action(), Action::SaveReviewNotes);
+        assert_eq!(notes.borrow().notes.len(), 1);
+        assert_eq!(view.list_state.selected(), Some(0));
+    }
+}
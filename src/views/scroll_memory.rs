@@ -0,0 +1,73 @@
+use git2::Oid;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+/// Commits whose scroll position is remembered
+pub const SCROLL_MEMORY_SIZE: usize = 64;
+
+/// Where each recently viewed commit's diff was scrolled to, so flipping
+/// back to a commit picks up where it was left
+#[derive(Debug, Clone)]
+pub struct ScrollMemory {
+    /// Most recently used first
+    offsets: VecDeque<(Oid, usize)>,
+    capacity: usize,
+}
+
+/// A scroll memory the diff views opened in a session share
+pub type SharedScrollMemory = Rc<RefCell<ScrollMemory>>;
+
+impl ScrollMemory {
+    /// Create a memory that forgets the least recently used commit past
+    /// `capacity`
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            offsets: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Create a memory to share between diff views
+    pub fn shared() -> SharedScrollMemory {
+        Rc::new(RefCell::new(Self::new(SCROLL_MEMORY_SIZE)))
+    }
+
+    /// Get where a commit's diff was scrolled to, marking it recently used
+    pub fn recall(&mut self, id: Oid) -> Option<usize> {
+        let index = self.offsets.iter().position(|(oid, _)| *oid == id)?;
+        let entry = self.offsets.remove(index)?;
+        self.offsets.push_front(entry);
+        Some(entry.1)
+    }
+
+    /// Remember where a commit's diff is scrolled to
+    pub fn remember(&mut self, id: Oid, offset: usize) {
+        self.offsets.retain(|(oid, _)| *oid != id);
+        self.offsets.push_front((id, offset));
+        self.offsets.truncate(self.capacity);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_forgets_least_recently_used() {
+        let [a, b, c] = [1u8, 2, 3].map(|byte| Oid::from_bytes(&[byte; 20]).unwrap());
+        let mut memory = ScrollMemory::new(2);
+        memory.remember(a, 10);
+        memory.remember(b, 20);
+        assert_eq!(memory.recall(a), Some(10));
+
+        // b was used least recently, so it goes first
+        memory.remember(c, 30);
+        assert_eq!(memory.recall(b), None);
+        assert_eq!(memory.recall(a), Some(10));
+
+        memory.remember(a, 15);
+        assert_eq!(memory.recall(a), Some(15));
+        assert_eq!(memory.recall(c), Some(30));
+    }
+}
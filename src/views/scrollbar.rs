@@ -0,0 +1,83 @@
+use crate::config::Symbols;
+use ratatui::{
+    Frame,
+    layout::{Margin, Rect},
+    widgets::ScrollbarState,
+};
+
+/// Compute the scrollbar state for `content_length` rows shown `visible` at a time
+///
+/// Returns `None` when everything fits, so no scrollbar is needed.
+pub fn scrollbar_state(
+    content_length: usize,
+    position: usize,
+    visible: usize,
+) -> Option<ScrollbarState> {
+    if content_length <= visible {
+        return None;
+    }
+    Some(
+        ScrollbarState::new(content_length)
+            .position(position.min(content_length - 1))
+            .viewport_content_length(visible),
+    )
+}
+
+/// Draw a vertical scrollbar on the right border of a bordered `area`
+///
+/// `position` is the scroll offset for scrolling views, or the selected row
+/// for lists so the thumb follows the selection.
+pub fn render_scrollbar(
+    frame: &mut Frame,
+    area: Rect,
+    symbols: &Symbols,
+    content_length: usize,
+    position: usize,
+) {
+    let inner = area.inner(Margin {
+        vertical: 1,
+        horizontal: 0,
+    });
+    if let Some(mut state) = scrollbar_state(content_length, position, inner.height as usize) {
+        frame.render_stateful_widget(symbols.scrollbar(), inner, &mut state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::{Terminal, backend::TestBackend};
+
+    /// Render a scrollbar into a 3x`height` bordered area and return its column
+    fn render(height: u16, content_length: usize, position: usize) -> Vec<String> {
+        let mut terminal = Terminal::new(TestBackend::new(3, height)).unwrap();
+        terminal
+            .draw(|frame| {
+                let area = frame.area();
+                render_scrollbar(frame, area, &Symbols::ASCII, content_length, position);
+            })
+            .unwrap();
+        let buffer = terminal.backend().buffer();
+        (0..height)
+            .map(|y| buffer[(2, y)].symbol().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_no_scrollbar_when_content_fits() {
+        assert!(scrollbar_state(5, 0, 5).is_none());
+        assert!(render(7, 5, 0).iter().all(|s| s == " "));
+    }
+
+    #[test]
+    fn test_scrollbar_follows_position() {
+        let top = render(12, 100, 0);
+        assert_eq!(top[1], "^");
+        assert_eq!(top[2], "#");
+        assert_eq!(top[10], "v");
+
+        let bottom = render(12, 100, 99);
+        assert_eq!(bottom[2], "|");
+        assert_eq!(bottom[9], "#");
+    }
+}
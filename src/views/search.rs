@@ -0,0 +1,102 @@
+use ratatui::{style::Style, text::Span};
+use std::ops::Range;
+
+/// Find the byte ranges of all non-overlapping occurrences of `query` in `text`
+pub fn match_ranges(text: &str, query: &str, case_sensitive: bool) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    if query.is_empty() {
+        return ranges;
+    }
+
+    let mut start = 0;
+    while start < text.len() {
+        match match_len_at(&text[start..], query, case_sensitive) {
+            Some(len) => {
+                ranges.push(start..start + len);
+                start += len;
+            }
+            None => {
+                start += text[start..].chars().next().map_or(1, |c| c.len_utf8());
+            }
+        }
+    }
+
+    ranges
+}
+
+/// Check whether `query` occurs anywhere in `text`
+pub fn matches(text: &str, query: &str, case_sensitive: bool) -> bool {
+    if case_sensitive {
+        return text.contains(query);
+    }
+    !match_ranges(text, query, false).is_empty()
+}
+
+/// Length in bytes of the match of `query` at the start of `text`, if any
+fn match_len_at(text: &str, query: &str, case_sensitive: bool) -> Option<usize> {
+    if case_sensitive {
+        return text.starts_with(query).then_some(query.len());
+    }
+
+    let mut text_chars = text.char_indices();
+    for q in query.chars() {
+        let (_, t) = text_chars.next()?;
+        if !t.to_lowercase().eq(q.to_lowercase()) {
+            return None;
+        }
+    }
+    Some(text_chars.next().map_or(text.len(), |(i, _)| i))
+}
+
+/// Split `text` into spans, styling the given ranges with `highlight`
+pub fn highlight_spans<'a>(
+    text: &'a str,
+    ranges: &[Range<usize>],
+    base: Style,
+    highlight: Style,
+) -> Vec<Span<'a>> {
+    let mut spans = Vec::new();
+    let mut pos = 0;
+
+    for range in ranges {
+        if range.start > pos {
+            spans.push(Span::styled(&text[pos..range.start], base));
+        }
+        spans.push(Span::styled(&text[range.clone()], base.patch(highlight)));
+        pos = range.end;
+    }
+    if pos < text.len() || spans.is_empty() {
+        spans.push(Span::styled(&text[pos..], base));
+    }
+
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::style::Color;
+
+    #[test]
+    fn test_match_ranges_case_insensitive() {
+        assert_eq!(match_ranges("Fix the fix", "fix", false), vec![0..3, 8..11]);
+        assert_eq!(match_ranges("Fix the fix", "fix", true), vec![8..11]);
+        assert!(match_ranges("Fix", "", false).is_empty());
+    }
+
+    #[test]
+    fn test_match_ranges_non_ascii() {
+        assert_eq!(match_ranges("Ärger über", "ü", false), vec![7..9]);
+        assert!(matches("ÄRGER", "ärg", false));
+    }
+
+    #[test]
+    fn test_highlight_spans() {
+        let highlight = Style::default().bg(Color::Yellow);
+        let ranges = match_ranges("add fix here", "fix", true);
+        let spans = highlight_spans("add fix here", &ranges, Style::default(), highlight);
+        let texts: Vec<&str> = spans.iter().map(|s| s.content.as_ref()).collect();
+        assert_eq!(texts, vec!["add ", "fix", " here"]);
+        assert_eq!(spans[1].style.bg, Some(Color::Yellow));
+    }
+}
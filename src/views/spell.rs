@@ -0,0 +1,116 @@
+use std::collections::HashSet;
+use std::fs;
+use std::ops::Range;
+use std::path::Path;
+
+/// Word lists tried in order when no dictionary is configured
+const SYSTEM_DICTIONARIES: [&str; 4] = [
+    "/usr/share/hunspell/en_US.dic",
+    "/usr/share/myspell/en_US.dic",
+    "/usr/share/dict/american-english",
+    "/usr/share/dict/words",
+];
+
+/// Words common in commit messages that general dictionaries lack
+const BUNDLED_WORDS: &str = include_str!("spell_words.txt");
+
+/// Finds misspelled words in commit messages against a word list
+#[derive(Debug, Clone, Default)]
+pub struct SpellChecker {
+    words: HashSet<String>,
+}
+
+impl SpellChecker {
+    /// Build a checker from a word list with one word per line, such as
+    /// `/usr/share/dict/words` or a hunspell `.dic` file
+    pub fn from_words(text: &str) -> Self {
+        let mut checker = Self::default();
+        checker.add_words(text);
+        checker.add_words(BUNDLED_WORDS);
+        checker
+    }
+
+    /// Load the word list at `path`, or the first system dictionary found
+    /// without one; None when there is none to load
+    pub fn load(path: Option<&Path>) -> Option<Self> {
+        let text = match path {
+            Some(path) => fs::read_to_string(path).ok()?,
+            None => SYSTEM_DICTIONARIES
+                .iter()
+                .find_map(|path| fs::read_to_string(path).ok())?,
+        };
+        Some(Self::from_words(&text))
+    }
+
+    fn add_words(&mut self, text: &str) {
+        for line in text.lines() {
+            // Hunspell lines carry affix flags after a slash, and the first
+            // line is a word count
+            let word = line.split('/').next().unwrap_or_default().trim();
+            if !word.is_empty() && !word.starts_with('#') {
+                self.words.insert(word.to_lowercase());
+            }
+        }
+    }
+
+    /// Check a word, letting through what can't be judged: acronyms,
+    /// identifiers, and words with digits
+    fn is_misspelled(&self, word: &str) -> bool {
+        let word = word.strip_suffix("'s").unwrap_or(word).trim_matches('\'');
+        let checkable = word.chars().count() > 1
+            && word.chars().all(|c| c.is_alphabetic() || c == '\'')
+            && !word.chars().skip(1).any(char::is_uppercase);
+        checkable && !self.words.contains(&word.to_lowercase())
+    }
+
+    /// Get the byte ranges of the misspelled words in `text`
+    ///
+    /// Words joined to code-like characters, as in `views/spell.rs` or
+    /// `--stdin`, are left alone.
+    pub fn misspelled_ranges(&self, text: &str) -> Vec<Range<usize>> {
+        let mut ranges = Vec::new();
+        let mut offset = 0;
+        for token in text.split_whitespace() {
+            let start = offset + text[offset..].find(token).unwrap_or(0);
+            offset = start + token.len();
+
+            let word = token.trim_matches(|c: char| c.is_ascii_punctuation() && c != '\'');
+            let code_like = word.contains(|c: char| !c.is_alphanumeric() && c != '\'')
+                || token.starts_with(['`', '-', '/', '.']);
+            if !word.is_empty() && !code_like && self.is_misspelled(word) {
+                let word_start = start + token.find(word).unwrap_or(0);
+                ranges.push(word_start..word_start + word.len());
+            }
+        }
+        ranges
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_misspelled_ranges() {
+        let checker = SpellChecker::from_words("3\nfix/S\nthe\nparser\n");
+        let text = "Fix teh parser's handling (of `HashMap`) in src/lib.rs, v2 and HTTP";
+        let words: Vec<&str> = checker
+            .misspelled_ranges(text)
+            .into_iter()
+            .map(|range| &text[range])
+            .collect();
+        assert_eq!(words, ["teh", "handling", "of", "in", "and"]);
+    }
+
+    #[test]
+    fn test_bundled_words_are_known() {
+        let checker = SpellChecker::from_words("");
+        let text = "Refactor the config";
+        let words: Vec<&str> = checker
+            .misspelled_ranges(text)
+            .into_iter()
+            .map(|r| &text[r])
+            .collect();
+        assert_eq!(words, ["the"]);
+    }
+}
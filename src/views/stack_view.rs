@@ -0,0 +1,411 @@
+use super::scrollbar::render_scrollbar;
+use super::view::{Action, Selection, View, ViewType};
+use crate::config::{ColorScheme, Symbols};
+use crate::error::{AppError, AppResult};
+use crate::git::{Repository, Result as GitResult, Stack, StackBranch};
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{List, ListItem, ListState},
+};
+use tokio::sync::mpsc;
+
+/// Branches a restack moved, each with the number of commits it replayed
+type Restacked = GitResult<Vec<(String, usize)>>;
+
+/// A line of the stack
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Row {
+    /// Index into the stack's branches
+    Branch(usize),
+    /// A branch's own commit, by branch and commit index
+    Commit(usize, usize),
+}
+
+/// Stacks of dependent branches on the trunk, each with the commits of its
+/// own, and restacking after a parent moves
+pub struct StackView {
+    repo: Repository,
+    stack: Option<Stack>,
+    rows: Vec<Row>,
+    list_state: ListState,
+    loading: bool,
+    error: Option<String>,
+    receiver: Option<mpsc::UnboundedReceiver<GitResult<Stack>>>,
+    restack_receiver: Option<mpsc::UnboundedReceiver<Restacked>>,
+    /// What the last restack moved, shown in the title
+    notice: Option<String>,
+    /// Set once a restack moved branches, so every view reloads
+    history_changed: bool,
+    colors: ColorScheme,
+    symbols: Symbols,
+}
+
+impl StackView {
+    /// Create a new stack view
+    pub fn new(repo: Repository, colors: ColorScheme) -> Self {
+        let mut list_state = ListState::default();
+        list_state.select(Some(0));
+
+        Self {
+            repo,
+            stack: None,
+            rows: Vec::new(),
+            list_state,
+            loading: false,
+            error: None,
+            receiver: None,
+            restack_receiver: None,
+            notice: None,
+            history_changed: false,
+            colors,
+            symbols: Symbols::default(),
+        }
+    }
+
+    /// Set the glyphs used for borders and the scrollbar
+    pub fn with_symbols(mut self, symbols: Symbols) -> Self {
+        self.symbols = symbols;
+        self
+    }
+
+    /// Start loading the stack asynchronously
+    pub fn start_loading(&mut self) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.receiver = Some(rx);
+        self.loading = true;
+
+        let repo = self.repo.clone();
+        tokio::spawn(async move {
+            let _ = tx.send(crate::git::stack::load_stack(&repo).await);
+        });
+    }
+
+    /// Restack the selected branch and the branches stacked on it
+    fn start_restack(&mut self) -> AppResult<()> {
+        let Some(branch) = self.selected_branch() else {
+            return Err(AppError::recoverable("Select a branch to restack"));
+        };
+        let name = branch.name.clone();
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.restack_receiver = Some(rx);
+
+        let repo = self.repo.clone();
+        tokio::spawn(async move {
+            let _ = tx.send(crate::git::stack::restack(&repo, name).await);
+        });
+        Ok(())
+    }
+
+    /// Get the branch of the selected line
+    fn selected_branch(&self) -> Option<&StackBranch> {
+        let stack = self.stack.as_ref()?;
+        match self.rows.get(self.list_state.selected()?)? {
+            Row::Branch(i) | Row::Commit(i, _) => stack.branches.get(*i),
+        }
+    }
+
+    /// Lay out a line per branch followed by a line per commit of its own
+    fn rebuild_rows(&mut self) {
+        self.rows.clear();
+        if let Some(stack) = &self.stack {
+            for (i, branch) in stack.branches.iter().enumerate() {
+                self.rows.push(Row::Branch(i));
+                self.rows
+                    .extend((0..branch.commits.len()).map(|j| Row::Commit(i, j)));
+            }
+        }
+        let last = self.rows.len().saturating_sub(1);
+        let selected = self.list_state.selected().unwrap_or(0).min(last);
+        self.list_state.select(Some(selected));
+    }
+
+    /// Move selection down by `amount`, stopping at the last line
+    fn select_down(&mut self, amount: usize) {
+        let last = self.rows.len().saturating_sub(1);
+        let i = self
+            .list_state
+            .selected()
+            .map_or(0, |i| (i + amount).min(last));
+        self.list_state.select(Some(i));
+    }
+
+    /// Move selection up by `amount`, stopping at the first line
+    fn select_up(&mut self, amount: usize) {
+        let i = self
+            .list_state
+            .selected()
+            .map_or(0, |i| i.saturating_sub(amount));
+        self.list_state.select(Some(i));
+    }
+
+    /// Create the list item for a line, indenting each branch by its depth
+    fn create_list_item(&self, stack: &Stack, row: Row) -> ListItem<'static> {
+        match row {
+            Row::Branch(i) => {
+                let branch = &stack.branches[i];
+                let indent = "  ".repeat(branch.depth - 1);
+                let head = if branch.is_head { "* " } else { "" };
+                let mut spans = vec![
+                    Span::raw(indent),
+                    Span::styled(
+                        format!("{}{}", head, branch.name),
+                        Style::default()
+                            .fg(self.colors.commit_hash)
+                            .add_modifier(Modifier::BOLD),
+                    ),
+                    Span::raw(format!(
+                        " on {} ({} commits)",
+                        branch.parent,
+                        branch.commits.len()
+                    )),
+                ];
+                if branch.needs_restack {
+                    spans.push(Span::styled(
+                        format!("  {} moved, needs restack", branch.parent),
+                        Style::default().fg(self.colors.modified),
+                    ));
+                }
+                ListItem::new(Line::from(spans))
+            }
+            Row::Commit(i, j) => {
+                let branch = &stack.branches[i];
+                let commit = &branch.commits[j];
+                ListItem::new(Line::from(vec![
+                    Span::raw("  ".repeat(branch.depth)),
+                    Span::styled(
+                        format!("{} ", commit.short_id),
+                        Style::default().fg(self.colors.date),
+                    ),
+                    Span::raw(commit.summary.clone()),
+                ]))
+            }
+        }
+    }
+}
+
+impl View for StackView {
+    fn handle_key(&mut self, key: KeyEvent) -> AppResult<Action> {
+        self.handle_key_with_count(key, 1)
+    }
+
+    fn handle_key_with_count(&mut self, key: KeyEvent, count: usize) -> AppResult<Action> {
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(Action::PopView),
+            KeyCode::Char('j') | KeyCode::Down => self.select_down(count),
+            KeyCode::Char('k') | KeyCode::Up => self.select_up(count),
+            KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.select_down(10 * count)
+            }
+            KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.select_up(10 * count)
+            }
+            KeyCode::PageDown => self.select_down(20 * count),
+            KeyCode::PageUp => self.select_up(20 * count),
+            KeyCode::Char('g') => self.list_state.select(Some(0)),
+            KeyCode::Char('G') => self
+                .list_state
+                .select(Some(self.rows.len().saturating_sub(1))),
+            KeyCode::Char('r') if self.restack_receiver.is_none() => self.start_restack()?,
+            KeyCode::Enter => {
+                let selected = self.list_state.selected().and_then(|i| self.rows.get(i));
+                let commit = match (selected, &self.stack) {
+                    (Some(Row::Branch(i)), Some(stack)) => stack.branches[*i].commits.first(),
+                    (Some(Row::Commit(i, j)), Some(stack)) => stack.branches[*i].commits.get(*j),
+                    _ => None,
+                };
+                if let Some(commit) = commit {
+                    return Ok(Action::OpenDiff {
+                        repo: self.repo.clone(),
+                        commit_id: commit.id,
+                        summary: commit.summary.clone(),
+                    });
+                }
+            }
+            KeyCode::Char('?') => return Ok(Action::ShowHelp(ViewType::Stack)),
+            _ => {}
+        }
+        Ok(Action::None)
+    }
+
+    fn update(&mut self) -> AppResult<()> {
+        if let Some(receiver) = &mut self.receiver
+            && let Ok(result) = receiver.try_recv()
+        {
+            self.loading = false;
+            self.receiver = None;
+            match result {
+                Ok(stack) => {
+                    self.stack = Some(stack);
+                    self.rebuild_rows();
+                }
+                Err(e) => self.error = Some(format!("Failed to load the branch stack: {}", e)),
+            }
+        }
+
+        if let Some(receiver) = &mut self.restack_receiver
+            && let Ok(result) = receiver.try_recv()
+        {
+            self.restack_receiver = None;
+            let moved = result?;
+            if moved.is_empty() {
+                return Err(AppError::recoverable(
+                    "Nothing to restack; the branches are on their parents",
+                ));
+            }
+            let moved: Vec<String> = moved
+                .iter()
+                .map(|(name, count)| format!("{} ({})", name, count))
+                .collect();
+            self.notice = Some(format!("restacked {}", moved.join(", ")));
+            self.history_changed = true;
+        }
+        Ok(())
+    }
+
+    fn poll_action(&mut self) -> Action {
+        match std::mem::take(&mut self.history_changed) {
+            true => Action::Refresh,
+            false => Action::None,
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame, area: Rect) {
+        if let Some(error) = &self.error {
+            let list = List::new(vec![ListItem::new(error.clone())])
+                .block(self.symbols.block().title("Branch Stack - Error"))
+                .style(Style::default().fg(self.colors.deleted));
+            frame.render_widget(list, area);
+            return;
+        }
+
+        let Some(stack) = &self.stack else {
+            let list = List::new(vec![ListItem::new("Loading branch stack...")])
+                .block(self.symbols.block().title("Branch Stack"));
+            frame.render_widget(list, area);
+            return;
+        };
+
+        let mut title = format!(
+            "Branch Stack on {} - {} branches",
+            stack.trunk,
+            stack.branches.len()
+        );
+        let behind = stack.branches.iter().filter(|b| b.needs_restack).count();
+        if behind > 0 {
+            title.push_str(&format!(" [{} need restack]", behind));
+        }
+        if let Some(notice) = &self.notice {
+            title.push_str(&format!(" [{}]", notice));
+        }
+
+        let items: Vec<ListItem> = match stack.branches.is_empty() {
+            true => vec![ListItem::new(format!(
+                "No branches stacked on {}",
+                stack.trunk
+            ))],
+            false => self
+                .rows
+                .iter()
+                .map(|row| self.create_list_item(stack, *row))
+                .collect(),
+        };
+        let list = List::new(items)
+            .block(self.symbols.block().title(title))
+            .highlight_style(self.colors.selected.add_modifier(Modifier::BOLD))
+            .highlight_symbol("> ");
+
+        frame.render_stateful_widget(list, area, &mut self.list_state);
+        let selected = self.list_state.selected().unwrap_or(0);
+        render_scrollbar(frame, area, &self.symbols, self.rows.len(), selected);
+    }
+
+    fn title(&self) -> &str {
+        "Branch Stack"
+    }
+
+    fn view_type(&self) -> ViewType {
+        ViewType::Stack
+    }
+
+    fn selection(&self) -> Selection {
+        let branch = self.selected_branch();
+        Selection {
+            commit: branch.map(|b| b.tip),
+            refname: branch.map(|b| b.name.clone()),
+            file: None,
+        }
+    }
+
+    fn refresh(&mut self) {
+        self.error = None;
+        self.start_loading();
+    }
+
+    fn busy(&self) -> Option<&'static str> {
+        self.restack_receiver.as_ref().map(|_| "restacking")
+    }
+
+    fn on_activate(&mut self) -> Result<()> {
+        if self.stack.is_none() && !self.loading {
+            self.start_loading();
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::testing::RepoBuilder;
+
+    #[tokio::test]
+    async fn test_lists_branches_with_their_commits_and_restacks() {
+        let builder = RepoBuilder::new().commit("Base");
+        let trunk = builder
+            .git2()
+            .head()
+            .unwrap()
+            .shorthand()
+            .unwrap()
+            .to_string();
+        let (_temp_dir, repo) = builder
+            .branch("a")
+            .checkout("a")
+            .file("a.txt", "a\n")
+            .commit("A")
+            .checkout(&trunk)
+            .file("t.txt", "t\n")
+            .commit("Trunk")
+            .open()
+            .await;
+
+        let mut view = StackView::new(repo, ColorScheme::from_config(&Config::default().colors));
+        view.on_activate().unwrap();
+        while view.stack.is_none() {
+            tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+            view.update().unwrap();
+        }
+        assert_eq!(view.rows, [Row::Branch(0), Row::Commit(0, 0)]);
+        assert!(view.stack.as_ref().unwrap().branches[0].needs_restack);
+
+        view.list_state.select(Some(1));
+        assert!(matches!(
+            view.handle_key(KeyEvent::from(KeyCode::Enter)).unwrap(),
+            Action::OpenDiff { .. }
+        ));
+        view.handle_key(KeyEvent::from(KeyCode::Char('r'))).unwrap();
+        assert_eq!(view.busy(), Some("restacking"));
+        while view.restack_receiver.is_some() {
+            tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+            view.update().unwrap();
+        }
+        assert_eq!(view.notice.as_deref(), Some("restacked a (1)"));
+        assert_eq!(view.poll_action(), Action::Refresh);
+    }
+}
@@ -0,0 +1,462 @@
+use super::popup::centered_rect;
+use super::scrollbar::render_scrollbar;
+use super::view::{Action, Selection, View, ViewType};
+use crate::config::{ColorScheme, Symbols};
+use crate::error::{AppError, AppResult};
+use crate::git::{Repository, Result as GitResult, StashEntry};
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    Frame,
+    layout::{Constraint, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Clear, List, ListItem, ListState, Paragraph},
+};
+use tokio::sync::mpsc;
+
+/// A change to the stash
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StashOp {
+    /// Stash the uncommitted changes, untracked files too if set
+    Push {
+        include_untracked: bool,
+    },
+    Apply(usize),
+    Pop(usize),
+    Drop(usize),
+}
+
+impl StashOp {
+    /// Describe the finished operation for the title
+    fn done(self) -> String {
+        match self {
+            StashOp::Push { .. } => "stashed the changes".to_string(),
+            StashOp::Apply(index) => format!("applied stash@{{{}}}", index),
+            StashOp::Pop(index) => format!("popped stash@{{{}}}", index),
+            StashOp::Drop(index) => format!("dropped stash@{{{}}}", index),
+        }
+    }
+}
+
+/// The stash, with stashing the working tree and applying, popping and
+/// dropping entries
+pub struct StashView {
+    repo: Repository,
+    stashes: Vec<StashEntry>,
+    list_state: ListState,
+    loading: bool,
+    error: Option<String>,
+    receiver: Option<mpsc::UnboundedReceiver<GitResult<Vec<StashEntry>>>>,
+    /// The message being typed for a new stash, and whether it takes
+    /// untracked files too
+    message_prompt: Option<(String, bool)>,
+    /// Asking whether to drop the selected entry
+    confirm_drop: bool,
+    op_receiver: Option<(StashOp, mpsc::UnboundedReceiver<GitResult<()>>)>,
+    /// What the last operation did, shown in the title
+    notice: Option<String>,
+    /// Set once the working tree changed, so every view reloads
+    tree_changed: bool,
+    colors: ColorScheme,
+    symbols: Symbols,
+}
+
+impl StashView {
+    /// Create a new stash view
+    pub fn new(repo: Repository, colors: ColorScheme) -> Self {
+        let mut list_state = ListState::default();
+        list_state.select(Some(0));
+
+        Self {
+            repo,
+            stashes: Vec::new(),
+            list_state,
+            loading: false,
+            error: None,
+            receiver: None,
+            message_prompt: None,
+            confirm_drop: false,
+            op_receiver: None,
+            notice: None,
+            tree_changed: false,
+            colors,
+            symbols: Symbols::default(),
+        }
+    }
+
+    /// Set the glyphs used for borders and the scrollbar
+    pub fn with_symbols(mut self, symbols: Symbols) -> Self {
+        self.symbols = symbols;
+        self
+    }
+
+    /// Start loading the stash asynchronously
+    pub fn start_loading(&mut self) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.receiver = Some(rx);
+        self.loading = true;
+
+        let repo = self.repo.clone();
+        tokio::spawn(async move {
+            let _ = tx.send(crate::git::stash::stash_list(&repo).await);
+        });
+    }
+
+    fn selected_stash(&self) -> Option<&StashEntry> {
+        self.stashes.get(self.list_state.selected()?)
+    }
+
+    /// Run a change to the stash, unless one is running already
+    fn start_op(&mut self, op: StashOp, message: Option<String>) -> AppResult<()> {
+        if self.op_receiver.is_some() {
+            return Err(AppError::recoverable(
+                "Wait for the stash to finish changing",
+            ));
+        }
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.op_receiver = Some((op, rx));
+
+        let repo = self.repo.clone();
+        tokio::spawn(async move {
+            let result = match op {
+                StashOp::Push { include_untracked } => {
+                    crate::git::stash::stash_push(&repo, message, include_untracked)
+                        .await
+                        .map(|_| ())
+                }
+                StashOp::Apply(index) => crate::git::stash::stash_apply(&repo, index).await,
+                StashOp::Pop(index) => crate::git::stash::stash_pop(&repo, index).await,
+                StashOp::Drop(index) => crate::git::stash::stash_drop(&repo, index).await,
+            };
+            let _ = tx.send(result);
+        });
+        Ok(())
+    }
+
+    /// Run `op` on the selected entry
+    fn start_entry_op(&mut self, op: fn(usize) -> StashOp) -> AppResult<()> {
+        let Some(stash) = self.selected_stash() else {
+            return Err(AppError::recoverable("The stash is empty"));
+        };
+        self.start_op(op(stash.index), None)
+    }
+
+    /// Handle a key while a stash message is being typed
+    fn handle_prompt_key(&mut self, key: KeyEvent) -> AppResult<()> {
+        let Some((message, include_untracked)) = &mut self.message_prompt else {
+            return Ok(());
+        };
+        match key.code {
+            KeyCode::Esc => self.message_prompt = None,
+            KeyCode::Backspace => {
+                message.pop();
+            }
+            KeyCode::Char(c) => message.push(c),
+            KeyCode::Enter => {
+                let include_untracked = *include_untracked;
+                let message = self
+                    .message_prompt
+                    .take()
+                    .map(|(message, _)| message.trim().to_string());
+                // An empty message gets git's "WIP on <branch>" one
+                let message = message.filter(|message| !message.is_empty());
+                self.start_op(StashOp::Push { include_untracked }, message)?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Move selection down by `amount`, stopping at the last entry
+    fn select_down(&mut self, amount: usize) {
+        let last = self.stashes.len().saturating_sub(1);
+        let i = self
+            .list_state
+            .selected()
+            .map_or(0, |i| (i + amount).min(last));
+        self.list_state.select(Some(i));
+    }
+
+    /// Move selection up by `amount`, stopping at the first entry
+    fn select_up(&mut self, amount: usize) {
+        let i = self
+            .list_state
+            .selected()
+            .map_or(0, |i| i.saturating_sub(amount));
+        self.list_state.select(Some(i));
+    }
+
+    fn create_list_item(&self, stash: &StashEntry) -> ListItem<'static> {
+        ListItem::new(Line::from(vec![
+            Span::styled(
+                format!("{:<10} ", stash.name()),
+                Style::default().fg(self.colors.commit_hash),
+            ),
+            Span::styled(
+                format!("{} ", stash.commit.date_str()),
+                Style::default().fg(self.colors.date),
+            ),
+            Span::raw(stash.message.clone()),
+        ]))
+    }
+}
+
+impl View for StashView {
+    fn handle_key(&mut self, key: KeyEvent) -> AppResult<Action> {
+        self.handle_key_with_count(key, 1)
+    }
+
+    fn handle_key_with_count(&mut self, key: KeyEvent, count: usize) -> AppResult<Action> {
+        if self.message_prompt.is_some() {
+            self.handle_prompt_key(key)?;
+            return Ok(Action::None);
+        }
+        if self.confirm_drop {
+            self.confirm_drop = false;
+            if key.code == KeyCode::Char('y') {
+                self.start_entry_op(StashOp::Drop)?;
+            }
+            return Ok(Action::None);
+        }
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(Action::PopView),
+            KeyCode::Char('j') | KeyCode::Down => self.select_down(count),
+            KeyCode::Char('k') | KeyCode::Up => self.select_up(count),
+            KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.select_down(10 * count)
+            }
+            KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.select_up(10 * count)
+            }
+            KeyCode::PageDown => self.select_down(20 * count),
+            KeyCode::PageUp => self.select_up(20 * count),
+            KeyCode::Char('g') => self.list_state.select(Some(0)),
+            KeyCode::Char('G') => self
+                .list_state
+                .select(Some(self.stashes.len().saturating_sub(1))),
+            KeyCode::Char('s') => self.message_prompt = Some((String::new(), false)),
+            KeyCode::Char('S') => self.message_prompt = Some((String::new(), true)),
+            KeyCode::Char('a') => self.start_entry_op(StashOp::Apply)?,
+            KeyCode::Char('p') => self.start_entry_op(StashOp::Pop)?,
+            KeyCode::Char('d') => self.confirm_drop = self.selected_stash().is_some(),
+            KeyCode::Char('r') if !self.loading => self.refresh(),
+            KeyCode::Enter => {
+                if let Some(stash) = self.selected_stash() {
+                    return Ok(Action::OpenDiff {
+                        repo: self.repo.clone(),
+                        commit_id: stash.commit.id,
+                        summary: stash.message.clone(),
+                    });
+                }
+            }
+            KeyCode::Char('?') => return Ok(Action::ShowHelp(ViewType::Stash)),
+            _ => {}
+        }
+        Ok(Action::None)
+    }
+
+    fn update(&mut self) -> AppResult<()> {
+        if let Some(receiver) = &mut self.receiver
+            && let Ok(result) = receiver.try_recv()
+        {
+            self.loading = false;
+            self.receiver = None;
+            match result {
+                Ok(stashes) => {
+                    self.stashes = stashes;
+                    self.select_down(0);
+                }
+                Err(e) => self.error = Some(format!("Failed to load the stash: {}", e)),
+            }
+        }
+
+        if let Some((op, receiver)) = &mut self.op_receiver
+            && let Ok(result) = receiver.try_recv()
+        {
+            let op = *op;
+            self.op_receiver = None;
+            self.refresh();
+            // Only dropping leaves the working tree alone; a failed apply or
+            // pop may still have touched it
+            self.tree_changed = !matches!(op, StashOp::Drop(_));
+            match result {
+                Ok(()) => self.notice = Some(op.done()),
+                Err(e) => return Err(AppError::recoverable(format!("Stash failed: {}", e))),
+            }
+        }
+        Ok(())
+    }
+
+    fn poll_action(&mut self) -> Action {
+        match std::mem::take(&mut self.tree_changed) {
+            true => Action::Refresh,
+            false => Action::None,
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame, area: Rect) {
+        let (list_area, prompt_area) = match self.message_prompt.is_some() {
+            true => {
+                let [list, prompt] =
+                    Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(area);
+                (list, Some(prompt))
+            }
+            false => (area, None),
+        };
+
+        if let Some(error) = &self.error {
+            let list = List::new(vec![ListItem::new(error.clone())])
+                .block(self.symbols.block().title("Stash - Error"))
+                .style(Style::default().fg(self.colors.deleted));
+            frame.render_widget(list, list_area);
+        } else {
+            let mut title = format!("Stash - {} entries", self.stashes.len());
+            if self.loading {
+                title.push_str(" [loading...]");
+            }
+            if let Some(notice) = &self.notice {
+                title.push_str(&format!(" [{}]", notice));
+            }
+            let items: Vec<ListItem> = match (self.stashes.is_empty(), self.loading) {
+                (true, true) => vec![ListItem::new("Loading the stash...")],
+                (true, false) => vec![ListItem::new(
+                    "The stash is empty; s stashes the uncommitted changes",
+                )],
+                (false, _) => self
+                    .stashes
+                    .iter()
+                    .map(|stash| self.create_list_item(stash))
+                    .collect(),
+            };
+            let list = List::new(items)
+                .block(self.symbols.block().title(title))
+                .highlight_style(self.colors.selected.add_modifier(Modifier::BOLD))
+                .highlight_symbol("> ");
+            frame.render_stateful_widget(list, list_area, &mut self.list_state);
+            let selected = self.list_state.selected().unwrap_or(0);
+            render_scrollbar(
+                frame,
+                list_area,
+                &self.symbols,
+                self.stashes.len(),
+                selected,
+            );
+        }
+
+        if let (Some((message, include_untracked)), Some(prompt_area)) =
+            (&self.message_prompt, prompt_area)
+        {
+            let label = match include_untracked {
+                true => "Stash with untracked files, message",
+                false => "Stash message",
+            };
+            frame.render_widget(
+                Paragraph::new(format!("{}: {}_", label, message)),
+                prompt_area,
+            );
+        }
+
+        if self.confirm_drop
+            && let Some(stash) = self.selected_stash()
+        {
+            let popup = centered_rect(52, 3, area);
+            frame.render_widget(Clear, popup);
+            frame.render_widget(
+                Paragraph::new(format!("Drop {}? (y/n)", stash.name()))
+                    .block(self.symbols.block().title("Drop stash"))
+                    .style(Style::default().fg(self.colors.deleted)),
+                popup,
+            );
+        }
+    }
+
+    fn title(&self) -> &str {
+        "Stash"
+    }
+
+    fn view_type(&self) -> ViewType {
+        ViewType::Stash
+    }
+
+    fn selection(&self) -> Selection {
+        Selection {
+            commit: self.selected_stash().map(|stash| stash.commit.id),
+            refname: self.selected_stash().map(StashEntry::name),
+            ..Selection::default()
+        }
+    }
+
+    fn refresh(&mut self) {
+        self.error = None;
+        self.start_loading();
+    }
+
+    fn captures_input(&self) -> bool {
+        self.message_prompt.is_some() || self.confirm_drop
+    }
+
+    fn busy(&self) -> Option<&'static str> {
+        self.op_receiver.as_ref().map(|_| "changing the stash")
+    }
+
+    fn on_activate(&mut self) -> Result<()> {
+        if self.stashes.is_empty() && !self.loading {
+            self.start_loading();
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::testing::RepoBuilder;
+
+    async fn settle(view: &mut StashView) {
+        while view.loading || view.op_receiver.is_some() {
+            tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+            view.update().unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stashes_and_pops() {
+        let (temp_dir, repo) = RepoBuilder::new()
+            .file("a.txt", "one\n")
+            .commit("Initial")
+            .write("a.txt", "two\n")
+            .open()
+            .await;
+        let mut view = StashView::new(repo, ColorScheme::from_config(&Config::default().colors));
+        view.on_activate().unwrap();
+        settle(&mut view).await;
+        assert!(view.stashes.is_empty());
+
+        view.handle_key(KeyEvent::from(KeyCode::Char('s'))).unwrap();
+        assert!(view.captures_input());
+        for c in "shelved".chars() {
+            view.handle_key(KeyEvent::from(KeyCode::Char(c))).unwrap();
+        }
+        view.handle_key(KeyEvent::from(KeyCode::Enter)).unwrap();
+        assert_eq!(view.busy(), Some("changing the stash"));
+        settle(&mut view).await;
+        assert_eq!(view.poll_action(), Action::Refresh);
+        assert_eq!(view.stashes.len(), 1);
+        assert!(view.stashes[0].message.ends_with("shelved"));
+        assert!(matches!(
+            view.handle_key(KeyEvent::from(KeyCode::Enter)).unwrap(),
+            Action::OpenDiff { .. }
+        ));
+
+        view.handle_key(KeyEvent::from(KeyCode::Char('p'))).unwrap();
+        settle(&mut view).await;
+        assert!(view.stashes.is_empty());
+        assert_eq!(view.notice.as_deref(), Some("popped stash@{0}"));
+        assert_eq!(
+            std::fs::read_to_string(temp_dir.path().join("a.txt")).unwrap(),
+            "two\n"
+        );
+    }
+}
@@ -0,0 +1,66 @@
+/// Fill in the `%(name)` placeholders of a view's title template from
+/// `context`; names the view doesn't know are left as typed
+pub fn render_title(template: &str, context: &[(&str, String)]) -> String {
+    let mut title = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("%(") {
+        title.push_str(&rest[..start]);
+        let placeholder = &rest[start..];
+        let value = placeholder.find(')').and_then(|end| {
+            let name = &placeholder[2..end];
+            let (_, value) = context.iter().find(|(key, _)| *key == name)?;
+            Some((value, end + 1))
+        });
+        match value {
+            Some((value, len)) => {
+                title.push_str(value);
+                rest = &placeholder[len..];
+            }
+            None => {
+                title.push_str("%(");
+                rest = &placeholder[2..];
+            }
+        }
+    }
+    title.push_str(rest);
+    title
+}
+
+/// Format a count with thousands separators, e.g. "3,214"
+pub fn group_digits(n: usize) -> String {
+    let digits = n.to_string();
+    let mut grouped = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(c);
+    }
+    grouped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_title() {
+        let context = [
+            ("view", "Main".to_string()),
+            ("count", "%(view)".to_string()),
+        ];
+        assert_eq!(
+            render_title("%(view) - %(count) commits", &context),
+            "Main - %(view) commits"
+        );
+        assert_eq!(render_title("%(nope) %(view", &context), "%(nope) %(view");
+        assert_eq!(render_title("", &context), "");
+    }
+
+    #[test]
+    fn test_group_digits() {
+        assert_eq!(group_digits(7), "7");
+        assert_eq!(group_digits(3214), "3,214");
+        assert_eq!(group_digits(1234567), "1,234,567");
+    }
+}
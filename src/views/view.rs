@@ -1,8 +1,11 @@
-use crate::git::Repository;
+use crate::error::AppResult;
+use crate::git::{CheckoutTarget, Repository};
 use anyhow::Result;
 use crossterm::event::KeyEvent;
 use git2::Oid;
-use ratatui::{layout::Rect, Frame};
+use ratatui::{Frame, layout::Rect};
+use std::io;
+use tokio::sync::mpsc;
 
 /// Actions that views can request
 #[derive(Debug, Clone, PartialEq)]
@@ -17,22 +20,153 @@ pub enum Action {
     PushView(ViewType),
     /// Pop the current view and return to the previous one
     PopView,
+    /// Show help, opened at the section for the given view
+    ShowHelp(ViewType),
     /// Open a diff view for a specific commit
     OpenDiff {
         repo: Repository,
         commit_id: Oid,
         summary: String,
     },
-    /// Open a diff view for staged changes
-    OpenStagedDiff {
+    /// Open a diff view of the changes between two commits
+    OpenRangeDiff {
+        repo: Repository,
+        from: Oid,
+        to: Oid,
+    },
+    /// Open a blame view of a file, at a commit or else HEAD
+    OpenBlame {
         repo: Repository,
         path: String,
+        revision: Option<Oid>,
     },
+    /// Open a diff view for staged changes
+    OpenStagedDiff { repo: Repository, path: String },
     /// Open a diff view for unstaged changes
-    OpenUnstagedDiff {
-        repo: Repository,
+    OpenUnstagedDiff { repo: Repository, path: String },
+    /// Open the history of HEAD compared against a branch or other revision
+    Compare(String),
+    /// Fetch one remote
+    Fetch(String),
+    /// Check out a branch, or a commit with HEAD detached
+    Checkout(CheckoutTarget),
+    /// Apply commits onto the checked out branch, oldest first
+    CherryPick(Vec<Oid>),
+    /// Undo a commit with a new one, after asking whether to edit its
+    /// message first
+    Revert { id: Oid, summary: String },
+    /// Reset the checked out branch to a commit, after asking how much of
+    /// the work to keep
+    Reset { id: Oid, summary: String },
+    /// Merge a reference, by its full name, into HEAD
+    Merge(String),
+    /// Copy text to the clipboard
+    Copy(String),
+    /// Open a page in the browser
+    OpenUrl(String),
+    /// Reload every view after the history changed, e.g. with a commit
+    Refresh,
+    /// Record a submitted search query in the session history
+    RecordSearch(String),
+    /// Record a submitted commit message in the session history
+    RecordCommitMessage(String),
+    /// Save the review notes after a view changed them
+    SaveReviewNotes,
+    /// Record a file of a commit's or range's diff as viewed, or not, in
+    /// the session
+    MarkViewed {
+        diff: String,
         path: String,
+        viewed: bool,
     },
+    /// Suspend the UI to edit text in the user's editor
+    EditText(EditRequest),
+}
+
+impl Action {
+    /// Name of the action without its data, which may hold private text
+    /// such as searches and commit messages
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::None => "None",
+            Action::Quit => "Quit",
+            Action::SwitchView(_) => "SwitchView",
+            Action::PushView(_) => "PushView",
+            Action::PopView => "PopView",
+            Action::ShowHelp(_) => "ShowHelp",
+            Action::OpenDiff { .. } => "OpenDiff",
+            Action::OpenRangeDiff { .. } => "OpenRangeDiff",
+            Action::OpenBlame { .. } => "OpenBlame",
+            Action::OpenStagedDiff { .. } => "OpenStagedDiff",
+            Action::OpenUnstagedDiff { .. } => "OpenUnstagedDiff",
+            Action::Compare(_) => "Compare",
+            Action::Fetch(_) => "Fetch",
+            Action::Checkout(_) => "Checkout",
+            Action::CherryPick(_) => "CherryPick",
+            Action::Revert { .. } => "Revert",
+            Action::Reset { .. } => "Reset",
+            Action::Merge(_) => "Merge",
+            Action::Copy(_) => "Copy",
+            Action::OpenUrl(_) => "OpenUrl",
+            Action::Refresh => "Refresh",
+            Action::RecordSearch(_) => "RecordSearch",
+            Action::RecordCommitMessage(_) => "RecordCommitMessage",
+            Action::SaveReviewNotes => "SaveReviewNotes",
+            Action::MarkViewed { .. } => "MarkViewed",
+            Action::EditText(_) => "EditText",
+        }
+    }
+}
+
+/// Text for the user to edit in their editor
+///
+/// The main loop runs the editor with the terminal handed over, then sends
+/// the edited text back to the view that asked.
+#[derive(Debug, Clone)]
+pub struct EditRequest {
+    /// Text the editor opens with
+    pub text: String,
+    /// Name of the file being edited, which editors use to pick a syntax
+    pub file_name: &'static str,
+    reply: mpsc::UnboundedSender<io::Result<String>>,
+}
+
+impl EditRequest {
+    /// Create a request, and the receiver its result arrives on
+    pub fn new(
+        text: String,
+        file_name: &'static str,
+    ) -> (Self, mpsc::UnboundedReceiver<io::Result<String>>) {
+        let (reply, receiver) = mpsc::unbounded_channel();
+        let request = Self {
+            text,
+            file_name,
+            reply,
+        };
+        (request, receiver)
+    }
+
+    /// Send the edited text, or why editing failed, back to the view
+    pub fn finish(self, result: io::Result<String>) {
+        let _ = self.reply.send(result);
+    }
+}
+
+impl PartialEq for EditRequest {
+    fn eq(&self, other: &Self) -> bool {
+        self.text == other.text && self.file_name == other.file_name
+    }
+}
+
+/// What a view has selected, filling the `%(commit)`-style placeholders
+/// of command aliases
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Selection {
+    pub commit: Option<Oid>,
+    /// Short name of a selected branch, tag or remote branch
+    pub refname: Option<String>,
+    /// Path of a selected file, relative to the repository
+    pub file: Option<String>,
 }
 
 /// Types of views available
@@ -41,16 +175,38 @@ pub enum ViewType {
     Main,
     Diff,
     Status,
+    Refs,
+    Blame,
+    Stack,
+    Lost,
+    Health,
+    Output,
+    Hooks,
+    Stash,
+    Review,
     Help,
+    Onboarding,
 }
 
 /// Trait that all views must implement
 pub trait View {
     /// Handle a key event, returns an action to perform
-    fn handle_key(&mut self, key: KeyEvent) -> Result<Action>;
+    fn handle_key(&mut self, key: KeyEvent) -> AppResult<Action>;
+
+    /// Handle a key event preceded by a numeric count (e.g. `10j`)
+    /// Views without count-aware keys ignore the count
+    fn handle_key_with_count(&mut self, key: KeyEvent, count: usize) -> AppResult<Action> {
+        let _ = count;
+        self.handle_key(key)
+    }
 
     /// Update the view state (called on each frame)
-    fn update(&mut self) -> Result<()>;
+    fn update(&mut self) -> AppResult<()>;
+
+    /// Action the view wants to perform on its own, checked after each update
+    fn poll_action(&mut self) -> Action {
+        Action::None
+    }
 
     /// Render the view
     fn draw(&mut self, frame: &mut Frame, area: Rect);
@@ -58,6 +214,35 @@ pub trait View {
     /// Get the view's title (for status bar)
     fn title(&self) -> &str;
 
+    /// Get the kind of view
+    fn view_type(&self) -> ViewType;
+
+    /// Whether the view is taking text input, so keys must reach it unmapped
+    fn captures_input(&self) -> bool {
+        false
+    }
+
+    /// Reload the view's data from the repository, keeping its place where
+    /// possible
+    fn refresh(&mut self) {}
+
+    /// Select a commit by its id, returning whether the view shows commits
+    /// to select from
+    fn select_commit(&mut self, _oid: Oid) -> bool {
+        false
+    }
+
+    /// Describe background work the view started that quitting would cut
+    /// short, such as "committing"
+    fn busy(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// What the view has selected, for command aliases
+    fn selection(&self) -> Selection {
+        Selection::default()
+    }
+
     /// Called when the view is activated (moved to foreground)
     fn on_activate(&mut self) -> Result<()> {
         Ok(())
@@ -80,6 +265,18 @@ mod tests {
         assert_ne!(Action::Quit, Action::None);
     }
 
+    #[test]
+    fn test_action_name_leaves_out_text() {
+        assert_eq!(
+            Action::RecordSearch("secret".to_string()).name(),
+            "RecordSearch"
+        );
+        assert_eq!(
+            Action::RecordCommitMessage("secret".to_string()).name(),
+            "RecordCommitMessage"
+        );
+    }
+
     #[test]
     fn test_view_type_equality() {
         assert_eq!(ViewType::Main, ViewType::Main);
@@ -1,7 +1,7 @@
 use rust_tig::config::{ColorScheme, Config};
-use rust_tig::git::{CommitWalker, Repository};
+use rust_tig::git::{CommitWalker, Repository, diff::SizeLimit, walker::WALK_BUFFER_CHUNKS};
+use rust_tig::testing::RepoBuilder;
 use rust_tig::views::{DiffView, MainView, StatusView, View};
-use std::fs;
 use tempfile::TempDir;
 
 /// Create a test color scheme
@@ -11,63 +11,15 @@ fn test_color_scheme() -> ColorScheme {
 
 /// Create a test repository with multiple commits
 async fn create_test_repo_with_history() -> (TempDir, Repository) {
-    let temp_dir = TempDir::new().unwrap();
-    let repo_path = temp_dir.path();
-
-    let git_repo = git2::Repository::init(repo_path).unwrap();
-    let sig = git2::Signature::now("Test User", "test@example.com").unwrap();
-
-    // Create initial commit
-    fs::write(repo_path.join("file1.txt"), "Initial content\n").unwrap();
-    let mut index = git_repo.index().unwrap();
-    index.add_path(std::path::Path::new("file1.txt")).unwrap();
-    index.write().unwrap();
-    let tree_id = index.write_tree().unwrap();
-    let tree = git_repo.find_tree(tree_id).unwrap();
-    git_repo
-        .commit(Some("HEAD"), &sig, &sig, "Initial commit", &tree, &[])
-        .unwrap();
-
-    // Create second commit
-    fs::write(repo_path.join("file2.txt"), "Second file\n").unwrap();
-    let mut index = git_repo.index().unwrap();
-    index.add_path(std::path::Path::new("file2.txt")).unwrap();
-    index.write().unwrap();
-    let tree_id = index.write_tree().unwrap();
-    let tree = git_repo.find_tree(tree_id).unwrap();
-    let parent = git_repo.head().unwrap().peel_to_commit().unwrap();
-    git_repo
-        .commit(
-            Some("HEAD"),
-            &sig,
-            &sig,
-            "Add second file",
-            &tree,
-            &[&parent],
-        )
-        .unwrap();
-
-    // Create third commit
-    fs::write(repo_path.join("file1.txt"), "Modified content\n").unwrap();
-    let mut index = git_repo.index().unwrap();
-    index.add_path(std::path::Path::new("file1.txt")).unwrap();
-    index.write().unwrap();
-    let tree_id = index.write_tree().unwrap();
-    let tree = git_repo.find_tree(tree_id).unwrap();
-    let parent = git_repo.head().unwrap().peel_to_commit().unwrap();
-    git_repo
-        .commit(
-            Some("HEAD"),
-            &sig,
-            &sig,
-            "Modify first file",
-            &tree,
-            &[&parent],
-        )
-        .unwrap();
-
-    let repo = Repository::open(repo_path).await.unwrap();
-    (temp_dir, repo)
+    RepoBuilder::new()
+        .file("file1.txt", "Initial content\n")
+        .commit("Initial commit")
+        .file("file2.txt", "Second file\n")
+        .commit("Add second file")
+        .file("file1.txt", "Modified content\n")
+        .commit("Modify first file")
+        .open()
+        .await
 }
 
 #[tokio::test]
@@ -98,7 +50,7 @@ async fn test_commit_walker_chunked_loading() {
     let (_temp_dir, repo) = create_test_repo_with_history().await;
 
     let walker = CommitWalker::new(repo).with_chunk_size(1);
-    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let (tx, mut rx) = tokio::sync::mpsc::channel(WALK_BUFFER_CHUNKS);
 
     tokio::spawn(async move {
         walker.walk(tx).await.unwrap();
@@ -123,7 +75,7 @@ async fn test_diff_loading_for_commit() {
 
     // Load diff for the first commit (most recent)
     let commit = &commits[0];
-    let diff = rust_tig::git::diff::load_commit_diff(&repo, commit.id)
+    let diff = rust_tig::git::diff::load_commit_diff(&repo, commit.id, &SizeLimit::default())
         .await
         .unwrap();
 
@@ -134,36 +86,14 @@ async fn test_diff_loading_for_commit() {
 
 #[tokio::test]
 async fn test_status_with_changes() {
-    let temp_dir = TempDir::new().unwrap();
-    let repo_path = temp_dir.path();
-
-    let git_repo = git2::Repository::init(repo_path).unwrap();
-    let sig = git2::Signature::now("Test", "test@example.com").unwrap();
-
-    // Create initial commit
-    fs::write(repo_path.join("existing.txt"), "existing\n").unwrap();
-    let mut index = git_repo.index().unwrap();
-    index.add_path(std::path::Path::new("existing.txt")).unwrap();
-    index.write().unwrap();
-    let tree_id = index.write_tree().unwrap();
-    let tree = git_repo.find_tree(tree_id).unwrap();
-    git_repo
-        .commit(Some("HEAD"), &sig, &sig, "Initial", &tree, &[])
-        .unwrap();
-
-    // Create staged file
-    fs::write(repo_path.join("staged.txt"), "staged\n").unwrap();
-    let mut index = git_repo.index().unwrap();
-    index.add_path(std::path::Path::new("staged.txt")).unwrap();
-    index.write().unwrap();
-
-    // Create unstaged change
-    fs::write(repo_path.join("existing.txt"), "modified\n").unwrap();
-
-    // Create untracked file
-    fs::write(repo_path.join("untracked.txt"), "untracked\n").unwrap();
-
-    let repo = Repository::open(repo_path).await.unwrap();
+    let (_temp_dir, repo) = RepoBuilder::new()
+        .file("existing.txt", "existing\n")
+        .commit("Initial")
+        .file("staged.txt", "staged\n")
+        .write("existing.txt", "modified\n")
+        .write("untracked.txt", "untracked\n")
+        .open()
+        .await;
     let status = rust_tig::git::status::load_status(&repo).await.unwrap();
 
     assert!(!status.staged.is_empty());
@@ -174,27 +104,12 @@ async fn test_status_with_changes() {
 
 #[tokio::test]
 async fn test_staging_and_unstaging_workflow() {
-    let temp_dir = TempDir::new().unwrap();
-    let repo_path = temp_dir.path();
-
-    let git_repo = git2::Repository::init(repo_path).unwrap();
-    let sig = git2::Signature::now("Test", "test@example.com").unwrap();
-
-    // Create initial commit
-    fs::write(repo_path.join("file.txt"), "content\n").unwrap();
-    let mut index = git_repo.index().unwrap();
-    index.add_path(std::path::Path::new("file.txt")).unwrap();
-    index.write().unwrap();
-    let tree_id = index.write_tree().unwrap();
-    let tree = git_repo.find_tree(tree_id).unwrap();
-    git_repo
-        .commit(Some("HEAD"), &sig, &sig, "Initial", &tree, &[])
-        .unwrap();
-
-    // Create untracked file
-    fs::write(repo_path.join("new.txt"), "new file\n").unwrap();
-
-    let repo = Repository::open(repo_path).await.unwrap();
+    let (_temp_dir, repo) = RepoBuilder::new()
+        .file("file.txt", "content\n")
+        .commit("Initial")
+        .write("new.txt", "new file\n")
+        .open()
+        .await;
 
     // Verify file is untracked
     let status = rust_tig::git::status::load_status(&repo).await.unwrap();
@@ -281,45 +196,32 @@ async fn test_diff_view_lifecycle() {
 
 #[tokio::test]
 async fn test_staged_and_unstaged_diffs() {
-    let temp_dir = TempDir::new().unwrap();
-    let repo_path = temp_dir.path();
-
-    let git_repo = git2::Repository::init(repo_path).unwrap();
-    let sig = git2::Signature::now("Test", "test@example.com").unwrap();
-
-    // Create initial commit
-    fs::write(repo_path.join("file.txt"), "line1\n").unwrap();
-    let mut index = git_repo.index().unwrap();
-    index.add_path(std::path::Path::new("file.txt")).unwrap();
-    index.write().unwrap();
-    let tree_id = index.write_tree().unwrap();
-    let tree = git_repo.find_tree(tree_id).unwrap();
-    git_repo
-        .commit(Some("HEAD"), &sig, &sig, "Initial", &tree, &[])
-        .unwrap();
-
-    // Make a staged change
-    fs::write(repo_path.join("file.txt"), "line1\nline2\n").unwrap();
-    let mut index = git_repo.index().unwrap();
-    index.add_path(std::path::Path::new("file.txt")).unwrap();
-    index.write().unwrap();
-
-    // Make an unstaged change
-    fs::write(repo_path.join("file.txt"), "line1\nline2\nline3\n").unwrap();
-
-    let repo = Repository::open(repo_path).await.unwrap();
+    let (_temp_dir, repo) = RepoBuilder::new()
+        .file("file.txt", "line1\n")
+        .commit("Initial")
+        .file("file.txt", "line1\nline2\n")
+        .write("file.txt", "line1\nline2\nline3\n")
+        .open()
+        .await;
 
     // Load staged diff
-    let staged_diff = rust_tig::git::diff::load_staged_diff(&repo, Some("file.txt".to_string()))
-        .await
-        .unwrap();
+    let staged_diff = rust_tig::git::diff::load_staged_diff(
+        &repo,
+        Some("file.txt".to_string()),
+        &SizeLimit::default(),
+    )
+    .await
+    .unwrap();
     assert!(!staged_diff.files.is_empty());
 
     // Load unstaged diff
-    let unstaged_diff =
-        rust_tig::git::diff::load_unstaged_diff(&repo, Some("file.txt".to_string()))
-            .await
-            .unwrap();
+    let unstaged_diff = rust_tig::git::diff::load_unstaged_diff(
+        &repo,
+        Some("file.txt".to_string()),
+        &SizeLimit::default(),
+    )
+    .await
+    .unwrap();
     assert!(!unstaged_diff.files.is_empty());
 }
 
@@ -351,20 +253,16 @@ async fn test_commit_with_no_parents() {
     assert_eq!(initial_commit.summary, "Initial commit");
 
     // Should be able to load diff for initial commit
-    let diff = rust_tig::git::diff::load_commit_diff(&repo, initial_commit.id)
-        .await
-        .unwrap();
+    let diff =
+        rust_tig::git::diff::load_commit_diff(&repo, initial_commit.id, &SizeLimit::default())
+            .await
+            .unwrap();
     assert!(!diff.files.is_empty());
 }
 
 #[tokio::test]
 async fn test_repository_with_no_commits() {
-    let temp_dir = TempDir::new().unwrap();
-    let repo_path = temp_dir.path();
-
-    git2::Repository::init(repo_path).unwrap();
-
-    let repo = Repository::open(repo_path).await.unwrap();
+    let (_temp_dir, repo) = RepoBuilder::new().open().await;
 
     // Check if repository is empty
     let is_empty = repo.is_empty().await.unwrap();